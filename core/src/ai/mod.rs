@@ -0,0 +1,379 @@
+//! AI Pipelines
+//!
+//! Multi-step assistant flows that span several subsystems:
+//! - Screen capture and OCR via a pluggable screen reader
+//! - Summarization through the model manager
+//! - Persisting results into memory under privacy rules
+
+use std::sync::Arc;
+use tracing::{info, debug, instrument};
+
+use crate::correlation;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+
+use crate::device::{ImageFormat, ScreenCapturer};
+use crate::kernel::TaskPriority;
+use crate::memory::{ContentType, Importance, MemoryItem, MemoryManager, MemoryType, VersionVector};
+use crate::models::ModelManager;
+use crate::privacy::{PrivacyControls, SCREEN_CAPTURE_SOURCE};
+use crate::security::SecurityManager;
+use crate::errors::{MisaError, Result as MisaResult};
+
+/// Audit resource recorded for captures taken to remember what is on screen
+const SCREEN_MEMORY_RESOURCE: &str = "screen_memory";
+
+/// Captures the screen and extracts its text
+#[async_trait::async_trait]
+pub trait ScreenReader: Send + Sync {
+    async fn capture_text(&self) -> MisaResult<String>;
+}
+
+/// Reads the screen by asking a local vision model to transcribe a captured frame
+///
+/// Screen contents never leave this machine: only local models are asked.
+pub struct VisionScreenReader {
+    user_id: String,
+    screen_capturer: ScreenCapturer,
+    model_manager: ModelManager,
+}
+
+impl VisionScreenReader {
+    pub fn new(
+        user_id: &str,
+        model_manager: ModelManager,
+        privacy_controls: PrivacyControls,
+        security_manager: SecurityManager,
+    ) -> Self {
+        Self {
+            user_id: user_id.to_string(),
+            screen_capturer: ScreenCapturer::new().with_privacy_controls(privacy_controls, security_manager),
+            model_manager,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ScreenReader for VisionScreenReader {
+    async fn capture_text(&self) -> MisaResult<String> {
+        let frame = self.screen_capturer.capture_frame(&self.user_id, None, ImageFormat::PNG).await?;
+        let model_id = self
+            .model_manager
+            .select_local_model_for_task("vision", &TaskPriority::Normal)
+            .await?;
+        let context = serde_json::json!({ "images": [BASE64.encode(&frame.data)] });
+
+        let response = self
+            .model_manager
+            .execute_local_task(
+                "Transcribe all of the text visible in this screenshot. Reply with the text only.",
+                &model_id,
+                Some(&context),
+            )
+            .await?;
+        response
+            .get("content")
+            .and_then(|content| content.as_str())
+            .map(|content| content.trim().to_string())
+            .ok_or_else(|| MisaError::Model("Screen transcription response had no content".to_string()))
+    }
+}
+
+/// Condenses captured text into something worth remembering
+#[async_trait::async_trait]
+pub trait Summarizer: Send + Sync {
+    async fn summarize(&self, text: &str) -> MisaResult<String>;
+}
+
+#[async_trait::async_trait]
+impl Summarizer for ModelManager {
+    async fn summarize(&self, text: &str) -> MisaResult<String> {
+        let model_id = self.select_model_for_task("summarization", None, &TaskPriority::Normal).await?;
+        let prompt = format!(
+            "Summarize the key information from the following text in a few sentences:\n\n{}",
+            text
+        );
+
+        let response = self.execute_task(&prompt, &model_id, None).await?;
+        response
+            .get("content")
+            .and_then(|content| content.as_str())
+            .map(|content| content.trim().to_string())
+            .ok_or_else(|| MisaError::Model("Summarization response had no content".to_string()))
+    }
+}
+
+/// AI manager coordinating vision, models and memory
+pub struct AIManager {
+    user_id: String,
+    screen_reader: Arc<dyn ScreenReader>,
+    /// Consent-checks and audits each capture, as for remote desktop
+    screen_capturer: ScreenCapturer,
+    summarizer: Arc<dyn Summarizer>,
+    memory_manager: MemoryManager,
+    privacy_controls: PrivacyControls,
+    default_retention_days: u32,
+}
+
+impl AIManager {
+    /// Create a new AI manager
+    pub fn new(
+        user_id: &str,
+        screen_reader: Arc<dyn ScreenReader>,
+        summarizer: Arc<dyn Summarizer>,
+        memory_manager: MemoryManager,
+        privacy_controls: PrivacyControls,
+        security_manager: SecurityManager,
+        default_retention_days: u32,
+    ) -> Self {
+        Self {
+            user_id: user_id.to_string(),
+            screen_reader,
+            screen_capturer: ScreenCapturer::new().with_privacy_controls(privacy_controls.clone(), security_manager),
+            summarizer,
+            memory_manager,
+            privacy_controls,
+            default_retention_days,
+        }
+    }
+
+    /// Capture the screen, summarize what is on it and store the summary as a memory
+    #[instrument(skip_all, fields(request_id))]
+    pub async fn capture_and_remember(&self) -> MisaResult<String> {
+        correlation::enter_request();
+        self.screen_capturer.authorize(&self.user_id, SCREEN_MEMORY_RESOURCE).await?;
+
+        let text = self.screen_reader.capture_text().await?;
+        if text.trim().is_empty() {
+            return Err(MisaError::Validation("No text found on screen".to_string()));
+        }
+        debug!("Captured {} characters of screen text", text.len());
+
+        let summary = self.summarizer.summarize(&text).await?;
+        let retention_days = self.capture_retention_days().await?;
+        let now = chrono::Utc::now();
+
+        let memory = MemoryItem {
+            id: uuid::Uuid::new_v4().to_string(),
+            content: summary,
+            content_type: ContentType::Text,
+            memory_type: Self::memory_type_for_retention(retention_days),
+            importance: Importance::Medium,
+            tags: vec![
+                "screen_capture".to_string(),
+                "ocr".to_string(),
+                "summary".to_string(),
+            ],
+            metadata: serde_json::json!({
+                "source": SCREEN_CAPTURE_SOURCE,
+                "source_length": text.len(),
+                "retention_days": retention_days,
+                "expires_at": now + chrono::Duration::days(retention_days as i64),
+            }),
+            created_at: now,
+            last_accessed: now,
+            access_count: 0,
+            encrypted: false,
+            version: VersionVector::default(),
+            binary: None,
+        };
+
+        let memory_id = self.memory_manager.store_memory(memory).await?;
+        info!("Remembered screen capture as memory {}", memory_id);
+        Ok(memory_id)
+    }
+
+    /// Retention for captured content, clamped to the screen capture source policy
+    async fn capture_retention_days(&self) -> MisaResult<u32> {
+        let max_age_days = self
+            .privacy_controls
+            .get_data_source_status(SCREEN_CAPTURE_SOURCE)
+            .await?
+            .and_then(|control| control.retention_policy)
+            .map(|rule| rule.max_age_days);
+
+        Ok(match max_age_days {
+            Some(max_age_days) => self.default_retention_days.min(max_age_days),
+            None => self.default_retention_days,
+        })
+    }
+
+    fn memory_type_for_retention(retention_days: u32) -> MemoryType {
+        match retention_days {
+            0..=1 => MemoryType::ShortTerm,
+            2..=30 => MemoryType::MediumTerm,
+            _ => MemoryType::LongTerm,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel::{MemoryConfig, SecurityConfig};
+    use crate::privacy::ConsentType;
+    use crate::security::{AuditEntry, AuditQuery, AuditResult};
+
+    struct StubScreenReader;
+
+    #[async_trait::async_trait]
+    impl ScreenReader for StubScreenReader {
+        async fn capture_text(&self) -> MisaResult<String> {
+            Ok("Quarterly review moved to Friday 3pm. Bring the revenue deck.".to_string())
+        }
+    }
+
+    struct StubSummarizer;
+
+    #[async_trait::async_trait]
+    impl Summarizer for StubSummarizer {
+        async fn summarize(&self, _text: &str) -> MisaResult<String> {
+            Ok("Quarterly review is Friday at 3pm".to_string())
+        }
+    }
+
+    async fn test_ai_manager(data_dir: &tempfile::TempDir) -> (AIManager, MemoryManager, PrivacyControls, SecurityManager) {
+        let dir = data_dir.path().to_str().unwrap();
+        let security_manager = SecurityManager::new(dir, SecurityConfig::default()).await.unwrap();
+        let memory_config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        let memory_manager = MemoryManager::new(dir, memory_config, security_manager.clone()).await.unwrap();
+        let privacy_controls = PrivacyControls::new(SecurityConfig::default(), dir).await.unwrap();
+
+        let manager = AIManager::new(
+            "user-1",
+            Arc::new(StubScreenReader),
+            Arc::new(StubSummarizer),
+            memory_manager.clone(),
+            privacy_controls.clone(),
+            security_manager.clone(),
+            365,
+        );
+
+        (manager, memory_manager, privacy_controls, security_manager)
+    }
+
+    async fn audited_captures(security_manager: &SecurityManager) -> Vec<AuditEntry> {
+        security_manager
+            .query_audit_log(&AuditQuery {
+                action: Some("screen_capture".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_capture_without_consent_is_rejected() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let (manager, _, _, security_manager) = test_ai_manager(&data_dir).await;
+
+        let result = manager.capture_and_remember().await;
+        assert!(matches!(result, Err(MisaError::Privacy(_))));
+
+        let audited = audited_captures(&security_manager).await;
+        assert_eq!(audited.len(), 1);
+        assert!(matches!(audited[0].result, AuditResult::Failure));
+    }
+
+    #[tokio::test]
+    async fn test_capture_and_remember_stores_summary() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let (manager, memory_manager, privacy_controls, security_manager) = test_ai_manager(&data_dir).await;
+        privacy_controls.insert_test_consent("user-1", ConsentType::ScreenCapture).await;
+        privacy_controls.set_data_source_control(SCREEN_CAPTURE_SOURCE, true).await.unwrap();
+
+        let memory_id = manager.capture_and_remember().await.unwrap();
+        let memory = memory_manager.get_memory(&memory_id).await.unwrap().unwrap();
+
+        assert_eq!(memory.content, "Quarterly review is Friday at 3pm");
+        assert!(memory.tags.contains(&"screen_capture".to_string()));
+        // Screen capture source only allows one day of retention
+        assert_eq!(memory.metadata["retention_days"], 1);
+        assert!(matches!(memory.memory_type, MemoryType::ShortTerm));
+
+        let audited = audited_captures(&security_manager).await;
+        assert_eq!(audited.len(), 1);
+        assert_eq!(audited[0].user_id.as_deref(), Some("user-1"));
+        assert_eq!(audited[0].resource, SCREEN_MEMORY_RESOURCE);
+        assert!(matches!(audited[0].result, AuditResult::Success));
+    }
+
+    #[tokio::test]
+    async fn test_capture_with_disabled_source_is_rejected() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let (manager, _, privacy_controls, _) = test_ai_manager(&data_dir).await;
+        privacy_controls.insert_test_consent("user-1", ConsentType::ScreenCapture).await;
+
+        // Consent alone is not enough while the screen capture source is switched off
+        let result = manager.capture_and_remember().await;
+        assert!(matches!(result, Err(MisaError::Privacy(_))));
+    }
+
+    /// Records which spans were given which request id
+    #[derive(Clone, Default)]
+    struct RequestIdRecorder {
+        recorded: Arc<std::sync::Mutex<Vec<(&'static str, String)>>>,
+    }
+
+    impl<S> tracing_subscriber::Layer<S> for RequestIdRecorder
+    where
+        S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+    {
+        fn on_record(
+            &self,
+            id: &tracing::span::Id,
+            values: &tracing::span::Record<'_>,
+            ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            let Some(span) = ctx.span(id) else { return };
+            let mut visitor = RequestIdVisitor(None);
+            values.record(&mut visitor);
+            if let Some(request_id) = visitor.0 {
+                self.recorded.lock().unwrap().push((span.name(), request_id));
+            }
+        }
+    }
+
+    struct RequestIdVisitor(Option<String>);
+
+    impl tracing::field::Visit for RequestIdVisitor {
+        fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+            if field.name() == correlation::REQUEST_ID_FIELD {
+                self.0 = Some(value.to_string());
+            }
+        }
+
+        fn record_debug(&mut self, _field: &tracing::field::Field, _value: &dyn std::fmt::Debug) {}
+    }
+
+    #[tokio::test]
+    async fn test_capture_shares_request_id_with_memory_store() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let recorder = RequestIdRecorder::default();
+        let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(recorder.clone()));
+
+        let data_dir = tempfile::tempdir().unwrap();
+        let (manager, _, privacy_controls, _) = test_ai_manager(&data_dir).await;
+        privacy_controls.insert_test_consent("user-1", ConsentType::ScreenCapture).await;
+        privacy_controls.set_data_source_control(SCREEN_CAPTURE_SOURCE, true).await.unwrap();
+
+        manager.capture_and_remember().await.unwrap();
+
+        let recorded = recorder.recorded.lock().unwrap().clone();
+        let capture_id = recorded
+            .iter()
+            .find(|(name, _)| *name == "capture_and_remember")
+            .map(|(_, request_id)| request_id.clone())
+            .unwrap();
+        let store_id = recorded
+            .iter()
+            .find(|(name, _)| *name == "store_memory")
+            .map(|(_, request_id)| request_id.clone())
+            .unwrap();
+        assert_eq!(store_id, capture_id);
+        assert!(!capture_id.is_empty());
+    }
+}
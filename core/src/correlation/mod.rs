@@ -0,0 +1,84 @@
+//! Request Correlation
+//!
+//! Ties log lines from different managers back to the user action behind them:
+//! - Public entry points run inside a span carrying a `request_id` field
+//! - Nested entry points reuse their caller's id instead of generating a new one
+//! - The id can be read back to tag events sent to subscribers
+
+use tracing::Span;
+use tracing_subscriber::registry::{LookupSpan, Registry};
+
+/// Span field entry points declare to hold the request id
+pub const REQUEST_ID_FIELD: &str = "request_id";
+
+/// Request id stored in the span's extensions so nested spans can find it
+#[derive(Clone)]
+struct RequestId(String);
+
+/// Give the current span a request id, inheriting the closest ancestor's when there is one.
+///
+/// Call this first thing in a function instrumented with `fields(request_id)`.
+pub fn enter_request() -> String {
+    let span = Span::current();
+    let request_id = lookup(&span, true).unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    span.with_subscriber(|(id, dispatch)| {
+        if let Some(span) = dispatch.downcast_ref::<Registry>().and_then(|registry| registry.span(id)) {
+            span.extensions_mut().replace(RequestId(request_id.clone()));
+        }
+    });
+    span.record(REQUEST_ID_FIELD, request_id.as_str());
+
+    request_id
+}
+
+/// Request id of the current span or its closest ancestor with one
+pub fn current_request_id() -> Option<String> {
+    lookup(&Span::current(), false)
+}
+
+fn lookup(span: &Span, skip_current: bool) -> Option<String> {
+    span.with_subscriber(|(id, dispatch)| {
+        let registry = dispatch.downcast_ref::<Registry>()?;
+        registry
+            .span(id)?
+            .scope()
+            .skip(usize::from(skip_current))
+            .find_map(|span| span.extensions().get::<RequestId>().map(|request_id| request_id.0.clone()))
+    })
+    .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tracing::instrument(skip_all, fields(request_id))]
+    async fn outer() -> (String, String, Option<String>) {
+        let request_id = enter_request();
+        let (nested, current) = inner().await;
+        (request_id, nested, current)
+    }
+
+    #[tracing::instrument(skip_all, fields(request_id))]
+    async fn inner() -> (String, Option<String>) {
+        let request_id = enter_request();
+        let span = tracing::info_span!("helper");
+        let _entered = span.enter();
+        (request_id, current_request_id())
+    }
+
+    #[tokio::test]
+    async fn test_nested_entry_points_share_request_id() {
+        let _guard = tracing::subscriber::set_default(tracing_subscriber::registry());
+
+        let (outer_id, inner_id, helper_id) = outer().await;
+        assert_eq!(inner_id, outer_id);
+        assert_eq!(helper_id.as_deref(), Some(outer_id.as_str()));
+
+        // A new top-level call starts a new request
+        let (next_id, _, _) = outer().await;
+        assert_ne!(next_id, outer_id);
+        assert!(current_request_id().is_none());
+    }
+}
@@ -9,40 +9,414 @@
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::io::Read;
+use sha2::{Digest, Sha256};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{Read, SeekFrom};
 use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::RwLock;
+use futures_util::stream::BoxStream;
+use futures_util::{SinkExt, StreamExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::{broadcast, mpsc, oneshot, watch, RwLock, Semaphore};
 use tokio_tungstenite::tungstenite::Message;
-use tracing::{info, warn, error, debug};
+use webrtc::data_channel::data_channel_message::DataChannelMessage;
+use webrtc::data_channel::RTCDataChannel;
+use webrtc::peer_connection::RTCPeerConnection;
+use tracing::{info, warn, error, debug, instrument};
+
+/// Hex-encoded SHA-256 digest of clipboard content, used for change detection
+fn clipboard_hash(content: impl AsRef<[u8]>) -> String {
+    encode_hex(&Sha256::digest(content.as_ref()))
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
 
-// Use type alias for MD5 to avoid dependency issues
-type Md5Digest = [u8; 16];
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
 
-fn compute_md5(data: &[u8]) -> Md5Digest {
-    // Simple MD5-like hash for demonstration
-    // In production, use proper crypto library
-    let mut result = [0u8; 16];
-    let len = data.len().min(16);
-    result[..len].copy_from_slice(&data[..len]);
-    result
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| hex.get(i..i + 2).and_then(|pair| u8::from_str_radix(pair, 16).ok()))
+        .collect()
 }
 
-use crate::kernel::DeviceConfig;
-use crate::security::{SecurityManager, EncryptedData};
-use crate::errors::{MisaError, Result as MisaResult};
+/// UDP port discovery packets are broadcast on; peers listen one port above
+const DISCOVERY_PORT: u16 = 8081;
+
+/// Prefix of a latency ping datagram; the discovery listener echoes it back as a pong
+const PING_PREFIX: &[u8] = b"MISA-PING:";
+const PONG_PREFIX: &[u8] = b"MISA-PONG:";
+
+/// Pings sent per latency measurement
+const LATENCY_PROBE_COUNT: u32 = 3;
+
+/// How long to wait for each pong
+const LATENCY_PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Probe rounds per device that jitter, packet loss and uptime are computed over
+const QUALITY_WINDOW: usize = 20;
+
+/// Jitter at which a lossless connection's stability score drops to one half
+const STABILITY_HALF_JITTER_MS: f32 = 30.0;
+
+/// DNS-SD service type advertised and browsed for peers
+const MDNS_SERVICE_TYPE: &str = "_misa._tcp.local.";
+
+/// Port peers connect to once discovered
+const DEVICE_SERVICE_PORT: u16 = 8080;
+
+/// Path of the device service's WebSocket endpoint, apart from the kernel's streaming `/ws`
+pub const DEVICE_CHANNEL_PATH: &str = "/device";
+
+/// How long each end of a new device channel waits for the other's hello
+const DEVICE_HELLO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Random bytes in each nonce a device channel handshake challenges the other end with
+const DEVICE_HELLO_NONCE_BYTES: usize = 16;
+
+/// Key derivation purpose for QR pairing token signatures
+const PAIRING_TOKEN_PURPOSE: &str = "device_pairing";
+
+/// How long to wait for a paired device to answer a key exchange
+const KEY_EXCHANGE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Most inbound rate limit buckets kept before idle ones are evicted
+const MAX_INBOUND_BUCKETS: usize = 1024;
+
+/// How long to wait for a host to answer the remote desktop capability handshake
+const REMOTE_DESKTOP_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a QR pairing token stays valid
+const PAIRING_TOKEN_TTL_MINUTES: i64 = 5;
+
+/// Key derivation purpose for file transfer grant signatures
+const TRANSFER_GRANT_PURPOSE: &str = "file_transfer_grant";
+
+/// How long a device's permission to send it a file stays valid
+const TRANSFER_GRANT_TTL_SECONDS: i64 = 120;
+
+/// How long to wait for a device to answer a file transfer request
+const TRANSFER_GRANT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Bytes read from the start of a file to detect its type
+const CONTENT_SNIFF_BYTES: u64 = 8192;
+
+/// Tags a browser would render text opening with as HTML
+const HTML_SNIFF_TAGS: &[&str] = &[
+    "<!doctype html", "<html", "<head", "<body", "<script", "<iframe", "<style", "<title",
+    "<table", "<div", "<font", "<h1", "<br", "<a", "<b", "<p", "<!--",
+];
+
+/// How long a single reconnect attempt may take to open a connection
+const RECONNECT_DIAL_TIMEOUT: Duration = Duration::from_secs(10);
+
+use crate::correlation;
+use crate::kernel::{DeviceConfig, DiscoveryTransport};
+use crate::metrics::{self, Metrics};
+use crate::scheduler::Scheduler;
+use crate::security::{AuditResult, DeviceKeyExchange, SecurityManager, EncryptedData};
+use crate::privacy::{PrivacyControls, SCREEN_CAPTURE_SOURCE};
+use crate::util::{read_json_map, write_file_atomic, write_json_atomic};
+use crate::errors::{MisaError, PrivacyError, Result as MisaResult};
 
 /// Device manager for multi-device orchestration
 pub struct DeviceManager {
     config: DeviceConfig,
+    /// Id this device stamps on the messages it sends
+    device_id: String,
     security_manager: SecurityManager,
     devices: Arc<RwLock<HashMap<String, DeviceInfo>>>,
     active_connections: Arc<RwLock<HashMap<String, DeviceConnection>>>,
-    discovery_service: DiscoveryService,
+    connection_quality: Arc<RwLock<HashMap<String, ConnectionQuality>>>,
+    /// Shared by every clone, so discovery started through one clone can be stopped through another
+    discovery_service: Arc<DiscoveryService>,
     remote_desktop_manager: RemoteDesktopManager,
     clipboard_sync: ClipboardSync,
+    pending_requests: Arc<RwLock<HashMap<String, PendingRequest>>>,
+    /// Our half of the key exchange for each pairing token handed out, by the device it names
+    pending_pairings: Arc<RwLock<HashMap<String, DeviceKeyExchange>>>,
+    /// Inbound message budget per source device
+    inbound_limits: Arc<RwLock<HashMap<String, TokenBucket>>>,
+    groups: Arc<RwLock<HashMap<String, DeviceGroup>>>,
+    /// File groups are written through to; groups live in memory only without one
+    groups_path: Option<PathBuf>,
+    /// Messages that failed every delivery attempt, oldest first
+    dead_letters: Arc<RwLock<VecDeque<DeadLetter>>>,
+    /// Decides which incoming file transfers to grant; paired devices only without one
+    transfer_approver: Option<Arc<dyn TransferApprover>>,
+    /// Runs tasks other devices delegate here; they are declined without one
+    task_handler: Option<Arc<dyn TaskHandler>>,
+    /// Decides what control paired devices get over this one; view only without one
+    remote_desktop_approver: Option<Arc<dyn RemoteDesktopApprover>>,
+    /// Opens new connections to devices whose connection dropped
+    connector: Arc<dyn DeviceConnector>,
+    /// Devices a reconnect supervisor is currently running for
+    reconnecting: Arc<RwLock<HashSet<String>>>,
+    connection_events: broadcast::Sender<DeviceConnectionEvent>,
+    /// Heartbeat loop started with the device service, stopped on shutdown
+    heartbeat_task: Arc<RwLock<Option<tokio::task::JoinHandle<()>>>>,
+    /// Slots for delegated tasks running on this device
+    delegated_tasks: Arc<Semaphore>,
+    /// Clipboard polling loop, stopped on shutdown
+    clipboard_task: Arc<RwLock<Option<tokio::task::JoinHandle<()>>>>,
+    metrics: Metrics,
+}
+
+/// Connections to devices opening and closing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceConnectionEvent {
+    DeviceConnected {
+        device_id: String,
+    },
+    DeviceDisconnected {
+        device_id: String,
+    },
+    /// Reconnecting to a paired device gave up after this many failed attempts
+    ReconnectFailed {
+        device_id: String,
+        attempts: u32,
+    },
+}
+
+/// Opens a connection to a device, used to re-establish dropped connections
+#[async_trait::async_trait]
+pub trait DeviceConnector: Send + Sync {
+    async fn connect(&self, manager: &DeviceManager, device: &DeviceInfo) -> MisaResult<DeviceConnection>;
+}
+
+/// Dials the device service's WebSocket endpoint at the device's last known address
+pub struct WebSocketConnector;
+
+#[async_trait::async_trait]
+impl DeviceConnector for WebSocketConnector {
+    async fn connect(&self, manager: &DeviceManager, device: &DeviceInfo) -> MisaResult<DeviceConnection> {
+        let addr = format!("{}:{}", device.network_info.ip_address, DEVICE_SERVICE_PORT);
+        manager.dial_device_channel(&addr, &device.device_id).await
+    }
+}
+
+/// Text frames a device channel writes, whichever WebSocket implementation carries them
+pub type DeviceFrameSink = Pin<Box<dyn futures_util::Sink<String, Error = MisaError> + Send>>;
+
+/// Text frames a device channel reads; the stream ends when the transport closes
+pub type DeviceFrameStream = BoxStream<'static, MisaResult<String>>;
+
+/// Split a tungstenite WebSocket into the text frames of a device channel
+pub fn websocket_frames<S>(websocket: tokio_tungstenite::WebSocketStream<S>) -> (DeviceFrameSink, DeviceFrameStream)
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (sink, stream) = websocket.split();
+    let sink = sink
+        .sink_map_err(|e| MisaError::Device(format!("WebSocket send failed: {}", e)))
+        .with(|text: String| futures_util::future::ok::<_, MisaError>(Message::Text(text)));
+    let stream = stream.filter_map(|frame| {
+        futures_util::future::ready(match frame {
+            Ok(Message::Text(text)) => Some(Ok(text)),
+            Ok(_) => None,
+            Err(e) => Some(Err(MisaError::Device(format!("WebSocket receive failed: {}", e)))),
+        })
+    });
+    (Box::pin(sink), stream.boxed())
+}
+
+/// Carry the text frames of a device channel over a WebRTC data channel
+pub fn data_channel_frames(channel: &Arc<RTCDataChannel>) -> (DeviceFrameSink, DeviceFrameStream) {
+    let (frames_tx, frames_rx) = mpsc::unbounded_channel::<MisaResult<String>>();
+    channel.on_message(Box::new(move |frame: DataChannelMessage| {
+        let frame = String::from_utf8(frame.data.to_vec())
+            .map_err(|e| MisaError::Device(format!("Data channel frame is not text: {}", e)));
+        let _ = frames_tx.send(frame);
+        Box::pin(async {})
+    }));
+
+    let sink = futures_util::sink::unfold(Arc::clone(channel), |channel, text: String| async move {
+        channel
+            .send(&bytes::Bytes::from(text.into_bytes()))
+            .await
+            .map_err(|e| MisaError::Device(format!("Data channel send failed: {}", e)))?;
+        Ok::<_, MisaError>(channel)
+    });
+    let stream = futures_util::stream::unfold(frames_rx, |mut frames_rx| async move {
+        frames_rx.recv().await.map(|frame| (frame, frames_rx))
+    });
+    (Box::pin(sink), stream.boxed())
+}
+
+/// First frame the accepting end of a device channel sends, before either side says who it is
+#[derive(Debug, Serialize, Deserialize)]
+struct DeviceChallenge {
+    nonce: String,
+}
+
+/// Frame each end of a device channel proves itself with, answering the other end's nonce
+#[derive(Debug, Serialize, Deserialize)]
+struct DeviceHello {
+    device_id: String,
+    /// Nonce the accepting end must answer in its own hello; only the dialer sends one
+    #[serde(default)]
+    challenge: Option<String>,
+    /// A `HelloProof` sealed with the key the two devices share
+    proof: EncryptedData,
+}
+
+/// What a hello proves: who sent it, to whom, and that it was made for this handshake
+#[derive(Debug, Serialize, Deserialize)]
+struct HelloProof {
+    from: String,
+    to: String,
+    /// The nonce the other end challenged the sender with
+    nonce: String,
+}
+
+/// Dead letters kept before the oldest are discarded
+const MAX_DEAD_LETTERS: usize = 1000;
+
+/// Upper bound on the delay between delivery retries
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// A message that could not be delivered after all retries
+#[derive(Debug, Clone)]
+pub struct DeadLetter {
+    pub device_id: String,
+    /// The message as it was sent, sealed for the device if encrypted
+    pub message: DeviceMessage,
+    pub attempts: u32,
+    pub last_error: String,
+    pub failed_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// File device groups are persisted in, relative to the data directory
+const DEVICE_GROUPS_FILE: &str = "device_groups.json";
+
+/// File this device's id is persisted in, relative to the data directory
+const DEVICE_ID_FILE: &str = "device_id";
+
+/// Read this device's id from `data_dir`, generating and persisting one on first start
+pub async fn load_or_create_device_id(data_dir: &str) -> MisaResult<String> {
+    let path = Path::new(data_dir).join(DEVICE_ID_FILE);
+    match tokio::fs::read_to_string(&path).await {
+        Ok(device_id) if !device_id.trim().is_empty() => return Ok(device_id.trim().to_string()),
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    let device_id = uuid::Uuid::new_v4().to_string();
+    write_file_atomic(&path, device_id.as_bytes()).await?;
+    info!("Generated device id {}", device_id);
+    Ok(device_id)
+}
+
+/// Named set of devices that can be messaged together
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceGroup {
+    pub id: String,
+    pub name: String,
+    pub device_ids: Vec<String>,
+}
+
+/// Outcome of sending a group message to one member
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeliveryResult {
+    Delivered,
+    /// The member had no live connection, so nothing was sent
+    Offline,
+    Failed(String),
+}
+
+/// Token bucket throttling the messages one device sends us
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: std::time::Instant,
+    /// Set while the device is over its limit, so only the first rejection is audited
+    throttled: bool,
+}
+
+impl TokenBucket {
+    fn new(capacity: f64) -> Self {
+        Self {
+            tokens: capacity,
+            last_refill: std::time::Instant::now(),
+            throttled: false,
+        }
+    }
+
+    /// Refill at `rate` tokens per second up to `capacity`, then take one if available
+    fn try_take(&mut self, rate: f64, capacity: f64) -> bool {
+        let now = std::time::Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            self.throttled = false;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Whether the bucket has refilled completely, making it no different from a fresh one
+    fn is_idle(&self, rate: f64, capacity: f64) -> bool {
+        self.tokens + self.last_refill.elapsed().as_secs_f64() * rate >= capacity
+    }
+}
+
+/// Workload profile used to weight device selection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TaskProfile {
+    /// Interactive work where round-trip time and link stability dominate
+    Latency,
+    /// Bulk data movement where bandwidth dominates
+    Throughput,
+    /// Heavy local computation where raw device capability dominates
+    Compute,
+}
+
+impl TaskProfile {
+    /// Weights for (compute, latency, bandwidth, stability) components
+    fn weights(&self) -> (f64, f64, f64, f64) {
+        match self {
+            TaskProfile::Latency => (0.1, 0.45, 0.05, 0.4),
+            TaskProfile::Throughput => (0.15, 0.1, 0.5, 0.25),
+            TaskProfile::Compute => (0.7, 0.05, 0.05, 0.2),
+        }
+    }
+
+    /// Infer a profile from a kernel task type
+    pub fn from_task_type(task_type: &str) -> Self {
+        match task_type.to_lowercase().as_str() {
+            "speech" | "tts" | "chat" => TaskProfile::Latency,
+            "file_transfer" | "remote_desktop" => TaskProfile::Throughput,
+            _ => TaskProfile::Compute,
+        }
+    }
+}
+
+/// Online device with the score it was ranked by
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceScore {
+    pub device_id: String,
+    pub score: f64,
+}
+
+/// Device picked for a task and the scored candidates it was picked from
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeviceSelection {
+    pub device_id: Option<String>,
+    /// Best first; empty when an explicit preference decided the device
+    pub candidates: Vec<DeviceScore>,
 }
 
 /// Device information
@@ -85,6 +459,77 @@ pub struct DeviceCapabilities {
     pub gpu_memory_mb: Option<u64>,
     pub battery_powered: bool,
     pub supports_remote_desktop: bool,
+    /// Offered during the remote desktop handshake
+    #[serde(default)]
+    pub remote_desktop: RemoteDesktopCapabilities,
+}
+
+/// Protocols, codecs and resolution a device can use for remote desktop,
+/// listed in order of preference
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteDesktopCapabilities {
+    pub protocols: Vec<RemoteDesktopProtocol>,
+    pub codecs: Vec<ImageFormat>,
+    pub max_resolution: (u32, u32),
+}
+
+/// Settings both ends of a remote desktop session agreed on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NegotiatedSettings {
+    pub protocol: RemoteDesktopProtocol,
+    pub codec: ImageFormat,
+    pub max_resolution: (u32, u32),
+}
+
+impl RemoteDesktopCapabilities {
+    /// Pick the first of our preferences the remote side also supports
+    ///
+    /// The resolution is capped per dimension by whichever side is smaller.
+    pub fn negotiate(&self, remote: &RemoteDesktopCapabilities) -> MisaResult<NegotiatedSettings> {
+        let protocol = self
+            .protocols
+            .iter()
+            .find(|protocol| remote.protocols.contains(protocol))
+            .cloned()
+            .ok_or_else(|| {
+                MisaError::RemoteDesktop(format!(
+                    "No common remote desktop protocol: local supports {:?}, remote supports {:?}",
+                    self.protocols, remote.protocols
+                ))
+            })?;
+
+        let codec = self
+            .codecs
+            .iter()
+            .find(|codec| remote.codecs.contains(codec))
+            .cloned()
+            .ok_or_else(|| {
+                MisaError::RemoteDesktop(format!(
+                    "No common remote desktop codec: local supports {:?}, remote supports {:?}",
+                    self.codecs, remote.codecs
+                ))
+            })?;
+
+        let max_resolution = (
+            self.max_resolution.0.min(remote.max_resolution.0),
+            self.max_resolution.1.min(remote.max_resolution.1),
+        );
+        if max_resolution.0 == 0 || max_resolution.1 == 0 {
+            return Err(MisaError::RemoteDesktop(format!(
+                "No usable remote desktop resolution: local max {:?}, remote max {:?}",
+                self.max_resolution, remote.max_resolution
+            )));
+        }
+
+        Ok(NegotiatedSettings { protocol, codec, max_resolution })
+    }
+}
+
+impl NegotiatedSettings {
+    /// Clamp `resolution` to the negotiated maximum
+    pub fn fit_resolution(&self, resolution: (u32, u32)) -> (u32, u32) {
+        (resolution.0.min(self.max_resolution.0), resolution.1.min(self.max_resolution.1))
+    }
 }
 
 /// Device status
@@ -130,10 +575,12 @@ pub struct LocationInfo {
 pub struct DeviceConnection {
     pub device_id: String,
     pub connection_type: ConnectionProtocol,
-    pub websocket: Option<Arc<tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>>>,
+    /// Messages for the task that seals and writes them to the device's WebSocket channel
+    pub websocket: Option<mpsc::UnboundedSender<DeviceMessage>>,
     pub webrtc_connection: Option<WebRTCConnection>,
     pub last_heartbeat: chrono::DateTime<chrono::Utc>,
     pub encrypted_channel: bool,
+    pub local_channel: Option<mpsc::UnboundedSender<DeviceMessage>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -142,20 +589,50 @@ pub enum ConnectionProtocol {
     WebRTC,
     gRPC,
     Bluetooth,
+    Local, // In-process channel between managers
 }
 
 /// WebRTC connection information
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct WebRTCConnection {
     pub peer_id: String,
-    pub data_channel: String,
+    pub peer_connection: Arc<RTCPeerConnection>,
+    /// Channel carrying serialized `DeviceMessage`s
+    pub data_channel: Arc<RTCDataChannel>,
     pub video_channels: Vec<String>,
     pub audio_channels: Vec<String>,
 }
 
+impl WebRTCConnection {
+    /// Wrap an established peer connection and the data channel used for messages
+    pub fn new(peer_id: &str, peer_connection: Arc<RTCPeerConnection>, data_channel: Arc<RTCDataChannel>) -> Self {
+        Self {
+            peer_id: peer_id.to_string(),
+            peer_connection,
+            data_channel,
+            video_channels: Vec::new(),
+            audio_channels: Vec::new(),
+        }
+    }
+}
+
+impl std::fmt::Debug for WebRTCConnection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebRTCConnection")
+            .field("peer_id", &self.peer_id)
+            .field("data_channel", &self.data_channel.label())
+            .field("video_channels", &self.video_channels)
+            .field("audio_channels", &self.audio_channels)
+            .finish()
+    }
+}
+
 /// Enhanced Discovery service for device finding
 pub struct DiscoveryService {
     enabled: bool,
+    transport: DiscoveryTransport,
+    device_id: String,
+    mdns: Arc<RwLock<Option<mdns_sd::ServiceDaemon>>>,
     discovery_port: u16,
     broadcast_interval_seconds: u64,
     active_discovery: Arc<RwLock<HashMap<String, DiscoverySession>>>,
@@ -188,6 +665,7 @@ pub struct DeviceHistory {
     pub success_rate: f32,
     pub preferred_for_tasks: Vec<String>,
     pub device_type: DeviceType,
+    pub last_known_addr: Option<SocketAddr>,
 }
 
 /// Connection quality monitor
@@ -195,6 +673,16 @@ pub struct DeviceHistory {
 pub struct ConnectionQualityMonitor {
     pub active_connections: Arc<RwLock<HashMap<String, ConnectionQuality>>>,
     pub quality_history: Arc<RwLock<Vec<QualityMeasurement>>>,
+    probe_addrs: Arc<RwLock<HashMap<String, SocketAddr>>>,
+    probe_port: u16,
+}
+
+/// Result of a round of latency probes
+#[derive(Debug, Clone)]
+pub struct LatencySample {
+    pub latency_ms: u64,
+    pub jitter_ms: u64,
+    pub packet_loss: f32,
 }
 
 #[derive(Debug, Clone)]
@@ -203,9 +691,15 @@ pub struct ConnectionQuality {
     pub latency_ms: u64,
     pub bandwidth_mbps: f32,
     pub signal_strength: f32,
+    /// Derived from jitter and packet loss, from 0.0 (unusable) to 1.0 (steady)
     pub stability_score: f32,
     pub last_updated: chrono::DateTime<chrono::Utc>,
+    /// Share of recent probe rounds the device answered
     pub uptime_percentage: f32,
+    /// Standard deviation of recent round latencies
+    pub jitter_ms: u64,
+    /// Share of recent pings that went unanswered
+    pub packet_loss: f32,
 }
 
 #[derive(Debug, Clone)]
@@ -229,9 +723,61 @@ pub enum PairingStatus {
 /// Remote desktop manager
 pub struct RemoteDesktopManager {
     enabled: bool,
+    /// This device's id, recorded as the client of the sessions it starts
+    device_id: String,
     active_sessions: Arc<RwLock<HashMap<String, RemoteDesktopSession>>>,
+    /// Sessions paired devices control on this one, by session id
+    hosted_sessions: Arc<RwLock<HashMap<String, HostedSession>>>,
+    capture_streams: Arc<RwLock<HashMap<String, ScreenCaptureStream>>>,
+    connection_quality: Arc<RwLock<HashMap<String, ConnectionQuality>>>,
     screen_capturer: ScreenCapturer,
     file_transfer_manager: FileTransferManager,
+    events: broadcast::Sender<RemoteDesktopEvent>,
+    quality_check_interval: Duration,
+    capabilities: RemoteDesktopCapabilities,
+    input_injector: Arc<dyn InputInjector>,
+}
+
+/// Session another device controls on this one, recorded when its handshake is answered
+#[derive(Debug, Clone)]
+struct HostedSession {
+    controller_device_id: String,
+    permissions: RemoteDesktopPermissions,
+}
+
+/// Bitrate a capture stream aims for unless the link allows less
+const DEFAULT_TARGET_BITRATE_KBPS: u32 = 8_000;
+
+/// How often capture streams re-check link quality
+const QUALITY_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Links slower than this lose one quality step regardless of bandwidth
+const HIGH_LATENCY_MS: u64 = 200;
+
+/// Share of measured bandwidth a stream may use
+const BANDWIDTH_HEADROOM: f32 = 0.8;
+
+/// Remote desktop session lifecycle events
+#[derive(Debug, Clone)]
+pub enum RemoteDesktopEvent {
+    SessionStarted {
+        session_id: String,
+        host_device_id: String,
+        request_id: Option<String>,
+    },
+    SessionStopped {
+        session_id: String,
+        request_id: Option<String>,
+    },
+    PermissionsUpdated {
+        session_id: String,
+        permissions: RemoteDesktopPermissions,
+        request_id: Option<String>,
+    },
+    QualityChanged {
+        session_id: String,
+        quality: VideoQuality,
+    },
 }
 
 /// Remote desktop session
@@ -241,14 +787,19 @@ pub struct RemoteDesktopSession {
     pub host_device_id: String,
     pub client_device_id: String,
     pub protocol: RemoteDesktopProtocol,
+    pub codec: ImageFormat,
+    pub max_resolution: (u32, u32),
     pub resolution: (u32, u32),
     pub quality: VideoQuality,
     pub permissions: RemoteDesktopPermissions,
     pub started_at: chrono::DateTime<chrono::Utc>,
     pub screen_recording: bool,
+    /// Display being streamed; the primary one when unset
+    #[serde(default)]
+    pub display_id: Option<u32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RemoteDesktopProtocol {
     VNC,
     RDP,
@@ -256,7 +807,7 @@ pub enum RemoteDesktopProtocol {
     Custom,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum VideoQuality {
     Low,     // 480p
     Medium,  // 720p
@@ -264,6 +815,72 @@ pub enum VideoQuality {
     Ultra,   // 4K
 }
 
+impl VideoQuality {
+    const ALL: [VideoQuality; 4] = [VideoQuality::Low, VideoQuality::Medium, VideoQuality::High, VideoQuality::Ultra];
+
+    pub fn resolution(&self) -> (u32, u32) {
+        match self {
+            VideoQuality::Low => (854, 480),
+            VideoQuality::Medium => (1280, 720),
+            VideoQuality::High => (1920, 1080),
+            VideoQuality::Ultra => (3840, 2160),
+        }
+    }
+
+    pub fn frame_rate(&self) -> u32 {
+        match self {
+            VideoQuality::Low => 10,
+            VideoQuality::Medium => 20,
+            VideoQuality::High | VideoQuality::Ultra => 30,
+        }
+    }
+
+    /// Approximate encoded bitrate needed at this quality
+    pub fn bitrate_kbps(&self) -> u32 {
+        match self {
+            VideoQuality::Low => 1_000,
+            VideoQuality::Medium => 2_500,
+            VideoQuality::High => 5_000,
+            VideoQuality::Ultra => 15_000,
+        }
+    }
+
+    /// Best quality that fits within `available_kbps`, never below `Low`
+    pub fn for_bitrate(available_kbps: u32) -> VideoQuality {
+        Self::ALL
+            .iter()
+            .rev()
+            .copied()
+            .find(|quality| quality.bitrate_kbps() <= available_kbps)
+            .unwrap_or(VideoQuality::Low)
+    }
+
+    /// Best quality a link can carry without exceeding the target bitrate
+    pub fn for_link(link: &ConnectionQuality, target_bitrate_kbps: u32) -> VideoQuality {
+        // Zero means bandwidth has not been measured yet, so only the target applies
+        let available_kbps = if link.bandwidth_mbps > 0.0 {
+            ((link.bandwidth_mbps * 1000.0 * BANDWIDTH_HEADROOM) as u32).min(target_bitrate_kbps)
+        } else {
+            target_bitrate_kbps
+        };
+        let quality = Self::for_bitrate(available_kbps);
+
+        if link.latency_ms > HIGH_LATENCY_MS {
+            quality.step_down()
+        } else {
+            quality
+        }
+    }
+
+    fn step_down(&self) -> VideoQuality {
+        match self {
+            VideoQuality::Ultra => VideoQuality::High,
+            VideoQuality::High => VideoQuality::Medium,
+            VideoQuality::Medium | VideoQuality::Low => VideoQuality::Low,
+        }
+    }
+}
+
 /// Remote desktop permissions
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RemoteDesktopPermissions {
@@ -276,14 +893,70 @@ pub struct RemoteDesktopPermissions {
     pub system_commands: bool,
 }
 
+impl RemoteDesktopPermissions {
+    /// Watching the screen and nothing else
+    pub fn view_only() -> Self {
+        Self {
+            view_screen: true,
+            control_mouse: false,
+            control_keyboard: false,
+            transfer_files: false,
+            access_clipboard: false,
+            record_session: false,
+            system_commands: false,
+        }
+    }
+
+    /// Only what both sets allow
+    pub fn intersect(&self, other: &RemoteDesktopPermissions) -> Self {
+        Self {
+            view_screen: self.view_screen && other.view_screen,
+            control_mouse: self.control_mouse && other.control_mouse,
+            control_keyboard: self.control_keyboard && other.control_keyboard,
+            transfer_files: self.transfer_files && other.transfer_files,
+            access_clipboard: self.access_clipboard && other.access_clipboard,
+            record_session: self.record_session && other.record_session,
+            system_commands: self.system_commands && other.system_commands,
+        }
+    }
+}
+
 /// Screen capturer
 pub struct ScreenCapturer {
     capture_interval_ms: u64,
     compression_enabled: bool,
     supported_formats: Vec<ImageFormat>,
+    /// Without a policy every capture is refused
+    capture_policy: Option<CapturePolicy>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Consent checks and auditing applied before the screen is captured
+#[derive(Clone)]
+struct CapturePolicy {
+    privacy_controls: PrivacyControls,
+    security_manager: SecurityManager,
+}
+
+/// A monitor that can be captured
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DisplayInfo {
+    pub id: u32,
+    pub name: String,
+    pub bounds: DisplayBounds,
+    pub scale_factor: f32,
+    pub is_primary: bool,
+}
+
+/// Where a display sits on the virtual desktop and its size, as reported by the OS
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisplayBounds {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ImageFormat {
     PNG,
     JPEG,
@@ -294,10 +967,125 @@ pub enum ImageFormat {
 
 /// File transfer manager
 pub struct FileTransferManager {
+    /// Id of this device, the source of every transfer it sends
+    device_id: String,
     max_file_size_mb: u64,
     allowed_file_types: Vec<String>,
     encryption_required: bool,
+    download_dir: PathBuf,
+    security_manager: SecurityManager,
+    connections: Arc<RwLock<HashMap<String, DeviceConnection>>>,
     active_transfers: Arc<RwLock<HashMap<String, FileTransfer>>>,
+    transfer_controls: Arc<RwLock<HashMap<String, watch::Sender<TransferControl>>>>,
+    incoming_transfers: Arc<RwLock<HashMap<String, IncomingTransfer>>>,
+    /// Expiry of grants already used, so each one starts a single transfer
+    redeemed_grants: Arc<RwLock<HashMap<String, i64>>>,
+}
+
+/// Control signal observed by a running transfer between chunks
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TransferControl {
+    Run,
+    Pause,
+    Cancel,
+}
+
+/// How a run of the chunk loop ended
+enum TransferOutcome {
+    Completed,
+    Paused,
+    Cancelled,
+}
+
+/// Request waiting for the device it was sent to to answer
+struct PendingRequest {
+    target_device_id: String,
+    responder: oneshot::Sender<serde_json::Value>,
+}
+
+/// File being reassembled from chunks sent by a peer
+#[derive(Debug, Clone)]
+pub struct IncomingTransfer {
+    pub transfer_id: String,
+    pub source_device_id: String,
+    pub file_path: PathBuf,
+    pub file_size: u64,
+    pub bytes_received: u64,
+    /// Offsets of the chunks written so far, so a resent chunk isn't counted twice
+    pub received_offsets: HashSet<u64>,
+    /// Chunks must arrive sealed with the sender's device key
+    pub encrypted: bool,
+}
+
+/// Asks a device to accept a file. Sent again with the device's grant to start the transfer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileTransferRequest {
+    pub file_name: String,
+    pub file_size: u64,
+    #[serde(default)]
+    pub encrypted: bool,
+    #[serde(default)]
+    pub grant: Option<TransferGrant>,
+}
+
+/// A device's answer to a `FileTransferRequest`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileTransferResponse {
+    pub grant: Option<TransferGrant>,
+    /// Why the transfer was declined
+    pub reason: Option<String>,
+}
+
+/// Short-lived permission, signed by the receiving device, to send it one file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransferGrant {
+    pub transfer_id: String,
+    /// Device allowed to send the file
+    pub source_device_id: String,
+    pub file_name: String,
+    pub file_size: u64,
+    /// Whether the file must be sent sealed with the device key; the receiver decides
+    #[serde(default)]
+    pub encrypted: bool,
+    /// Unix timestamp after which the grant is void
+    pub expires_at: i64,
+    /// Hex HMAC by the granting device over the fields above
+    pub signature: String,
+}
+
+impl TransferGrant {
+    fn signed_payload(&self) -> MisaResult<Vec<u8>> {
+        Ok(serde_json::to_vec(&(
+            &self.transfer_id,
+            &self.source_device_id,
+            &self.file_name,
+            self.file_size,
+            self.encrypted,
+            self.expires_at,
+        ))?)
+    }
+
+    fn is_expired(&self) -> bool {
+        chrono::Utc::now().timestamp() >= self.expires_at
+    }
+}
+
+/// Decides whether to accept files offered by other devices
+pub trait TransferApprover: Send + Sync {
+    fn approve(&self, source_device_id: &str, request: &FileTransferRequest) -> bool;
+}
+
+/// Decides what control another device gets when it starts a remote desktop session on this one
+pub trait RemoteDesktopApprover: Send + Sync {
+    /// Permissions to grant `controller_device_id`, or `None` to decline the session.
+    /// Anything beyond what was requested is never granted.
+    fn approve(&self, controller_device_id: &str, requested: &RemoteDesktopPermissions) -> Option<RemoteDesktopPermissions>;
+}
+
+/// Runs a task delegated by another device, returning the result sent back to it
+#[async_trait::async_trait]
+pub trait TaskHandler: Send + Sync {
+    async fn handle_task(&self, source_device_id: &str, task: serde_json::Value) -> MisaResult<serde_json::Value>;
 }
 
 /// File transfer
@@ -312,6 +1100,10 @@ pub struct FileTransfer {
     pub encryption_key: Option<String>,
     pub status: FileTransferStatus,
     pub started_at: chrono::DateTime<chrono::Utc>,
+    /// MIME type detected from the file's content
+    pub mime_type: String,
+    /// The target's permission, presented when the transfer is announced
+    pub grant: TransferGrant,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -330,41 +1122,360 @@ pub struct ClipboardSync {
     sync_interval_seconds: u64,
     last_clipboard_hash: Arc<RwLock<Option<String>>>,
     supported_formats: Vec<String>,
+    backend: Arc<dyn ClipboardBackend>,
 }
 
-/// Device discovery packet for network broadcasting
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DeviceDiscoveryPacket {
-    pub device_id: String,
-    pub device_name: String,
-    pub device_type: String,
-    pub capabilities: Vec<String>,
-    pub port: u16,
-    pub timestamp: chrono::DateTime<chrono::Utc>,
+/// Clipboard data in one of the synced formats
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClipboardContent {
+    Text(String),
+    /// Raw RGBA pixels; sent over the wire as PNG
+    Image {
+        width: u32,
+        height: u32,
+        rgba: Vec<u8>,
+    },
 }
 
-/// Device communication message
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DeviceMessage {
-    pub message_id: String,
-    pub source_device_id: String,
-    pub target_device_id: Option<String>, // None for broadcast
-    pub message_type: MessageType,
-    pub payload: serde_json::Value,
-    pub timestamp: chrono::DateTime<chrono::Utc>,
-    pub encrypted: bool,
-    pub priority: MessagePriority,
-}
+impl ClipboardContent {
+    /// MIME type used in sync payloads
+    pub fn format(&self) -> &'static str {
+        match self {
+            ClipboardContent::Text(_) => "text/plain",
+            ClipboardContent::Image { .. } => "image/png",
+        }
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum MessageType {
-    Heartbeat,
-    SystemInfo,
-    TaskRequest,
-    TaskResponse,
-    RemoteDesktopRequest,
-    RemoteDesktopData,
-    FileTransferRequest,
+    /// Change-detection hash. Images hash their pixels so re-encoding does not look like a change.
+    fn content_hash(&self) -> String {
+        match self {
+            ClipboardContent::Text(text) => clipboard_hash(text),
+            ClipboardContent::Image { width, height, rgba } => {
+                let mut data = format!("image:{}x{}:", width, height).into_bytes();
+                data.extend_from_slice(rgba);
+                clipboard_hash(data)
+            }
+        }
+    }
+
+    /// Content field of a `ClipboardSync` payload
+    fn to_payload_content(&self) -> MisaResult<String> {
+        match self {
+            ClipboardContent::Text(text) => Ok(text.clone()),
+            ClipboardContent::Image { width, height, rgba } => {
+                let image = image::RgbaImage::from_raw(*width, *height, rgba.clone())
+                    .ok_or_else(|| MisaError::Device("Clipboard image size does not match its pixels".to_string()))?;
+                let mut png = Vec::new();
+                image::DynamicImage::ImageRgba8(image)
+                    .write_to(&mut std::io::Cursor::new(&mut png), image::ImageOutputFormat::Png)
+                    .map_err(|e| MisaError::Device(format!("Failed to encode clipboard image: {}", e)))?;
+                Ok(BASE64.encode(png))
+            }
+        }
+    }
+
+    /// Rebuild content from a `ClipboardSync` payload
+    fn from_payload(payload: &serde_json::Value) -> MisaResult<Self> {
+        let content = payload["content"]
+            .as_str()
+            .ok_or_else(|| MisaError::Device("Clipboard payload has no content".to_string()))?;
+
+        match payload["format"].as_str().unwrap_or("text/plain") {
+            "text/plain" => Ok(ClipboardContent::Text(content.to_string())),
+            "image/png" => {
+                let png = BASE64
+                    .decode(content)
+                    .map_err(|e| MisaError::Device(format!("Invalid clipboard image encoding: {}", e)))?;
+                let image = image::load_from_memory_with_format(&png, image::ImageFormat::Png)
+                    .map_err(|e| MisaError::Device(format!("Invalid clipboard image: {}", e)))?
+                    .to_rgba8();
+                Ok(ClipboardContent::Image {
+                    width: image.width(),
+                    height: image.height(),
+                    rgba: image.into_raw(),
+                })
+            }
+            other => Err(MisaError::Device(format!("Unsupported clipboard format: {}", other))),
+        }
+    }
+}
+
+/// Access to the platform clipboard
+pub trait ClipboardBackend: Send + Sync {
+    /// Current content, or `None` if the clipboard holds nothing we sync
+    fn read(&self) -> MisaResult<Option<ClipboardContent>>;
+    fn write(&self, content: &ClipboardContent) -> MisaResult<()>;
+}
+
+/// The system clipboard via `arboard`
+pub struct SystemClipboard;
+
+impl ClipboardBackend for SystemClipboard {
+    fn read(&self) -> MisaResult<Option<ClipboardContent>> {
+        let mut clipboard = arboard::Clipboard::new()
+            .map_err(|e| MisaError::Device(format!("Clipboard unavailable: {}", e)))?;
+
+        match clipboard.get_text() {
+            Ok(text) if !text.is_empty() => return Ok(Some(ClipboardContent::Text(text))),
+            Ok(_) | Err(arboard::Error::ContentNotAvailable) => {}
+            Err(e) => return Err(MisaError::Device(format!("Failed to read clipboard text: {}", e))),
+        }
+
+        match clipboard.get_image() {
+            Ok(image) => Ok(Some(ClipboardContent::Image {
+                width: image.width as u32,
+                height: image.height as u32,
+                rgba: image.bytes.into_owned(),
+            })),
+            Err(arboard::Error::ContentNotAvailable) => Ok(None),
+            Err(e) => Err(MisaError::Device(format!("Failed to read clipboard image: {}", e))),
+        }
+    }
+
+    fn write(&self, content: &ClipboardContent) -> MisaResult<()> {
+        let mut clipboard = arboard::Clipboard::new()
+            .map_err(|e| MisaError::Device(format!("Clipboard unavailable: {}", e)))?;
+
+        let result = match content {
+            ClipboardContent::Text(text) => clipboard.set_text(text.clone()),
+            ClipboardContent::Image { width, height, rgba } => clipboard.set_image(arboard::ImageData {
+                width: *width as usize,
+                height: *height as usize,
+                bytes: std::borrow::Cow::Borrowed(rgba),
+            }),
+        };
+
+        result.map_err(|e| MisaError::Device(format!("Failed to write clipboard: {}", e)))
+    }
+}
+
+/// Input a remote controller sends in the payload of a `ControlCommand`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteInputCommand {
+    pub session_id: String,
+    pub input: InputEvent,
+}
+
+/// A single mouse or keyboard event to replay on this device
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InputEvent {
+    /// Move the pointer to absolute screen coordinates
+    MouseMove { x: i32, y: i32 },
+    MouseButton {
+        button: MouseButton,
+        #[serde(default)]
+        action: InputAction,
+    },
+    /// `key` is a single character or a named key such as `"enter"`
+    Key {
+        key: String,
+        #[serde(default)]
+        action: InputAction,
+    },
+}
+
+impl InputEvent {
+    fn is_keyboard(&self) -> bool {
+        matches!(self, InputEvent::Key { .. })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputAction {
+    Press,
+    Release,
+    /// Press and release
+    #[default]
+    Click,
+}
+
+/// Replays input events on the local machine
+pub trait InputInjector: Send + Sync {
+    fn inject(&self, event: &InputEvent) -> MisaResult<()>;
+}
+
+/// An event for the injector thread and where to send the outcome
+type InjectRequest = (InputEvent, std::sync::mpsc::Sender<MisaResult<()>>);
+
+/// Platform input via `enigo`, replayed on one thread that keeps the injector open.
+/// Opening it connects to the display server, which is too slow to do per event.
+pub struct SystemInput {
+    requests: std::sync::Mutex<Option<std::sync::mpsc::Sender<InjectRequest>>>,
+}
+
+impl SystemInput {
+    pub fn new() -> Self {
+        Self { requests: std::sync::Mutex::new(None) }
+    }
+
+    /// Channel to the injector thread, started on first use; it stops once `self` is dropped
+    fn requests(&self) -> std::sync::mpsc::Sender<InjectRequest> {
+        let mut requests = self.requests.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        requests
+            .get_or_insert_with(|| {
+                let (tx, rx) = std::sync::mpsc::channel::<InjectRequest>();
+                std::thread::spawn(move || {
+                    let mut enigo = None;
+                    for (event, reply) in rx {
+                        let _ = reply.send(Self::replay(&mut enigo, &event));
+                    }
+                });
+                tx
+            })
+            .clone()
+    }
+
+    fn replay(enigo: &mut Option<enigo::Enigo>, event: &InputEvent) -> MisaResult<()> {
+        use enigo::{Coordinate, Keyboard, Mouse};
+
+        // A failed open is retried on the next event
+        if enigo.is_none() {
+            *enigo = Some(
+                enigo::Enigo::new(&enigo::Settings::default())
+                    .map_err(|e| MisaError::Device(format!("Input injection unavailable: {}", e)))?,
+            );
+        }
+        let Some(enigo) = enigo.as_mut() else {
+            return Err(MisaError::Device("Input injection unavailable".to_string()));
+        };
+
+        let result = match event {
+            InputEvent::MouseMove { x, y } => enigo.move_mouse(*x, *y, Coordinate::Abs),
+            InputEvent::MouseButton { button, action } => {
+                let button = match button {
+                    MouseButton::Left => enigo::Button::Left,
+                    MouseButton::Middle => enigo::Button::Middle,
+                    MouseButton::Right => enigo::Button::Right,
+                };
+                enigo.button(button, enigo_direction(*action))
+            }
+            InputEvent::Key { key, action } => enigo.key(enigo_key(key)?, enigo_direction(*action)),
+        };
+
+        result.map_err(|e| MisaError::Device(format!("Failed to inject input: {}", e)))
+    }
+}
+
+impl Default for SystemInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InputInjector for SystemInput {
+    /// Blocks until the injector thread has replayed `event`
+    fn inject(&self, event: &InputEvent) -> MisaResult<()> {
+        let stopped = || MisaError::Device("Input injector stopped".to_string());
+        let (reply_tx, reply_rx) = std::sync::mpsc::channel();
+        self.requests().send((event.clone(), reply_tx)).map_err(|_| stopped())?;
+        reply_rx.recv().map_err(|_| stopped())?
+    }
+}
+
+fn enigo_direction(action: InputAction) -> enigo::Direction {
+    match action {
+        InputAction::Press => enigo::Direction::Press,
+        InputAction::Release => enigo::Direction::Release,
+        InputAction::Click => enigo::Direction::Click,
+    }
+}
+
+fn enigo_key(key: &str) -> MisaResult<enigo::Key> {
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(enigo::Key::Unicode(c));
+    }
+
+    let key = match key.to_ascii_lowercase().as_str() {
+        "enter" | "return" => enigo::Key::Return,
+        "tab" => enigo::Key::Tab,
+        "escape" | "esc" => enigo::Key::Escape,
+        "backspace" => enigo::Key::Backspace,
+        "delete" => enigo::Key::Delete,
+        "space" => enigo::Key::Space,
+        "shift" => enigo::Key::Shift,
+        "control" | "ctrl" => enigo::Key::Control,
+        "alt" => enigo::Key::Alt,
+        "meta" | "super" | "command" => enigo::Key::Meta,
+        "up" => enigo::Key::UpArrow,
+        "down" => enigo::Key::DownArrow,
+        "left" => enigo::Key::LeftArrow,
+        "right" => enigo::Key::RightArrow,
+        "home" => enigo::Key::Home,
+        "end" => enigo::Key::End,
+        "pageup" => enigo::Key::PageUp,
+        "pagedown" => enigo::Key::PageDown,
+        other => return Err(MisaError::Device(format!("Unsupported key: {}", other))),
+    };
+    Ok(key)
+}
+
+/// Device discovery packet for network broadcasting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceDiscoveryPacket {
+    pub device_id: String,
+    pub device_name: String,
+    pub device_type: String,
+    pub capabilities: Vec<String>,
+    pub port: u16,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Directed probes ask the receiver to answer with its own packet
+    #[serde(default)]
+    pub probe: bool,
+}
+
+/// Device communication message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceMessage {
+    pub message_id: String,
+    pub source_device_id: String,
+    pub target_device_id: Option<String>, // None for broadcast
+    pub message_type: MessageType,
+    pub payload: serde_json::Value,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub encrypted: bool,
+    pub priority: MessagePriority,
+}
+
+impl DeviceMessage {
+    /// Build the `TaskResponse` answering this request
+    pub fn response(&self, source_device_id: &str, result: serde_json::Value) -> DeviceMessage {
+        DeviceMessage {
+            message_id: uuid::Uuid::new_v4().to_string(),
+            source_device_id: source_device_id.to_string(),
+            target_device_id: Some(self.source_device_id.clone()),
+            message_type: MessageType::TaskResponse,
+            payload: serde_json::json!({
+                "correlation_id": self.message_id,
+                "result": result,
+            }),
+            timestamp: chrono::Utc::now(),
+            encrypted: self.encrypted,
+            priority: self.priority.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum MessageType {
+    Heartbeat,
+    SystemInfo,
+    TaskRequest,
+    TaskResponse,
+    RemoteDesktopRequest,
+    RemoteDesktopData,
+    FileTransferRequest,
+    FileTransferResponse,
     FileTransferData,
     ClipboardSync,
     DeviceDiscovery,
@@ -386,19 +1497,52 @@ impl DeviceManager {
     pub async fn new(config: DeviceConfig, security_manager: SecurityManager) -> MisaResult<Self> {
         let devices = Arc::new(RwLock::new(HashMap::new()));
         let active_connections = Arc::new(RwLock::new(HashMap::new()));
-
-        let discovery_service = DiscoveryService::new(config.discovery_enabled);
-        let remote_desktop_manager = RemoteDesktopManager::new(config.remote_desktop_enabled);
-        let clipboard_sync = ClipboardSync::new(true);
+        let device_id = config.device_id.clone().unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+        let discovery_service =
+            DiscoveryService::new(config.discovery_enabled, config.discovery_transport).with_device_id(&device_id);
+        let connection_quality = Arc::clone(&discovery_service.connection_quality_monitor.active_connections);
+        let file_transfer_manager = FileTransferManager::new(
+            &config.file_transfer,
+            &device_id,
+            security_manager.clone(),
+            Arc::clone(&active_connections),
+        );
+        let remote_desktop_manager = RemoteDesktopManager::new(
+            config.remote_desktop_enabled,
+            &device_id,
+            file_transfer_manager,
+            Arc::clone(&connection_quality),
+        );
+        let clipboard_sync = ClipboardSync::new(true).with_enabled(config.clipboard_sync_enabled);
+        let delegated_tasks = Arc::new(Semaphore::new(config.max_concurrent_delegated_tasks.max(1)));
 
         let manager = Self {
             config,
+            device_id,
             security_manager,
             devices,
             active_connections,
-            discovery_service,
+            connection_quality,
+            discovery_service: Arc::new(discovery_service),
             remote_desktop_manager,
             clipboard_sync,
+            pending_requests: Arc::new(RwLock::new(HashMap::new())),
+            pending_pairings: Arc::new(RwLock::new(HashMap::new())),
+            inbound_limits: Arc::new(RwLock::new(HashMap::new())),
+            groups: Arc::new(RwLock::new(HashMap::new())),
+            groups_path: None,
+            dead_letters: Arc::new(RwLock::new(VecDeque::new())),
+            transfer_approver: None,
+            task_handler: None,
+            remote_desktop_approver: None,
+            connector: Arc::new(WebSocketConnector),
+            reconnecting: Arc::new(RwLock::new(HashSet::new())),
+            connection_events: broadcast::channel(64).0,
+            heartbeat_task: Arc::new(RwLock::new(None)),
+            delegated_tasks,
+            clipboard_task: Arc::new(RwLock::new(None)),
+            metrics: Metrics::disabled(),
         };
 
         info!("Device manager initialized");
@@ -421,12 +1565,15 @@ impl DeviceManager {
         Ok(())
     }
 
-    /// Pair with a device using QR token
-    pub async fn pair_device(&self, qr_token: &str) -> MisaResult<PairingResult> {
+    /// Pair with the device a QR token was generated for, once it scanned the token
+    /// and answered with its half of the key exchange (see `scan_pairing_token`)
+    pub async fn pair_device(&self, qr_token: &str, peer_public_key: &str) -> MisaResult<PairingResult> {
         info!("Initiating device pairing with QR token");
 
         // Validate QR token format
         let pairing_data = self.parse_qr_token(qr_token)?;
+        let peer_public_key =
+            decode_hex(peer_public_key).ok_or_else(|| MisaError::Device("Invalid device public key".to_string()))?;
 
         // Create discovery session
         let session = DiscoverySession {
@@ -438,22 +1585,80 @@ impl DeviceManager {
         };
 
         // Initiate pairing process
-        let result = self.initiate_pairing(pairing_data, session).await?;
+        self.initiate_pairing(pairing_data, session, &peer_public_key).await
+    }
+
+    /// Take a QR token another device generated for this one: agree the message key
+    /// with the public key the token carries and remember the issuing device.
+    ///
+    /// Returns our public key, which the issuer passes to `pair_device` to finish pairing.
+    pub async fn scan_pairing_token(&self, qr_token: &str) -> MisaResult<String> {
+        let pairing_data = self.parse_qr_token(qr_token)?;
+        if pairing_data.device_id != self.device_id {
+            return Err(MisaError::Device(format!(
+                "Pairing token was issued for device {}, not this one",
+                pairing_data.device_id
+            )));
+        }
+        Self::check_pairing_timestamp(pairing_data.timestamp)?;
+
+        let exchange = self.security_manager.start_device_key_exchange()?;
+        let public_key = encode_hex(exchange.public_key());
+        self.security_manager
+            .complete_device_key_exchange(&pairing_data.host_id, exchange, &pairing_data.host_public_key)
+            .await?;
+        self.devices
+            .write()
+            .await
+            .insert(pairing_data.host_id.clone(), Self::paired_device_info(&pairing_data.host_id));
+
+        info!("Scanned pairing token from device {}", pairing_data.host_id);
+        Ok(public_key)
+    }
+
+    /// Agree a fresh end-to-end message key with a paired, connected device over X25519
+    pub async fn exchange_device_key(&self, device_id: &str) -> MisaResult<()> {
+        if !self.devices.read().await.contains_key(device_id) {
+            return Err(MisaError::Permission(format!("Device {} is not paired", device_id)));
+        }
+
+        let exchange = self.security_manager.start_device_key_exchange()?;
+        let response = self
+            .send_and_await(
+                device_id,
+                MessageType::PairingRequest,
+                serde_json::json!({ "public_key": encode_hex(exchange.public_key()) }),
+                KEY_EXCHANGE_TIMEOUT,
+            )
+            .await?;
+        let peer_public_key = response["public_key"]
+            .as_str()
+            .and_then(decode_hex)
+            .ok_or_else(|| MisaError::Device(format!("Device {} answered the key exchange without a key", device_id)))?;
+
+        self.security_manager
+            .complete_device_key_exchange(device_id, exchange, &peer_public_key)
+            .await
+    }
 
-        Ok(result)
+    /// Generate a signed QR pairing token for a device, carrying our half of the key
+    /// exchange the device completes when it scans it
+    pub async fn generate_pairing_token(&self, device_id: &str) -> MisaResult<String> {
+        self.sign_pairing_token(device_id, chrono::Utc::now().timestamp()).await
     }
 
     /// Send message to device
+    #[instrument(skip_all, fields(request_id, message_id = %message.message_id))]
     pub async fn send_message(&self, message: DeviceMessage) -> MisaResult<()> {
+        correlation::enter_request();
         debug!("Sending message to device: {:?}", message.target_device_id);
 
         if let Some(target_device_id) = &message.target_device_id {
-            let connections = self.active_connections.read().await;
-            if let Some(connection) = connections.get(target_device_id) {
-                self.send_message_via_connection(connection, &message).await?;
-            } else {
+            if !self.active_connections.read().await.contains_key(target_device_id) {
                 return Err(MisaError::Device(format!("No connection to device: {}", target_device_id)));
             }
+            let outgoing = self.seal_message(target_device_id, &message).await?;
+            self.deliver(target_device_id, &outgoing).await?;
         } else {
             // Broadcast to all connected devices
             self.broadcast_message(&message).await?;
@@ -462,12 +1667,220 @@ impl DeviceManager {
         Ok(())
     }
 
-    /// Start remote desktop session
+    /// Create a group of devices that can be messaged together
+    pub async fn create_group(&self, name: &str, device_ids: Vec<String>) -> MisaResult<DeviceGroup> {
+        let group = DeviceGroup {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            device_ids,
+        };
+        self.save_group(group, false).await
+    }
+
+    /// Rename a group and replace its members
+    pub async fn update_group(&self, group_id: &str, name: &str, device_ids: Vec<String>) -> MisaResult<DeviceGroup> {
+        let group = DeviceGroup {
+            id: group_id.to_string(),
+            name: name.to_string(),
+            device_ids,
+        };
+        self.save_group(group, true).await
+    }
+
+    /// Delete a group, returning whether it existed
+    pub async fn delete_group(&self, group_id: &str) -> MisaResult<bool> {
+        let mut groups = self.groups.write().await;
+        if groups.remove(group_id).is_none() {
+            return Ok(false);
+        }
+        self.persist_groups(&groups).await?;
+        Ok(true)
+    }
+
+    pub async fn get_group(&self, group_id: &str) -> Option<DeviceGroup> {
+        self.groups.read().await.get(group_id).cloned()
+    }
+
+    /// All groups, sorted by name
+    pub async fn list_groups(&self) -> Vec<DeviceGroup> {
+        let mut groups: Vec<DeviceGroup> = self.groups.read().await.values().cloned().collect();
+        groups.sort_by(|a, b| a.name.cmp(&b.name));
+        groups
+    }
+
+    /// Send a message to every online member of a group.
+    /// Offline members are skipped and reported rather than failing the whole send.
+    #[instrument(skip_all, fields(request_id, group_id = %group_id))]
+    pub async fn send_to_group(&self, group_id: &str, message: DeviceMessage) -> MisaResult<HashMap<String, DeliveryResult>> {
+        correlation::enter_request();
+        let group = self
+            .get_group(group_id)
+            .await
+            .ok_or_else(|| MisaError::NotFound(format!("Device group not found: {}", group_id)))?;
+
+        let mut results = HashMap::new();
+        for device_id in group.device_ids {
+            if !self.active_connections.read().await.contains_key(&device_id) {
+                results.insert(device_id, DeliveryResult::Offline);
+                continue;
+            }
+
+            let targeted = DeviceMessage {
+                target_device_id: Some(device_id.clone()),
+                ..message.clone()
+            };
+            let result = match self.seal_message(&device_id, &targeted).await {
+                Ok(outgoing) => self.deliver(&device_id, &outgoing).await,
+                Err(e) => Err(e),
+            };
+            let result = match result {
+                Ok(()) => DeliveryResult::Delivered,
+                Err(e) => {
+                    warn!("Failed to send group {} message to device {}: {}", group.id, device_id, e);
+                    DeliveryResult::Failed(e.to_string())
+                }
+            };
+            results.insert(device_id, result);
+        }
+
+        debug!("Sent message {} to group {}: {:?}", message.message_id, group.id, results);
+        Ok(results)
+    }
+
+    async fn save_group(&self, mut group: DeviceGroup, must_exist: bool) -> MisaResult<DeviceGroup> {
+        if group.name.trim().is_empty() {
+            return Err(MisaError::Validation("Device group name must not be empty".to_string()));
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        group.device_ids.retain(|device_id| seen.insert(device_id.clone()));
+        if group.device_ids.is_empty() {
+            return Err(MisaError::Validation(format!("Device group {} has no members", group.name)));
+        }
+
+        let mut groups = self.groups.write().await;
+        if must_exist && !groups.contains_key(&group.id) {
+            return Err(MisaError::NotFound(format!("Device group not found: {}", group.id)));
+        }
+        groups.insert(group.id.clone(), group.clone());
+        self.persist_groups(&groups).await?;
+
+        info!("Saved device group {} with {} members", group.name, group.device_ids.len());
+        Ok(group)
+    }
+
+    async fn persist_groups(&self, groups: &HashMap<String, DeviceGroup>) -> MisaResult<()> {
+        match &self.groups_path {
+            Some(path) => write_json_atomic(path, groups).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Take every message that exhausted its delivery retries, oldest first
+    pub async fn drain_dead_letters(&self) -> Vec<DeadLetter> {
+        self.dead_letters.write().await.drain(..).collect()
+    }
+
+    /// Send a `TaskRequest` to a device and wait for the `TaskResponse` that answers it
+    pub async fn send_request(
+        &self,
+        device_id: &str,
+        payload: serde_json::Value,
+        timeout: Duration,
+    ) -> MisaResult<serde_json::Value> {
+        self.send_and_await(device_id, MessageType::TaskRequest, payload, timeout).await
+    }
+
+    /// Send a request and wait for the response carrying its message id as correlation id
+    async fn send_and_await(
+        &self,
+        device_id: &str,
+        message_type: MessageType,
+        payload: serde_json::Value,
+        timeout: Duration,
+    ) -> MisaResult<serde_json::Value> {
+        let message_id = uuid::Uuid::new_v4().to_string();
+        let (responder, response) = oneshot::channel();
+        self.pending_requests.write().await.insert(
+            message_id.clone(),
+            PendingRequest { target_device_id: device_id.to_string(), responder },
+        );
+
+        let request = DeviceMessage {
+            message_id: message_id.clone(),
+            source_device_id: self.device_id.clone(),
+            target_device_id: Some(device_id.to_string()),
+            message_type,
+            payload,
+            timestamp: chrono::Utc::now(),
+            // Paired devices share a key, so their requests always travel encrypted
+            encrypted: self.security_manager.has_device_key(device_id).await,
+            priority: MessagePriority::Normal,
+        };
+
+        if let Err(e) = self.send_message(request).await {
+            self.pending_requests.write().await.remove(&message_id);
+            return Err(e);
+        }
+
+        match tokio::time::timeout(timeout, response).await {
+            Ok(Ok(result)) => Ok(result),
+            Ok(Err(_)) => Err(MisaError::Device(format!("Request {} was dropped", message_id))),
+            Err(_) => {
+                self.pending_requests.write().await.remove(&message_id);
+                Err(MisaError::Timeout(format!(
+                    "No response from device {} within {:?}",
+                    device_id, timeout
+                )))
+            }
+        }
+    }
+
+    /// Hand a `TaskResponse` or `FileTransferResponse` to whoever is waiting on its correlation id
+    async fn resolve_request(&self, message: &DeviceMessage) {
+        let Some(correlation_id) = message.payload["correlation_id"].as_str() else {
+            warn!("Task response from {} has no correlation id", message.source_device_id);
+            return;
+        };
+
+        let mut pending_requests = self.pending_requests.write().await;
+        match pending_requests.get(correlation_id) {
+            // Correlation ids travel in the clear, so only the device asked may answer
+            Some(pending) if pending.target_device_id != message.source_device_id => {
+                warn!(
+                    "Ignoring response to request {} from {}, which was sent to {}",
+                    correlation_id, message.source_device_id, pending.target_device_id
+                );
+            }
+            Some(_) => {
+                let pending = pending_requests.remove(correlation_id).expect("request is pending");
+                // The caller may have given up between the timeout and now
+                let _ = pending.responder.send(message.payload["result"].clone());
+            }
+            None => debug!("Ignoring response to unknown or expired request {}", correlation_id),
+        }
+    }
+
+    /// Gate screen capture on user consent and the screen capture data source
+    pub fn with_privacy_controls(mut self, privacy_controls: PrivacyControls) -> Self {
+        self.remote_desktop_manager = self
+            .remote_desktop_manager
+            .with_privacy_controls(privacy_controls, self.security_manager.clone());
+        self
+    }
+
+    /// Start remote desktop session on behalf of a user, streaming `display_id` or the primary display
     pub async fn start_remote_desktop(
         &self,
+        user_id: &str,
         target_device_id: &str,
         permissions: RemoteDesktopPermissions,
+        display_id: Option<u32>,
     ) -> MisaResult<String> {
+        if !self.security_manager.check_permission(user_id, "remote_desktop:start").await? {
+            return Err(MisaError::Permission(format!("{} may not start remote desktop sessions", user_id)));
+        }
+
         info!("Starting remote desktop session with device: {}", target_device_id);
 
         // Check if device supports remote desktop
@@ -481,20 +1894,204 @@ impl DeviceManager {
 
         drop(devices);
 
+        let session_id = uuid::Uuid::new_v4().to_string();
+        // The host decides how much of what we asked for it grants
+        let (remote_capabilities, permissions) = self
+            .exchange_remote_desktop_capabilities(target_device_id, &session_id, &permissions, display_id)
+            .await?;
+
         // Start remote desktop session
-        let session_id = self.remote_desktop_manager.start_session(
+        self.remote_desktop_manager.open_session(
+            session_id,
+            user_id,
             target_device_id,
+            &remote_capabilities,
             permissions,
-        ).await?;
+            display_id,
+        ).await
+    }
 
-        Ok(session_id)
+    /// Send mouse or keyboard input to the host of a session this device controls
+    pub async fn send_remote_input(&self, session_id: &str, input: InputEvent) -> MisaResult<()> {
+        let host_device_id = self
+            .remote_desktop_manager
+            .list_sessions()
+            .await
+            .into_iter()
+            .find(|session| session.session_id == session_id && session.client_device_id == self.device_id)
+            .map(|session| session.host_device_id)
+            .ok_or_else(|| MisaError::NotFound(format!("Remote desktop session not found: {}", session_id)))?;
+
+        let command = RemoteInputCommand { session_id: session_id.to_string(), input };
+        self.send_message(DeviceMessage {
+            message_id: uuid::Uuid::new_v4().to_string(),
+            source_device_id: self.device_id.clone(),
+            target_device_id: Some(host_device_id.clone()),
+            message_type: MessageType::ControlCommand,
+            payload: serde_json::to_value(&command)?,
+            timestamp: chrono::Utc::now(),
+            encrypted: self.security_manager.has_device_key(&host_device_id).await,
+            priority: MessagePriority::High,
+        })
+        .await
+    }
+
+    /// Offer our remote desktop capabilities to a host, naming the session, the display and the control
+    /// we ask for, and return the capabilities it answers with and the permissions it granted
+    async fn exchange_remote_desktop_capabilities(
+        &self,
+        device_id: &str,
+        session_id: &str,
+        permissions: &RemoteDesktopPermissions,
+        display_id: Option<u32>,
+    ) -> MisaResult<(RemoteDesktopCapabilities, RemoteDesktopPermissions)> {
+        let offer = serde_json::json!({
+            "capabilities": self.remote_desktop_manager.capabilities,
+            "session_id": session_id,
+            "permissions": permissions,
+            "display_id": display_id,
+        });
+        let result = self
+            .send_and_await(device_id, MessageType::RemoteDesktopRequest, offer, REMOTE_DESKTOP_HANDSHAKE_TIMEOUT)
+            .await?;
+
+        if let Some(error) = result["error"].as_str() {
+            return Err(MisaError::RemoteDesktop(format!("{} declined remote desktop: {}", device_id, error)));
+        }
+        let capabilities: RemoteDesktopCapabilities = serde_json::from_value(result["capabilities"].clone())
+            .map_err(|e| MisaError::RemoteDesktop(format!("Invalid capabilities from {}: {}", device_id, e)))?;
+        let granted: RemoteDesktopPermissions = serde_json::from_value(result["permissions"].clone())
+            .map_err(|e| MisaError::RemoteDesktop(format!("Invalid permissions from {}: {}", device_id, e)))?;
+
+        if let Some(device) = self.devices.write().await.get_mut(device_id) {
+            device.capabilities.remote_desktop = capabilities.clone();
+        }
+        Ok((capabilities, permissions.intersect(&granted)))
+    }
+
+    /// Stop a remote desktop session on behalf of a user
+    pub async fn stop_remote_desktop(&self, user_id: &str, session_id: &str) -> MisaResult<()> {
+        if !self.security_manager.check_permission(user_id, "remote_desktop:start").await? {
+            return Err(MisaError::Permission(format!("{} may not stop remote desktop sessions", user_id)));
+        }
+
+        let host_device_id = self
+            .remote_desktop_manager
+            .list_sessions()
+            .await
+            .into_iter()
+            .find(|session| session.session_id == session_id && session.client_device_id == self.device_id)
+            .map(|session| session.host_device_id);
+        self.remote_desktop_manager.stop_session(session_id).await?;
+
+        // Tell the host so it stops taking input for the session; it also ends once we disconnect
+        if let Some(host_device_id) = host_device_id {
+            let end = DeviceMessage {
+                message_id: uuid::Uuid::new_v4().to_string(),
+                source_device_id: self.device_id.clone(),
+                target_device_id: Some(host_device_id.clone()),
+                message_type: MessageType::ControlCommand,
+                payload: serde_json::json!({ "end_session": session_id }),
+                timestamp: chrono::Utc::now(),
+                encrypted: self.security_manager.has_device_key(&host_device_id).await,
+                priority: MessagePriority::High,
+            };
+            if let Err(e) = self.send_message(end).await {
+                warn!("Failed to tell {} that session {} ended: {}", host_device_id, session_id, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// List active remote desktop sessions
+    pub async fn list_remote_desktop_sessions(&self) -> Vec<RemoteDesktopSession> {
+        self.remote_desktop_manager.list_sessions().await
+    }
+
+    /// Change the permissions of a running remote desktop session on behalf of a user
+    pub async fn update_remote_desktop_permissions(
+        &self,
+        user_id: &str,
+        session_id: &str,
+        permissions: RemoteDesktopPermissions,
+    ) -> MisaResult<()> {
+        if !self.security_manager.check_permission(user_id, "remote_desktop:start").await? {
+            return Err(MisaError::Permission(format!("{} may not change remote desktop sessions", user_id)));
+        }
+
+        self.remote_desktop_manager.update_permissions(user_id, session_id, permissions).await
+    }
+
+    /// Subscribe to remote desktop session events
+    pub fn subscribe_remote_desktop_events(&self) -> broadcast::Receiver<RemoteDesktopEvent> {
+        self.remote_desktop_manager.subscribe_events()
+    }
+
+    /// Decide which incoming file transfers to grant
+    pub fn with_transfer_approver(mut self, approver: Arc<dyn TransferApprover>) -> Self {
+        self.transfer_approver = Some(approver);
+        self
+    }
+
+    /// Decide what control paired devices get over this one in remote desktop sessions
+    pub fn with_remote_desktop_approver(mut self, approver: Arc<dyn RemoteDesktopApprover>) -> Self {
+        self.remote_desktop_approver = Some(approver);
+        self
+    }
+
+    /// Run tasks delegated by other devices
+    pub fn with_task_handler(mut self, handler: Arc<dyn TaskHandler>) -> Self {
+        self.task_handler = Some(handler);
+        self
+    }
+
+    /// Replace how remote mouse and keyboard input is replayed
+    pub fn with_input_injector(mut self, injector: Arc<dyn InputInjector>) -> Self {
+        self.remote_desktop_manager = self.remote_desktop_manager.with_input_injector(injector);
+        self
+    }
+
+    /// Ask a device for permission to send it a file
+    pub async fn request_transfer_grant(&self, target_device_id: &str, file_path: &str) -> MisaResult<TransferGrant> {
+        self.validate_file(file_path)?;
+
+        let file_size = tokio::fs::metadata(file_path).await.map_err(|e| MisaError::Io(e))?.len();
+        let file_name = Path::new(file_path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or_else(|| MisaError::Validation(format!("Not a file: {}", file_path)))?;
+        let request = FileTransferRequest {
+            file_name,
+            file_size,
+            encrypted: self.config.file_transfer.encryption_required,
+            grant: None,
+        };
+
+        let result = self
+            .send_and_await(
+                target_device_id,
+                MessageType::FileTransferRequest,
+                serde_json::to_value(&request)?,
+                TRANSFER_GRANT_TIMEOUT,
+            )
+            .await?;
+        let response: FileTransferResponse = serde_json::from_value(result)?;
+
+        response.grant.ok_or_else(|| {
+            MisaError::Permission(format!(
+                "Device {} declined the file transfer: {}",
+                target_device_id,
+                response.reason.unwrap_or_else(|| "no reason given".to_string())
+            ))
+        })
     }
 
-    /// Transfer file to device
+    /// Transfer a file to a device that granted it with `request_transfer_grant`
     pub async fn transfer_file(
         &self,
         target_device_id: &str,
         file_path: &str,
+        grant: TransferGrant,
     ) -> MisaResult<String> {
         info!("Starting file transfer to device: {} - file: {}", target_device_id, file_path);
 
@@ -505,28 +2102,651 @@ impl DeviceManager {
         let transfer_id = self.remote_desktop_manager.file_transfer_manager.start_transfer(
             target_device_id,
             file_path,
+            grant,
         ).await?;
 
         Ok(transfer_id)
     }
 
+    /// Get progress of an outgoing file transfer
+    pub async fn get_transfer_progress(&self, transfer_id: &str) -> MisaResult<Option<FileTransfer>> {
+        self.remote_desktop_manager.file_transfer_manager.get_transfer_progress(transfer_id).await
+    }
+
+    /// Pause an outgoing file transfer
+    pub async fn pause_transfer(&self, transfer_id: &str) -> MisaResult<()> {
+        self.remote_desktop_manager.file_transfer_manager.pause_transfer(transfer_id).await
+    }
+
+    /// Resume a paused outgoing file transfer
+    pub async fn resume_transfer(&self, transfer_id: &str) -> MisaResult<()> {
+        self.remote_desktop_manager.file_transfer_manager.resume_transfer(transfer_id).await
+    }
+
+    /// Register an established connection to a device
+    pub async fn register_connection(&self, connection: DeviceConnection) {
+        let device_id = connection.device_id.clone();
+        let mut connections = self.active_connections.write().await;
+        connections.insert(device_id.clone(), connection);
+        drop(connections);
+
+        self.metrics.inc(metrics::CONNECTION_EVENTS_TOTAL, &[("event", "opened")]).await;
+        // Having no subscribers right now is not an error
+        let _ = self.connection_events.send(DeviceConnectionEvent::DeviceConnected { device_id });
+    }
+
+    /// Drop a connection whose transport closed, marking the device offline.
+    /// Paired devices are reconnected to in the background.
+    pub async fn connection_lost(&self, device_id: &str) {
+        if self.active_connections.write().await.remove(device_id).is_none() {
+            return;
+        }
+        self.inbound_limits.write().await.remove(device_id);
+        self.remote_desktop_manager.end_hosted_sessions(device_id).await;
+        if let Some(device) = self.devices.write().await.get_mut(device_id) {
+            device.status = DeviceStatus::Offline;
+        }
+
+        warn!("Lost connection to device {}", device_id);
+        self.metrics.inc(metrics::CONNECTION_EVENTS_TOTAL, &[("event", "lost")]).await;
+        let _ = self.connection_events.send(DeviceConnectionEvent::DeviceDisconnected {
+            device_id: device_id.to_string(),
+        });
+        self.supervise_reconnect(device_id).await;
+    }
+
+    /// Subscribe to devices connecting, disconnecting and failing to reconnect
+    pub fn subscribe_connection_events(&self) -> broadcast::Receiver<DeviceConnectionEvent> {
+        self.connection_events.subscribe()
+    }
+
+    /// Open reconnections through `connector` instead of dialing the device's WebSocket service
+    pub fn with_connector(mut self, connector: Arc<dyn DeviceConnector>) -> Self {
+        self.connector = connector;
+        self
+    }
+
+    /// Retry connecting to a paired device with jittered exponential backoff,
+    /// giving up after the configured number of attempts
+    async fn supervise_reconnect(&self, device_id: &str) {
+        let max_attempts = self.config.reconnect_max_attempts;
+        if max_attempts == 0 || !self.security_manager.has_device_key(device_id).await {
+            return;
+        }
+        if !self.reconnecting.write().await.insert(device_id.to_string()) {
+            return;
+        }
+
+        let manager = self.clone();
+        let device_id = device_id.to_string();
+        tokio::spawn(async move {
+            let base_delay = Duration::from_millis(manager.config.reconnect_base_delay_ms);
+            let max_delay = Duration::from_millis(manager.config.reconnect_max_delay_ms);
+
+            let mut attempts = 0;
+            let reconnected = loop {
+                if attempts >= max_attempts {
+                    break false;
+                }
+                tokio::time::sleep(Self::jittered(Scheduler::backoff_delay(base_delay, attempts, max_delay))).await;
+                attempts += 1;
+
+                // The device may have connected to us, or been forgotten, while we waited
+                if manager.active_connections.read().await.contains_key(&device_id) {
+                    break true;
+                }
+                let Some(device) = manager.devices.read().await.get(&device_id).cloned() else {
+                    break false;
+                };
+
+                match manager.connector.connect(&manager, &device).await {
+                    Ok(connection) => {
+                        if let Some(device) = manager.devices.write().await.get_mut(&device_id) {
+                            device.status = DeviceStatus::Online;
+                        }
+                        info!("Reconnected to device {} after {} attempts", device_id, attempts);
+                        manager.register_connection(connection).await;
+                        break true;
+                    }
+                    Err(e) => warn!("Reconnect attempt {} to device {} failed: {}", attempts, device_id, e),
+                }
+            };
+
+            manager.reconnecting.write().await.remove(&device_id);
+            if !reconnected {
+                warn!("Giving up on reconnecting to device {} after {} attempts", device_id, attempts);
+                let _ = manager
+                    .connection_events
+                    .send(DeviceConnectionEvent::ReconnectFailed { device_id, attempts });
+            }
+        });
+    }
+
+    /// Add up to half of `delay` at random so devices dropped together don't retry in lockstep
+    fn jittered(delay: Duration) -> Duration {
+        use rand::Rng;
+
+        let spread = delay.as_millis() as u64 / 2;
+        delay + Duration::from_millis(rand::thread_rng().gen_range(0..=spread))
+    }
+
+    /// Dial the device service at `addr` and authenticate the channel as `device_id`'s
+    async fn dial_device_channel(&self, addr: &str, device_id: &str) -> MisaResult<DeviceConnection> {
+        let dial = async {
+            let stream = tokio::net::TcpStream::connect(addr).await?;
+            let (websocket, _) = tokio_tungstenite::client_async(format!("ws://{}{}", addr, DEVICE_CHANNEL_PATH), stream)
+                .await
+                .map_err(|e| MisaError::Device(format!("WebSocket handshake with {} failed: {}", addr, e)))?;
+            let (mut sink, mut stream) = websocket_frames(websocket);
+
+            self.dial_handshake(device_id, &mut sink, &mut stream).await?;
+            Ok(self.spawn_device_channel(device_id.to_string(), sink, stream))
+        };
+
+        tokio::time::timeout(RECONNECT_DIAL_TIMEOUT, dial)
+            .await
+            .map_err(|_| MisaError::Timeout(format!("Connecting to device {} at {}", device_id, addr)))?
+    }
+
+    /// Answer the accepting end's challenge as this device and check it proves to be `device_id`
+    async fn dial_handshake(&self, device_id: &str, sink: &mut DeviceFrameSink, stream: &mut DeviceFrameStream) -> MisaResult<()> {
+        let challenge = stream
+            .next()
+            .await
+            .ok_or_else(|| MisaError::Device("Device channel closed before its challenge".to_string()))??;
+        let challenge: DeviceChallenge = serde_json::from_str(&challenge)?;
+        let nonce = Self::hello_nonce();
+        sink.send(self.device_hello(device_id, &challenge.nonce, Some(&nonce)).await?).await?;
+        let (peer_id, _) = self.verify_device_hello(stream.next().await, &nonce).await?;
+        if peer_id != device_id {
+            return Err(MisaError::Permission(format!("Device {} answered as {}", device_id, peer_id)));
+        }
+        Ok(())
+    }
+
+    /// Take a device channel a peer opened to this device's service, once the peer
+    /// proves it holds the key paired with the device it claims to be.
+    ///
+    /// The peer's proof must answer a nonce fresh for this channel, so a hello captured
+    /// from an earlier handshake can't open another.
+    pub async fn accept_device_channel(&self, mut sink: DeviceFrameSink, mut stream: DeviceFrameStream) -> MisaResult<String> {
+        let peer_id = self.accept_handshake(&mut sink, &mut stream).await?;
+
+        self.mark_online(&peer_id).await;
+        info!("Device {} opened a channel to this device", peer_id);
+        let connection = self.spawn_device_channel(peer_id.clone(), sink, stream);
+        self.register_connection(connection).await;
+        Ok(peer_id)
+    }
+
+    /// Challenge the dialing end, check its hello and answer its challenge in turn,
+    /// returning the device the dialer proved to be
+    async fn accept_handshake(&self, sink: &mut DeviceFrameSink, stream: &mut DeviceFrameStream) -> MisaResult<String> {
+        let nonce = Self::hello_nonce();
+        sink.send(serde_json::to_string(&DeviceChallenge { nonce: nonce.clone() })?).await?;
+
+        let hello = tokio::time::timeout(DEVICE_HELLO_TIMEOUT, stream.next())
+            .await
+            .map_err(|_| MisaError::Timeout("Waiting for a device channel hello".to_string()))?;
+        let (peer_id, challenge) = self.verify_device_hello(hello, &nonce).await?;
+        let challenge = challenge
+            .ok_or_else(|| MisaError::Permission(format!("Device {} sent a hello without a challenge", peer_id)))?;
+        sink.send(self.device_hello(&peer_id, &challenge, None).await?).await?;
+        Ok(peer_id)
+    }
+
+    async fn mark_online(&self, device_id: &str) {
+        if let Some(device) = self.devices.write().await.get_mut(device_id) {
+            device.status = DeviceStatus::Online;
+        }
+    }
+
+    /// Fresh random nonce for one device channel handshake
+    fn hello_nonce() -> String {
+        use rand::RngCore;
+
+        let mut nonce = [0u8; DEVICE_HELLO_NONCE_BYTES];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        encode_hex(&nonce)
+    }
+
+    /// Hello introducing this device to `peer_id`, answering the peer's `nonce` and sealed
+    /// with the key the two share; `challenge` is the nonce the peer must answer in turn
+    async fn device_hello(&self, peer_id: &str, nonce: &str, challenge: Option<&str>) -> MisaResult<String> {
+        let proof = serde_json::to_vec(&HelloProof {
+            from: self.device_id.clone(),
+            to: peer_id.to_string(),
+            nonce: nonce.to_string(),
+        })?;
+        let hello = DeviceHello {
+            device_id: self.device_id.clone(),
+            challenge: challenge.map(str::to_string),
+            proof: self.security_manager.encrypt_for_device(peer_id, &proof).await?,
+        };
+        Ok(serde_json::to_string(&hello)?)
+    }
+
+    /// Check the hello a channel opened with against the nonce we challenged the peer with,
+    /// returning the device it proves the peer to be and the nonce it challenges us with
+    async fn verify_device_hello(
+        &self,
+        frame: Option<MisaResult<String>>,
+        nonce: &str,
+    ) -> MisaResult<(String, Option<String>)> {
+        let frame = frame.ok_or_else(|| MisaError::Device("Device channel closed before its hello".to_string()))??;
+        let hello: DeviceHello = serde_json::from_str(&frame)?;
+
+        // Only the holder of the key paired with the claimed device can seal the proof
+        let proof = self.security_manager.decrypt_from_device(&hello.device_id, &hello.proof).await?;
+        let proof: HelloProof = serde_json::from_slice(&proof)?;
+        if proof.from != hello.device_id || proof.to != self.device_id || proof.nonce != nonce {
+            return Err(MisaError::Permission(format!("Device {} sent an invalid hello", hello.device_id)));
+        }
+        Ok((hello.device_id, hello.challenge))
+    }
+
+    /// Carry messages over an authenticated device channel, every frame sealed for the peer.
+    ///
+    /// Frames read go through `handle_incoming_message`, the same path as every other
+    /// transport, and the connection is reported lost once the transport closes.
+    fn spawn_device_channel(&self, peer_id: String, mut sink: DeviceFrameSink, mut stream: DeviceFrameStream) -> DeviceConnection {
+        let (outbound, mut outgoing) = mpsc::unbounded_channel::<DeviceMessage>();
+        let channel = outbound.downgrade();
+        let manager = self.clone();
+        let channel_peer = peer_id.clone();
+
+        tokio::spawn(async move {
+            let peer_id = channel_peer;
+            loop {
+                tokio::select! {
+                    message = outgoing.recv() => {
+                        // Every sender is gone once the connection was dropped or replaced
+                        let Some(message) = message else {
+                            let _ = sink.close().await;
+                            return;
+                        };
+                        if let Err(e) = manager.write_device_frame(&peer_id, &mut sink, &message).await {
+                            warn!("Device channel to {} failed: {}", peer_id, e);
+                            break;
+                        }
+                    }
+                    frame = stream.next() => match frame {
+                        Some(Ok(frame)) => {
+                            let handled = match manager.read_device_frame(&peer_id, &frame).await {
+                                Ok(message) => manager.handle_incoming_message(&peer_id, message).await,
+                                Err(e) => Err(e),
+                            };
+                            if let Err(e) = handled {
+                                warn!("Failed to handle message from {}: {}", peer_id, e);
+                            }
+                        }
+                        Some(Err(e)) => {
+                            warn!("Device channel to {} failed: {}", peer_id, e);
+                            break;
+                        }
+                        None => break,
+                    },
+                }
+            }
+
+            // A channel that was already replaced must not take its successor down with it
+            let still_current = match channel.upgrade() {
+                Some(outbound) => manager
+                    .active_connections
+                    .read()
+                    .await
+                    .get(&peer_id)
+                    .and_then(|connection| connection.websocket.as_ref())
+                    .map_or(false, |current| current.same_channel(&outbound)),
+                None => false,
+            };
+            if still_current {
+                manager.connection_lost(&peer_id).await;
+            }
+        });
+
+        DeviceConnection {
+            device_id: peer_id,
+            connection_type: ConnectionProtocol::WebSocket,
+            websocket: Some(outbound),
+            webrtc_connection: None,
+            last_heartbeat: chrono::Utc::now(),
+            // Every frame is sealed with the key paired with the peer
+            encrypted_channel: true,
+            local_channel: None,
+        }
+    }
+
+    /// Seal a whole message for `peer_id` and write it to the channel
+    async fn write_device_frame(&self, peer_id: &str, sink: &mut DeviceFrameSink, message: &DeviceMessage) -> MisaResult<()> {
+        let sealed = self
+            .security_manager
+            .encrypt_for_device(peer_id, &serde_json::to_vec(message)?)
+            .await?;
+        sink.send(serde_json::to_string(&sealed)?).await
+    }
+
+    /// Open a frame `peer_id` sealed back into the message it carries
+    async fn read_device_frame(&self, peer_id: &str, frame: &str) -> MisaResult<DeviceMessage> {
+        let sealed: EncryptedData = serde_json::from_str(frame)?;
+        let plaintext = self.security_manager.decrypt_from_device(peer_id, &sealed).await?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    /// Register a WebRTC peer whose data channel this device opened, once the peer
+    /// answers the device channel handshake as `device_id`
+    ///
+    /// Frames arriving on the channel go through `handle_incoming_message`,
+    /// the same path as every other transport.
+    pub async fn register_webrtc_connection(&self, device_id: &str, connection: WebRTCConnection) -> MisaResult<()> {
+        let (mut sink, mut stream) = data_channel_frames(&connection.data_channel);
+        tokio::time::timeout(DEVICE_HELLO_TIMEOUT, self.dial_handshake(device_id, &mut sink, &mut stream))
+            .await
+            .map_err(|_| MisaError::Timeout(format!("Authenticating the WebRTC channel to {}", device_id)))??;
+
+        self.serve_webrtc_connection(device_id.to_string(), connection, stream).await;
+        Ok(())
+    }
+
+    /// Take a WebRTC data channel a peer opened to this device, once the peer proves
+    /// it holds the key paired with the device the connection was negotiated with
+    pub async fn accept_webrtc_connection(&self, connection: WebRTCConnection) -> MisaResult<String> {
+        let (mut sink, mut stream) = data_channel_frames(&connection.data_channel);
+        let peer_id = self.accept_handshake(&mut sink, &mut stream).await?;
+        if peer_id != connection.peer_id {
+            return Err(MisaError::Permission(format!(
+                "WebRTC peer {} answered as {}",
+                connection.peer_id, peer_id
+            )));
+        }
+
+        self.mark_online(&peer_id).await;
+        info!("Device {} opened a WebRTC channel to this device", peer_id);
+        self.serve_webrtc_connection(peer_id.clone(), connection, stream).await;
+        Ok(peer_id)
+    }
+
+    /// Hand frames from an authenticated data channel to `handle_incoming_message` and register it
+    async fn serve_webrtc_connection(&self, peer_id: String, connection: WebRTCConnection, mut stream: DeviceFrameStream) {
+        let manager = self.clone();
+        let channel_peer = peer_id.clone();
+        tokio::spawn(async move {
+            let peer_id = channel_peer;
+            while let Some(frame) = stream.next().await {
+                let handled = match frame.and_then(|frame| Ok(serde_json::from_str::<DeviceMessage>(&frame)?)) {
+                    Ok(message) => manager.handle_incoming_message(&peer_id, message).await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = handled {
+                    warn!("Failed to handle WebRTC message from {}: {}", peer_id, e);
+                }
+            }
+        });
+
+        self.register_connection(DeviceConnection {
+            device_id: peer_id,
+            connection_type: ConnectionProtocol::WebRTC,
+            websocket: None,
+            webrtc_connection: Some(connection),
+            last_heartbeat: chrono::Utc::now(),
+            // Data channels always run over DTLS, and the peer proved its paired key on this one
+            encrypted_channel: true,
+            local_channel: None,
+        })
+        .await;
+    }
+
+    /// Count connection churn in a shared metrics registry
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Load device groups from `data_dir` and persist later changes there
+    pub async fn with_groups_store(mut self, data_dir: &str) -> MisaResult<Self> {
+        let groups_path = Path::new(data_dir).join(DEVICE_GROUPS_FILE);
+        let groups: HashMap<String, DeviceGroup> = read_json_map(&groups_path).await?;
+        debug!("Loaded {} device groups from {}", groups.len(), groups_path.display());
+
+        self.groups = Arc::new(RwLock::new(groups));
+        self.groups_path = Some(groups_path);
+        Ok(self)
+    }
+
+    /// Id this device is known by to its peers
+    pub fn device_id(&self) -> &str {
+        &self.device_id
+    }
+
+    /// Number of devices with a live connection
+    pub async fn active_connection_count(&self) -> usize {
+        self.active_connections.read().await.len()
+    }
+
+    /// Handle a message received from another device over the connection to `device_id`
+    pub async fn handle_incoming_message(&self, device_id: &str, mut message: DeviceMessage) -> MisaResult<()> {
+        // Throttle before decrypting so a flood costs as little as possible
+        self.admit_inbound(device_id, &message).await?;
+
+        // The sender is whoever is on the other end of the connection, not whoever the message names
+        if message.source_device_id != device_id {
+            debug!(
+                "Message {} over the connection to {} claimed to be from {}",
+                message.message_id, device_id, message.source_device_id
+            );
+            message.source_device_id = device_id.to_string();
+        }
+
+        // Undecryptable messages are dropped before anything acts on them
+        let message = self.open_message(device_id, message).await?;
+
+        // Any traffic proves the peer is still alive
+        if let Some(connection) = self.active_connections.write().await.get_mut(&message.source_device_id) {
+            connection.last_heartbeat = chrono::Utc::now();
+        }
+
+        match message.message_type {
+            MessageType::Heartbeat => {
+                debug!("Heartbeat from {}", message.source_device_id);
+            }
+            MessageType::ClipboardSync => {
+                self.clipboard_sync.handle_sync_message(&message).await?;
+            }
+            MessageType::TaskRequest => {
+                let Ok(permit) = Arc::clone(&self.delegated_tasks).try_acquire_owned() else {
+                    debug!("Declining task from {}, all task slots are busy", message.source_device_id);
+                    let busy = serde_json::json!({ "error": "Device is busy, try again later" });
+                    return self.send_message(message.response(&self.device_id, busy)).await;
+                };
+
+                // Tasks can run for a long time, so answer without holding up this transport
+                let manager = self.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = manager.answer_task_request(&message).await {
+                        warn!("Failed to answer task request from {}: {}", message.source_device_id, e);
+                    }
+                    drop(permit);
+                });
+            }
+            MessageType::TaskResponse | MessageType::FileTransferResponse | MessageType::PairingResponse => {
+                self.resolve_request(&message).await;
+            }
+            MessageType::PairingRequest => {
+                self.answer_key_exchange(device_id, &message).await?;
+            }
+            MessageType::RemoteDesktopRequest => {
+                self.answer_remote_desktop_handshake(device_id, &message).await?;
+            }
+            MessageType::FileTransferRequest if message.payload["grant"].is_null() => {
+                self.answer_transfer_request(&message).await?;
+            }
+            MessageType::FileTransferRequest | MessageType::FileTransferData => {
+                if let Some(path) = self.remote_desktop_manager.file_transfer_manager.handle_incoming(&message).await? {
+                    info!("Received file from {}: {}", message.source_device_id, path.display());
+                }
+            }
+            MessageType::ControlCommand if !message.payload["input"].is_null() => {
+                let command: RemoteInputCommand = serde_json::from_value(message.payload.clone())
+                    .map_err(|e| MisaError::Device(format!("Invalid input command: {}", e)))?;
+                // Only the connection the input arrived on identifies its sender
+                self.remote_desktop_manager.inject_input(device_id, &command).await?;
+            }
+            MessageType::ControlCommand if message.payload["end_session"].is_string() => {
+                let session_id = message.payload["end_session"].as_str().unwrap_or_default();
+                self.remote_desktop_manager.end_hosted_session(session_id, device_id).await?;
+            }
+            _ => {
+                debug!("Unhandled message from {}: {:?}", message.source_device_id, message.message_type);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Send a heartbeat to every connected device
+    pub async fn send_heartbeats(&self) -> MisaResult<()> {
+        let heartbeat = DeviceMessage {
+            message_id: uuid::Uuid::new_v4().to_string(),
+            source_device_id: self.device_id.clone(),
+            target_device_id: None,
+            message_type: MessageType::Heartbeat,
+            payload: serde_json::Value::Null,
+            timestamp: chrono::Utc::now(),
+            encrypted: false,
+            priority: MessagePriority::Low,
+        };
+
+        self.broadcast_message(&heartbeat).await
+    }
+
+    /// Drop connections that have been silent for longer than `idle_timeout`,
+    /// marking their devices offline. Returns the evicted device ids.
+    pub async fn evict_stale_connections(&self, idle_timeout: Duration) -> Vec<String> {
+        let cutoff = chrono::Utc::now() - chrono::Duration::seconds(idle_timeout.as_secs() as i64);
+
+        let evicted: Vec<String> = {
+            let mut connections = self.active_connections.write().await;
+            let stale: Vec<String> = connections
+                .iter()
+                .filter(|(_, connection)| connection.last_heartbeat < cutoff)
+                .map(|(device_id, _)| device_id.clone())
+                .collect();
+            for device_id in &stale {
+                connections.remove(device_id);
+            }
+            stale
+        };
+
+        if !evicted.is_empty() {
+            let mut limits = self.inbound_limits.write().await;
+            for device_id in &evicted {
+                limits.remove(device_id);
+            }
+            drop(limits);
+
+            let mut devices = self.devices.write().await;
+            for device_id in &evicted {
+                warn!("Evicting stale connection to device {}", device_id);
+                self.metrics.inc(metrics::CONNECTION_EVENTS_TOTAL, &[("event", "evicted")]).await;
+                if let Some(device) = devices.get_mut(device_id) {
+                    device.status = DeviceStatus::Offline;
+                }
+            }
+            drop(devices);
+
+            for device_id in &evicted {
+                let _ = self.connection_events.send(DeviceConnectionEvent::DeviceDisconnected {
+                    device_id: device_id.clone(),
+                });
+                self.supervise_reconnect(device_id).await;
+            }
+        }
+
+        evicted
+    }
+
+    /// Periodically heartbeat connected devices and evict the ones that stopped answering,
+    /// publishing an event for each eviction
+    pub fn start_connection_heartbeat<E, F>(
+        &self,
+        events: broadcast::Sender<E>,
+        to_event: F,
+    ) -> tokio::task::JoinHandle<()>
+    where
+        E: Send + 'static,
+        F: Fn(&str) -> E + Send + 'static,
+    {
+        // Having no subscribers right now is not an error
+        self.spawn_heartbeat(move |device_id| {
+            let _ = events.send(to_event(device_id));
+        })
+    }
+
+    /// Start heartbeating connected devices; evictions are published as
+    /// `DeviceDisconnected` connection events. Does nothing if already running.
+    pub async fn start_heartbeats(&self) {
+        let mut task = self.heartbeat_task.write().await;
+        if task.is_none() {
+            *task = Some(self.spawn_heartbeat(|_| {}));
+        }
+    }
+
+    /// Start sharing clipboard changes with connected devices, if enabled in the
+    /// config. Does nothing if already running.
+    pub async fn start_clipboard_sync(&self) {
+        let mut task = self.clipboard_task.write().await;
+        if task.is_none() {
+            *task = self.clipboard_sync.start_sync(Arc::new(self.clone()));
+        }
+    }
+
+    fn spawn_heartbeat<F>(&self, on_evicted: F) -> tokio::task::JoinHandle<()>
+    where
+        F: Fn(&str) + Send + 'static,
+    {
+        let manager = self.clone();
+        let interval = Duration::from_secs(self.config.heartbeat_interval_secs.max(1));
+        let idle_timeout = Duration::from_secs(self.config.connection_idle_timeout_secs);
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+
+                for device_id in manager.evict_stale_connections(idle_timeout).await {
+                    on_evicted(&device_id);
+                }
+
+                if let Err(e) = manager.send_heartbeats().await {
+                    warn!("Failed to send heartbeats: {}", e);
+                }
+            }
+        })
+    }
+
     /// Select optimal device for task
-    pub async fn select_device(&self, preferences: &[String]) -> MisaResult<Option<String>> {
+    pub async fn select_device(&self, preferences: &[String], profile: TaskProfile) -> MisaResult<Option<String>> {
+        Ok(self.select_device_scored(preferences, profile).await?.device_id)
+    }
+
+    /// Select optimal device for task, keeping the scores behind the choice
+    pub async fn select_device_scored(&self, preferences: &[String], profile: TaskProfile) -> MisaResult<DeviceSelection> {
         let devices = self.devices.read().await;
 
         if preferences.is_empty() {
             // Select best available device
-            self.select_best_device(&devices).await
+            self.select_best_device(&devices, profile).await
         } else {
             // Check preferred devices in order
-            for preference in preferences {
-                if let Some(device) = devices.get(preference) {
-                    if matches!(device.status, DeviceStatus::Online) {
-                        return Ok(Some(preference.clone()));
-                    }
-                }
-            }
-            Ok(None)
+            let device_id = preferences
+                .iter()
+                .find(|preference| {
+                    devices
+                        .get(preference.as_str())
+                        .is_some_and(|device| matches!(device.status, DeviceStatus::Online))
+                })
+                .cloned();
+            Ok(DeviceSelection { device_id, candidates: Vec::new() })
         }
     }
 
@@ -549,6 +2769,13 @@ impl DeviceManager {
         // Stop discovery service
         self.discovery_service.stop().await?;
 
+        if let Some(task) = self.heartbeat_task.write().await.take() {
+            task.abort();
+        }
+        if let Some(task) = self.clipboard_task.write().await.take() {
+            task.abort();
+        }
+
         // Close all connections
         self.close_all_connections().await?;
 
@@ -562,46 +2789,131 @@ impl DeviceManager {
     /// Private helper methods
 
     fn parse_qr_token(&self, qr_token: &str) -> MisaResult<PairingData> {
-        // Parse QR token format: "misa://pair/{device_id}/{timestamp}/{signature}"
+        // Parse QR token format: "misa://pair/{device_id}/{timestamp}/{host_id}/{host_public_key}/{signature}"
         if !qr_token.starts_with("misa://pair/") {
             return Err(MisaError::Device("Invalid QR token format".to_string()));
         }
 
         let parts: Vec<&str> = qr_token.trim_start_matches("misa://pair/").split('/').collect();
-        if parts.len() != 3 {
+        if parts.len() != 5 {
             return Err(MisaError::Device("Invalid QR token format".to_string()));
         }
 
+        for device_id in [parts[0], parts[2]] {
+            if device_id.is_empty() || device_id.contains('|') {
+                return Err(MisaError::Device("Invalid device id".to_string()));
+            }
+        }
+
         Ok(PairingData {
             device_id: parts[0].to_string(),
             timestamp: parts[1].parse().map_err(|_| MisaError::Device("Invalid timestamp".to_string()))?,
-            signature: parts[2].to_string(),
+            host_id: parts[2].to_string(),
+            host_public_key: decode_hex(parts[3]).ok_or_else(|| MisaError::Device("Invalid public key".to_string()))?,
+            signature: decode_hex(parts[4]).ok_or_else(|| MisaError::Device("Invalid signature".to_string()))?,
         })
     }
 
-    async fn initiate_pairing(
-        &self,
-        pairing_data: PairingData,
-        session: DiscoverySession,
-    ) -> MisaResult<PairingResult> {
-        // Validate timestamp (prevent replay attacks)
+    /// Sign a token for `device_id`, replacing any key exchange an earlier token for it started
+    async fn sign_pairing_token(&self, device_id: &str, timestamp: i64) -> MisaResult<String> {
+        let exchange = self.security_manager.start_device_key_exchange()?;
+        let public_key = encode_hex(exchange.public_key());
+        let payload = Self::pairing_payload(device_id, timestamp, &self.device_id, &public_key);
+        let signature = self.security_manager.sign_data(payload.as_bytes(), PAIRING_TOKEN_PURPOSE).await?;
+        self.pending_pairings.write().await.insert(device_id.to_string(), exchange);
+
+        Ok(format!(
+            "misa://pair/{}/{}/{}/{}/{}",
+            device_id,
+            timestamp,
+            self.device_id,
+            public_key,
+            encode_hex(&signature)
+        ))
+    }
+
+    fn pairing_payload(device_id: &str, timestamp: i64, host_id: &str, host_public_key: &str) -> String {
+        format!("{}|{}|{}|{}", device_id, timestamp, host_id, host_public_key)
+    }
+
+    /// Reject tokens issued too long ago or in the future (prevents replay attacks)
+    fn check_pairing_timestamp(timestamp: i64) -> MisaResult<()> {
         let now = chrono::Utc::now();
-        let pair_time = chrono::DateTime::from_timestamp(pairing_data.timestamp, 0)
+        let pair_time = chrono::DateTime::from_timestamp(timestamp, 0)
             .ok_or_else(|| MisaError::Device("Invalid timestamp".to_string()))?;
 
-        if now.signed_duration_since(pair_time).num_minutes() > 5 {
+        if now.signed_duration_since(pair_time).num_minutes() >= PAIRING_TOKEN_TTL_MINUTES {
             return Err(MisaError::Device("QR token expired".to_string()));
         }
 
-        // Verify signature (in real implementation, use proper cryptographic verification)
-        if pairing_data.signature.is_empty() {
+        if pair_time.signed_duration_since(now).num_minutes() >= 1 {
+            return Err(MisaError::Device("QR token timestamp is in the future".to_string()));
+        }
+        Ok(())
+    }
+
+    async fn initiate_pairing(
+        &self,
+        pairing_data: PairingData,
+        session: DiscoverySession,
+        peer_public_key: &[u8],
+    ) -> MisaResult<PairingResult> {
+        Self::check_pairing_timestamp(pairing_data.timestamp)?;
+
+        // Verify the HMAC over the token's fields in constant time; only tokens we issued pass
+        let payload = Self::pairing_payload(
+            &pairing_data.device_id,
+            pairing_data.timestamp,
+            &pairing_data.host_id,
+            &encode_hex(&pairing_data.host_public_key),
+        );
+        let valid = pairing_data.host_id == self.device_id
+            && self
+                .security_manager
+                .verify_signature(payload.as_bytes(), &pairing_data.signature, PAIRING_TOKEN_PURPOSE)
+                .await?;
+
+        if !valid {
+            warn!("Rejected QR token with invalid signature for device {}", pairing_data.device_id);
             return Err(MisaError::Device("Invalid signature".to_string()));
         }
 
+        // The key exchange is the one this token carried, not one a newer token started
+        let exchange = {
+            let mut pending = self.pending_pairings.write().await;
+            match pending.get(&pairing_data.device_id) {
+                Some(exchange) if exchange.public_key() == pairing_data.host_public_key.as_slice() => {
+                    pending.remove(&pairing_data.device_id)
+                }
+                _ => None,
+            }
+        };
+        let exchange = exchange.ok_or_else(|| {
+            MisaError::Device(format!("Pairing token for device {} was already used or replaced", pairing_data.device_id))
+        })?;
+        self.security_manager
+            .complete_device_key_exchange(&pairing_data.device_id, exchange, peer_public_key)
+            .await?;
+        debug!("Pairing session {} agreed a key with device {}", session.session_id, session.device_id);
+
         // Add device to registry
-        let device_info = DeviceInfo {
-            device_id: pairing_data.device_id.clone(),
-            name: format!("Device-{}", &pairing_data.device_id[..8]),
+        self.devices
+            .write()
+            .await
+            .insert(pairing_data.device_id.clone(), Self::paired_device_info(&pairing_data.device_id));
+
+        Ok(PairingResult {
+            success: true,
+            device_id: pairing_data.device_id,
+            message: "Device paired successfully".to_string(),
+        })
+    }
+
+    /// Registry entry for a device just paired, until discovery tells us more about it
+    fn paired_device_info(device_id: &str) -> DeviceInfo {
+        DeviceInfo {
+            device_id: device_id.to_string(),
+            name: format!("Device-{}", device_id.chars().take(8).collect::<String>()),
             device_type: DeviceType::Phone, // Default, would be detected
             capabilities: DeviceCapabilities::default(),
             status: DeviceStatus::Online,
@@ -609,110 +2921,222 @@ impl DeviceManager {
             battery_level: None,
             cpu_usage: None,
             memory_usage: None,
-            network_info: NetworkInfo::default(),
+            network_info: NetworkInfo {
+                ip_address: "127.0.0.1".to_string(),
+                mac_address: None,
+                connection_type: ConnectionType::Ethernet,
+                signal_strength: None,
+                bandwidth_mbps: None,
+            },
             location: None,
-        };
+        }
+    }
 
-        let mut devices = self.devices.write().await;
-        devices.insert(pairing_data.device_id.clone(), device_info);
+    async fn broadcast_message(&self, message: &DeviceMessage) -> MisaResult<()> {
+        let device_ids: Vec<String> = self.active_connections.read().await.keys().cloned().collect();
 
-        Ok(PairingResult {
-            success: true,
-            device_id: pairing_data.device_id,
-            message: "Device paired successfully".to_string(),
-        })
-    }
+        // Retries for one device must not hold up the others
+        let deliveries = device_ids.into_iter().map(|device_id| async move {
+            // Each device gets its own ciphertext; devices without a key get nothing
+            let outgoing = match self.seal_message(&device_id, message).await {
+                Ok(outgoing) => outgoing,
+                Err(e) => {
+                    warn!("Not sending message to device {}: {}", device_id, e);
+                    return;
+                }
+            };
 
-    async fn send_message_via_connection(
-        &self,
-        connection: &DeviceConnection,
-        message: &DeviceMessage,
-    ) -> MisaResult<()> {
-        let message_data = serde_json::to_vec(message)?;
+            if let Err(e) = self.deliver(&device_id, &outgoing).await {
+                warn!("Failed to send message to device {}: {}", device_id, e);
+            }
+        });
+        futures_util::future::join_all(deliveries).await;
 
-        match connection.connection_type {
-            ConnectionProtocol::WebSocket => {
-                // Send via WebSocket
-                if let Some(ws) = &connection.websocket {
-                    // Convert message to WebSocket text message
-                    let ws_message = Message::Text(serde_json::to_string(message)?);
+        Ok(())
+    }
 
-                    // In a real implementation, we would send through the WebSocket
-                    // For now, we'll simulate the send operation
-                    debug!("Sending message via WebSocket to {}: {:?}", connection.device_id, message.message_type);
+    /// Send an already sealed message, retrying with exponential backoff.
+    /// The connection is looked up on every attempt so a device that reconnects
+    /// during backoff still gets the message; one that never does is dead-lettered.
+    async fn deliver(&self, device_id: &str, message: &DeviceMessage) -> MisaResult<()> {
+        // A missed heartbeat is superseded by the next one
+        let retries = match message.message_type {
+            MessageType::Heartbeat => 0,
+            _ => self.config.message_retry_attempts,
+        };
+        let base_delay = Duration::from_millis(self.config.message_retry_base_delay_ms);
+
+        let mut failures = 0;
+        loop {
+            let connection = self.active_connections.read().await.get(device_id).cloned();
+            let result = match connection {
+                Some(connection) => connection.send(message).await,
+                None => Err(MisaError::Device(format!("No connection to device: {}", device_id))),
+            };
 
-                    // TODO: Actual WebSocket send implementation
-                    // ws.send(ws_message).await.map_err(|e| MisaError::Device(format!("WebSocket send failed: {}", e)))?;
-                } else {
-                    return Err(MisaError::Device(format!("No WebSocket connection to device: {}", connection.device_id)));
+            let error = match result {
+                Ok(()) => return Ok(()),
+                Err(e) => e,
+            };
+            if failures >= retries {
+                if retries > 0 {
+                    self.dead_letter(device_id, message, failures + 1, &error).await;
                 }
+                return Err(error);
             }
-            ConnectionProtocol::WebRTC => {
-                // Send via WebRTC data channel
-                if let Some(webrtc) = &connection.webrtc_connection {
-                    debug!("Sending message via WebRTC data channel to {}: {:?}", connection.device_id, message.message_type);
 
-                    // TODO: Actual WebRTC data channel send implementation
-                    // webrtc.data_channel.send(&message_data).await.map_err(|e| MisaError::Device(format!("WebRTC send failed: {}", e)))?;
-                } else {
-                    return Err(MisaError::Device(format!("No WebRTC connection to device: {}", connection.device_id)));
-                }
-            }
-            ConnectionProtocol::gRPC => {
-                debug!("Sending message via gRPC to device: {}", connection.device_id);
-                // TODO: Implement gRPC client communication
-            }
-            ConnectionProtocol::Bluetooth => {
-                debug!("Sending message via Bluetooth to device: {}", connection.device_id);
-                // TODO: Implement Bluetooth communication
-            }
+            let delay = Scheduler::backoff_delay(base_delay, failures, MAX_RETRY_DELAY);
+            debug!("Send to {} failed ({}), retrying in {:?}", device_id, error, delay);
+            failures += 1;
+            tokio::time::sleep(delay).await;
         }
+    }
 
-        Ok(())
+    async fn dead_letter(&self, device_id: &str, message: &DeviceMessage, attempts: u32, error: &MisaError) {
+        warn!(
+            "Giving up on message {} to device {} after {} attempts: {}",
+            message.message_id, device_id, attempts, error
+        );
+
+        let mut dead_letters = self.dead_letters.write().await;
+        if dead_letters.len() >= MAX_DEAD_LETTERS {
+            dead_letters.pop_front();
+        }
+        dead_letters.push_back(DeadLetter {
+            device_id: device_id.to_string(),
+            message: message.clone(),
+            attempts,
+            last_error: error.to_string(),
+            failed_at: chrono::Utc::now(),
+        });
     }
 
-    async fn broadcast_message(&self, message: &DeviceMessage) -> MisaResult<()> {
-        let connections = self.active_connections.read().await;
+    /// Replace the payload of an encrypted message with an envelope only `device_id` can open
+    async fn seal_message(&self, device_id: &str, message: &DeviceMessage) -> MisaResult<DeviceMessage> {
+        if !message.encrypted {
+            return Ok(message.clone());
+        }
 
-        for (device_id, connection) in connections.iter() {
-            if let Err(e) = self.send_message_via_connection(connection, message).await {
-                warn!("Failed to send message to device {}: {}", device_id, e);
+        let plaintext = serde_json::to_vec(&message.payload)?;
+        let envelope = self.security_manager.encrypt_for_device(device_id, &plaintext).await?;
+
+        Ok(DeviceMessage {
+            payload: serde_json::json!({ "envelope": envelope }),
+            ..message.clone()
+        })
+    }
+
+    /// Charge a message to the inbound budget of the connection it arrived on,
+    /// rejecting it once the budget is spent
+    async fn admit_inbound(&self, device_id: &str, message: &DeviceMessage) -> MisaResult<()> {
+        let rate = self.config.inbound_messages_per_second;
+        if rate <= 0.0 {
+            return Ok(());
+        }
+        let capacity = f64::from(self.config.inbound_message_burst.max(1));
+
+        let first_rejection = {
+            let mut limits = self.inbound_limits.write().await;
+            if limits.len() >= MAX_INBOUND_BUCKETS && !limits.contains_key(device_id) {
+                limits.retain(|_, bucket| !bucket.is_idle(rate, capacity));
+                if limits.len() >= MAX_INBOUND_BUCKETS {
+                    let oldest = limits
+                        .iter()
+                        .min_by_key(|(_, bucket)| bucket.last_refill)
+                        .map(|(id, _)| id.clone());
+                    if let Some(oldest) = oldest {
+                        limits.remove(&oldest);
+                    }
+                }
             }
+            let bucket = limits
+                .entry(device_id.to_string())
+                .or_insert_with(|| TokenBucket::new(capacity));
+            if bucket.try_take(rate, capacity) {
+                return Ok(());
+            }
+            !std::mem::replace(&mut bucket.throttled, true)
+        };
+
+        if first_rejection {
+            warn!("Device {} exceeded its inbound message rate, dropping messages", device_id);
+            self.security_manager
+                .log_security_event(
+                    None,
+                    "device_rate_limited",
+                    device_id,
+                    AuditResult::Failure,
+                    serde_json::json!({
+                        "message_type": format!("{:?}", message.message_type),
+                        "messages_per_second": rate,
+                        "burst": capacity,
+                    }),
+                )
+                .await?;
         }
 
-        Ok(())
+        Err(MisaError::RateLimit(format!(
+            "Device {} exceeded {} messages per second",
+            device_id, rate
+        )))
+    }
+
+    /// Restore the payload of an encrypted message from its sender's envelope
+    async fn open_message(&self, device_id: &str, message: DeviceMessage) -> MisaResult<DeviceMessage> {
+        if !message.encrypted {
+            return Ok(message);
+        }
+
+        // The key belongs to the connection, never to whoever the message claims to be from
+        let envelope: EncryptedData = serde_json::from_value(message.payload["envelope"].clone())
+            .map_err(|_| MisaError::Device(format!("Encrypted message {} has no envelope", message.message_id)))?;
+        let plaintext = self
+            .security_manager
+            .decrypt_from_device(device_id, &envelope)
+            .await?;
+
+        Ok(DeviceMessage {
+            payload: serde_json::from_slice(&plaintext)?,
+            ..message
+        })
     }
 
-    async fn select_best_device(&self, devices: &HashMap<String, DeviceInfo>) -> MisaResult<Option<String>> {
+    async fn select_best_device(
+        &self,
+        devices: &HashMap<String, DeviceInfo>,
+        profile: TaskProfile,
+    ) -> MisaResult<DeviceSelection> {
+        let qualities = self.connection_quality.read().await;
+        let (compute_weight, latency_weight, bandwidth_weight, stability_weight) = profile.weights();
+
         let mut best_device = None;
         let mut best_score = -1.0;
+        let mut candidates = Vec::new();
 
         for (device_id, device) in devices.iter() {
             if !matches!(device.status, DeviceStatus::Online) {
                 continue;
             }
 
-            let mut score = 0.0;
-
-            // Prefer devices with GPU
-            if device.capabilities.supports_gpu {
-                score += 10.0;
-            }
+            let compute = (Self::compute_score(device) / 40.0).clamp(0.0, 1.0);
 
-            // Prefer devices with more memory
-            score += (device.capabilities.max_memory_mb as f64) / 1024.0; // Convert to GB
+            // Devices without quality data get neutral link scores
+            let (latency, bandwidth, stability) = match qualities.get(device_id) {
+                Some(quality) => (
+                    1.0 / (1.0 + quality.latency_ms as f64 / 50.0),
+                    (quality.bandwidth_mbps as f64 / 100.0).min(1.0),
+                    (quality.stability_score as f64).clamp(0.0, 1.0),
+                ),
+                None => (0.5, 0.5, 0.5),
+            };
 
-            // Prefer non-battery powered devices
-            if !device.capabilities.battery_powered {
-                score += 5.0;
-            }
+            let score = compute_weight * compute
+                + latency_weight * latency
+                + bandwidth_weight * bandwidth
+                + stability_weight * stability;
 
-            // Penalize low battery
-            if let Some(battery) = device.battery_level {
-                if battery < 20.0 {
-                    score -= 5.0;
-                }
-            }
+            debug!("Device {} scored {:.3} for {:?} profile", device_id, score, profile);
+            candidates.push(DeviceScore { device_id: device_id.clone(), score });
 
             if score > best_score {
                 best_score = score;
@@ -720,7 +3144,35 @@ impl DeviceManager {
             }
         }
 
-        Ok(best_device)
+        candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(DeviceSelection { device_id: best_device, candidates })
+    }
+
+    /// Raw compute capability score based on hardware and power state
+    fn compute_score(device: &DeviceInfo) -> f64 {
+        let mut score = 0.0;
+
+        // Prefer devices with GPU
+        if device.capabilities.supports_gpu {
+            score += 10.0;
+        }
+
+        // Prefer devices with more memory
+        score += (device.capabilities.max_memory_mb as f64) / 1024.0; // Convert to GB
+
+        // Prefer non-battery powered devices
+        if !device.capabilities.battery_powered {
+            score += 5.0;
+        }
+
+        // Penalize low battery
+        if let Some(battery) = device.battery_level {
+            if battery < 20.0 {
+                score -= 5.0;
+            }
+        }
+
+        score
     }
 
     async fn start_device_monitoring(&self) -> MisaResult<()> {
@@ -742,6 +3194,147 @@ impl DeviceManager {
         Ok(())
     }
 
+    /// Answer a paired device's key exchange with our half and keep the agreed key
+    async fn answer_key_exchange(&self, device_id: &str, message: &DeviceMessage) -> MisaResult<()> {
+        if !self.devices.read().await.contains_key(device_id) {
+            return Err(MisaError::Permission(format!("Key exchange from unpaired device {}", device_id)));
+        }
+
+        let peer_public_key = message.payload["public_key"]
+            .as_str()
+            .and_then(decode_hex)
+            .ok_or_else(|| MisaError::Device(format!("Key exchange from {} carries no key", device_id)))?;
+        let exchange = self.security_manager.start_device_key_exchange()?;
+        let public_key = encode_hex(exchange.public_key());
+        self.security_manager
+            .complete_device_key_exchange(device_id, exchange, &peer_public_key)
+            .await?;
+
+        // Public keys are not secret, and the requester can't open the new key yet
+        let mut reply = message.response(&self.device_id, serde_json::json!({ "public_key": public_key }));
+        reply.message_type = MessageType::PairingResponse;
+        reply.target_device_id = Some(device_id.to_string());
+        reply.encrypted = false;
+        self.send_message(reply).await
+    }
+
+    /// Answer a paired device's remote desktop offer with our own capabilities, remembering theirs
+    /// and hosting the session it offered to control with the permissions this device grants
+    async fn answer_remote_desktop_handshake(&self, device_id: &str, message: &DeviceMessage) -> MisaResult<()> {
+        // Enumerating displays blocks, so it happens before the device lock is taken
+        let display_error = match message.payload["display_id"].as_u64() {
+            Some(id) => {
+                let displays = ScreenCapturer::available_displays().await?;
+                (!displays.iter().any(|display| u64::from(display.id) == id)).then(|| format!("Display {} not found", id))
+            }
+            None => None,
+        };
+
+        let result = match self.devices.write().await.get_mut(device_id) {
+            None => serde_json::json!({ "error": "Device is not paired" }),
+            Some(_) if !self.remote_desktop_manager.enabled => serde_json::json!({ "error": "Remote desktop disabled" }),
+            Some(_) if display_error.is_some() => serde_json::json!({ "error": display_error }),
+            Some(device) => {
+                match serde_json::from_value(message.payload["capabilities"].clone()) {
+                    Ok(capabilities) => device.capabilities.remote_desktop = capabilities,
+                    Err(e) => warn!("Invalid remote desktop capabilities from {}: {}", device_id, e),
+                }
+
+                // `device_id` is the connection the offer arrived on, so only that peer can drive the session.
+                // What it asks for is only an upper bound; this device decides what it gets.
+                let requested: Result<RemoteDesktopPermissions, _> =
+                    serde_json::from_value(message.payload["permissions"].clone());
+                let hosted = match (message.payload["session_id"].as_str(), requested) {
+                    (Some(session_id), Ok(requested)) => {
+                        let granted = match &self.remote_desktop_approver {
+                            Some(approver) => approver.approve(device_id, &requested),
+                            None => Some(RemoteDesktopPermissions::view_only()),
+                        };
+                        match granted {
+                            Some(granted) => {
+                                let granted = requested.intersect(&granted);
+                                self.remote_desktop_manager
+                                    .host_session(session_id, device_id, granted.clone())
+                                    .await
+                                    .map(|()| granted)
+                            }
+                            None => Err(MisaError::Permission("Remote desktop session declined".to_string())),
+                        }
+                    }
+                    _ => Err(MisaError::Validation("Remote desktop offer names no session".to_string())),
+                };
+                match hosted {
+                    Ok(granted) => serde_json::json!({
+                        "capabilities": self.remote_desktop_manager.capabilities,
+                        "permissions": granted,
+                    }),
+                    Err(e) => serde_json::json!({ "error": e.to_string() }),
+                }
+            }
+        };
+
+        let mut reply = message.response(&self.device_id, result);
+        reply.target_device_id = Some(device_id.to_string());
+        self.send_message(reply).await
+    }
+
+    /// Grant or decline a device's request to send us a file
+    async fn answer_transfer_request(&self, message: &DeviceMessage) -> MisaResult<()> {
+        let request: FileTransferRequest = serde_json::from_value(message.payload.clone())?;
+        let source_device_id = &message.source_device_id;
+
+        let approved = match &self.transfer_approver {
+            Some(approver) => approver.approve(source_device_id, &request),
+            None => self.security_manager.has_device_key(source_device_id).await,
+        };
+        let response = if !approved {
+            FileTransferResponse {
+                grant: None,
+                reason: Some("Transfer declined".to_string()),
+            }
+        } else {
+            match self.remote_desktop_manager.file_transfer_manager.issue_grant(source_device_id, &request).await {
+                Ok(grant) => FileTransferResponse { grant: Some(grant), reason: None },
+                Err(e) => FileTransferResponse { grant: None, reason: Some(e.to_string()) },
+            }
+        };
+        info!(
+            "{} file transfer of {} from {}",
+            if response.grant.is_some() { "Granted" } else { "Declined" },
+            request.file_name,
+            source_device_id
+        );
+
+        let mut reply = message.response(&self.device_id, serde_json::to_value(&response)?);
+        reply.message_type = MessageType::FileTransferResponse;
+        self.send_message(reply).await
+    }
+
+    /// Run a delegated task and send back its result, or `{"error": ...}` when it can't run here
+    async fn answer_task_request(&self, message: &DeviceMessage) -> MisaResult<()> {
+        let source_device_id = &message.source_device_id;
+        let result = match &self.task_handler {
+            None => Err(MisaError::Device("This device does not accept delegated tasks".to_string())),
+            Some(_) if !self.accepts_tasks_from(source_device_id).await => Err(MisaError::Permission(format!(
+                "Device {} is not allowed to delegate tasks",
+                source_device_id
+            ))),
+            Some(handler) => handler.handle_task(source_device_id, message.payload.clone()).await,
+        };
+        let result = result.unwrap_or_else(|e| {
+            warn!("Delegated task from {} failed: {}", source_device_id, e);
+            serde_json::json!({ "error": e.to_string() })
+        });
+
+        self.send_message(message.response(&self.device_id, result)).await
+    }
+
+    /// Whether `device_id` may run tasks here: it is paired, or explicitly allowed in the config
+    async fn accepts_tasks_from(&self, device_id: &str) -> bool {
+        self.config.task_delegation_devices.iter().any(|allowed| allowed == device_id)
+            || self.security_manager.has_device_key(device_id).await
+    }
+
     fn validate_file(&self, file_path: &str) -> MisaResult<()> {
         // Check file exists
         if !std::path::Path::new(file_path).exists() {
@@ -765,12 +3358,114 @@ impl DeviceManager {
     }
 }
 
+impl DeviceConnection {
+    /// Send a message over this connection
+    pub async fn send(&self, message: &DeviceMessage) -> MisaResult<()> {
+        let message_data = serde_json::to_vec(message)?;
+
+        match self.connection_type {
+            ConnectionProtocol::WebSocket => {
+                let sender = self.websocket.as_ref()
+                    .ok_or_else(|| MisaError::Device(format!("No WebSocket connection to device: {}", self.device_id)))?;
+                debug!("Sending message via WebSocket to {}: {:?}", self.device_id, message.message_type);
+                sender.send(message.clone())
+                    .map_err(|_| MisaError::Device(format!("WebSocket channel to device {} closed", self.device_id)))?;
+            }
+            ConnectionProtocol::WebRTC => {
+                // Send via WebRTC data channel
+                if let Some(webrtc) = &self.webrtc_connection {
+                    debug!("Sending message via WebRTC data channel to {}: {:?}", self.device_id, message.message_type);
+
+                    webrtc
+                        .data_channel
+                        .send(&bytes::Bytes::from(message_data))
+                        .await
+                        .map_err(|e| MisaError::Device(format!("WebRTC send to {} failed: {}", self.device_id, e)))?;
+                } else {
+                    return Err(MisaError::Device(format!("No WebRTC connection to device: {}", self.device_id)));
+                }
+            }
+            ConnectionProtocol::gRPC => {
+                debug!("Sending message via gRPC to device: {}", self.device_id);
+                // TODO: Implement gRPC client communication
+            }
+            ConnectionProtocol::Bluetooth => {
+                debug!("Sending message via Bluetooth to device: {}", self.device_id);
+                // TODO: Implement Bluetooth communication
+            }
+            ConnectionProtocol::Local => {
+                let sender = self.local_channel.as_ref()
+                    .ok_or_else(|| MisaError::Device(format!("No local channel to device: {}", self.device_id)))?;
+                sender.send(message.clone())
+                    .map_err(|_| MisaError::Device(format!("Local channel to device {} closed", self.device_id)))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Detect a file's MIME type from its leading bytes, ignoring its name
+async fn sniff_mime_type(file_path: &str) -> MisaResult<String> {
+    let file_path = file_path.to_string();
+    tokio::task::spawn_blocking(move || -> MisaResult<String> {
+        let mut header = Vec::new();
+        std::fs::File::open(&file_path)?
+            .take(CONTENT_SNIFF_BYTES)
+            .read_to_end(&mut header)?;
+        Ok(classify_content(&header).to_string())
+    })
+    .await
+    .map_err(|e| MisaError::FileTransfer(format!("Detecting the file type failed: {}", e)))?
+}
+
+/// MIME type of content starting with `header`
+fn classify_content(header: &[u8]) -> &'static str {
+    if let Some(kind) = infer::get(header) {
+        return kind.mime_type();
+    }
+
+    // Text has no magic bytes; a character cut off at the end of the header still counts
+    let text = match std::str::from_utf8(header) {
+        Ok(text) => text,
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&header[..e.valid_up_to()]).unwrap_or_default(),
+        Err(_) => return "application/octet-stream",
+    };
+
+    // Text that runs or renders is told apart from plain text, which allow-lists tend to admit
+    let start = text.trim_start_matches('\u{feff}').trim_start().to_ascii_lowercase();
+    if start.starts_with("#!") {
+        "text/x-script"
+    } else if start.starts_with("@echo off") {
+        "application/x-bat"
+    } else if start.starts_with("<svg") || (start.starts_with("<?xml") && start.contains("<svg")) {
+        "image/svg+xml"
+    } else if HTML_SNIFF_TAGS.iter().any(|tag| opens_with_tag(&start, tag)) {
+        "text/html"
+    } else if start.starts_with("<?xml") {
+        "application/xml"
+    } else {
+        "text/plain"
+    }
+}
+
+/// Whether `text` opens with `tag` as a whole tag name, so `<b` doesn't match `<bold`
+fn opens_with_tag(text: &str, tag: &str) -> bool {
+    text.strip_prefix(tag)
+        .and_then(|rest| rest.chars().next())
+        .map_or(false, |next| next == '>' || next.is_whitespace())
+}
+
 /// Pairing data from QR token
 #[derive(Debug, Clone)]
 struct PairingData {
     device_id: String,
     timestamp: i64,
-    signature: String,
+    /// Device that issued the token
+    host_id: String,
+    /// Issuer's half of the key exchange
+    host_public_key: Vec<u8>,
+    signature: Vec<u8>,
 }
 
 /// Pairing result
@@ -794,6 +3489,17 @@ impl Default for DeviceCapabilities {
             gpu_memory_mb: None,
             battery_powered: false,
             supports_remote_desktop: true,
+            remote_desktop: RemoteDesktopCapabilities::default(),
+        }
+    }
+}
+
+impl Default for RemoteDesktopCapabilities {
+    fn default() -> Self {
+        Self {
+            protocols: vec![RemoteDesktopProtocol::WebRTC, RemoteDesktopProtocol::VNC],
+            codecs: vec![ImageFormat::H264, ImageFormat::VP9, ImageFormat::WebP, ImageFormat::JPEG, ImageFormat::PNG],
+            max_resolution: VideoQuality::Ultra.resolution(),
         }
     }
 }
@@ -811,10 +3517,13 @@ impl Default for NetworkInfo {
 }
 
 impl DiscoveryService {
-    pub fn new(enabled: bool) -> Self {
+    pub fn new(enabled: bool, transport: DiscoveryTransport) -> Self {
         Self {
             enabled,
-            discovery_port: 8081,
+            transport,
+            device_id: "local-device".to_string(), // Would get from config
+            mdns: Arc::new(RwLock::new(None)),
+            discovery_port: DISCOVERY_PORT,
             broadcast_interval_seconds: 30,
             active_discovery: Arc::new(RwLock::new(HashMap::new())),
             background_scanning: true,
@@ -825,11 +3534,33 @@ impl DiscoveryService {
         }
     }
 
+    /// Identify this device to peers under a specific id
+    pub fn with_device_id(mut self, device_id: &str) -> Self {
+        self.device_id = device_id.to_string();
+        self
+    }
+
     pub async fn start(&self) -> MisaResult<()> {
         if !self.enabled {
             return Ok(());
         }
 
+        if matches!(self.transport, DiscoveryTransport::Udp | DiscoveryTransport::Both) {
+            self.start_udp().await?;
+        }
+
+        if matches!(self.transport, DiscoveryTransport::Mdns | DiscoveryTransport::Both) {
+            self.start_mdns().await?;
+        }
+
+        // Start connection quality monitoring
+        self.connection_quality_monitor.start_monitoring().await?;
+
+        info!("Enhanced discovery service started successfully");
+        Ok(())
+    }
+
+    async fn start_udp(&self) -> MisaResult<()> {
         info!("Starting enhanced discovery service on port {}", self.discovery_port);
 
         // Start UDP discovery service
@@ -844,6 +3575,9 @@ impl DiscoveryService {
         let last_scan = Arc::clone(&self.last_scan);
         let device_history = Arc::clone(&self.device_history);
         let quality_monitor = Arc::clone(&self.connection_quality_monitor.active_connections);
+        let scan_quality_monitor = self.connection_quality_monitor.clone();
+        let probe_port = self.discovery_port + 1;
+        let local_packet = self.local_discovery_packet();
 
         // Spawn enhanced discovery broadcaster
         tokio::spawn(async move {
@@ -856,15 +3590,25 @@ impl DiscoveryService {
                 // Update last scan time
                 *last_scan.write().await = chrono::Utc::now();
 
-                if let Err(e) = Self::broadcast_device_info_enhanced(&socket, &device_history, &quality_monitor).await {
+                if let Err(e) = Self::broadcast_device_info_enhanced(&socket, &local_packet, &device_history, &quality_monitor).await {
                     warn!("Failed to broadcast device info: {}", e);
                 }
 
                 // Background scanning
                 if background_scanning {
-                    if let Err(e) = Self::background_device_scan(&socket, &device_history).await {
+                    if let Err(e) = Self::background_device_scan(&socket, &local_packet, probe_port, &device_history).await {
                         warn!("Background scan failed: {}", e);
                     }
+
+                    if let Err(e) = Self::collect_probe_responses(
+                        &socket,
+                        PROBE_RESPONSE_WINDOW,
+                        &active_discovery,
+                        &device_history,
+                        &scan_quality_monitor,
+                    ).await {
+                        warn!("Failed to collect probe responses: {}", e);
+                    }
                 }
 
                 // Smart suggestions
@@ -880,6 +3624,7 @@ impl DiscoveryService {
         let active_discovery_listener = Arc::clone(&self.active_discovery);
         let device_history_listener = Arc::clone(&self.device_history);
         let quality_monitor_listener = self.connection_quality_monitor.clone();
+        let reply_packet = self.local_discovery_packet();
         let listener_socket = tokio::net::UdpSocket::bind(("0.0.0.0", self.discovery_port + 1))
             .await
             .map_err(|e| MisaError::Device(format!("Failed to bind listener socket: {}", e)))?;
@@ -890,14 +3635,27 @@ impl DiscoveryService {
                 match listener_socket.recv_from(&mut buf).await {
                     Ok((len, addr)) => {
                         let data = &buf[..len];
-                        if let Err(e) = Self::handle_discovery_packet_enhanced(
+                        if let Some(pong) = ping_reply(data) {
+                            if let Err(e) = listener_socket.send_to(&pong, addr).await {
+                                debug!("Failed to answer ping from {}: {}", addr, e);
+                            }
+                            continue;
+                        }
+
+                        match Self::handle_discovery_packet_enhanced(
                             data,
                             addr,
                             &active_discovery_listener,
                             &device_history_listener,
                             &quality_monitor_listener
                         ).await {
-                            warn!("Failed to handle discovery packet: {}", e);
+                            Ok(packet) if packet.probe => {
+                                if let Err(e) = Self::send_packet(&listener_socket, &reply_packet, addr).await {
+                                    debug!("Failed to answer probe from {}: {}", addr, e);
+                                }
+                            }
+                            Ok(_) => {}
+                            Err(e) => warn!("Failed to handle discovery packet: {}", e),
                         }
                     }
                     Err(e) => warn!("Discovery listener error: {}", e),
@@ -905,21 +3663,128 @@ impl DiscoveryService {
             }
         });
 
-        // Start connection quality monitoring
-        self.connection_quality_monitor.start_monitoring().await?;
+        Ok(())
+    }
 
-        info!("Enhanced discovery service started successfully");
+    /// Advertise this device over mDNS and browse for peers doing the same
+    async fn start_mdns(&self) -> MisaResult<()> {
+        info!("Starting mDNS discovery for {}", MDNS_SERVICE_TYPE);
+
+        let daemon = mdns_sd::ServiceDaemon::new()
+            .map_err(|e| MisaError::Device(format!("Failed to start mDNS daemon: {}", e)))?;
+
+        let packet = self.local_discovery_packet();
+        let capabilities = packet.capabilities.join(",");
+        let properties = [
+            ("id", packet.device_id.as_str()),
+            ("name", packet.device_name.as_str()),
+            ("type", packet.device_type.as_str()),
+            ("caps", capabilities.as_str()),
+        ];
+        let service = mdns_sd::ServiceInfo::new(
+            MDNS_SERVICE_TYPE,
+            &packet.device_id,
+            &format!("{}.local.", packet.device_id),
+            "",
+            packet.port,
+            &properties[..],
+        )
+        .map_err(|e| MisaError::Device(format!("Invalid mDNS service info: {}", e)))?
+        .enable_addr_auto();
+
+        daemon
+            .register(service)
+            .map_err(|e| MisaError::Device(format!("Failed to advertise mDNS service: {}", e)))?;
+        let browser = daemon
+            .browse(MDNS_SERVICE_TYPE)
+            .map_err(|e| MisaError::Device(format!("Failed to browse mDNS services: {}", e)))?;
+
+        let local_device_id = self.device_id.clone();
+        let active_discovery = Arc::clone(&self.active_discovery);
+        let device_history = Arc::clone(&self.device_history);
+        let quality_monitor = self.connection_quality_monitor.clone();
+
+        tokio::spawn(async move {
+            while let Ok(event) = browser.recv_async().await {
+                let info = match event {
+                    mdns_sd::ServiceEvent::ServiceResolved(info) => info,
+                    _ => continue,
+                };
+
+                let packet = match Self::packet_from_service(&info) {
+                    Some(packet) if packet.device_id != local_device_id => packet,
+                    _ => continue,
+                };
+
+                let addr = match info.get_addresses().iter().next() {
+                    Some(ip) => SocketAddr::new((*ip).into(), info.get_port()),
+                    None => continue,
+                };
+
+                if let Err(e) = Self::record_discovery(
+                    &packet,
+                    addr,
+                    &active_discovery,
+                    &device_history,
+                    &quality_monitor,
+                ).await {
+                    warn!("Failed to record mDNS peer {}: {}", packet.device_id, e);
+                }
+            }
+            debug!("mDNS browse ended");
+        });
+
+        *self.mdns.write().await = Some(daemon);
         Ok(())
     }
 
-    async fn broadcast_device_info(socket: &Arc<tokio::net::UdpSocket>) -> MisaResult<()> {
+    /// What this device announces about itself
+    fn local_discovery_packet(&self) -> DeviceDiscoveryPacket {
+        DeviceDiscoveryPacket {
+            device_id: self.device_id.clone(),
+            device_name: "Misa Device".to_string(),
+            device_type: "Desktop".to_string(),
+            capabilities: vec![
+                "gpu".to_string(),
+                "vision".to_string(),
+                "audio".to_string(),
+                "remote_desktop".to_string(),
+                "background_discovery".to_string(),
+            ],
+            port: DEVICE_SERVICE_PORT,
+            timestamp: chrono::Utc::now(),
+            probe: false,
+        }
+    }
+
+    /// Rebuild a discovery packet from a resolved service's TXT records
+    fn packet_from_service(info: &mdns_sd::ServiceInfo) -> Option<DeviceDiscoveryPacket> {
+        let device_id = info.get_property_val_str("id")?.to_string();
+        let capabilities = info
+            .get_property_val_str("caps")
+            .map(|caps| caps.split(',').filter(|cap| !cap.is_empty()).map(str::to_string).collect())
+            .unwrap_or_default();
+
+        Some(DeviceDiscoveryPacket {
+            device_name: info.get_property_val_str("name").unwrap_or(&device_id).to_string(),
+            device_type: info.get_property_val_str("type").unwrap_or("Desktop").to_string(),
+            device_id,
+            capabilities,
+            port: info.get_port(),
+            timestamp: chrono::Utc::now(),
+            probe: false,
+        })
+    }
+
+    async fn broadcast_device_info(socket: &Arc<tokio::net::UdpSocket>, local_packet: &DeviceDiscoveryPacket) -> MisaResult<()> {
         let device_info = DeviceDiscoveryPacket {
-            device_id: "local-device".to_string(), // Would get from config
+            device_id: local_packet.device_id.clone(),
             device_name: "Misa Device".to_string(),
             device_type: "Desktop".to_string(),
             capabilities: vec!["gpu".to_string(), "vision".to_string(), "audio".to_string()],
             port: 8080,
             timestamp: chrono::Utc::now(),
+            probe: false,
         };
 
         let packet_data = serde_json::to_vec(&device_info)
@@ -963,6 +3828,12 @@ impl DiscoveryService {
     pub async fn stop(&self) -> MisaResult<()> {
         info!("Stopping enhanced discovery service");
 
+        if let Some(daemon) = self.mdns.write().await.take() {
+            if let Err(e) = daemon.shutdown() {
+                warn!("Failed to shut down mDNS daemon: {}", e);
+            }
+        }
+
         // Cleanup active discovery sessions
         let mut sessions = self.active_discovery.write().await;
         sessions.clear();
@@ -976,6 +3847,7 @@ impl DiscoveryService {
     /// Enhanced broadcast with device history and quality information
     async fn broadcast_device_info_enhanced(
         socket: &Arc<tokio::net::UdpSocket>,
+        local_packet: &DeviceDiscoveryPacket,
         device_history: &Arc<RwLock<HashMap<String, DeviceHistory>>>,
         quality_monitor: &Arc<RwLock<HashMap<String, ConnectionQuality>>>,
     ) -> MisaResult<()> {
@@ -983,7 +3855,7 @@ impl DiscoveryService {
         let quality = quality_monitor.read().await;
 
         let device_info = DeviceDiscoveryPacket {
-            device_id: "local-device".to_string(),
+            device_id: local_packet.device_id.clone(),
             device_name: "Misa Device".to_string(),
             device_type: "Desktop".to_string(),
             capabilities: vec![
@@ -995,6 +3867,7 @@ impl DiscoveryService {
             ],
             port: 8080,
             timestamp: chrono::Utc::now(),
+            probe: false,
         };
 
         let packet_data = serde_json::to_vec(&device_info)
@@ -1013,23 +3886,29 @@ impl DiscoveryService {
     /// Background device scanning for continuous discovery
     async fn background_device_scan(
         socket: &Arc<tokio::net::UdpSocket>,
+        local_packet: &DeviceDiscoveryPacket,
+        probe_port: u16,
         device_history: &Arc<RwLock<HashMap<String, DeviceHistory>>>,
     ) -> MisaResult<()> {
         debug!("Performing background device scan");
 
         // Scan for known devices first
-        let history = device_history.read().await;
-        for (device_id, device_info) in history.iter() {
-            if should_scan_device(device_info) {
-                // Send directed discovery packet to known device
-                if let Err(e) = Self::send_directed_discovery(socket, device_id).await {
-                    debug!("Failed to scan device {}: {}", device_id, e);
-                }
+        let known: Vec<String> = device_history
+            .read()
+            .await
+            .iter()
+            .filter(|(_, device_info)| should_scan_device(device_info))
+            .map(|(device_id, _)| device_id.clone())
+            .collect();
+        for device_id in known {
+            // Send directed discovery packet to known device
+            if let Err(e) = Self::send_directed_discovery(socket, local_packet, &device_id, device_history).await {
+                debug!("Failed to scan device {}: {}", device_id, e);
             }
         }
 
         // Perform general network scan
-        if let Err(e) = Self::network_discovery_scan(socket).await {
+        if let Err(e) = Self::network_discovery_scan(socket, local_packet, probe_port).await {
             warn!("Network discovery scan failed: {}", e);
         }
 
@@ -1063,23 +3942,129 @@ impl DiscoveryService {
         Ok(())
     }
 
-    /// Send directed discovery to specific device
+    /// Probe a known device at its last-known address
     async fn send_directed_discovery(
         socket: &Arc<tokio::net::UdpSocket>,
+        local_packet: &DeviceDiscoveryPacket,
         device_id: &str,
+        device_history: &Arc<RwLock<HashMap<String, DeviceHistory>>>,
+    ) -> MisaResult<()> {
+        let addr = device_history
+            .read()
+            .await
+            .get(device_id)
+            .and_then(|device_info| device_info.last_known_addr)
+            .ok_or_else(|| MisaError::Device(format!("No known address for device: {}", device_id)))?;
+
+        debug!("Sending directed discovery to device {} at {}", device_id, addr);
+        Self::send_probe(socket, local_packet, addr).await
+    }
+
+    /// Sweep the local /24 with directed probes
+    async fn network_discovery_scan(
+        socket: &Arc<tokio::net::UdpSocket>,
+        local_packet: &DeviceDiscoveryPacket,
+        probe_port: u16,
+    ) -> MisaResult<()> {
+        let local_ip = match local_ipv4().await {
+            Some(ip) => ip,
+            None => {
+                debug!("No local IPv4 address, skipping network discovery scan");
+                return Ok(());
+            }
+        };
+
+        Self::sweep_subnet(socket, local_packet, local_ip, probe_port).await
+    }
+
+    /// Probe every other host in `local_ip`'s /24, pacing sends to avoid flooding the network
+    async fn sweep_subnet(
+        socket: &Arc<tokio::net::UdpSocket>,
+        local_packet: &DeviceDiscoveryPacket,
+        local_ip: std::net::Ipv4Addr,
+        probe_port: u16,
     ) -> MisaResult<()> {
-        // Implementation would send directed packet to specific device
-        debug!("Sending directed discovery to device: {}", device_id);
+        debug!("Performing network discovery scan of {}/24", local_ip);
+
+        let [a, b, c, own] = local_ip.octets();
+        for host in 1..=254u8 {
+            if host == own {
+                continue;
+            }
+
+            let addr = SocketAddr::from(([a, b, c, host], probe_port));
+            if let Err(e) = Self::send_probe(socket, local_packet, addr).await {
+                debug!("Probe to {} failed: {}", addr, e);
+            }
+            tokio::time::sleep(SCAN_PROBE_INTERVAL).await;
+        }
+
         Ok(())
     }
 
-    /// Perform general network discovery scan
-    async fn network_discovery_scan(socket: &Arc<tokio::net::UdpSocket>) -> MisaResult<()> {
-        // Implementation would scan local network for devices
-        debug!("Performing network discovery scan");
+    async fn send_probe(
+        socket: &tokio::net::UdpSocket,
+        local_packet: &DeviceDiscoveryPacket,
+        addr: SocketAddr,
+    ) -> MisaResult<()> {
+        let probe = DeviceDiscoveryPacket {
+            timestamp: chrono::Utc::now(),
+            probe: true,
+            ..local_packet.clone()
+        };
+        Self::send_packet(socket, &probe, addr).await
+    }
+
+    async fn send_packet(
+        socket: &tokio::net::UdpSocket,
+        packet: &DeviceDiscoveryPacket,
+        addr: SocketAddr,
+    ) -> MisaResult<()> {
+        let data = serde_json::to_vec(packet)?;
+        socket
+            .send_to(&data, addr)
+            .await
+            .map_err(|e| MisaError::Device(format!("Failed to send discovery packet to {}: {}", addr, e)))?;
         Ok(())
     }
 
+    /// Record every probe answer that arrives within `window`
+    async fn collect_probe_responses(
+        socket: &tokio::net::UdpSocket,
+        window: Duration,
+        active_discovery: &Arc<RwLock<HashMap<String, DiscoverySession>>>,
+        device_history: &Arc<RwLock<HashMap<String, DeviceHistory>>>,
+        quality_monitor: &ConnectionQualityMonitor,
+    ) -> MisaResult<usize> {
+        let deadline = tokio::time::Instant::now() + window;
+        let mut buf = [0u8; 1024];
+        let mut responders = 0;
+
+        loop {
+            let (len, addr) = match tokio::time::timeout_at(deadline, socket.recv_from(&mut buf)).await {
+                Ok(Ok(received)) => received,
+                Ok(Err(e)) => {
+                    debug!("Probe response receive failed: {}", e);
+                    continue;
+                }
+                Err(_) => break,
+            };
+
+            let packet: DeviceDiscoveryPacket = match serde_json::from_slice(&buf[..len]) {
+                Ok(packet) => packet,
+                Err(_) => continue,
+            };
+            if packet.probe {
+                continue;
+            }
+
+            Self::record_discovery(&packet, addr, active_discovery, device_history, quality_monitor).await?;
+            responders += 1;
+        }
+
+        Ok(responders)
+    }
+
     /// Enhanced packet handler with device history tracking
     async fn handle_discovery_packet_enhanced(
         data: &[u8],
@@ -1087,14 +4072,28 @@ impl DiscoveryService {
         active_discovery: &Arc<RwLock<HashMap<String, DiscoverySession>>>,
         device_history: &Arc<RwLock<HashMap<String, DeviceHistory>>>,
         quality_monitor: &ConnectionQualityMonitor,
-    ) -> MisaResult<()> {
+    ) -> MisaResult<DeviceDiscoveryPacket> {
         let packet: DeviceDiscoveryPacket = serde_json::from_slice(data)
             .map_err(|_| MisaError::Device("Invalid discovery packet".to_string()))?;
 
         debug!("Received enhanced discovery packet from {}: {}", addr, packet.device_id);
 
+        Self::record_discovery(&packet, addr, active_discovery, device_history, quality_monitor).await?;
+        Ok(packet)
+    }
+
+    /// Track a discovered peer, whichever transport found it
+    async fn record_discovery(
+        packet: &DeviceDiscoveryPacket,
+        addr: std::net::SocketAddr,
+        active_discovery: &Arc<RwLock<HashMap<String, DiscoverySession>>>,
+        device_history: &Arc<RwLock<HashMap<String, DeviceHistory>>>,
+        quality_monitor: &ConnectionQualityMonitor,
+    ) -> MisaResult<()> {
+        let signal_strength = estimate_signal_strength(addr);
+
         // Update device history
-        Self::update_device_history(&packet, device_history).await?;
+        Self::update_device_history(packet, addr, signal_strength, device_history).await?;
 
         // Create enhanced discovery session
         let session = DiscoverySession {
@@ -1103,8 +4102,8 @@ impl DiscoveryService {
             started_at: chrono::Utc::now(),
             qr_token: format!("misa://pair/{}/{}", packet.device_id, chrono::Utc::now().timestamp()),
             pairing_status: PairingStatus::PendingConfirmation,
-            auto_pair_enabled: should_auto_pair(&packet, device_history).await,
-            connection_strength: estimate_signal_strength(addr),
+            auto_pair_enabled: should_auto_pair(packet, device_history).await,
+            connection_strength: signal_strength,
         };
 
         let mut sessions = active_discovery.write().await;
@@ -1119,6 +4118,8 @@ impl DiscoveryService {
     /// Update device history with new discovery information
     async fn update_device_history(
         packet: &DeviceDiscoveryPacket,
+        addr: SocketAddr,
+        signal_strength: f32,
         device_history: &Arc<RwLock<HashMap<String, DeviceHistory>>>,
     ) -> MisaResult<()> {
         let mut history = device_history.write().await;
@@ -1131,11 +4132,18 @@ impl DiscoveryService {
             success_rate: 1.0,
             preferred_for_tasks: Vec::new(),
             device_type: DeviceType::Desktop, // Default
+            last_known_addr: None,
         });
 
         // Update connection info
         device_history_entry.last_connected = chrono::Utc::now();
         device_history_entry.connection_count += 1;
+        device_history_entry.last_known_addr = Some(addr);
+
+        // Running mean over every sighting
+        let count = device_history_entry.connection_count as f32;
+        device_history_entry.average_signal_strength +=
+            (signal_strength - device_history_entry.average_signal_strength) / count;
 
         Ok(())
     }
@@ -1143,6 +4151,22 @@ impl DiscoveryService {
 
 /// Helper functions for enhanced discovery
 
+/// Gap between probes during a subnet sweep
+const SCAN_PROBE_INTERVAL: Duration = Duration::from_millis(5);
+
+/// How long to wait for probe answers after a scan
+const PROBE_RESPONSE_WINDOW: Duration = Duration::from_secs(2);
+
+/// Address of the interface used for outbound traffic. Connecting a UDP socket sends nothing.
+async fn local_ipv4() -> Option<std::net::Ipv4Addr> {
+    let socket = tokio::net::UdpSocket::bind(("0.0.0.0", 0)).await.ok()?;
+    socket.connect(("8.8.8.8", 80)).await.ok()?;
+    match socket.local_addr().ok()?.ip() {
+        std::net::IpAddr::V4(ip) if !ip.is_unspecified() => Some(ip),
+        _ => None,
+    }
+}
+
 fn should_scan_device(device_info: &DeviceHistory) -> bool {
     let hours_since_last_use = (chrono::Utc::now() - device_info.last_connected).num_hours();
     hours_since_last_use < 168 && device_info.success_rate > 0.5 // Scan devices used in last week with decent success rate
@@ -1181,6 +4205,8 @@ impl ConnectionQualityMonitor {
         Self {
             active_connections: Arc::new(RwLock::new(HashMap::new())),
             quality_history: Arc::new(RwLock::new(Vec::new())),
+            probe_addrs: Arc::new(RwLock::new(HashMap::new())),
+            probe_port: DISCOVERY_PORT + 1,
         }
     }
 
@@ -1189,6 +4215,7 @@ impl ConnectionQualityMonitor {
 
         let connections = Arc::clone(&self.active_connections);
         let history = Arc::clone(&self.quality_history);
+        let probe_addrs = Arc::clone(&self.probe_addrs);
 
         tokio::spawn(async move {
             let mut interval = tokio::time::interval(Duration::from_secs(10));
@@ -1196,7 +4223,7 @@ impl ConnectionQualityMonitor {
             loop {
                 interval.tick().await;
 
-                if let Err(e) = Self::monitor_connection_quality(&connections, &history).await {
+                if let Err(e) = Self::monitor_connection_quality(&connections, &history, &probe_addrs).await {
                     warn!("Connection quality monitoring error: {}", e);
                 }
             }
@@ -1214,102 +4241,576 @@ impl ConnectionQualityMonitor {
         let mut history = self.quality_history.write().await;
         history.clear();
 
+        self.probe_addrs.write().await.clear();
+
         Ok(())
     }
 
     pub async fn update_connection_quality(&self, device_id: &str, addr: std::net::SocketAddr) -> MisaResult<()> {
+        // Peers answer pings on their discovery listener, not the port they broadcast from
+        self.probe_addrs
+            .write()
+            .await
+            .insert(device_id.to_string(), SocketAddr::new(addr.ip(), self.probe_port));
+
         let mut connections = self.active_connections.write().await;
+        let signal_strength = estimate_signal_strength(addr);
 
-        let quality = ConnectionQuality {
+        // Keep earlier measurements; only the signal estimate comes from the sighting
+        let quality = connections.entry(device_id.to_string()).or_insert_with(|| ConnectionQuality {
             device_id: device_id.to_string(),
-            latency_ms: 0, // Would measure actual latency
-            bandwidth_mbps: 0.0, // Would measure actual bandwidth
-            signal_strength: estimate_signal_strength(addr),
+            latency_ms: 0, // Filled in by the next latency probe
+            bandwidth_mbps: 0.0, // Unmeasured
+            signal_strength,
             stability_score: 1.0,
             last_updated: chrono::Utc::now(),
             uptime_percentage: 100.0,
-        };
-
-        connections.insert(device_id.to_string(), quality);
+            jitter_ms: 0,
+            packet_loss: 0.0,
+        });
+        quality.signal_strength = signal_strength;
+        quality.last_updated = chrono::Utc::now();
 
         Ok(())
     }
 
-    async fn monitor_connection_quality(
-        connections: &Arc<RwLock<HashMap<String, ConnectionQuality>>>,
-        history: &Arc<RwLock<Vec<QualityMeasurement>>>,
-    ) -> MisaResult<()> {
-        let current_connections = connections.read().await;
-        let mut quality_history = history.write().await;
+    /// Measure round-trip latency to a discovery listener with a few pings.
+    /// Returns `None` when no ping was answered.
+    pub async fn probe_latency(addr: SocketAddr) -> MisaResult<Option<LatencySample>> {
+        let bind_addr: SocketAddr = if addr.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            (std::net::Ipv6Addr::UNSPECIFIED, 0).into()
+        };
+        let socket = tokio::net::UdpSocket::bind(bind_addr)
+            .await
+            .map_err(|e| MisaError::Device(format!("Failed to bind latency probe socket: {}", e)))?;
 
-        for (device_id, quality) in current_connections.iter() {
-            let measurement = QualityMeasurement {
-                device_id: device_id.clone(),
-                timestamp: chrono::Utc::now(),
-                latency_ms: quality.latency_ms,
-                packet_loss: 0.0, // Would measure actual packet loss
-                jitter_ms: 0, // Would measure actual jitter
-            };
+        let mut round_trips = Vec::new();
+        let mut buf = [0u8; 64];
+
+        for sequence in 0..LATENCY_PROBE_COUNT {
+            let nonce = format!("{}-{}", uuid::Uuid::new_v4(), sequence);
+            let mut ping = PING_PREFIX.to_vec();
+            ping.extend_from_slice(nonce.as_bytes());
+            let mut expected = PONG_PREFIX.to_vec();
+            expected.extend_from_slice(nonce.as_bytes());
+
+            let sent_at = tokio::time::Instant::now();
+            if let Err(e) = socket.send_to(&ping, addr).await {
+                debug!("Latency ping to {} failed: {}", addr, e);
+                continue;
+            }
+
+            let deadline = sent_at + LATENCY_PROBE_TIMEOUT;
+            loop {
+                match tokio::time::timeout_at(deadline, socket.recv_from(&mut buf)).await {
+                    Ok(Ok((len, from))) if from.ip() == addr.ip() && buf[..len] == expected[..] => {
+                        round_trips.push(sent_at.elapsed());
+                        break;
+                    }
+                    // Late pongs from earlier pings and stray datagrams
+                    Ok(Ok(_)) => continue,
+                    Ok(Err(e)) => {
+                        debug!("Latency probe receive from {} failed: {}", addr, e);
+                        break;
+                    }
+                    Err(_) => break,
+                }
+            }
+        }
+
+        if round_trips.is_empty() {
+            return Ok(None);
+        }
+
+        // Round up so a reachable device never reports zero latency
+        let millis: Vec<u64> = round_trips
+            .iter()
+            .map(|rtt| (rtt.as_micros() as u64 + 999) / 1000)
+            .collect();
+        let latency_ms = millis.iter().sum::<u64>() / millis.len() as u64;
+        let jitter_ms = millis.iter().max().unwrap() - millis.iter().min().unwrap();
+        let packet_loss = 1.0 - round_trips.len() as f32 / LATENCY_PROBE_COUNT as f32;
+
+        Ok(Some(LatencySample {
+            latency_ms,
+            jitter_ms,
+            packet_loss,
+        }))
+    }
 
-            quality_history.push(measurement);
+    async fn monitor_connection_quality(
+        connections: &Arc<RwLock<HashMap<String, ConnectionQuality>>>,
+        history: &Arc<RwLock<Vec<QualityMeasurement>>>,
+        probe_addrs: &Arc<RwLock<HashMap<String, SocketAddr>>>,
+    ) -> MisaResult<()> {
+        // Probe without holding any locks
+        let targets: Vec<(String, SocketAddr)> = probe_addrs
+            .read()
+            .await
+            .iter()
+            .map(|(device_id, addr)| (device_id.clone(), *addr))
+            .collect();
+
+        // Probe every device at once so one silent device does not delay the rest
+        let probes = targets.into_iter().map(|(device_id, addr)| async move {
+            (Self::probe_latency(addr).await, device_id)
+        });
 
-            // Keep only last 100 measurements per device
-            if quality_history.len() > 1000 {
-                quality_history.drain(0..quality_history.len() - 1000);
+        for (probe, device_id) in futures_util::future::join_all(probes).await {
+            match probe {
+                Ok(sample) => Self::record_sample(connections, history, &device_id, sample).await,
+                // A probe that could not be sent says nothing about the device
+                Err(e) => warn!("Skipping latency probe for device {}: {}", device_id, e),
             }
         }
 
         Ok(())
     }
 
+    /// Record a probe round for a device, `None` meaning no ping was answered,
+    /// and recompute its jitter, packet loss, uptime and stability
+    pub async fn record_probe(&self, device_id: &str, sample: Option<LatencySample>) {
+        Self::record_sample(&self.active_connections, &self.quality_history, device_id, sample).await
+    }
+
+    async fn record_sample(
+        connections: &Arc<RwLock<HashMap<String, ConnectionQuality>>>,
+        history: &Arc<RwLock<Vec<QualityMeasurement>>>,
+        device_id: &str,
+        sample: Option<LatencySample>,
+    ) {
+        let sample = sample.unwrap_or(LatencySample {
+            latency_ms: LATENCY_PROBE_TIMEOUT.as_millis() as u64,
+            jitter_ms: 0,
+            packet_loss: 1.0,
+        });
+
+        let mut quality_history = history.write().await;
+        quality_history.push(QualityMeasurement {
+            device_id: device_id.to_string(),
+            timestamp: chrono::Utc::now(),
+            latency_ms: sample.latency_ms,
+            packet_loss: sample.packet_loss,
+            jitter_ms: sample.jitter_ms,
+        });
+
+        // Bound the shared history
+        if quality_history.len() > 1000 {
+            let excess = quality_history.len() - 1000;
+            quality_history.drain(0..excess);
+        }
+
+        let recent: Vec<&QualityMeasurement> = quality_history
+            .iter()
+            .rev()
+            .filter(|measurement| measurement.device_id == device_id)
+            .take(QUALITY_WINDOW)
+            .collect();
+        let answered: Vec<u64> = recent
+            .iter()
+            .filter(|measurement| measurement.packet_loss < 1.0)
+            .map(|measurement| measurement.latency_ms)
+            .collect();
+
+        // A single answered round only has the spread of its own pings to go on
+        let jitter_ms = if answered.len() >= 2 { latency_std_dev(&answered) } else { sample.jitter_ms };
+        let packet_loss = recent.iter().map(|measurement| measurement.packet_loss).sum::<f32>() / recent.len() as f32;
+        let uptime_percentage = answered.len() as f32 / recent.len() as f32 * 100.0;
+        drop(quality_history);
+
+        if let Some(quality) = connections.write().await.get_mut(device_id) {
+            quality.latency_ms = sample.latency_ms;
+            quality.jitter_ms = jitter_ms;
+            quality.packet_loss = packet_loss;
+            quality.uptime_percentage = uptime_percentage;
+            quality.stability_score = stability_score(jitter_ms, packet_loss);
+            quality.last_updated = chrono::Utc::now();
+        }
+    }
+}
+
+/// Standard deviation of latencies, rounded to whole milliseconds
+fn latency_std_dev(latencies: &[u64]) -> u64 {
+    let mean = latencies.iter().sum::<u64>() as f64 / latencies.len() as f64;
+    let variance = latencies
+        .iter()
+        .map(|&latency| (latency as f64 - mean).powi(2))
+        .sum::<f64>()
+        / latencies.len() as f64;
+    variance.sqrt().round() as u64
+}
+
+/// Stability in `[0, 1]`: the delivered share of pings divided by `1 + jitter / STABILITY_HALF_JITTER_MS`,
+/// so it is halved at `STABILITY_HALF_JITTER_MS` of jitter, a third at twice that, and so on
+fn stability_score(jitter_ms: u64, packet_loss: f32) -> f32 {
+    let delivered = (1.0 - packet_loss).clamp(0.0, 1.0);
+    delivered / (1.0 + jitter_ms as f32 / STABILITY_HALF_JITTER_MS)
+}
+
+/// Pong for a latency ping, or `None` if the datagram is not a ping
+fn ping_reply(data: &[u8]) -> Option<Vec<u8>> {
+    let nonce = data.strip_prefix(PING_PREFIX)?;
+    let mut pong = PONG_PREFIX.to_vec();
+    pong.extend_from_slice(nonce);
+    Some(pong)
+}
+
 impl RemoteDesktopManager {
-    pub fn new(enabled: bool) -> Self {
+    pub fn new(
+        enabled: bool,
+        device_id: &str,
+        file_transfer_manager: FileTransferManager,
+        connection_quality: Arc<RwLock<HashMap<String, ConnectionQuality>>>,
+    ) -> Self {
+        let (events, _) = broadcast::channel(64);
+
         Self {
             enabled,
+            device_id: device_id.to_string(),
             active_sessions: Arc::new(RwLock::new(HashMap::new())),
+            hosted_sessions: Arc::new(RwLock::new(HashMap::new())),
+            capture_streams: Arc::new(RwLock::new(HashMap::new())),
+            connection_quality,
             screen_capturer: ScreenCapturer::new(),
-            file_transfer_manager: FileTransferManager::new(),
+            file_transfer_manager,
+            events,
+            quality_check_interval: QUALITY_CHECK_INTERVAL,
+            capabilities: RemoteDesktopCapabilities::default(),
+            input_injector: Arc::new(SystemInput::new()),
         }
     }
 
+    /// Override how often capture streams adapt to link quality
+    pub fn with_quality_check_interval(mut self, interval: Duration) -> Self {
+        self.quality_check_interval = interval;
+        self
+    }
+
+    /// Subscribe to session lifecycle events
+    pub fn subscribe_events(&self) -> broadcast::Receiver<RemoteDesktopEvent> {
+        self.events.subscribe()
+    }
+
+    /// Override what this device offers during the remote desktop handshake
+    pub fn with_capabilities(mut self, capabilities: RemoteDesktopCapabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Check consent before sharing the screen and audit every capture
+    pub fn with_privacy_controls(mut self, privacy_controls: PrivacyControls, security_manager: SecurityManager) -> Self {
+        self.screen_capturer = self.screen_capturer.with_privacy_controls(privacy_controls, security_manager);
+        self
+    }
+
+    /// Replace how remote mouse and keyboard input is replayed
+    pub fn with_input_injector(mut self, injector: Arc<dyn InputInjector>) -> Self {
+        self.input_injector = injector;
+        self
+    }
+
+    /// Start a session for `user_id` after negotiating settings with the host's `remote_capabilities`,
+    /// streaming `display_id` or the primary display
+    #[instrument(skip_all, fields(request_id, target_device_id = %target_device_id))]
     pub async fn start_session(
         &self,
+        user_id: &str,
+        target_device_id: &str,
+        remote_capabilities: &RemoteDesktopCapabilities,
+        permissions: RemoteDesktopPermissions,
+        display_id: Option<u32>,
+    ) -> MisaResult<String> {
+        let session_id = uuid::Uuid::new_v4().to_string();
+        self.open_session(session_id, user_id, target_device_id, remote_capabilities, permissions, display_id).await
+    }
+
+    /// Start the session `session_id`, whose id the host already learned during the handshake
+    async fn open_session(
+        &self,
+        session_id: String,
+        user_id: &str,
         target_device_id: &str,
+        remote_capabilities: &RemoteDesktopCapabilities,
         permissions: RemoteDesktopPermissions,
+        display_id: Option<u32>,
     ) -> MisaResult<String> {
+        let request_id = correlation::enter_request();
         if !self.enabled {
             return Err(MisaError::Device("Remote desktop disabled".to_string()));
         }
 
-        let session_id = uuid::Uuid::new_v4().to_string();
+        let settings = self.capabilities.negotiate(remote_capabilities)?;
+        debug!("Negotiated remote desktop settings with {}: {:?}", target_device_id, settings);
+
+        let quality = VideoQuality::for_bitrate(DEFAULT_TARGET_BITRATE_KBPS);
         let session = RemoteDesktopSession {
             session_id: session_id.clone(),
             host_device_id: target_device_id.to_string(),
-            client_device_id: "local".to_string(), // Would be actual device ID
-            protocol: RemoteDesktopProtocol::WebRTC,
-            resolution: (1920, 1080),
-            quality: VideoQuality::High,
+            client_device_id: self.device_id.clone(),
+            resolution: settings.fit_resolution(quality.resolution()),
+            protocol: settings.protocol,
+            codec: settings.codec,
+            max_resolution: settings.max_resolution,
+            quality,
             permissions,
             started_at: chrono::Utc::now(),
             screen_recording: false,
+            display_id,
         };
+        let view_screen = session.permissions.view_screen;
 
         let mut sessions = self.active_sessions.write().await;
         sessions.insert(session_id.clone(), session);
+        drop(sessions);
+
+        if view_screen {
+            if let Err(e) = self.start_capture(user_id, &session_id, target_device_id, display_id).await {
+                self.active_sessions.write().await.remove(&session_id);
+                return Err(e);
+            }
+        }
+
+        // Having no subscribers right now is not an error
+        let _ = self.events.send(RemoteDesktopEvent::SessionStarted {
+            session_id: session_id.clone(),
+            host_device_id: target_device_id.to_string(),
+            request_id: Some(request_id),
+        });
 
         info!("Started remote desktop session: {}", session_id);
         Ok(session_id)
     }
 
+    /// Stop a single session and its screen capture
+    #[instrument(skip_all, fields(request_id, session_id = %session_id))]
+    pub async fn stop_session(&self, session_id: &str) -> MisaResult<()> {
+        let request_id = correlation::enter_request();
+        let removed = self.active_sessions.write().await.remove(session_id);
+        if removed.is_none() {
+            return Err(MisaError::NotFound(format!("Remote desktop session not found: {}", session_id)));
+        }
+
+        self.stop_capture(session_id).await;
+
+        let _ = self.events.send(RemoteDesktopEvent::SessionStopped {
+            session_id: session_id.to_string(),
+            request_id: Some(request_id),
+        });
+
+        info!("Stopped remote desktop session: {}", session_id);
+        Ok(())
+    }
+
+    /// Currently active sessions, oldest first
+    pub async fn list_sessions(&self) -> Vec<RemoteDesktopSession> {
+        let sessions = self.active_sessions.read().await;
+        let mut sessions: Vec<RemoteDesktopSession> = sessions.values().cloned().collect();
+        sessions.sort_by_key(|session| session.started_at);
+        sessions
+    }
+
+    /// Change what the remote side may do without restarting the session
+    #[instrument(skip_all, fields(request_id, session_id = %session_id))]
+    pub async fn update_permissions(
+        &self,
+        user_id: &str,
+        session_id: &str,
+        permissions: RemoteDesktopPermissions,
+    ) -> MisaResult<()> {
+        let request_id = correlation::enter_request();
+        let mut sessions = self.active_sessions.write().await;
+        let session = sessions
+            .get_mut(session_id)
+            .ok_or_else(|| MisaError::NotFound(format!("Remote desktop session not found: {}", session_id)))?;
+
+        let was_viewing = session.permissions.view_screen;
+        let host_device_id = session.host_device_id.clone();
+        let display_id = session.display_id;
+        session.permissions = permissions.clone();
+        drop(sessions);
+
+        match (was_viewing, permissions.view_screen) {
+            (false, true) => self.start_capture(user_id, session_id, &host_device_id, display_id).await?,
+            (true, false) => self.stop_capture(session_id).await,
+            _ => {}
+        }
+
+        let _ = self.events.send(RemoteDesktopEvent::PermissionsUpdated {
+            session_id: session_id.to_string(),
+            permissions,
+            request_id: Some(request_id),
+        });
+
+        info!("Updated permissions for remote desktop session: {}", session_id);
+        Ok(())
+    }
+
+    /// Let `controller_device_id` control this device in session `session_id` with `permissions`
+    pub async fn host_session(
+        &self,
+        session_id: &str,
+        controller_device_id: &str,
+        permissions: RemoteDesktopPermissions,
+    ) -> MisaResult<()> {
+        let mut hosted = self.hosted_sessions.write().await;
+        if let Some(existing) = hosted.get(session_id) {
+            if existing.controller_device_id != controller_device_id {
+                return Err(MisaError::Permission(format!(
+                    "Remote desktop session {} belongs to another device",
+                    session_id
+                )));
+            }
+        }
+        hosted.insert(
+            session_id.to_string(),
+            HostedSession {
+                controller_device_id: controller_device_id.to_string(),
+                permissions,
+            },
+        );
+
+        info!("Hosting remote desktop session {} for {}", session_id, controller_device_id);
+        Ok(())
+    }
+
+    /// End a session hosted here at the request of the device controlling it
+    pub async fn end_hosted_session(&self, session_id: &str, controller_device_id: &str) -> MisaResult<()> {
+        let mut hosted = self.hosted_sessions.write().await;
+        match hosted.get(session_id) {
+            None => return Err(MisaError::NotFound(format!("Remote desktop session not found: {}", session_id))),
+            Some(session) if session.controller_device_id != controller_device_id => {
+                return Err(MisaError::Permission(format!(
+                    "{} does not control remote desktop session {}",
+                    controller_device_id, session_id
+                )));
+            }
+            Some(_) => {}
+        }
+        hosted.remove(session_id);
+
+        info!("{} ended remote desktop session {}", controller_device_id, session_id);
+        Ok(())
+    }
+
+    /// Forget every session `controller_device_id` controls on this device
+    pub async fn end_hosted_sessions(&self, controller_device_id: &str) {
+        self.hosted_sessions
+            .write()
+            .await
+            .retain(|_, session| session.controller_device_id != controller_device_id);
+    }
+
+    /// Replay input on this device when it hosts the command's session, `controller_device_id`
+    /// is the device controlling it and the session allows that kind of control
+    #[instrument(skip_all, fields(request_id, session_id = %command.session_id))]
+    pub async fn inject_input(&self, controller_device_id: &str, command: &RemoteInputCommand) -> MisaResult<()> {
+        correlation::enter_request();
+        let sessions = self.hosted_sessions.read().await;
+        let session = sessions
+            .get(&command.session_id)
+            .ok_or_else(|| MisaError::NotFound(format!("Remote desktop session not found: {}", command.session_id)))?;
+
+        if session.controller_device_id != controller_device_id {
+            return Err(MisaError::Permission(format!(
+                "{} does not control remote desktop session {}",
+                controller_device_id, command.session_id
+            )));
+        }
+
+        let allowed = if command.input.is_keyboard() {
+            session.permissions.control_keyboard
+        } else {
+            session.permissions.control_mouse
+        };
+        drop(sessions);
+
+        if !allowed {
+            return Err(MisaError::Permission(format!(
+                "Session {} does not allow {} control",
+                command.session_id,
+                if command.input.is_keyboard() { "keyboard" } else { "mouse" }
+            )));
+        }
+
+        // Injection blocks on the platform input APIs
+        let injector = Arc::clone(&self.input_injector);
+        let input = command.input.clone();
+        tokio::task::spawn_blocking(move || injector.inject(&input))
+            .await
+            .map_err(|e| MisaError::Device(format!("Input injection task failed: {}", e)))?
+    }
+
     pub async fn shutdown(&self) -> MisaResult<()> {
         info!("Shutting down remote desktop manager");
 
         // Close all sessions
-        let mut sessions = self.active_sessions.write().await;
-        sessions.clear();
+        let session_ids: Vec<String> = self.active_sessions.read().await.keys().cloned().collect();
+        for session_id in session_ids {
+            if let Err(e) = self.stop_session(&session_id).await {
+                debug!("Session {} already stopped: {}", session_id, e);
+            }
+        }
+
+        Ok(())
+    }
 
+    async fn start_capture(
+        &self,
+        user_id: &str,
+        session_id: &str,
+        host_device_id: &str,
+        display_id: Option<u32>,
+    ) -> MisaResult<()> {
+        let stream = self
+            .screen_capturer
+            .start_capture(user_id, session_id.to_string(), display_id, DEFAULT_TARGET_BITRATE_KBPS)
+            .await?;
+        self.capture_streams.write().await.insert(session_id.to_string(), stream.clone());
+        self.spawn_quality_control(stream, host_device_id.to_string());
         Ok(())
     }
+
+    /// Adapt a stream to its link until the stream stops, mirroring changes onto the session
+    fn spawn_quality_control(&self, stream: ScreenCaptureStream, host_device_id: String) {
+        let connection_quality = Arc::clone(&self.connection_quality);
+        let active_sessions = Arc::clone(&self.active_sessions);
+        let events = self.events.clone();
+        let interval = self.quality_check_interval;
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            while stream.is_active() {
+                ticker.tick().await;
+
+                let link = match connection_quality.read().await.get(&host_device_id) {
+                    Some(link) => link.clone(),
+                    None => continue,
+                };
+
+                if let Some(quality) = stream.adapt(&link) {
+                    if let Some(session) = active_sessions.write().await.get_mut(&stream.session_id) {
+                        let (max_width, max_height) = session.max_resolution;
+                        let (width, height) = quality.resolution();
+                        session.quality = quality;
+                        session.resolution = (width.min(max_width), height.min(max_height));
+                    }
+
+                    let _ = events.send(RemoteDesktopEvent::QualityChanged {
+                        session_id: stream.session_id.clone(),
+                        quality,
+                    });
+                }
+            }
+        });
+    }
+
+    async fn stop_capture(&self, session_id: &str) {
+        if let Some(stream) = self.capture_streams.write().await.remove(session_id) {
+            stream.stop();
+        }
+    }
 }
 
 impl ScreenCapturer {
@@ -1317,97 +4818,396 @@ impl ScreenCapturer {
         Self {
             capture_interval_ms: 100, // 10 FPS
             compression_enabled: true,
-            supported_formats: vec![ImageFormat::JPEG, ImageFormat::PNG, ImageFormat::H264],
+            supported_formats: vec![ImageFormat::PNG, ImageFormat::JPEG, ImageFormat::WebP],
+            capture_policy: None,
+        }
+    }
+
+    /// Only capture for users who consented while the screen capture source is enabled
+    pub fn with_privacy_controls(mut self, privacy_controls: PrivacyControls, security_manager: SecurityManager) -> Self {
+        self.capture_policy = Some(CapturePolicy {
+            privacy_controls,
+            security_manager,
+        });
+        self
+    }
+
+    /// Refuse captures the user has not allowed; allowed and refused attempts are both audited
+    pub(crate) async fn authorize(&self, user_id: &str, resource: &str) -> MisaResult<()> {
+        let policy = self.capture_policy.as_ref().ok_or_else(|| {
+            MisaError::from(PrivacyError::ConsentRequired {
+                action: SCREEN_CAPTURE_SOURCE.to_string(),
+            })
+        })?;
+
+        let decision = policy.privacy_controls.check_screen_capture(user_id).await;
+        let (result, reason) = match &decision {
+            Ok(()) => (AuditResult::Success, None),
+            Err(e) => (AuditResult::Failure, Some(e.to_string())),
+        };
+        policy
+            .security_manager
+            .log_security_event(
+                Some(user_id),
+                "screen_capture",
+                resource,
+                result,
+                serde_json::json!({ "reason": reason }),
+            )
+            .await?;
+
+        decision
+    }
+
+    /// Every connected display; empty when displays can't be enumerated, e.g. when headless
+    pub fn list_displays() -> Vec<DisplayInfo> {
+        match enumerate_displays() {
+            Ok(displays) => displays,
+            Err(e) => {
+                warn!("Failed to list displays: {}", e);
+                Vec::new()
+            }
         }
     }
 
-    /// Start screen capture for remote desktop
-    pub async fn start_capture(&self, session_id: String) -> MisaResult<ScreenCaptureStream> {
-        debug!("Starting screen capture for session: {}", session_id);
+    /// `list_displays` off the async runtime, since enumerating monitors blocks
+    pub async fn available_displays() -> MisaResult<Vec<DisplayInfo>> {
+        tokio::task::spawn_blocking(Self::list_displays)
+            .await
+            .map_err(|e| MisaError::RemoteDesktop(format!("Display enumeration task failed: {}", e)))
+    }
+
+    /// Start screen capture for remote desktop, never exceeding `target_bitrate_kbps`.
+    /// Captures the primary display unless `display_id` names another one.
+    pub async fn start_capture(
+        &self,
+        user_id: &str,
+        session_id: String,
+        display_id: Option<u32>,
+        target_bitrate_kbps: u32,
+    ) -> MisaResult<ScreenCaptureStream> {
+        self.authorize(user_id, &session_id).await?;
+        let displays = Self::available_displays().await?;
+        let display_id = match display_id {
+            Some(id) if !displays.iter().any(|display| display.id == id) => {
+                return Err(MisaError::RemoteDesktop(format!("Display {} not found", id)));
+            }
+            Some(id) => Some(id),
+            None => default_display(&displays).map(|display| display.id),
+        };
+        debug!("Starting screen capture of display {:?} for session: {}", display_id, session_id);
 
         // In a real implementation, this would:
         // - Use platform-specific screen capture APIs (Windows Desktop Duplication, macOS ScreenCaptureKit, Linux X11/Wayland)
         // - Set up video encoding pipeline
         // - Create streaming endpoints
 
+        info!("Screen capture started for session: {}", session_id);
+
         let capture_stream = ScreenCaptureStream {
             session_id,
+            display_id,
             format: ImageFormat::H264,
-            resolution: (1920, 1080),
-            frame_rate: 30,
+            target_bitrate_kbps,
             started_at: chrono::Utc::now(),
+            quality: Arc::new(std::sync::Mutex::new(VideoQuality::for_bitrate(target_bitrate_kbps))),
+            active: Arc::new(std::sync::atomic::AtomicBool::new(true)),
         };
 
-        info!("Screen capture started for session: {}", session_id);
         Ok(capture_stream)
     }
 
-    /// Capture single frame
-    pub async fn capture_frame(&self, format: ImageFormat) -> MisaResult<Vec<u8>> {
-        // In a real implementation, this would:
-        // - Capture screen using platform APIs
-        // - Encode to requested format
-        // - Return frame data
+    /// Capture a display, the primary one unless `display_id` is given, as a single encoded frame
+    pub async fn capture_frame(&self, user_id: &str, display_id: Option<u32>, format: ImageFormat) -> MisaResult<CapturedFrame> {
+        self.authorize(user_id, "frame").await?;
+        debug!("Capturing frame of display {:?} in format: {:?}", display_id, format);
 
-        debug!("Capturing screen frame in format: {:?}", format);
+        let image = tokio::task::spawn_blocking(move || capture_display(display_id))
+            .await
+            .map_err(|e| MisaError::RemoteDesktop(format!("Screen capture task failed: {}", e)))??;
 
-        // Simulate frame capture (would be actual screen data)
-        let frame_data = vec![0u8; 1024 * 768 * 3]; // RGB frame data placeholder
+        encode_frame(image, format, self.compression_enabled)
+    }
+}
 
-        match format {
-            ImageFormat::JPEG => {
-                // Simulate JPEG encoding
-                Ok(frame_data)
-            }
-            ImageFormat::PNG => {
-                // Simulate PNG encoding
-                Ok(frame_data)
-            }
-            ImageFormat::H264 => {
-                // Simulate H.264 encoding
-                Ok(frame_data)
-            }
-            ImageFormat::WebP => {
-                // Simulate WebP encoding
-                Ok(frame_data)
-            }
-            ImageFormat::VP9 => {
-                // Simulate VP9 encoding
-                Ok(frame_data)
-            }
+/// A single encoded screen frame
+#[derive(Debug, Clone)]
+pub struct CapturedFrame {
+    pub data: Vec<u8>,
+    pub format: ImageFormat,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// JPEG quality used when compression is enabled
+const COMPRESSED_JPEG_QUALITY: u8 = 75;
+
+/// The primary display, or the first one when none is marked primary
+fn default_display(displays: &[DisplayInfo]) -> Option<&DisplayInfo> {
+    displays
+        .iter()
+        .find(|display| display.is_primary)
+        .or_else(|| displays.first())
+}
+
+#[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+fn all_monitors() -> MisaResult<Vec<xcap::Monitor>> {
+    xcap::Monitor::all().map_err(|e| MisaError::RemoteDesktop(format!("Failed to enumerate displays: {}", e)))
+}
+
+#[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+fn enumerate_displays() -> MisaResult<Vec<DisplayInfo>> {
+    Ok(all_monitors()?
+        .iter()
+        .map(|monitor| DisplayInfo {
+            id: monitor.id(),
+            name: monitor.name().to_string(),
+            bounds: DisplayBounds {
+                x: monitor.x(),
+                y: monitor.y(),
+                width: monitor.width(),
+                height: monitor.height(),
+            },
+            scale_factor: monitor.scale_factor(),
+            is_primary: monitor.is_primary(),
+        })
+        .collect())
+}
+
+#[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+fn capture_display(display_id: Option<u32>) -> MisaResult<image::RgbaImage> {
+    let monitors = all_monitors()?;
+
+    let monitor = match display_id {
+        Some(id) => monitors
+            .iter()
+            .find(|monitor| monitor.id() == id)
+            .ok_or_else(|| MisaError::RemoteDesktop(format!("Display {} not found", id)))?,
+        None => monitors
+            .iter()
+            .find(|monitor| monitor.is_primary())
+            .or_else(|| monitors.first())
+            .ok_or_else(|| MisaError::RemoteDesktop("No display available to capture".to_string()))?,
+    };
+
+    monitor
+        .capture_image()
+        .map_err(|e| MisaError::RemoteDesktop(format!("Failed to capture display: {}", e)))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn enumerate_displays() -> MisaResult<Vec<DisplayInfo>> {
+    Err(MisaError::RemoteDesktop(
+        "Screen capture is not supported on this platform".to_string(),
+    ))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn capture_display(_display_id: Option<u32>) -> MisaResult<image::RgbaImage> {
+    Err(MisaError::RemoteDesktop(
+        "Screen capture is not supported on this platform".to_string(),
+    ))
+}
+
+/// Encode a captured frame as a still image
+fn encode_frame(image: image::RgbaImage, format: ImageFormat, compress: bool) -> MisaResult<CapturedFrame> {
+    let (width, height) = image.dimensions();
+    let mut data = Vec::new();
+    let mut cursor = std::io::Cursor::new(&mut data);
+    let image = image::DynamicImage::ImageRgba8(image);
+
+    let result = match format {
+        ImageFormat::PNG => image.write_to(&mut cursor, image::ImageOutputFormat::Png),
+        ImageFormat::JPEG => {
+            // JPEG has no alpha channel
+            let quality = if compress { COMPRESSED_JPEG_QUALITY } else { 100 };
+            image::DynamicImage::ImageRgb8(image.to_rgb8())
+                .write_to(&mut cursor, image::ImageOutputFormat::Jpeg(quality))
         }
-    }
+        ImageFormat::WebP => image::codecs::webp::WebPEncoder::new_lossless(&mut cursor)
+            .encode(image.as_bytes(), width, height, image::ColorType::Rgba8),
+        ImageFormat::H264 | ImageFormat::VP9 => {
+            return Err(MisaError::RemoteDesktop(format!(
+                "{:?} is a video codec and cannot encode a single frame",
+                format
+            )));
+        }
+    };
+
+    result.map_err(|e| MisaError::RemoteDesktop(format!("Failed to encode {:?} frame: {}", format, e)))?;
+
+    Ok(CapturedFrame {
+        data,
+        format,
+        width,
+        height,
+    })
 }
 
 /// Screen capture stream for remote desktop
 #[derive(Debug, Clone)]
 pub struct ScreenCaptureStream {
     pub session_id: String,
+    /// Display being streamed; unset when no display could be found
+    pub display_id: Option<u32>,
     pub format: ImageFormat,
-    pub resolution: (u32, u32),
-    pub frame_rate: u32,
+    pub target_bitrate_kbps: u32,
     pub started_at: chrono::DateTime<chrono::Utc>,
+    quality: Arc<std::sync::Mutex<VideoQuality>>,
+    active: Arc<std::sync::atomic::AtomicBool>,
+}
+
+impl ScreenCaptureStream {
+    pub fn quality(&self) -> VideoQuality {
+        *self.quality.lock().unwrap()
+    }
+
+    /// Current capture resolution
+    pub fn resolution(&self) -> (u32, u32) {
+        self.quality().resolution()
+    }
+
+    /// Current capture frame rate
+    pub fn frame_rate(&self) -> u32 {
+        self.quality().frame_rate()
+    }
+
+    /// Re-pick quality for the current link, returning the new quality if it changed
+    pub fn adapt(&self, link: &ConnectionQuality) -> Option<VideoQuality> {
+        let next = VideoQuality::for_link(link, self.target_bitrate_kbps);
+        let mut quality = self.quality.lock().unwrap();
+        if *quality == next {
+            return None;
+        }
+
+        debug!("Capture for session {} switching from {:?} to {:?}", self.session_id, *quality, next);
+        *quality = next;
+        Some(next)
+    }
+
+    /// Stop producing frames; shared by every clone of the stream
+    pub fn stop(&self) {
+        if self.active.swap(false, std::sync::atomic::Ordering::SeqCst) {
+            debug!("Screen capture stopped for session: {}", self.session_id);
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.load(std::sync::atomic::Ordering::SeqCst)
+    }
 }
 
 impl FileTransferManager {
-    pub fn new() -> Self {
+    pub fn new(
+        config: &crate::kernel::FileTransferConfig,
+        device_id: &str,
+        security_manager: SecurityManager,
+        connections: Arc<RwLock<HashMap<String, DeviceConnection>>>,
+    ) -> Self {
         Self {
-            max_file_size_mb: 1024,
-            allowed_file_types: vec!["*".to_string()], // All types
-            encryption_required: true,
+            device_id: device_id.to_string(),
+            max_file_size_mb: config.max_file_size_mb,
+            allowed_file_types: config.allowed_types.clone(),
+            encryption_required: config.encryption_required,
+            download_dir: PathBuf::from(&config.download_dir),
+            security_manager,
+            connections,
             active_transfers: Arc::new(RwLock::new(HashMap::new())),
+            transfer_controls: Arc::new(RwLock::new(HashMap::new())),
+            incoming_transfers: Arc::new(RwLock::new(HashMap::new())),
+            redeemed_grants: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
-    pub async fn start_transfer(&self, target_device_id: &str, file_path: &str) -> MisaResult<String> {
-        let transfer_id = uuid::Uuid::new_v4().to_string();
+    /// Sign a short-lived grant letting `source_device_id` send the requested file
+    pub async fn issue_grant(&self, source_device_id: &str, request: &FileTransferRequest) -> MisaResult<TransferGrant> {
+        if request.file_size / (1024 * 1024) > self.max_file_size_mb {
+            return Err(MisaError::Device(format!("Incoming file too large: {} bytes", request.file_size)));
+        }
 
-        let metadata = std::fs::metadata(file_path)
+        let mut grant = TransferGrant {
+            transfer_id: uuid::Uuid::new_v4().to_string(),
+            source_device_id: source_device_id.to_string(),
+            file_name: request.file_name.clone(),
+            file_size: request.file_size,
+            // The sender may ask for encryption, but can't opt out of ours
+            encrypted: self.encryption_required || request.encrypted,
+            expires_at: chrono::Utc::now().timestamp() + TRANSFER_GRANT_TTL_SECONDS,
+            signature: String::new(),
+        };
+        let signature = self.security_manager.sign_data(&grant.signed_payload()?, TRANSFER_GRANT_PURPOSE).await?;
+        grant.signature = encode_hex(&signature);
+
+        Ok(grant)
+    }
+
+    /// Check that `grant` was issued by this device for this sender and file and is still valid
+    async fn verify_grant(&self, grant: &TransferGrant, source_device_id: &str, file_size: u64) -> MisaResult<()> {
+        if grant.is_expired() {
+            return Err(MisaError::Permission(format!("Transfer grant {} has expired", grant.transfer_id)));
+        }
+        if grant.source_device_id != source_device_id || grant.file_size != file_size {
+            return Err(MisaError::Permission(format!("Transfer grant {} does not cover this file", grant.transfer_id)));
+        }
+
+        let signature = decode_hex(&grant.signature)
+            .ok_or_else(|| MisaError::Permission("Invalid transfer grant signature".to_string()))?;
+        let valid = self
+            .security_manager
+            .verify_signature(&grant.signed_payload()?, &signature, TRANSFER_GRANT_PURPOSE)
+            .await?;
+        if !valid {
+            warn!("Rejected forged transfer grant from {}", source_device_id);
+            return Err(MisaError::Permission("Invalid transfer grant signature".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Whether `mime_type` matches an entry of the allow-list, exactly or through a wildcard
+    fn is_allowed_type(&self, mime_type: &str) -> bool {
+        self.allowed_file_types.iter().any(|allowed| {
+            if allowed == "*" {
+                return true;
+            }
+            match allowed.strip_suffix("/*") {
+                // SVG can carry script, so only an explicit entry admits it
+                Some(top_level) => mime_type.split('/').next() == Some(top_level) && mime_type != "image/svg+xml",
+                None => allowed.eq_ignore_ascii_case(mime_type),
+            }
+        })
+    }
+
+    /// Start sending a file the target granted; expired or mismatched grants are refused
+    pub async fn start_transfer(&self, target_device_id: &str, file_path: &str, grant: TransferGrant) -> MisaResult<String> {
+        if grant.is_expired() {
+            return Err(MisaError::Permission(format!("Transfer grant {} has expired", grant.transfer_id)));
+        }
+
+        let metadata = tokio::fs::metadata(file_path)
+            .await
             .map_err(|e| MisaError::Io(e))?;
+        if metadata.len() != grant.file_size {
+            return Err(MisaError::Permission(format!("Transfer grant {} does not cover this file", grant.transfer_id)));
+        }
+
+        let transfer_id = grant.transfer_id.clone();
+        if self.active_transfers.read().await.contains_key(&transfer_id) {
+            return Err(MisaError::Validation(format!("Transfer grant {} was already used", transfer_id)));
+        }
+
+        // Judge the file by its content so renaming it can't get around the allow-list
+        let mime_type = sniff_mime_type(file_path).await?;
+        if !self.is_allowed_type(&mime_type) {
+            return Err(MisaError::FileTransfer(format!(
+                "File type {} is not allowed: {}",
+                mime_type, file_path
+            )));
+        }
 
         let transfer = FileTransfer {
             transfer_id: transfer_id.clone(),
-            source_device_id: "local".to_string(),
+            source_device_id: self.device_id.clone(),
             target_device_id: target_device_id.to_string(),
             file_path: file_path.to_string(),
             file_size: metadata.len(),
@@ -1415,10 +5215,13 @@ impl FileTransferManager {
             encryption_key: None,
             status: FileTransferStatus::Pending,
             started_at: chrono::Utc::now(),
+            mime_type,
+            grant,
         };
 
         let mut transfers = self.active_transfers.write().await;
         transfers.insert(transfer_id.clone(), transfer);
+        drop(transfers);
 
         // Start the actual file transfer in background
         self.execute_file_transfer(transfer_id.clone(), file_path.to_string()).await?;
@@ -1427,74 +5230,382 @@ impl FileTransferManager {
         Ok(transfer_id)
     }
 
-    /// Execute the actual file transfer with progress tracking
+    /// Execute the actual file transfer with progress tracking, resuming from
+    /// `bytes_transferred` if the transfer was previously paused
     async fn execute_file_transfer(&self, transfer_id: String, file_path: String) -> MisaResult<()> {
         let active_transfers = Arc::clone(&self.active_transfers);
+        let transfer_controls = Arc::clone(&self.transfer_controls);
+        let connections = Arc::clone(&self.connections);
+        let security_manager = self.security_manager.clone();
         let encryption_required = self.encryption_required;
+        let device_id = self.device_id.clone();
 
-        tokio::spawn(async move {
-            // Read file in chunks and simulate transfer
-            let chunk_size = 64 * 1024; // 64KB chunks
-            let mut bytes_transferred = 0u64;
-
-            // Update status to InProgress
-            {
-                let mut transfers = active_transfers.write().await;
-                if let Some(transfer) = transfers.get_mut(&transfer_id) {
-                    transfer.status = FileTransferStatus::InProgress;
-                }
-            }
+        let (control_tx, control_rx) = watch::channel(TransferControl::Run);
+        transfer_controls.write().await.insert(transfer_id.clone(), control_tx);
 
-            // Simulate file reading and transfer
-            match std::fs::File::open(&file_path) {
-                Ok(mut file) => {
-                    let mut buffer = vec![0u8; chunk_size];
+        tokio::spawn(async move {
+            let result = Self::send_file_chunks(
+                &transfer_id,
+                &file_path,
+                &device_id,
+                &active_transfers,
+                &connections,
+                &security_manager,
+                encryption_required,
+                control_rx,
+            ).await;
+
+            let mut transfers = active_transfers.write().await;
+            if let Some(transfer) = transfers.get_mut(&transfer_id) {
+                match result {
+                    Ok(TransferOutcome::Completed) => {
+                        transfer.status = FileTransferStatus::Completed;
+                        info!("File transfer completed: {}", transfer_id);
+                    }
+                    Ok(TransferOutcome::Paused) => {
+                        transfer.status = FileTransferStatus::Paused;
+                        info!("File transfer paused at {} bytes: {}", transfer.bytes_transferred, transfer_id);
+                        // Keep the control channel around so the transfer can be resumed
+                        return;
+                    }
+                    Ok(TransferOutcome::Cancelled) => {
+                        transfer.status = FileTransferStatus::Failed("Transfer cancelled".to_string());
+                    }
+                    Err(e) => {
+                        error!("File transfer {} failed: {}", transfer_id, e);
+                        transfer.status = FileTransferStatus::Failed(e.to_string());
+                    }
+                }
+            }
+            drop(transfers);
 
-                    loop {
-                        match file.read(&mut buffer) {
-                            Ok(0) => break, // EOF
-                            Ok(bytes_read) => {
-                                bytes_transferred += bytes_read as u64;
+            transfer_controls.write().await.remove(&transfer_id);
+        });
 
-                                // Update transfer progress
-                                {
-                                    let mut transfers = active_transfers.write().await;
-                                    if let Some(transfer) = transfers.get_mut(&transfer_id) {
-                                        transfer.bytes_transferred = bytes_transferred;
-                                    }
-                                }
+        Ok(())
+    }
 
-                                // Simulate network transfer delay
-                                tokio::time::sleep(Duration::from_millis(10)).await;
-                            }
-                            Err(e) => {
-                                error!("Error reading file during transfer: {}", e);
-                                break;
-                            }
-                        }
-                    }
+    /// Pause an in-progress transfer after the current chunk
+    pub async fn pause_transfer(&self, transfer_id: &str) -> MisaResult<()> {
+        let controls = self.transfer_controls.read().await;
+        let control = controls.get(transfer_id)
+            .ok_or_else(|| MisaError::Device(format!("No active transfer: {}", transfer_id)))?;
 
-                    // Mark as completed
-                    let mut transfers = active_transfers.write().await;
-                    if let Some(transfer) = transfers.get_mut(&transfer_id) {
-                        transfer.status = FileTransferStatus::Completed;
-                    }
+        control.send_replace(TransferControl::Pause);
+        info!("Pause requested for file transfer: {}", transfer_id);
+        Ok(())
+    }
 
-                    info!("File transfer completed: {}", transfer_id);
-                }
-                Err(e) => {
-                    error!("Failed to open file for transfer: {}", e);
-                    let mut transfers = active_transfers.write().await;
-                    if let Some(transfer) = transfers.get_mut(&transfer_id) {
-                        transfer.status = FileTransferStatus::Failed(format!("Failed to open file: {}", e));
-                    }
+    /// Resume a paused transfer from where it stopped
+    pub async fn resume_transfer(&self, transfer_id: &str) -> MisaResult<()> {
+        let file_path = {
+            let mut transfers = self.active_transfers.write().await;
+            let transfer = transfers.get_mut(transfer_id)
+                .ok_or_else(|| MisaError::Device(format!("Unknown transfer: {}", transfer_id)))?;
+
+            if !matches!(transfer.status, FileTransferStatus::Paused) {
+                return Err(MisaError::Device(format!("Transfer {} is not paused", transfer_id)));
+            }
+
+            transfer.status = FileTransferStatus::Pending;
+            transfer.file_path.clone()
+        };
+
+        info!("Resuming file transfer: {}", transfer_id);
+        self.execute_file_transfer(transfer_id.to_string(), file_path).await
+    }
+
+    /// Read the file in chunks and send each one to the target device
+    async fn send_file_chunks(
+        transfer_id: &str,
+        file_path: &str,
+        device_id: &str,
+        active_transfers: &Arc<RwLock<HashMap<String, FileTransfer>>>,
+        connections: &Arc<RwLock<HashMap<String, DeviceConnection>>>,
+        security_manager: &SecurityManager,
+        encryption_required: bool,
+        control: watch::Receiver<TransferControl>,
+    ) -> MisaResult<TransferOutcome> {
+        let chunk_size = 64 * 1024; // 64KB chunks
+
+        let (target_device_id, file_size, start_offset, grant) = {
+            let mut transfers = active_transfers.write().await;
+            let transfer = transfers.get_mut(transfer_id)
+                .ok_or_else(|| MisaError::Device(format!("Unknown transfer: {}", transfer_id)))?;
+            transfer.status = FileTransferStatus::InProgress;
+            (transfer.target_device_id.clone(), transfer.file_size, transfer.bytes_transferred, transfer.grant.clone())
+        };
+        // The receiver's grant can require encryption even where ours doesn't
+        let encryption_required = encryption_required || grant.encrypted;
+
+        let connection = connections.read().await.get(&target_device_id).cloned()
+            .ok_or_else(|| MisaError::Device(format!("No connection to device: {}", target_device_id)))?;
+
+        let file_name = Path::new(file_path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| transfer_id.to_string());
+
+        // A resumed transfer was already announced to the peer
+        if start_offset == 0 {
+            let request = FileTransferRequest {
+                file_name,
+                file_size,
+                encrypted: encryption_required,
+                grant: Some(grant),
+            };
+            let message = Self::transfer_message(
+                device_id,
+                &target_device_id,
+                MessageType::FileTransferRequest,
+                serde_json::to_value(&request)?,
+            );
+            connection.send(&Self::seal(security_manager, message, encryption_required).await?).await?;
+        }
+
+        let mut file = tokio::fs::File::open(file_path)
+            .await
+            .map_err(|e| MisaError::Device(format!("Failed to open file: {}", e)))?;
+        file.seek(SeekFrom::Start(start_offset))
+            .await
+            .map_err(|e| MisaError::Device(format!("Failed to seek file: {}", e)))?;
+        let mut buffer = vec![0u8; chunk_size];
+        let mut bytes_transferred = start_offset;
+
+        loop {
+            match *control.borrow() {
+                TransferControl::Run => {}
+                TransferControl::Pause => return Ok(TransferOutcome::Paused),
+                TransferControl::Cancel => return Ok(TransferOutcome::Cancelled),
+            }
+
+            let bytes_read = file.read(&mut buffer)
+                .await
+                .map_err(|e| MisaError::Device(format!("Error reading file during transfer: {}", e)))?;
+            if bytes_read == 0 {
+                break; // EOF
+            }
+
+            let message = Self::transfer_message(
+                device_id,
+                &target_device_id,
+                MessageType::FileTransferData,
+                serde_json::json!({
+                    "transfer_id": transfer_id,
+                    "offset": bytes_transferred,
+                    "data": BASE64.encode(&buffer[..bytes_read]),
+                }),
+            );
+            connection.send(&Self::seal(security_manager, message, encryption_required).await?).await?;
+
+            bytes_transferred += bytes_read as u64;
+
+            // Update transfer progress
+            {
+                let mut transfers = active_transfers.write().await;
+                if let Some(transfer) = transfers.get_mut(transfer_id) {
+                    transfer.bytes_transferred = bytes_transferred;
                 }
             }
-        });
 
+            // Give pause/cancel requests a chance to land between chunks
+            tokio::task::yield_now().await;
+        }
+
+        Ok(TransferOutcome::Completed)
+    }
+
+    /// Unencrypted transfer message from `source_device_id`
+    fn transfer_message(
+        source_device_id: &str,
+        target_device_id: &str,
+        message_type: MessageType,
+        payload: serde_json::Value,
+    ) -> DeviceMessage {
+        DeviceMessage {
+            message_id: uuid::Uuid::new_v4().to_string(),
+            source_device_id: source_device_id.to_string(),
+            target_device_id: Some(target_device_id.to_string()),
+            message_type,
+            payload,
+            timestamp: chrono::Utc::now(),
+            encrypted: false,
+            priority: MessagePriority::Normal,
+        }
+    }
+
+    /// Seal the payload with the key paired with the target device, in the envelope the
+    /// receiving `DeviceManager` opens before the message reaches `handle_incoming`
+    async fn seal(security_manager: &SecurityManager, message: DeviceMessage, encrypt: bool) -> MisaResult<DeviceMessage> {
+        if !encrypt {
+            return Ok(message);
+        }
+
+        let target_device_id = message.target_device_id.as_deref().unwrap_or_default();
+        let plaintext = serde_json::to_vec(&message.payload)?;
+        let envelope = security_manager.encrypt_for_device(target_device_id, &plaintext).await?;
+
+        Ok(DeviceMessage {
+            payload: serde_json::json!({ "envelope": envelope }),
+            encrypted: true,
+            ..message
+        })
+    }
+
+    /// Handle an incoming transfer message, returning the file path once the file is complete
+    pub async fn handle_incoming(&self, message: &DeviceMessage) -> MisaResult<Option<PathBuf>> {
+        match message.message_type {
+            MessageType::FileTransferRequest => {
+                let request: FileTransferRequest = serde_json::from_value(message.payload.clone())?;
+                self.accept_incoming_transfer(&request, message).await?;
+                Ok(None)
+            }
+            MessageType::FileTransferData => {
+                let transfer_id = message.payload["transfer_id"].as_str()
+                    .ok_or_else(|| MisaError::Device("Transfer message missing transfer_id".to_string()))?;
+                self.write_incoming_chunk(transfer_id, message).await
+            }
+            _ => Err(MisaError::Device(format!("Not a file transfer message: {:?}", message.message_type))),
+        }
+    }
+
+    /// Start receiving a file, provided the sender presents a valid grant we issued
+    async fn accept_incoming_transfer(&self, request: &FileTransferRequest, message: &DeviceMessage) -> MisaResult<()> {
+        let grant = request.grant.as_ref()
+            .ok_or_else(|| MisaError::Permission(format!("File transfer from {} was not granted", message.source_device_id)))?;
+        self.verify_grant(grant, &message.source_device_id, request.file_size).await?;
+
+        // Whether chunks must be sealed is for the signed grant and our own setting to say,
+        // never the unsigned flag on the request
+        let encrypted = grant.encrypted || self.encryption_required;
+        if encrypted && !message.encrypted {
+            return Err(MisaError::Encryption(format!(
+                "Unencrypted request for encrypted transfer {}",
+                grant.transfer_id
+            )));
+        }
+
+        let transfer_id = grant.transfer_id.as_str();
+        let file_size = grant.file_size;
+        {
+            let mut redeemed = self.redeemed_grants.write().await;
+            let now = chrono::Utc::now().timestamp();
+            redeemed.retain(|_, expires_at| *expires_at > now);
+            if redeemed.insert(transfer_id.to_string(), grant.expires_at).is_some() {
+                return Err(MisaError::Permission(format!("Transfer grant {} was already used", transfer_id)));
+            }
+        }
+
+        // Only keep the final path component so peers can't write outside the download dir
+        let file_name = Path::new(&grant.file_name)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| transfer_id.to_string());
+
+        tokio::fs::create_dir_all(&self.download_dir).await?;
+        let file_path = self.download_dir.join(format!("{}-{}", transfer_id, file_name));
+        tokio::fs::File::create(&file_path).await?;
+
+        let incoming = IncomingTransfer {
+            transfer_id: transfer_id.to_string(),
+            source_device_id: message.source_device_id.clone(),
+            file_path,
+            file_size,
+            bytes_received: 0,
+            received_offsets: HashSet::new(),
+            encrypted,
+        };
+
+        let mut incoming_transfers = self.incoming_transfers.write().await;
+        incoming_transfers.insert(transfer_id.to_string(), incoming);
+
+        debug!("Accepted incoming file transfer: {}", transfer_id);
         Ok(())
     }
 
+    async fn write_incoming_chunk(&self, transfer_id: &str, message: &DeviceMessage) -> MisaResult<Option<PathBuf>> {
+        let incoming = self.incoming_transfers.read().await.get(transfer_id).cloned()
+            .ok_or_else(|| MisaError::Device(format!("Unknown incoming transfer: {}", transfer_id)))?;
+
+        // Transfer ids travel in the clear, so only the granted sender may write to the file
+        if message.source_device_id != incoming.source_device_id {
+            return Err(MisaError::Permission(format!(
+                "{} is not the sender of transfer {}",
+                message.source_device_id, transfer_id
+            )));
+        }
+        if incoming.encrypted && !message.encrypted {
+            return Err(MisaError::Encryption(format!("Unencrypted chunk for encrypted transfer {}", transfer_id)));
+        }
+
+        let offset = message.payload["offset"].as_u64()
+            .ok_or_else(|| MisaError::Device("Transfer chunk missing offset".to_string()))?;
+        let chunk = Self::decode_payload_field(&message.payload, "data")?;
+
+        if offset + chunk.len() as u64 > incoming.file_size {
+            return Err(MisaError::Device(format!("Chunk exceeds declared size for transfer {}", transfer_id)));
+        }
+
+        // The sender's type check can't be trusted, so the content is sniffed again before anything is written
+        if incoming.bytes_received == 0 {
+            let mime_type = if offset == 0 {
+                classify_content(&chunk[..chunk.len().min(CONTENT_SNIFF_BYTES as usize)])
+            } else {
+                "application/octet-stream"
+            };
+            if !self.is_allowed_type(mime_type) {
+                self.incoming_transfers.write().await.remove(transfer_id);
+                let _ = tokio::fs::remove_file(&incoming.file_path).await;
+                return Err(MisaError::FileTransfer(format!(
+                    "File type {} is not allowed for transfer {}",
+                    mime_type, transfer_id
+                )));
+            }
+        }
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .write(true)
+            .open(&incoming.file_path)
+            .await?;
+        file.seek(SeekFrom::Start(offset)).await?;
+        file.write_all(&chunk).await?;
+        file.flush().await?;
+
+        let bytes_received = {
+            let mut incoming_transfers = self.incoming_transfers.write().await;
+            let entry = incoming_transfers.get_mut(transfer_id)
+                .ok_or_else(|| MisaError::Device(format!("Unknown incoming transfer: {}", transfer_id)))?;
+            if entry.received_offsets.insert(offset) {
+                entry.bytes_received += chunk.len() as u64;
+            }
+            entry.bytes_received
+        };
+
+        if bytes_received < incoming.file_size {
+            return Ok(None);
+        }
+
+        self.incoming_transfers.write().await.remove(transfer_id);
+
+        let written = tokio::fs::metadata(&incoming.file_path).await?.len();
+        if written != incoming.file_size {
+            return Err(MisaError::Device(format!(
+                "Size mismatch for transfer {}: expected {} bytes, got {}",
+                transfer_id, incoming.file_size, written
+            )));
+        }
+
+        info!("Incoming file transfer completed: {}", transfer_id);
+        Ok(Some(incoming.file_path))
+    }
+
+    fn decode_payload_field(payload: &serde_json::Value, field: &str) -> MisaResult<Vec<u8>> {
+        let encoded = payload[field].as_str()
+            .ok_or_else(|| MisaError::Device(format!("Transfer chunk missing {}", field)))?;
+        BASE64.decode(encoded)
+            .map_err(|e| MisaError::Device(format!("Invalid {} encoding: {}", field, e)))
+    }
+
     /// Get transfer progress
     pub async fn get_transfer_progress(&self, transfer_id: &str) -> MisaResult<Option<FileTransfer>> {
         let transfers = self.active_transfers.read().await;
@@ -1503,6 +5614,11 @@ impl FileTransferManager {
 
     /// Cancel active transfer
     pub async fn cancel_transfer(&self, transfer_id: &str) -> MisaResult<()> {
+        // Stop the background task if it is still running
+        if let Some(control) = self.transfer_controls.write().await.remove(transfer_id) {
+            control.send_replace(TransferControl::Cancel);
+        }
+
         let mut transfers = self.active_transfers.write().await;
         if let Some(transfer) = transfers.get_mut(transfer_id) {
             transfer.status = FileTransferStatus::Failed("Transfer cancelled".to_string());
@@ -1520,14 +5636,27 @@ impl ClipboardSync {
             sync_interval_seconds: 1,
             last_clipboard_hash: Arc::new(RwLock::new(None)),
             supported_formats: vec!["text/plain".to_string(), "image/png".to_string()],
+            backend: Arc::new(SystemClipboard),
         }
     }
 
-    /// Start clipboard synchronization service
-    pub async fn start_sync(&self, device_manager: Arc<DeviceManager>) -> MisaResult<()> {
+    /// Use a different clipboard than the system one
+    pub fn with_backend(mut self, backend: Arc<dyn ClipboardBackend>) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Turn polling the local clipboard on or off
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Start clipboard synchronization service, returning the polling loop
+    pub fn start_sync(&self, device_manager: Arc<DeviceManager>) -> Option<tokio::task::JoinHandle<()>> {
         if !self.enabled {
             info!("Clipboard sync disabled");
-            return Ok(());
+            return None;
         }
 
         info!("Starting clipboard synchronization service");
@@ -1535,8 +5664,9 @@ impl ClipboardSync {
         let sync_interval = self.sync_interval_seconds;
         let last_clipboard_hash = Arc::clone(&self.last_clipboard_hash);
         let encryption_enabled = self.encryption_enabled;
+        let backend = Arc::clone(&self.backend);
 
-        tokio::spawn(async move {
+        let task = tokio::spawn(async move {
             let mut interval = tokio::time::interval(Duration::from_secs(sync_interval));
 
             loop {
@@ -1544,6 +5674,7 @@ impl ClipboardSync {
 
                 if let Err(e) = Self::check_and_sync_clipboard(
                     &device_manager,
+                    &backend,
                     &last_clipboard_hash,
                     encryption_enabled,
                 ).await {
@@ -1553,31 +5684,42 @@ impl ClipboardSync {
         });
 
         info!("Clipboard synchronization service started");
-        Ok(())
+        Some(task)
     }
 
     /// Check clipboard for changes and sync to connected devices
     async fn check_and_sync_clipboard(
         device_manager: &Arc<DeviceManager>,
+        backend: &Arc<dyn ClipboardBackend>,
         last_clipboard_hash: &Arc<RwLock<Option<String>>>,
         encryption_enabled: bool,
-    ) -> MisaResult<()> {
+    ) -> MisaResult<bool> {
         // Get current clipboard content
-        let clipboard_content = Self::get_clipboard_content().await?;
+        let clipboard_content = match Self::get_clipboard_content(backend).await? {
+            Some(content) => content,
+            None => return Ok(false),
+        };
+
+        Self::sync_clipboard_content(device_manager, last_clipboard_hash, clipboard_content, encryption_enabled).await
+    }
 
-        // Calculate hash of current content
-        let digest = compute_md5(clipboard_content.as_bytes());
-        let content_hash = format!("{:02x?}", digest);
+    /// Broadcast clipboard content if it differs from the last seen content.
+    /// Returns whether a sync message was sent.
+    async fn sync_clipboard_content(
+        device_manager: &Arc<DeviceManager>,
+        last_clipboard_hash: &Arc<RwLock<Option<String>>>,
+        clipboard_content: ClipboardContent,
+        encryption_enabled: bool,
+    ) -> MisaResult<bool> {
+        let content_hash = clipboard_content.content_hash();
 
         // Check if content has changed
         {
             let mut last_hash = last_clipboard_hash.write().await;
-            if let Some(ref hash) = *last_hash {
-                if hash == &content_hash {
-                    return Ok(()); // No change
-                }
+            if last_hash.as_deref() == Some(content_hash.as_str()) {
+                return Ok(false); // No change
             }
-            *last_hash = Some(content_hash.clone());
+            *last_hash = Some(content_hash);
         }
 
         debug!("Clipboard content changed, syncing to devices");
@@ -1585,12 +5727,12 @@ impl ClipboardSync {
         // Create clipboard sync message
         let sync_message = DeviceMessage {
             message_id: uuid::Uuid::new_v4().to_string(),
-            source_device_id: "local".to_string(),
+            source_device_id: device_manager.device_id().to_string(),
             target_device_id: None, // Broadcast to all
             message_type: MessageType::ClipboardSync,
             payload: serde_json::json!({
-                "content": clipboard_content,
-                "format": "text/plain",
+                "content": clipboard_content.to_payload_content()?,
+                "format": clipboard_content.format(),
                 "timestamp": chrono::Utc::now(),
                 "encrypted": encryption_enabled
             }),
@@ -1602,48 +5744,67 @@ impl ClipboardSync {
         // Broadcast to all connected devices
         device_manager.send_message(sync_message).await?;
 
-        Ok(())
+        Ok(true)
     }
 
     /// Get current clipboard content (platform-specific)
-    async fn get_clipboard_content() -> MisaResult<String> {
-        // In a real implementation, this would use platform-specific clipboard APIs:
-        // - Windows: Windows API
-        // - macOS: NSPasteboard
-        // - Linux: X11 clipboard or Wayland clipboard
-
-        // For now, simulate clipboard content
-        Ok("Sample clipboard content".to_string())
+    async fn get_clipboard_content(backend: &Arc<dyn ClipboardBackend>) -> MisaResult<Option<ClipboardContent>> {
+        // Platform clipboard APIs block
+        let backend = Arc::clone(backend);
+        tokio::task::spawn_blocking(move || backend.read())
+            .await
+            .map_err(|e| MisaError::Device(format!("Clipboard read task failed: {}", e)))?
     }
 
-    /// Set clipboard content (platform-specific)
-    pub async fn set_clipboard_content(&self, content: &str, source_device_id: &str) -> MisaResult<()> {
-        info!("Setting clipboard content from device: {}", source_device_id);
+    /// Set clipboard content received from another device
+    pub async fn set_clipboard_content(&self, content: &ClipboardContent, source_device_id: &str) -> MisaResult<()> {
+        info!("Setting {} clipboard content from device: {}", content.format(), source_device_id);
 
-        // In a real implementation, this would use platform-specific clipboard APIs
-        debug!("Setting clipboard: {}", content);
+        // Update last clipboard hash first so the next poll does not echo it back
+        *self.last_clipboard_hash.write().await = Some(content.content_hash());
 
-        // Update last clipboard hash to prevent sync loop
-        let digest = compute_md5(content.as_bytes());
-        let content_hash = format!("{:02x?}", digest);
-        let mut last_hash = self.last_clipboard_hash.write().await;
-        *last_hash = Some(content_hash);
+        let backend = Arc::clone(&self.backend);
+        let content = content.clone();
+        tokio::task::spawn_blocking(move || backend.write(&content))
+            .await
+            .map_err(|e| MisaError::Device(format!("Clipboard write task failed: {}", e)))?
+    }
 
-        Ok(())
+    /// Apply a `ClipboardSync` message from another device
+    pub async fn handle_sync_message(&self, message: &DeviceMessage) -> MisaResult<()> {
+        let content = ClipboardContent::from_payload(&message.payload)?;
+        self.set_clipboard_content(&content, &message.source_device_id).await
     }
 }
 
-// Implement Clone for Arc-wrapped structs
 impl Clone for DeviceManager {
     fn clone(&self) -> Self {
         Self {
             config: self.config.clone(),
+            device_id: self.device_id.clone(),
             security_manager: self.security_manager.clone(),
             devices: Arc::clone(&self.devices),
             active_connections: Arc::clone(&self.active_connections),
-            discovery_service: DiscoveryService::new(self.config.discovery_enabled),
-            remote_desktop_manager: RemoteDesktopManager::new(self.config.remote_desktop_enabled),
-            clipboard_sync: ClipboardSync::new(true),
+            connection_quality: Arc::clone(&self.connection_quality),
+            discovery_service: Arc::clone(&self.discovery_service),
+            remote_desktop_manager: self.remote_desktop_manager.clone(),
+            clipboard_sync: self.clipboard_sync.clone(),
+            pending_requests: Arc::clone(&self.pending_requests),
+            pending_pairings: Arc::clone(&self.pending_pairings),
+            inbound_limits: Arc::clone(&self.inbound_limits),
+            groups: Arc::clone(&self.groups),
+            groups_path: self.groups_path.clone(),
+            dead_letters: Arc::clone(&self.dead_letters),
+            transfer_approver: self.transfer_approver.clone(),
+            task_handler: self.task_handler.clone(),
+            remote_desktop_approver: self.remote_desktop_approver.clone(),
+            connector: Arc::clone(&self.connector),
+            reconnecting: Arc::clone(&self.reconnecting),
+            connection_events: self.connection_events.clone(),
+            heartbeat_task: Arc::clone(&self.heartbeat_task),
+            delegated_tasks: Arc::clone(&self.delegated_tasks),
+            clipboard_task: Arc::clone(&self.clipboard_task),
+            metrics: self.metrics.clone(),
         }
     }
 }
@@ -1652,9 +5813,17 @@ impl Clone for DiscoveryService {
     fn clone(&self) -> Self {
         Self {
             enabled: self.enabled,
+            transport: self.transport,
+            device_id: self.device_id.clone(),
+            mdns: Arc::clone(&self.mdns),
             discovery_port: self.discovery_port,
             broadcast_interval_seconds: self.broadcast_interval_seconds,
             active_discovery: Arc::clone(&self.active_discovery),
+            background_scanning: self.background_scanning,
+            smart_suggestions: self.smart_suggestions,
+            last_scan: Arc::clone(&self.last_scan),
+            device_history: Arc::clone(&self.device_history),
+            connection_quality_monitor: self.connection_quality_monitor.clone(),
         }
     }
 }
@@ -1663,9 +5832,28 @@ impl Clone for RemoteDesktopManager {
     fn clone(&self) -> Self {
         Self {
             enabled: self.enabled,
+            device_id: self.device_id.clone(),
             active_sessions: Arc::clone(&self.active_sessions),
-            screen_capturer: ScreenCapturer::new(),
-            file_transfer_manager: FileTransferManager::new(),
+            hosted_sessions: Arc::clone(&self.hosted_sessions),
+            capture_streams: Arc::clone(&self.capture_streams),
+            connection_quality: Arc::clone(&self.connection_quality),
+            screen_capturer: self.screen_capturer.clone(),
+            file_transfer_manager: self.file_transfer_manager.clone(),
+            events: self.events.clone(),
+            quality_check_interval: self.quality_check_interval,
+            capabilities: self.capabilities.clone(),
+            input_injector: Arc::clone(&self.input_injector),
+        }
+    }
+}
+
+impl Clone for ScreenCapturer {
+    fn clone(&self) -> Self {
+        Self {
+            capture_interval_ms: self.capture_interval_ms,
+            compression_enabled: self.compression_enabled,
+            supported_formats: self.supported_formats.clone(),
+            capture_policy: self.capture_policy.clone(),
         }
     }
 }
@@ -1673,10 +5861,17 @@ impl Clone for RemoteDesktopManager {
 impl Clone for FileTransferManager {
     fn clone(&self) -> Self {
         Self {
+            device_id: self.device_id.clone(),
             max_file_size_mb: self.max_file_size_mb,
             allowed_file_types: self.allowed_file_types.clone(),
             encryption_required: self.encryption_required,
+            download_dir: self.download_dir.clone(),
+            security_manager: self.security_manager.clone(),
+            connections: Arc::clone(&self.connections),
             active_transfers: Arc::clone(&self.active_transfers),
+            transfer_controls: Arc::clone(&self.transfer_controls),
+            incoming_transfers: Arc::clone(&self.incoming_transfers),
+            redeemed_grants: Arc::clone(&self.redeemed_grants),
         }
     }
 }
@@ -1689,6 +5884,2454 @@ impl Clone for ClipboardSync {
             sync_interval_seconds: self.sync_interval_seconds,
             last_clipboard_hash: Arc::clone(&self.last_clipboard_hash),
             supported_formats: self.supported_formats.clone(),
+            backend: Arc::clone(&self.backend),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    async fn test_manager() -> (DeviceManager, tempfile::TempDir) {
+        let (manager, data_dir) = test_manager_with(DeviceConfig::default()).await;
+        let privacy_controls = screen_capture_privacy(&data_dir, true).await;
+        (manager.with_privacy_controls(privacy_controls), data_dir)
+    }
+
+    /// A manager with `config` in a fresh data directory
+    async fn test_manager_with(config: DeviceConfig) -> (DeviceManager, tempfile::TempDir) {
+        let data_dir = tempfile::tempdir().unwrap();
+        let security_manager = test_security_manager(&data_dir).await;
+        (DeviceManager::new(config, security_manager).await.unwrap(), data_dir)
+    }
+
+    async fn test_security_manager(data_dir: &tempfile::TempDir) -> SecurityManager {
+        let security_manager = SecurityManager::new(
+            data_dir.path().to_str().unwrap(),
+            crate::kernel::SecurityConfig::default(),
+        )
+        .await
+        .unwrap();
+        security_manager.initialize().await.unwrap();
+        security_manager
+    }
+
+    /// Privacy controls with the screen capture source enabled, and consent for `TEST_USER` if `consented`
+    async fn screen_capture_privacy(data_dir: &tempfile::TempDir, consented: bool) -> PrivacyControls {
+        let privacy_controls = PrivacyControls::new(
+            crate::kernel::SecurityConfig::default(),
+            data_dir.path().to_str().unwrap(),
+        )
+        .await
+        .unwrap();
+        privacy_controls.set_data_source_control(SCREEN_CAPTURE_SOURCE, true).await.unwrap();
+        if consented {
+            privacy_controls
+                .insert_test_consent(TEST_USER, crate::privacy::ConsentType::ScreenCapture)
+                .await;
+        }
+        privacy_controls
+    }
+
+    const TEST_USER: &str = "user-1";
+
+    impl DeviceManager {
+        /// Add a peer directly, bypassing discovery and pairing
+        pub(crate) async fn insert_test_device(&self, device: DeviceInfo) {
+            self.devices.write().await.insert(device.device_id.clone(), device);
+        }
+    }
+
+    pub(crate) fn test_device(device_id: &str, supports_gpu: bool, max_memory_mb: u64, battery_powered: bool) -> DeviceInfo {
+        DeviceInfo {
+            device_id: device_id.to_string(),
+            name: device_id.to_string(),
+            device_type: DeviceType::Desktop,
+            capabilities: DeviceCapabilities {
+                supports_gpu,
+                supports_vision: false,
+                supports_audio: false,
+                has_camera: false,
+                has_microphone: false,
+                max_memory_mb,
+                cpu_cores: 4,
+                gpu_memory_mb: None,
+                battery_powered,
+                supports_remote_desktop: false,
+                remote_desktop: RemoteDesktopCapabilities::default(),
+            },
+            status: DeviceStatus::Online,
+            last_seen: chrono::Utc::now(),
+            battery_level: None,
+            cpu_usage: None,
+            memory_usage: None,
+            network_info: NetworkInfo {
+                ip_address: "127.0.0.1".to_string(),
+                mac_address: None,
+                connection_type: ConnectionType::Ethernet,
+                signal_strength: None,
+                bandwidth_mbps: None,
+            },
+            location: None,
+        }
+    }
+
+    fn test_quality(device_id: &str, latency_ms: u64, bandwidth_mbps: f32, stability_score: f32) -> ConnectionQuality {
+        ConnectionQuality {
+            device_id: device_id.to_string(),
+            latency_ms,
+            bandwidth_mbps,
+            signal_strength: 1.0,
+            stability_score,
+            last_updated: chrono::Utc::now(),
+            uptime_percentage: 100.0,
+            jitter_ms: 0,
+            packet_loss: 0.0,
+        }
+    }
+
+    /// A powerful but flaky workstation, a weak but snappy phone, and a
+    /// mid-range machine on a fat pipe.
+    async fn seed_devices(manager: &DeviceManager) {
+        let mut devices = manager.devices.write().await;
+        devices.insert("workstation".to_string(), test_device("workstation", true, 32768, false));
+        devices.insert("phone".to_string(), test_device("phone", false, 4096, true));
+        devices.insert("nas".to_string(), test_device("nas", false, 8192, false));
+
+        let mut qualities = manager.connection_quality.write().await;
+        qualities.insert("workstation".to_string(), test_quality("workstation", 300, 20.0, 0.3));
+        qualities.insert("phone".to_string(), test_quality("phone", 5, 10.0, 0.99));
+        qualities.insert("nas".to_string(), test_quality("nas", 80, 900.0, 0.8));
+    }
+
+    #[tokio::test]
+    async fn test_latency_profile_prefers_reliable_device() {
+        let (manager, _data_dir) = test_manager().await;
+        seed_devices(&manager).await;
+
+        let selected = manager.select_device(&[], TaskProfile::Latency).await.unwrap();
+        assert_eq!(selected.as_deref(), Some("phone"));
+    }
+
+    #[tokio::test]
+    async fn test_throughput_profile_prefers_high_bandwidth_device() {
+        let (manager, _data_dir) = test_manager().await;
+        seed_devices(&manager).await;
+
+        let selected = manager.select_device(&[], TaskProfile::Throughput).await.unwrap();
+        assert_eq!(selected.as_deref(), Some("nas"));
+    }
+
+    #[tokio::test]
+    async fn test_compute_profile_prefers_powerful_device() {
+        let (manager, _data_dir) = test_manager().await;
+        seed_devices(&manager).await;
+
+        let selected = manager.select_device(&[], TaskProfile::Compute).await.unwrap();
+        assert_eq!(selected.as_deref(), Some("workstation"));
+    }
+
+    /// Public half of a key exchange, as a device scanning a pairing token would answer with
+    fn scanner_public_key(manager: &DeviceManager) -> String {
+        encode_hex(manager.security_manager.start_device_key_exchange().unwrap().public_key())
+    }
+
+    #[tokio::test]
+    async fn test_signed_pairing_token_is_accepted() {
+        let (manager, _data_dir) = test_manager().await;
+
+        let token = manager.generate_pairing_token("phone-1234").await.unwrap();
+        let result = manager.pair_device(&token, &scanner_public_key(&manager)).await.unwrap();
+
+        assert!(result.success);
+        assert!(manager.get_device("phone-1234").await.unwrap().is_some());
+        assert!(manager.security_manager.has_device_key("phone-1234").await);
+        // A token pairs once
+        assert!(manager.pair_device(&token, &scanner_public_key(&manager)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tampered_pairing_token_is_rejected() {
+        let (manager, _data_dir) = test_manager().await;
+
+        let token = manager.generate_pairing_token("phone-1234").await.unwrap();
+        let tampered = token.replace("phone-1234", "phone-9999");
+
+        assert!(manager.pair_device(&tampered, &scanner_public_key(&manager)).await.is_err());
+        assert!(manager.get_device("phone-9999").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_expired_pairing_token_is_rejected() {
+        let (manager, _data_dir) = test_manager().await;
+
+        let issued_at = (chrono::Utc::now() - chrono::Duration::minutes(PAIRING_TOKEN_TTL_MINUTES + 1)).timestamp();
+        let token = manager.sign_pairing_token("phone-1234", issued_at).await.unwrap();
+
+        assert!(manager.pair_device(&token, &scanner_public_key(&manager)).await.is_err());
+        assert!(manager.get_device("phone-1234").await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_paired_device_name_keeps_multibyte_ids_whole() {
+        // Slicing the first eight bytes would split "é" and panic
+        let info = DeviceManager::paired_device_info("téléphone-42");
+        assert_eq!(info.name, "Device-téléphon");
+        assert_eq!(DeviceManager::paired_device_info("tab").name, "Device-tab");
+    }
+
+    #[tokio::test]
+    async fn test_clipboard_changes_after_prefix_are_synced() {
+        let (manager, _data_dir) = test_manager().await;
+        let manager = Arc::new(manager);
+        let last_hash = Arc::new(RwLock::new(None));
+
+        let first = "The quick brown fox jumps over the lazy dog".to_string();
+        let second = "The quick brown fox jumps over the lazy cat".to_string();
+        assert_ne!(clipboard_hash(&first), clipboard_hash(&second));
+
+        assert!(ClipboardSync::sync_clipboard_content(&manager, &last_hash, ClipboardContent::Text(first), false).await.unwrap());
+        assert!(ClipboardSync::sync_clipboard_content(&manager, &last_hash, ClipboardContent::Text(second.clone()), false).await.unwrap());
+        assert!(!ClipboardSync::sync_clipboard_content(&manager, &last_hash, ClipboardContent::Text(second), false).await.unwrap());
+    }
+
+    #[derive(Default)]
+    struct MemoryClipboard(std::sync::Mutex<Option<ClipboardContent>>);
+
+    impl ClipboardBackend for MemoryClipboard {
+        fn read(&self) -> MisaResult<Option<ClipboardContent>> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+
+        fn write(&self, content: &ClipboardContent) -> MisaResult<()> {
+            *self.0.lock().unwrap() = Some(content.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_received_clipboard_text_is_not_synced_back() {
+        let (manager, _data_dir) = test_manager().await;
+        let manager = Arc::new(manager);
+        let backend = Arc::new(MemoryClipboard::default());
+        let clipboard = ClipboardSync::new(false).with_backend(backend.clone());
+
+        let message = DeviceMessage {
+            message_id: "clip-1".to_string(),
+            source_device_id: "phone".to_string(),
+            target_device_id: None,
+            message_type: MessageType::ClipboardSync,
+            payload: serde_json::json!({ "content": "copied on the phone", "format": "text/plain" }),
+            timestamp: chrono::Utc::now(),
+            encrypted: false,
+            priority: MessagePriority::Normal,
+        };
+        clipboard.handle_sync_message(&message).await.unwrap();
+
+        assert_eq!(
+            backend.read().unwrap(),
+            Some(ClipboardContent::Text("copied on the phone".to_string()))
+        );
+
+        let backend: Arc<dyn ClipboardBackend> = backend;
+        let synced = ClipboardSync::check_and_sync_clipboard(&manager, &backend, &clipboard.last_clipboard_hash, false)
+            .await
+            .unwrap();
+        assert!(!synced);
+    }
+
+    #[tokio::test]
+    async fn test_clones_share_the_clipboard_echo_guard() {
+        let (mut manager, _data_dir) = test_manager().await;
+        let backend = Arc::new(MemoryClipboard::default());
+        manager.clipboard_sync = ClipboardSync::new(false).with_backend(backend.clone());
+
+        // Messages are handled on a clone; the polling loop runs on another
+        let content = ClipboardContent::Text("copied on the phone".to_string());
+        manager.clone().clipboard_sync.set_clipboard_content(&content, "phone").await.unwrap();
+
+        let poller = Arc::new(manager.clone());
+        let backend: Arc<dyn ClipboardBackend> = backend;
+        let synced = ClipboardSync::check_and_sync_clipboard(&poller, &backend, &poller.clipboard_sync.last_clipboard_hash, false)
+            .await
+            .unwrap();
+        assert!(!synced);
+    }
+
+    #[test]
+    fn test_clipboard_image_survives_payload_round_trip() {
+        let content = ClipboardContent::Image {
+            width: 2,
+            height: 1,
+            rgba: vec![255, 0, 0, 255, 0, 0, 255, 128],
+        };
+        let payload = serde_json::json!({
+            "content": content.to_payload_content().unwrap(),
+            "format": content.format(),
+        });
+
+        let decoded = ClipboardContent::from_payload(&payload).unwrap();
+        assert_eq!(decoded.content_hash(), content.content_hash());
+        assert_eq!(decoded, content);
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_clipboard_message_requires_device_key() {
+        let key = [7u8; 32];
+        let (sender, _sender_dir) = test_manager().await;
+        sender.security_manager.register_device_key("phone", &key).await.unwrap();
+        let (mut receiver, _receiver_dir) = test_manager().await;
+        receiver.security_manager.register_device_key("laptop", &key).await.unwrap();
+        let backend = Arc::new(MemoryClipboard::default());
+        receiver.clipboard_sync = ClipboardSync::new(true).with_backend(backend.clone());
+        let (stranger, _stranger_dir) = test_manager().await;
+
+        let message = DeviceMessage {
+            message_id: "clip-secret".to_string(),
+            source_device_id: "laptop".to_string(),
+            target_device_id: Some("phone".to_string()),
+            message_type: MessageType::ClipboardSync,
+            payload: serde_json::json!({ "content": "hunter2", "format": "text/plain" }),
+            timestamp: chrono::Utc::now(),
+            encrypted: true,
+            priority: MessagePriority::High,
+        };
+        let sealed = sender.seal_message("phone", &message).await.unwrap();
+
+        assert!(sealed.payload.get("content").is_none());
+        assert!(!sealed.payload.to_string().contains("hunter2"));
+        assert!(ClipboardContent::from_payload(&sealed.payload).is_err());
+
+        // Without the shared key the message is rejected rather than passed through
+        let result = stranger.handle_incoming_message("laptop", sealed.clone()).await;
+        assert!(matches!(result, Err(MisaError::Encryption(_))));
+
+        receiver.handle_incoming_message("laptop", sealed).await.unwrap();
+        assert_eq!(backend.read().unwrap(), Some(ClipboardContent::Text("hunter2".to_string())));
+    }
+
+    async fn wait_for_status(manager: &DeviceManager, transfer_id: &str, done: fn(&FileTransferStatus) -> bool) -> FileTransfer {
+        loop {
+            let transfer = manager.get_transfer_progress(transfer_id).await.unwrap().unwrap();
+            if done(&transfer.status) {
+                return transfer;
+            }
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_paused_transfer_resumes_to_completion() {
+        let (manager, data_dir) = test_manager().await;
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        manager.register_connection(DeviceConnection {
+            device_id: "peer".to_string(),
+            connection_type: ConnectionProtocol::Local,
+            websocket: None,
+            webrtc_connection: None,
+            last_heartbeat: chrono::Utc::now(),
+            encrypted_channel: true,
+            local_channel: Some(tx),
+        }).await;
+
+        manager.security_manager.register_device_key("peer", &[3u8; 32]).await.unwrap();
+
+        let file_size = 10 * 64 * 1024;
+        let file_path = data_dir.path().join("large.bin");
+        std::fs::write(&file_path, vec![7u8; file_size]).unwrap();
+
+        let (peer, _peer_dir) = test_manager().await;
+        let grant = peer
+            .remote_desktop_manager
+            .file_transfer_manager
+            .issue_grant(manager.device_id(), &FileTransferRequest {
+                file_name: "large.bin".to_string(),
+                file_size: file_size as u64,
+                encrypted: true,
+                grant: None,
+            })
+            .await
+            .unwrap();
+        let transfer_id = manager.transfer_file("peer", file_path.to_str().unwrap(), grant).await.unwrap();
+
+        // Let at least one chunk go out, then pause
+        loop {
+            let transfer = manager.get_transfer_progress(&transfer_id).await.unwrap().unwrap();
+            if transfer.bytes_transferred > 0 {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+        manager.pause_transfer(&transfer_id).await.unwrap();
+
+        let paused = wait_for_status(&manager, &transfer_id, |s| matches!(s, FileTransferStatus::Paused)).await;
+        assert!(paused.bytes_transferred < file_size as u64);
+
+        for _ in 0..20 {
+            tokio::task::yield_now().await;
+        }
+        let still_paused = manager.get_transfer_progress(&transfer_id).await.unwrap().unwrap();
+        assert_eq!(still_paused.bytes_transferred, paused.bytes_transferred);
+
+        manager.resume_transfer(&transfer_id).await.unwrap();
+        let completed = wait_for_status(&manager, &transfer_id, |s| matches!(s, FileTransferStatus::Completed)).await;
+        assert_eq!(completed.bytes_transferred, file_size as u64);
+
+        // One announcement and every chunk exactly once, despite the pause, all sealed for the peer
+        let mut requests = 0;
+        let mut offsets = Vec::new();
+        while let Ok(message) = rx.try_recv() {
+            assert!(message.encrypted);
+            assert_eq!(message.source_device_id, manager.device_id());
+            let opened = manager.open_message("peer", message).await.unwrap();
+            match opened.message_type {
+                MessageType::FileTransferRequest => requests += 1,
+                MessageType::FileTransferData => offsets.push(opened.payload["offset"].as_u64().unwrap()),
+                _ => {}
+            }
         }
+        assert_eq!(requests, 1);
+        assert_eq!(offsets, (0..10).map(|i| i * 64 * 1024).collect::<Vec<u64>>());
+    }
+
+    fn local_connection(device_id: &str, last_heartbeat: chrono::DateTime<chrono::Utc>) -> (DeviceConnection, mpsc::UnboundedReceiver<DeviceMessage>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let connection = DeviceConnection {
+            device_id: device_id.to_string(),
+            connection_type: ConnectionProtocol::Local,
+            websocket: None,
+            webrtc_connection: None,
+            last_heartbeat,
+            encrypted_channel: true,
+            local_channel: Some(tx),
+        };
+        (connection, rx)
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_stale_connection_is_evicted_and_marked_offline() {
+        let (manager, _data_dir) = test_manager().await;
+        seed_devices(&manager).await;
+
+        let long_ago = chrono::Utc::now() - chrono::Duration::minutes(10);
+        let (stale, _stale_rx) = local_connection("phone", long_ago);
+        let (fresh, mut fresh_rx) = local_connection("nas", long_ago);
+        manager.register_connection(stale).await;
+        manager.register_connection(fresh).await;
+
+        // Inbound traffic from the NAS refreshes its heartbeat
+        manager.handle_incoming_message("nas", DeviceMessage {
+            message_id: "hb-1".to_string(),
+            source_device_id: "nas".to_string(),
+            target_device_id: Some("local".to_string()),
+            message_type: MessageType::Heartbeat,
+            payload: serde_json::Value::Null,
+            timestamp: chrono::Utc::now(),
+            encrypted: false,
+            priority: MessagePriority::Low,
+        }).await.unwrap();
+
+        let evicted = manager.evict_stale_connections(Duration::from_secs(60)).await;
+        assert_eq!(evicted, vec!["phone".to_string()]);
+
+        let connections = manager.active_connections.read().await;
+        assert!(!connections.contains_key("phone"));
+        assert!(connections.contains_key("nas"));
+        drop(connections);
+
+        let phone = manager.get_device("phone").await.unwrap().unwrap();
+        assert!(matches!(phone.status, DeviceStatus::Offline));
+
+        manager.send_heartbeats().await.unwrap();
+        assert!(matches!(fresh_rx.try_recv().unwrap().message_type, MessageType::Heartbeat));
+    }
+
+    #[tokio::test]
+    async fn test_send_request_resolves_with_matching_response() {
+        let (manager, _data_dir) = test_manager().await;
+        let manager = Arc::new(manager);
+        let (peer, mut peer_rx) = local_connection("phone", chrono::Utc::now());
+        manager.register_connection(peer).await;
+
+        // Echo peer answering every request with its own payload
+        let echo = Arc::clone(&manager);
+        tokio::spawn(async move {
+            while let Some(request) = peer_rx.recv().await {
+                if matches!(request.message_type, MessageType::TaskRequest) {
+                    let result = serde_json::json!({ "echo": request.payload, "from": request.source_device_id });
+                    let response = request.response("phone", result);
+                    echo.handle_incoming_message("phone", response).await.unwrap();
+                }
+            }
+        });
+
+        let result = manager
+            .send_request("phone", serde_json::json!({ "task": "ping" }), Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        assert_eq!(result, serde_json::json!({ "echo": { "task": "ping" }, "from": manager.device_id() }));
+        assert!(manager.pending_requests.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_response_from_another_device_is_ignored() {
+        let (manager, _data_dir) = test_manager().await;
+        let manager = Arc::new(manager);
+        let (phone, mut phone_rx) = local_connection("phone", chrono::Utc::now());
+        manager.register_connection(phone).await;
+
+        let requester = Arc::clone(&manager);
+        let result = tokio::spawn(async move {
+            requester.send_request("phone", serde_json::json!({ "task": "ping" }), Duration::from_secs(5)).await
+        });
+        let request = loop {
+            let message = phone_rx.recv().await.unwrap();
+            if matches!(message.message_type, MessageType::TaskRequest) {
+                break message;
+            }
+        };
+
+        let forged = request.response("laptop", serde_json::json!("forged"));
+        manager.handle_incoming_message("laptop", forged).await.unwrap();
+        assert_eq!(manager.pending_requests.read().await.len(), 1);
+
+        let answer = request.response("phone", serde_json::json!("pong"));
+        manager.handle_incoming_message("phone", answer).await.unwrap();
+        assert_eq!(result.await.unwrap().unwrap(), serde_json::json!("pong"));
+    }
+
+    #[tokio::test]
+    async fn test_send_request_times_out_and_forgets_request() {
+        let (manager, _data_dir) = test_manager().await;
+        let (peer, _peer_rx) = local_connection("phone", chrono::Utc::now());
+        manager.register_connection(peer).await;
+
+        let result = manager
+            .send_request("phone", serde_json::json!({ "task": "ping" }), Duration::from_millis(50))
+            .await;
+
+        assert!(matches!(result, Err(MisaError::Timeout(_))));
+        assert!(manager.pending_requests.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_task_publishes_evictions() {
+        let (manager, _data_dir) = test_manager_with(DeviceConfig {
+            heartbeat_interval_secs: 1,
+            connection_idle_timeout_secs: 0,
+            ..DeviceConfig::default()
+        })
+        .await;
+
+        let (connection, _rx) = local_connection("peer", chrono::Utc::now() - chrono::Duration::seconds(5));
+        manager.register_connection(connection).await;
+
+        let (events, mut receiver) = broadcast::channel(4);
+        let handle = manager.start_connection_heartbeat(events, |device_id: &str| format!("disconnected:{}", device_id));
+
+        let event = tokio::time::timeout(Duration::from_secs(5), receiver.recv()).await.unwrap().unwrap();
+        assert_eq!(event, "disconnected:peer");
+        handle.abort();
+    }
+
+    fn view_only_permissions() -> RemoteDesktopPermissions {
+        RemoteDesktopPermissions {
+            view_screen: true,
+            control_mouse: false,
+            control_keyboard: false,
+            transfer_files: false,
+            access_clipboard: false,
+            record_session: false,
+            system_commands: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stopping_one_session_keeps_the_other() {
+        let (manager, _data_dir) = test_manager().await;
+        let remote_desktop = &manager.remote_desktop_manager;
+        let mut events = remote_desktop.subscribe_events();
+
+        let first = remote_desktop.start_session(TEST_USER, "workstation", &RemoteDesktopCapabilities::default(), view_only_permissions(), None).await.unwrap();
+        let second = remote_desktop.start_session(TEST_USER, "nas", &RemoteDesktopCapabilities::default(), view_only_permissions(), None).await.unwrap();
+
+        let sessions = remote_desktop.list_sessions().await;
+        assert_eq!(sessions.len(), 2);
+
+        let first_stream = remote_desktop.capture_streams.read().await.get(&first).cloned().unwrap();
+        assert!(first_stream.is_active());
+
+        remote_desktop.stop_session(&first).await.unwrap();
+        assert!(!first_stream.is_active());
+
+        let sessions = remote_desktop.list_sessions().await;
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].session_id, second);
+        assert_eq!(sessions[0].host_device_id, "nas");
+        assert!(remote_desktop.capture_streams.read().await.contains_key(&second));
+
+        assert!(matches!(remote_desktop.stop_session(&first).await, Err(MisaError::NotFound(_))));
+
+        let mut stopped = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let RemoteDesktopEvent::SessionStopped { session_id, .. } = event {
+                stopped.push(session_id);
+            }
+        }
+        assert_eq!(stopped, vec![first]);
+    }
+
+    #[tokio::test]
+    async fn test_revoking_view_permission_stops_capture() {
+        let (manager, _data_dir) = test_manager().await;
+        let remote_desktop = &manager.remote_desktop_manager;
+        let session_id = remote_desktop.start_session(TEST_USER, "workstation", &RemoteDesktopCapabilities::default(), view_only_permissions(), None).await.unwrap();
+
+        let permissions = RemoteDesktopPermissions {
+            view_screen: false,
+            ..view_only_permissions()
+        };
+        remote_desktop.update_permissions(TEST_USER, &session_id, permissions).await.unwrap();
+
+        let sessions = remote_desktop.list_sessions().await;
+        assert!(!sessions[0].permissions.view_screen);
+        assert!(!remote_desktop.capture_streams.read().await.contains_key(&session_id));
+    }
+
+    fn gradient(width: u32, height: u32) -> image::RgbaImage {
+        image::RgbaImage::from_fn(width, height, |x, y| image::Rgba([(x % 256) as u8, (y % 256) as u8, 128, 255]))
+    }
+
+    #[test]
+    fn test_png_frame_decodes_to_reported_dimensions() {
+        let frame = encode_frame(gradient(64, 48), ImageFormat::PNG, true).unwrap();
+        let decoded = image::load_from_memory_with_format(&frame.data, image::ImageFormat::Png).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (frame.width, frame.height));
+        assert_eq!((frame.width, frame.height), (64, 48));
+    }
+
+    #[test]
+    fn test_jpeg_and_webp_frames_are_valid_images() {
+        let jpeg = encode_frame(gradient(32, 16), ImageFormat::JPEG, true).unwrap();
+        assert_eq!(image::guess_format(&jpeg.data).unwrap(), image::ImageFormat::Jpeg);
+
+        let webp = encode_frame(gradient(32, 16), ImageFormat::WebP, false).unwrap();
+        assert_eq!(image::guess_format(&webp.data).unwrap(), image::ImageFormat::WebP);
+
+        assert!(matches!(
+            encode_frame(gradient(8, 8), ImageFormat::H264, true),
+            Err(MisaError::RemoteDesktop(_))
+        ));
+    }
+
+    #[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+    #[tokio::test]
+    async fn test_captured_png_matches_display_resolution() {
+        let (manager, _data_dir) = test_manager().await;
+        let capturer = &manager.remote_desktop_manager.screen_capturer;
+        let frame = match capturer.capture_frame(TEST_USER, None, ImageFormat::PNG).await {
+            Ok(frame) => frame,
+            // Headless runners have no display to capture
+            Err(MisaError::RemoteDesktop(_)) => return,
+            Err(e) => panic!("unexpected capture error: {}", e),
+        };
+
+        let decoded = image::load_from_memory(&frame.data).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (frame.width, frame.height));
+    }
+
+    #[tokio::test]
+    async fn test_low_bandwidth_lowers_frame_rate() {
+        let (manager, _data_dir) = test_manager().await;
+        let stream = manager
+            .remote_desktop_manager
+            .screen_capturer
+            .start_capture(TEST_USER, "session".to_string(), None, 8_000)
+            .await
+            .unwrap();
+        let initial_frame_rate = stream.frame_rate();
+        assert_eq!(stream.quality(), VideoQuality::High);
+
+        // 1.5 Mbps only leaves room for the lowest profile
+        assert_eq!(stream.adapt(&test_quality("peer", 20, 1.5, 0.9)), Some(VideoQuality::Low));
+        assert!(stream.frame_rate() < initial_frame_rate);
+        assert_eq!(stream.resolution(), (854, 480));
+
+        // No change, no update
+        assert_eq!(stream.adapt(&test_quality("peer", 20, 1.5, 0.9)), None);
+
+        // Plenty of bandwidth is still capped by the bitrate target
+        assert_eq!(stream.adapt(&test_quality("peer", 20, 500.0, 0.9)), Some(VideoQuality::High));
+        // High latency costs one step
+        assert_eq!(stream.adapt(&test_quality("peer", 400, 500.0, 0.9)), Some(VideoQuality::Medium));
+    }
+
+    #[test]
+    fn test_unmeasured_bandwidth_only_applies_the_target() {
+        // A sighting records zero bandwidth until it is measured, which must not force the lowest profile
+        assert_eq!(VideoQuality::for_link(&test_quality("peer", 20, 0.0, 0.9), 8_000), VideoQuality::High);
+        assert_eq!(VideoQuality::for_link(&test_quality("peer", 400, 0.0, 0.9), 8_000), VideoQuality::Medium);
+
+        // Measured bandwidth still caps the quality as before
+        assert_eq!(VideoQuality::for_link(&test_quality("peer", 20, 1.5, 0.9), 8_000), VideoQuality::Low);
+    }
+
+    #[tokio::test]
+    async fn test_congested_session_reports_quality_change() {
+        let (manager, _data_dir) = test_manager().await;
+        let remote_desktop = manager
+            .remote_desktop_manager
+            .clone()
+            .with_quality_check_interval(Duration::from_millis(10));
+        let mut events = remote_desktop.subscribe_events();
+
+        manager
+            .connection_quality
+            .write()
+            .await
+            .insert("workstation".to_string(), test_quality("workstation", 30, 2.0, 0.9));
+
+        let session_id = remote_desktop.start_session(TEST_USER, "workstation", &RemoteDesktopCapabilities::default(), view_only_permissions(), None).await.unwrap();
+
+        let quality = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let Ok(RemoteDesktopEvent::QualityChanged { quality, .. }) = events.recv().await {
+                    return quality;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(quality, VideoQuality::Low);
+
+        let session = remote_desktop.list_sessions().await.into_iter().find(|s| s.session_id == session_id).unwrap();
+        assert_eq!(session.quality, VideoQuality::Low);
+        assert_eq!(session.resolution, (854, 480));
+
+        remote_desktop.stop_session(&session_id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_mdns_discovers_advertised_peer() {
+        let first = DiscoveryService::new(true, DiscoveryTransport::Mdns).with_device_id("mdns-first");
+        let second = DiscoveryService::new(true, DiscoveryTransport::Mdns).with_device_id("mdns-second");
+        first.start_mdns().await.unwrap();
+        second.start_mdns().await.unwrap();
+
+        let found = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                if first.active_discovery.read().await.contains_key("mdns-second") {
+                    return;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        })
+        .await;
+
+        first.stop().await.unwrap();
+        second.stop().await.unwrap();
+
+        assert!(found.is_ok(), "first instance never saw the second over mDNS");
+        assert!(first.device_history.read().await.contains_key("mdns-second"));
+        // Instances ignore their own advertisement
+        assert!(!first.device_history.read().await.contains_key("mdns-first"));
+    }
+
+    /// Answers one directed probe with its own discovery packet
+    async fn spawn_probe_responder(bind: &str, device_id: &str) -> SocketAddr {
+        let socket = tokio::net::UdpSocket::bind(bind).await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        let reply = DeviceDiscoveryPacket {
+            device_id: device_id.to_string(),
+            device_name: "Responder".to_string(),
+            device_type: "Laptop".to_string(),
+            capabilities: vec!["audio".to_string()],
+            port: DEVICE_SERVICE_PORT,
+            timestamp: chrono::Utc::now(),
+            probe: false,
+        };
+
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1024];
+            let (len, from) = socket.recv_from(&mut buf).await.unwrap();
+            let probe: DeviceDiscoveryPacket = serde_json::from_slice(&buf[..len]).unwrap();
+            assert!(probe.probe);
+            DiscoveryService::send_packet(&socket, &reply, from).await.unwrap();
+        });
+
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_directed_probe_discovers_known_device() {
+        let service = DiscoveryService::new(true, DiscoveryTransport::Udp);
+        let responder_addr = spawn_probe_responder("127.0.0.1:0", "responder").await;
+
+        service.device_history.write().await.insert("responder".to_string(), DeviceHistory {
+            device_id: "responder".to_string(),
+            last_connected: chrono::Utc::now(),
+            connection_count: 1,
+            average_signal_strength: 0.5,
+            success_rate: 1.0,
+            preferred_for_tasks: Vec::new(),
+            device_type: DeviceType::Laptop,
+            last_known_addr: Some(responder_addr),
+        });
+
+        let socket = Arc::new(tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let local_packet = service.local_discovery_packet();
+        DiscoveryService::send_directed_discovery(&socket, &local_packet, "responder", &service.device_history)
+            .await
+            .unwrap();
+
+        let responders = DiscoveryService::collect_probe_responses(
+            &socket,
+            Duration::from_millis(500),
+            &service.active_discovery,
+            &service.device_history,
+            &service.connection_quality_monitor,
+        )
+        .await
+        .unwrap();
+        assert_eq!(responders, 1);
+
+        let history = service.device_history.read().await;
+        let responder = history.get("responder").unwrap();
+        assert_eq!(responder.connection_count, 2);
+        // Loopback sighting at full strength pulls the mean up
+        assert!((responder.average_signal_strength - 0.75).abs() < 1e-6);
+        assert!(service.active_discovery.read().await.contains_key("responder"));
+    }
+
+    #[tokio::test]
+    async fn test_subnet_sweep_finds_responder() {
+        let service = DiscoveryService::new(true, DiscoveryTransport::Udp);
+        let responder_addr = spawn_probe_responder("127.0.0.1:0", "sweep-responder").await;
+
+        let socket = Arc::new(tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let local_packet = service.local_discovery_packet();
+        // Sweeping from .2 covers the responder on .1
+        DiscoveryService::sweep_subnet(&socket, &local_packet, std::net::Ipv4Addr::new(127, 0, 0, 2), responder_addr.port())
+            .await
+            .unwrap();
+
+        DiscoveryService::collect_probe_responses(
+            &socket,
+            Duration::from_millis(500),
+            &service.active_discovery,
+            &service.device_history,
+            &service.connection_quality_monitor,
+        )
+        .await
+        .unwrap();
+
+        let history = service.device_history.read().await;
+        let responder = history.get("sweep-responder").unwrap();
+        assert_eq!(responder.last_known_addr, Some(responder_addr));
+    }
+
+    #[tokio::test]
+    async fn test_loopback_latency_probe_is_bounded() {
+        const REPLY_DELAY: Duration = Duration::from_millis(40);
+
+        let responder = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let responder_addr = responder.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            while let Ok((len, from)) = responder.recv_from(&mut buf).await {
+                if let Some(pong) = ping_reply(&buf[..len]) {
+                    tokio::time::sleep(REPLY_DELAY).await;
+                    responder.send_to(&pong, from).await.unwrap();
+                }
+            }
+        });
+
+        // Every round trip takes at least the reply delay and, on loopback, not much longer
+        let sample = ConnectionQualityMonitor::probe_latency(responder_addr).await.unwrap().unwrap();
+        let delay_ms = REPLY_DELAY.as_millis() as u64;
+        assert!(sample.latency_ms >= delay_ms, "latency {}ms below the reply delay", sample.latency_ms);
+        assert!(sample.latency_ms < delay_ms + 100, "latency {}ms on loopback", sample.latency_ms);
+        assert!(sample.jitter_ms < 100);
+        assert_eq!(sample.packet_loss, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_latency_probes_run_concurrently() {
+        let monitor = ConnectionQualityMonitor::new();
+        let silent: Vec<tokio::net::UdpSocket> = futures_util::future::join_all(
+            (0..3).map(|_| tokio::net::UdpSocket::bind("127.0.0.1:0")),
+        )
+        .await
+        .into_iter()
+        .map(Result::unwrap)
+        .collect();
+        {
+            let mut probe_addrs = monitor.probe_addrs.write().await;
+            for (index, socket) in silent.iter().enumerate() {
+                probe_addrs.insert(format!("silent-{}", index), socket.local_addr().unwrap());
+            }
+        }
+
+        // Three unanswered probe rounds in sequence would take three times as long
+        let started = tokio::time::Instant::now();
+        ConnectionQualityMonitor::monitor_connection_quality(
+            &monitor.active_connections,
+            &monitor.quality_history,
+            &monitor.probe_addrs,
+        )
+        .await
+        .unwrap();
+        let round = LATENCY_PROBE_TIMEOUT * LATENCY_PROBE_COUNT;
+        assert!(started.elapsed() < round * 2, "probing took {:?}", started.elapsed());
+        assert_eq!(monitor.quality_history.read().await.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_unanswered_latency_probe_reports_nothing() {
+        // Bound but silent
+        let silent = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let sample = ConnectionQualityMonitor::probe_latency(silent.local_addr().unwrap()).await.unwrap();
+        assert!(sample.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_session_uses_negotiated_settings() {
+        let (manager, _data_dir) = test_manager().await;
+        let remote_desktop = &manager.remote_desktop_manager;
+
+        let host = RemoteDesktopCapabilities {
+            protocols: vec![RemoteDesktopProtocol::RDP, RemoteDesktopProtocol::VNC],
+            codecs: vec![ImageFormat::PNG, ImageFormat::JPEG],
+            max_resolution: (1280, 720),
+        };
+        let session_id = remote_desktop.start_session(TEST_USER, "workstation", &host, view_only_permissions(), None).await.unwrap();
+
+        let session = remote_desktop.list_sessions().await.into_iter().find(|s| s.session_id == session_id).unwrap();
+        assert_eq!(session.protocol, RemoteDesktopProtocol::VNC);
+        assert_eq!(session.codec, ImageFormat::JPEG);
+        assert_eq!(session.max_resolution, (1280, 720));
+        assert_eq!(session.resolution, (1280, 720));
+
+        remote_desktop.stop_session(&session_id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_negotiation_without_overlap_fails() {
+        let (manager, _data_dir) = test_manager().await;
+        let remote_desktop = &manager.remote_desktop_manager;
+
+        let host = RemoteDesktopCapabilities {
+            protocols: vec![RemoteDesktopProtocol::RDP],
+            ..RemoteDesktopCapabilities::default()
+        };
+        match remote_desktop.start_session(TEST_USER, "workstation", &host, view_only_permissions(), None).await {
+            Err(MisaError::RemoteDesktop(message)) => {
+                assert!(message.contains("protocol"), "{}", message);
+                assert!(message.contains("RDP"), "{}", message);
+            }
+            other => panic!("expected a negotiation failure, got {:?}", other),
+        }
+        assert!(remote_desktop.list_sessions().await.is_empty());
+
+        let host = RemoteDesktopCapabilities {
+            codecs: vec![],
+            ..RemoteDesktopCapabilities::default()
+        };
+        assert!(matches!(
+            RemoteDesktopCapabilities::default().negotiate(&host),
+            Err(MisaError::RemoteDesktop(message)) if message.contains("codec")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_inbound_burst_above_limit_is_rejected() {
+        let (manager, _data_dir) = test_manager_with(DeviceConfig {
+            inbound_messages_per_second: 1.0,
+            inbound_message_burst: 5,
+            ..DeviceConfig::default()
+        })
+        .await;
+
+        let (connection, _rx) = local_connection("peer", chrono::Utc::now());
+        manager.register_connection(connection).await;
+
+        let mut accepted = 0;
+        let mut rejected = 0;
+        for _ in 0..20 {
+            let message = DeviceMessage {
+                message_id: uuid::Uuid::new_v4().to_string(),
+                source_device_id: "peer".to_string(),
+                target_device_id: None,
+                message_type: MessageType::Heartbeat,
+                payload: serde_json::Value::Null,
+                timestamp: chrono::Utc::now(),
+                encrypted: false,
+                priority: MessagePriority::Low,
+            };
+            match manager.handle_incoming_message("peer", message).await {
+                Ok(()) => accepted += 1,
+                Err(MisaError::RateLimit(_)) => rejected += 1,
+                Err(e) => panic!("unexpected error: {}", e),
+            }
+        }
+
+        assert_eq!(accepted, 5);
+        assert_eq!(rejected, 15);
+        assert!(manager.active_connections.read().await.contains_key("peer"));
+
+        let audited = manager
+            .security_manager
+            .query_audit_log(&crate::security::AuditQuery {
+                action: Some("device_rate_limited".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(audited.len(), 1);
+        assert_eq!(audited[0].resource, "peer");
+
+        // Claiming another source does not buy the connection a fresh budget
+        let mut spoofed = heartbeat_message("peer");
+        spoofed.source_device_id = "someone-else".to_string();
+        assert!(matches!(
+            manager.handle_incoming_message("peer", spoofed).await,
+            Err(MisaError::RateLimit(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_idle_inbound_buckets_are_evicted_at_capacity() {
+        let (manager, _data_dir) = test_manager_with(DeviceConfig {
+            inbound_messages_per_second: 1000.0,
+            inbound_message_burst: 1,
+            ..DeviceConfig::default()
+        })
+        .await;
+
+        for index in 0..MAX_INBOUND_BUCKETS {
+            let connection = format!("peer-{}", index);
+            manager.admit_inbound(&connection, &heartbeat_message(&connection)).await.unwrap();
+        }
+        assert_eq!(manager.inbound_limits.read().await.len(), MAX_INBOUND_BUCKETS);
+
+        // Every bucket refills within a millisecond, so all of them are idle by now
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        manager.admit_inbound("newcomer", &heartbeat_message("newcomer")).await.unwrap();
+        let limits = manager.inbound_limits.read().await;
+        assert_eq!(limits.len(), 1);
+        assert!(limits.contains_key("newcomer"));
+    }
+
+    fn heartbeat_message(source_device_id: &str) -> DeviceMessage {
+        DeviceMessage {
+            message_id: uuid::Uuid::new_v4().to_string(),
+            source_device_id: source_device_id.to_string(),
+            target_device_id: None,
+            message_type: MessageType::Heartbeat,
+            payload: serde_json::Value::Null,
+            timestamp: chrono::Utc::now(),
+            encrypted: false,
+            priority: MessagePriority::Low,
+        }
+    }
+
+    /// Two in-process peer connections joined by an open data channel
+    async fn webrtc_pair() -> (Arc<RTCPeerConnection>, Arc<RTCPeerConnection>, Arc<RTCDataChannel>, Arc<RTCDataChannel>) {
+        use webrtc::api::APIBuilder;
+        use webrtc::peer_connection::configuration::RTCConfiguration;
+
+        let api = APIBuilder::new().build();
+        let local = Arc::new(api.new_peer_connection(RTCConfiguration::default()).await.unwrap());
+        let remote = Arc::new(api.new_peer_connection(RTCConfiguration::default()).await.unwrap());
+
+        let channel = local.create_data_channel("misa", None).await.unwrap();
+        let (opened_tx, opened_rx) = oneshot::channel::<()>();
+        channel.on_open(Box::new(move || {
+            let _ = opened_tx.send(());
+            Box::pin(async {})
+        }));
+
+        let (remote_channel_tx, mut remote_channel_rx) = mpsc::unbounded_channel();
+        remote.on_data_channel(Box::new(move |channel| {
+            let _ = remote_channel_tx.send(channel);
+            Box::pin(async {})
+        }));
+
+        // In-process signalling: hand each side the other's description once ICE gathering is done
+        let offer = local.create_offer(None).await.unwrap();
+        let mut gathered = local.gathering_complete_promise().await;
+        local.set_local_description(offer).await.unwrap();
+        let _ = gathered.recv().await;
+        remote.set_remote_description(local.local_description().await.unwrap()).await.unwrap();
+
+        let answer = remote.create_answer(None).await.unwrap();
+        let mut gathered = remote.gathering_complete_promise().await;
+        remote.set_local_description(answer).await.unwrap();
+        let _ = gathered.recv().await;
+        local.set_remote_description(remote.local_description().await.unwrap()).await.unwrap();
+
+        tokio::time::timeout(Duration::from_secs(10), opened_rx).await.unwrap().unwrap();
+        let remote_channel = tokio::time::timeout(Duration::from_secs(10), remote_channel_rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        (local, remote, channel, remote_channel)
+    }
+
+    #[tokio::test]
+    async fn test_webrtc_data_channel_exchanges_messages() {
+        let (local, remote, channel, remote_channel) = webrtc_pair().await;
+        let (dialer, _dialer_dir) = test_manager().await;
+        let (acceptor, _acceptor_dir) = test_manager().await;
+        dialer.security_manager.register_device_key(acceptor.device_id(), &[9u8; 32]).await.unwrap();
+        acceptor.security_manager.register_device_key(dialer.device_id(), &[9u8; 32]).await.unwrap();
+
+        let (registered, accepted) = tokio::join!(
+            dialer.register_webrtc_connection(
+                acceptor.device_id(),
+                WebRTCConnection::new(acceptor.device_id(), Arc::clone(&local), channel),
+            ),
+            acceptor.accept_webrtc_connection(WebRTCConnection::new(dialer.device_id(), Arc::clone(&remote), remote_channel)),
+        );
+        registered.unwrap();
+        assert_eq!(accepted.unwrap(), dialer.device_id());
+
+        // The acceptor declines the task, but the request and its answer crossed the channel
+        let response = dialer
+            .send_request(acceptor.device_id(), serde_json::json!({ "task": "ping" }), Duration::from_secs(10))
+            .await
+            .unwrap();
+        assert!(response["error"].is_string());
+
+        local.close().await.unwrap();
+        remote.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_webrtc_data_channel_refuses_unpaired_devices() {
+        let (local, remote, channel, remote_channel) = webrtc_pair().await;
+        let (dialer, _dialer_dir) = test_manager().await;
+        let (acceptor, _acceptor_dir) = test_manager().await;
+        dialer.security_manager.register_device_key(acceptor.device_id(), &[9u8; 32]).await.unwrap();
+
+        let (registered, accepted) = tokio::join!(
+            dialer.register_webrtc_connection(
+                acceptor.device_id(),
+                WebRTCConnection::new(acceptor.device_id(), Arc::clone(&local), channel),
+            ),
+            acceptor.accept_webrtc_connection(WebRTCConnection::new(dialer.device_id(), Arc::clone(&remote), remote_channel)),
+        );
+        assert!(accepted.is_err());
+        assert!(registered.is_err());
+        assert!(!dialer.active_connections.read().await.contains_key(acceptor.device_id()));
+        assert!(!acceptor.active_connections.read().await.contains_key(dialer.device_id()));
+
+        local.close().await.unwrap();
+        remote.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_screen_capture_requires_consent() {
+        let (manager, data_dir) = test_manager_with(DeviceConfig::default()).await;
+        let privacy_controls = screen_capture_privacy(&data_dir, false).await;
+        let manager = manager.with_privacy_controls(privacy_controls.clone());
+        let remote_desktop = &manager.remote_desktop_manager;
+
+        let result = remote_desktop
+            .start_session(TEST_USER, "workstation", &RemoteDesktopCapabilities::default(), view_only_permissions(), None)
+            .await;
+        assert!(matches!(result, Err(MisaError::Privacy(_))));
+        assert!(remote_desktop.list_sessions().await.is_empty());
+        assert!(remote_desktop.capture_streams.read().await.is_empty());
+
+        // Consent does not help while the source is switched off
+        privacy_controls
+            .insert_test_consent(TEST_USER, crate::privacy::ConsentType::ScreenCapture)
+            .await;
+        privacy_controls.set_data_source_control(SCREEN_CAPTURE_SOURCE, false).await.unwrap();
+        assert!(matches!(
+            remote_desktop.screen_capturer.start_capture(TEST_USER, "session".to_string(), None, 8_000).await,
+            Err(MisaError::Privacy(_))
+        ));
+
+        // A capturer nobody attached privacy controls to never captures
+        assert!(matches!(
+            ScreenCapturer::new().start_capture(TEST_USER, "session".to_string(), None, 8_000).await,
+            Err(MisaError::Privacy(_))
+        ));
+
+        let audited = manager
+            .security_manager
+            .query_audit_log(&crate::security::AuditQuery {
+                action: Some("screen_capture".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(audited.len(), 2);
+        assert!(audited.iter().all(|entry| matches!(entry.result, AuditResult::Failure)));
+    }
+
+    #[tokio::test]
+    async fn test_consented_screen_capture_is_audited() {
+        let (manager, _data_dir) = test_manager().await;
+        let remote_desktop = &manager.remote_desktop_manager;
+
+        let session_id = remote_desktop
+            .start_session(TEST_USER, "workstation", &RemoteDesktopCapabilities::default(), view_only_permissions(), None)
+            .await
+            .unwrap();
+        assert!(remote_desktop.capture_streams.read().await.contains_key(&session_id));
+
+        let audited = manager
+            .security_manager
+            .query_audit_log(&crate::security::AuditQuery {
+                user_id: Some(TEST_USER.to_string()),
+                action: Some("screen_capture".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(audited.len(), 1);
+        assert_eq!(audited[0].resource, session_id);
+        assert!(matches!(audited[0].result, AuditResult::Success));
+    }
+
+    #[tokio::test]
+    async fn test_device_groups_are_persisted() {
+        let (manager, data_dir) = test_manager().await;
+        let dir = data_dir.path().to_str().unwrap();
+        let manager = manager.with_groups_store(dir).await.unwrap();
+
+        let laptops = manager
+            .create_group("Laptops", vec!["workstation".to_string(), "phone".to_string(), "workstation".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(laptops.device_ids, vec!["workstation".to_string(), "phone".to_string()]);
+        assert!(matches!(manager.create_group(" ", vec!["phone".to_string()]).await, Err(MisaError::Validation(_))));
+        assert!(matches!(
+            manager.update_group("missing", "Ghosts", vec!["phone".to_string()]).await,
+            Err(MisaError::NotFound(_))
+        ));
+
+        let updated = manager
+            .update_group(&laptops.id, "Portables", vec!["phone".to_string(), "nas".to_string()])
+            .await
+            .unwrap();
+
+        let (reloaded, _other_dir) = test_manager().await;
+        let reloaded = reloaded.with_groups_store(dir).await.unwrap();
+        assert_eq!(reloaded.list_groups().await, vec![updated.clone()]);
+
+        assert!(reloaded.delete_group(&updated.id).await.unwrap());
+        assert!(!reloaded.delete_group(&updated.id).await.unwrap());
+        let (reloaded, _other_dir) = test_manager().await;
+        assert!(reloaded.with_groups_store(dir).await.unwrap().list_groups().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_group_message_skips_offline_members() {
+        let (manager, _data_dir) = test_manager().await;
+        let (phone, mut phone_rx) = local_connection("phone", chrono::Utc::now());
+        let (workstation, mut workstation_rx) = local_connection("workstation", chrono::Utc::now());
+        manager.register_connection(phone).await;
+        manager.register_connection(workstation).await;
+
+        let group = manager
+            .create_group("Everything", vec!["phone".to_string(), "nas".to_string(), "workstation".to_string()])
+            .await
+            .unwrap();
+
+        let message = DeviceMessage {
+            message_id: "group-1".to_string(),
+            source_device_id: "local".to_string(),
+            target_device_id: None,
+            message_type: MessageType::ControlCommand,
+            payload: serde_json::json!({ "command": "lock" }),
+            timestamp: chrono::Utc::now(),
+            encrypted: false,
+            priority: MessagePriority::Normal,
+        };
+        let results = manager.send_to_group(&group.id, message).await.unwrap();
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results["phone"], DeliveryResult::Delivered);
+        assert_eq!(results["workstation"], DeliveryResult::Delivered);
+        assert_eq!(results["nas"], DeliveryResult::Offline);
+
+        let received = phone_rx.try_recv().unwrap();
+        assert_eq!(received.target_device_id.as_deref(), Some("phone"));
+        assert_eq!(workstation_rx.try_recv().unwrap().target_device_id.as_deref(), Some("workstation"));
+
+        assert!(matches!(
+            manager.send_to_group("missing", received).await,
+            Err(MisaError::NotFound(_))
+        ));
+    }
+
+    fn retry_config(message_retry_attempts: u32, message_retry_base_delay_ms: u64) -> DeviceConfig {
+        DeviceConfig {
+            message_retry_attempts,
+            message_retry_base_delay_ms,
+            ..DeviceConfig::default()
+        }
+    }
+
+    fn command_for(device_id: &str) -> DeviceMessage {
+        DeviceMessage {
+            message_id: uuid::Uuid::new_v4().to_string(),
+            source_device_id: "local".to_string(),
+            target_device_id: Some(device_id.to_string()),
+            message_type: MessageType::ControlCommand,
+            payload: serde_json::json!({ "command": "sync" }),
+            timestamp: chrono::Utc::now(),
+            encrypted: false,
+            priority: MessagePriority::Normal,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_retries_until_device_reconnects() {
+        // Attempts at 0ms and 100ms fail, the one at 300ms goes out on the new connection
+        let (manager, _data_dir) = test_manager_with(retry_config(3, 100)).await;
+        let (broken, broken_rx) = local_connection("phone", chrono::Utc::now());
+        drop(broken_rx);
+        manager.register_connection(broken).await;
+
+        let sender = manager.clone();
+        let message = command_for("phone");
+        let message_id = message.message_id.clone();
+        let send = tokio::spawn(async move { sender.send_message(message).await });
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let (healthy, mut healthy_rx) = local_connection("phone", chrono::Utc::now());
+        manager.register_connection(healthy).await;
+
+        send.await.unwrap().unwrap();
+        assert_eq!(healthy_rx.try_recv().unwrap().message_id, message_id);
+        assert!(manager.drain_dead_letters().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_undeliverable_message_is_dead_lettered() {
+        let (manager, _data_dir) = test_manager_with(retry_config(2, 10)).await;
+        let (broken, broken_rx) = local_connection("phone", chrono::Utc::now());
+        drop(broken_rx);
+        manager.register_connection(broken).await;
+
+        let message = command_for("phone");
+        assert!(matches!(manager.send_message(message.clone()).await, Err(MisaError::Device(_))));
+
+        let dead_letters = manager.drain_dead_letters().await;
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].device_id, "phone");
+        assert_eq!(dead_letters[0].message.message_id, message.message_id);
+        assert_eq!(dead_letters[0].attempts, 3);
+        assert!(manager.drain_dead_letters().await.is_empty());
+    }
+
+    struct AcceptAllTransfers;
+
+    impl TransferApprover for AcceptAllTransfers {
+        fn approve(&self, _source_device_id: &str, _request: &FileTransferRequest) -> bool {
+            true
+        }
+    }
+
+    /// Forward everything `rx` receives into `manager` as if it came over the network
+    fn forward_to(manager: Arc<DeviceManager>, peer_id: &str, mut rx: mpsc::UnboundedReceiver<DeviceMessage>) {
+        let peer_id = peer_id.to_string();
+        tokio::spawn(async move {
+            while let Some(message) = rx.recv().await {
+                let _ = manager.handle_incoming_message(&peer_id, message).await;
+            }
+        });
+    }
+
+    #[tokio::test]
+    async fn test_granted_file_transfer_completes() {
+        let (mut sender, data_dir) = test_manager().await;
+        sender.remote_desktop_manager.file_transfer_manager.encryption_required = false;
+        let (mut receiver, receiver_dir) = test_manager().await;
+        receiver.remote_desktop_manager.file_transfer_manager.download_dir = receiver_dir.path().join("downloads");
+        // Neither side shares a key, so neither may require encryption
+        receiver.remote_desktop_manager.file_transfer_manager.encryption_required = false;
+        let sender = Arc::new(sender);
+        let receiver = Arc::new(receiver.with_transfer_approver(Arc::new(AcceptAllTransfers)));
+
+        let (to_receiver, receiver_rx) = local_connection("peer", chrono::Utc::now());
+        sender.register_connection(to_receiver).await;
+        forward_to(Arc::clone(&receiver), sender.device_id(), receiver_rx);
+        let (to_sender, sender_rx) = local_connection(sender.device_id(), chrono::Utc::now());
+        receiver.register_connection(to_sender).await;
+        forward_to(Arc::clone(&sender), "peer", sender_rx);
+
+        let file_path = data_dir.path().join("notes.txt");
+        std::fs::write(&file_path, b"meeting notes").unwrap();
+        let file_path = file_path.to_str().unwrap();
+
+        let grant = sender.request_transfer_grant("peer", file_path).await.unwrap();
+        assert_eq!(grant.file_name, "notes.txt");
+        let transfer_id = sender.transfer_file("peer", file_path, grant.clone()).await.unwrap();
+        assert_eq!(transfer_id, grant.transfer_id);
+        wait_for_status(&sender, &transfer_id, |s| matches!(s, FileTransferStatus::Completed)).await;
+
+        let received = receiver_dir.path().join("downloads").join(format!("{}-notes.txt", transfer_id));
+        for _ in 0..100 {
+            if std::fs::read(&received).map_or(false, |bytes| bytes == b"meeting notes") {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("File never arrived at {}", received.display());
+    }
+
+    #[tokio::test]
+    async fn test_default_approver_grants_paired_devices_only() {
+        let (sender, data_dir) = test_manager().await;
+        let (mut receiver, receiver_dir) = test_manager().await;
+        receiver.remote_desktop_manager.file_transfer_manager.download_dir = receiver_dir.path().join("downloads");
+        sender.devices.write().await.insert("peer".to_string(), test_device("peer", false, 4096, false));
+        let sender_id = sender.device_id().to_string();
+        receiver.devices.write().await.insert(sender_id.clone(), test_device(&sender_id, false, 4096, false));
+        let sender = Arc::new(sender);
+        let receiver = Arc::new(receiver);
+
+        let (to_receiver, receiver_rx) = local_connection("peer", chrono::Utc::now());
+        sender.register_connection(to_receiver).await;
+        forward_to(Arc::clone(&receiver), &sender_id, receiver_rx);
+        let (to_sender, sender_rx) = local_connection(&sender_id, chrono::Utc::now());
+        receiver.register_connection(to_sender).await;
+        forward_to(Arc::clone(&sender), "peer", sender_rx);
+
+        let file_path = data_dir.path().join("notes.txt");
+        std::fs::write(&file_path, b"meeting notes").unwrap();
+        let file_path = file_path.to_str().unwrap();
+
+        // Without a shared key the sender is not trusted yet
+        let declined = sender.request_transfer_grant("peer", file_path).await;
+        assert!(matches!(declined, Err(MisaError::Permission(_))));
+
+        sender.exchange_device_key("peer").await.unwrap();
+        let grant = sender.request_transfer_grant("peer", file_path).await.unwrap();
+        assert_eq!(grant.source_device_id, sender_id);
+
+        let transfer_id = sender.transfer_file("peer", file_path, grant).await.unwrap();
+        wait_for_status(&sender, &transfer_id, |s| matches!(s, FileTransferStatus::Completed)).await;
+        let received = receiver_dir.path().join("downloads").join(format!("{}-notes.txt", transfer_id));
+        for _ in 0..100 {
+            if std::fs::read(&received).map_or(false, |bytes| bytes == b"meeting notes") {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("File never arrived at {}", received.display());
+    }
+
+    #[tokio::test]
+    async fn test_missing_or_expired_transfer_grant_is_rejected() {
+        let (receiver, receiver_dir) = test_manager().await;
+        let receiver = &receiver.remote_desktop_manager.file_transfer_manager;
+        let request = FileTransferRequest {
+            file_name: "notes.txt".to_string(),
+            file_size: 13,
+            encrypted: false,
+            grant: None,
+        };
+        let announce = |request: &FileTransferRequest| {
+            FileTransferManager::transfer_message("laptop", "receiver", MessageType::FileTransferRequest, serde_json::to_value(request).unwrap())
+        };
+
+        // No grant at all
+        let result = receiver.handle_incoming(&announce(&request)).await;
+        assert!(matches!(result, Err(MisaError::Permission(_))));
+
+        // Expired grant; re-signing keeps the signature valid so only the expiry is at fault
+        let mut expired = receiver.issue_grant("laptop", &request).await.unwrap();
+        expired.expires_at = chrono::Utc::now().timestamp() - 1;
+        let signature = receiver.security_manager.sign_data(&expired.signed_payload().unwrap(), TRANSFER_GRANT_PURPOSE).await.unwrap();
+        expired.signature = encode_hex(&signature);
+        let result = receiver.handle_incoming(&announce(&FileTransferRequest { grant: Some(expired.clone()), ..request.clone() })).await;
+        assert!(matches!(result, Err(MisaError::Permission(_))));
+
+        // Tampered grant
+        let mut forged = receiver.issue_grant("laptop", &request).await.unwrap();
+        forged.file_size = 1024;
+        let result = receiver.handle_incoming(&announce(&FileTransferRequest { file_size: 1024, grant: Some(forged), ..request.clone() })).await;
+        assert!(matches!(result, Err(MisaError::Permission(_))));
+        assert!(receiver.incoming_transfers.read().await.is_empty());
+
+        // The sending side refuses an expired grant before anything goes out
+        let (sender, _sender_dir) = test_manager().await;
+        let file_path = receiver_dir.path().join("notes.txt");
+        std::fs::write(&file_path, b"meeting notes").unwrap();
+        let result = sender.transfer_file("receiver", file_path.to_str().unwrap(), expired).await;
+        assert!(matches!(result, Err(MisaError::Permission(_))));
+    }
+
+    /// Refuses a set number of connection attempts, then hands out in-process connections
+    struct FlakyConnector {
+        failures_left: std::sync::atomic::AtomicU32,
+        attempts: std::sync::atomic::AtomicU32,
+    }
+
+    impl FlakyConnector {
+        fn new(failures: u32) -> Arc<Self> {
+            Arc::new(Self {
+                failures_left: std::sync::atomic::AtomicU32::new(failures),
+                attempts: std::sync::atomic::AtomicU32::new(0),
+            })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl DeviceConnector for FlakyConnector {
+        async fn connect(&self, _manager: &DeviceManager, device: &DeviceInfo) -> MisaResult<DeviceConnection> {
+            use std::sync::atomic::Ordering::SeqCst;
+
+            self.attempts.fetch_add(1, SeqCst);
+            if self.failures_left.fetch_update(SeqCst, SeqCst, |left| left.checked_sub(1)).is_ok() {
+                return Err(MisaError::Device(format!("{} is unreachable", device.device_id)));
+            }
+            Ok(local_connection(&device.device_id, chrono::Utc::now()).0)
+        }
+    }
+
+    async fn reconnecting_manager(connector: Arc<FlakyConnector>, max_attempts: u32) -> (DeviceManager, tempfile::TempDir) {
+        let (manager, data_dir) = test_manager_with(DeviceConfig {
+            reconnect_max_attempts: max_attempts,
+            reconnect_base_delay_ms: 10,
+            reconnect_max_delay_ms: 40,
+            ..DeviceConfig::default()
+        })
+        .await;
+        // Only paired devices, which hold a device key, are reconnected to
+        manager.security_manager.register_device_key("phone", &[7u8; 32]).await.unwrap();
+
+        let manager = manager.with_connector(connector);
+        seed_devices(&manager).await;
+        (manager, data_dir)
+    }
+
+    #[tokio::test]
+    async fn test_dropped_paired_device_is_reconnected() {
+        let connector = FlakyConnector::new(1);
+        let (manager, _data_dir) = reconnecting_manager(connector.clone(), 3).await;
+        let mut events = manager.subscribe_connection_events();
+
+        let (connection, _rx) = local_connection("phone", chrono::Utc::now());
+        manager.register_connection(connection).await;
+        manager.connection_lost("phone").await;
+
+        let mut seen = Vec::new();
+        for _ in 0..3 {
+            seen.push(tokio::time::timeout(Duration::from_secs(5), events.recv()).await.unwrap().unwrap());
+        }
+        let phone = || "phone".to_string();
+        assert_eq!(
+            seen,
+            vec![
+                DeviceConnectionEvent::DeviceConnected { device_id: phone() },
+                DeviceConnectionEvent::DeviceDisconnected { device_id: phone() },
+                DeviceConnectionEvent::DeviceConnected { device_id: phone() },
+            ]
+        );
+
+        // The first attempt failed and the second one got through
+        assert_eq!(connector.attempts.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert!(manager.active_connections.read().await.contains_key("phone"));
+        let device = manager.get_device("phone").await.unwrap().unwrap();
+        assert!(matches!(device.status, DeviceStatus::Online));
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_gives_up_after_max_attempts() {
+        let connector = FlakyConnector::new(u32::MAX);
+        let (manager, _data_dir) = reconnecting_manager(connector.clone(), 2).await;
+
+        let (connection, _rx) = local_connection("phone", chrono::Utc::now());
+        manager.register_connection(connection).await;
+        let mut events = manager.subscribe_connection_events();
+        manager.connection_lost("phone").await;
+
+        let disconnected = events.recv().await.unwrap();
+        assert_eq!(disconnected, DeviceConnectionEvent::DeviceDisconnected { device_id: "phone".to_string() });
+        let gave_up = tokio::time::timeout(Duration::from_secs(5), events.recv()).await.unwrap().unwrap();
+        assert_eq!(gave_up, DeviceConnectionEvent::ReconnectFailed { device_id: "phone".to_string(), attempts: 2 });
+
+        assert_eq!(connector.attempts.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert!(!manager.active_connections.read().await.contains_key("phone"));
+    }
+
+    async fn type_checked_transfers(data_dir: &tempfile::TempDir) -> FileTransferManager {
+        let security_manager = test_security_manager(data_dir).await;
+        let config = crate::kernel::FileTransferConfig {
+            allowed_types: vec!["image/*".to_string(), "text/plain".to_string()],
+            ..crate::kernel::FileTransferConfig::default()
+        };
+        FileTransferManager::new(&config, "laptop", security_manager, Arc::new(RwLock::new(HashMap::new())))
+    }
+
+    async fn send_file(transfers: &FileTransferManager, path: &Path, content: &[u8]) -> MisaResult<String> {
+        std::fs::write(path, content).unwrap();
+        let request = FileTransferRequest {
+            file_name: path.file_name().unwrap().to_string_lossy().to_string(),
+            file_size: content.len() as u64,
+            encrypted: false,
+            grant: None,
+        };
+        let grant = transfers.issue_grant(&transfers.device_id, &request).await.unwrap();
+        transfers.start_transfer("peer", path.to_str().unwrap(), grant).await
+    }
+
+    const PNG_HEADER: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+    #[tokio::test]
+    async fn test_allowed_file_type_is_transferred() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let transfers = type_checked_transfers(&data_dir).await;
+
+        let transfer_id = send_file(&transfers, &data_dir.path().join("photo.png"), PNG_HEADER).await.unwrap();
+        let notes_id = send_file(&transfers, &data_dir.path().join("notes"), "Grüße from the meeting".as_bytes()).await.unwrap();
+
+        let active = transfers.active_transfers.read().await;
+        assert_eq!(active[&transfer_id].mime_type, "image/png");
+        assert_eq!(active[&notes_id].mime_type, "text/plain");
+    }
+
+    #[tokio::test]
+    async fn test_disallowed_file_type_is_rejected() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let transfers = type_checked_transfers(&data_dir).await;
+
+        let result = send_file(&transfers, &data_dir.path().join("report.pdf"), b"%PDF-1.7\n%\xe2\xe3\xcf\xd3").await;
+
+        assert!(matches!(result, Err(MisaError::FileTransfer(message)) if message.contains("application/pdf")));
+        assert!(transfers.active_transfers.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_renamed_executable_is_caught_by_content() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let transfers = type_checked_transfers(&data_dir).await;
+
+        // An ELF binary dressed up as a picture
+        let mut elf = b"\x7fELF\x02\x01\x01\0".to_vec();
+        elf.resize(64, 0);
+        let result = send_file(&transfers, &data_dir.path().join("holiday.png"), &elf).await;
+
+        assert!(matches!(result, Err(MisaError::FileTransfer(message)) if !message.contains("image/png")));
+        assert!(transfers.active_transfers.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_svg_needs_an_explicit_entry() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let mut transfers = type_checked_transfers(&data_dir).await;
+        let svg = b"<svg xmlns=\"http://www.w3.org/2000/svg\"><script>alert(1)</script></svg>";
+
+        let result = send_file(&transfers, &data_dir.path().join("logo.svg"), svg).await;
+        assert!(matches!(result, Err(MisaError::FileTransfer(message)) if message.contains("image/svg+xml")));
+
+        transfers.allowed_file_types.push("image/svg+xml".to_string());
+        let transfer_id = send_file(&transfers, &data_dir.path().join("logo.svg"), svg).await.unwrap();
+        assert_eq!(transfers.active_transfers.read().await[&transfer_id].mime_type, "image/svg+xml");
+    }
+
+    #[tokio::test]
+    async fn test_receiver_rejects_disallowed_content() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let receiver = type_checked_transfers(&data_dir).await;
+        let incoming_path = data_dir.path().join("holiday.png");
+        std::fs::write(&incoming_path, b"").unwrap();
+        let mut elf = b"\x7fELF\x02\x01\x01\0".to_vec();
+        elf.resize(64, 0);
+        receiver.incoming_transfers.write().await.insert("transfer-1".to_string(), IncomingTransfer {
+            transfer_id: "transfer-1".to_string(),
+            source_device_id: "peer".to_string(),
+            file_path: incoming_path.clone(),
+            file_size: elf.len() as u64,
+            bytes_received: 0,
+            received_offsets: HashSet::new(),
+            encrypted: false,
+        });
+
+        let chunk = FileTransferManager::transfer_message(
+            "peer",
+            "laptop",
+            MessageType::FileTransferData,
+            serde_json::json!({ "transfer_id": "transfer-1", "offset": 0, "data": BASE64.encode(&elf) }),
+        );
+        let result = receiver.handle_incoming(&chunk).await;
+
+        assert!(matches!(result, Err(MisaError::FileTransfer(_))));
+        assert!(receiver.incoming_transfers.read().await.is_empty());
+        assert!(!incoming_path.exists());
+    }
+
+    fn steady_sample(latency_ms: u64) -> Option<LatencySample> {
+        Some(LatencySample {
+            latency_ms,
+            jitter_ms: 0,
+            packet_loss: 0.0,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_stability_drops_as_jitter_rises() {
+        let monitor = ConnectionQualityMonitor::new();
+        monitor.update_connection_quality("phone", "192.168.1.20:5353".parse().unwrap()).await.unwrap();
+
+        for _ in 0..10 {
+            monitor.record_probe("phone", steady_sample(20)).await;
+        }
+        let steady = monitor.active_connections.read().await["phone"].clone();
+        assert_eq!(steady.jitter_ms, 0);
+        assert_eq!(steady.stability_score, 1.0);
+
+        for latency_ms in [5, 60, 10, 90, 15, 120] {
+            monitor.record_probe("phone", steady_sample(latency_ms)).await;
+        }
+        let jittery = monitor.active_connections.read().await["phone"].clone();
+        assert!(jittery.jitter_ms > 0);
+        assert!(jittery.stability_score < steady.stability_score);
+        assert!(jittery.stability_score > 0.0);
+        assert_eq!(jittery.uptime_percentage, 100.0);
+    }
+
+    #[tokio::test]
+    async fn test_stability_and_uptime_drop_as_probes_go_unanswered() {
+        let monitor = ConnectionQualityMonitor::new();
+        monitor.update_connection_quality("laptop", "192.168.1.30:5353".parse().unwrap()).await.unwrap();
+
+        for _ in 0..4 {
+            monitor.record_probe("laptop", steady_sample(20)).await;
+        }
+        let mut previous = monitor.active_connections.read().await["laptop"].clone();
+        assert_eq!(previous.stability_score, 1.0);
+
+        for _ in 0..4 {
+            monitor.record_probe("laptop", None).await;
+            let quality = monitor.active_connections.read().await["laptop"].clone();
+            assert!(quality.packet_loss > previous.packet_loss);
+            assert!(quality.stability_score < previous.stability_score);
+            assert!(quality.uptime_percentage < previous.uptime_percentage);
+            previous = quality;
+        }
+
+        let quality = monitor.active_connections.read().await["laptop"].clone();
+        assert_eq!(quality.uptime_percentage, 50.0);
+        assert!((quality.packet_loss - 0.5).abs() < f32::EPSILON);
+        assert!(quality.stability_score <= 0.5);
+    }
+
+    #[tokio::test]
+    async fn test_task_request_is_declined_without_handler() {
+        let (manager, _data_dir) = test_manager().await;
+        let (peer, mut peer_rx) = local_connection("phone", chrono::Utc::now());
+        manager.register_connection(peer).await;
+
+        manager.handle_incoming_message("phone", DeviceMessage {
+            message_id: "task-1".to_string(),
+            source_device_id: "phone".to_string(),
+            target_device_id: Some("local".to_string()),
+            message_type: MessageType::TaskRequest,
+            payload: serde_json::json!({ "task": "ping" }),
+            timestamp: chrono::Utc::now(),
+            encrypted: false,
+            priority: MessagePriority::Normal,
+        }).await.unwrap();
+
+        let reply = tokio::time::timeout(Duration::from_secs(5), peer_rx.recv()).await.unwrap().unwrap();
+        assert!(matches!(reply.message_type, MessageType::TaskResponse));
+        assert_eq!(reply.payload["correlation_id"], "task-1");
+        assert!(reply.payload["result"]["error"].as_str().unwrap().contains("does not accept"));
+    }
+
+    /// Task handler that answers with the task it was given
+    struct EchoTaskHandler;
+
+    #[async_trait::async_trait]
+    impl TaskHandler for EchoTaskHandler {
+        async fn handle_task(&self, _source_device_id: &str, task: serde_json::Value) -> MisaResult<serde_json::Value> {
+            Ok(task)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tasks_run_only_for_paired_or_allowed_devices() {
+        let (manager, _data_dir) = test_manager().await;
+        let mut manager = manager.with_task_handler(Arc::new(EchoTaskHandler));
+        manager.config.task_delegation_devices = vec!["tablet".to_string()];
+        manager.security_manager.register_device_key("laptop", &[3u8; 32]).await.unwrap();
+
+        for (device_id, allowed) in [("phone", false), ("tablet", true), ("laptop", true)] {
+            let (peer, mut peer_rx) = local_connection(device_id, chrono::Utc::now());
+            manager.register_connection(peer).await;
+
+            let mut request = heartbeat_message(device_id);
+            request.message_type = MessageType::TaskRequest;
+            request.payload = serde_json::json!({ "task": "ping" });
+            manager.handle_incoming_message(device_id, request).await.unwrap();
+
+            let reply = tokio::time::timeout(Duration::from_secs(5), peer_rx.recv()).await.unwrap().unwrap();
+            let reply = manager.open_message(device_id, reply).await.unwrap();
+            assert_eq!(reply.source_device_id, manager.device_id());
+            assert_eq!(reply.target_device_id.as_deref(), Some(device_id));
+            if allowed {
+                assert_eq!(reply.payload["result"]["task"], "ping", "{} should be served", device_id);
+            } else {
+                assert!(reply.payload["result"]["error"].as_str().unwrap().contains("not allowed"));
+            }
+        }
+    }
+
+    /// Task handler that holds every task until released
+    struct BlockingTaskHandler(Arc<tokio::sync::Notify>);
+
+    #[async_trait::async_trait]
+    impl TaskHandler for BlockingTaskHandler {
+        async fn handle_task(&self, _source_device_id: &str, task: serde_json::Value) -> MisaResult<serde_json::Value> {
+            self.0.notified().await;
+            Ok(task)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_task_requests_beyond_the_limit_are_answered_busy() {
+        let release = Arc::new(tokio::sync::Notify::new());
+        let (manager, _data_dir) = test_manager_with(DeviceConfig {
+            max_concurrent_delegated_tasks: 1,
+            task_delegation_devices: vec!["tablet".to_string()],
+            ..DeviceConfig::default()
+        })
+        .await;
+        let manager = manager.with_task_handler(Arc::new(BlockingTaskHandler(Arc::clone(&release))));
+        let (peer, mut peer_rx) = local_connection("tablet", chrono::Utc::now());
+        manager.register_connection(peer).await;
+
+        for task in ["first", "second"] {
+            let mut request = heartbeat_message("tablet");
+            request.message_type = MessageType::TaskRequest;
+            request.payload = serde_json::json!({ "task": task });
+            manager.handle_incoming_message("tablet", request).await.unwrap();
+        }
+
+        let busy = tokio::time::timeout(Duration::from_secs(5), peer_rx.recv()).await.unwrap().unwrap();
+        let busy = manager.open_message("tablet", busy).await.unwrap();
+        assert!(busy.payload["result"]["error"].as_str().unwrap().contains("busy"));
+
+        release.notify_one();
+        let done = tokio::time::timeout(Duration::from_secs(5), peer_rx.recv()).await.unwrap().unwrap();
+        let done = manager.open_message("tablet", done).await.unwrap();
+        assert_eq!(done.payload["result"]["task"], "first");
+    }
+
+    #[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+    #[tokio::test]
+    async fn test_named_display_capture_matches_listed_bounds() {
+        let displays = ScreenCapturer::list_displays();
+        let Some(primary) = displays.iter().find(|display| display.is_primary).cloned() else {
+            // Headless runners have no display to capture
+            return;
+        };
+        assert_eq!(displays.iter().filter(|display| display.is_primary).count(), 1);
+
+        let (manager, _data_dir) = test_manager().await;
+        let capturer = &manager.remote_desktop_manager.screen_capturer;
+        let frame = capturer.capture_frame(TEST_USER, Some(primary.id), ImageFormat::PNG).await.unwrap();
+        // Bounds are in logical points; captured frames are in physical pixels
+        let physical = |points: u32| (points as f32 * primary.scale_factor).round() as u32;
+        assert_eq!((frame.width, frame.height), (physical(primary.bounds.width), physical(primary.bounds.height)));
+
+        let stream = capturer.start_capture(TEST_USER, "session".to_string(), None, 8_000).await.unwrap();
+        assert_eq!(stream.display_id, Some(primary.id));
+    }
+
+    #[tokio::test]
+    async fn test_capturing_unknown_display_fails() {
+        let (manager, _data_dir) = test_manager().await;
+        let capturer = &manager.remote_desktop_manager.screen_capturer;
+
+        let missing = u32::MAX;
+        assert!(matches!(
+            capturer.start_capture(TEST_USER, "session".to_string(), Some(missing), 8_000).await,
+            Err(MisaError::RemoteDesktop(_))
+        ));
+        assert!(matches!(
+            capturer.capture_frame(TEST_USER, Some(missing), ImageFormat::PNG).await,
+            Err(MisaError::RemoteDesktop(_))
+        ));
+    }
+
+    #[derive(Default)]
+    struct RecordingInjector(std::sync::Mutex<Vec<InputEvent>>);
+
+    impl InputInjector for RecordingInjector {
+        fn inject(&self, event: &InputEvent) -> MisaResult<()> {
+            self.0.lock().unwrap().push(event.clone());
+            Ok(())
+        }
+    }
+
+    fn mouse_only_permissions() -> RemoteDesktopPermissions {
+        RemoteDesktopPermissions {
+            view_screen: false,
+            control_mouse: true,
+            ..view_only_permissions()
+        }
+    }
+
+    /// Grants every session the same permissions, whatever was asked for
+    struct FixedApprover(RemoteDesktopPermissions);
+
+    impl RemoteDesktopApprover for FixedApprover {
+        fn approve(&self, _controller_device_id: &str, _requested: &RemoteDesktopPermissions) -> Option<RemoteDesktopPermissions> {
+            Some(self.0.clone())
+        }
+    }
+
+    /// A laptop and a phone hosting remote desktop for it, connected to each other
+    async fn remote_desktop_pair(phone: DeviceManager) -> (Arc<DeviceManager>, Arc<DeviceManager>, tempfile::TempDir) {
+        let (laptop, laptop_dir) = test_manager().await;
+        let mut host = test_device("phone", false, 4096, true);
+        host.capabilities.supports_remote_desktop = true;
+        laptop.devices.write().await.insert("phone".to_string(), host);
+        phone.devices.write().await.insert("laptop".to_string(), test_device("laptop", true, 16384, false));
+        let laptop = Arc::new(laptop);
+        let phone = Arc::new(phone);
+
+        let (to_phone, phone_rx) = local_connection("phone", chrono::Utc::now());
+        laptop.register_connection(to_phone).await;
+        forward_to(Arc::clone(&phone), "laptop", phone_rx);
+        let (to_laptop, laptop_rx) = local_connection("laptop", chrono::Utc::now());
+        phone.register_connection(to_laptop).await;
+        forward_to(Arc::clone(&laptop), "phone", laptop_rx);
+
+        laptop.security_manager.create_user(TEST_USER, "correct horse battery staple").await.unwrap();
+        laptop.security_manager.authenticate_password(TEST_USER, "correct horse battery staple").await.unwrap();
+        (laptop, phone, laptop_dir)
+    }
+
+    fn input_message(session_id: &str, input: InputEvent) -> DeviceMessage {
+        DeviceMessage {
+            message_id: uuid::Uuid::new_v4().to_string(),
+            source_device_id: "workstation".to_string(),
+            target_device_id: None,
+            message_type: MessageType::ControlCommand,
+            payload: serde_json::to_value(RemoteInputCommand { session_id: session_id.to_string(), input }).unwrap(),
+            timestamp: chrono::Utc::now(),
+            encrypted: false,
+            priority: MessagePriority::Normal,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mouse_move_command_reaches_injector() {
+        let (manager, _data_dir) = test_manager().await;
+        let injector = Arc::new(RecordingInjector::default());
+        let manager = manager.with_input_injector(injector.clone());
+        manager
+            .remote_desktop_manager
+            .host_session("session-1", "workstation", mouse_only_permissions())
+            .await
+            .unwrap();
+
+        manager
+            .handle_incoming_message("workstation", input_message("session-1", InputEvent::MouseMove { x: 120, y: 48 }))
+            .await
+            .unwrap();
+
+        assert_eq!(*injector.0.lock().unwrap(), vec![InputEvent::MouseMove { x: 120, y: 48 }]);
+    }
+
+    #[tokio::test]
+    async fn test_keyboard_command_refused_without_keyboard_permission() {
+        let (manager, _data_dir) = test_manager().await;
+        let injector = Arc::new(RecordingInjector::default());
+        let manager = manager.with_input_injector(injector.clone());
+        manager
+            .remote_desktop_manager
+            .host_session("session-1", "workstation", mouse_only_permissions())
+            .await
+            .unwrap();
+
+        let key = InputEvent::Key { key: "a".to_string(), action: InputAction::Click };
+        let result = manager.handle_incoming_message("workstation", input_message("session-1", key)).await;
+
+        assert!(matches!(result, Err(MisaError::Permission(_))));
+        assert!(injector.0.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_input_is_only_taken_from_the_controlling_connection() {
+        let (manager, _data_dir) = test_manager().await;
+        let injector = Arc::new(RecordingInjector::default());
+        let manager = manager.with_input_injector(injector.clone());
+        manager
+            .remote_desktop_manager
+            .host_session("session-1", "workstation", mouse_only_permissions())
+            .await
+            .unwrap();
+
+        // The message names the controller, but arrived over another device's connection
+        let result = manager
+            .handle_incoming_message("phone", input_message("session-1", InputEvent::MouseMove { x: 1, y: 1 }))
+            .await;
+        assert!(matches!(result, Err(MisaError::Permission(_))));
+
+        // Sessions this device controls elsewhere never replay input here
+        let client_session = manager
+            .remote_desktop_manager
+            .start_session(TEST_USER, "workstation", &RemoteDesktopCapabilities::default(), mouse_only_permissions(), None)
+            .await
+            .unwrap();
+        let result = manager
+            .handle_incoming_message("workstation", input_message(&client_session, InputEvent::MouseMove { x: 1, y: 1 }))
+            .await;
+        assert!(matches!(result, Err(MisaError::NotFound(_))));
+        assert!(injector.0.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_host_replays_input_from_the_device_that_started_the_session() {
+        let (phone, _phone_dir) = test_manager().await;
+        let injector = Arc::new(RecordingInjector::default());
+        let phone = phone
+            .with_input_injector(injector.clone())
+            .with_remote_desktop_approver(Arc::new(FixedApprover(mouse_only_permissions())));
+        let (laptop, phone, _laptop_dir) = remote_desktop_pair(phone).await;
+
+        // The laptop asks for the keyboard too, but the host only grants the mouse
+        let requested = RemoteDesktopPermissions { control_keyboard: true, ..mouse_only_permissions() };
+        let session_id = laptop.start_remote_desktop(TEST_USER, "phone", requested, None).await.unwrap();
+        assert!(!laptop.list_remote_desktop_sessions().await[0].permissions.control_keyboard);
+
+        let key = InputEvent::Key { key: "a".to_string(), action: InputAction::Click };
+        laptop.send_remote_input(&session_id, key).await.unwrap();
+        laptop.send_remote_input(&session_id, InputEvent::MouseMove { x: 64, y: 32 }).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while injector.0.lock().unwrap().is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(*injector.0.lock().unwrap(), vec![InputEvent::MouseMove { x: 64, y: 32 }]);
+
+        // Once the controller is gone its session no longer takes input
+        phone.connection_lost("laptop").await;
+        let result = phone
+            .handle_incoming_message("laptop", input_message(&session_id, InputEvent::MouseMove { x: 0, y: 0 }))
+            .await;
+        assert!(matches!(result, Err(MisaError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_host_grants_view_only_by_default_and_ends_sessions_on_request() {
+        let (phone, _phone_dir) = test_manager().await;
+        let injector = Arc::new(RecordingInjector::default());
+        let (laptop, phone, _laptop_dir) = remote_desktop_pair(phone.with_input_injector(injector.clone())).await;
+
+        let requested = RemoteDesktopPermissions { control_keyboard: true, ..mouse_only_permissions() };
+        let session_id = laptop.start_remote_desktop(TEST_USER, "phone", requested, None).await.unwrap();
+        let result = phone
+            .handle_incoming_message("laptop", input_message(&session_id, InputEvent::MouseMove { x: 1, y: 1 }))
+            .await;
+        assert!(matches!(result, Err(MisaError::Permission(_))));
+        assert!(injector.0.lock().unwrap().is_empty());
+
+        laptop.stop_remote_desktop(TEST_USER, &session_id).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while phone.remote_desktop_manager.hosted_sessions.read().await.contains_key(&session_id) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unencrypted_chunk_for_encrypted_transfer_is_rejected() {
+        let (receiver, receiver_dir) = test_manager().await;
+        let receiver = &receiver.remote_desktop_manager.file_transfer_manager;
+        let incoming_path = receiver_dir.path().join("incoming.txt");
+        std::fs::write(&incoming_path, b"").unwrap();
+        receiver.incoming_transfers.write().await.insert("transfer-1".to_string(), IncomingTransfer {
+            transfer_id: "transfer-1".to_string(),
+            source_device_id: "laptop".to_string(),
+            file_path: incoming_path.clone(),
+            file_size: 6,
+            bytes_received: 0,
+            received_offsets: HashSet::new(),
+            encrypted: true,
+        });
+
+        let chunk = FileTransferManager::transfer_message(
+            "laptop",
+            "receiver",
+            MessageType::FileTransferData,
+            serde_json::json!({ "transfer_id": "transfer-1", "offset": 0, "data": BASE64.encode(b"secret") }),
+        );
+        let result = receiver.handle_incoming(&chunk).await;
+        assert!(matches!(result, Err(MisaError::Encryption(_))));
+        assert!(std::fs::read(&incoming_path).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_chunks_from_another_device_are_rejected() {
+        let (receiver, receiver_dir) = test_manager().await;
+        let receiver = &receiver.remote_desktop_manager.file_transfer_manager;
+        let incoming_path = receiver_dir.path().join("incoming.txt");
+        std::fs::write(&incoming_path, b"").unwrap();
+        receiver.incoming_transfers.write().await.insert("transfer-1".to_string(), IncomingTransfer {
+            transfer_id: "transfer-1".to_string(),
+            source_device_id: "laptop".to_string(),
+            file_path: incoming_path.clone(),
+            file_size: 6,
+            bytes_received: 0,
+            received_offsets: HashSet::new(),
+            encrypted: false,
+        });
+
+        let chunk = FileTransferManager::transfer_message(
+            "intruder",
+            "receiver",
+            MessageType::FileTransferData,
+            serde_json::json!({ "transfer_id": "transfer-1", "offset": 0, "data": BASE64.encode(b"forged") }),
+        );
+        let result = receiver.handle_incoming(&chunk).await;
+        assert!(matches!(result, Err(MisaError::Permission(_))));
+        assert!(std::fs::read(&incoming_path).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_resent_chunk_is_counted_once() {
+        let (receiver, receiver_dir) = test_manager().await;
+        let receiver = &receiver.remote_desktop_manager.file_transfer_manager;
+        let incoming_path = receiver_dir.path().join("incoming.txt");
+        std::fs::write(&incoming_path, b"").unwrap();
+        receiver.incoming_transfers.write().await.insert("transfer-1".to_string(), IncomingTransfer {
+            transfer_id: "transfer-1".to_string(),
+            source_device_id: "laptop".to_string(),
+            file_path: incoming_path.clone(),
+            file_size: 12,
+            bytes_received: 0,
+            received_offsets: HashSet::new(),
+            encrypted: false,
+        });
+
+        let chunk = |offset: u64, data: &[u8]| {
+            FileTransferManager::transfer_message(
+                "laptop",
+                "receiver",
+                MessageType::FileTransferData,
+                serde_json::json!({ "transfer_id": "transfer-1", "offset": offset, "data": BASE64.encode(data) }),
+            )
+        };
+        assert_eq!(receiver.handle_incoming(&chunk(0, b"hello ")).await.unwrap(), None);
+        assert_eq!(receiver.handle_incoming(&chunk(0, b"hello ")).await.unwrap(), None);
+        assert_eq!(receiver.handle_incoming(&chunk(6, b"world!")).await.unwrap(), Some(incoming_path.clone()));
+        assert_eq!(std::fs::read(&incoming_path).unwrap(), b"hello world!");
+    }
+
+    #[tokio::test]
+    async fn test_receiver_requires_encryption_whatever_the_request_says() {
+        let (receiver, _receiver_dir) = test_manager().await;
+        let mut receiver = receiver.remote_desktop_manager.file_transfer_manager.clone();
+        receiver.encryption_required = true;
+        let mut request = FileTransferRequest {
+            file_name: "notes.txt".to_string(),
+            file_size: 13,
+            encrypted: false,
+            grant: None,
+        };
+        let grant = receiver.issue_grant("laptop", &request).await.unwrap();
+        assert!(grant.encrypted);
+
+        // The sender claims an unencrypted transfer and sends the announcement in the clear
+        request.grant = Some(grant);
+        let announce = FileTransferManager::transfer_message(
+            "laptop",
+            "receiver",
+            MessageType::FileTransferRequest,
+            serde_json::to_value(&request).unwrap(),
+        );
+        let result = receiver.handle_incoming(&announce).await;
+        assert!(matches!(result, Err(MisaError::Encryption(_))));
+        assert!(receiver.incoming_transfers.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_started_heartbeats_carry_the_device_id_and_evict_silent_peers() {
+        let (manager, _data_dir) = test_manager_with(DeviceConfig {
+            heartbeat_interval_secs: 1,
+            connection_idle_timeout_secs: 3,
+            ..DeviceConfig::default()
+        })
+        .await;
+
+        let (silent, _silent_rx) = local_connection("silent", chrono::Utc::now() - chrono::Duration::seconds(30));
+        let (fresh, mut fresh_rx) = local_connection("fresh", chrono::Utc::now());
+        manager.register_connection(silent).await;
+        manager.register_connection(fresh).await;
+
+        let mut events = manager.subscribe_connection_events();
+        manager.start_heartbeats().await;
+        manager.start_heartbeats().await;
+
+        let event = tokio::time::timeout(Duration::from_secs(5), events.recv()).await.unwrap().unwrap();
+        assert_eq!(event, DeviceConnectionEvent::DeviceDisconnected { device_id: "silent".to_string() });
+
+        let heartbeat = tokio::time::timeout(Duration::from_secs(5), fresh_rx.recv()).await.unwrap().unwrap();
+        assert!(matches!(heartbeat.message_type, MessageType::Heartbeat));
+        assert_eq!(heartbeat.source_device_id, manager.device_id());
+
+        manager.shutdown().await.unwrap();
+        assert!(manager.heartbeat_task.read().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stopping_a_session_requires_permission() {
+        let (manager, _data_dir) = test_manager().await;
+        let session_id = manager
+            .remote_desktop_manager
+            .start_session(TEST_USER, "workstation", &RemoteDesktopCapabilities::default(), view_only_permissions(), None)
+            .await
+            .unwrap();
+
+        let result = manager.stop_remote_desktop(TEST_USER, &session_id).await;
+        assert!(matches!(result, Err(MisaError::Permission(_))));
+        assert_eq!(manager.list_remote_desktop_sessions().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_discovery_announces_the_device_id() {
+        let (manager, _data_dir) = test_manager().await;
+
+        assert_eq!(manager.discovery_service.local_discovery_packet().device_id, manager.device_id());
+        assert_eq!(manager.clone().discovery_service.local_discovery_packet().device_id, manager.device_id());
+        assert!(Arc::ptr_eq(&manager.clone().discovery_service, &manager.discovery_service));
+    }
+
+    #[tokio::test]
+    async fn test_paired_devices_agree_keys_bound_to_their_connection() {
+        let (laptop, _laptop_dir) = test_manager().await;
+        let (phone, _phone_dir) = test_manager().await;
+        laptop.devices.write().await.insert("phone".to_string(), test_device("phone", false, 4096, true));
+        phone.devices.write().await.insert("laptop".to_string(), test_device("laptop", true, 16384, false));
+        let laptop = Arc::new(laptop);
+        let phone = Arc::new(phone);
+
+        let (to_phone, phone_rx) = local_connection("phone", chrono::Utc::now());
+        laptop.register_connection(to_phone).await;
+        forward_to(Arc::clone(&phone), "laptop", phone_rx);
+        let (to_laptop, laptop_rx) = local_connection("laptop", chrono::Utc::now());
+        phone.register_connection(to_laptop).await;
+        forward_to(Arc::clone(&laptop), "phone", laptop_rx);
+
+        assert!(matches!(laptop.exchange_device_key("tablet").await, Err(MisaError::Permission(_))));
+        laptop.exchange_device_key("phone").await.unwrap();
+
+        let message = DeviceMessage {
+            message_id: "clip-1".to_string(),
+            source_device_id: "laptop".to_string(),
+            target_device_id: Some("phone".to_string()),
+            message_type: MessageType::Heartbeat,
+            payload: serde_json::json!({ "secret": "hunter2" }),
+            timestamp: chrono::Utc::now(),
+            encrypted: true,
+            priority: MessagePriority::Normal,
+        };
+        let sealed = laptop.seal_message("phone", &message).await.unwrap();
+        assert_eq!(phone.open_message("laptop", sealed.clone()).await.unwrap().payload["secret"], "hunter2");
+
+        // Claiming to be the laptop over another connection does not select the laptop's key
+        phone.security_manager.register_device_key("tablet", &[9u8; 32]).await.unwrap();
+        let result = phone.handle_incoming_message("tablet", sealed).await;
+        assert!(matches!(result, Err(MisaError::Encryption(_))));
+    }
+
+    #[tokio::test]
+    async fn test_remote_desktop_negotiates_with_the_hosts_answered_capabilities() {
+        let (laptop, _laptop_dir) = test_manager().await;
+        let (mut phone, _phone_dir) = test_manager().await;
+        phone.remote_desktop_manager = phone.remote_desktop_manager.clone().with_capabilities(RemoteDesktopCapabilities {
+            protocols: vec![RemoteDesktopProtocol::VNC],
+            codecs: vec![ImageFormat::PNG],
+            max_resolution: (1280, 720),
+        });
+        let mut host = test_device("phone", false, 4096, true);
+        host.capabilities.supports_remote_desktop = true;
+        laptop.devices.write().await.insert("phone".to_string(), host);
+        phone.devices.write().await.insert("laptop".to_string(), test_device("laptop", true, 16384, false));
+        let laptop = Arc::new(laptop);
+        let phone = Arc::new(phone);
+
+        let (to_phone, phone_rx) = local_connection("phone", chrono::Utc::now());
+        laptop.register_connection(to_phone).await;
+        forward_to(Arc::clone(&phone), "laptop", phone_rx);
+        let (to_laptop, laptop_rx) = local_connection("laptop", chrono::Utc::now());
+        phone.register_connection(to_laptop).await;
+        forward_to(Arc::clone(&laptop), "phone", laptop_rx);
+
+        laptop.security_manager.create_user(TEST_USER, "correct horse battery staple").await.unwrap();
+        laptop.security_manager.authenticate_password(TEST_USER, "correct horse battery staple").await.unwrap();
+        let session_id = laptop.start_remote_desktop(TEST_USER, "phone", view_only_permissions(), None).await.unwrap();
+
+        let session = laptop.list_remote_desktop_sessions().await.into_iter().find(|s| s.session_id == session_id).unwrap();
+        assert_eq!(session.client_device_id, laptop.device_id());
+        assert_eq!(session.protocol, RemoteDesktopProtocol::VNC);
+        assert_eq!(session.codec, ImageFormat::PNG);
+        assert_eq!(session.max_resolution, (1280, 720));
+        // The host learned what the client offered
+        assert_eq!(
+            phone.devices.read().await["laptop"].capabilities.remote_desktop,
+            RemoteDesktopCapabilities::default()
+        );
+        assert_eq!(session.display_id, None);
+        laptop.remote_desktop_manager.stop_session(&session_id).await.unwrap();
+
+        // The host refuses to stream a display it doesn't have
+        let result = laptop.start_remote_desktop(TEST_USER, "phone", view_only_permissions(), Some(u32::MAX)).await;
+        assert!(matches!(result, Err(MisaError::RemoteDesktop(message)) if message.contains("not found")));
+        assert!(laptop.list_remote_desktop_sessions().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_incoming_messages_are_attributed_to_their_connection() {
+        let (manager, _data_dir) = test_manager().await;
+        let manager = Arc::new(manager);
+        let (connection, mut rx) = local_connection("phone", chrono::Utc::now());
+        manager.register_connection(connection).await;
+
+        let mut request = heartbeat_message("laptop");
+        request.message_type = MessageType::TaskRequest;
+        request.payload = serde_json::json!({ "task": "ping" });
+        manager.handle_incoming_message("phone", request).await.unwrap();
+
+        // The answer goes back over the connection the request arrived on
+        let reply = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await.unwrap().unwrap();
+        assert_eq!(reply.target_device_id.as_deref(), Some("phone"));
+    }
+
+    /// Serve one device channel from `acceptor` on a local port, returning the address to dial
+    async fn serve_device_channel(acceptor: &DeviceManager) -> (String, tokio::task::JoinHandle<MisaResult<String>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let acceptor = acceptor.clone();
+        let accepted = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let websocket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let (sink, stream) = websocket_frames(websocket);
+            acceptor.accept_device_channel(sink, stream).await
+        });
+        (addr, accepted)
+    }
+
+    #[tokio::test]
+    async fn test_websocket_channel_carries_sealed_messages_both_ways() {
+        let (dialer, _dialer_dir) = test_manager().await;
+        let (acceptor, _acceptor_dir) = test_manager().await;
+        dialer.security_manager.register_device_key(acceptor.device_id(), &[9u8; 32]).await.unwrap();
+        acceptor.security_manager.register_device_key(dialer.device_id(), &[9u8; 32]).await.unwrap();
+
+        let (addr, accepted) = serve_device_channel(&acceptor).await;
+        let connection = dialer.dial_device_channel(&addr, acceptor.device_id()).await.unwrap();
+        assert!(connection.encrypted_channel);
+        dialer.register_connection(connection).await;
+        assert_eq!(accepted.await.unwrap().unwrap(), dialer.device_id());
+
+        // The acceptor declines the task, but the request and its answer crossed the channel
+        let response = dialer
+            .send_request(acceptor.device_id(), serde_json::json!({ "task": "ping" }), Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert!(response["error"].is_string());
+
+        // Closing one end is noticed by the read loop at the other
+        let mut events = dialer.subscribe_connection_events();
+        acceptor.connection_lost(dialer.device_id()).await;
+        let lost = tokio::time::timeout(Duration::from_secs(5), events.recv()).await.unwrap().unwrap();
+        assert_eq!(lost, DeviceConnectionEvent::DeviceDisconnected { device_id: acceptor.device_id().to_string() });
+        assert!(!dialer.active_connections.read().await.contains_key(acceptor.device_id()));
+    }
+
+    #[tokio::test]
+    async fn test_freshly_paired_devices_open_a_channel() {
+        let (host, _host_dir) = test_manager().await;
+        let (phone, _phone_dir) = test_manager().await;
+
+        let token = host.generate_pairing_token(phone.device_id()).await.unwrap();
+        let phone_public_key = phone.scan_pairing_token(&token).await.unwrap();
+        host.pair_device(&token, &phone_public_key).await.unwrap();
+
+        let (addr, accepted) = serve_device_channel(&host).await;
+        let connection = phone.dial_device_channel(&addr, host.device_id()).await.unwrap();
+        phone.register_connection(connection).await;
+        assert_eq!(accepted.await.unwrap().unwrap(), phone.device_id());
+
+        let response = phone
+            .send_request(host.device_id(), serde_json::json!({ "task": "ping" }), Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert!(response["error"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_device_hello_only_answers_its_own_challenge() {
+        let (dialer, _dialer_dir) = test_manager().await;
+        let (acceptor, _acceptor_dir) = test_manager().await;
+        dialer.security_manager.register_device_key(acceptor.device_id(), &[9u8; 32]).await.unwrap();
+        acceptor.security_manager.register_device_key(dialer.device_id(), &[9u8; 32]).await.unwrap();
+
+        let hello = dialer.device_hello(acceptor.device_id(), "first-nonce", Some("dialer-nonce")).await.unwrap();
+        let (peer_id, challenge) = acceptor.verify_device_hello(Some(Ok(hello.clone())), "first-nonce").await.unwrap();
+        assert_eq!(peer_id, dialer.device_id());
+        assert_eq!(challenge.as_deref(), Some("dialer-nonce"));
+
+        // The same hello replayed against a later handshake is refused
+        let replayed = acceptor.verify_device_hello(Some(Ok(hello)), "second-nonce").await;
+        assert!(matches!(replayed, Err(MisaError::Permission(_))));
+    }
+
+    #[tokio::test]
+    async fn test_websocket_channel_refuses_unpaired_devices() {
+        let (dialer, _dialer_dir) = test_manager().await;
+        let (acceptor, _acceptor_dir) = test_manager().await;
+        // Only the dialer thinks the two are paired
+        dialer.security_manager.register_device_key(acceptor.device_id(), &[9u8; 32]).await.unwrap();
+
+        let (addr, accepted) = serve_device_channel(&acceptor).await;
+        assert!(dialer.dial_device_channel(&addr, acceptor.device_id()).await.is_err());
+        assert!(accepted.await.unwrap().is_err());
+        assert_eq!(acceptor.active_connection_count().await, 0);
+    }
+
+    #[test]
+    fn test_active_text_formats_are_not_plain_text() {
+        assert_eq!(classify_content(b"#!/bin/sh\nrm -rf ~\n"), "text/x-script");
+        assert_eq!(classify_content(b"@ECHO OFF\r\ndel /q *\r\n"), "application/x-bat");
+        assert_eq!(classify_content(b"\xef\xbb\xbf  <!DOCTYPE html><html></html>"), "text/html");
+        assert_eq!(classify_content(b"<script>alert(1)</script>"), "text/html");
+        assert_eq!(classify_content(b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>"), "image/svg+xml");
+        assert_eq!(classify_content(b"<?xml version=\"1.0\"?>\n<svg/>"), "image/svg+xml");
+        assert_eq!(classify_content(b"<?xml version=\"1.0\"?>\n<notes/>"), "application/xml");
+        assert_eq!(classify_content(b"<bold> is not a tag we render"), "text/plain");
+        assert_eq!(classify_content(b"Meeting notes"), "text/plain");
+    }
+
+    #[tokio::test]
+    async fn test_html_renamed_to_text_is_rejected() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let transfers = type_checked_transfers(&data_dir).await;
+
+        let result = send_file(&transfers, &data_dir.path().join("notes.txt"), b"<html><body onload=\"steal()\">").await;
+
+        assert!(matches!(result, Err(MisaError::FileTransfer(message)) if message.contains("text/html")));
+        assert!(transfers.active_transfers.read().await.is_empty());
+    }
+}
@@ -26,6 +26,10 @@ pub enum MisaError {
     #[error("Model error: {0}")]
     Model(String),
 
+    /// Model the local server knows of but hasn't pulled
+    #[error("Model not found: {0}")]
+    ModelNotFound(String),
+
     /// Memory/storage errors
     #[error("Memory error: {0}")]
     Memory(String),
@@ -38,6 +42,10 @@ pub enum MisaError {
     #[error("Network error: {0}")]
     Network(#[from] reqwest::Error),
 
+    /// Network requests that got no answer in time
+    #[error("Network timeout: {0}")]
+    NetworkTimeout(String),
+
     /// Database errors
     #[error("Database error: {0}")]
     Database(#[from] sqlx::Error),
@@ -86,6 +94,10 @@ pub enum MisaError {
     #[error("Rate limit exceeded: {0}")]
     RateLimit(String),
 
+    /// Capacity errors when too many requests are queued
+    #[error("Service overloaded: {0}")]
+    Overloaded(String),
+
     /// Cryptographic errors
     #[error("Cryptographic error: {0}")]
     Cryptographic(String),
@@ -239,6 +251,9 @@ pub enum PluginError {
     #[error("Plugin loading failed: {plugin_id}")]
     PluginLoadingFailed { plugin_id: String },
 
+    #[error("Plugin already installed: {plugin_id}")]
+    PluginAlreadyInstalled { plugin_id: String },
+
     #[error("Plugin execution failed: {plugin_id}")]
     PluginExecutionFailed { plugin_id: String },
 
@@ -10,25 +10,47 @@
 
 use anyhow::Result;
 use axum::{
-    extract::{ws::WebSocketUpgrade, State},
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
     http::StatusCode,
     response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::{broadcast, RwLock};
 use tracing::{info, warn, error};
 
+use crate::ai::{AIManager, VisionScreenReader};
 use crate::models::{ModelManager, ModelType, ModelCapabilities};
-use crate::security::SecurityManager;
-use crate::device::DeviceManager;
-use crate::memory::MemoryManager;
-use crate::privacy::PrivacyControls;
-use crate::errors::{MisaError, Result as MisaResult};
+use crate::security::{AuditQuery, AuditResult, PermissionChecker, SandboxStatus, SecurityManager};
+use crate::device::{load_or_create_device_id, DeviceManager, TaskHandler, TaskProfile, DEVICE_CHANNEL_PATH};
+use crate::memory::{ConflictStrategy, DetectedAnomaly, Importance, MemoryManager, MemorySchemas, MemoryType, Prediction, SearchQuery};
+use crate::metrics::{self, Metrics};
+use crate::privacy::{ConsentType, DataType, PrivacyControls, PrivacyEvent};
+use crate::scheduler::Scheduler;
+use crate::telemetry::TelemetryManager;
+use crate::util::{read_json_map, write_json_atomic};
+use crate::errors::{MisaError, PluginError, Result as MisaResult};
+
+/// How long a health probe may take before its subsystem counts as down
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long a delegated task may run on another device unless its constraints say otherwise
+const DEFAULT_DELEGATION_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How often predictions are generated for subscribers
+const PREDICTION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often recent memories are scanned for anomalies
+const ANOMALY_SCAN_INTERVAL: Duration = Duration::from_secs(60 * 60);
 
 /// Main kernel orchestrator
 pub struct MisaKernel {
@@ -39,7 +61,15 @@ pub struct MisaKernel {
     device_manager: DeviceManager,
     memory_manager: MemoryManager,
     privacy_controls: PrivacyControls,
+    metrics: Metrics,
+    telemetry: TelemetryManager,
+    scheduler: Scheduler,
     active_plugins: Arc<RwLock<HashMap<String, PluginInstance>>>,
+    plugin_events: broadcast::Sender<PluginEvent>,
+    prediction_events: broadcast::Sender<Prediction>,
+    /// Loop publishing predictions, started with the kernel and stopped on shutdown
+    prediction_task: Arc<RwLock<Option<tokio::task::JoinHandle<()>>>>,
+    anomaly_events: broadcast::Sender<DetectedAnomaly>,
 }
 
 /// Kernel configuration
@@ -55,6 +85,21 @@ pub struct KernelConfig {
     pub memory: MemoryConfig,
     /// Network and API settings
     pub network: NetworkConfig,
+    /// Opt-in usage telemetry
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+}
+
+impl KernelConfig {
+    /// Check every section, reporting all problems at once
+    pub fn validate(&self) -> MisaResult<()> {
+        let mut problems = self.models.problems();
+        problems.extend(self.devices.problems());
+        problems.extend(self.memory.problems());
+        problems.extend(self.network.problems());
+        problems.extend(self.telemetry.problems());
+        config_result(problems)
+    }
 }
 
 impl Default for KernelConfig {
@@ -65,6 +110,7 @@ impl Default for KernelConfig {
             security: SecurityConfig::default(),
             memory: MemoryConfig::default(),
             network: NetworkConfig::default(),
+            telemetry: TelemetryConfig::default(),
         }
     }
 }
@@ -79,6 +125,102 @@ pub struct ModelConfig {
     pub cloud_providers: HashMap<String, CloudProviderConfig>,
     /// Model switching preferences
     pub switching_preferences: ModelSwitchingPreferences,
+    /// Concurrent execution limits
+    #[serde(default)]
+    pub concurrency: ModelConcurrencyConfig,
+    /// Local model used to embed text for semantic search
+    #[serde(default = "default_embedding_model")]
+    pub embedding_model: String,
+    /// Timeouts and retries for the local model server
+    #[serde(default)]
+    pub ollama: OllamaClientConfig,
+    /// How often to re-scan the local model server for pulled or deleted models; 0 disables it
+    #[serde(default = "default_catalog_refresh_interval_seconds")]
+    pub catalog_refresh_interval_seconds: u64,
+    /// Pull a local model the server doesn't have yet and retry, instead of failing the task
+    #[serde(default = "default_auto_pull_models")]
+    pub auto_pull_models: bool,
+    /// Estimated cloud spend allowed per UTC day in USD; unlimited when unset
+    #[serde(default)]
+    pub daily_budget_usd: Option<f64>,
+}
+
+impl ModelConfig {
+    /// Check ranges and URLs, reporting all problems at once
+    pub fn validate(&self) -> MisaResult<()> {
+        config_result(self.problems())
+    }
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if self.default_model.trim().is_empty() {
+            problems.push("models.default_model must not be empty".to_string());
+        }
+        if self.embedding_model.trim().is_empty() {
+            problems.push("models.embedding_model must not be empty".to_string());
+        }
+        check_url(&mut problems, "models.local_server_url", &self.local_server_url);
+
+        let mut providers: Vec<_> = self.cloud_providers.iter().collect();
+        providers.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, provider) in providers {
+            check_url(&mut problems, &format!("models.cloud_providers.{}.base_url", name), &provider.base_url);
+        }
+
+        let preferences = &self.switching_preferences;
+        if !(0.0..=1.0).contains(&preferences.gpu_threshold) {
+            problems.push(format!(
+                "models.switching_preferences.gpu_threshold must be between 0 and 1, got {}",
+                preferences.gpu_threshold
+            ));
+        }
+        for (field, value) in [
+            ("cost_weight", preferences.cost_weight),
+            ("latency_weight", preferences.latency_weight),
+            ("quality_weight", preferences.quality_weight),
+            ("local_bias", preferences.local_bias),
+        ] {
+            if !value.is_finite() || value < 0.0 {
+                problems.push(format!(
+                    "models.switching_preferences.{} must be a non-negative number, got {}",
+                    field, value
+                ));
+            }
+        }
+
+        if self.concurrency.max_concurrent_local == 0 {
+            problems.push("models.concurrency.max_concurrent_local must be at least 1".to_string());
+        }
+        if self.concurrency.max_concurrent_cloud == 0 {
+            problems.push("models.concurrency.max_concurrent_cloud must be at least 1".to_string());
+        }
+        if self.ollama.connect_timeout_ms == 0 {
+            problems.push("models.ollama.connect_timeout_ms must be greater than 0".to_string());
+        }
+        if self.ollama.request_timeout_ms == 0 {
+            problems.push("models.ollama.request_timeout_ms must be greater than 0".to_string());
+        }
+        if let Some(budget) = self.daily_budget_usd {
+            if !budget.is_finite() || budget < 0.0 {
+                problems.push(format!("models.daily_budget_usd must be a non-negative amount, got {}", budget));
+            }
+        }
+
+        problems
+    }
+}
+
+fn default_catalog_refresh_interval_seconds() -> u64 {
+    300
+}
+
+fn default_auto_pull_models() -> bool {
+    false
+}
+
+fn default_embedding_model() -> String {
+    "nomic-embed-text".to_string()
 }
 
 impl Default for ModelConfig {
@@ -89,12 +231,67 @@ impl Default for ModelConfig {
             base_url: "https://api.openai.com/v1".to_string(),
             models: vec!["gpt-4".to_string(), "gpt-3.5-turbo".to_string()],
         });
+        cloud_providers.insert("anthropic".to_string(), CloudProviderConfig {
+            api_key: std::env::var("ANTHROPIC_API_KEY").unwrap_or_default(),
+            base_url: "https://api.anthropic.com".to_string(),
+            models: vec!["claude-3-5-sonnet-20241022".to_string(), "claude-3-haiku-20240307".to_string()],
+        });
 
         Self {
             default_model: "mixtral".to_string(),
             local_server_url: "http://localhost:11434".to_string(),
             cloud_providers,
             switching_preferences: ModelSwitchingPreferences::default(),
+            concurrency: ModelConcurrencyConfig::default(),
+            embedding_model: default_embedding_model(),
+            ollama: OllamaClientConfig::default(),
+            catalog_refresh_interval_seconds: default_catalog_refresh_interval_seconds(),
+            auto_pull_models: default_auto_pull_models(),
+            daily_budget_usd: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelConcurrencyConfig {
+    /// Maximum simultaneous executions on local models
+    pub max_concurrent_local: usize,
+    /// Maximum simultaneous executions on cloud models
+    pub max_concurrent_cloud: usize,
+    /// Maximum requests waiting for a slot before new ones are rejected
+    pub max_queue_depth: usize,
+}
+
+impl Default for ModelConcurrencyConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_local: 2,
+            max_concurrent_cloud: 8,
+            max_queue_depth: 32,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OllamaClientConfig {
+    /// Time allowed to establish a connection (milliseconds)
+    pub connect_timeout_ms: u64,
+    /// Time allowed for a complete non-streaming request (milliseconds)
+    pub request_timeout_ms: u64,
+    /// Extra attempts after a connection failure or unavailable server
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each further one (milliseconds)
+    pub retry_backoff_ms: u64,
+}
+
+impl Default for OllamaClientConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout_ms: 5_000,
+            request_timeout_ms: 120_000,
+            max_retries: 2,
+            retry_backoff_ms: 250,
         }
     }
 }
@@ -112,9 +309,34 @@ pub struct ModelSwitchingPreferences {
     pub prefer_local: bool,
     /// GPU acceleration threshold
     pub gpu_threshold: f32,
-    /// Cost optimization level (0.0 - 1.0)
+    /// Retry a failed local model task on a cloud model of the same type, when
+    /// `cloud_consent_user_id` has consented to cloud processing
+    #[serde(default = "default_allow_cloud_fallback")]
+    pub allow_cloud_fallback: bool,
+    /// User whose `CloudProcessing` consent cloud fallback requires
+    #[serde(default)]
+    pub cloud_consent_user_id: Option<String>,
+    /// Maximum number of fallback models tried after the first failure
+    #[serde(default = "default_max_fallback_attempts")]
+    pub max_fallback_attempts: usize,
+    /// How much ranking favours cheap models; local models cost nothing
+    #[serde(default = "default_selection_weight")]
+    pub cost_weight: f32,
+    /// How much ranking favours models that answered quickly
+    #[serde(default = "default_selection_weight")]
+    pub latency_weight: f32,
+    /// How much ranking favours models whose requests succeed and that specialise
+    /// in the task
+    #[serde(default = "default_selection_weight")]
+    pub quality_weight: f32,
+    /// How much ranking favours local models when `prefer_local` is set
+    #[serde(default = "default_local_bias")]
+    pub local_bias: f32,
+    /// Deprecated and ignored; use `cost_weight`. Still read so older configs load.
+    #[serde(default = "default_cost_optimization")]
     pub cost_optimization: f32,
-    /// Quality optimization level (0.0 - 1.0)
+    /// Deprecated and ignored; use `quality_weight`. Still read so older configs load.
+    #[serde(default = "default_quality_optimization")]
     pub quality_optimization: f32,
 }
 
@@ -123,14 +345,48 @@ impl Default for ModelSwitchingPreferences {
         Self {
             prefer_local: true,
             gpu_threshold: 0.7,
-            cost_optimization: 0.6,
-            quality_optimization: 0.8,
+            allow_cloud_fallback: default_allow_cloud_fallback(),
+            cloud_consent_user_id: None,
+            max_fallback_attempts: default_max_fallback_attempts(),
+            cost_weight: default_selection_weight(),
+            latency_weight: default_selection_weight(),
+            quality_weight: default_selection_weight(),
+            local_bias: default_local_bias(),
+            cost_optimization: default_cost_optimization(),
+            quality_optimization: default_quality_optimization(),
         }
     }
 }
 
+fn default_allow_cloud_fallback() -> bool {
+    false
+}
+
+fn default_max_fallback_attempts() -> usize {
+    2
+}
+
+fn default_selection_weight() -> f32 {
+    1.0
+}
+
+fn default_local_bias() -> f32 {
+    2.0
+}
+
+fn default_cost_optimization() -> f32 {
+    0.6
+}
+
+fn default_quality_optimization() -> f32 {
+    0.8
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeviceConfig {
+    /// Id this device is known by to its peers; generated and kept in the data directory when unset
+    #[serde(default)]
+    pub device_id: Option<String>,
     /// Enable device discovery
     pub discovery_enabled: bool,
     /// Remote desktop enabled
@@ -139,15 +395,150 @@ pub struct DeviceConfig {
     pub file_transfer: FileTransferConfig,
     /// Energy management
     pub energy_management: EnergyConfig,
+    /// Seconds between heartbeats sent to connected devices
+    #[serde(default = "default_heartbeat_interval_secs")]
+    pub heartbeat_interval_secs: u64,
+    /// Seconds without traffic from a device before its connection is dropped
+    #[serde(default = "default_connection_idle_timeout_secs")]
+    pub connection_idle_timeout_secs: u64,
+    /// How peers are discovered on the local network
+    #[serde(default = "default_discovery_transport")]
+    pub discovery_transport: DiscoveryTransport,
+    /// Sustained inbound messages accepted per device each second; 0 disables the limit
+    #[serde(default = "default_inbound_messages_per_second")]
+    pub inbound_messages_per_second: f64,
+    /// Inbound messages a device may send in a burst before being throttled
+    #[serde(default = "default_inbound_message_burst")]
+    pub inbound_message_burst: u32,
+    /// Extra attempts for a message that failed to send before it is dead-lettered
+    #[serde(default = "default_message_retry_attempts")]
+    pub message_retry_attempts: u32,
+    /// Delay before the first retry, doubled for each further one
+    #[serde(default = "default_message_retry_base_delay_ms")]
+    pub message_retry_base_delay_ms: u64,
+    /// Attempts to reconnect to a paired device after its connection drops; 0 disables reconnecting
+    #[serde(default = "default_reconnect_max_attempts")]
+    pub reconnect_max_attempts: u32,
+    /// Delay before the first reconnect attempt, doubled for each further one
+    #[serde(default = "default_reconnect_base_delay_ms")]
+    pub reconnect_base_delay_ms: u64,
+    /// Upper bound on the delay between reconnect attempts
+    #[serde(default = "default_reconnect_max_delay_ms")]
+    pub reconnect_max_delay_ms: u64,
+    /// Unpaired devices allowed to delegate tasks to this one; paired devices always may
+    #[serde(default)]
+    pub task_delegation_devices: Vec<String>,
+    /// Delegated tasks run at once; further requests are answered as busy
+    #[serde(default = "default_max_concurrent_delegated_tasks")]
+    pub max_concurrent_delegated_tasks: usize,
+    /// Share clipboard changes with connected devices
+    #[serde(default)]
+    pub clipboard_sync_enabled: bool,
+}
+
+/// Local network discovery mechanism
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiscoveryTransport {
+    /// Custom UDP broadcast packets
+    Udp,
+    /// mDNS/DNS-SD service advertisement and browsing
+    Mdns,
+    /// Both of the above
+    Both,
+}
+
+impl DeviceConfig {
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if self.file_transfer.download_dir.trim().is_empty() {
+            problems.push("devices.file_transfer.download_dir must not be empty".to_string());
+        }
+        if !(0.0..=100.0).contains(&self.energy_management.cloud_fallback_battery) {
+            problems.push(format!(
+                "devices.energy_management.cloud_fallback_battery must be a percentage, got {}",
+                self.energy_management.cloud_fallback_battery
+            ));
+        }
+        if !self.inbound_messages_per_second.is_finite() || self.inbound_messages_per_second < 0.0 {
+            problems.push(format!(
+                "devices.inbound_messages_per_second must be non-negative, got {}",
+                self.inbound_messages_per_second
+            ));
+        }
+        if self.max_concurrent_delegated_tasks == 0 {
+            problems.push("devices.max_concurrent_delegated_tasks must be at least 1".to_string());
+        }
+
+        problems
+    }
+}
+
+fn default_discovery_transport() -> DiscoveryTransport {
+    DiscoveryTransport::Both
+}
+
+fn default_inbound_messages_per_second() -> f64 {
+    50.0
+}
+
+fn default_inbound_message_burst() -> u32 {
+    100
+}
+
+fn default_message_retry_attempts() -> u32 {
+    3
+}
+
+fn default_message_retry_base_delay_ms() -> u64 {
+    200
+}
+
+fn default_reconnect_max_attempts() -> u32 {
+    8
+}
+
+fn default_reconnect_base_delay_ms() -> u64 {
+    1_000
+}
+
+fn default_reconnect_max_delay_ms() -> u64 {
+    60_000
+}
+
+fn default_max_concurrent_delegated_tasks() -> usize {
+    4
+}
+
+fn default_heartbeat_interval_secs() -> u64 {
+    15
+}
+
+fn default_connection_idle_timeout_secs() -> u64 {
+    60
 }
 
 impl Default for DeviceConfig {
     fn default() -> Self {
         Self {
+            device_id: None,
             discovery_enabled: true,
             remote_desktop_enabled: true,
             file_transfer: FileTransferConfig::default(),
             energy_management: EnergyConfig::default(),
+            heartbeat_interval_secs: default_heartbeat_interval_secs(),
+            connection_idle_timeout_secs: default_connection_idle_timeout_secs(),
+            discovery_transport: default_discovery_transport(),
+            inbound_messages_per_second: default_inbound_messages_per_second(),
+            inbound_message_burst: default_inbound_message_burst(),
+            message_retry_attempts: default_message_retry_attempts(),
+            message_retry_base_delay_ms: default_message_retry_base_delay_ms(),
+            reconnect_max_attempts: default_reconnect_max_attempts(),
+            reconnect_base_delay_ms: default_reconnect_base_delay_ms(),
+            reconnect_max_delay_ms: default_reconnect_max_delay_ms(),
+            task_delegation_devices: Vec::new(),
+            max_concurrent_delegated_tasks: default_max_concurrent_delegated_tasks(),
+            clipboard_sync_enabled: false,
         }
     }
 }
@@ -156,10 +547,17 @@ impl Default for DeviceConfig {
 pub struct FileTransferConfig {
     /// Maximum file size (MB)
     pub max_file_size_mb: u64,
-    /// Allowed file types
+    /// MIME types files may be sent as, judged by their content; `*` and `image/*` style wildcards work
     pub allowed_types: Vec<String>,
     /// Encryption required
     pub encryption_required: bool,
+    /// Directory where incoming files are written
+    #[serde(default = "default_download_dir")]
+    pub download_dir: String,
+}
+
+fn default_download_dir() -> String {
+    "downloads".to_string()
 }
 
 impl Default for FileTransferConfig {
@@ -168,6 +566,7 @@ impl Default for FileTransferConfig {
             max_file_size_mb: 1024,
             allowed_types: vec!["*".to_string()], // All types allowed
             encryption_required: true,
+            download_dir: default_download_dir(),
         }
     }
 }
@@ -200,10 +599,25 @@ pub struct SecurityConfig {
     pub biometric_auth: bool,
     /// Session timeout (minutes)
     pub session_timeout_minutes: u64,
+    /// Extend a session's expiry on every use instead of expiring it a fixed time after login
+    #[serde(default = "default_sliding_session_expiry")]
+    pub sliding_session_expiry: bool,
     /// Plugin sandboxing
     pub plugin_sandboxing: bool,
     /// Audit logging
     pub audit_logging: bool,
+    /// Size at which the audit log file is rotated (bytes)
+    #[serde(default = "default_audit_log_max_bytes")]
+    pub audit_log_max_bytes: u64,
+    /// How often to look for expiring consents (seconds, 0 disables the sweep)
+    #[serde(default = "default_consent_sweep_interval_seconds")]
+    pub consent_sweep_interval_seconds: u64,
+    /// Ask users to renew consents this many days before they expire
+    #[serde(default = "default_consent_expiry_warning_days")]
+    pub consent_expiry_warning_days: u32,
+    /// How often to apply data source retention rules to stored artifacts (seconds, 0 disables it)
+    #[serde(default = "default_retention_enforcement_interval_seconds")]
+    pub retention_enforcement_interval_seconds: u64,
 }
 
 impl Default for SecurityConfig {
@@ -212,12 +626,37 @@ impl Default for SecurityConfig {
             auth_required: true,
             biometric_auth: true,
             session_timeout_minutes: 30,
+            sliding_session_expiry: default_sliding_session_expiry(),
             plugin_sandboxing: true,
             audit_logging: true,
+            audit_log_max_bytes: default_audit_log_max_bytes(),
+            consent_sweep_interval_seconds: default_consent_sweep_interval_seconds(),
+            consent_expiry_warning_days: default_consent_expiry_warning_days(),
+            retention_enforcement_interval_seconds: default_retention_enforcement_interval_seconds(),
         }
     }
 }
 
+fn default_sliding_session_expiry() -> bool {
+    true
+}
+
+fn default_audit_log_max_bytes() -> u64 {
+    10 * 1024 * 1024 // 10 MiB
+}
+
+fn default_consent_sweep_interval_seconds() -> u64 {
+    60 * 60
+}
+
+fn default_consent_expiry_warning_days() -> u32 {
+    14
+}
+
+fn default_retention_enforcement_interval_seconds() -> u64 {
+    60 * 60
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryConfig {
     /// Local database path
@@ -228,6 +667,155 @@ pub struct MemoryConfig {
     pub compression_enabled: bool,
     /// Encryption enabled
     pub encryption_enabled: bool,
+    /// Interval between background pruning runs (seconds)
+    #[serde(default = "default_prune_interval_seconds")]
+    pub prune_interval_seconds: u64,
+    /// Cloud synchronization settings
+    #[serde(default)]
+    pub cloud_sync: CloudSyncConfig,
+    /// Z-score above which memory activity is reported as anomalous
+    #[serde(default = "default_anomaly_threshold")]
+    pub anomaly_threshold: f64,
+    /// Days of memory history used as the anomaly baseline
+    #[serde(default = "default_anomaly_baseline_window_size")]
+    pub anomaly_baseline_window_size: usize,
+    /// Largest memory content accepted by `store_memory` (bytes)
+    #[serde(default = "default_max_memory_content_bytes")]
+    pub max_memory_content_bytes: usize,
+    /// Weights used to rank memories by relevance
+    #[serde(default)]
+    pub relevance: RelevanceConfig,
+    /// Memory types whose expired entries are overwritten when pruned
+    #[serde(default)]
+    pub secure_delete_types: Vec<MemoryType>,
+    /// Most memories kept in the context's short-term memory
+    #[serde(default = "default_short_term_capacity")]
+    pub short_term_capacity: usize,
+    /// Interval between writes of buffered access statistics (seconds)
+    #[serde(default = "default_access_flush_interval_seconds")]
+    pub access_flush_interval_seconds: u64,
+    /// Buffered reads that trigger an early write of access statistics
+    #[serde(default = "default_access_flush_threshold")]
+    pub access_flush_threshold: u32,
+    /// Most open connections to the memory database
+    #[serde(default = "default_db_max_connections")]
+    pub db_max_connections: u32,
+}
+
+impl MemoryConfig {
+    /// Check ranges, paths and the sync endpoint, reporting all problems at once
+    pub fn validate(&self) -> MisaResult<()> {
+        config_result(self.problems())
+    }
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if self.local_db_path.trim().is_empty() {
+            problems.push("memory.local_db_path must not be empty".to_string());
+        }
+        if self.retention_days == 0 {
+            problems.push("memory.retention_days must be at least 1".to_string());
+        } else {
+            // Memories pruned before they reach their compaction age are never summarized
+            let schemas = MemorySchemas::new(self.retention_days, self.short_term_capacity);
+            let longest_unreached = [MemoryType::ShortTerm, MemoryType::MediumTerm]
+                .iter()
+                .filter_map(|memory_type| {
+                    let compaction_age = schemas.compaction_age(memory_type)?;
+                    let prune_age = schemas.retention_period(memory_type, &Importance::Low, self.retention_days);
+                    (prune_age <= compaction_age).then_some(compaction_age.num_days())
+                })
+                .max();
+            if let Some(days) = longest_unreached {
+                problems.push(format!(
+                    "memory.retention_days must be greater than {} so memories are compacted before they are pruned, got {}",
+                    days, self.retention_days
+                ));
+            }
+        }
+        if !self.anomaly_threshold.is_finite() || self.anomaly_threshold <= 0.0 {
+            problems.push(format!("memory.anomaly_threshold must be positive, got {}", self.anomaly_threshold));
+        }
+        if self.anomaly_baseline_window_size == 0 {
+            problems.push("memory.anomaly_baseline_window_size must be at least 1".to_string());
+        }
+        if self.max_memory_content_bytes == 0 {
+            problems.push("memory.max_memory_content_bytes must be greater than 0".to_string());
+        }
+        if self.short_term_capacity == 0 {
+            problems.push("memory.short_term_capacity must be at least 1".to_string());
+        }
+        if self.access_flush_interval_seconds == 0 {
+            problems.push("memory.access_flush_interval_seconds must be at least 1".to_string());
+        }
+        if self.access_flush_threshold == 0 {
+            problems.push("memory.access_flush_threshold must be at least 1".to_string());
+        }
+        if self.db_max_connections == 0 {
+            problems.push("memory.db_max_connections must be at least 1".to_string());
+        }
+
+        let relevance = &self.relevance;
+        let weights = [
+            ("time_decay_per_hour", relevance.time_decay_per_hour),
+            ("recency_weight", relevance.recency_weight),
+            ("frequency_weight", relevance.frequency_weight),
+            ("context_weight", relevance.context_weight),
+        ];
+        for (field, value) in weights {
+            if !value.is_finite() || value < 0.0 {
+                problems.push(format!("memory.relevance.{} must be non-negative, got {}", field, value));
+            }
+        }
+        if relevance.recency_weight + relevance.frequency_weight + relevance.context_weight <= 0.0 {
+            problems.push("memory.relevance weights must not all be zero".to_string());
+        }
+
+        if self.cloud_sync.enabled {
+            match &self.cloud_sync.endpoint {
+                Some(endpoint) => check_url(&mut problems, "memory.cloud_sync.endpoint", endpoint),
+                None => problems.push("memory.cloud_sync.endpoint is required when cloud sync is enabled".to_string()),
+            }
+            if self.cloud_sync.interval_minutes == 0 {
+                problems.push("memory.cloud_sync.interval_minutes must be at least 1".to_string());
+            }
+        }
+
+        problems
+    }
+}
+
+fn default_prune_interval_seconds() -> u64 {
+    24 * 3600 // Daily
+}
+
+fn default_anomaly_threshold() -> f64 {
+    2.0 // Standard deviations
+}
+
+fn default_anomaly_baseline_window_size() -> usize {
+    100
+}
+
+fn default_max_memory_content_bytes() -> usize {
+    1024 * 1024 // 1 MiB
+}
+
+fn default_short_term_capacity() -> usize {
+    50
+}
+
+fn default_access_flush_interval_seconds() -> u64 {
+    30
+}
+
+fn default_access_flush_threshold() -> u32 {
+    500
+}
+
+fn default_db_max_connections() -> u32 {
+    8
 }
 
 impl Default for MemoryConfig {
@@ -237,6 +825,68 @@ impl Default for MemoryConfig {
             retention_days: 365,
             compression_enabled: true,
             encryption_enabled: true,
+            prune_interval_seconds: default_prune_interval_seconds(),
+            cloud_sync: CloudSyncConfig::default(),
+            anomaly_threshold: default_anomaly_threshold(),
+            anomaly_baseline_window_size: default_anomaly_baseline_window_size(),
+            max_memory_content_bytes: default_max_memory_content_bytes(),
+            relevance: RelevanceConfig::default(),
+            secure_delete_types: Vec::new(),
+            short_term_capacity: default_short_term_capacity(),
+            access_flush_interval_seconds: default_access_flush_interval_seconds(),
+            access_flush_threshold: default_access_flush_threshold(),
+            db_max_connections: default_db_max_connections(),
+        }
+    }
+}
+
+/// Relevance scoring weights; they are normalized, so only their ratios matter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RelevanceConfig {
+    /// Exponential decay of the recency score per hour since last access
+    pub time_decay_per_hour: f32,
+    /// Weight of how recently the memory was accessed
+    pub recency_weight: f32,
+    /// Weight of how often the memory was accessed
+    pub frequency_weight: f32,
+    /// Weight of how well the memory matches the current context
+    pub context_weight: f32,
+}
+
+impl Default for RelevanceConfig {
+    fn default() -> Self {
+        Self {
+            time_decay_per_hour: 0.1,
+            recency_weight: 0.4,
+            frequency_weight: 0.3,
+            context_weight: 0.3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloudSyncConfig {
+    /// Cloud sync enabled
+    pub enabled: bool,
+    /// Base URL of the sync backend
+    pub endpoint: Option<String>,
+    /// API key sent as a bearer token
+    pub api_key: Option<String>,
+    /// Interval between background syncs (minutes)
+    pub interval_minutes: u64,
+    /// How to resolve memories edited both locally and remotely
+    pub conflict_strategy: ConflictStrategy,
+}
+
+impl Default for CloudSyncConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: None,
+            api_key: std::env::var("MISA_CLOUD_API_KEY").ok(),
+            interval_minutes: 30,
+            conflict_strategy: ConflictStrategy::LastModifiedWins,
         }
     }
 }
@@ -253,6 +903,95 @@ pub struct NetworkConfig {
     pub cert_path: Option<String>,
     /// Private key path
     pub key_path: Option<String>,
+    /// Serve Prometheus metrics at `/metrics`
+    #[serde(default)]
+    pub metrics_enabled: bool,
+}
+
+impl NetworkConfig {
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if self.websocket_port == self.grpc_port {
+            problems.push(format!(
+                "network.websocket_port and network.grpc_port must differ, both are {}",
+                self.websocket_port
+            ));
+        }
+        if self.tls_enabled {
+            for (field, path) in [("cert_path", &self.cert_path), ("key_path", &self.key_path)] {
+                if path.as_deref().map_or(true, |path| path.trim().is_empty()) {
+                    problems.push(format!("network.{} is required when TLS is enabled", field));
+                }
+            }
+        }
+
+        problems
+    }
+}
+
+/// Where anonymized usage counters are sent; only users with analytics consent are counted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryConfig {
+    /// Base URL of the telemetry backend; nothing is collected without one
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// API key sent as a bearer token
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// User whose analytics consent gates collection; nothing is collected without one
+    #[serde(default)]
+    pub user_id: Option<String>,
+    /// Interval between flushes of buffered counters (seconds)
+    #[serde(default = "default_telemetry_flush_interval_seconds")]
+    pub flush_interval_seconds: u64,
+}
+
+fn default_telemetry_flush_interval_seconds() -> u64 {
+    60 * 60
+}
+
+impl TelemetryConfig {
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if let Some(endpoint) = &self.endpoint {
+            check_url(&mut problems, "telemetry.endpoint", endpoint);
+        }
+        if self.flush_interval_seconds == 0 {
+            problems.push("telemetry.flush_interval_seconds must be at least 1".to_string());
+        }
+
+        problems
+    }
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            api_key: None,
+            user_id: None,
+            flush_interval_seconds: default_telemetry_flush_interval_seconds(),
+        }
+    }
+}
+
+/// Record a problem unless `url` is an http(s) URL with a host
+fn check_url(problems: &mut Vec<String>, field: &str, url: &str) {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") && parsed.host_str().is_some() => {}
+        Ok(_) => problems.push(format!("{} must be an http(s) URL with a host, got {}", field, url)),
+        Err(e) => problems.push(format!("{} is not a valid URL ({}): {}", field, e, url)),
+    }
+}
+
+fn config_result(problems: Vec<String>) -> MisaResult<()> {
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(MisaError::Configuration(format!("Invalid configuration: {}", problems.join("; "))))
+    }
 }
 
 impl Default for NetworkConfig {
@@ -263,23 +1002,80 @@ impl Default for NetworkConfig {
             tls_enabled: false,
             cert_path: None,
             key_path: None,
+            metrics_enabled: false,
         }
     }
 }
 
+/// What a plugin declares about itself when it is installed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginManifest {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    /// Program started inside the plugin's sandbox
+    pub executable: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// Permissions the plugin may use; anything else is denied
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
 /// Plugin instance information
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct PluginInstance {
     pub id: String,
     pub name: String,
     pub version: String,
+    pub executable: String,
+    pub args: Vec<String>,
     pub capabilities: Vec<String>,
     pub permissions: Vec<String>,
     pub pid: Option<u32>,
+    /// Sandbox the plugin runs in while started
+    pub sandbox_id: Option<String>,
     pub status: PluginStatus,
+    /// Secret the running plugin authenticates its requests with
+    #[serde(skip)]
+    request_token: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Request a running plugin makes of the kernel, tagged by `type`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PluginRequest {
+    SearchMemories {
+        query: String,
+        #[serde(default)]
+        limit: Option<u32>,
+    },
+    ListDevices,
+}
+
+impl PluginRequest {
+    /// Permission the plugin must have declared to make this request
+    pub fn permission(&self) -> &'static str {
+        match self {
+            PluginRequest::SearchMemories { .. } => "memory:read",
+            PluginRequest::ListDevices => "devices:read",
+        }
+    }
+}
+
+/// Plugin lifecycle changes published by the kernel
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PluginEvent {
+    Installed { plugin_id: String },
+    Started { plugin_id: String, pid: u32 },
+    Stopped { plugin_id: String },
+    Failed { plugin_id: String, error: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum PluginStatus {
     Running,
     Stopped,
@@ -319,45 +1115,271 @@ impl Default for TaskPriority {
     }
 }
 
-#[derive(Debug, Serialize)]
-pub struct TaskResponse {
-    pub success: bool,
-    pub task_id: String,
-    pub assigned_device: Option<String>,
-    pub assigned_model: String,
-    pub estimated_duration: Option<u64>,
-    pub result: Option<serde_json::Value>,
-    pub error: Option<String>,
+/// Overall kernel health
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Healthy,
+    /// Only the model server is down; memory and security still work
+    Degraded,
+    Unhealthy,
 }
 
-impl MisaKernel {
-    /// Create a new kernel instance
-    pub async fn new(
-        config_path: String,
-        data_dir: String,
-        security_manager: SecurityManager,
-    ) -> MisaResult<Self> {
-        // Load configuration
-        let config = Self::load_config(&config_path).unwrap_or_default();
+/// Health of a single subsystem
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubsystemHealth {
+    pub name: String,
+    pub up: bool,
+    pub latency_ms: Option<u64>,
+    pub detail: Option<String>,
+}
 
-        // Initialize managers
-        let model_manager = ModelManager::new(config.models.clone()).await?;
-        let device_manager = DeviceManager::new(config.devices.clone()).await?;
-        let memory_manager = MemoryManager::new(&data_dir, config.memory.clone()).await?;
-        let privacy_controls = PrivacyControls::new(config.security.clone()).await?;
+impl SubsystemHealth {
+    fn from_probe(name: &str, probe: MisaResult<Duration>) -> Self {
+        match probe {
+            Ok(latency) => Self {
+                name: name.to_string(),
+                up: true,
+                latency_ms: Some(latency.as_millis() as u64),
+                detail: None,
+            },
+            Err(e) => Self {
+                name: name.to_string(),
+                up: false,
+                latency_ms: None,
+                detail: Some(e.to_string()),
+            },
+        }
+    }
+}
 
-        info!("MISA Kernel initialized successfully");
+/// Aggregated health of every kernel subsystem
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub version: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub subsystems: Vec<SubsystemHealth>,
+}
 
-        Ok(Self {
-            config,
-            data_dir,
-            security_manager,
-            model_manager,
-            device_manager,
-            memory_manager,
+/// Most hits taken from each source by a global search
+const GLOBAL_SEARCH_LIMIT_PER_SOURCE: usize = 50;
+
+/// Directory under the data dir that plugin executables must be installed in
+const PLUGINS_DIR: &str = "plugins";
+
+/// File installed plugin manifests are persisted in, relative to the data directory
+const PLUGINS_FILE: &str = "plugins.json";
+
+/// Directory under the system temp dir plugins run in, one subdirectory each,
+/// so they don't start inside the data directory that holds keys and credentials
+const PLUGIN_WORK_DIR: &str = "misa-plugins";
+
+/// Endpoint running plugins send their requests of the kernel to
+const PLUGIN_REQUEST_PATH: &str = "/api/v1/plugins/request";
+
+/// Most memories a plugin's search returns
+const PLUGIN_SEARCH_LIMIT: u32 = 50;
+
+/// Subsystem a global search hit came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchSource {
+    Memory,
+    Audit,
+    Consent,
+}
+
+/// One match found by `MisaKernel::global_search`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlobalSearchHit {
+    pub source: SearchSource,
+    /// Id of the memory, audit entry or consent record
+    pub id: String,
+    /// Matched text after privacy filtering
+    pub snippet: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+/// Hits from every searched subsystem, newest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlobalSearchResults {
+    pub query: String,
+    pub hits: Vec<GlobalSearchHit>,
+}
+
+/// A task shipped to another device's kernel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelegatedTask {
+    pub task: String,
+    pub task_type: String,
+    pub context: Option<serde_json::Value>,
+}
+
+/// Which devices may run a delegated task and how long to wait for them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DelegationConstraints {
+    /// Devices to use, in order of preference; the best-scoring online device when empty
+    #[serde(default)]
+    pub device_preferences: Vec<String>,
+    /// Workload profile devices are scored by; inferred from the task type when unset
+    pub profile: Option<TaskProfile>,
+    /// Seconds to wait for the remote result
+    pub timeout_secs: Option<u64>,
+}
+
+/// Runs tasks delegated by other devices on this kernel's local models
+///
+/// Nobody on this device consented to sending another device's task to a cloud
+/// provider, so delegated work only ever runs on local models.
+struct DelegatedTaskHandler {
+    model_manager: ModelManager,
+    security_manager: SecurityManager,
+}
+
+#[async_trait::async_trait]
+impl TaskHandler for DelegatedTaskHandler {
+    async fn handle_task(&self, source_device_id: &str, task: serde_json::Value) -> MisaResult<serde_json::Value> {
+        let task: DelegatedTask = serde_json::from_value(task)?;
+        info!("Running {} task delegated by {}", task.task_type, source_device_id);
+        let response = run_task_locally(&self.model_manager, &task).await;
+
+        let (result, details) = match &response {
+            Ok(response) => (
+                AuditResult::Success,
+                serde_json::json!({ "source_device": source_device_id, "model": response.assigned_model }),
+            ),
+            Err(e) => (
+                AuditResult::Failure,
+                serde_json::json!({ "source_device": source_device_id, "error": e.to_string() }),
+            ),
+        };
+        if let Err(e) = self
+            .security_manager
+            .log_security_event(None, "delegated_task", &task.task_type, result, details)
+            .await
+        {
+            warn!("Failed to audit task delegated by {}: {}", source_device_id, e);
+        }
+
+        Ok(serde_json::to_value(response?)?)
+    }
+}
+
+/// Run a task on the best local model for its type
+async fn run_task_locally(model_manager: &ModelManager, task: &DelegatedTask) -> MisaResult<TaskResponse> {
+    let model_id = model_manager
+        .select_local_model_for_task(&task.task_type, &TaskPriority::Normal)
+        .await?;
+    let result = model_manager.execute_local_task(&task.task, &model_id, task.context.as_ref()).await?;
+
+    Ok(TaskResponse {
+        success: true,
+        task_id: uuid::Uuid::new_v4().to_string(),
+        assigned_device: None,
+        assigned_model: model_id,
+        estimated_duration: None,
+        result: Some(result),
+        error: None,
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskResponse {
+    pub success: bool,
+    pub task_id: String,
+    pub assigned_device: Option<String>,
+    pub assigned_model: String,
+    pub estimated_duration: Option<u64>,
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+}
+
+impl MisaKernel {
+    /// Create a new kernel instance
+    pub async fn new(
+        config_path: String,
+        data_dir: String,
+        security_manager: SecurityManager,
+    ) -> MisaResult<Self> {
+        // Load configuration
+        let config = Self::load_config(&config_path).unwrap_or_default();
+        Self::with_config(config, data_dir, security_manager).await
+    }
+
+    /// Create a kernel instance from an already loaded configuration
+    pub async fn with_config(
+        config: KernelConfig,
+        data_dir: String,
+        security_manager: SecurityManager,
+    ) -> MisaResult<Self> {
+        config.validate()?;
+
+        let metrics = if config.network.metrics_enabled {
+            Metrics::new()
+        } else {
+            Metrics::disabled()
+        };
+
+        // Initialize managers; their background jobs all run on one scheduler
+        let scheduler = Scheduler::new();
+        let privacy_controls = PrivacyControls::new(config.security.clone(), &data_dir)
+            .await?
+            .with_security_manager(security_manager.clone())
+            .with_scheduler(scheduler.clone());
+        let model_manager = ModelManager::new(config.models.clone())
+            .await?
+            .with_spend_store(&data_dir)
+            .await?
+            .with_metrics(metrics.clone())
+            .with_scheduler(scheduler.clone())
+            .with_privacy_controls(privacy_controls.clone());
+        let memory_manager = MemoryManager::new(&data_dir, config.memory.clone(), security_manager.clone())
+            .await?
+            .with_embedder(Arc::new(model_manager.clone()))
+            .with_content_filter(Arc::new(privacy_controls.data_controls()))
+            .with_metrics(metrics.clone())
+            .with_scheduler(scheduler.clone());
+        let privacy_controls = privacy_controls.with_memory_manager(memory_manager.clone());
+        let mut device_config = config.devices.clone();
+        if device_config.device_id.is_none() {
+            device_config.device_id = Some(load_or_create_device_id(&data_dir).await?);
+        }
+        let device_manager = DeviceManager::new(device_config, security_manager.clone())
+            .await?
+            .with_groups_store(&data_dir)
+            .await?
+            .with_metrics(metrics.clone())
+            .with_privacy_controls(privacy_controls.clone())
+            .with_task_handler(Arc::new(DelegatedTaskHandler {
+                model_manager: model_manager.clone(),
+                security_manager: security_manager.clone(),
+            }));
+        let telemetry = TelemetryManager::new(&config.telemetry, &data_dir, privacy_controls.clone())
+            .await?
+            .with_scheduler(scheduler.clone());
+
+        let kernel = Self {
+            config,
+            data_dir,
+            security_manager,
+            model_manager,
+            device_manager,
+            memory_manager,
             privacy_controls,
+            metrics,
+            telemetry,
+            scheduler,
             active_plugins: Arc::new(RwLock::new(HashMap::new())),
-        })
+            plugin_events: broadcast::channel(64).0,
+            prediction_events: broadcast::channel(64).0,
+            prediction_task: Arc::new(RwLock::new(None)),
+            anomaly_events: broadcast::channel(64).0,
+        };
+        kernel.restore_plugins().await?;
+
+        info!("MISA Kernel initialized successfully");
+        Ok(kernel)
     }
 
     /// Start the kernel service
@@ -365,7 +1387,15 @@ impl MisaKernel {
         // Initialize subsystems
         self.model_manager.initialize().await?;
         self.device_manager.start_discovery().await?;
+        self.device_manager.start_heartbeats().await;
+        self.device_manager.start_clipboard_sync().await;
         self.memory_manager.initialize().await?;
+        self.privacy_controls.initialize().await?;
+        self.telemetry.start().await?;
+        self.start_prediction_events().await;
+        self.memory_manager
+            .start_anomaly_alerts(self.anomaly_events.clone(), DetectedAnomaly::clone, ANOMALY_SCAN_INTERVAL)
+            .await?;
 
         // Start API server
         let app = self.create_router();
@@ -381,27 +1411,135 @@ impl MisaKernel {
         Ok(())
     }
 
+    /// Publish predictions on the kernel's event bus until shutdown. Does nothing if already running.
+    async fn start_prediction_events(&self) {
+        let mut task = self.prediction_task.write().await;
+        if task.is_none() {
+            *task = Some(self.memory_manager.start_prediction_events(
+                self.prediction_events.clone(),
+                Prediction::clone,
+                PREDICTION_INTERVAL,
+            ));
+        }
+    }
+
     /// Shutdown the kernel gracefully
     pub async fn shutdown(&self) -> MisaResult<()> {
         info!("Shutting down kernel subsystems");
 
         // Stop all plugins
-        let mut plugins = self.active_plugins.write().await;
-        for (id, plugin) in plugins.iter_mut() {
-            if let PluginStatus::Running = plugin.status {
-                self.stop_plugin(id).await?;
-            }
+        let running: Vec<String> = self
+            .active_plugins
+            .read()
+            .await
+            .values()
+            .filter(|plugin| plugin.status == PluginStatus::Running)
+            .map(|plugin| plugin.id.clone())
+            .collect();
+        for id in running {
+            self.stop_plugin(&id).await?;
         }
 
         // Shutdown subsystems
+        if let Some(task) = self.prediction_task.write().await.take() {
+            task.abort();
+        }
         self.device_manager.shutdown().await?;
         self.memory_manager.shutdown().await?;
         self.model_manager.shutdown().await?;
+        self.privacy_controls.shutdown().await?;
+        self.telemetry.shutdown().await?;
+        self.scheduler.shutdown().await;
 
         info!("Kernel shutdown complete");
         Ok(())
     }
 
+    /// Probe each subsystem; a down model server degrades the kernel, anything else makes it unhealthy
+    pub async fn health(&self) -> HealthReport {
+        let (models, memory) = tokio::join!(
+            self.model_manager.ping_local_server(HEALTH_CHECK_TIMEOUT),
+            tokio::time::timeout(HEALTH_CHECK_TIMEOUT, self.memory_manager.ping_database()),
+        );
+        let memory = memory.unwrap_or_else(|_| Err(MisaError::Timeout("Memory database did not answer".to_string())));
+
+        let has_master_key = self.security_manager.has_master_key().await;
+        let security = SubsystemHealth {
+            name: "security".to_string(),
+            up: has_master_key,
+            latency_ms: None,
+            detail: (!has_master_key).then(|| "Master key not initialized".to_string()),
+        };
+
+        let subsystems = vec![
+            SubsystemHealth::from_probe("models", models),
+            SubsystemHealth::from_probe("memory", memory),
+            security,
+        ];
+        let status = if subsystems.iter().all(|subsystem| subsystem.up) {
+            HealthStatus::Healthy
+        } else if subsystems.iter().all(|subsystem| subsystem.up || subsystem.name == "models") {
+            HealthStatus::Degraded
+        } else {
+            HealthStatus::Unhealthy
+        };
+
+        HealthReport {
+            status,
+            version: crate::VERSION.to_string(),
+            timestamp: chrono::Utc::now(),
+            subsystems,
+        }
+    }
+
+    /// Refresh gauges and render all metrics in the Prometheus text format
+    pub async fn render_metrics(&self) -> String {
+        match self.memory_manager.count_memories().await {
+            Ok(count) => self.metrics.set(metrics::MEMORY_ITEMS, &[], count as f64).await,
+            Err(e) => warn!("Failed to count memories for metrics: {}", e),
+        }
+        let connections = self.device_manager.active_connection_count().await;
+        self.metrics.set(metrics::ACTIVE_CONNECTIONS, &[], connections as f64).await;
+
+        self.metrics.render().await
+    }
+
+    /// Pipeline that captures `user_id`'s screen, reads it with a local vision model
+    /// and remembers a summary of it
+    pub fn screen_memory(&self, user_id: &str) -> AIManager {
+        let screen_reader = VisionScreenReader::new(
+            user_id,
+            self.model_manager.clone(),
+            self.privacy_controls.clone(),
+            self.security_manager.clone(),
+        );
+        AIManager::new(
+            user_id,
+            Arc::new(screen_reader),
+            Arc::new(self.model_manager.clone()),
+            self.memory_manager.clone(),
+            self.privacy_controls.clone(),
+            self.security_manager.clone(),
+            self.config.memory.retention_days,
+        )
+    }
+
+    /// Stop telemetry for good: nothing is collected or sent again, even after a restart
+    pub async fn disable_telemetry(&self) -> MisaResult<()> {
+        self.telemetry.kill_switch().await
+    }
+
+    /// Count a use of `feature`, and the class of its error if it failed
+    async fn record_usage<T>(&self, feature: &'static str, result: &MisaResult<T>) {
+        let mut recorded = self.telemetry.record_feature(feature).await.map(|_| ());
+        if let Err(e) = result {
+            recorded = recorded.and(self.telemetry.record_error(e).await.map(|_| ()));
+        }
+        if let Err(e) = recorded {
+            warn!("Failed to record telemetry for {}: {}", feature, e);
+        }
+    }
+
     /// Switch to a different AI model
     pub async fn switch_model(&self, request: SwitchModelRequest) -> MisaResult<String> {
         self.model_manager.switch_model(
@@ -416,19 +1554,50 @@ impl MisaKernel {
         // Analyze task requirements
         let task_type = self.analyze_task_type(&request.task, &request.task_type);
 
+        let priority = request.priority.as_ref().unwrap_or(&TaskPriority::Normal);
+
         // Select optimal model
-        let model_id = self.model_manager.select_model_for_task(
+        let model_ranking = self.model_manager.rank_models_for_task_type(
             &task_type,
             request.device_preferences.as_deref(),
-            request.priority.as_ref().unwrap_or(&TaskPriority::Normal),
+            priority,
         ).await?;
+        let model_id = model_ranking[0].model_id.clone();
 
         // Select optimal device if specified
-        let assigned_device = if let Some(preferences) = &request.device_preferences {
-            self.device_manager.select_device(preferences).await?
+        let device_selection = if let Some(preferences) = &request.device_preferences {
+            Some(self.device_manager.select_device_scored(preferences, TaskProfile::from_task_type(&task_type)).await?)
         } else {
             None
         };
+        let assigned_device = device_selection.as_ref().and_then(|selection| selection.device_id.clone());
+
+        // Record why this model and device won before running anything; the record is
+        // diagnostic, so a failed write doesn't cost the user their task
+        let audited = self
+            .security_manager
+            .log_security_event(
+                None,
+                "route_decision",
+                &task_type,
+                AuditResult::Success,
+                serde_json::json!({
+                    "task_type": task_type,
+                    "priority": priority,
+                    "model": {
+                        "winner": model_id,
+                        "candidates": model_ranking,
+                    },
+                    "device": device_selection.map(|selection| serde_json::json!({
+                        "winner": selection.device_id,
+                        "candidates": selection.candidates,
+                    })),
+                }),
+            )
+            .await;
+        if let Err(e) = audited {
+            warn!("Failed to audit route decision for {} task: {}", task_type, e);
+        }
 
         // Execute task
         let result = self.execute_task(&request.task, &model_id, request.context.as_ref()).await?;
@@ -444,6 +1613,116 @@ impl MisaKernel {
         })
     }
 
+    /// Run a task on the most suitable other device, or locally when none is suitable
+    ///
+    /// Returns the `TaskResponse` of whichever kernel ran the task.
+    pub async fn delegate_task(&self, task: DelegatedTask, constraints: DelegationConstraints) -> MisaResult<serde_json::Value> {
+        let task = DelegatedTask {
+            task_type: self.analyze_task_type(&task.task, &task.task_type),
+            ..task
+        };
+        let profile = constraints.profile.unwrap_or_else(|| TaskProfile::from_task_type(&task.task_type));
+
+        let Some(device_id) = self.device_manager.select_device(&constraints.device_preferences, profile).await? else {
+            info!("No device suitable for {} task, running it locally", task.task_type);
+            return Ok(serde_json::to_value(run_task_locally(&self.model_manager, &task).await?)?);
+        };
+
+        let timeout = constraints.timeout_secs.map(Duration::from_secs).unwrap_or(DEFAULT_DELEGATION_TIMEOUT);
+        info!("Delegating {} task to device {}", task.task_type, device_id);
+        let reply = self
+            .device_manager
+            .send_request(&device_id, serde_json::to_value(&task)?, timeout)
+            .await?;
+
+        let mut response: TaskResponse = serde_json::from_value(reply.clone()).map_err(|_| {
+            MisaError::Device(format!(
+                "Device {} could not run the task: {}",
+                device_id,
+                reply["error"].as_str().unwrap_or("malformed response")
+            ))
+        })?;
+        response.assigned_device = Some(device_id);
+        Ok(serde_json::to_value(response)?)
+    }
+
+    /// Search memories, audit entries and consent purposes for `query`, newest first
+    ///
+    /// Every hit passes through the privacy filters and is dropped when the
+    /// query only matched content they redacted or withheld.
+    pub async fn global_search(&self, query: &str) -> MisaResult<GlobalSearchResults> {
+        let term = query.trim();
+        if term.is_empty() {
+            return Err(MisaError::Validation("Search query must not be empty".to_string()));
+        }
+        let needle = term.to_lowercase();
+        let mut candidates: Vec<(GlobalSearchHit, DataType)> = Vec::new();
+
+        let mut memory_query = SearchQuery::new();
+        memory_query.text = Some(term.to_string());
+        memory_query.limit = Some(GLOBAL_SEARCH_LIMIT_PER_SOURCE as u32);
+        for memory in self.memory_manager.search_memories(&memory_query).await? {
+            let data_type = DataType::for_memory(&memory);
+            candidates.push((
+                GlobalSearchHit {
+                    source: SearchSource::Memory,
+                    id: memory.id,
+                    snippet: memory.content,
+                    timestamp: memory.created_at,
+                },
+                data_type,
+            ));
+        }
+
+        let audit_hits: Vec<GlobalSearchHit> = self
+            .security_manager
+            .query_audit_log(&AuditQuery {
+                text: Some(term.to_string()),
+                limit: Some(GLOBAL_SEARCH_LIMIT_PER_SOURCE),
+                ..AuditQuery::default()
+            })
+            .await?
+            .into_iter()
+            .map(|entry| GlobalSearchHit {
+                source: SearchSource::Audit,
+                id: entry.id,
+                snippet: format!("{} {} {}", entry.action, entry.resource, entry.details),
+                timestamp: entry.timestamp,
+            })
+            .collect();
+        candidates.extend(audit_hits.into_iter().map(|hit| (hit, DataType::UsageData)));
+
+        for consent in self.privacy_controls.search_consents(term).await? {
+            let timestamp = consent.revoked_at.or(consent.granted_at).unwrap_or(chrono::DateTime::<chrono::Utc>::MIN_UTC);
+            candidates.push((
+                GlobalSearchHit {
+                    source: SearchSource::Consent,
+                    id: consent.consent_id,
+                    snippet: consent.purpose,
+                    timestamp,
+                },
+                DataType::PersonalInfo,
+            ));
+        }
+
+        let mut hits = Vec::new();
+        for (mut hit, data_type) in candidates {
+            match self.privacy_controls.filter_for_display(data_type, hit.snippet).await? {
+                Some(snippet) if snippet.to_lowercase().contains(&needle) => {
+                    hit.snippet = snippet;
+                    hits.push(hit);
+                }
+                _ => {}
+            }
+        }
+        hits.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+
+        Ok(GlobalSearchResults {
+            query: term.to_string(),
+            hits,
+        })
+    }
+
     /// Load kernel configuration from file
     fn load_config(path: &str) -> Option<KernelConfig> {
         match std::fs::read_to_string(path) {
@@ -465,12 +1744,19 @@ impl MisaKernel {
 
     /// Create the Axum router for API endpoints
     fn create_router(&self) -> Router {
-        Router::new()
+        let mut router = Router::new()
             .route("/health", get(health_check))
             .route("/api/v1/kernel/switch_model", post(switch_model_handler))
             .route("/api/v1/kernel/route_task", post(route_task_handler))
             .route("/ws", get(websocket_handler))
-            .with_state(Arc::new(self.clone()))
+            .route(DEVICE_CHANNEL_PATH, get(device_channel_handler))
+            .route(PLUGIN_REQUEST_PATH, post(plugin_request_handler));
+
+        if self.metrics.is_enabled() {
+            router = router.route("/metrics", get(metrics_handler));
+        }
+
+        router.with_state(Arc::new(self.clone()))
     }
 
     /// Analyze task type from content and hint
@@ -492,17 +1778,373 @@ impl MisaKernel {
         self.model_manager.execute_task(task, model_id, context).await
     }
 
-    /// Plugin management methods
-    async fn start_plugin(&self, plugin_id: &str) -> MisaResult<()> {
-        info!("Starting plugin: {}", plugin_id);
-        // Implementation for plugin startup
+    /// Register a plugin from its manifest; it stays stopped until started
+    pub async fn install_plugin(&self, manifest: PluginManifest) -> MisaResult<PluginInstance> {
+        if manifest.id.trim().is_empty() {
+            return Err(MisaError::Validation("Plugin id must not be empty".to_string()));
+        }
+
+        let executable = self.validate_plugin_executable(&manifest).await?;
+
+        let mut plugins = self.active_plugins.write().await;
+        if plugins.contains_key(&manifest.id) {
+            return Err(PluginError::PluginAlreadyInstalled { plugin_id: manifest.id }.into());
+        }
+
+        let plugin = PluginInstance {
+            id: manifest.id,
+            name: manifest.name,
+            version: manifest.version,
+            executable,
+            args: manifest.args,
+            capabilities: manifest.capabilities,
+            permissions: manifest.permissions,
+            pid: None,
+            sandbox_id: None,
+            status: PluginStatus::Stopped,
+            request_token: None,
+        };
+        plugins.insert(plugin.id.clone(), plugin.clone());
+        // Written under the lock so concurrent installs can't drop each other's manifest
+        self.persist_plugins(&plugins).await?;
+        drop(plugins);
+
+        info!("Installed plugin {} {}", plugin.id, plugin.version);
+        let _ = self.plugin_events.send(PluginEvent::Installed { plugin_id: plugin.id.clone() });
+        Ok(plugin)
+    }
+
+    /// Re-register the plugins installed before the last restart; they start out stopped
+    async fn restore_plugins(&self) -> MisaResult<()> {
+        let manifests: HashMap<String, PluginManifest> =
+            read_json_map(&std::path::Path::new(&self.data_dir).join(PLUGINS_FILE)).await?;
+
+        let mut plugins = self.active_plugins.write().await;
+        for manifest in manifests.into_values() {
+            // An executable removed since it was installed only takes its own plugin with it
+            let executable = match self.validate_plugin_executable(&manifest).await {
+                Ok(executable) => executable,
+                Err(e) => {
+                    warn!("Not restoring plugin {}: {}", manifest.id, e);
+                    continue;
+                }
+            };
+            plugins.insert(
+                manifest.id.clone(),
+                PluginInstance {
+                    id: manifest.id,
+                    name: manifest.name,
+                    version: manifest.version,
+                    executable,
+                    args: manifest.args,
+                    capabilities: manifest.capabilities,
+                    permissions: manifest.permissions,
+                    pid: None,
+                    sandbox_id: None,
+                    status: PluginStatus::Stopped,
+                    request_token: None,
+                },
+            );
+        }
+        if !plugins.is_empty() {
+            info!("Restored {} installed plugins", plugins.len());
+        }
         Ok(())
     }
 
-    async fn stop_plugin(&self, plugin_id: &str) -> MisaResult<()> {
+    /// Write the manifests of every installed plugin to the data directory
+    async fn persist_plugins(&self, plugins: &HashMap<String, PluginInstance>) -> MisaResult<()> {
+        let manifests: HashMap<&String, PluginManifest> = plugins
+            .iter()
+            .map(|(id, plugin)| {
+                (
+                    id,
+                    PluginManifest {
+                        id: plugin.id.clone(),
+                        name: plugin.name.clone(),
+                        version: plugin.version.clone(),
+                        executable: plugin.executable.clone(),
+                        args: plugin.args.clone(),
+                        capabilities: plugin.capabilities.clone(),
+                        permissions: plugin.permissions.clone(),
+                    },
+                )
+            })
+            .collect();
+        write_json_atomic(&std::path::Path::new(&self.data_dir).join(PLUGINS_FILE), &manifests).await
+    }
+
+    /// Directory a plugin runs in, outside the data directory
+    async fn plugin_work_dir(plugin_id: &str) -> MisaResult<std::path::PathBuf> {
+        // Plugin ids are caller supplied, so they never name anything but a single directory
+        let name: String = plugin_id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        let work_dir = std::env::temp_dir().join(PLUGIN_WORK_DIR).join(name);
+        tokio::fs::create_dir_all(&work_dir).await?;
+        Ok(work_dir)
+    }
+
+    /// Resolve a manifest's executable, which must be an executable file inside the plugins directory
+    async fn validate_plugin_executable(&self, manifest: &PluginManifest) -> MisaResult<String> {
+        let incompatible = |reason: String| -> MisaError { PluginError::PluginIncompatible { reason }.into() };
+
+        let plugins_dir = std::path::Path::new(&self.data_dir).join(PLUGINS_DIR);
+        let plugins_dir = tokio::fs::canonicalize(&plugins_dir)
+            .await
+            .map_err(|_| incompatible(format!("No plugins directory at {}", plugins_dir.display())))?;
+
+        // Resolving links and `..` first keeps absolute paths and escapes out of the plugins directory
+        let executable = tokio::fs::canonicalize(plugins_dir.join(&manifest.executable))
+            .await
+            .map_err(|e| incompatible(format!("Plugin {} executable {}: {}", manifest.id, manifest.executable, e)))?;
+        if !executable.starts_with(&plugins_dir) {
+            return Err(incompatible(format!(
+                "Plugin {} executable {} is outside {}",
+                manifest.id,
+                manifest.executable,
+                plugins_dir.display()
+            )));
+        }
+
+        let metadata = tokio::fs::metadata(&executable).await?;
+        if !metadata.is_file() {
+            return Err(incompatible(format!("Plugin {} executable {} is not a file", manifest.id, manifest.executable)));
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if metadata.permissions().mode() & 0o111 == 0 {
+                return Err(incompatible(format!("Plugin {} executable {} is not executable", manifest.id, manifest.executable)));
+            }
+        }
+
+        Ok(executable.to_string_lossy().into_owned())
+    }
+
+    /// Run an installed plugin as a monitored child process with only its declared permissions.
+    ///
+    /// The process inherits none of the kernel's environment, so provider API keys stay
+    /// with the kernel; it gets `PATH`, its id and the token it authenticates requests with.
+    pub async fn start_plugin(&self, plugin_id: &str) -> MisaResult<PluginInstance> {
+        let (mut command, permissions) = {
+            let mut plugins = self.active_plugins.write().await;
+            let plugin = plugins
+                .get_mut(plugin_id)
+                .ok_or_else(|| PluginError::PluginNotFound { plugin_id: plugin_id.to_string() })?;
+            if matches!(plugin.status, PluginStatus::Running | PluginStatus::Starting) {
+                return Ok(plugin.clone());
+            }
+            plugin.status = PluginStatus::Starting;
+
+            let mut command = tokio::process::Command::new(&plugin.executable);
+            command.args(&plugin.args);
+            (command, plugin.permissions.clone())
+        };
+        let request_token = uuid::Uuid::new_v4().simple().to_string();
+        command.env_clear();
+        if let Some(path) = std::env::var_os("PATH") {
+            command.env("PATH", path);
+        }
+        command.env("MISA_PLUGIN_ID", plugin_id);
+        command.env("MISA_PLUGIN_TOKEN", &request_token);
+        match Self::plugin_work_dir(plugin_id).await {
+            Ok(work_dir) => {
+                command.current_dir(work_dir);
+            }
+            Err(e) => {
+                self.active_plugins.write().await.entry(plugin_id.to_string()).and_modify(|plugin| {
+                    plugin.status = PluginStatus::Error(e.to_string());
+                });
+                return Err(e);
+            }
+        }
+
+        info!("Starting plugin: {}", plugin_id);
+        let started = match self.security_manager.create_plugin_sandbox(plugin_id, command, permissions).await {
+            Ok(sandbox_id) => self
+                .security_manager
+                .get_plugin_sandbox(&sandbox_id)
+                .await
+                .map(|sandbox| (sandbox_id, sandbox.pid))
+                .ok_or_else(|| PluginError::PluginLoadingFailed { plugin_id: plugin_id.to_string() }.into()),
+            Err(e) => Err(e),
+        };
+
+        let mut plugins = self.active_plugins.write().await;
+        let plugin = plugins
+            .get_mut(plugin_id)
+            .ok_or_else(|| PluginError::PluginNotFound { plugin_id: plugin_id.to_string() })?;
+        match started {
+            Ok((sandbox_id, pid)) => {
+                plugin.sandbox_id = Some(sandbox_id);
+                plugin.pid = Some(pid);
+                plugin.request_token = Some(request_token);
+                plugin.status = PluginStatus::Running;
+                let _ = self.plugin_events.send(PluginEvent::Started { plugin_id: plugin_id.to_string(), pid });
+                Ok(plugin.clone())
+            }
+            Err(e) => {
+                warn!("Failed to start plugin {}: {}", plugin_id, e);
+                plugin.status = PluginStatus::Error(e.to_string());
+                let _ = self.plugin_events.send(PluginEvent::Failed {
+                    plugin_id: plugin_id.to_string(),
+                    error: e.to_string(),
+                });
+                Err(e)
+            }
+        }
+    }
+
+    /// Stop a plugin's sandbox; stopping a plugin that isn't running is a no-op
+    pub async fn stop_plugin(&self, plugin_id: &str) -> MisaResult<PluginInstance> {
+        let sandbox_id = {
+            let mut plugins = self.active_plugins.write().await;
+            let plugin = plugins
+                .get_mut(plugin_id)
+                .ok_or_else(|| PluginError::PluginNotFound { plugin_id: plugin_id.to_string() })?;
+            match plugin.sandbox_id.take() {
+                Some(sandbox_id) => {
+                    plugin.status = PluginStatus::Stopping;
+                    sandbox_id
+                }
+                None => return Ok(plugin.clone()),
+            }
+        };
+
         info!("Stopping plugin: {}", plugin_id);
-        // Implementation for plugin shutdown
-        Ok(())
+        if let Err(e) = self.security_manager.stop_plugin_sandbox(&sandbox_id).await {
+            // The sandbox already ended on its own
+            warn!("Plugin {} sandbox was not running: {}", plugin_id, e);
+        }
+
+        let mut plugins = self.active_plugins.write().await;
+        let plugin = plugins
+            .get_mut(plugin_id)
+            .ok_or_else(|| PluginError::PluginNotFound { plugin_id: plugin_id.to_string() })?;
+        plugin.pid = None;
+        plugin.request_token = None;
+        plugin.status = PluginStatus::Stopped;
+        let _ = self.plugin_events.send(PluginEvent::Stopped { plugin_id: plugin_id.to_string() });
+        Ok(plugin.clone())
+    }
+
+    /// Installed plugins sorted by id, with running ones checked against their sandbox
+    pub async fn list_plugins(&self) -> Vec<PluginInstance> {
+        let mut plugins = self.active_plugins.write().await;
+        for plugin in plugins.values_mut() {
+            let Some(sandbox_id) = plugin.sandbox_id.clone() else { continue };
+            let Some(sandbox) = self.security_manager.get_plugin_sandbox(&sandbox_id).await else { continue };
+
+            // A sandbox that ended by itself takes the plugin down with it
+            let ended = match sandbox.status {
+                SandboxStatus::Stopped => Some(PluginStatus::Stopped),
+                SandboxStatus::Error(error) => Some(PluginStatus::Error(error)),
+                SandboxStatus::Running | SandboxStatus::Suspended => None,
+            };
+            if let Some(status) = ended {
+                plugin.sandbox_id = None;
+                plugin.pid = None;
+                plugin.request_token = None;
+                let event = match &status {
+                    PluginStatus::Error(error) => PluginEvent::Failed { plugin_id: plugin.id.clone(), error: error.clone() },
+                    _ => PluginEvent::Stopped { plugin_id: plugin.id.clone() },
+                };
+                plugin.status = status;
+                let _ = self.plugin_events.send(event);
+            }
+        }
+
+        let mut listed: Vec<PluginInstance> = plugins.values().cloned().collect();
+        listed.sort_by(|a, b| a.id.cmp(&b.id));
+        listed
+    }
+
+    /// Check a plugin's request against the permissions it declared, auditing denials
+    pub async fn authorize_plugin(&self, plugin_id: &str, permission: &str) -> MisaResult<()> {
+        let declared = self
+            .active_plugins
+            .read()
+            .await
+            .get(plugin_id)
+            .map(|plugin| plugin.permissions.clone())
+            .ok_or_else(|| PluginError::PluginNotFound { plugin_id: plugin_id.to_string() })?;
+
+        if PermissionChecker::is_declared(&declared, permission) {
+            return Ok(());
+        }
+
+        self.security_manager
+            .log_security_event(
+                None,
+                "plugin_permission_denied",
+                permission,
+                AuditResult::Failure,
+                serde_json::json!({ "plugin_id": plugin_id, "declared": declared }),
+            )
+            .await?;
+        Err(PluginError::InsufficientPermissions {
+            required_permissions: vec![permission.to_string()],
+        }
+        .into())
+    }
+
+    /// Running plugin a request token was handed to
+    async fn plugin_for_token(&self, token: &str) -> Option<String> {
+        self.active_plugins
+            .read()
+            .await
+            .values()
+            .filter(|plugin| plugin.status == PluginStatus::Running)
+            .find(|plugin| {
+                plugin.request_token.as_deref().map_or(false, |expected| {
+                    ring::constant_time::verify_slices_are_equal(expected.as_bytes(), token.as_bytes()).is_ok()
+                })
+            })
+            .map(|plugin| plugin.id.clone())
+    }
+
+    /// Carry out a plugin's request once the permissions it declared allow it
+    pub async fn handle_plugin_request(&self, plugin_id: &str, request: PluginRequest) -> MisaResult<serde_json::Value> {
+        self.authorize_plugin(plugin_id, request.permission()).await?;
+
+        match request {
+            PluginRequest::SearchMemories { query, limit } => {
+                let mut memory_query = SearchQuery::new();
+                memory_query.text = Some(query);
+                memory_query.limit = Some(limit.unwrap_or(PLUGIN_SEARCH_LIMIT).min(PLUGIN_SEARCH_LIMIT));
+
+                // Plugins only ever see memory content through the privacy filters
+                let data_controls = self.privacy_controls.data_controls();
+                let mut memories = self.memory_manager.search_memories(&memory_query).await?;
+                for memory in &mut memories {
+                    memory.content = data_controls.apply_filters(&memory.content).await.0;
+                }
+                Ok(serde_json::to_value(memories)?)
+            }
+            PluginRequest::ListDevices => Ok(serde_json::to_value(self.device_manager.get_devices().await?)?),
+        }
+    }
+
+    /// Receive plugin lifecycle events
+    pub fn subscribe_plugin_events(&self) -> broadcast::Receiver<PluginEvent> {
+        self.plugin_events.subscribe()
+    }
+
+    /// Receive predictions as they are generated, each suggestion once while it is valid
+    pub fn subscribe_predictions(&self) -> broadcast::Receiver<Prediction> {
+        self.prediction_events.subscribe()
+    }
+
+    /// Receive anomalies found in recent memories, each once
+    pub fn subscribe_anomalies(&self) -> broadcast::Receiver<DetectedAnomaly> {
+        self.anomaly_events.subscribe()
+    }
+
+    /// Receive re-consent prompts and other privacy events
+    pub fn subscribe_privacy_events(&self) -> broadcast::Receiver<PrivacyEvent> {
+        self.privacy_controls.subscribe_events()
     }
 }
 
@@ -517,25 +2159,42 @@ impl Clone for MisaKernel {
             device_manager: self.device_manager.clone(),
             memory_manager: self.memory_manager.clone(),
             privacy_controls: self.privacy_controls.clone(),
+            metrics: self.metrics.clone(),
+            telemetry: self.telemetry.clone(),
+            scheduler: self.scheduler.clone(),
             active_plugins: Arc::clone(&self.active_plugins),
+            plugin_events: self.plugin_events.clone(),
+            prediction_events: self.prediction_events.clone(),
+            prediction_task: Arc::clone(&self.prediction_task),
+            anomaly_events: self.anomaly_events.clone(),
         }
     }
 }
 
 // API Handlers
-async fn health_check() -> impl IntoResponse {
-    Json(serde_json::json!({
-        "status": "healthy",
-        "version": crate::VERSION,
-        "timestamp": chrono::Utc::now().to_rfc3339()
-    }))
+async fn health_check(State(kernel): State<Arc<MisaKernel>>) -> impl IntoResponse {
+    let report = kernel.health().await;
+    let status = match report.status {
+        HealthStatus::Healthy | HealthStatus::Degraded => StatusCode::OK,
+        HealthStatus::Unhealthy => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (status, Json(report))
+}
+
+async fn metrics_handler(State(kernel): State<Arc<MisaKernel>>) -> impl IntoResponse {
+    (
+        [(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        kernel.render_metrics().await,
+    )
 }
 
 async fn switch_model_handler(
     State(kernel): State<Arc<MisaKernel>>,
     Json(request): Json<SwitchModelRequest>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    match kernel.switch_model(request).await {
+    let result = kernel.switch_model(request).await;
+    kernel.record_usage("kernel.switch_model", &result).await;
+    match result {
         Ok(model_id) => Ok(Json(serde_json::json!({
             "success": true,
             "model_id": model_id
@@ -548,7 +2207,9 @@ async fn route_task_handler(
     State(kernel): State<Arc<MisaKernel>>,
     Json(request): Json<RouteTaskRequest>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    match kernel.route_task(request).await {
+    let result = kernel.route_task(request).await;
+    kernel.record_usage("kernel.route_task", &result).await;
+    match result {
         Ok(response) => Ok(Json(response)),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
@@ -561,21 +2222,128 @@ async fn websocket_handler(
     ws.on_upgrade(move |socket| handle_websocket(socket, kernel))
 }
 
+/// Running plugins authenticate with the token they were started with
+async fn plugin_request_handler(
+    State(kernel): State<Arc<MisaKernel>>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<PluginRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let token = headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    let plugin_id = kernel.plugin_for_token(token).await.ok_or(StatusCode::UNAUTHORIZED)?;
+
+    match kernel.handle_plugin_request(&plugin_id, request).await {
+        Ok(result) => Ok(Json(result)),
+        Err(MisaError::Plugin(_)) => Err(StatusCode::FORBIDDEN),
+        Err(e) => {
+            warn!("Plugin {} request failed: {}", plugin_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Peers dial this endpoint to open a device channel to this device
+async fn device_channel_handler(
+    ws: WebSocketUpgrade,
+    State(kernel): State<Arc<MisaKernel>>,
+) -> impl IntoResponse {
+    use futures_util::SinkExt;
+
+    ws.on_upgrade(move |socket| async move {
+        let (sink, stream) = socket.split();
+        let sink = sink
+            .sink_map_err(|e| MisaError::Device(format!("WebSocket send failed: {}", e)))
+            .with(|text: String| futures_util::future::ok::<_, MisaError>(Message::Text(text)));
+        let stream = stream.filter_map(|frame| {
+            futures_util::future::ready(match frame {
+                Ok(Message::Text(text)) => Some(Ok(text)),
+                Ok(_) => None,
+                Err(e) => Some(Err(MisaError::Device(format!("WebSocket receive failed: {}", e)))),
+            })
+        });
+
+        if let Err(e) = kernel.device_manager.accept_device_channel(Box::pin(sink), stream.boxed()).await {
+            warn!("Refused a device channel: {}", e);
+        }
+    })
+}
+
+/// Streaming requests sent over the kernel WebSocket, tagged by `type`
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamRequest {
+    Generate {
+        prompt: String,
+        #[serde(default)]
+        model: Option<String>,
+    },
+    /// Keep the connection open and push predictions as they are generated
+    SubscribePredictions,
+    /// Keep the connection open and push privacy events, e.g. consents needing renewal
+    SubscribePrivacyEvents,
+    /// Keep the connection open and push anomalies as scans find them
+    SubscribeAnomalies,
+}
+
+/// Frames sent back while answering a streaming request
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamFrame {
+    Token { content: String },
+    Done,
+    Error { message: String },
+    Prediction { prediction_type: String, suggestion: String, confidence: f32 },
+    ReConsentRequired { user_id: String, consent_id: String, consent_type: ConsentType, expired: bool },
+    Anomaly { anomaly_type: String, severity: String, description: String },
+}
+
 async fn handle_websocket(
-    mut socket: axum::extract::ws::WebSocket,
+    mut socket: WebSocket,
     kernel: Arc<MisaKernel>,
 ) {
     info!("WebSocket connection established");
 
     while let Some(msg) = socket.recv().await {
         match msg {
-            Ok(axum::extract::ws::Message::Text(text)) => {
+            Ok(Message::Text(text)) => {
+                match serde_json::from_str(&text) {
+                    Ok(StreamRequest::Generate { prompt, model }) => {
+                        if let Err(e) = stream_generation(&mut socket, &kernel, &prompt, model.as_deref()).await {
+                            error!("WebSocket stream error: {}", e);
+                            break;
+                        }
+                        continue;
+                    }
+                    Ok(StreamRequest::SubscribePredictions) => {
+                        if let Err(e) = stream_predictions(&mut socket, &kernel).await {
+                            error!("WebSocket prediction stream error: {}", e);
+                        }
+                        break;
+                    }
+                    Ok(StreamRequest::SubscribePrivacyEvents) => {
+                        if let Err(e) = stream_privacy_events(&mut socket, &kernel).await {
+                            error!("WebSocket privacy event stream error: {}", e);
+                        }
+                        break;
+                    }
+                    Ok(StreamRequest::SubscribeAnomalies) => {
+                        if let Err(e) = stream_anomalies(&mut socket, &kernel).await {
+                            error!("WebSocket anomaly stream error: {}", e);
+                        }
+                        break;
+                    }
+                    Err(_) => {}
+                }
+
                 // Handle JSON-RPC requests
                 if let Err(e) = handle_json_rpc(&text, &kernel, &mut socket).await {
                     error!("JSON-RPC error: {}", e);
                 }
             }
-            Ok(axum::extract::ws::Message::Close(_)) => {
+            Ok(Message::Close(_)) => {
                 info!("WebSocket connection closed");
                 break;
             }
@@ -588,10 +2356,130 @@ async fn handle_websocket(
     }
 }
 
+/// Pipe a streaming model response to the client as token frames, ending with `done`
+///
+/// Other messages from the client are ignored until the stream ends. If the client
+/// disconnects the upstream stream is dropped, which aborts the model request.
+async fn stream_generation(
+    socket: &mut WebSocket,
+    kernel: &MisaKernel,
+    prompt: &str,
+    model: Option<&str>,
+) -> Result<(), axum::Error> {
+    let stream = match kernel.model_manager.generate_stream(prompt, model).await {
+        Ok(stream) => stream,
+        Err(e) => return send_frame(socket, &StreamFrame::Error { message: e.to_string() }).await,
+    };
+    let mut stream = Box::pin(stream);
+
+    loop {
+        tokio::select! {
+            fragment = stream.next() => match fragment {
+                Some(Ok(content)) if content.is_empty() => {}
+                Some(Ok(content)) => send_frame(socket, &StreamFrame::Token { content }).await?,
+                Some(Err(e)) => return send_frame(socket, &StreamFrame::Error { message: e.to_string() }).await,
+                None => return send_frame(socket, &StreamFrame::Done).await,
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => {
+                    info!("WebSocket client disconnected mid-stream, cancelling generation");
+                    return Ok(());
+                }
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+/// Push each prediction to the client as a `prediction` frame until it disconnects
+async fn stream_predictions(socket: &mut WebSocket, kernel: &MisaKernel) -> Result<(), axum::Error> {
+    let mut predictions = kernel.subscribe_predictions();
+
+    loop {
+        tokio::select! {
+            prediction = predictions.recv() => match prediction {
+                Ok(prediction) => {
+                    let frame = StreamFrame::Prediction {
+                        prediction_type: prediction.prediction_type,
+                        suggestion: prediction.suggestion,
+                        confidence: prediction.confidence,
+                    };
+                    send_frame(socket, &frame).await?;
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!("Prediction subscriber fell behind, skipped {} predictions", missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return Ok(()),
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+/// Push each privacy event to the client as a frame until it disconnects
+async fn stream_privacy_events(socket: &mut WebSocket, kernel: &MisaKernel) -> Result<(), axum::Error> {
+    let mut events = kernel.subscribe_privacy_events();
+
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(PrivacyEvent::ReConsentRequired { user_id, consent_id, consent_type, expired, .. }) => {
+                    let frame = StreamFrame::ReConsentRequired { user_id, consent_id, consent_type, expired };
+                    send_frame(socket, &frame).await?;
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!("Privacy event subscriber fell behind, skipped {} events", missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return Ok(()),
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+/// Push each anomaly to the client as an `anomaly` frame until it disconnects
+async fn stream_anomalies(socket: &mut WebSocket, kernel: &MisaKernel) -> Result<(), axum::Error> {
+    let mut anomalies = kernel.subscribe_anomalies();
+
+    loop {
+        tokio::select! {
+            anomaly = anomalies.recv() => match anomaly {
+                Ok(anomaly) => {
+                    let frame = StreamFrame::Anomaly {
+                        anomaly_type: format!("{:?}", anomaly.anomaly_type),
+                        severity: format!("{:?}", anomaly.severity),
+                        description: anomaly.description,
+                    };
+                    send_frame(socket, &frame).await?;
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!("Anomaly subscriber fell behind, skipped {} anomalies", missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return Ok(()),
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+async fn send_frame(socket: &mut WebSocket, frame: &StreamFrame) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(frame).map_err(axum::Error::new)?;
+    socket.send(Message::Text(text)).await
+}
+
 async fn handle_json_rpc(
     text: &str,
     kernel: &MisaKernel,
-    socket: &mut axum::extract::ws::WebSocket,
+    socket: &mut WebSocket,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let rpc_request: serde_json::Value = serde_json::from_str(text)?;
 
@@ -618,7 +2506,899 @@ async fn handle_json_rpc(
     });
 
     let response_text = response.to_string();
-    socket.send(axum::extract::ws::Message::Text(response_text)).await?;
+    socket.send(Message::Text(response_text)).await?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn test_kernel(data_dir: &tempfile::TempDir, config: KernelConfig) -> MisaKernel {
+        let dir = data_dir.path().to_str().unwrap().to_string();
+        let security_manager = SecurityManager::new(&dir, SecurityConfig::default()).await.unwrap();
+        security_manager.initialize().await.unwrap();
+        MisaKernel::with_config(config, dir, security_manager).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_metrics_count_model_executions() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/tags"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "models": [{"name": "mixtral", "size": 26, "digest": "abc", "modified_at": "2024-01-01T00:00:00Z"}]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "model": "mixtral",
+                "response": "Hello there",
+                "done": true,
+                "eval_count": 20,
+                "prompt_eval_count": 5
+            })))
+            .mount(&server)
+            .await;
+
+        let data_dir = tempfile::tempdir().unwrap();
+        let mut config = KernelConfig::default();
+        config.models.local_server_url = server.uri();
+        config.models.cloud_providers.clear();
+        config.memory.encryption_enabled = false;
+        config.network.metrics_enabled = true;
+        let kernel = test_kernel(&data_dir, config).await;
+
+        let executions = "requests_total{subsystem=\"models\",operation=\"execute\",outcome=\"success\"}";
+        assert!(!kernel.render_metrics().await.contains(executions));
+
+        kernel.execute_task("Say hello", "mixtral", None).await.unwrap();
+
+        let scraped = kernel.render_metrics().await;
+        assert!(scraped.contains(&format!("{} 1", executions)), "{}", scraped);
+        assert!(scraped.contains("model_latency_seconds_count{model=\"mixtral\"} 1"));
+        assert!(scraped.contains("memory_items 0"));
+        assert!(scraped.contains("# TYPE requests_total counter"));
+    }
+
+    /// Serve `kernel`'s router on an ephemeral port, returning its base URL
+    fn serve_router(kernel: &MisaKernel) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = kernel.create_router();
+        tokio::spawn(async move {
+            axum::Server::from_tcp(listener).unwrap().serve(app.into_make_service()).await.unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_serves_prometheus_text() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let mut config = KernelConfig::default();
+        config.models.cloud_providers.clear();
+        config.memory.encryption_enabled = false;
+        config.network.metrics_enabled = true;
+        let kernel = test_kernel(&data_dir, config).await;
+        let base_url = serve_router(&kernel);
+
+        let response = reqwest::get(format!("{}/metrics", base_url)).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(response.headers()[reqwest::header::CONTENT_TYPE], "text/plain; version=0.0.4");
+        let body = response.text().await.unwrap();
+        assert!(body.contains("memory_items 0"), "{}", body);
+        assert!(body.contains("active_connections 0"), "{}", body);
+
+        // Without the flag the route is not mounted at all
+        let disabled_dir = tempfile::tempdir().unwrap();
+        let mut config = KernelConfig::default();
+        config.models.cloud_providers.clear();
+        config.memory.encryption_enabled = false;
+        let disabled = test_kernel(&disabled_dir, config).await;
+        let response = reqwest::get(format!("{}/metrics", serve_router(&disabled))).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_health_is_degraded_when_ollama_is_down() {
+        // Nothing listens on the discard port, so the model server is unreachable
+        let data_dir = tempfile::tempdir().unwrap();
+        let mut config = KernelConfig::default();
+        config.models.local_server_url = "http://127.0.0.1:9".to_string();
+        config.models.cloud_providers.clear();
+        config.memory.encryption_enabled = false;
+        let kernel = test_kernel(&data_dir, config).await;
+
+        let report = kernel.health().await;
+        assert_eq!(report.status, HealthStatus::Degraded);
+        let subsystem = |name: &str| report.subsystems.iter().find(|s| s.name == name).unwrap().clone();
+        assert!(!subsystem("models").up);
+        assert!(subsystem("models").detail.is_some());
+        assert!(subsystem("memory").up);
+        assert!(subsystem("security").up);
+
+        let response = health_check(State(Arc::new(kernel))).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_health_is_unhealthy_when_the_database_is_down() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let mut config = KernelConfig::default();
+        config.models.local_server_url = "http://127.0.0.1:9".to_string();
+        config.models.cloud_providers.clear();
+        config.memory.encryption_enabled = false;
+        let kernel = test_kernel(&data_dir, config).await;
+        kernel.memory_manager.shutdown().await.unwrap();
+
+        let report = kernel.health().await;
+        assert_eq!(report.status, HealthStatus::Unhealthy);
+
+        let response = health_check(State(Arc::new(kernel))).await.into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_websocket_streams_generated_tokens() {
+        use futures_util::SinkExt;
+        use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/tags"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "models": [{"name": "mixtral", "size": 26, "digest": "abc", "modified_at": "2024-01-01T00:00:00Z"}]
+            })))
+            .mount(&server)
+            .await;
+        let body = concat!(
+            r#"{"model":"mixtral","response":"Hel","done":false}"#, "\n",
+            r#"{"model":"mixtral","response":"lo","done":false}"#, "\n",
+            r#"{"model":"mixtral","response":"","done":true}"#, "\n",
+        );
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(body))
+            .mount(&server)
+            .await;
+
+        let data_dir = tempfile::tempdir().unwrap();
+        let mut config = KernelConfig::default();
+        config.models.local_server_url = server.uri();
+        config.models.cloud_providers.clear();
+        config.memory.encryption_enabled = false;
+        let kernel = test_kernel(&data_dir, config).await;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = kernel.create_router();
+        tokio::spawn(async move {
+            axum::Server::from_tcp(listener).unwrap().serve(app.into_make_service()).await.unwrap();
+        });
+
+        let (mut client, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws", addr)).await.unwrap();
+        let request = serde_json::json!({"type": "generate", "prompt": "Say hello", "model": "mixtral"});
+        client.send(WsMessage::Text(request.to_string())).await.unwrap();
+
+        let mut frames = Vec::new();
+        while let Some(message) = client.next().await {
+            let frame: serde_json::Value = serde_json::from_str(message.unwrap().to_text().unwrap()).unwrap();
+            let done = frame["type"] == "done";
+            frames.push(frame);
+            if done {
+                break;
+            }
+        }
+
+        assert_eq!(
+            frames,
+            vec![
+                serde_json::json!({"type": "token", "content": "Hel"}),
+                serde_json::json!({"type": "token", "content": "lo"}),
+                serde_json::json!({"type": "done"}),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_websocket_pushes_predictions_to_subscribers() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let mut config = KernelConfig::default();
+        config.models.cloud_providers.clear();
+        config.memory.encryption_enabled = false;
+        let kernel = test_kernel(&data_dir, config).await;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = kernel.create_router();
+        tokio::spawn(async move {
+            axum::Server::from_tcp(listener).unwrap().serve(app.into_make_service()).await.unwrap();
+        });
+
+        let (mut client, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws", addr)).await.unwrap();
+        client.send(WsMessage::Text(r#"{"type": "subscribe_predictions"}"#.to_string())).await.unwrap();
+
+        // Publish only once the socket is listening, as the prediction task would
+        while kernel.prediction_events.receiver_count() == 0 {
+            tokio::task::yield_now().await;
+        }
+        kernel.prediction_events.send(Prediction {
+            prediction_type: "time_based".to_string(),
+            confidence: 0.5,
+            suggestion: "Plan your morning".to_string(),
+            supporting_memories: Vec::new(),
+            valid_until: chrono::Utc::now() + chrono::Duration::hours(1),
+        }).unwrap();
+
+        let message = tokio::time::timeout(Duration::from_secs(5), client.next()).await.unwrap().unwrap().unwrap();
+        let frame: serde_json::Value = serde_json::from_str(message.to_text().unwrap()).unwrap();
+        assert_eq!(
+            frame,
+            serde_json::json!({
+                "type": "prediction",
+                "prediction_type": "time_based",
+                "suggestion": "Plan your morning",
+                "confidence": 0.5,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_websocket_pushes_re_consent_prompts_to_subscribers() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let mut config = KernelConfig::default();
+        config.models.cloud_providers.clear();
+        config.memory.encryption_enabled = false;
+        let kernel = test_kernel(&data_dir, config).await;
+        let base_url = serve_router(&kernel);
+
+        let ws_url = format!("{}/ws", base_url.replacen("http", "ws", 1));
+        let (mut client, _) = tokio_tungstenite::connect_async(ws_url).await.unwrap();
+        client.send(WsMessage::Text(r#"{"type": "subscribe_privacy_events"}"#.to_string())).await.unwrap();
+
+        let events = kernel.privacy_controls.test_events();
+        while events.receiver_count() == 0 {
+            tokio::task::yield_now().await;
+        }
+        events.send(PrivacyEvent::ReConsentRequired {
+            user_id: "user-1".to_string(),
+            consent_id: "consent-1".to_string(),
+            consent_type: ConsentType::ScreenCapture,
+            expires_at: chrono::Utc::now(),
+            expired: true,
+        }).unwrap();
+
+        let message = tokio::time::timeout(Duration::from_secs(5), client.next()).await.unwrap().unwrap().unwrap();
+        let frame: serde_json::Value = serde_json::from_str(message.to_text().unwrap()).unwrap();
+        assert_eq!(
+            frame,
+            serde_json::json!({
+                "type": "re_consent_required",
+                "user_id": "user-1",
+                "consent_id": "consent-1",
+                "consent_type": "ScreenCapture",
+                "expired": true,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_websocket_pushes_anomalies_to_subscribers() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let mut config = KernelConfig::default();
+        config.models.cloud_providers.clear();
+        config.memory.encryption_enabled = false;
+        let kernel = test_kernel(&data_dir, config).await;
+        let base_url = serve_router(&kernel);
+
+        let ws_url = format!("{}/ws", base_url.replacen("http", "ws", 1));
+        let (mut client, _) = tokio_tungstenite::connect_async(ws_url).await.unwrap();
+        client.send(WsMessage::Text(r#"{"type": "subscribe_anomalies"}"#.to_string())).await.unwrap();
+
+        while kernel.anomaly_events.receiver_count() == 0 {
+            tokio::task::yield_now().await;
+        }
+        kernel.anomaly_events.send(DetectedAnomaly {
+            anomaly_type: crate::memory::AnomalyType::MemoryVolumeSpike,
+            severity: crate::memory::AnomalySeverity::High,
+            description: "Unusual memory volume".to_string(),
+            affected_memories: Vec::new(),
+            detected_at: chrono::Utc::now(),
+        }).unwrap();
+
+        let message = tokio::time::timeout(Duration::from_secs(5), client.next()).await.unwrap().unwrap().unwrap();
+        let frame: serde_json::Value = serde_json::from_str(message.to_text().unwrap()).unwrap();
+        assert_eq!(
+            frame,
+            serde_json::json!({
+                "type": "anomaly",
+                "anomaly_type": "MemoryVolumeSpike",
+                "severity": "High",
+                "description": "Unusual memory volume",
+            })
+        );
+    }
+
+    fn configuration_error(config: &KernelConfig) -> String {
+        match config.validate() {
+            Err(MisaError::Configuration(message)) => message,
+            other => panic!("expected a configuration error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_default_config_is_valid() {
+        KernelConfig::default().validate().unwrap();
+        ModelConfig::default().validate().unwrap();
+        MemoryConfig::default().validate().unwrap();
+    }
+
+    #[test]
+    fn test_invalid_config_lists_every_problem() {
+        let mut config = KernelConfig::default();
+        config.memory.local_db_path = "  ".to_string();
+        config.memory.retention_days = 0;
+        config.models.local_server_url = "localhost:11434".to_string();
+        config.models.switching_preferences.gpu_threshold = 1.5;
+
+        let message = configuration_error(&config);
+        assert!(message.contains("memory.local_db_path must not be empty"), "{}", message);
+        assert!(message.contains("memory.retention_days must be at least 1"), "{}", message);
+        assert!(message.contains("models.local_server_url"), "{}", message);
+        assert!(message.contains("models.switching_preferences.gpu_threshold"), "{}", message);
+        assert_eq!(message.matches("; ").count(), 3, "{}", message);
+    }
+
+    #[test]
+    fn test_cloud_sync_and_tls_settings_are_checked() {
+        let mut config = KernelConfig::default();
+        config.memory.cloud_sync.enabled = true;
+        config.memory.cloud_sync.endpoint = None;
+        config.network.tls_enabled = true;
+        config.network.cert_path = Some("cert.pem".to_string());
+
+        let message = configuration_error(&config);
+        assert!(message.contains("memory.cloud_sync.endpoint is required"), "{}", message);
+        assert!(message.contains("network.key_path is required"), "{}", message);
+        assert!(!message.contains("cert_path"), "{}", message);
+
+        config.memory.cloud_sync.endpoint = Some("ftp://sync.example.com".to_string());
+        let message = configuration_error(&config);
+        assert!(message.contains("memory.cloud_sync.endpoint must be an http(s) URL"), "{}", message);
+    }
+
+    #[test]
+    fn test_retention_must_outlast_compaction() {
+        let mut config = KernelConfig::default();
+        config.memory.retention_days = 15;
+        let message = configuration_error(&config);
+        assert!(message.contains("memory.retention_days must be greater than 15"), "{}", message);
+
+        config.memory.retention_days = 16;
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn test_partial_ollama_settings_keep_other_defaults() {
+        let ollama: OllamaClientConfig = toml::from_str("max_retries = 5").unwrap();
+        assert_eq!(ollama.max_retries, 5);
+        assert_eq!(ollama.request_timeout_ms, OllamaClientConfig::default().request_timeout_ms);
+    }
+
+    #[test]
+    fn test_old_switching_preferences_still_load() {
+        let preferences: ModelSwitchingPreferences = toml::from_str(
+            "prefer_local = true\ngpu_threshold = 0.7\ncost_optimization = 0.2\nquality_optimization = 0.9",
+        )
+        .unwrap();
+        assert_eq!(preferences.cost_weight, default_selection_weight());
+        assert_eq!(preferences.quality_weight, default_selection_weight());
+    }
+
+    #[test]
+    fn test_partial_relevance_weights_keep_other_defaults() {
+        let relevance: RelevanceConfig = toml::from_str("recency_weight = 0.8").unwrap();
+        assert_eq!(relevance.recency_weight, 0.8);
+        assert_eq!(relevance.time_decay_per_hour, RelevanceConfig::default().time_decay_per_hour);
+    }
+
+    #[tokio::test]
+    async fn test_kernel_rejects_invalid_config() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let dir = data_dir.path().to_str().unwrap().to_string();
+        let security_manager = SecurityManager::new(&dir, SecurityConfig::default()).await.unwrap();
+
+        let mut config = KernelConfig::default();
+        config.memory.retention_days = 0;
+        assert!(matches!(
+            MisaKernel::with_config(config, dir, security_manager).await,
+            Err(MisaError::Configuration(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_global_search_tags_hits_by_source() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let mut config = KernelConfig::default();
+        config.models.cloud_providers.clear();
+        config.memory.encryption_enabled = false;
+        let kernel = test_kernel(&data_dir, config).await;
+
+        let now = chrono::Utc::now();
+        let memory = |content: &str, created_at| crate::memory::MemoryItem {
+            id: uuid::Uuid::new_v4().to_string(),
+            content: content.to_string(),
+            content_type: crate::memory::ContentType::Text,
+            memory_type: MemoryType::LongTerm,
+            importance: Importance::Medium,
+            tags: Vec::new(),
+            metadata: serde_json::json!({}),
+            created_at,
+            last_accessed: created_at,
+            access_count: 0,
+            encrypted: false,
+            version: Default::default(),
+            binary: None,
+        };
+        let memory_id = kernel
+            .memory_manager
+            .store_memory(memory("Kickoff notes for project Zephyr", now - chrono::Duration::hours(1)))
+            .await
+            .unwrap();
+        // Only matches inside an address the PII filter redacts
+        kernel
+            .memory_manager
+            .store_memory(memory("Write to zephyr@example.com", now))
+            .await
+            .unwrap();
+        kernel
+            .security_manager
+            .log_security_event(
+                Some("user-1"),
+                "file_read",
+                "projects/zephyr/plan.md",
+                crate::security::AuditResult::Success,
+                serde_json::json!({}),
+            )
+            .await
+            .unwrap();
+
+        let results = kernel.global_search("zephyr").await.unwrap();
+        let sources: Vec<SearchSource> = results.hits.iter().map(|hit| hit.source).collect();
+        assert_eq!(sources, vec![SearchSource::Audit, SearchSource::Memory]);
+        assert_eq!(results.hits[1].id, memory_id);
+        assert!(results.hits.iter().all(|hit| !hit.snippet.contains("@example.com")));
+
+        assert!(matches!(kernel.global_search("  ").await, Err(MisaError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_route_task_audits_route_decision() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/tags"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "models": [
+                    {"name": "mixtral", "size": 26, "digest": "abc", "modified_at": "2024-01-01T00:00:00Z"},
+                    {"name": "llama2", "size": 7, "digest": "def", "modified_at": "2024-01-01T00:00:00Z"}
+                ]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "model": "mixtral",
+                "response": "Hello there",
+                "done": true,
+                "eval_count": 20,
+                "prompt_eval_count": 5
+            })))
+            .mount(&server)
+            .await;
+
+        let data_dir = tempfile::tempdir().unwrap();
+        let mut config = KernelConfig::default();
+        config.models.local_server_url = server.uri();
+        config.models.cloud_providers.clear();
+        config.memory.encryption_enabled = false;
+        let kernel = test_kernel(&data_dir, config).await;
+
+        // A successful run gives mixtral performance metrics, so it outranks llama2
+        kernel.execute_task("Say hello", "mixtral", None).await.unwrap();
+
+        let response = kernel
+            .route_task(RouteTaskRequest {
+                task: "Say hello again".to_string(),
+                task_type: "chat".to_string(),
+                context: None,
+                device_preferences: None,
+                priority: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.assigned_model, "mixtral");
+
+        let entries = kernel
+            .security_manager
+            .query_audit_log(&AuditQuery { action: Some("route_decision".to_string()), ..AuditQuery::default() })
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        let details = &entries[0].details;
+        assert_eq!(entries[0].resource, "chat");
+        assert_eq!(details["model"]["winner"], "mixtral");
+        let candidates: Vec<&str> = details["model"]["candidates"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|candidate| candidate["model_id"].as_str().unwrap())
+            .collect();
+        assert_eq!(candidates, vec!["mixtral", "llama2"]);
+        let score = |index: usize| details["model"]["candidates"][index]["score"].as_f64().unwrap();
+        assert!(score(0) > score(1));
+        assert!(details["device"].is_null());
+    }
+
+    /// Manifest of a plugin that sleeps, its script installed in the kernel's plugins directory
+    fn sleeper_manifest(data_dir: &tempfile::TempDir, permissions: &[&str]) -> PluginManifest {
+        let plugins_dir = data_dir.path().join(PLUGINS_DIR);
+        std::fs::create_dir_all(&plugins_dir).unwrap();
+        let script = plugins_dir.join("notes-sync.sh");
+        std::fs::write(&script, "#!/bin/sh\nexec sleep \"$1\"\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        PluginManifest {
+            id: "notes-sync".to_string(),
+            name: "Notes Sync".to_string(),
+            version: "1.0.0".to_string(),
+            executable: "notes-sync.sh".to_string(),
+            args: vec!["30".to_string()],
+            capabilities: vec!["sync".to_string()],
+            permissions: permissions.iter().map(|permission| permission.to_string()).collect(),
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_plugin_install_start_stop() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let kernel = test_kernel(&data_dir, KernelConfig::default()).await;
+        let mut events = kernel.subscribe_plugin_events();
+
+        let installed = kernel.install_plugin(sleeper_manifest(&data_dir, &["memory:read"])).await.unwrap();
+        assert_eq!(installed.status, PluginStatus::Stopped);
+        let duplicate = kernel.install_plugin(sleeper_manifest(&data_dir, &[])).await;
+        assert!(matches!(duplicate, Err(MisaError::Plugin(message)) if message.contains("already installed")));
+
+        let started = kernel.start_plugin("notes-sync").await.unwrap();
+        assert_eq!(started.status, PluginStatus::Running);
+        assert!(started.pid.is_some());
+        assert_eq!(kernel.list_plugins().await[0].status, PluginStatus::Running);
+
+        let stopped = kernel.stop_plugin("notes-sync").await.unwrap();
+        assert_eq!(stopped.status, PluginStatus::Stopped);
+        assert!(stopped.pid.is_none() && stopped.sandbox_id.is_none());
+
+        assert!(matches!(events.recv().await.unwrap(), PluginEvent::Installed { .. }));
+        assert!(matches!(events.recv().await.unwrap(), PluginEvent::Started { .. }));
+        assert!(matches!(events.recv().await.unwrap(), PluginEvent::Stopped { .. }));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_plugin_runs_without_the_kernel_environment() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let kernel = test_kernel(&data_dir, KernelConfig::default()).await;
+        let report = data_dir.path().join("plugin-env.txt");
+        let mut manifest = sleeper_manifest(&data_dir, &[]);
+        std::fs::write(
+            data_dir.path().join(PLUGINS_DIR).join("notes-sync.sh"),
+            "#!/bin/sh\n{ pwd; env; } > \"$1.tmp\" && mv \"$1.tmp\" \"$1\"\nexec sleep 30\n",
+        )
+        .unwrap();
+        manifest.args = vec![report.to_string_lossy().into_owned()];
+        kernel.install_plugin(manifest).await.unwrap();
+
+        kernel.start_plugin("notes-sync").await.unwrap();
+        let output = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let Ok(output) = std::fs::read_to_string(&report) {
+                    return output;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .unwrap();
+        kernel.stop_plugin("notes-sync").await.unwrap();
+
+        let work_dir = std::path::PathBuf::from(output.lines().next().unwrap());
+        assert!(!work_dir.starts_with(data_dir.path().canonicalize().unwrap()));
+        assert!(output.contains("MISA_PLUGIN_ID=notes-sync"));
+        assert!(output.contains("MISA_PLUGIN_TOKEN="));
+        for inherited in ["HOME=", "USER=", "OPENAI_API_KEY=", "MISA_CLOUD_API_KEY="] {
+            assert!(!output.lines().any(|line| line.starts_with(inherited)), "{}", inherited);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_installed_plugins_survive_a_restart() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let kernel = test_kernel(&data_dir, KernelConfig::default()).await;
+        kernel.install_plugin(sleeper_manifest(&data_dir, &["memory:read"])).await.unwrap();
+        drop(kernel);
+
+        let restarted = test_kernel(&data_dir, KernelConfig::default()).await;
+        let plugins = restarted.list_plugins().await;
+        assert_eq!(plugins.len(), 1);
+        assert_eq!(plugins[0].id, "notes-sync");
+        assert_eq!(plugins[0].status, PluginStatus::Stopped);
+        assert_eq!(plugins[0].permissions, vec!["memory:read".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_plugin_denied_undeclared_permission() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let kernel = test_kernel(&data_dir, KernelConfig::default()).await;
+        kernel.install_plugin(sleeper_manifest(&data_dir, &["memory:read", "files:*"])).await.unwrap();
+
+        kernel.authorize_plugin("notes-sync", "memory:read").await.unwrap();
+        kernel.authorize_plugin("notes-sync", "files:write").await.unwrap();
+        let denied = kernel.authorize_plugin("notes-sync", "devices:pair").await;
+        assert!(matches!(denied, Err(MisaError::Plugin(_))));
+
+        let audit = kernel
+            .security_manager
+            .query_audit_log(&AuditQuery {
+                action: Some("plugin_permission_denied".to_string()),
+                ..AuditQuery::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(audit.len(), 1);
+        assert_eq!(audit[0].resource, "devices:pair");
+    }
+
+    #[tokio::test]
+    async fn test_plugin_executable_must_be_installed_in_plugins_dir() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let kernel = test_kernel(&data_dir, KernelConfig::default()).await;
+
+        // A runnable copy just outside the plugins directory
+        let script = data_dir.path().join(PLUGINS_DIR).join("notes-sync.sh");
+        sleeper_manifest(&data_dir, &[]);
+        std::fs::copy(&script, data_dir.path().join("notes-sync.sh")).unwrap();
+
+        for executable in ["/bin/sh", "../notes-sync.sh", "missing.sh"] {
+            let mut manifest = sleeper_manifest(&data_dir, &[]);
+            manifest.executable = executable.to_string();
+            let result = kernel.install_plugin(manifest).await;
+            assert!(matches!(result, Err(MisaError::Plugin(message)) if message.contains("incompatible")), "{}", executable);
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let manifest = sleeper_manifest(&data_dir, &[]);
+            std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o644)).unwrap();
+            assert!(kernel.install_plugin(manifest).await.is_err());
+        }
+        assert!(kernel.list_plugins().await.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_plugin_requests_go_through_declared_permissions() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let kernel = test_kernel(&data_dir, KernelConfig::default()).await;
+        kernel.install_plugin(sleeper_manifest(&data_dir, &["memory:read"])).await.unwrap();
+        kernel.start_plugin("notes-sync").await.unwrap();
+        let token = kernel.active_plugins.read().await["notes-sync"].request_token.clone().unwrap();
+
+        let base_url = serve_router(&kernel);
+        let client = reqwest::Client::new();
+        let request = |body: serde_json::Value, token: &str| {
+            client
+                .post(format!("{}{}", base_url, PLUGIN_REQUEST_PATH))
+                .bearer_auth(token)
+                .json(&body)
+                .send()
+        };
+
+        let search = request(serde_json::json!({ "type": "search_memories", "query": "notes" }), &token).await.unwrap();
+        assert_eq!(search.status(), reqwest::StatusCode::OK);
+        let devices = request(serde_json::json!({ "type": "list_devices" }), &token).await.unwrap();
+        assert_eq!(devices.status(), reqwest::StatusCode::FORBIDDEN);
+        let forged = request(serde_json::json!({ "type": "list_devices" }), "not-a-token").await.unwrap();
+        assert_eq!(forged.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let audit = kernel
+            .security_manager
+            .query_audit_log(&AuditQuery {
+                action: Some("plugin_permission_denied".to_string()),
+                ..AuditQuery::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(audit.len(), 1);
+        assert_eq!(audit[0].resource, "devices:read");
+
+        // The token stops working once the plugin does
+        kernel.stop_plugin("notes-sync").await.unwrap();
+        let stale = request(serde_json::json!({ "type": "search_memories", "query": "notes" }), &token).await.unwrap();
+        assert_eq!(stale.status(), reqwest::StatusCode::UNAUTHORIZED);
+    }
+
+    /// Carry device messages one way between two kernels, as a transport would
+    async fn link(from: &MisaKernel, to: &MisaKernel, from_id: &str, to_id: &str) {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        from.device_manager
+            .register_connection(crate::device::DeviceConnection {
+                device_id: to_id.to_string(),
+                connection_type: crate::device::ConnectionProtocol::Local,
+                websocket: None,
+                webrtc_connection: None,
+                last_heartbeat: chrono::Utc::now(),
+                encrypted_channel: true,
+                local_channel: Some(tx),
+            })
+            .await;
+
+        let receiver = to.device_manager.clone();
+        let source_id = from_id.to_string();
+        tokio::spawn(async move {
+            while let Some(message) = rx.recv().await {
+                receiver.handle_incoming_message(&source_id, message).await.unwrap();
+            }
+        });
+    }
+
+    #[tokio::test]
+    async fn test_delegate_task_runs_on_remote_kernel() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/tags"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "models": [{"name": "mixtral", "size": 26, "digest": "abc", "modified_at": "2024-01-01T00:00:00Z"}]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "model": "mixtral",
+                "response": "Done remotely",
+                "done": true,
+                "eval_count": 20,
+                "prompt_eval_count": 5
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        // Only the workstation can reach a model server
+        let workstation_dir = tempfile::tempdir().unwrap();
+        let mut config = KernelConfig::default();
+        config.models.local_server_url = server.uri();
+        config.models.cloud_providers.clear();
+        config.memory.encryption_enabled = false;
+        let mut workstation_config = config.clone();
+        workstation_config.devices.task_delegation_devices = vec!["laptop".to_string()];
+        let workstation = test_kernel(&workstation_dir, workstation_config).await;
+
+        let laptop_dir = tempfile::tempdir().unwrap();
+        config.models.local_server_url = "http://127.0.0.1:9".to_string();
+        let laptop = test_kernel(&laptop_dir, config).await;
+        laptop
+            .device_manager
+            .insert_test_device(crate::device::tests::test_device("workstation", true, 16384, false))
+            .await;
+        link(&laptop, &workstation, "laptop", "workstation").await;
+        link(&workstation, &laptop, "workstation", "laptop").await;
+
+        let task = DelegatedTask {
+            task: "Draft a reply to the team".to_string(),
+            task_type: "chat".to_string(),
+            context: None,
+        };
+        let response = laptop
+            .delegate_task(task, DelegationConstraints { timeout_secs: Some(10), ..DelegationConstraints::default() })
+            .await
+            .unwrap();
+
+        assert_eq!(response["success"], true);
+        assert_eq!(response["assigned_device"], "workstation");
+        assert_eq!(response["assigned_model"], "mixtral");
+        assert_eq!(response["result"]["content"], "Done remotely");
+
+        // The workstation records who made it run the task and on which model
+        let audit = workstation
+            .security_manager
+            .query_audit_log(&AuditQuery { action: Some("delegated_task".to_string()), ..AuditQuery::default() })
+            .await
+            .unwrap();
+        assert_eq!(audit.len(), 1);
+        assert_eq!(audit[0].details["source_device"], "laptop");
+        assert_eq!(audit[0].details["model"], "mixtral");
+    }
+
+    #[tokio::test]
+    async fn test_delegate_task_runs_locally_without_suitable_device() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let mut config = KernelConfig::default();
+        config.models.local_server_url = "http://127.0.0.1:9".to_string();
+        config.models.cloud_providers.clear();
+        config.memory.encryption_enabled = false;
+        let kernel = test_kernel(&data_dir, config).await;
+
+        let task = DelegatedTask {
+            task: "Say hello".to_string(),
+            task_type: "chat".to_string(),
+            context: None,
+        };
+        let constraints = DelegationConstraints {
+            device_preferences: vec!["offline-phone".to_string()],
+            ..DelegationConstraints::default()
+        };
+
+        // Nothing was sent anywhere; the local kernel has no models to run it on
+        let result = kernel.delegate_task(task, constraints).await;
+        assert!(matches!(result, Err(MisaError::Model(_))));
+        assert!(kernel.device_manager.drain_dead_letters().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_disabled_telemetry_stays_off_after_restart() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let mut config = KernelConfig::default();
+        config.telemetry.endpoint = Some("https://telemetry.example.com".to_string());
+        config.telemetry.user_id = Some("user-1".to_string());
+
+        let kernel = test_kernel(&data_dir, config.clone()).await;
+        assert!(kernel.telemetry.is_active());
+        kernel.disable_telemetry().await.unwrap();
+        assert!(!kernel.telemetry.is_active());
+        drop(kernel);
+
+        let restarted = test_kernel(&data_dir, config).await;
+        assert!(!restarted.telemetry.is_active());
+    }
+
+    #[tokio::test]
+    async fn test_managers_share_the_kernel_scheduler() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let kernel = test_kernel(&data_dir, KernelConfig::default()).await;
+
+        kernel.privacy_controls.initialize().await.unwrap();
+        let jobs = kernel.scheduler.job_names().await;
+        assert!(jobs.contains(&"privacy.consent_sweep".to_string()));
+
+        kernel.shutdown().await.unwrap();
+        assert!(kernel.scheduler.job_names().await.is_empty());
+        let rejected = kernel.scheduler.register("late", Duration::from_secs(60), || async { Ok(()) }).await;
+        assert!(rejected.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_stops_the_prediction_loop() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let kernel = test_kernel(&data_dir, KernelConfig::default()).await;
+
+        kernel.start_prediction_events().await;
+        kernel.start_prediction_events().await;
+        assert!(kernel.prediction_task.read().await.is_some());
+
+        kernel.shutdown().await.unwrap();
+        assert!(kernel.prediction_task.read().await.is_none());
+    }
+}
@@ -6,6 +6,11 @@
 //! - Security and privacy management
 //! - Memory and context management
 //! - Plugin system orchestration
+//! - Cross-subsystem AI pipelines
+//! - Background job scheduling
+//! - Runtime metrics
+//! - Request correlation across managers
+//! - Opt-in anonymized telemetry
 
 pub mod kernel;
 pub mod models;
@@ -13,6 +18,12 @@ pub mod security;
 pub mod device;
 pub mod memory;
 pub mod privacy;
+pub mod ai;
+pub mod scheduler;
+pub mod metrics;
+pub mod correlation;
+pub mod telemetry;
+mod util;
 
 // Include the comprehensive errors module
 include!("errors.rs");
@@ -22,8 +33,12 @@ pub use kernel::{MisaKernel, KernelConfig};
 pub use models::{ModelManager, ModelType, ModelCapabilities};
 pub use security::{SecurityManager, AuthManager, EncryptionManager};
 pub use device::{DeviceManager, RemoteDesktopManager};
-pub use memory::{MemoryManager, ContextEngine};
+pub use memory::{MemoryManager, ContextEngine, ContextProvider};
 pub use privacy::{PrivacyControls, ConsentManager};
+pub use ai::AIManager;
+pub use scheduler::Scheduler;
+pub use metrics::Metrics;
+pub use telemetry::TelemetryManager;
 
 /// Core version information
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
\ No newline at end of file
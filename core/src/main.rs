@@ -10,6 +10,8 @@ mod security;
 mod device;
 mod memory;
 mod privacy;
+mod scheduler;
+mod metrics;
 
 use kernel::MisaKernel;
 use security::SecurityManager;
@@ -8,16 +8,25 @@
 //! - Memory pruning and summarization algorithms
 
 use anyhow::Result;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as CURSOR_BASE64, Engine as _};
 use serde::{Deserialize, Serialize};
-use sqlx::{sqlite::SqlitePool, Row};
-use std::collections::HashMap;
-use std::path::Path;
+use sha2::{Digest, Sha256};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions, SqliteSynchronous};
+use sqlx::Row;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use tracing::{info, warn, error, debug};
-
-use crate::kernel::MemoryConfig;
-use crate::security::{SecurityManager, EncryptedData};
+use tokio::sync::{broadcast, watch, RwLock};
+use tracing::{info, warn, error, debug, instrument};
+
+use crate::ai::Summarizer;
+use crate::correlation;
+use crate::kernel::{CloudSyncConfig, MemoryConfig, RelevanceConfig};
+use crate::metrics::{self, Metrics};
+use crate::models::ModelManager;
+use crate::scheduler::Scheduler;
+use crate::security::{AuditResult, SecurityManager, EncryptedData};
 use crate::errors::{MisaError, Result as MisaResult};
 
 /// Memory manager for intelligent data storage and retrieval
@@ -29,13 +38,322 @@ pub struct MemoryManager {
     context_engine: ContextEngine,
     memory_schemas: MemorySchemas,
     cloud_sync: CloudSync,
+    clock: Clock,
+    fts_available: bool,
+    /// Embeds stored memories in the background for `semantic_search`
+    embeddings: Option<EmbeddingQueue>,
+    summarizer: Option<Arc<dyn Summarizer>>,
+    content_filter: Option<Arc<dyn ContentFilter>>,
+    scheduler: Scheduler,
+    metrics: Metrics,
+    /// Identifies this database in version vectors
+    replica_id: String,
+    /// Reads not yet written to `access_count`/`last_accessed`
+    pending_access: Arc<RwLock<HashMap<String, PendingAccess>>>,
+    /// Set while a threshold flush is running so further reads don't spawn another
+    access_flush_in_flight: Arc<AtomicBool>,
+    /// Held for a whole compaction pass so overlapping passes can't summarize the same group
+    compaction_lock: Arc<tokio::sync::Mutex<()>>,
+    /// Held from writing a blob until the memory referencing it is committed, and from
+    /// counting a blob's references until it is removed, so neither can overtake the other
+    blob_lock: Arc<tokio::sync::Mutex<()>>,
+}
+
+/// Memories waiting for an embedding, written by one background task at a time so
+/// storing a memory never waits on the embedding model
+#[derive(Clone)]
+pub(crate) struct EmbeddingQueue {
+    embedder: Arc<dyn Embedder>,
+    db_pool: SqlitePool,
+    state: Arc<std::sync::Mutex<EmbeddingQueueState>>,
+    /// True while nothing is queued or being embedded
+    idle: Arc<watch::Sender<bool>>,
+}
+
+#[derive(Default)]
+struct EmbeddingQueueState {
+    pending: VecDeque<MemoryItem>,
+    draining: bool,
+}
+
+impl EmbeddingQueue {
+    fn new(embedder: Arc<dyn Embedder>, db_pool: SqlitePool) -> Self {
+        Self {
+            embedder,
+            db_pool,
+            state: Arc::new(std::sync::Mutex::new(EmbeddingQueueState::default())),
+            idle: Arc::new(watch::channel(true).0),
+        }
+    }
+
+    /// Queue `memory` for embedding; a plaintext vector would leak what an encrypted memory is about
+    fn push(&self, memory: &MemoryItem) {
+        if memory.encrypted {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.pending.push_back(memory.clone());
+        self.idle.send_replace(false);
+        if !state.draining {
+            state.draining = true;
+            let queue = self.clone();
+            tokio::spawn(async move { queue.drain().await });
+        }
+    }
+
+    async fn drain(&self) {
+        loop {
+            let memory = {
+                let mut state = self.state.lock().unwrap();
+                match state.pending.pop_front() {
+                    Some(memory) => memory,
+                    None => {
+                        state.draining = false;
+                        self.idle.send_replace(true);
+                        return;
+                    }
+                }
+            };
+
+            // A missing embedding only hides the memory from semantic search
+            if let Err(e) = self.write(&memory).await {
+                warn!("Failed to embed memory {}: {}", memory.id, e);
+            }
+        }
+    }
+
+    async fn write(&self, memory: &MemoryItem) -> MisaResult<()> {
+        let vector = self.embedder.embed(&memory.content).await?;
+
+        // A memory erased while it waited gets no embedding
+        sqlx::query(
+            r#"
+            INSERT INTO memory_embeddings (memory_id, dimensions, vector)
+            SELECT ?, ?, ? WHERE EXISTS (SELECT 1 FROM memories WHERE id = ?)
+            ON CONFLICT(memory_id) DO UPDATE SET dimensions = excluded.dimensions, vector = excluded.vector
+            "#
+        )
+        .bind(&memory.id)
+        .bind(vector.len() as i64)
+        .bind(encode_vector(&vector))
+        .bind(&memory.id)
+        .execute(&self.db_pool)
+        .await
+        .map_err(|e| MisaError::Database(e))?;
+
+        Ok(())
+    }
+
+    async fn wait_idle(&self) {
+        let _ = self.idle.subscribe().wait_for(|idle| *idle).await;
+    }
+}
+
+/// Reads of one memory buffered since the last flush
+#[derive(Debug, Clone, Copy)]
+struct PendingAccess {
+    count: u32,
+    last_accessed: chrono::DateTime<chrono::Utc>,
+}
+
+/// Pooled connection with `secure_delete` on, switched back off before it rejoins the pool
+/// however the work on it ends
+struct SecureDeleteConnection(Option<sqlx::pool::PoolConnection<sqlx::Sqlite>>);
+
+impl std::ops::Deref for SecureDeleteConnection {
+    type Target = sqlx::SqliteConnection;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_deref().expect("connection is only taken on drop")
+    }
+}
+
+impl std::ops::DerefMut for SecureDeleteConnection {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.as_deref_mut().expect("connection is only taken on drop")
+    }
+}
+
+impl Drop for SecureDeleteConnection {
+    fn drop(&mut self) {
+        let Some(mut conn) = self.0.take() else { return };
+        // The connection stays out of the pool until the pragma is reset
+        tokio::spawn(async move {
+            if let Err(e) = sqlx::query("PRAGMA secure_delete = OFF").execute(&mut *conn).await {
+                warn!("Closing connection whose secure_delete could not be reset: {}", e);
+                drop(conn.detach());
+            }
+        });
+    }
+}
+
+/// Background job pruning memories past their retention
+const PRUNE_JOB: &str = "memory.prune";
+
+/// Background job syncing memories with the cloud
+const CLOUD_SYNC_JOB: &str = "memory.cloud_sync";
+
+/// Stored key sync envelopes are sealed with; import the same key on every replica
+pub const SYNC_KEY_NAME: &str = "cloud-sync";
+
+/// Stored key context snapshots are sealed with; import it on the machine that restores them
+pub const CONTEXT_SNAPSHOT_KEY_NAME: &str = "context-snapshot";
+
+/// Background job writing buffered access statistics
+const ACCESS_FLUSH_JOB: &str = "memory.access_flush";
+
+/// Background job scanning recent memories for anomalies
+const ANOMALY_SCAN_JOB: &str = "memory.anomaly_scan";
+
+/// How long a connection waits on a locked database before giving up
+const DB_BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Metadata source marking memories produced by compaction
+const COMPACTION_SOURCE: &str = "compaction";
+
+/// Longest path or URI accepted as a reference to image or video content
+const MAX_CONTENT_REFERENCE_LEN: usize = 4096;
+
+/// Directory under the data directory holding binary memory content
+const BLOB_DIR: &str = "blobs";
+
+/// Start of `content` for memories whose bytes live in the blob store, followed by their SHA-256
+const BLOB_REFERENCE_PREFIX: &str = "blob://sha256/";
+
+/// Stored key blobs are sealed with, shared so identical bytes still map to one file
+pub const BLOB_KEY_NAME: &str = "memory-blobs";
+
+/// Encrypted blob files start with the AES-GCM nonce and tag, then the ciphertext
+const BLOB_NONCE_LEN: usize = 12;
+const BLOB_TAG_LEN: usize = 16;
+
+/// Smallest group of memories worth condensing into a summary
+const MIN_COMPACTION_GROUP: usize = 2;
+
+/// Memories removed by a pruning pass, by importance
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PruneReport {
+    pub low: u32,
+    pub medium: u32,
+    pub high: u32,
+    pub critical: u32,
+}
+
+impl PruneReport {
+    pub fn total(&self) -> u32 {
+        self.low + self.medium + self.high + self.critical
+    }
+
+    fn record(&mut self, importance: &Importance, deleted: u32) {
+        match importance {
+            Importance::Low => self.low += deleted,
+            Importance::Medium => self.medium += deleted,
+            Importance::High => self.high += deleted,
+            Importance::Critical => self.critical += deleted,
+        }
+    }
+}
+
+/// Outcome of a compaction pass
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompactionReport {
+    pub groups_compacted: usize,
+    pub summaries_created: usize,
+    pub memories_deleted: usize,
+    pub memories_retained: usize,
+}
+
+/// Outcome of a batch import
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportReport {
+    /// Ids of the memories that were stored, in input order
+    pub imported: Vec<String>,
+    /// Memories that were skipped; only populated in best-effort mode
+    pub failed: Vec<ImportFailure>,
+}
+
+/// A memory a best-effort import could not store
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportFailure {
+    pub memory_id: String,
+    pub error: String,
+}
+
+/// Source of the current time, replaceable so retention can be tested
+pub type Clock = Arc<dyn Fn() -> chrono::DateTime<chrono::Utc> + Send + Sync>;
+
+/// Turns text into a vector for semantic search
+#[async_trait::async_trait]
+pub trait Embedder: Send + Sync {
+    async fn embed(&self, text: &str) -> MisaResult<Vec<f32>>;
+}
+
+/// Scrubs memory content from privacy-sensitive sources before it is stored
+#[async_trait::async_trait]
+pub trait ContentFilter: Send + Sync {
+    /// Content to store for a memory from `source`, or `None` if it must not be stored
+    async fn filter_content(&self, source: &str, content: &str) -> MisaResult<Option<String>>;
+}
+
+/// Feeds context from an external source on a fixed interval
+#[async_trait::async_trait]
+pub trait ContextProvider: Send + Sync {
+    /// Source the provider's data is pushed as
+    fn source(&self) -> ContextSource;
+
+    /// How often to poll the provider
+    fn interval(&self) -> std::time::Duration;
+
+    /// Fetch the latest data, or `None` when nothing changed
+    async fn poll(&self) -> MisaResult<Option<serde_json::Value>>;
+}
+
+/// Most upcoming calendar events kept in the fused context
+const MAX_CALENDAR_EVENTS: usize = 50;
+
+/// Short-term memory capacity when none is configured
+const DEFAULT_SHORT_TERM_CAPACITY: usize = 50;
+
+#[async_trait::async_trait]
+impl Embedder for ModelManager {
+    async fn embed(&self, text: &str) -> MisaResult<Vec<f32>> {
+        ModelManager::embed(self, text).await
+    }
 }
 
 /// Context engine for context fusion and management
 pub struct ContextEngine {
     active_context: Arc<RwLock<ContextState>>,
     context_sources: Arc<RwLock<HashMap<String, ContextSource>>>,
+    /// Source that last wrote each fused part of the context
+    field_owners: Arc<RwLock<HashMap<FusedField, String>>>,
     fusion_algorithms: FusionAlgorithms,
+    /// Most memories kept in the context's short-term memory
+    short_term_capacity: usize,
+    /// Time the environment's time of day and day of week are computed for
+    clock: Clock,
+}
+
+/// Part of the context state that a source type feeds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FusedField {
+    SystemState,
+    ActiveApplications,
+    Location,
+    Calendar,
+}
+
+impl FusedField {
+    pub fn for_source(source_type: &ContextSourceType) -> Option<Self> {
+        match source_type {
+            ContextSourceType::System => Some(Self::SystemState),
+            ContextSourceType::Application => Some(Self::ActiveApplications),
+            ContextSourceType::Location => Some(Self::Location),
+            ContextSourceType::Calendar => Some(Self::Calendar),
+            _ => None,
+        }
+    }
 }
 
 /// Current context state
@@ -48,10 +366,25 @@ pub struct ContextState {
     pub system_state: SystemState,
     pub environment: EnvironmentContext,
     pub user_preferences: UserPreferences,
-    pub short_term_memory: Vec<MemoryItem>,
+    pub short_term_memory: VecDeque<MemoryItem>,
+    /// Calendar events reported by calendar sources, ordered by start time
+    #[serde(default)]
+    pub calendar_events: Vec<CalendarEvent>,
     pub last_updated: chrono::DateTime<chrono::Utc>,
 }
 
+/// Calendar event pushed by a calendar context source
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalendarEvent {
+    pub event_id: String,
+    pub title: String,
+    pub start: chrono::DateTime<chrono::Utc>,
+    #[serde(default)]
+    pub end: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    pub location: Option<String>,
+}
+
 /// Context source information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContextSource {
@@ -258,6 +591,82 @@ pub struct MemoryItem {
     pub last_accessed: chrono::DateTime<chrono::Utc>,
     pub access_count: u32,
     pub encrypted: bool,
+    /// Edits seen from each replica, used to detect concurrent edits during sync
+    #[serde(default)]
+    pub version: VersionVector,
+    /// Raw bytes of image, audio or video content; stored in the blob store with only
+    /// a reference kept in `content`, and loaded back by `get_memory`
+    #[serde(skip)]
+    pub binary: Option<Vec<u8>>,
+}
+
+/// Per-replica edit counters that tell causal updates apart from concurrent edits
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct VersionVector(std::collections::BTreeMap<String, u64>);
+
+/// How one version vector relates to another
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionOrder {
+    Equal,
+    /// Every edit in this version is also in the other one
+    Before,
+    /// This version contains every edit of the other one and more
+    After,
+    /// Each version has edits the other hasn't seen
+    Concurrent,
+}
+
+impl VersionVector {
+    /// Record a new edit made on `replica_id`
+    pub fn increment(&mut self, replica_id: &str) {
+        *self.0.entry(replica_id.to_string()).or_insert(0) += 1;
+    }
+
+    pub fn get(&self, replica_id: &str) -> u64 {
+        self.0.get(replica_id).copied().unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Compare this version with another
+    pub fn compare(&self, other: &Self) -> VersionOrder {
+        let (mut behind, mut ahead) = (false, false);
+        for replica_id in self.0.keys().chain(other.0.keys()) {
+            match self.get(replica_id).cmp(&other.get(replica_id)) {
+                std::cmp::Ordering::Less => behind = true,
+                std::cmp::Ordering::Greater => ahead = true,
+                std::cmp::Ordering::Equal => {}
+            }
+        }
+
+        match (behind, ahead) {
+            (false, false) => VersionOrder::Equal,
+            (true, false) => VersionOrder::Before,
+            (false, true) => VersionOrder::After,
+            (true, true) => VersionOrder::Concurrent,
+        }
+    }
+
+    /// Version that has seen the edits of both
+    pub fn merged(&self, other: &Self) -> Self {
+        let mut merged = self.clone();
+        for (replica_id, counter) in &other.0 {
+            let entry = merged.0.entry(replica_id.clone()).or_insert(0);
+            *entry = (*entry).max(*counter);
+        }
+        merged
+    }
+
+    /// Parse the JSON stored in the `version` column; rows from before versioning have none
+    fn from_column(value: Option<&str>) -> MisaResult<Self> {
+        match value {
+            Some(json) if !json.is_empty() => Ok(serde_json::from_str(json)?),
+            _ => Ok(Self::default()),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -271,7 +680,7 @@ pub enum ContentType {
     StructuredData,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MemoryType {
     ShortTerm,     // Current session
     MediumTerm,    // Days to weeks
@@ -287,6 +696,17 @@ pub enum Importance {
     Critical,
 }
 
+impl Importance {
+    /// How many times longer than the base retention a memory of this importance is kept
+    pub fn retention_multiplier(&self) -> i32 {
+        match self {
+            Importance::Low | Importance::Medium => 1,
+            Importance::High => 2,
+            Importance::Critical => 4,
+        }
+    }
+}
+
 /// Memory schemas
 pub struct MemorySchemas {
     short_term_capacity: usize,
@@ -307,9 +727,80 @@ pub struct FusionAlgorithms {
 /// Cloud synchronization
 pub struct CloudSync {
     enabled: bool,
+    endpoint: Option<String>,
+    api_key: Option<String>,
     sync_interval_minutes: u64,
     last_sync: Arc<RwLock<chrono::DateTime<chrono::Utc>>>,
     conflict_resolver: ConflictResolver,
+    client: reqwest::Client,
+    replica_id: String,
+}
+
+/// Memory as exchanged with the cloud backend, encrypted client-side
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncEnvelope {
+    pub id: String,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+    pub payload: EncryptedData,
+}
+
+/// Batch of envelopes sent to or received from the cloud backend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SyncBatch {
+    records: Vec<SyncEnvelope>,
+}
+
+/// Outcome of a single sync run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncReport {
+    pub pushed: u32,
+    pub pulled: u32,
+    pub conflicts: u32,
+    /// Memories with concurrent edits left for manual resolution
+    #[serde(default)]
+    pub unresolved: Vec<String>,
+}
+
+/// Concurrent edits of a memory left for manual resolution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncConflict {
+    pub memory_id: String,
+    /// The copy stored here, which is not pushed until the conflict is resolved
+    pub local: MemoryItem,
+    pub remote: MemoryItem,
+    pub remote_updated_at: chrono::DateTime<chrono::Utc>,
+    pub detected_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Outcome chosen for a sync conflict
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictChoice {
+    KeepLocal,
+    TakeRemote,
+    /// Store this memory instead of either side, e.g. a hand-merged copy
+    Replace(MemoryItem),
+}
+
+/// Memory together with its sync bookkeeping
+#[derive(Debug, Clone)]
+pub struct SyncedMemory {
+    pub memory: MemoryItem,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+    pub dirty: bool,
+}
+
+/// Result of resolving a conflicting edit
+#[derive(Debug, Clone)]
+pub enum Resolution {
+    /// Keep the local copy; it is pushed on the next push
+    KeepLocal,
+    /// Replace the local copy with the remote one
+    TakeRemote,
+    /// Store a combination of both and push it back
+    Merged(MemoryItem),
+    /// Concurrent edits that need a person to choose; the local copy is kept but not pushed
+    Unresolved,
 }
 
 /// Conflict resolver for cloud sync
@@ -335,12 +826,17 @@ impl MemoryManager {
 
         // Initialize database
         let db_path = Path::new(data_dir).join(&config.local_db_path);
-        let db_pool = Self::initialize_database(&db_path).await?;
+        let db_pool = Self::initialize_database(&db_path, config.db_max_connections).await?;
+        let fts_available = Self::create_fts_index(&db_pool).await;
+        let replica_id = Self::load_replica_id(&db_pool).await?;
 
         // Initialize components
-        let context_engine = ContextEngine::new().await?;
-        let memory_schemas = MemorySchemas::new(config.retention_days);
-        let cloud_sync = CloudSync::new(true);
+        let memory_schemas = MemorySchemas::new(config.retention_days, config.short_term_capacity);
+        let context_engine = ContextEngine::new()
+            .await?
+            .with_relevance_scorer(RelevanceScorer::from_config(&config.relevance))
+            .with_short_term_capacity(memory_schemas.short_term_capacity());
+        let cloud_sync = CloudSync::new(&config.cloud_sync, replica_id.clone());
 
         let manager = Self {
             config,
@@ -350,12 +846,61 @@ impl MemoryManager {
             context_engine,
             memory_schemas,
             cloud_sync,
+            clock: Arc::new(chrono::Utc::now),
+            fts_available,
+            embeddings: None,
+            summarizer: None,
+            content_filter: None,
+            scheduler: Scheduler::new(),
+            metrics: Metrics::disabled(),
+            replica_id,
+            pending_access: Arc::new(RwLock::new(HashMap::new())),
+            access_flush_in_flight: Arc::new(AtomicBool::new(false)),
+            compaction_lock: Arc::new(tokio::sync::Mutex::new(())),
+            blob_lock: Arc::new(tokio::sync::Mutex::new(())),
         };
 
         info!("Memory manager initialized");
         Ok(manager)
     }
 
+    /// Use a custom clock for retention cutoffs and the context's time of day
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.context_engine.clock = Arc::clone(&clock);
+        self.clock = clock;
+        self
+    }
+
+    /// Condense aging memories with `compact`
+    pub fn with_summarizer(mut self, summarizer: Arc<dyn Summarizer>) -> Self {
+        self.summarizer = Some(summarizer);
+        self
+    }
+
+    /// Count stores and searches in a shared metrics registry
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Run background tasks on a scheduler shared with other managers
+    pub fn with_scheduler(mut self, scheduler: Scheduler) -> Self {
+        self.scheduler = scheduler;
+        self
+    }
+
+    /// Embed stored memories so they can be found with `semantic_search`
+    pub fn with_embedder(mut self, embedder: Arc<dyn Embedder>) -> Self {
+        self.embeddings = Some(EmbeddingQueue::new(embedder, self.db_pool.clone()));
+        self
+    }
+
+    /// Filter content tagged with a `source` in its metadata before storing it
+    pub fn with_content_filter(mut self, content_filter: Arc<dyn ContentFilter>) -> Self {
+        self.content_filter = Some(content_filter);
+        self
+    }
+
     /// Initialize the memory manager
     pub async fn initialize(&self) -> MisaResult<()> {
         info!("Initializing memory manager");
@@ -371,9 +916,16 @@ impl MemoryManager {
     }
 
     /// Store memory item
-    pub async fn store_memory(&self, memory: MemoryItem) -> MisaResult<String> {
+    #[instrument(skip_all, fields(request_id, memory_id = %memory.id))]
+    pub async fn store_memory(&self, mut memory: MemoryItem) -> MisaResult<String> {
+        correlation::enter_request();
         debug!("Storing memory item: {}", memory.id);
 
+        let blob = Self::stage_blob(&mut memory);
+        self.validate_content(&memory)?;
+        self.filter_content(&mut memory).await?;
+        memory.version.increment(&self.replica_id);
+
         // Encrypt if required
         let encrypted_memory = if self.config.encryption_enabled {
             Some(self.encrypt_memory(&memory).await?)
@@ -381,8 +933,33 @@ impl MemoryManager {
             None
         };
 
+        // Only memories that passed validation and filtering reach the blob store
+        let blob_guard = match &blob {
+            Some(blob) => {
+                let guard = self.blob_lock.lock().await;
+                self.write_blob(blob).await?;
+                Some(guard)
+            }
+            None => None,
+        };
+
         // Store in database
-        let memory_id = self.insert_memory_to_db(&memory, encrypted_memory).await?;
+        let inserted = self.insert_memory_to_db(&self.db_pool, &memory, encrypted_memory).await;
+        drop(blob_guard);
+        let memory_id = match inserted {
+            Ok(memory_id) => memory_id,
+            Err(e) => {
+                if let Some(blob) = &blob {
+                    self.discard_blobs(&[blob.reference()]).await;
+                }
+                return Err(e);
+            }
+        };
+        self.metrics
+            .inc(metrics::REQUESTS_TOTAL, &[("subsystem", "memory"), ("operation", "store")])
+            .await;
+
+        self.queue_embedding(&memory);
 
         // Add to short-term context if appropriate
         if matches!(memory.memory_type, MemoryType::ShortTerm) {
@@ -393,6 +970,168 @@ impl MemoryManager {
         Ok(memory_id)
     }
 
+    /// Store many memories in one transaction; any invalid item rolls the whole batch back
+    pub async fn import_memories(&self, memories: Vec<MemoryItem>) -> MisaResult<ImportReport> {
+        self.import_batch(memories, false).await
+    }
+
+    /// Store many memories in one transaction, skipping and reporting the ones that fail
+    pub async fn import_memories_best_effort(&self, memories: Vec<MemoryItem>) -> MisaResult<ImportReport> {
+        self.import_batch(memories, true).await
+    }
+
+    async fn import_batch(&self, memories: Vec<MemoryItem>, best_effort: bool) -> MisaResult<ImportReport> {
+        debug!("Importing {} memories (best effort: {})", memories.len(), best_effort);
+
+        let mut report = ImportReport::default();
+        let mut stored = Vec::with_capacity(memories.len());
+        // References to every blob this batch wrote, released unless a committed memory holds them
+        let mut staged_blobs = Vec::new();
+        // Blobs written here stay unreferenced until the batch commits
+        let blob_guard = self.blob_lock.lock().await;
+        let mut tx = self.db_pool.begin().await.map_err(|e| MisaError::Database(e))?;
+
+        for mut memory in memories {
+            let blob = Self::stage_blob(&mut memory);
+            staged_blobs.extend(blob.as_ref().map(StagedBlob::reference));
+
+            // A failed INSERT is undone on its own, so the transaction stays usable
+            let result = async {
+                self.validate_content(&memory)?;
+                self.filter_content(&mut memory).await?;
+                memory.version.increment(&self.replica_id);
+                let encrypted_memory = if self.config.encryption_enabled {
+                    Some(self.encrypt_memory(&memory).await?)
+                } else {
+                    None
+                };
+                if let Some(blob) = &blob {
+                    self.write_blob(blob).await?;
+                }
+                self.insert_memory_to_db(&mut *tx, &memory, encrypted_memory).await
+            }
+            .await;
+
+            match result {
+                Ok(memory_id) => {
+                    report.imported.push(memory_id);
+                    stored.push(memory);
+                }
+                Err(e) if best_effort => {
+                    debug!("Skipping memory {} during import: {}", memory.id, e);
+                    report.failed.push(ImportFailure {
+                        memory_id: memory.id.clone(),
+                        error: e.to_string(),
+                    });
+                }
+                Err(e) => {
+                    // Rolling back undoes everything inserted so far
+                    warn!("Import aborted at memory {}: {}", memory.id, e);
+                    let _ = tx.rollback().await;
+                    drop(blob_guard);
+                    self.discard_blobs(&staged_blobs).await;
+                    return Err(e);
+                }
+            }
+        }
+
+        let committed = tx.commit().await;
+        drop(blob_guard);
+        if let Err(e) = committed {
+            self.discard_blobs(&staged_blobs).await;
+            return Err(MisaError::Database(e));
+        }
+        if !report.failed.is_empty() {
+            self.discard_blobs(&staged_blobs).await;
+        }
+
+        for memory in stored {
+            self.metrics
+                .inc(metrics::REQUESTS_TOTAL, &[("subsystem", "memory"), ("operation", "store")])
+                .await;
+            self.queue_embedding(&memory);
+            if matches!(memory.memory_type, MemoryType::ShortTerm) {
+                self.context_engine.add_to_short_term_memory(memory).await?;
+            }
+        }
+
+        info!(
+            "Imported {} memories, {} failed",
+            report.imported.len(),
+            report.failed.len()
+        );
+        Ok(report)
+    }
+
+    /// Edit a stored memory in place, keeping its creation time and access history
+    #[instrument(skip_all, fields(request_id, memory_id = %memory_id))]
+    pub async fn update_memory(&self, memory_id: &str, patch: MemoryPatch) -> MisaResult<MemoryItem> {
+        correlation::enter_request();
+        let mut memory = self
+            .get_memory_from_db(memory_id)
+            .await?
+            .ok_or_else(|| MisaError::Memory(format!("Memory not found: {}", memory_id)))?;
+
+        let content_changed = patch.content.as_ref().map_or(false, |content| *content != memory.content);
+        if let Some(content) = patch.content {
+            memory.content = content;
+        }
+        if let Some(tags) = patch.tags {
+            memory.tags = tags;
+        }
+        if let Some(importance) = patch.importance {
+            memory.importance = importance;
+        }
+        let metadata_changed = patch.metadata.is_some();
+        if let Some(metadata) = patch.metadata {
+            memory.metadata = metadata;
+        }
+        self.validate_content(&memory)?;
+        // New content, or a new source, gets the same scrubbing as a fresh memory
+        if content_changed || metadata_changed {
+            self.filter_content(&mut memory).await?;
+        }
+        memory.version.increment(&self.replica_id);
+
+        let encrypted_blob = if self.config.encryption_enabled {
+            Some(self.encrypt_memory(&memory).await?.ciphertext)
+        } else {
+            None
+        };
+
+        sqlx::query(
+            r#"
+            UPDATE memories
+            SET content = ?, tags = ?, importance = ?, metadata = ?,
+                encrypted_data = ?, version = ?, updated_at = ?, dirty = TRUE
+            WHERE id = ?
+            "#
+        )
+        .bind(&memory.content)
+        .bind(serde_json::to_string(&memory.tags)?)
+        .bind(serde_json::to_string(&memory.importance)?)
+        .bind(serde_json::to_string(&memory.metadata)?)
+        .bind(encrypted_blob)
+        .bind(serde_json::to_string(&memory.version)?)
+        .bind((self.clock)())
+        .bind(memory_id)
+        .execute(&self.db_pool)
+        .await
+        .map_err(|e| MisaError::Database(e))?;
+
+        if content_changed {
+            self.queue_embedding(&memory);
+        }
+
+        if matches!(memory.memory_type, MemoryType::ShortTerm) {
+            self.context_engine.remove_from_short_term_memory(memory_id).await;
+            self.context_engine.add_to_short_term_memory(memory.clone()).await?;
+        }
+
+        info!("Updated memory item: {}", memory_id);
+        Ok(memory)
+    }
+
     /// Retrieve memory item
     pub async fn get_memory(&self, memory_id: &str) -> MisaResult<Option<MemoryItem>> {
         debug!("Retrieving memory item: {}", memory_id);
@@ -404,9 +1143,13 @@ impl MemoryManager {
             if memory.encrypted {
                 memory = self.decrypt_memory(&memory).await?;
             }
+            memory.binary = self.load_blob(&memory.content).await?;
 
-            // Update access statistics
-            self.update_memory_access_stats(memory_id).await?;
+            // Count the read in memory; the database catches up on the next flush
+            if let Some(pending) = self.record_access(memory_id).await {
+                memory.access_count += pending.count;
+                memory.last_accessed = pending.last_accessed;
+            }
 
             Ok(Some(memory))
         } else {
@@ -416,13 +1159,21 @@ impl MemoryManager {
 
     /// Search memories
     pub async fn search_memories(&self, query: &SearchQuery) -> MisaResult<Vec<MemoryItem>> {
+        Ok(self.search_memories_page(query).await?.memories)
+    }
+
+    /// Search memories, returning a cursor for the page after this one
+    pub async fn search_memories_page(&self, query: &SearchQuery) -> MisaResult<SearchPage> {
         debug!("Searching memories with query: {:?}", query);
 
-        let memories = self.search_memories_in_db(query).await?;
+        let page = self.search_memories_in_db(query).await?;
+        self.metrics
+            .inc(metrics::REQUESTS_TOTAL, &[("subsystem", "memory"), ("operation", "search")])
+            .await;
 
         // Decrypt if needed and filter results
         let mut results = Vec::new();
-        for memory in memories {
+        for memory in page.memories {
             let mut memory = memory;
             if memory.encrypted {
                 memory = self.decrypt_memory(&memory).await?;
@@ -430,50 +1181,648 @@ impl MemoryManager {
             results.push(memory);
         }
 
-        Ok(results)
+        Ok(SearchPage {
+            memories: results,
+            next_cursor: page.next_cursor,
+        })
     }
 
-    /// Get current context
-    pub async fn get_current_context(&self) -> MisaResult<ContextState> {
-        self.context_engine.get_current_context().await
-    }
+    /// Find the `k` memories whose embeddings are closest to the query, most similar first.
+    /// Encrypted memories have no embedding, so they are never returned.
+    pub async fn semantic_search(&self, query: &str, k: usize) -> MisaResult<Vec<MemoryItem>> {
+        let embedder = self
+            .embeddings
+            .as_ref()
+            .map(|embeddings| &embeddings.embedder)
+            .ok_or_else(|| MisaError::Configuration("No embedding model configured".to_string()))?;
+        let query_vector = embedder.embed(query).await?;
+
+        let rows = sqlx::query("SELECT memory_id, vector FROM memory_embeddings")
+            .fetch_all(&self.db_pool)
+            .await
+            .map_err(|e| MisaError::Database(e))?;
 
-    /// Update context with new data
-    pub async fn update_context(&self, context_source: ContextSource, data: serde_json::Value) -> MisaResult<()> {
-        self.context_engine.update_context(context_source, data).await
-    }
+        let mut scored: Vec<(String, f32)> = rows
+            .iter()
+            .filter_map(|row| {
+                let vector = decode_vector(row.get::<Vec<u8>, _>("vector").as_slice());
+                cosine_similarity(&query_vector, &vector).map(|score| (row.get("memory_id"), score))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        scored.truncate(k);
+
+        let mut results = Vec::with_capacity(scored.len());
+        for (memory_id, _) in scored {
+            if let Some(mut memory) = self.get_memory_from_db(&memory_id).await? {
+                if memory.encrypted {
+                    memory = self.decrypt_memory(&memory).await?;
+                }
+                results.push(memory);
+            }
+        }
 
-    /// Prune old memories based on retention policy
-    pub async fn prune_memories(&self) -> MisaResult<u32> {
-        info!("Pruning old memories");
+        Ok(results)
+    }
 
-        let cutoff_date = chrono::Utc::now() - chrono::Duration::days(self.config.retention_days as i64);
-        let deleted_count = self.delete_old_memories(cutoff_date).await?;
+    /// Pairs of memories whose embeddings are more similar than `threshold`, most similar first
+    pub async fn find_duplicates(&self, threshold: f32) -> MisaResult<Vec<(String, String, f32)>> {
+        let rows = sqlx::query("SELECT memory_id, vector FROM memory_embeddings ORDER BY memory_id")
+            .fetch_all(&self.db_pool)
+            .await
+            .map_err(|e| MisaError::Database(e))?;
 
-        info!("Pruned {} old memories", deleted_count);
-        Ok(deleted_count)
-    }
+        let embeddings: Vec<(String, Vec<f32>)> = rows
+            .iter()
+            .map(|row| (row.get("memory_id"), decode_vector(row.get::<Vec<u8>, _>("vector").as_slice())))
+            .collect();
+
+        let mut pairs = Vec::new();
+        for (i, (first_id, first)) in embeddings.iter().enumerate() {
+            for (second_id, second) in &embeddings[i + 1..] {
+                if let Some(score) = cosine_similarity(first, second) {
+                    if score > threshold {
+                        pairs.push((first_id.clone(), second_id.clone(), score));
+                    }
+                }
+            }
+        }
+        pairs.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(std::cmp::Ordering::Equal));
+
+        debug!("Found {} duplicate memory pairs above {}", pairs.len(), threshold);
+        Ok(pairs)
+    }
+
+    /// Fold `drop_id` into `keep_id`: tags are unioned, metadata merged with the kept
+    /// memory's keys winning, and the dropped memory is erased
+    pub async fn merge_memories(&self, keep_id: &str, drop_id: &str) -> MisaResult<MemoryItem> {
+        if keep_id == drop_id {
+            return Err(MisaError::Validation("Cannot merge a memory into itself".to_string()));
+        }
+
+        let keep = self
+            .get_memory_from_db(keep_id)
+            .await?
+            .ok_or_else(|| MisaError::NotFound(format!("Memory not found: {}", keep_id)))?;
+        let drop = self
+            .get_memory_from_db(drop_id)
+            .await?
+            .ok_or_else(|| MisaError::NotFound(format!("Memory not found: {}", drop_id)))?;
+
+        let mut tags = keep.tags.clone();
+        for tag in drop.tags {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+
+        let mut metadata = match drop.metadata {
+            serde_json::Value::Object(map) => map,
+            _ => serde_json::Map::new(),
+        };
+        if let serde_json::Value::Object(map) = keep.metadata {
+            metadata.extend(map);
+        }
+        let mut merged_from: Vec<serde_json::Value> = metadata
+            .get("merged_from")
+            .and_then(|value| value.as_array())
+            .cloned()
+            .unwrap_or_default();
+        merged_from.push(serde_json::Value::String(drop_id.to_string()));
+        metadata.insert("merged_from".to_string(), serde_json::Value::Array(merged_from));
+
+        let merged = self
+            .update_memory(
+                keep_id,
+                MemoryPatch {
+                    tags: Some(tags),
+                    metadata: Some(serde_json::Value::Object(metadata)),
+                    ..MemoryPatch::default()
+                },
+            )
+            .await?;
+        self.erase_memory(drop_id, false).await?;
+
+        info!("Merged memory {} into {}", drop_id, keep_id);
+        Ok(merged)
+    }
+
+    /// Permanently erase a memory, returning whether it existed.
+    /// With `secure_delete` the stored content is overwritten before the row is removed.
+    #[instrument(skip_all, fields(request_id, memory_id = %memory_id))]
+    pub async fn erase_memory(&self, memory_id: &str, secure_delete: bool) -> MisaResult<bool> {
+        correlation::enter_request();
+        let erased = if secure_delete {
+            Self::secure_erase(&self.db_pool, &self.data_dir, &self.blob_lock, &[memory_id.to_string()], self.fts_available).await? > 0
+        } else {
+            let content: Option<String> = sqlx::query_scalar("SELECT content FROM memories WHERE id = ?")
+                .bind(memory_id)
+                .fetch_optional(&self.db_pool)
+                .await
+                .map_err(|e| MisaError::Database(e))?;
+            let result = sqlx::query("DELETE FROM memories WHERE id = ?")
+                .bind(memory_id)
+                .execute(&self.db_pool)
+                .await
+                .map_err(|e| MisaError::Database(e))?;
+            if result.rows_affected() > 0 {
+                Self::release_blobs(&self.db_pool, &self.data_dir, &self.blob_lock, &content.into_iter().collect::<Vec<_>>())
+                    .await?;
+            }
+            result.rows_affected() > 0
+        };
+
+        self.context_engine.remove_from_short_term_memory(memory_id).await;
+
+        if erased {
+            info!("Erased memory item: {}", memory_id);
+        }
+        Ok(erased)
+    }
+
+    /// Forget a memory for good: its row, embedding, search index entries and blob are
+    /// securely erased and it leaves short-term context. Permanent memories are refused.
+    pub async fn forget(&self, memory_id: &str) -> MisaResult<()> {
+        self.forget_memory(memory_id, false).await
+    }
+
+    /// Forget a memory even if it is permanent
+    pub async fn force_forget(&self, memory_id: &str) -> MisaResult<()> {
+        self.forget_memory(memory_id, true).await
+    }
+
+    async fn forget_memory(&self, memory_id: &str, force: bool) -> MisaResult<()> {
+        let memory_type: Option<String> = sqlx::query_scalar("SELECT memory_type FROM memories WHERE id = ?")
+            .bind(memory_id)
+            .fetch_optional(&self.db_pool)
+            .await
+            .map_err(|e| MisaError::Database(e))?;
+        let memory_type: MemoryType = match memory_type {
+            Some(memory_type) => serde_json::from_str(&memory_type)?,
+            None => return Err(MisaError::NotFound(format!("Memory not found: {}", memory_id))),
+        };
+
+        if memory_type == MemoryType::Permanent && !force {
+            return Err(MisaError::Memory(format!(
+                "Memory {} is permanent; use force_forget to remove it",
+                memory_id
+            )));
+        }
+
+        if !self.erase_memory(memory_id, true).await? {
+            return Err(MisaError::NotFound(format!("Memory not found: {}", memory_id)));
+        }
+
+        self.security_manager
+            .log_security_event(
+                None,
+                "memory_forgotten",
+                memory_id,
+                AuditResult::Success,
+                serde_json::json!({ "memory_type": memory_type, "forced": force }),
+            )
+            .await
+    }
+
+    /// Get current context
+    pub async fn get_current_context(&self) -> MisaResult<ContextState> {
+        self.context_engine.get_current_context().await
+    }
+
+    /// Replace the user's preferences, e.g. after they change their timezone
+    pub async fn update_user_preferences(&self, preferences: UserPreferences) {
+        self.context_engine.update_preferences(preferences).await
+    }
+
+    /// Serialize the current context, including short-term memory, sealed with the
+    /// `CONTEXT_SNAPSHOT_KEY_NAME` stored key so another machine holding that key can restore it
+    pub async fn export_context_snapshot(&self) -> MisaResult<Vec<u8>> {
+        let context = self.context_engine.get_current_context().await?;
+        let plaintext = serde_json::to_vec(&context)?;
+        let sealed = self
+            .security_manager
+            .encrypt_with_stored_key(CONTEXT_SNAPSHOT_KEY_NAME, &plaintext)
+            .await?;
+
+        info!(
+            "Exported context snapshot with {} short-term memories",
+            context.short_term_memory.len()
+        );
+        Ok(serde_json::to_vec(&sealed)?)
+    }
+
+    /// Validate a snapshot from `export_context_snapshot` and make it the active context
+    ///
+    /// Snapshots belonging to another user are rejected unless `force` is set.
+    pub async fn import_context_snapshot(&self, bytes: &[u8], force: bool) -> MisaResult<()> {
+        let sealed: EncryptedData = serde_json::from_slice(bytes)
+            .map_err(|e| MisaError::Validation(format!("Malformed context snapshot: {}", e)))?;
+        let plaintext = self
+            .security_manager
+            .decrypt_with_stored_key(CONTEXT_SNAPSHOT_KEY_NAME, &sealed)
+            .await?;
+        let snapshot: ContextState = serde_json::from_slice(&plaintext)
+            .map_err(|e| MisaError::Validation(format!("Invalid context snapshot: {}", e)))?;
+
+        let current = self.context_engine.get_current_context().await?;
+        if snapshot.user_id != current.user_id && !force {
+            return Err(MisaError::Permission(format!(
+                "Context snapshot belongs to user {}, not {}",
+                snapshot.user_id, current.user_id
+            )));
+        }
+
+        if snapshot.session_id.trim().is_empty() {
+            return Err(MisaError::Validation("Context snapshot has no session id".to_string()));
+        }
+        if snapshot.short_term_memory.len() > self.memory_schemas.short_term_capacity() {
+            return Err(MisaError::Validation(format!(
+                "Context snapshot holds {} short-term memories, limit is {}",
+                snapshot.short_term_memory.len(),
+                self.memory_schemas.short_term_capacity()
+            )));
+        }
+        if snapshot.calendar_events.len() > MAX_CALENDAR_EVENTS {
+            return Err(MisaError::Validation(format!(
+                "Context snapshot holds {} calendar events, limit is {}",
+                snapshot.calendar_events.len(),
+                MAX_CALENDAR_EVENTS
+            )));
+        }
+        for memory in &snapshot.short_term_memory {
+            self.validate_content(memory)?;
+        }
+
+        info!(
+            "Imported context snapshot for user {} with {} short-term memories",
+            snapshot.user_id,
+            snapshot.short_term_memory.len()
+        );
+        self.context_engine.restore(snapshot).await;
+        Ok(())
+    }
+
+    /// Update context with new data
+    pub async fn update_context(&self, context_source: ContextSource, data: serde_json::Value) -> MisaResult<()> {
+        self.context_engine.update_context(context_source, data).await
+    }
+
+    /// Register a custom context source
+    pub async fn register_context_source(&self, source: ContextSource) -> MisaResult<()> {
+        self.context_engine.register_source(source).await
+    }
+
+    /// Push data from a registered context source
+    pub async fn push_context(&self, source_id: &str, data: serde_json::Value) -> MisaResult<()> {
+        self.context_engine.push(source_id, data).await
+    }
+
+    /// Register a provider's source and poll it on the background scheduler
+    pub async fn register_context_provider(&self, provider: Arc<dyn ContextProvider>) -> MisaResult<()> {
+        let source = provider.source();
+        let source_id = source.source_id.clone();
+        self.context_engine.register_source(source).await?;
+
+        let engine = self.context_engine.clone();
+        let job_name = format!("context.{}", source_id);
+        self.scheduler
+            .register(&job_name, provider.interval(), move || {
+                let provider = Arc::clone(&provider);
+                let engine = engine.clone();
+                let source_id = source_id.clone();
+                async move {
+                    if let Some(data) = provider.poll().await? {
+                        engine.push(&source_id, data).await?;
+                    }
+                    Ok(())
+                }
+            })
+            .await
+    }
+
+    /// Prune old memories based on retention policy
+    pub async fn prune_memories(&self) -> MisaResult<PruneReport> {
+        info!("Pruning old memories");
+
+        let report = Self::delete_old_memories(
+            &self.db_pool,
+            &self.data_dir,
+            &self.blob_lock,
+            &self.memory_schemas,
+            (self.clock)(),
+            self.config.retention_days,
+            &self.config.secure_delete_types,
+            self.fts_available,
+        )
+        .await?;
+
+        info!("Pruned {} old memories", report.total());
+        Ok(report)
+    }
+
+    /// Condense old short and medium-term memories into per-day summaries
+    ///
+    /// Each day's group becomes one `MediumTerm` summary. The originals are deleted
+    /// when the share of low-value memories in the group exceeds the compression
+    /// threshold, otherwise they are kept and marked so later passes skip them.
+    pub async fn compact(&self) -> MisaResult<CompactionReport> {
+        let mut report = CompactionReport::default();
+        if !self.memory_schemas.summarization_enabled {
+            return Ok(report);
+        }
+
+        let summarizer = self
+            .summarizer
+            .clone()
+            .ok_or_else(|| MisaError::Configuration("No summarizer configured for memory compaction".to_string()))?;
+
+        let _pass = self.compaction_lock.lock().await;
+
+        let now = (self.clock)();
+        let candidates = self.load_compaction_candidates().await?;
+
+        // Sources of an existing summary were covered by a pass that stopped part way
+        let mut covered: HashMap<String, String> = HashMap::new();
+        for summary in candidates.iter().filter(|memory| memory.metadata["source"] == COMPACTION_SOURCE) {
+            for source_id in summary.metadata["compacted_from"].as_array().into_iter().flatten() {
+                if let Some(source_id) = source_id.as_str() {
+                    covered.insert(source_id.to_string(), summary.id.clone());
+                }
+            }
+        }
+
+        let mut groups: std::collections::BTreeMap<chrono::NaiveDate, Vec<MemoryItem>> = std::collections::BTreeMap::new();
+        for memory in candidates {
+            if !self.memory_schemas.is_compaction_candidate(&memory, now) {
+                continue;
+            }
+            match covered.get(&memory.id) {
+                // Finish the earlier pass instead of summarizing the memory twice
+                Some(summary_id) => {
+                    self.mark_compacted(&memory, summary_id).await?;
+                    report.memories_retained += 1;
+                }
+                None => groups.entry(memory.created_at.date_naive()).or_default().push(memory),
+            }
+        }
+
+        for (day, group) in groups {
+            if group.len() < MIN_COMPACTION_GROUP {
+                continue;
+            }
+
+            let text = group
+                .iter()
+                .map(|memory| format!("- {}", memory.content))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let summary = summarizer.summarize(&text).await?;
+            let source_ids: Vec<String> = group.iter().map(|memory| memory.id.clone()).collect();
+
+            let summary_id = self
+                .store_memory(MemoryItem {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    content: summary,
+                    content_type: ContentType::Text,
+                    memory_type: MemoryType::MediumTerm,
+                    importance: Importance::Medium,
+                    tags: vec!["summary".to_string()],
+                    metadata: serde_json::json!({
+                        "source": COMPACTION_SOURCE,
+                        "day": day.to_string(),
+                        "compacted_from": source_ids,
+                    }),
+                    created_at: now,
+                    last_accessed: now,
+                    access_count: 0,
+                    encrypted: false,
+                    version: VersionVector::default(),
+                    binary: None,
+                })
+                .await?;
+
+            if self.memory_schemas.compaction_density(&group) > self.memory_schemas.compression_threshold {
+                for memory in &group {
+                    self.erase_memory(&memory.id, false).await?;
+                }
+                report.memories_deleted += group.len();
+            } else {
+                for memory in &group {
+                    self.mark_compacted(memory, &summary_id).await?;
+                }
+                report.memories_retained += group.len();
+            }
+
+            report.groups_compacted += 1;
+            report.summaries_created += 1;
+            debug!("Compacted {} memories from {} into {}", group.len(), day, summary_id);
+        }
+
+        info!(
+            "Compaction created {} summaries, deleted {} memories",
+            report.summaries_created, report.memories_deleted
+        );
+        Ok(report)
+    }
 
     /// Sync with cloud storage
-    pub async fn sync_with_cloud(&self) -> MisaResult<()> {
-        if !self.cloud_sync.enabled {
+    pub async fn sync_with_cloud(&self) -> MisaResult<SyncReport> {
+        if !self.cloud_sync.is_active() {
             debug!("Cloud sync disabled");
-            return Ok(());
+            return Ok(SyncReport::default());
         }
 
         info!("Starting cloud synchronization");
+        let report = self.cloud_sync.sync(&self.db_pool, &self.security_manager, self.content_filter.as_ref(), self.embeddings(), &self.clock).await?;
+
+        info!(
+            "Cloud synchronization completed: {} pushed, {} pulled, {} conflicts",
+            report.pushed, report.pulled, report.conflicts
+        );
+        Ok(report)
+    }
 
-        // In real implementation, this would:
-        // - Upload new memories to cloud
-        // - Download remote changes
-        // - Resolve conflicts
-        // - Update sync timestamp
+    /// Memories edited concurrently here and on another device, oldest conflict first
+    pub async fn list_sync_conflicts(&self) -> MisaResult<Vec<SyncConflict>> {
+        let rows = sqlx::query("SELECT memory_id FROM sync_conflicts ORDER BY detected_at")
+            .fetch_all(&self.db_pool)
+            .await
+            .map_err(|e| MisaError::Database(e))?;
 
-        let mut last_sync = self.cloud_sync.last_sync.write().await;
-        *last_sync = chrono::Utc::now();
+        let mut conflicts = Vec::with_capacity(rows.len());
+        for row in &rows {
+            let memory_id: String = row.get("memory_id");
+            conflicts.push(self.load_sync_conflict(&memory_id).await?);
+        }
+        Ok(conflicts)
+    }
 
-        info!("Cloud synchronization completed");
-        Ok(())
+    /// Settle a sync conflict; the outcome supersedes both edits and is pushed on the next sync
+    pub async fn resolve_sync_conflict(&self, memory_id: &str, choice: ConflictChoice) -> MisaResult<MemoryItem> {
+        let conflict = self.load_sync_conflict(memory_id).await?;
+
+        // The local copy was filtered when it was stored; anything else is new content
+        let keep_local = matches!(choice, ConflictChoice::KeepLocal);
+        let mut resolved = match choice {
+            ConflictChoice::KeepLocal => conflict.local.clone(),
+            ConflictChoice::TakeRemote => conflict.remote.clone(),
+            ConflictChoice::Replace(memory) => {
+                if memory.id != memory_id {
+                    return Err(MisaError::Validation(format!(
+                        "Replacement memory {} does not match conflict {}",
+                        memory.id, memory_id
+                    )));
+                }
+                memory
+            }
+        };
+        if !keep_local {
+            filter_memory_content(self.content_filter.as_ref(), &mut resolved).await?;
+        }
+        resolved.version = conflict.local.version.merged(&conflict.remote.version);
+        resolved.version.increment(&self.replica_id);
+
+        CloudSync::upsert_memory(&self.db_pool, &self.security_manager, &resolved, (self.clock)(), true).await?;
+        CloudSync::clear_conflict(&self.db_pool, memory_id).await?;
+        self.queue_embedding(&resolved);
+
+        info!("Resolved sync conflict on memory {}", memory_id);
+        Ok(resolved)
+    }
+
+    async fn load_sync_conflict(&self, memory_id: &str) -> MisaResult<SyncConflict> {
+        let row = sqlx::query("SELECT * FROM sync_conflicts WHERE memory_id = ?")
+            .bind(memory_id)
+            .fetch_optional(&self.db_pool)
+            .await
+            .map_err(|e| MisaError::Database(e))?
+            .ok_or_else(|| MisaError::Memory(format!("No sync conflict on memory {}", memory_id)))?;
+
+        let envelope: SyncEnvelope = serde_json::from_str(&row.get::<String, _>("remote_envelope"))?;
+        let remote = CloudSync::open_envelope(&self.security_manager, &envelope).await?;
+        let local = CloudSync::load_synced_memory(&self.db_pool, memory_id)
+            .await?
+            .ok_or_else(|| MisaError::Memory(format!("Memory not found: {}", memory_id)))?;
+
+        Ok(SyncConflict {
+            memory_id: memory_id.to_string(),
+            local: local.memory,
+            remote,
+            remote_updated_at: row.get("remote_updated_at"),
+            detected_at: row.get("detected_at"),
+        })
+    }
+
+    /// Generate predictions from the current context and recent memories
+    pub async fn generate_predictions(&self) -> MisaResult<Vec<Prediction>> {
+        let context = self.context_engine.get_current_context().await?;
+        let memories = self.search_memories(&SearchQuery::new()).await?;
+
+        Ok(self
+            .context_engine
+            .fusion_algorithms
+            .prediction_engine
+            .generate_predictions(&context, &memories)
+            .await)
+    }
+
+    /// Periodically generate predictions and publish them on an event bus.
+    /// A suggestion is not re-emitted while an earlier copy is still valid.
+    pub fn start_prediction_events<E, F>(
+        &self,
+        events: broadcast::Sender<E>,
+        to_event: F,
+        interval: tokio::time::Duration,
+    ) -> tokio::task::JoinHandle<()>
+    where
+        E: Send + 'static,
+        F: Fn(&Prediction) -> E + Send + 'static,
+    {
+        let manager = self.clone();
+
+        tokio::spawn(async move {
+            let mut debouncer = PredictionDebouncer::default();
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+
+                let predictions = match manager.generate_predictions().await {
+                    Ok(predictions) => predictions,
+                    Err(e) => {
+                        warn!("Prediction generation failed: {}", e);
+                        continue;
+                    }
+                };
+
+                let now = (manager.clock)();
+                for prediction in predictions {
+                    if debouncer.should_emit(&prediction, now) {
+                        debug!("Emitting {} prediction", prediction.prediction_type);
+                        // Having no subscribers right now is not an error
+                        let _ = events.send(to_event(&prediction));
+                    }
+                }
+            }
+        })
+    }
+
+    /// Run the anomaly detector over memories inside the baseline window
+    pub async fn run_anomaly_scan(&self) -> MisaResult<Vec<DetectedAnomaly>> {
+        let now = (self.clock)();
+        let window_days = self.config.anomaly_baseline_window_size as i64;
+
+        let mut query = SearchQuery::new();
+        query.date_range = Some((now - chrono::Duration::days(window_days), now));
+        query.limit = None;
+        query.offset = None;
+        let memories = self.search_memories(&query).await?;
+
+        let detector = AnomalyDetector::with_settings(
+            self.config.anomaly_threshold,
+            self.config.anomaly_baseline_window_size,
+        );
+        let anomalies = detector.detect_anomalies(&memories).await;
+
+        if !anomalies.is_empty() {
+            info!("Anomaly scan over {} memories found {} anomalies", memories.len(), anomalies.len());
+        }
+        Ok(anomalies)
+    }
+
+    /// Scan for anomalies every `interval` on the scheduler and publish each new one on an
+    /// event bus. An anomaly over the same memories is only published once.
+    pub async fn start_anomaly_alerts<E, F>(
+        &self,
+        events: broadcast::Sender<E>,
+        to_event: F,
+        interval: std::time::Duration,
+    ) -> MisaResult<()>
+    where
+        E: Send + 'static,
+        F: Fn(&DetectedAnomaly) -> E + Send + Sync + 'static,
+    {
+        let manager = self.clone();
+        let to_event = Arc::new(to_event);
+        let published = Arc::new(std::sync::Mutex::new(HashSet::new()));
+
+        self.scheduler
+            .register(ANOMALY_SCAN_JOB, interval, move || {
+                let manager = manager.clone();
+                let events = events.clone();
+                let to_event = Arc::clone(&to_event);
+                let published = Arc::clone(&published);
+                async move {
+                    for anomaly in manager.run_anomaly_scan().await? {
+                        let key = format!("{:?}:{}", anomaly.anomaly_type, anomaly.affected_memories.join(","));
+                        if published.lock().unwrap().insert(key) {
+                            // Having no subscribers right now is not an error
+                            let _ = events.send(to_event(&anomaly));
+                        }
+                    }
+                    Ok(())
+                }
+            })
+            .await
     }
 
     /// Get memory statistics
@@ -509,10 +1858,84 @@ impl MemoryManager {
         })
     }
 
+    /// Memory statistics plus counts per content type and the `top_tags` most used tags
+    pub async fn get_memory_stats_detailed(&self, top_tags: usize) -> MisaResult<DetailedMemoryStats> {
+        let stats = self.get_memory_stats().await?;
+
+        let rows = sqlx::query(
+            "SELECT content_type, COUNT(*) AS count FROM memories GROUP BY content_type ORDER BY count DESC, content_type",
+        )
+        .fetch_all(&self.db_pool)
+        .await
+        .map_err(|e| MisaError::Database(e))?;
+        let by_content_type = rows
+            .iter()
+            .map(|row| {
+                Ok(ContentTypeCount {
+                    content_type: serde_json::from_str(row.get("content_type"))?,
+                    count: row.get::<i64, _>("count") as u32,
+                })
+            })
+            .collect::<MisaResult<Vec<_>>>()?;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT tag.value AS tag, COUNT(*) AS count
+            FROM memories, json_each(memories.tags) AS tag
+            GROUP BY tag.value
+            ORDER BY count DESC, tag.value
+            LIMIT ?
+            "#
+        )
+        .bind(top_tags as i64)
+        .fetch_all(&self.db_pool)
+        .await
+        .map_err(|e| MisaError::Database(e))?;
+        let top_tags = rows
+            .iter()
+            .map(|row| TagCount {
+                tag: row.get("tag"),
+                count: row.get::<i64, _>("count") as u32,
+            })
+            .collect();
+
+        Ok(DetailedMemoryStats {
+            stats,
+            by_content_type,
+            top_tags,
+        })
+    }
+
+    /// Number of memories currently stored
+    pub async fn count_memories(&self) -> MisaResult<u64> {
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM memories")
+            .fetch_one(&self.db_pool)
+            .await
+            .map_err(|e| MisaError::Database(e))?;
+        Ok(count as u64)
+    }
+
+    /// Round-trip time of a trivial query against the memory database
+    pub async fn ping_database(&self) -> MisaResult<std::time::Duration> {
+        let started = std::time::Instant::now();
+        sqlx::query("SELECT 1")
+            .execute(&self.db_pool)
+            .await
+            .map_err(|e| MisaError::Database(e))?;
+        Ok(started.elapsed())
+    }
+
     /// Shutdown memory manager
     pub async fn shutdown(&self) -> MisaResult<()> {
         info!("Shutting down memory manager");
 
+        // Stop background tasks before the pool they use goes away
+        self.scheduler.cancel(PRUNE_JOB).await;
+        self.scheduler.cancel(CLOUD_SYNC_JOB).await;
+        self.scheduler.cancel(ACCESS_FLUSH_JOB).await;
+        self.scheduler.cancel(ANOMALY_SCAN_JOB).await;
+        self.flush_access_stats().await?;
+
         // Final sync with cloud
         self.sync_with_cloud().await?;
 
@@ -525,11 +1948,19 @@ impl MemoryManager {
 
     /// Private helper methods
 
-    async fn initialize_database(db_path: &Path) -> MisaResult<SqlitePool> {
-        let connection_string = format!("sqlite:{}", db_path.display());
+    async fn initialize_database(db_path: &Path, max_connections: u32) -> MisaResult<SqlitePool> {
+        // WAL lets readers run alongside the writer; NORMAL sync is durable enough under WAL
+        let options = SqliteConnectOptions::new()
+            .filename(db_path)
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .synchronous(SqliteSynchronous::Normal)
+            .busy_timeout(DB_BUSY_TIMEOUT);
 
         // Create database with connection pool
-        let pool = SqlitePool::connect(&connection_string)
+        let pool = SqlitePoolOptions::new()
+            .max_connections(max_connections.max(1))
+            .connect_with(options)
             .await
             .map_err(|e| MisaError::Database(e))?;
 
@@ -539,6 +1970,22 @@ impl MemoryManager {
         Ok(pool)
     }
 
+    /// Id this database uses in version vectors, created on first start
+    async fn load_replica_id(pool: &SqlitePool) -> MisaResult<String> {
+        sqlx::query("INSERT OR IGNORE INTO sync_state (key, value) VALUES ('replica_id', ?)")
+            .bind(uuid::Uuid::new_v4().to_string())
+            .execute(pool)
+            .await
+            .map_err(|e| MisaError::Database(e))?;
+
+        let replica_id: String = sqlx::query_scalar("SELECT value FROM sync_state WHERE key = 'replica_id'")
+            .fetch_one(pool)
+            .await
+            .map_err(|e| MisaError::Database(e))?;
+
+        Ok(replica_id)
+    }
+
     async fn create_tables(pool: &SqlitePool) -> MisaResult<()> {
         sqlx::query(
             r#"
@@ -554,7 +2001,14 @@ impl MemoryManager {
                 last_accessed DATETIME NOT NULL,
                 access_count INTEGER NOT NULL DEFAULT 0,
                 encrypted BOOLEAN NOT NULL DEFAULT FALSE,
-                encrypted_data BLOB -- Encrypted content if encryption enabled
+                encrypted_data BLOB, -- Encrypted content if encryption enabled
+                updated_at DATETIME, -- Last local or synced modification
+                dirty BOOLEAN NOT NULL DEFAULT TRUE, -- Changed since the last cloud push
+                version TEXT -- JSON version vector, replica id -> edit count
+            );
+            CREATE TABLE IF NOT EXISTS sync_state (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
             );
             CREATE INDEX IF NOT EXISTS idx_memories_type ON memories(memory_type);
             CREATE INDEX IF NOT EXISTS idx_memories_created ON memories(created_at);
@@ -565,68 +2019,329 @@ impl MemoryManager {
         .await
         .map_err(|e| MisaError::Database(e))?;
 
+        // Databases created before cloud sync lack the sync columns; the
+        // statements fail harmlessly once the columns exist
+        for statement in [
+            "ALTER TABLE memories ADD COLUMN updated_at DATETIME",
+            "ALTER TABLE memories ADD COLUMN dirty BOOLEAN NOT NULL DEFAULT TRUE",
+            "ALTER TABLE memories ADD COLUMN version TEXT",
+        ] {
+            let _ = sqlx::query(statement).execute(pool).await;
+        }
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_memories_dirty ON memories(dirty)")
+            .execute(pool)
+            .await
+            .map_err(|e| MisaError::Database(e))?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS memory_embeddings (
+                memory_id TEXT PRIMARY KEY,
+                dimensions INTEGER NOT NULL,
+                vector BLOB NOT NULL -- Little-endian f32 values
+            );
+            CREATE TRIGGER IF NOT EXISTS memory_embeddings_delete AFTER DELETE ON memories BEGIN
+                DELETE FROM memory_embeddings WHERE memory_id = old.id;
+            END;
+            CREATE TABLE IF NOT EXISTS sync_conflicts (
+                memory_id TEXT PRIMARY KEY,
+                remote_envelope TEXT NOT NULL, -- JSON sync envelope, still sealed
+                remote_updated_at DATETIME NOT NULL,
+                detected_at DATETIME NOT NULL
+            );
+            CREATE TRIGGER IF NOT EXISTS sync_conflicts_delete AFTER DELETE ON memories BEGIN
+                DELETE FROM sync_conflicts WHERE memory_id = old.id;
+            END;
+            "#
+        )
+        .execute(pool)
+        .await
+        .map_err(|e| MisaError::Database(e))?;
+
         Ok(())
     }
 
-    async fn encrypt_memory(&self, memory: &MemoryItem) -> MisaResult<EncryptedData> {
-        let content_bytes = memory.content.as_bytes();
-        self.security_manager.encrypt_data(content_bytes, &memory.id).await
+    /// Embedding queue, unless memories are stored encrypted and must not get plaintext vectors
+    fn embeddings(&self) -> Option<&EmbeddingQueue> {
+        self.embeddings.as_ref().filter(|_| !self.config.encryption_enabled)
     }
 
-    async fn decrypt_memory(&self, memory: &MemoryItem) -> MisaResult<MemoryItem> {
-        // This would need the encrypted data from database
-        // For now, return memory as-is
-        Ok(memory.clone())
+    fn queue_embedding(&self, memory: &MemoryItem) {
+        if let Some(embeddings) = self.embeddings() {
+            embeddings.push(memory);
+        }
     }
 
-    async fn insert_memory_to_db(&self, memory: &MemoryItem, encrypted_data: Option<EncryptedData>) -> MisaResult<String> {
-        let tags_json = serde_json::to_string(&memory.tags)?;
-        let metadata_json = serde_json::to_string(&memory.metadata)?;
+    /// Wait until every memory stored so far has been embedded
+    pub async fn wait_for_embeddings(&self) {
+        if let Some(embeddings) = &self.embeddings {
+            embeddings.wait_idle().await;
+        }
+    }
 
-        let encrypted_blob = if let Some(encrypted) = encrypted_data {
-            Some(encrypted.ciphertext)
-        } else {
-            None
-        };
+    /// Create the FTS5 index over memory content and tags, kept in sync by triggers.
+    /// Returns false when SQLite was built without FTS5.
+    async fn create_fts_index(pool: &SqlitePool) -> bool {
+        let existing = sqlx::query("SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'memories_fts'")
+            .fetch_optional(pool)
+            .await;
 
-        sqlx::query!(
+        let result = sqlx::query(
             r#"
-            INSERT INTO memories (
-                id, content, content_type, memory_type, importance,
-                tags, metadata, created_at, last_accessed,
-                access_count, encrypted, encrypted_data
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-            "#,
-            memory.id,
-            memory.content,
-            serde_json::to_string(&memory.content_type)?,
-            serde_json::to_string(&memory.memory_type)?,
-            serde_json::to_string(&memory.importance)?,
-            tags_json,
-            metadata_json,
-            memory.created_at,
-            memory.last_accessed,
-            memory.access_count,
-            memory.encrypted,
-            encrypted_blob
+            CREATE VIRTUAL TABLE IF NOT EXISTS memories_fts USING fts5(
+                content, tags, content='memories', content_rowid='rowid'
+            );
+            CREATE TRIGGER IF NOT EXISTS memories_fts_insert AFTER INSERT ON memories BEGIN
+                INSERT INTO memories_fts(rowid, content, tags) VALUES (new.rowid, new.content, new.tags);
+            END;
+            CREATE TRIGGER IF NOT EXISTS memories_fts_delete AFTER DELETE ON memories BEGIN
+                INSERT INTO memories_fts(memories_fts, rowid, content, tags)
+                VALUES ('delete', old.rowid, old.content, old.tags);
+            END;
+            CREATE TRIGGER IF NOT EXISTS memories_fts_update AFTER UPDATE OF content, tags ON memories BEGIN
+                INSERT INTO memories_fts(memories_fts, rowid, content, tags)
+                VALUES ('delete', old.rowid, old.content, old.tags);
+                INSERT INTO memories_fts(rowid, content, tags) VALUES (new.rowid, new.content, new.tags);
+            END;
+            "#
         )
-        .execute(&self.db_pool)
-        .await
-        .map_err(|e| MisaError::Database(e))?;
+        .execute(pool)
+        .await;
 
-        Ok(memory.id.clone())
+        if let Err(e) = result {
+            warn!("Full-text search unavailable, falling back to LIKE matching: {}", e);
+            return false;
+        }
+
+        // Index memories stored before the FTS table existed
+        if matches!(existing, Ok(None)) {
+            if let Err(e) = sqlx::query("INSERT INTO memories_fts(memories_fts) VALUES ('rebuild')")
+                .execute(pool)
+                .await
+            {
+                warn!("Failed to build full-text index: {}", e);
+                return false;
+            }
+        }
+
+        true
     }
 
-    async fn get_memory_from_db(&self, memory_id: &str) -> MisaResult<Option<MemoryItem>> {
-        let row = sqlx::query!(
-            r#"
-            SELECT
-                id, content, content_type, memory_type, importance,
-                tags, metadata, created_at, last_accessed,
-                access_count, encrypted
-            FROM memories
-            WHERE id = ?
-            "#,
+    /// Run content from a tagged source through the content filter, if one is attached
+    async fn filter_content(&self, memory: &mut MemoryItem) -> MisaResult<()> {
+        filter_memory_content(self.content_filter.as_ref(), memory).await
+    }
+
+    /// Reject oversized content and inline media that belongs on disk
+    fn validate_content(&self, memory: &MemoryItem) -> MisaResult<()> {
+        let max_bytes = self.config.max_memory_content_bytes;
+        if memory.content.len() > max_bytes {
+            return Err(MisaError::Memory(format!(
+                "Memory content is {} bytes, limit is {}",
+                memory.content.len(),
+                max_bytes
+            )));
+        }
+
+        if matches!(memory.content_type, ContentType::Image | ContentType::Video) && !is_content_reference(&memory.content) {
+            return Err(MisaError::Memory(format!(
+                "{:?} memories must reference their media by path or URI, not inline data",
+                memory.content_type
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn blob_path(&self, hash: &str, encrypted: bool) -> PathBuf {
+        blob_file(&self.data_dir, hash, encrypted)
+    }
+
+    /// Take a memory's binary content out of it, leaving a reference to the blob in `content`.
+    /// Nothing is written until `write_blob`.
+    fn stage_blob(memory: &mut MemoryItem) -> Option<StagedBlob> {
+        let bytes = memory.binary.take()?;
+        let blob = StagedBlob {
+            hash: format!("{:x}", Sha256::digest(&bytes)),
+            bytes,
+        };
+        memory.content = blob.reference();
+        Some(blob)
+    }
+
+    /// Write staged bytes to the blob store, sealed with the `BLOB_KEY_NAME` stored key
+    /// when memories are encrypted
+    async fn write_blob(&self, blob: &StagedBlob) -> MisaResult<()> {
+        let encrypted = self.config.encryption_enabled;
+        let path = self.blob_path(&blob.hash, encrypted);
+
+        // Content-addressed, so an existing file already holds these bytes
+        if tokio::fs::try_exists(&path).await? {
+            return Ok(());
+        }
+        let contents = if encrypted {
+            let sealed = self.security_manager.encrypt_with_stored_key(BLOB_KEY_NAME, &blob.bytes).await?;
+            [sealed.nonce, sealed.tag, sealed.ciphertext].concat()
+        } else {
+            blob.bytes.clone()
+        };
+        crate::util::write_file_atomic(&path, &contents).await
+    }
+
+    /// Bytes behind a blob reference, checked against their hash; `None` for other content
+    async fn load_blob(&self, content: &str) -> MisaResult<Option<Vec<u8>>> {
+        let Some(hash) = blob_hash(content) else {
+            return Ok(None);
+        };
+
+        let bytes = match tokio::fs::read(self.blob_path(hash, true)).await {
+            Ok(sealed) => {
+                if sealed.len() < BLOB_NONCE_LEN + BLOB_TAG_LEN {
+                    return Err(MisaError::Memory(format!("Blob {} is truncated", hash)));
+                }
+                let (nonce, rest) = sealed.split_at(BLOB_NONCE_LEN);
+                let (tag, ciphertext) = rest.split_at(BLOB_TAG_LEN);
+                let encrypted_data = EncryptedData {
+                    ciphertext: ciphertext.to_vec(),
+                    nonce: nonce.to_vec(),
+                    key_id: BLOB_KEY_NAME.to_string(),
+                    algorithm: "AES-256-GCM".to_string(),
+                    tag: tag.to_vec(),
+                };
+                self.security_manager.decrypt_with_stored_key(BLOB_KEY_NAME, &encrypted_data).await?
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => match tokio::fs::read(self.blob_path(hash, false)).await {
+                Ok(bytes) => bytes,
+                // Only the reference syncs between replicas, so the bytes may live elsewhere
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    warn!("Blob {} is not stored on this device", hash);
+                    return Ok(None);
+                }
+                Err(e) => return Err(e.into()),
+            },
+            Err(e) => return Err(e.into()),
+        };
+
+        if format!("{:x}", Sha256::digest(&bytes)) != hash {
+            return Err(MisaError::Memory(format!("Blob {} does not match its hash", hash)));
+        }
+        Ok(Some(bytes))
+    }
+
+    /// Delete the blobs behind `contents` that no memory references any more;
+    /// contents that aren't blob references are skipped
+    async fn release_blobs(
+        db_pool: &SqlitePool,
+        data_dir: &str,
+        blob_lock: &tokio::sync::Mutex<()>,
+        contents: &[String],
+    ) -> MisaResult<()> {
+        let _guard = blob_lock.lock().await;
+        for content in contents {
+            let Some(hash) = blob_hash(content) else {
+                continue;
+            };
+
+            let references: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM memories WHERE content = ?")
+                .bind(content)
+                .fetch_one(db_pool)
+                .await
+                .map_err(|e| MisaError::Database(e))?;
+            if references > 0 {
+                continue;
+            }
+
+            for encrypted in [true, false] {
+                match tokio::fs::remove_file(blob_file(data_dir, hash, encrypted)).await {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                    _ => {}
+                }
+            }
+            debug!("Removed unreferenced blob {}", hash);
+        }
+        Ok(())
+    }
+
+    /// Release blobs a failed store wrote; shared ones stay while another memory holds them
+    async fn discard_blobs(&self, contents: &[String]) {
+        if let Err(e) = Self::release_blobs(&self.db_pool, &self.data_dir, &self.blob_lock, contents).await {
+            warn!("Failed to remove blobs of memories that weren't stored: {}", e);
+        }
+    }
+
+    async fn encrypt_memory(&self, memory: &MemoryItem) -> MisaResult<EncryptedData> {
+        let content_bytes = memory.content.as_bytes();
+        self.security_manager.encrypt_data(content_bytes, &memory.id).await
+    }
+
+    async fn decrypt_memory(&self, memory: &MemoryItem) -> MisaResult<MemoryItem> {
+        // This would need the encrypted data from database
+        // For now, return memory as-is
+        Ok(memory.clone())
+    }
+
+    async fn insert_memory_to_db<'e, E>(
+        &self,
+        executor: E,
+        memory: &MemoryItem,
+        encrypted_data: Option<EncryptedData>,
+    ) -> MisaResult<String>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+    {
+        let tags_json = serde_json::to_string(&memory.tags)?;
+        let metadata_json = serde_json::to_string(&memory.metadata)?;
+
+        let encrypted_blob = if let Some(encrypted) = encrypted_data {
+            Some(encrypted.ciphertext)
+        } else {
+            None
+        };
+        let version_json = serde_json::to_string(&memory.version)?;
+        let updated_at = (self.clock)();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO memories (
+                id, content, content_type, memory_type, importance,
+                tags, metadata, created_at, last_accessed,
+                access_count, encrypted, encrypted_data,
+                version, updated_at, dirty
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, TRUE)
+            "#,
+            memory.id,
+            memory.content,
+            serde_json::to_string(&memory.content_type)?,
+            serde_json::to_string(&memory.memory_type)?,
+            serde_json::to_string(&memory.importance)?,
+            tags_json,
+            metadata_json,
+            memory.created_at,
+            memory.last_accessed,
+            memory.access_count,
+            memory.encrypted,
+            encrypted_blob,
+            version_json,
+            updated_at
+        )
+        .execute(executor)
+        .await
+        .map_err(|e| MisaError::Database(e))?;
+
+        Ok(memory.id.clone())
+    }
+
+    async fn get_memory_from_db(&self, memory_id: &str) -> MisaResult<Option<MemoryItem>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                id, content, content_type, memory_type, importance,
+                tags, metadata, created_at, last_accessed,
+                access_count, encrypted, version
+            FROM memories
+            WHERE id = ?
+            "#,
             memory_id
         )
         .fetch_optional(&self.db_pool)
@@ -646,6 +2361,8 @@ impl MemoryManager {
                 last_accessed: row.last_accessed,
                 access_count: row.access_count as u32,
                 encrypted: row.encrypted,
+                version: VersionVector::from_column(row.version.as_deref())?,
+                binary: None,
             };
             Ok(Some(memory))
         } else {
@@ -653,9 +2370,21 @@ impl MemoryManager {
         }
     }
 
-    async fn search_memories_in_db(&self, query: &SearchQuery) -> MisaResult<Vec<MemoryItem>> {
-        let sql = query.build_sql();
-        let mut q = sqlx::query(&sql);
+    async fn search_memories_in_db(&self, query: &SearchQuery) -> MisaResult<SearchPage> {
+        let mut query = query.clone();
+        query.full_text &= self.fts_available;
+
+        // A position in one ordering says nothing about where to resume another
+        if let Some(cursor) = &query.cursor {
+            if !query.is_ranked() && cursor.sort != query.sort_key() {
+                return Err(MisaError::Validation(
+                    "Search cursor was issued for a different sort order".to_string(),
+                ));
+            }
+        }
+        query.build_sql();
+
+        let mut q = sqlx::query(&query.sql);
 
         for param in &query.params {
             q = q.bind(param);
@@ -665,93 +2394,470 @@ impl MemoryManager {
             .await
             .map_err(|e| MisaError::Database(e))?;
 
-        let mut memories = Vec::new();
-        for row in rows {
-            let memory = MemoryItem {
-                id: row.get("id"),
-                content: row.get("content"),
-                content_type: serde_json::from_str(row.get("content_type"))?,
-                memory_type: serde_json::from_str(row.get("memory_type"))?,
-                importance: serde_json::from_str(row.get("importance"))?,
-                tags: serde_json::from_str(row.get::<_, Option<String>>("tags").unwrap_or_default())?,
-                metadata: serde_json::from_str(row.get::<_, Option<String>>("metadata").unwrap_or_default())?,
-                created_at: row.get("created_at"),
-                last_accessed: row.get("last_accessed"),
-                access_count: row.get::<_, i64>("access_count") as u32,
-                encrypted: row.get("encrypted"),
-            };
-            memories.push(memory);
+        // A full page may have more rows behind it; ranked results can't be resumed by key
+        let page_full = query.limit.map_or(false, |limit| rows.len() as u32 >= limit && limit > 0);
+        let next_cursor = match rows.last() {
+            Some(row) if page_full && !query.is_ranked() => Some(SearchCursor::from_row(row, &query)?.encode()),
+            _ => None,
+        };
+
+        Ok(SearchPage {
+            memories: rows.iter().map(memory_from_row).collect::<MisaResult<_>>()?,
+            next_cursor,
+        })
+    }
+
+    /// Buffer a read of `memory_id`, returning the reads buffered before this one.
+    /// Once enough reads pile up they are flushed in the background, one flush at a time.
+    async fn record_access(&self, memory_id: &str) -> Option<PendingAccess> {
+        let now = (self.clock)();
+        let mut pending_access = self.pending_access.write().await;
+        let earlier = pending_access.get(memory_id).copied();
+        let entry = pending_access.entry(memory_id.to_string()).or_insert(PendingAccess {
+            count: 0,
+            last_accessed: now,
+        });
+        entry.count += 1;
+        entry.last_accessed = now;
+
+        let buffered: u32 = pending_access.values().map(|pending| pending.count).sum();
+        drop(pending_access);
+
+        if buffered >= self.config.access_flush_threshold && !self.access_flush_in_flight.swap(true, Ordering::AcqRel) {
+            let db_pool = self.db_pool.clone();
+            let pending_access = Arc::clone(&self.pending_access);
+            let in_flight = Arc::clone(&self.access_flush_in_flight);
+            tokio::spawn(async move {
+                if let Err(e) = Self::flush_pending_access(&db_pool, &pending_access).await {
+                    warn!("Failed to flush memory access statistics: {}", e);
+                }
+                in_flight.store(false, Ordering::Release);
+            });
         }
 
-        Ok(memories)
+        earlier
     }
 
-    async fn update_memory_access_stats(&self, memory_id: &str) -> MisaResult<()> {
-        sqlx::query!(
-            r#"
-            UPDATE memories
-            SET last_accessed = ?, access_count = access_count + 1
-            WHERE id = ?
-            "#,
-            chrono::Utc::now(),
-            memory_id
-        )
-        .execute(&self.db_pool)
-        .await
-        .map_err(|e| MisaError::Database(e))?;
+    /// Write buffered access statistics now, returning how many memories were updated
+    pub async fn flush_access_stats(&self) -> MisaResult<usize> {
+        Self::flush_pending_access(&self.db_pool, &self.pending_access).await
+    }
+
+    /// Apply every buffered read in one transaction; on failure the reads go back in the buffer
+    async fn flush_pending_access(
+        db_pool: &SqlitePool,
+        pending_access: &Arc<RwLock<HashMap<String, PendingAccess>>>,
+    ) -> MisaResult<usize> {
+        let batch = std::mem::take(&mut *pending_access.write().await);
+        if batch.is_empty() {
+            return Ok(0);
+        }
+
+        let result = async {
+            let mut tx = db_pool.begin().await?;
+            let mut updated = 0;
+            for (memory_id, pending) in &batch {
+                // Memories erased since they were read simply match no row
+                updated += sqlx::query("UPDATE memories SET last_accessed = ?, access_count = access_count + ? WHERE id = ?")
+                    .bind(pending.last_accessed)
+                    .bind(pending.count)
+                    .bind(memory_id)
+                    .execute(&mut *tx)
+                    .await?
+                    .rows_affected() as usize;
+            }
+            tx.commit().await?;
+            Ok::<_, sqlx::Error>(updated)
+        }
+        .await;
+
+        let updated = match result {
+            Ok(updated) => updated,
+            Err(e) => {
+                let mut pending_access = pending_access.write().await;
+                for (memory_id, pending) in batch {
+                    pending_access
+                        .entry(memory_id)
+                        .and_modify(|newer| newer.count += pending.count)
+                        .or_insert(pending);
+                }
+                return Err(MisaError::Database(e));
+            }
+        };
+
+        debug!("Flushed access statistics for {} memories", updated);
+        Ok(updated)
+    }
+
+    async fn load_compaction_candidates(&self) -> MisaResult<Vec<MemoryItem>> {
+        let rows = sqlx::query("SELECT * FROM memories WHERE memory_type IN (?, ?) ORDER BY created_at")
+            .bind(serde_json::to_string(&MemoryType::ShortTerm)?)
+            .bind(serde_json::to_string(&MemoryType::MediumTerm)?)
+            .fetch_all(&self.db_pool)
+            .await
+            .map_err(|e| MisaError::Database(e))?;
+
+        rows.iter().map(memory_from_row).collect()
+    }
+
+    /// Record which summary covers a memory that was kept after compaction
+    async fn mark_compacted(&self, memory: &MemoryItem, summary_id: &str) -> MisaResult<()> {
+        let mut metadata = memory.metadata.clone();
+        if !metadata.is_object() {
+            metadata = serde_json::json!({});
+        }
+        metadata["compacted_into"] = serde_json::json!(summary_id);
+        let mut version = memory.version.clone();
+        version.increment(&self.replica_id);
+
+        sqlx::query("UPDATE memories SET metadata = ?, version = ?, updated_at = ?, dirty = TRUE WHERE id = ?")
+            .bind(serde_json::to_string(&metadata)?)
+            .bind(serde_json::to_string(&version)?)
+            .bind((self.clock)())
+            .bind(&memory.id)
+            .execute(&self.db_pool)
+            .await
+            .map_err(|e| MisaError::Database(e))?;
 
         Ok(())
     }
 
-    async fn delete_old_memories(&self, cutoff_date: chrono::DateTime<chrono::Utc>) -> MisaResult<u32> {
-        let result = sqlx::query!(
-            r#"
-            DELETE FROM memories
-            WHERE created_at < ? AND memory_type != 'Permanent'
-            "#,
-            cutoff_date
-        )
-        .execute(&self.db_pool)
-        .await
-        .map_err(|e| MisaError::Database(e))?;
+    /// Acquire a connection on which SQLite zeroes freed pages instead of leaving old content in the file
+    async fn acquire_secure_delete(db_pool: &SqlitePool) -> MisaResult<SecureDeleteConnection> {
+        let mut conn = SecureDeleteConnection(Some(db_pool.acquire().await.map_err(|e| MisaError::Database(e))?));
+        sqlx::query("PRAGMA secure_delete = ON")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| MisaError::Database(e))?;
+        Ok(conn)
+    }
+
+    /// Overwrite memories' stored content with random bytes, then delete them so
+    /// nothing lingers in freed pages, the search index or the WAL. Returns how many existed.
+    ///
+    /// The rows, and through triggers their embeddings and index entries, go in one transaction.
+    async fn secure_erase(
+        db_pool: &SqlitePool,
+        data_dir: &str,
+        blob_lock: &tokio::sync::Mutex<()>,
+        memory_ids: &[String],
+        fts_available: bool,
+    ) -> MisaResult<u64> {
+        let mut conn = Self::acquire_secure_delete(db_pool).await?;
+        let mut tx = sqlx::Connection::begin(&mut *conn).await.map_err(|e| MisaError::Database(e))?;
+        let mut erased = 0;
+        let mut contents = Vec::new();
+
+        for memory_id in memory_ids {
+            let content: Option<String> = sqlx::query_scalar("SELECT content FROM memories WHERE id = ?")
+                .bind(memory_id)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|e| MisaError::Database(e))?;
+            contents.extend(content);
+
+            sqlx::query(
+                r#"
+                UPDATE memories
+                SET content = '', encrypted_data = CASE
+                    WHEN encrypted_data IS NULL THEN NULL
+                    ELSE randomblob(length(encrypted_data))
+                END
+                WHERE id = ?
+                "#
+            )
+            .bind(memory_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| MisaError::Database(e))?;
+
+            let result = sqlx::query("DELETE FROM memories WHERE id = ?")
+                .bind(memory_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| MisaError::Database(e))?;
+            erased += result.rows_affected();
+        }
+        tx.commit().await.map_err(|e| MisaError::Database(e))?;
+        Self::release_blobs(db_pool, data_dir, blob_lock, &contents).await?;
+
+        if erased > 0 {
+            // Deleted terms stay in older index segments until they are merged
+            if fts_available {
+                sqlx::query("INSERT INTO memories_fts(memories_fts) VALUES ('optimize')")
+                    .execute(&mut *conn)
+                    .await
+                    .map_err(|e| MisaError::Database(e))?;
+            }
+
+            // Old page images stay in the WAL until it is checkpointed
+            sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| MisaError::Database(e))?;
+        }
+
+        Ok(erased)
+    }
+
+    /// Delete memories past the retention for their type, stretched by their importance
+    ///
+    /// Memories of a type in `secure_types` are overwritten before they are removed.
+    async fn delete_old_memories(
+        db_pool: &SqlitePool,
+        data_dir: &str,
+        blob_lock: &tokio::sync::Mutex<()>,
+        schemas: &MemorySchemas,
+        now: chrono::DateTime<chrono::Utc>,
+        retention_days: u32,
+        secure_types: &[MemoryType],
+        fts_available: bool,
+    ) -> MisaResult<PruneReport> {
+        let mut report = PruneReport::default();
+
+        for memory_type in [MemoryType::ShortTerm, MemoryType::MediumTerm, MemoryType::LongTerm] {
+            for importance in [Importance::Low, Importance::Medium, Importance::High, Importance::Critical] {
+                let cutoff_date = now - schemas.retention_period(&memory_type, &importance, retention_days);
+
+                if secure_types.contains(&memory_type) {
+                    let expired: Vec<String> = sqlx::query_scalar(
+                        "SELECT id FROM memories WHERE created_at < ? AND memory_type = ? AND importance = ?"
+                    )
+                    .bind(cutoff_date)
+                    .bind(serde_json::to_string(&memory_type)?)
+                    .bind(serde_json::to_string(&importance)?)
+                    .fetch_all(db_pool)
+                    .await
+                    .map_err(|e| MisaError::Database(e))?;
+
+                    let erased = Self::secure_erase(db_pool, data_dir, blob_lock, &expired, fts_available).await?;
+                    report.record(&importance, erased as u32);
+                    continue;
+                }
+
+                // memory_type and importance are stored serialized, so compare against the serialized form
+                let blob_references: Vec<String> = sqlx::query_scalar(
+                    "SELECT content FROM memories WHERE created_at < ? AND memory_type = ? AND importance = ? AND content LIKE ?"
+                )
+                .bind(cutoff_date)
+                .bind(serde_json::to_string(&memory_type)?)
+                .bind(serde_json::to_string(&importance)?)
+                .bind(format!("{}%", BLOB_REFERENCE_PREFIX))
+                .fetch_all(db_pool)
+                .await
+                .map_err(|e| MisaError::Database(e))?;
+
+                let result = sqlx::query(
+                    r#"
+                    DELETE FROM memories
+                    WHERE created_at < ? AND memory_type = ? AND importance = ?
+                    "#
+                )
+                .bind(cutoff_date)
+                .bind(serde_json::to_string(&memory_type)?)
+                .bind(serde_json::to_string(&importance)?)
+                .execute(db_pool)
+                .await
+                .map_err(|e| MisaError::Database(e))?;
+
+                Self::release_blobs(db_pool, data_dir, blob_lock, &blob_references).await?;
+                report.record(&importance, result.rows_affected() as u32);
+            }
+        }
 
-        Ok(result.rows_affected() as u32)
+        Ok(report)
     }
 
     async fn start_background_tasks(&self) -> MisaResult<()> {
         // Start memory pruning task
-        let memory_schemas = self.memory_schemas.clone();
         let db_pool = self.db_pool.clone();
+        let data_dir = self.data_dir.clone();
+        let blob_lock = Arc::clone(&self.blob_lock);
+        let clock = Arc::clone(&self.clock);
+        let schemas = self.memory_schemas.clone();
+        let retention_days = self.config.retention_days;
+        let secure_types = self.config.secure_delete_types.clone();
+        let fts_available = self.fts_available;
+        let prune_interval = tokio::time::Duration::from_secs(self.config.prune_interval_seconds.max(1));
+
+        self.scheduler
+            .register(PRUNE_JOB, prune_interval, move || {
+                let db_pool = db_pool.clone();
+                let data_dir = data_dir.clone();
+                let blob_lock = Arc::clone(&blob_lock);
+                let clock = Arc::clone(&clock);
+                let schemas = schemas.clone();
+                let secure_types = secure_types.clone();
+                async move {
+                    debug!("Running background memory pruning");
+                    let report = Self::delete_old_memories(
+                        &db_pool,
+                        &data_dir,
+                        &blob_lock,
+                        &schemas,
+                        clock(),
+                        retention_days,
+                        &secure_types,
+                        fts_available,
+                    )
+                    .await?;
+                    info!("Background pruning removed {} old memories", report.total());
+                    Ok(())
+                }
+            })
+            .await?;
 
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(3600 * 24)); // Daily
-            loop {
-                interval.tick().await;
-                debug!("Running background memory pruning");
-                // Implementation would prune old memories
-            }
-        });
+        // Write buffered access statistics
+        let db_pool = self.db_pool.clone();
+        let pending_access = Arc::clone(&self.pending_access);
+        let flush_interval = tokio::time::Duration::from_secs(self.config.access_flush_interval_seconds.max(1));
+
+        self.scheduler
+            .register(ACCESS_FLUSH_JOB, flush_interval, move || {
+                let db_pool = db_pool.clone();
+                let pending_access = Arc::clone(&pending_access);
+                async move {
+                    Self::flush_pending_access(&db_pool, &pending_access).await?;
+                    Ok(())
+                }
+            })
+            .await?;
 
         // Start cloud sync task
-        if self.cloud_sync.enabled {
+        if self.cloud_sync.is_active() {
             let cloud_sync = self.cloud_sync.clone();
-            tokio::spawn(async move {
-                let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(
-                    cloud_sync.sync_interval_minutes * 60,
-                ));
-                loop {
-                    interval.tick().await;
-                    debug!("Running background cloud sync");
-                    let mut last_sync = cloud_sync.last_sync.write().await;
-                    *last_sync = chrono::Utc::now();
-                }
-            });
+            let db_pool = self.db_pool.clone();
+            let security_manager = self.security_manager.clone();
+            let content_filter = self.content_filter.clone();
+            let embeddings = self.embeddings().cloned();
+            let clock = Arc::clone(&self.clock);
+            let sync_interval = tokio::time::Duration::from_secs(cloud_sync.sync_interval_minutes.max(1) * 60);
+
+            self.scheduler
+                .register(CLOUD_SYNC_JOB, sync_interval, move || {
+                    let cloud_sync = cloud_sync.clone();
+                    let db_pool = db_pool.clone();
+                    let security_manager = security_manager.clone();
+                    let content_filter = content_filter.clone();
+                    let embeddings = embeddings.clone();
+                    let clock = Arc::clone(&clock);
+                    async move {
+                        debug!("Running background cloud sync");
+                        let report = cloud_sync
+                            .sync(&db_pool, &security_manager, content_filter.as_ref(), embeddings.as_ref(), &clock)
+                            .await?;
+                        debug!(
+                            "Background cloud sync: {} pushed, {} pulled, {} conflicts",
+                            report.pushed, report.pulled, report.conflicts
+                        );
+                        Ok(())
+                    }
+                })
+                .await?;
         }
 
         Ok(())
     }
 }
 
+/// Binary memory content staged for the blob store
+struct StagedBlob {
+    hash: String,
+    bytes: Vec<u8>,
+}
+
+impl StagedBlob {
+    /// Memory content pointing at this blob
+    fn reference(&self) -> String {
+        format!("{}{}", BLOB_REFERENCE_PREFIX, self.hash)
+    }
+}
+
+/// File holding a blob's bytes, with `.enc` appended when they are sealed
+fn blob_file(data_dir: &str, hash: &str, encrypted: bool) -> PathBuf {
+    let file_name = if encrypted { format!("{}.enc", hash) } else { hash.to_string() };
+    Path::new(data_dir).join(BLOB_DIR).join(file_name)
+}
+
+/// SHA-256 hex digest named by a blob reference
+fn blob_hash(content: &str) -> Option<&str> {
+    content
+        .strip_prefix(BLOB_REFERENCE_PREFIX)
+        .filter(|hash| hash.len() == 64 && hash.bytes().all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f')))
+}
+
+/// Whether content is a single path or URI pointing at media stored elsewhere
+fn is_content_reference(content: &str) -> bool {
+    if content.is_empty() || content.len() > MAX_CONTENT_REFERENCE_LEN || content.contains(['\n', '\r', '\0']) {
+        return false;
+    }
+
+    if let Some((scheme, rest)) = content.split_once("://") {
+        return !scheme.is_empty()
+            && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+            && !rest.is_empty();
+    }
+
+    // data: URIs carry the bytes inline
+    !content.starts_with("data:")
+        && (Path::new(content).is_absolute() || ["./", "../", "~/"].iter().any(|prefix| content.starts_with(prefix)))
+}
+
+fn encode_vector(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|value| value.to_le_bytes()).collect()
+}
+
+fn decode_vector(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+/// Cosine similarity, or None when the vectors can't be compared
+fn cosine_similarity(a: &[f32], b: &[f32]) -> Option<f32> {
+    if a.len() != b.len() || a.is_empty() {
+        return None;
+    }
+
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|y| y * y).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return None;
+    }
+
+    Some(dot / (norm_a * norm_b))
+}
+
+/// Build a memory item from a `SELECT *` row of the memories table
+fn memory_from_row(row: &sqlx::sqlite::SqliteRow) -> MisaResult<MemoryItem> {
+    Ok(MemoryItem {
+        id: row.get("id"),
+        content: row.get("content"),
+        content_type: serde_json::from_str(row.get("content_type"))?,
+        memory_type: serde_json::from_str(row.get("memory_type"))?,
+        importance: serde_json::from_str(row.get("importance"))?,
+        tags: serde_json::from_str(&row.get::<Option<String>, _>("tags").unwrap_or_else(|| "[]".to_string()))?,
+        metadata: serde_json::from_str(&row.get::<Option<String>, _>("metadata").unwrap_or_else(|| "null".to_string()))?,
+        created_at: row.get("created_at"),
+        last_accessed: row.get("last_accessed"),
+        access_count: row.get::<i64, _>("access_count") as u32,
+        encrypted: row.get("encrypted"),
+        version: VersionVector::from_column(row.try_get::<Option<String>, _>("version").ok().flatten().as_deref())?,
+        binary: None,
+    })
+}
+
+/// Fields to change with `update_memory`; `None` leaves a field as it is
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MemoryPatch {
+    pub content: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub importance: Option<Importance>,
+    pub metadata: Option<serde_json::Value>,
+}
+
 /// Search query for memories
 #[derive(Debug, Clone)]
 pub struct SearchQuery {
@@ -765,10 +2871,59 @@ pub struct SearchQuery {
     pub offset: Option<u32>,
     pub sort_by: SortField,
     pub sort_order: SortOrder,
+    /// Match `text` with FTS5 and rank by BM25 instead of a `LIKE` scan.
+    /// Ranking replaces `sort_by`; falls back to `LIKE` when FTS5 is unavailable.
+    pub full_text: bool,
+    /// Resume after the last row of a previous page; replaces `offset`
+    pub cursor: Option<SearchCursor>,
     pub sql: String,
     pub params: Vec<String>,
 }
 
+/// Position of a row in a sorted search: the sort it was taken under, its sort column value and id
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchCursor {
+    sort: String,
+    value: String,
+    id: String,
+}
+
+impl SearchCursor {
+    /// Opaque form handed to callers as `next_cursor`
+    pub fn encode(&self) -> String {
+        CURSOR_BASE64.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub fn decode(cursor: &str) -> MisaResult<Self> {
+        let bytes = CURSOR_BASE64
+            .decode(cursor)
+            .map_err(|_| MisaError::Validation("Invalid search cursor".to_string()))?;
+        serde_json::from_slice(&bytes).map_err(|_| MisaError::Validation("Invalid search cursor".to_string()))
+    }
+
+    /// Capture the stored sort value as text so it compares exactly like the column
+    fn from_row(row: &sqlx::sqlite::SqliteRow, query: &SearchQuery) -> MisaResult<Self> {
+        // SQLite renders timestamps and counts alike as text, matching how the cursor is bound
+        let value = row
+            .try_get_unchecked::<String, _>(query.sort_column().trim_start_matches("memories."))
+            .map_err(|e| MisaError::Database(e))?;
+
+        Ok(Self {
+            sort: query.sort_key(),
+            value,
+            id: row.try_get("id").map_err(|e| MisaError::Database(e))?,
+        })
+    }
+}
+
+/// One page of search results
+#[derive(Debug, Clone)]
+pub struct SearchPage {
+    pub memories: Vec<MemoryItem>,
+    /// Pass to `SearchQuery::after` for the next page; `None` once results run out
+    pub next_cursor: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub enum SortField {
     CreatedAt,
@@ -796,44 +2951,99 @@ impl SearchQuery {
             offset: Some(0),
             sort_by: SortField::LastAccessed,
             sort_order: SortOrder::Desc,
+            full_text: false,
+            cursor: None,
             sql: String::new(),
             params: Vec::new(),
         }
     }
 
+    /// Continue from the `next_cursor` of an earlier page
+    pub fn after(mut self, cursor: &str) -> MisaResult<Self> {
+        self.cursor = Some(SearchCursor::decode(cursor)?);
+        Ok(self)
+    }
+
+    fn is_ranked(&self) -> bool {
+        self.full_text && self.text.as_deref().and_then(Self::fts_match_expression).is_some()
+    }
+
+    /// Column and direction a cursor's position is only meaningful under
+    fn sort_key(&self) -> String {
+        let order = match self.sort_order {
+            SortOrder::Asc => "asc",
+            SortOrder::Desc => "desc",
+        };
+        format!("{} {}", self.sort_column(), order)
+    }
+
+    fn sort_column(&self) -> &'static str {
+        match self.sort_by {
+            SortField::CreatedAt => "memories.created_at",
+            SortField::AccessCount => "memories.access_count",
+            SortField::LastAccessed | SortField::Importance => "memories.last_accessed",
+        }
+    }
+
     pub fn build_sql(&mut self) {
         let mut conditions = Vec::new();
         let mut params = Vec::new();
+        let mut from_clause = "memories";
+        let mut ranked = false;
 
         if let Some(text) = &self.text {
-            conditions.push("content LIKE ?");
-            params.push(format!("%{}%", text));
+            match Self::fts_match_expression(text) {
+                Some(expression) if self.full_text => {
+                    from_clause = "memories JOIN memories_fts ON memories_fts.rowid = memories.rowid";
+                    conditions.push("memories_fts MATCH ?");
+                    params.push(expression);
+                    ranked = true;
+                }
+                _ => {
+                    conditions.push("memories.content LIKE ?");
+                    params.push(format!("%{}%", text));
+                }
+            }
         }
 
         if let Some(content_type) = &self.content_type {
-            conditions.push("content_type = ?");
+            conditions.push("memories.content_type = ?");
             params.push(serde_json::to_string(content_type).unwrap());
         }
 
         if let Some(memory_type) = &self.memory_type {
-            conditions.push("memory_type = ?");
+            conditions.push("memories.memory_type = ?");
             params.push(serde_json::to_string(memory_type).unwrap());
         }
 
         if let Some(importance) = &self.importance {
-            conditions.push("importance = ?");
+            conditions.push("memories.importance = ?");
             params.push(serde_json::to_string(importance).unwrap());
         }
 
         if let Some((start, end)) = &self.date_range {
-            conditions.push("created_at BETWEEN ? AND ?");
+            conditions.push("memories.created_at BETWEEN ? AND ?");
             params.push(start.to_rfc3339());
             params.push(end.to_rfc3339());
         }
 
         for tag in &self.tags {
-            conditions.push("JSON_EXTRACT(tags, ?) IS NOT NULL");
-            params.push(format!("$[?]", tag));
+            conditions.push("EXISTS (SELECT 1 FROM json_each(memories.tags) WHERE json_each.value = ?)");
+            params.push(tag.clone());
+        }
+
+        // Keyset paging: rows strictly past the cursor in sort order, with id breaking ties
+        let cursor_condition;
+        let keyset = !ranked && self.cursor.is_some();
+        if let (false, Some(cursor)) = (ranked, &self.cursor) {
+            let comparison = match self.sort_order {
+                SortOrder::Asc => ">",
+                SortOrder::Desc => "<",
+            };
+            cursor_condition = format!("({}, memories.id) {} (?, ?)", self.sort_column(), comparison);
+            conditions.push(cursor_condition.as_str());
+            params.push(cursor.value.clone());
+            params.push(cursor.id.clone());
         }
 
         let where_clause = if conditions.is_empty() {
@@ -842,14 +3052,15 @@ impl SearchQuery {
             format!("WHERE {}", conditions.join(" AND "))
         };
 
-        let sort_clause = match (&self.sort_by, &self.sort_order) {
-            (SortField::CreatedAt, SortOrder::Asc) => "ORDER BY created_at ASC",
-            (SortField::CreatedAt, SortOrder::Desc) => "ORDER BY created_at DESC",
-            (SortField::LastAccessed, SortOrder::Asc) => "ORDER BY last_accessed ASC",
-            (SortField::LastAccessed, SortOrder::Desc) => "ORDER BY last_accessed DESC",
-            (SortField::AccessCount, SortOrder::Asc) => "ORDER BY access_count ASC",
-            (SortField::AccessCount, SortOrder::Desc) => "ORDER BY access_count DESC",
-            _ => "ORDER BY last_accessed DESC",
+        let sort_clause = if ranked {
+            // Lower BM25 scores are better matches
+            "ORDER BY bm25(memories_fts) ASC".to_string()
+        } else {
+            let direction = match self.sort_order {
+                SortOrder::Asc => "ASC",
+                SortOrder::Desc => "DESC",
+            };
+            format!("ORDER BY {} {}, memories.id {}", self.sort_column(), direction, direction)
         };
 
         let limit_clause = if let Some(limit) = self.limit {
@@ -858,18 +3069,31 @@ impl SearchQuery {
             String::new()
         };
 
-        let offset_clause = if let Some(offset) = self.offset {
-            format!("OFFSET {}", offset)
-        } else {
-            String::new()
+        let offset_clause = match self.offset {
+            Some(offset) if !keyset => format!("OFFSET {}", offset),
+            _ => String::new(),
         };
 
         self.sql = format!(
-            "SELECT * FROM memories {} {} {} {}",
-            where_clause, sort_clause, limit_clause, offset_clause
+            "SELECT memories.* FROM {} {} {} {} {}",
+            from_clause, where_clause, sort_clause, limit_clause, offset_clause
         );
         self.params = params;
     }
+
+    /// Quote each term so user input can't inject FTS5 query syntax; any term may match
+    fn fts_match_expression(text: &str) -> Option<String> {
+        let terms: Vec<String> = text
+            .split_whitespace()
+            .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+            .collect();
+
+        if terms.is_empty() {
+            None
+        } else {
+            Some(terms.join(" OR "))
+        }
+    }
 }
 
 /// Memory statistics
@@ -885,6 +3109,28 @@ pub struct MemoryStats {
     pub oldest_memory: Option<chrono::DateTime<chrono::Utc>>,
 }
 
+/// Memory statistics broken down for a "what's in my memory" view
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetailedMemoryStats {
+    pub stats: MemoryStats,
+    /// Most common content type first
+    pub by_content_type: Vec<ContentTypeCount>,
+    /// Most used tag first
+    pub top_tags: Vec<TagCount>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentTypeCount {
+    pub content_type: ContentType,
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: u32,
+}
+
 /// Database row for stats
 struct MemoryStatsRow {
     total_memories: Option<i64>,
@@ -902,10 +3148,26 @@ impl ContextEngine {
         Ok(Self {
             active_context: Arc::new(RwLock::new(ContextState::default())),
             context_sources: Arc::new(RwLock::new(HashMap::new())),
+            field_owners: Arc::new(RwLock::new(HashMap::new())),
             fusion_algorithms: FusionAlgorithms::new(),
+            short_term_capacity: DEFAULT_SHORT_TERM_CAPACITY,
+            clock: Arc::new(chrono::Utc::now),
         })
     }
 
+    /// Keep at most `capacity` memories in short-term memory
+    pub fn with_short_term_capacity(mut self, capacity: usize) -> Self {
+        self.short_term_capacity = capacity.max(1);
+        self
+    }
+
+    /// Rank memories with a custom relevance scorer
+    pub fn with_relevance_scorer(mut self, scorer: RelevanceScorer) -> Self {
+        self.fusion_algorithms.prediction_engine.relevance_scorer = scorer.clone();
+        self.fusion_algorithms.relevance_scorer = scorer;
+        self
+    }
+
     pub async fn initialize(&self) -> MisaResult<()> {
         info!("Initializing context engine");
 
@@ -924,45 +3186,154 @@ impl ContextEngine {
         Ok(())
     }
 
+    /// Snapshot of the context with time buckets as of now in the user's timezone
     pub async fn get_current_context(&self) -> MisaResult<ContextState> {
-        let context = self.active_context.read().await;
-        Ok(context.clone())
+        let mut context = self.active_context.read().await.clone();
+        let timezone = context.user_preferences.timezone.clone();
+        context.environment.refresh_time((self.clock)(), &timezone);
+        Ok(context)
+    }
+
+    /// Replace the user's preferences, moving the time buckets to their timezone
+    pub async fn update_preferences(&self, preferences: UserPreferences) {
+        let mut context = self.active_context.write().await;
+        context.environment.refresh_time((self.clock)(), &preferences.timezone);
+        context.user_preferences = preferences;
+        context.last_updated = chrono::Utc::now();
+    }
+
+    /// Register a custom context source so data can be pushed from it
+    pub async fn register_source(&self, source: ContextSource) -> MisaResult<()> {
+        if source.source_id.trim().is_empty() {
+            return Err(MisaError::Validation("Context source id must not be empty".to_string()));
+        }
+
+        let mut sources = self.context_sources.write().await;
+        if sources.contains_key(&source.source_id) {
+            return Err(MisaError::Validation(format!(
+                "Context source {} is already registered",
+                source.source_id
+            )));
+        }
+
+        info!("Registered context source {} ({:?})", source.source_id, source.source_type);
+        sources.insert(source.source_id.clone(), source);
+        Ok(())
+    }
+
+    /// Push data from a registered source, rejecting payloads that don't match its type
+    pub async fn push(&self, source_id: &str, data: serde_json::Value) -> MisaResult<()> {
+        let source = self
+            .context_sources
+            .read()
+            .await
+            .get(source_id)
+            .cloned()
+            .ok_or_else(|| MisaError::NotFound(format!("Context source {} is not registered", source_id)))?;
+
+        FusionAlgorithms::validate(&source.source_type, &data)?;
+        self.update_context(source, data).await
     }
 
-    pub async fn update_context(&self, source: ContextSource, data: serde_json::Value) -> MisaResult<()> {
+    pub async fn update_context(&self, mut source: ContextSource, data: serde_json::Value) -> MisaResult<()> {
+        let now = chrono::Utc::now();
+        let source_id = source.source_id.clone();
+        let source_type = source.source_type.clone();
+        let priority = source.priority;
+        let enabled = source.enabled;
+
         // Update context source
-        {
-            let mut sources = self.context_sources.write().await;
-            sources.insert(source.source_id.clone(), source);
+        source.last_data = Some(data.clone());
+        source.last_updated = now;
+        let mut sources = self.context_sources.write().await;
+        sources.insert(source_id.clone(), source);
+
+        if !enabled {
+            debug!("Ignoring data from disabled context source {}", source_id);
+            return Ok(());
         }
 
         // Process context fusion
+        let field = FusedField::for_source(&source_type);
+        let mut owners = self.field_owners.write().await;
+        if let Some(field) = field {
+            // A lower priority source must not overwrite what a higher priority one reported
+            let outranked = owners
+                .get(&field)
+                .filter(|owner_id| **owner_id != source_id)
+                .and_then(|owner_id| sources.get(owner_id))
+                .map_or(false, |owner| owner.enabled && owner.priority > priority);
+            if outranked {
+                debug!("Context source {} outranked for {:?}", source_id, field);
+                return Ok(());
+            }
+        }
+
         let mut context = self.active_context.write().await;
-        context.last_updated = chrono::Utc::now();
+        self.fusion_algorithms.fuse(&mut context, &source_type, data)?;
+        context.last_updated = now;
 
-        // In real implementation, this would use fusion algorithms
-        // to intelligently merge the new data
+        if let Some(field) = field {
+            owners.insert(field, source_id);
+        }
 
         Ok(())
     }
 
+    /// Add a memory, evicting the least useful ones once over capacity
     pub async fn add_to_short_term_memory(&self, memory: MemoryItem) -> MisaResult<()> {
         let mut context = self.active_context.write().await;
-        context.short_term_memory.push(memory);
+        context.short_term_memory.push_back(memory);
 
-        // Limit short-term memory size
-        if context.short_term_memory.len() > 50 {
-            context.short_term_memory.remove(0);
+        let now = chrono::Utc::now();
+        while context.short_term_memory.len() > self.short_term_capacity {
+            let Some(index) = self.eviction_candidate(&context, now) else {
+                break;
+            };
+            if let Some(evicted) = context.short_term_memory.remove(index) {
+                debug!("Evicted memory {} from short-term memory", evicted.id);
+            }
         }
 
         Ok(())
     }
+
+    /// Index of the memory with the lowest importance-weighted relevance,
+    /// the least recently accessed one among equals
+    fn eviction_candidate(&self, context: &ContextState, now: chrono::DateTime<chrono::Utc>) -> Option<usize> {
+        let scorer = &self.fusion_algorithms.relevance_scorer;
+        context
+            .short_term_memory
+            .iter()
+            .enumerate()
+            .map(|(index, memory)| {
+                let weight = memory.importance.retention_multiplier() as f32;
+                let score = scorer.calculate_relevance_at(memory, context, now) * weight;
+                (index, score, memory.last_accessed)
+            })
+            .min_by(|a, b| {
+                a.1.partial_cmp(&b.1)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then(a.2.cmp(&b.2))
+            })
+            .map(|(index, _, _)| index)
+    }
+
+    pub async fn remove_from_short_term_memory(&self, memory_id: &str) {
+        let mut context = self.active_context.write().await;
+        context.short_term_memory.retain(|memory| memory.id != memory_id);
+    }
+
+    /// Replace the whole active context, e.g. from an imported snapshot
+    pub async fn restore(&self, state: ContextState) {
+        *self.active_context.write().await = state;
+    }
 }
 
 impl MemorySchemas {
-    pub fn new(retention_days: u32) -> Self {
+    pub fn new(retention_days: u32, short_term_capacity: usize) -> Self {
         Self {
-            short_term_capacity: 100,
+            short_term_capacity: short_term_capacity.max(1),
             medium_term_retention_days: 30,
             long_term_retention_days: retention_days,
             compression_threshold: 0.8,
@@ -970,6 +3341,11 @@ impl MemorySchemas {
         }
     }
 
+    /// Most memories kept in short-term memory
+    pub fn short_term_capacity(&self) -> usize {
+        self.short_term_capacity
+    }
+
     /// Determine if memory should be compressed/summarized
     pub fn should_compress(&self, memory: &MemoryItem) -> bool {
         match memory.memory_type {
@@ -991,6 +3367,41 @@ impl MemorySchemas {
         }
     }
 
+    /// Whether a memory is old enough to be folded into a compaction summary
+    pub fn is_compaction_candidate(&self, memory: &MemoryItem, now: chrono::DateTime<chrono::Utc>) -> bool {
+        // Summaries and memories already covered by one are never regrouped
+        if memory.metadata["source"] == COMPACTION_SOURCE || memory.metadata.get("compacted_into").is_some() {
+            return false;
+        }
+
+        match self.compaction_age(&memory.memory_type) {
+            Some(min_age) => now.signed_duration_since(memory.created_at).num_days() >= min_age.num_days(),
+            None => false,
+        }
+    }
+
+    /// Age at which memories of `memory_type` are folded into a summary; `None` for types never compacted
+    pub fn compaction_age(&self, memory_type: &MemoryType) -> Option<chrono::Duration> {
+        match memory_type {
+            MemoryType::ShortTerm => Some(chrono::Duration::days(1)),
+            MemoryType::MediumTerm => Some(chrono::Duration::days((self.medium_term_retention_days / 2) as i64)),
+            MemoryType::LongTerm | MemoryType::Permanent => None,
+        }
+    }
+
+    /// Share of a group made up of low-value memories: rarely accessed and not important
+    pub fn compaction_density(&self, group: &[MemoryItem]) -> f32 {
+        if group.is_empty() {
+            return 0.0;
+        }
+
+        let low_value = group
+            .iter()
+            .filter(|memory| matches!(memory.importance, Importance::Low | Importance::Medium) && memory.access_count < 5)
+            .count();
+        low_value as f32 / group.len() as f32
+    }
+
     /// Generate memory summary using AI-like heuristics
     pub async fn generate_summary(&self, memory: &MemoryItem) -> String {
         if !self.summarization_enabled {
@@ -1163,12 +3574,21 @@ impl MemorySchemas {
     /// Get memory retention policy
     pub fn get_retention_policy(&self, memory_type: MemoryType) -> chrono::Duration {
         match memory_type {
-            MemoryType::ShortTerm => chrono::Duration::days(1),
+            // Kept past the compaction age so compaction gets to summarize them first
+            MemoryType::ShortTerm => chrono::Duration::days(2),
             MemoryType::MediumTerm => chrono::Duration::days(self.medium_term_retention_days as i64),
             MemoryType::LongTerm => chrono::Duration::days(self.long_term_retention_days as i64),
             MemoryType::Permanent => chrono::Duration::days(365 * 100), // 100 years
         }
     }
+
+    /// Effective retention: the type's policy capped by the configured retention, scaled by importance
+    pub fn retention_period(&self, memory_type: &MemoryType, importance: &Importance, retention_days: u32) -> chrono::Duration {
+        let base = self
+            .get_retention_policy(memory_type.clone())
+            .min(chrono::Duration::days(retention_days as i64));
+        base * importance.retention_multiplier()
+    }
 }
 
 impl FusionAlgorithms {
@@ -1180,16 +3600,447 @@ impl FusionAlgorithms {
             prediction_engine: PredictionEngine::new(),
         }
     }
+
+    /// Check a payload has the shape its source type's fusion expects
+    pub fn validate(source_type: &ContextSourceType, data: &serde_json::Value) -> MisaResult<()> {
+        let invalid = |e: serde_json::Error| {
+            MisaError::Validation(format!("Invalid {:?} context payload: {}", source_type, e))
+        };
+
+        match source_type {
+            ContextSourceType::Application => {
+                serde_json::from_value::<Vec<ApplicationInfo>>(data.clone()).map_err(invalid)?;
+            }
+            ContextSourceType::Location => {
+                serde_json::from_value::<LocationData>(data.clone()).map_err(invalid)?;
+            }
+            ContextSourceType::Calendar => {
+                serde_json::from_value::<CalendarEvent>(data.clone()).map_err(invalid)?;
+            }
+            _ if !data.is_object() => {
+                return Err(MisaError::Validation(format!(
+                    "{:?} context payload must be a JSON object",
+                    source_type
+                )));
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Merge data reported by a source into the context
+    pub fn fuse(
+        &self,
+        context: &mut ContextState,
+        source_type: &ContextSourceType,
+        data: serde_json::Value,
+    ) -> MisaResult<()> {
+        match source_type {
+            ContextSourceType::System => {
+                // System monitors often report a subset of fields, so overlay them
+                let mut merged = serde_json::to_value(&context.system_state)?;
+                if let (Some(current), serde_json::Value::Object(update)) = (merged.as_object_mut(), data) {
+                    current.extend(update);
+                }
+                context.system_state = serde_json::from_value(merged)?;
+            }
+            ContextSourceType::Application => {
+                context.active_applications = serde_json::from_value(data)?;
+            }
+            ContextSourceType::Location => {
+                context.environment.location = serde_json::from_value(data)?;
+            }
+            ContextSourceType::Calendar => {
+                // Each push is one event; a repeated id replaces the earlier version
+                let event: CalendarEvent = serde_json::from_value(data)?;
+                let events = &mut context.calendar_events;
+                events.retain(|existing| existing.event_id != event.event_id);
+                events.push(event);
+                events.sort_by_key(|event| event.start);
+                if events.len() > MAX_CALENDAR_EVENTS {
+                    // Finished events make room first, oldest first; only then the furthest upcoming ones
+                    let now = chrono::Utc::now();
+                    let mut excess = events.len() - MAX_CALENDAR_EVENTS;
+                    events.retain(|event| {
+                        let finished = event.end.unwrap_or(event.start) < now;
+                        if finished && excess > 0 {
+                            excess -= 1;
+                            return false;
+                        }
+                        true
+                    });
+                    events.truncate(MAX_CALENDAR_EVENTS);
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// Run content from a tagged source through `content_filter`, failing if the filter withholds it
+async fn filter_memory_content(content_filter: Option<&Arc<dyn ContentFilter>>, memory: &mut MemoryItem) -> MisaResult<()> {
+    let (content_filter, source) = match (content_filter, memory.metadata["source"].as_str()) {
+        (Some(content_filter), Some(source)) => (content_filter, source.to_string()),
+        _ => return Ok(()),
+    };
+
+    match content_filter.filter_content(&source, &memory.content).await? {
+        Some(content) => {
+            memory.content = content;
+            Ok(())
+        }
+        None => Err(MisaError::Privacy(format!(
+            "Memory {} from {} was blocked by a privacy filter",
+            memory.id, source
+        ))),
+    }
 }
 
 impl CloudSync {
-    pub fn new(enabled: bool) -> Self {
+    pub fn new(config: &CloudSyncConfig, replica_id: String) -> Self {
         Self {
-            enabled,
-            sync_interval_minutes: 30,
-            last_sync: Arc::new(RwLock::new(chrono::Utc::now())),
-            conflict_resolver: ConflictResolver::new(ConflictStrategy::LastModifiedWins),
+            enabled: config.enabled,
+            endpoint: config.endpoint.as_ref().map(|endpoint| endpoint.trim_end_matches('/').to_string()),
+            api_key: config.api_key.clone(),
+            sync_interval_minutes: config.interval_minutes,
+            // Nothing has been pulled yet, so the first sync fetches every remote change
+            last_sync: Arc::new(RwLock::new(chrono::DateTime::<chrono::Utc>::UNIX_EPOCH)),
+            conflict_resolver: ConflictResolver::new(config.conflict_strategy.clone()),
+            client: reqwest::Client::new(),
+            replica_id,
+        }
+    }
+
+    /// Whether sync is enabled and has a backend to talk to
+    pub fn is_active(&self) -> bool {
+        self.enabled && self.endpoint.is_some()
+    }
+
+    /// Pull remote changes, resolve conflicts, then push local changes
+    pub(crate) async fn sync(
+        &self,
+        db_pool: &SqlitePool,
+        security_manager: &SecurityManager,
+        content_filter: Option<&Arc<dyn ContentFilter>>,
+        embeddings: Option<&EmbeddingQueue>,
+        clock: &Clock,
+    ) -> MisaResult<SyncReport> {
+        let endpoint = self.endpoint.as_deref()
+            .ok_or_else(|| MisaError::Configuration("Cloud sync endpoint not configured".to_string()))?;
+        let sync_started = clock();
+        let since = *self.last_sync.read().await;
+        let mut report = SyncReport::default();
+        let mut earliest_unresolved: Option<chrono::DateTime<chrono::Utc>> = None;
+
+        // Pull first so conflicting edits are settled before anything is pushed
+        for envelope in self.fetch_remote_changes(endpoint, since).await? {
+            let remote = SyncedMemory {
+                memory: Self::open_envelope(security_manager, &envelope).await?,
+                updated_at: envelope.updated_at,
+                dirty: false,
+            };
+
+            match Self::load_synced_memory(db_pool, &remote.memory.id).await? {
+                Some(local) if local.dirty => {
+                    report.conflicts += 1;
+                    let resolution = self.conflict_resolver.resolve(&local, &remote);
+                    if matches!(resolution, Resolution::Unresolved) {
+                        Self::record_conflict(db_pool, &envelope, sync_started).await?;
+                    } else {
+                        Self::clear_conflict(db_pool, &local.memory.id).await?;
+                    }
+                    match resolution {
+                        Resolution::KeepLocal => {}
+                        Resolution::TakeRemote => {
+                            if Self::store_pulled(db_pool, security_manager, content_filter, embeddings, remote.memory, remote.updated_at, false).await? {
+                                report.pulled += 1;
+                            }
+                        }
+                        Resolution::Merged(mut memory) => {
+                            // The merge is a new edit on top of both versions
+                            memory.version = local.memory.version.merged(&remote.memory.version);
+                            memory.version.increment(&self.replica_id);
+                            let updated_at = local.updated_at.max(remote.updated_at);
+                            if Self::store_pulled(db_pool, security_manager, content_filter, embeddings, memory, updated_at, true).await? {
+                                report.pulled += 1;
+                            }
+                        }
+                        Resolution::Unresolved => {
+                            report.unresolved.push(local.memory.id.clone());
+                            earliest_unresolved = Some(match earliest_unresolved {
+                                Some(earliest) => earliest.min(remote.updated_at),
+                                None => remote.updated_at,
+                            });
+                        }
+                    }
+                }
+                _ => {
+                    if Self::store_pulled(db_pool, security_manager, content_filter, embeddings, remote.memory, remote.updated_at, false).await? {
+                        report.pulled += 1;
+                    }
+                }
+            }
+        }
+
+        // Pushing an unresolved memory would overwrite the remote side of the conflict
+        let mut dirty = Self::load_dirty_memories(db_pool).await?;
+        dirty.retain(|synced| !report.unresolved.contains(&synced.memory.id));
+        if !dirty.is_empty() {
+            let mut records = Vec::with_capacity(dirty.len());
+            for synced in &dirty {
+                records.push(Self::seal_envelope(security_manager, &synced.memory, synced.updated_at).await?);
+            }
+
+            self.push_changes(endpoint, records).await?;
+            for synced in &dirty {
+                Self::mark_clean(db_pool, synced).await?;
+            }
+            report.pushed = dirty.len() as u32;
+        }
+
+        // Stop short of unresolved remote edits so the next sync pulls them again
+        // instead of losing them behind the watermark
+        *self.last_sync.write().await = match earliest_unresolved {
+            Some(earliest) => sync_started.min(earliest - chrono::Duration::milliseconds(1)),
+            None => sync_started,
+        };
+        Ok(report)
+    }
+
+    fn authorized(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.api_key {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        }
+    }
+
+    async fn fetch_remote_changes(
+        &self,
+        endpoint: &str,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> MisaResult<Vec<SyncEnvelope>> {
+        let request = self.client
+            .get(format!("{}/memories/changes", endpoint))
+            .query(&[("since", since.to_rfc3339())]);
+
+        let batch: SyncBatch = self.authorized(request)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(batch.records)
+    }
+
+    async fn push_changes(&self, endpoint: &str, records: Vec<SyncEnvelope>) -> MisaResult<()> {
+        let request = self.client
+            .post(format!("{}/memories", endpoint))
+            .json(&SyncBatch { records });
+
+        self.authorized(request)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Encrypt a memory for upload with the persisted sync key; the backend only ever sees ciphertext
+    async fn seal_envelope(
+        security_manager: &SecurityManager,
+        memory: &MemoryItem,
+        updated_at: chrono::DateTime<chrono::Utc>,
+    ) -> MisaResult<SyncEnvelope> {
+        let plaintext = serde_json::to_vec(memory)?;
+        let payload = security_manager.encrypt_with_stored_key(SYNC_KEY_NAME, &plaintext).await?;
+
+        Ok(SyncEnvelope {
+            id: memory.id.clone(),
+            updated_at,
+            payload,
+        })
+    }
+
+    async fn open_envelope(security_manager: &SecurityManager, envelope: &SyncEnvelope) -> MisaResult<MemoryItem> {
+        let plaintext = security_manager.decrypt_with_stored_key(SYNC_KEY_NAME, &envelope.payload).await?;
+        let memory: MemoryItem = serde_json::from_slice(&plaintext)?;
+
+        if memory.id != envelope.id {
+            return Err(MisaError::Validation(format!(
+                "Sync envelope {} contained memory {}",
+                envelope.id, memory.id
+            )));
+        }
+
+        Ok(memory)
+    }
+
+    async fn load_synced_memory(db_pool: &SqlitePool, memory_id: &str) -> MisaResult<Option<SyncedMemory>> {
+        let row = sqlx::query("SELECT * FROM memories WHERE id = ?")
+            .bind(memory_id)
+            .fetch_optional(db_pool)
+            .await
+            .map_err(|e| MisaError::Database(e))?;
+
+        row.as_ref().map(Self::synced_memory_from_row).transpose()
+    }
+
+    async fn load_dirty_memories(db_pool: &SqlitePool) -> MisaResult<Vec<SyncedMemory>> {
+        let rows = sqlx::query("SELECT * FROM memories WHERE dirty = TRUE")
+            .fetch_all(db_pool)
+            .await
+            .map_err(|e| MisaError::Database(e))?;
+
+        rows.iter().map(Self::synced_memory_from_row).collect()
+    }
+
+    fn synced_memory_from_row(row: &sqlx::sqlite::SqliteRow) -> MisaResult<SyncedMemory> {
+        let memory = memory_from_row(row)?;
+        // Rows written before sync existed have no modification time of their own
+        let updated_at = row
+            .get::<Option<chrono::DateTime<chrono::Utc>>, _>("updated_at")
+            .unwrap_or(memory.created_at);
+
+        Ok(SyncedMemory {
+            memory,
+            updated_at,
+            dirty: row.get("dirty"),
+        })
+    }
+
+    /// Store a pulled memory after running it through the content filter; withheld memories are skipped
+    async fn store_pulled(
+        db_pool: &SqlitePool,
+        security_manager: &SecurityManager,
+        content_filter: Option<&Arc<dyn ContentFilter>>,
+        embeddings: Option<&EmbeddingQueue>,
+        mut memory: MemoryItem,
+        updated_at: chrono::DateTime<chrono::Utc>,
+        dirty: bool,
+    ) -> MisaResult<bool> {
+        match filter_memory_content(content_filter, &mut memory).await {
+            Ok(()) => {}
+            Err(MisaError::Privacy(reason)) => {
+                warn!("Skipping pulled memory: {}", reason);
+                return Ok(false);
+            }
+            Err(e) => return Err(e),
+        }
+
+        Self::upsert_memory(db_pool, security_manager, &memory, updated_at, dirty).await?;
+        if let Some(embeddings) = embeddings {
+            embeddings.push(&memory);
         }
+        Ok(true)
+    }
+
+    async fn upsert_memory(
+        db_pool: &SqlitePool,
+        security_manager: &SecurityManager,
+        memory: &MemoryItem,
+        updated_at: chrono::DateTime<chrono::Utc>,
+        dirty: bool,
+    ) -> MisaResult<()> {
+        // Encrypted memories keep their local ciphertext, as if they had been stored here
+        let encrypted_blob = if memory.encrypted {
+            Some(security_manager.encrypt_data(memory.content.as_bytes(), &memory.id).await?.ciphertext)
+        } else {
+            None
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO memories (
+                id, content, content_type, memory_type, importance,
+                tags, metadata, created_at, last_accessed,
+                access_count, encrypted, encrypted_data, version, updated_at, dirty
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                content = excluded.content,
+                content_type = excluded.content_type,
+                memory_type = excluded.memory_type,
+                importance = excluded.importance,
+                tags = excluded.tags,
+                metadata = excluded.metadata,
+                encrypted = excluded.encrypted,
+                encrypted_data = excluded.encrypted_data,
+                version = excluded.version,
+                updated_at = excluded.updated_at,
+                dirty = excluded.dirty
+            "#
+        )
+        .bind(&memory.id)
+        .bind(&memory.content)
+        .bind(serde_json::to_string(&memory.content_type)?)
+        .bind(serde_json::to_string(&memory.memory_type)?)
+        .bind(serde_json::to_string(&memory.importance)?)
+        .bind(serde_json::to_string(&memory.tags)?)
+        .bind(serde_json::to_string(&memory.metadata)?)
+        .bind(memory.created_at)
+        .bind(memory.last_accessed)
+        .bind(memory.access_count)
+        .bind(memory.encrypted)
+        .bind(encrypted_blob)
+        .bind(serde_json::to_string(&memory.version)?)
+        .bind(updated_at)
+        .bind(dirty)
+        .execute(db_pool)
+        .await
+        .map_err(|e| MisaError::Database(e))?;
+
+        Ok(())
+    }
+
+    /// Keep the remote side of a conflict until someone resolves it, first detection time included
+    async fn record_conflict(
+        db_pool: &SqlitePool,
+        envelope: &SyncEnvelope,
+        detected_at: chrono::DateTime<chrono::Utc>,
+    ) -> MisaResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO sync_conflicts (memory_id, remote_envelope, remote_updated_at, detected_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(memory_id) DO UPDATE SET
+                remote_envelope = excluded.remote_envelope,
+                remote_updated_at = excluded.remote_updated_at
+            "#
+        )
+        .bind(&envelope.id)
+        .bind(serde_json::to_string(envelope)?)
+        .bind(envelope.updated_at)
+        .bind(detected_at)
+        .execute(db_pool)
+        .await
+        .map_err(|e| MisaError::Database(e))?;
+
+        Ok(())
+    }
+
+    async fn clear_conflict(db_pool: &SqlitePool, memory_id: &str) -> MisaResult<()> {
+        sqlx::query("DELETE FROM sync_conflicts WHERE memory_id = ?")
+            .bind(memory_id)
+            .execute(db_pool)
+            .await
+            .map_err(|e| MisaError::Database(e))?;
+
+        Ok(())
+    }
+
+    /// Clear the dirty flag unless the memory was edited again while pushing
+    async fn mark_clean(db_pool: &SqlitePool, synced: &SyncedMemory) -> MisaResult<()> {
+        sqlx::query("UPDATE memories SET dirty = FALSE WHERE id = ? AND COALESCE(updated_at, created_at) = ?")
+            .bind(&synced.memory.id)
+            .bind(synced.updated_at)
+            .execute(db_pool)
+            .await
+            .map_err(|e| MisaError::Database(e))?;
+
+        Ok(())
     }
 }
 
@@ -1197,9 +4048,72 @@ impl ConflictResolver {
     pub fn new(strategy: ConflictStrategy) -> Self {
         Self { strategy }
     }
+
+    /// Decide between a locally edited memory and a remote edit of the same memory
+    pub fn resolve(&self, local: &SyncedMemory, remote: &SyncedMemory) -> Resolution {
+        match self.strategy {
+            ConflictStrategy::LocalWins => Resolution::KeepLocal,
+            ConflictStrategy::RemoteWins => Resolution::TakeRemote,
+            ConflictStrategy::LastModifiedWins => {
+                // Ties go to the local copy so an unchanged device never loses edits
+                if remote.updated_at > local.updated_at {
+                    Resolution::TakeRemote
+                } else {
+                    Resolution::KeepLocal
+                }
+            }
+            ConflictStrategy::Merge => match Self::causal_order(local, remote) {
+                VersionOrder::After => Resolution::TakeRemote,
+                VersionOrder::Before | VersionOrder::Equal => Resolution::KeepLocal,
+                VersionOrder::Concurrent => Self::merge(local, remote),
+            },
+            ConflictStrategy::ManualResolution => match Self::causal_order(local, remote) {
+                VersionOrder::After => Resolution::TakeRemote,
+                VersionOrder::Before | VersionOrder::Equal => Resolution::KeepLocal,
+                VersionOrder::Concurrent => {
+                    warn!(
+                        "Memory {} was edited concurrently on two devices; leaving it for manual resolution",
+                        local.memory.id
+                    );
+                    Resolution::Unresolved
+                }
+            },
+        }
+    }
+
+    /// How the remote edit relates to the local one
+    fn causal_order(local: &SyncedMemory, remote: &SyncedMemory) -> VersionOrder {
+        // Without any history neither edit can be shown to include the other
+        if local.memory.version.is_empty() && remote.memory.version.is_empty() {
+            return VersionOrder::Concurrent;
+        }
+        remote.memory.version.compare(&local.memory.version)
+    }
+
+    /// Take the newer edit and union in the older one's tags
+    fn merge(local: &SyncedMemory, remote: &SyncedMemory) -> Resolution {
+        let (newer, older) = if remote.updated_at > local.updated_at {
+            (&remote.memory, &local.memory)
+        } else {
+            (&local.memory, &remote.memory)
+        };
+
+        let mut merged = newer.clone();
+        for tag in &older.tags {
+            if !merged.tags.contains(tag) {
+                merged.tags.push(tag.clone());
+            }
+        }
+        merged.access_count = local.memory.access_count.max(remote.memory.access_count);
+        Resolution::Merged(merged)
+    }
 }
 
+/// Access count at which the frequency score reaches its maximum
+const FREQUENCY_SATURATION: f32 = 100.0;
+
 /// Relevance scoring algorithm for memory items
+#[derive(Debug, Clone)]
 pub struct RelevanceScorer {
     time_decay_factor: f32,
     frequency_weight: f32,
@@ -1209,36 +4123,61 @@ pub struct RelevanceScorer {
 
 impl RelevanceScorer {
     pub fn new() -> Self {
+        Self::from_config(&RelevanceConfig::default())
+    }
+
+    /// Build a scorer whose weights are normalized to sum to 1
+    pub fn from_config(config: &RelevanceConfig) -> Self {
+        let weights = [config.recency_weight, config.frequency_weight, config.context_weight]
+            .map(|weight| if weight.is_finite() { weight.max(0.0) } else { 0.0 });
+        let total: f32 = weights.iter().sum();
+        // Without any usable weight every component counts the same
+        let [recency_weight, frequency_weight, context_weight] = if total > 0.0 {
+            weights.map(|weight| weight / total)
+        } else {
+            [1.0 / 3.0; 3]
+        };
+
         Self {
-            time_decay_factor: 0.1,
-            frequency_weight: 0.3,
-            recency_weight: 0.4,
-            context_weight: 0.3,
+            time_decay_factor: config.time_decay_per_hour.max(0.0),
+            frequency_weight,
+            recency_weight,
+            context_weight,
         }
     }
 
-    /// Calculate relevance score for a memory item
+    /// Calculate relevance score for a memory item, in [0, 1]
     pub fn calculate_relevance(&self, memory: &MemoryItem, current_context: &ContextState) -> f32 {
-        let time_score = self.calculate_time_score(memory);
+        self.calculate_relevance_at(memory, current_context, chrono::Utc::now())
+    }
+
+    /// Relevance score as of `now`
+    pub fn calculate_relevance_at(
+        &self,
+        memory: &MemoryItem,
+        current_context: &ContextState,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> f32 {
+        let time_score = self.calculate_time_score(memory, now);
         let frequency_score = self.calculate_frequency_score(memory);
         let context_score = self.calculate_context_score(memory, current_context);
 
-        (time_score * self.recency_weight) +
-        (frequency_score * self.frequency_weight) +
-        (context_score * self.context_weight)
+        let score = (time_score * self.recency_weight)
+            + (frequency_score * self.frequency_weight)
+            + (context_score * self.context_weight);
+        score.clamp(0.0, 1.0)
     }
 
-    fn calculate_time_score(&self, memory: &MemoryItem) -> f32 {
-        let now = chrono::Utc::now();
-        let hours_since_access = now.signed_duration_since(memory.last_accessed).num_hours();
+    fn calculate_time_score(&self, memory: &MemoryItem, now: chrono::DateTime<chrono::Utc>) -> f32 {
+        let hours_since_access = now.signed_duration_since(memory.last_accessed).num_seconds().max(0) as f32 / 3600.0;
 
         // Exponential decay based on time
-        (-self.time_decay_factor * hours_since_access as f32).exp()
+        (-self.time_decay_factor * hours_since_access).exp()
     }
 
     fn calculate_frequency_score(&self, memory: &MemoryItem) -> f32 {
-        // Normalize access count (logarithmic scale)
-        (1.0 + memory.access_count as f32).log10() / 10.0
+        // Logarithmic scale, saturating once a memory is accessed often enough
+        ((1.0 + memory.access_count as f32).ln() / (1.0 + FREQUENCY_SATURATION).ln()).min(1.0)
     }
 
     fn calculate_context_score(&self, memory: &MemoryItem, context: &ContextState) -> f32 {
@@ -1420,7 +4359,7 @@ impl PatternDetector {
 
 /// Anomaly detection for unusual patterns or behaviors
 pub struct AnomalyDetector {
-    anomaly_threshold: f32,
+    anomaly_threshold: f64,
     baseline_window_size: usize,
 }
 
@@ -1451,9 +4390,14 @@ pub enum AnomalySeverity {
 
 impl AnomalyDetector {
     pub fn new() -> Self {
+        Self::with_settings(2.0, 100) // 2 standard deviations over 100 days
+    }
+
+    /// Detector with a custom z-score threshold and baseline window (days)
+    pub fn with_settings(anomaly_threshold: f64, baseline_window_size: usize) -> Self {
         Self {
-            anomaly_threshold: 2.0, // 2 standard deviations
-            baseline_window_size: 100,
+            anomaly_threshold,
+            baseline_window_size,
         }
     }
 
@@ -1510,12 +4454,17 @@ impl AnomalyDetector {
         let mut anomalies = Vec::new();
 
         // Group memories by creation date
-        let mut daily_counts = std::collections::HashMap::new();
+        let mut daily_counts = std::collections::BTreeMap::new();
         for memory in memories {
             let date = memory.created_at.date_naive();
             *daily_counts.entry(date).or_insert(0) += 1;
         }
 
+        // Only the most recent days form the baseline
+        while daily_counts.len() > self.baseline_window_size {
+            daily_counts.pop_first();
+        }
+
         if daily_counts.len() < 7 {
             return anomalies; // Insufficient data
         }
@@ -1590,6 +4539,7 @@ impl AnomalyDetector {
 pub struct PredictionEngine {
     prediction_models: Vec<PredictionModel>,
     confidence_threshold: f32,
+    relevance_scorer: RelevanceScorer,
 }
 
 #[derive(Debug, Clone)]
@@ -1620,11 +4570,32 @@ pub struct Prediction {
     pub valid_until: chrono::DateTime<chrono::Utc>,
 }
 
+/// Remembers emitted predictions until they expire
+#[derive(Debug, Default)]
+struct PredictionDebouncer {
+    active: HashMap<(String, String), chrono::DateTime<chrono::Utc>>,
+}
+
+impl PredictionDebouncer {
+    fn should_emit(&mut self, prediction: &Prediction, now: chrono::DateTime<chrono::Utc>) -> bool {
+        self.active.retain(|_, valid_until| *valid_until > now);
+
+        let key = (prediction.prediction_type.clone(), prediction.suggestion.clone());
+        if self.active.contains_key(&key) {
+            return false;
+        }
+
+        self.active.insert(key, prediction.valid_until);
+        true
+    }
+}
+
 impl PredictionEngine {
     pub fn new() -> Self {
         Self {
             prediction_models: Vec::new(),
             confidence_threshold: 0.6,
+            relevance_scorer: RelevanceScorer::new(),
         }
     }
 
@@ -1688,10 +4659,9 @@ impl PredictionEngine {
         let mut predictions = Vec::new();
 
         // Find memories relevant to current context
-        let relevance_scorer = RelevanceScorer::new();
         let mut relevant_memories: Vec<(f32, &MemoryItem)> = memories
             .iter()
-            .map(|m| (relevance_scorer.calculate_relevance(m, context), m))
+            .map(|m| (self.relevance_scorer.calculate_relevance(m, context), m))
             .filter(|(score, _)| *score > 0.5)
             .collect();
 
@@ -1719,9 +4689,7 @@ impl PredictionEngine {
     fn predict_time_based_needs(&self, context: &ContextState, memories: &[MemoryItem]) -> Vec<Prediction> {
         let mut predictions = Vec::new();
 
-        let current_time = context.environment.time_of_day;
-
-        match current_time {
+        match context.environment.time_of_day {
             TimeOfDay::Morning => {
                 predictions.push(Prediction {
                     prediction_type: "time_based".to_string(),
@@ -1783,7 +4751,10 @@ impl Clone for ContextEngine {
         Self {
             active_context: Arc::clone(&self.active_context),
             context_sources: Arc::clone(&self.context_sources),
-            fusion_algorithms: FusionAlgorithms::new(),
+            field_owners: Arc::clone(&self.field_owners),
+            fusion_algorithms: self.fusion_algorithms.clone(),
+            short_term_capacity: self.short_term_capacity,
+            clock: Arc::clone(&self.clock),
         }
     }
 }
@@ -1792,9 +4763,13 @@ impl Clone for CloudSync {
     fn clone(&self) -> Self {
         Self {
             enabled: self.enabled,
+            endpoint: self.endpoint.clone(),
+            api_key: self.api_key.clone(),
             sync_interval_minutes: self.sync_interval_minutes,
             last_sync: Arc::clone(&self.last_sync),
             conflict_resolver: ConflictResolver::new(self.conflict_resolver.strategy.clone()),
+            client: self.client.clone(),
+            replica_id: self.replica_id.clone(),
         }
     }
 }
@@ -1809,15 +4784,17 @@ impl Clone for ConflictResolver {
 
 impl Default for ContextState {
     fn default() -> Self {
+        let user_preferences = UserPreferences::default();
         Self {
             session_id: uuid::Uuid::new_v4().to_string(),
             user_id: "default".to_string(),
             current_task: None,
             active_applications: Vec::new(),
             system_state: SystemState::default(),
-            environment: EnvironmentContext::default(),
-            user_preferences: UserPreferences::default(),
-            short_term_memory: Vec::new(),
+            environment: EnvironmentContext::for_preferences(&user_preferences),
+            user_preferences,
+            short_term_memory: VecDeque::new(),
+            calendar_events: Vec::new(),
             last_updated: chrono::Utc::now(),
         }
     }
@@ -1843,21 +4820,65 @@ impl Default for SystemState {
 }
 
 impl Default for EnvironmentContext {
+    /// Time buckets in the server's local timezone
     fn default() -> Self {
+        Self::in_zone(chrono::Utc::now(), None)
+    }
+}
+
+impl EnvironmentContext {
+    /// Current environment with time buckets in the user's configured timezone
+    pub fn for_preferences(preferences: &UserPreferences) -> Self {
+        Self::at(chrono::Utc::now(), &preferences.timezone)
+    }
+
+    /// Environment at `now` in an IANA `timezone` such as "America/New_York".
+    /// Unknown timezones fall back to the server's local time.
+    pub fn at(now: chrono::DateTime<chrono::Utc>, timezone: &str) -> Self {
+        let zone = match timezone.parse::<chrono_tz::Tz>() {
+            Ok(zone) => Some(zone),
+            Err(e) => {
+                warn!("Unknown timezone {:?}, using local time: {}", timezone, e);
+                None
+            }
+        };
+        Self::in_zone(now, zone)
+    }
+
+    /// Move the time of day and day of week to `now` in `timezone`, keeping everything else
+    pub fn refresh_time(&mut self, now: chrono::DateTime<chrono::Utc>, timezone: &str) {
+        let current = Self::at(now, timezone);
+        self.time_of_day = current.time_of_day;
+        self.day_of_week = current.day_of_week;
+    }
+
+    fn in_zone(now: chrono::DateTime<chrono::Utc>, zone: Option<chrono_tz::Tz>) -> Self {
+        use chrono::{Datelike, Timelike};
+
+        let (hour, weekday) = match zone {
+            Some(zone) => {
+                let local = now.with_timezone(&zone);
+                (local.hour(), local.weekday())
+            }
+            None => {
+                let local = now.with_timezone(&chrono::Local);
+                (local.hour(), local.weekday())
+            }
+        };
+
         Self {
             location: None,
-            time_of_day: Self::get_current_time_of_day(),
-            day_of_week: Self::get_current_day_of_week(),
+            time_of_day: TimeOfDay::from_hour(hour),
+            day_of_week: DayOfWeek::from_weekday(weekday),
             ambient_conditions: None,
             nearby_devices: Vec::new(),
         }
     }
 }
 
-impl EnvironmentContext {
-    fn get_current_time_of_day() -> TimeOfDay {
-        use chrono::Local;
-        let hour = Local::now().hour();
+impl TimeOfDay {
+    /// Bucket for an hour of the day (0-23)
+    pub fn from_hour(hour: u32) -> Self {
         match hour {
             5..=7 => TimeOfDay::EarlyMorning,
             8..=11 => TimeOfDay::Morning,
@@ -1867,10 +4888,11 @@ impl EnvironmentContext {
             _ => TimeOfDay::LateNight,
         }
     }
+}
 
-    fn get_current_day_of_week() -> DayOfWeek {
-        use chrono::Local;
-        match Local::now().weekday() {
+impl DayOfWeek {
+    pub fn from_weekday(weekday: chrono::Weekday) -> Self {
+        match weekday {
             chrono::Weekday::Mon => DayOfWeek::Monday,
             chrono::Weekday::Tue => DayOfWeek::Tuesday,
             chrono::Weekday::Wed => DayOfWeek::Wednesday,
@@ -1956,9 +4978,21 @@ impl Clone for MemoryManager {
             data_dir: self.data_dir.clone(),
             security_manager: self.security_manager.clone(),
             db_pool: self.db_pool.clone(),
-            context_engine: ContextEngine::new().await.unwrap(),
-            memory_schemas: MemorySchemas::new(self.config.retention_days),
-            cloud_sync: CloudSync::new(self.cloud_sync.enabled),
+            context_engine: self.context_engine.clone(),
+            memory_schemas: self.memory_schemas.clone(),
+            cloud_sync: self.cloud_sync.clone(),
+            clock: Arc::clone(&self.clock),
+            fts_available: self.fts_available,
+            embeddings: self.embeddings.clone(),
+            summarizer: self.summarizer.clone(),
+            content_filter: self.content_filter.clone(),
+            scheduler: self.scheduler.clone(),
+            metrics: self.metrics.clone(),
+            replica_id: self.replica_id.clone(),
+            pending_access: Arc::clone(&self.pending_access),
+            access_flush_in_flight: Arc::clone(&self.access_flush_in_flight),
+            compaction_lock: Arc::clone(&self.compaction_lock),
+            blob_lock: Arc::clone(&self.blob_lock),
         }
     }
 }
@@ -1977,11 +5011,1901 @@ impl Clone for MemorySchemas {
 
 impl Clone for FusionAlgorithms {
     fn clone(&self) -> Self {
+        let mut prediction_engine = PredictionEngine::new();
+        prediction_engine.relevance_scorer = self.relevance_scorer.clone();
+
         Self {
-            relevance_scorer: RelevanceScorer::new(),
+            relevance_scorer: self.relevance_scorer.clone(),
             pattern_detector: PatternDetector::new(),
             anomaly_detector: AnomalyDetector::new(),
-            prediction_engine: PredictionEngine::new(),
+            prediction_engine,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn test_memory_manager(data_dir: &tempfile::TempDir, config: MemoryConfig) -> MemoryManager {
+        let dir = data_dir.path().to_str().unwrap();
+        let security_manager = SecurityManager::new(dir, crate::kernel::SecurityConfig::default()).await.unwrap();
+        security_manager.initialize().await.unwrap();
+        MemoryManager::new(dir, config, security_manager).await.unwrap()
+    }
+
+    fn sync_config(server: &MockServer, conflict_strategy: ConflictStrategy) -> MemoryConfig {
+        MemoryConfig {
+            encryption_enabled: false,
+            cloud_sync: CloudSyncConfig {
+                enabled: true,
+                endpoint: Some(server.uri()),
+                api_key: Some("test-key".to_string()),
+                interval_minutes: 30,
+                conflict_strategy,
+            },
+            ..MemoryConfig::default()
+        }
+    }
+
+    async fn mount_remote_changes(server: &MockServer, records: Vec<SyncEnvelope>) {
+        Mock::given(method("GET"))
+            .and(path("/memories/changes"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(SyncBatch { records }))
+            .mount(server)
+            .await;
+    }
+
+    async fn mount_push(server: &MockServer, expected_calls: u64) {
+        Mock::given(method("POST"))
+            .and(path("/memories"))
+            .and(header("authorization", "Bearer test-key"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(expected_calls)
+            .mount(server)
+            .await;
+    }
+
+    fn test_memory(content: &str, memory_type: MemoryType, created_at: chrono::DateTime<chrono::Utc>) -> MemoryItem {
+        MemoryItem {
+            id: uuid::Uuid::new_v4().to_string(),
+            content: content.to_string(),
+            content_type: ContentType::Text,
+            memory_type,
+            importance: Importance::Medium,
+            tags: Vec::new(),
+            metadata: serde_json::json!({}),
+            created_at,
+            last_accessed: created_at,
+            access_count: 0,
+            encrypted: false,
+            version: VersionVector::default(),
+            binary: None,
+        }
+    }
+
+    #[test]
+    fn test_relevance_is_monotonic_in_each_component() {
+        let scorer = RelevanceScorer::new();
+        let now = chrono::Utc::now();
+        let mut context = ContextState::default();
+        context.current_task = Some("quarterly report".to_string());
+
+        let base = test_memory("notes from lunch", MemoryType::MediumTerm, now - chrono::Duration::hours(5));
+        let score = |memory: &MemoryItem| scorer.calculate_relevance_at(memory, &context, now);
+
+        let mut recent = base.clone();
+        recent.last_accessed = now - chrono::Duration::minutes(10);
+        assert!(score(&recent) > score(&base));
+
+        let mut frequent = base.clone();
+        frequent.access_count = 20;
+        assert!(score(&frequent) > score(&base));
+
+        let mut contextual = base.clone();
+        contextual.content = "draft of the quarterly report".to_string();
+        assert!(score(&contextual) > score(&base));
+    }
+
+    #[test]
+    fn test_relevance_is_bounded_with_unnormalized_weights() {
+        let scorer = RelevanceScorer::from_config(&RelevanceConfig {
+            time_decay_per_hour: 0.1,
+            recency_weight: 5.0,
+            frequency_weight: 5.0,
+            context_weight: 5.0,
+        });
+        let now = chrono::Utc::now();
+        let mut context = ContextState::default();
+        context.current_task = Some("report".to_string());
+
+        let mut best = test_memory("report", MemoryType::LongTerm, now);
+        best.access_count = u32::MAX;
+        let worst = test_memory("unrelated", MemoryType::LongTerm, now - chrono::Duration::days(3650));
+
+        let high = scorer.calculate_relevance_at(&best, &context, now);
+        let low = scorer.calculate_relevance_at(&worst, &context, now);
+        assert!((0.0..=1.0).contains(&high) && high > 0.6, "{}", high);
+        assert!((0.0..=1.0).contains(&low) && low < 0.01, "{}", low);
+    }
+
+    #[tokio::test]
+    async fn test_background_pruning_keeps_permanent_memories() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            retention_days: 7,
+            encryption_enabled: false,
+            prune_interval_seconds: 1,
+            ..MemoryConfig::default()
+        };
+
+        // Pretend a month has passed so everything stored now is past retention
+        let now = chrono::Utc::now();
+        let later = now + chrono::Duration::days(30);
+        let manager = test_memory_manager(&data_dir, config).await.with_clock(Arc::new(move || later));
+
+        let stale_id = manager.store_memory(test_memory("stale", MemoryType::MediumTerm, now)).await.unwrap();
+        let permanent_id = manager.store_memory(test_memory("keep", MemoryType::Permanent, now)).await.unwrap();
+        let fresh_id = manager
+            .store_memory(test_memory("fresh", MemoryType::LongTerm, later - chrono::Duration::days(1)))
+            .await
+            .unwrap();
+
+        manager.initialize().await.unwrap();
+
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            while manager.get_memory(&stale_id).await.unwrap().is_some() {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("stale memory was never pruned");
+
+        assert!(manager.get_memory(&permanent_id).await.unwrap().is_some());
+        assert!(manager.get_memory(&fresh_id).await.unwrap().is_some());
+    }
+
+    struct StubSummarizer(std::sync::Mutex<Vec<String>>);
+
+    #[async_trait::async_trait]
+    impl Summarizer for StubSummarizer {
+        async fn summarize(&self, text: &str) -> MisaResult<String> {
+            self.0.lock().unwrap().push(text.to_string());
+            Ok("Planned the launch and booked the venue".to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compact_collapses_old_memories_into_one_summary() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        let summarizer = Arc::new(StubSummarizer(std::sync::Mutex::new(Vec::new())));
+        let manager = test_memory_manager(&data_dir, config).await.with_summarizer(summarizer.clone());
+
+        let day = (chrono::Utc::now() - chrono::Duration::days(3))
+            .date_naive()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+            .and_utc();
+        let contents = ["Drafted launch plan", "Called the venue", "Booked the venue", "Sent launch invites"];
+        for (i, content) in contents.iter().enumerate() {
+            let created_at = day + chrono::Duration::minutes(i as i64);
+            manager.store_memory(test_memory(content, MemoryType::ShortTerm, created_at)).await.unwrap();
+        }
+        let recent_id = manager
+            .store_memory(test_memory("Just now", MemoryType::ShortTerm, chrono::Utc::now()))
+            .await
+            .unwrap();
+
+        let report = manager.compact().await.unwrap();
+        assert_eq!(report.summaries_created, 1);
+        assert_eq!(report.memories_deleted, contents.len());
+
+        let mut query = SearchQuery::new();
+        query.memory_type = Some(MemoryType::MediumTerm);
+        let summaries = manager.search_memories(&query).await.unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].content, "Planned the launch and booked the venue");
+        assert_eq!(summaries[0].metadata["compacted_from"].as_array().unwrap().len(), contents.len());
+        assert!(summarizer.0.lock().unwrap()[0].contains("- Booked the venue"));
+        assert!(manager.get_memory(&recent_id).await.unwrap().is_some());
+
+        // A second pass finds nothing left to compact
+        let report = manager.compact().await.unwrap();
+        assert_eq!(report, CompactionReport::default());
+        assert_eq!(summarizer.0.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_pruning_keeps_critical_memories_past_base_cutoff() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await;
+
+        // Short-term memories keep for two days; three is past that but within 4x for critical ones
+        let three_days_ago = chrono::Utc::now() - chrono::Duration::days(3);
+        let mut low = test_memory("Parked on level 3", MemoryType::ShortTerm, three_days_ago);
+        low.importance = Importance::Low;
+        let mut critical = test_memory("Passport renewal due", MemoryType::ShortTerm, three_days_ago);
+        critical.importance = Importance::Critical;
+        let low_id = manager.store_memory(low).await.unwrap();
+        let critical_id = manager.store_memory(critical).await.unwrap();
+
+        let report = manager.prune_memories().await.unwrap();
+
+        assert_eq!(report, PruneReport { low: 1, ..PruneReport::default() });
+        assert!(manager.get_memory(&low_id).await.unwrap().is_none());
+        assert!(manager.get_memory(&critical_id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_cursor_paging_survives_concurrent_inserts() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await;
+
+        let base = chrono::Utc::now() - chrono::Duration::hours(1);
+        let mut expected = Vec::new();
+        for minutes in [1, 2, 2, 3, 4] {
+            // Two memories share a timestamp so the id tie-break is exercised
+            let memory = test_memory("entry", MemoryType::LongTerm, base + chrono::Duration::minutes(minutes));
+            expected.push(manager.store_memory(memory).await.unwrap());
         }
+
+        let mut query = SearchQuery::new();
+        query.sort_by = SortField::CreatedAt;
+        query.limit = Some(2);
+
+        let mut seen = Vec::new();
+        let mut page = manager.search_memories_page(&query).await.unwrap();
+        loop {
+            seen.extend(page.memories.iter().map(|memory| memory.id.clone()));
+
+            // New rows land at the front and would shift an offset-based page
+            manager
+                .store_memory(test_memory("newer", MemoryType::LongTerm, chrono::Utc::now()))
+                .await
+                .unwrap();
+
+            match page.next_cursor {
+                Some(cursor) => {
+                    page = manager.search_memories_page(&query.clone().after(&cursor).unwrap()).await.unwrap();
+                }
+                None => break,
+            }
+        }
+
+        let mut unique = seen.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), seen.len(), "a row was repeated");
+        expected.sort();
+        assert_eq!(unique, expected);
+        assert!(SearchQuery::new().after("not a cursor").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cursor_is_rejected_under_a_different_sort() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await;
+        let base = chrono::Utc::now() - chrono::Duration::hours(1);
+        for minutes in 0..3 {
+            let memory = test_memory("entry", MemoryType::LongTerm, base + chrono::Duration::minutes(minutes));
+            manager.store_memory(memory).await.unwrap();
+        }
+
+        let mut query = SearchQuery::new();
+        query.sort_by = SortField::CreatedAt;
+        query.limit = Some(1);
+        let cursor = manager.search_memories_page(&query).await.unwrap().next_cursor.unwrap();
+
+        let mut by_access_count = query.clone().after(&cursor).unwrap();
+        by_access_count.sort_by = SortField::AccessCount;
+        let result = manager.search_memories_page(&by_access_count).await;
+        assert!(matches!(result, Err(MisaError::Validation(_))));
+
+        let mut ascending = query.clone().after(&cursor).unwrap();
+        ascending.sort_order = SortOrder::Asc;
+        assert!(matches!(manager.search_memories_page(&ascending).await, Err(MisaError::Validation(_))));
+
+        assert_eq!(manager.search_memories_page(&query.after(&cursor).unwrap()).await.unwrap().memories.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_store_rejects_content_over_limit() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            max_memory_content_bytes: 16,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await;
+        let now = chrono::Utc::now();
+
+        let at_limit = test_memory(&"a".repeat(16), MemoryType::LongTerm, now);
+        let at_limit_id = manager.store_memory(at_limit).await.unwrap();
+        assert!(manager.get_memory(&at_limit_id).await.unwrap().is_some());
+
+        let over_limit = test_memory(&"a".repeat(17), MemoryType::LongTerm, now);
+        let result = manager.store_memory(over_limit).await;
+        assert!(matches!(result, Err(MisaError::Memory(_))));
+    }
+
+    #[tokio::test]
+    async fn test_store_requires_media_references() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await;
+        let now = chrono::Utc::now();
+
+        let mut inline = test_memory("iVBORw0KGgoAAAANSUhEUgAAAAEAAAAB", MemoryType::LongTerm, now);
+        inline.content_type = ContentType::Image;
+        assert!(matches!(manager.store_memory(inline).await, Err(MisaError::Memory(_))));
+
+        let mut data_uri = test_memory("data:image/png;base64,iVBORw0KGgo=", MemoryType::LongTerm, now);
+        data_uri.content_type = ContentType::Image;
+        assert!(matches!(manager.store_memory(data_uri).await, Err(MisaError::Memory(_))));
+
+        let mut path = test_memory("/home/user/Pictures/receipt.png", MemoryType::LongTerm, now);
+        path.content_type = ContentType::Image;
+        manager.store_memory(path).await.unwrap();
+
+        let mut uri = test_memory("https://example.com/clips/demo.mp4", MemoryType::LongTerm, now);
+        uri.content_type = ContentType::Video;
+        manager.store_memory(uri).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_update_memory_preserves_history() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await;
+
+        let created_at = chrono::Utc::now() - chrono::Duration::days(2);
+        let memory_id = manager
+            .store_memory(test_memory("Dentist on Tuesday", MemoryType::LongTerm, created_at))
+            .await
+            .unwrap();
+        manager.get_memory(&memory_id).await.unwrap();
+        manager.get_memory(&memory_id).await.unwrap();
+
+        let patch = MemoryPatch {
+            tags: Some(vec!["health".to_string(), "appointment".to_string()]),
+            ..MemoryPatch::default()
+        };
+        manager.update_memory(&memory_id, patch).await.unwrap();
+
+        let row = sqlx::query("SELECT access_count, created_at, updated_at FROM memories WHERE id = ?")
+            .bind(&memory_id)
+            .fetch_one(&manager.db_pool)
+            .await
+            .unwrap();
+        assert_eq!(row.get::<i64, _>("access_count"), 2);
+        assert_eq!(row.get::<chrono::DateTime<chrono::Utc>, _>("created_at"), created_at);
+        assert!(row.get::<chrono::DateTime<chrono::Utc>, _>("updated_at") > created_at);
+
+        let memory = manager.get_memory(&memory_id).await.unwrap().unwrap();
+        assert_eq!(memory.tags, vec!["health".to_string(), "appointment".to_string()]);
+        assert_eq!(memory.content, "Dentist on Tuesday");
+
+        let missing = manager.update_memory("missing", MemoryPatch::default()).await;
+        assert!(matches!(missing, Err(MisaError::Memory(_))));
+    }
+
+    fn context_source(source_id: &str, source_type: ContextSourceType, priority: u8) -> ContextSource {
+        ContextSource {
+            source_id: source_id.to_string(),
+            source_type,
+            name: source_id.to_string(),
+            enabled: true,
+            priority,
+            last_data: None,
+            last_updated: chrono::Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_system_source_updates_context() {
+        let engine = ContextEngine::new().await.unwrap();
+        engine.initialize().await.unwrap();
+
+        let source = context_source("system", ContextSourceType::System, 10);
+        engine
+            .update_context(source, serde_json::json!({ "cpu_usage_percent": 42.5, "memory_usage_mb": 8192 }))
+            .await
+            .unwrap();
+
+        let context = engine.get_current_context().await.unwrap();
+        assert_eq!(context.system_state.cpu_usage_percent, 42.5);
+        assert_eq!(context.system_state.memory_usage_mb, 8192);
+        // Fields the source did not report are left alone
+        assert!(!context.system_state.network_status.connected);
+    }
+
+    #[tokio::test]
+    async fn test_lower_priority_source_does_not_override() {
+        let engine = ContextEngine::new().await.unwrap();
+        let gps = context_source("gps", ContextSourceType::Location, 10);
+        let ip_lookup = context_source("ip_lookup", ContextSourceType::Location, 1);
+
+        engine
+            .update_context(gps, serde_json::json!({ "latitude": 51.5, "longitude": -0.12, "accuracy": 5.0, "address": null }))
+            .await
+            .unwrap();
+        engine
+            .update_context(ip_lookup, serde_json::json!({ "latitude": 48.8, "longitude": 2.35, "accuracy": 5000.0, "address": null }))
+            .await
+            .unwrap();
+
+        let location = engine.get_current_context().await.unwrap().environment.location.unwrap();
+        assert_eq!(location.latitude, 51.5);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_custom_calendar_source_feeds_context() {
+        let engine = ContextEngine::new().await.unwrap();
+        engine.initialize().await.unwrap();
+        engine
+            .register_source(context_source("work_calendar", ContextSourceType::Calendar, 5))
+            .await
+            .unwrap();
+
+        engine
+            .push(
+                "work_calendar",
+                serde_json::json!({ "event_id": "standup", "title": "Standup", "start": "2024-05-06T09:00:00Z" }),
+            )
+            .await
+            .unwrap();
+
+        let context = engine.get_current_context().await.unwrap();
+        assert_eq!(context.calendar_events.len(), 1);
+        assert_eq!(context.calendar_events[0].title, "Standup");
+        assert!(context.calendar_events[0].end.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_full_calendar_evicts_past_events_before_upcoming_ones() {
+        let engine = ContextEngine::new().await.unwrap();
+        engine.initialize().await.unwrap();
+        engine
+            .register_source(context_source("work_calendar", ContextSourceType::Calendar, 5))
+            .await
+            .unwrap();
+
+        let now = chrono::Utc::now();
+        for day in 0..MAX_CALENDAR_EVENTS as i64 {
+            let start = now - chrono::Duration::days(day + 1);
+            engine
+                .push(
+                    "work_calendar",
+                    serde_json::json!({ "event_id": format!("past-{}", day), "title": "Done", "start": start }),
+                )
+                .await
+                .unwrap();
+        }
+        let upcoming = now + chrono::Duration::days(1);
+        engine
+            .push(
+                "work_calendar",
+                serde_json::json!({ "event_id": "review", "title": "Review", "start": upcoming }),
+            )
+            .await
+            .unwrap();
+
+        let events = engine.get_current_context().await.unwrap().calendar_events;
+        assert_eq!(events.len(), MAX_CALENDAR_EVENTS);
+        assert!(events.iter().any(|event| event.event_id == "review"));
+        // The oldest finished event made room
+        let oldest = format!("past-{}", MAX_CALENDAR_EVENTS - 1);
+        assert!(events.iter().all(|event| event.event_id != oldest));
+    }
+
+    #[tokio::test]
+    async fn test_push_rejects_mismatched_payload() {
+        let engine = ContextEngine::new().await.unwrap();
+        engine
+            .register_source(context_source("work_calendar", ContextSourceType::Calendar, 5))
+            .await
+            .unwrap();
+
+        let result = engine.push("work_calendar", serde_json::json!({ "latitude": 51.5 })).await;
+        assert!(matches!(result, Err(MisaError::Validation(_))));
+        assert!(matches!(
+            engine.push("unknown", serde_json::json!({})).await,
+            Err(MisaError::NotFound(_))
+        ));
+        assert!(engine.register_source(context_source("work_calendar", ContextSourceType::Calendar, 1)).await.is_err());
+        assert!(engine.get_current_context().await.unwrap().calendar_events.is_empty());
+    }
+
+    #[derive(Debug, Clone)]
+    struct PredictionEvent {
+        prediction_type: String,
+        suggestion: String,
+    }
+
+    #[tokio::test]
+    async fn test_prediction_events_for_morning_context() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        let morning = chrono::DateTime::parse_from_rfc3339("2024-03-15T09:00:00Z").unwrap().with_timezone(&chrono::Utc);
+        let manager = test_memory_manager(&data_dir, config).await.with_clock(Arc::new(move || morning));
+
+        let (events, mut receiver) = broadcast::channel(16);
+        let task = manager.start_prediction_events(
+            events,
+            |prediction: &Prediction| PredictionEvent {
+                prediction_type: prediction.prediction_type.clone(),
+                suggestion: prediction.suggestion.clone(),
+            },
+            tokio::time::Duration::from_millis(20),
+        );
+
+        let event = tokio::time::timeout(std::time::Duration::from_secs(5), receiver.recv())
+            .await
+            .expect("no prediction was emitted")
+            .unwrap();
+        assert_eq!(event.prediction_type, "time_based");
+        assert!(event.suggestion.contains("morning"));
+
+        // Later runs produce the same still-valid suggestion, which is debounced
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert!(matches!(receiver.try_recv(), Err(broadcast::error::TryRecvError::Empty)));
+
+        task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_anomaly_scan_reports_volume_spike() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await;
+        let now = chrono::Utc::now();
+
+        // Two memories a day for ten days, then a day with ten times as many
+        for days_ago in 1..=10 {
+            for _ in 0..2 {
+                let created_at = now - chrono::Duration::days(days_ago);
+                manager.store_memory(test_memory("routine note", MemoryType::MediumTerm, created_at)).await.unwrap();
+            }
+        }
+        let spike_day = now - chrono::Duration::days(11);
+        for _ in 0..20 {
+            manager.store_memory(test_memory("burst note", MemoryType::MediumTerm, spike_day)).await.unwrap();
+        }
+
+        let anomalies = manager.run_anomaly_scan().await.unwrap();
+        let spike = anomalies
+            .iter()
+            .find(|anomaly| matches!(anomaly.anomaly_type, AnomalyType::MemoryVolumeSpike))
+            .expect("volume spike not detected");
+
+        assert!(matches!(spike.severity, AnomalySeverity::Medium | AnomalySeverity::High));
+        assert_eq!(spike.affected_memories.len(), 20);
+    }
+
+    #[tokio::test]
+    async fn test_anomaly_alerts_publish_each_anomaly_once() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await;
+        let now = chrono::Utc::now();
+        for days_ago in 1..=10 {
+            for _ in 0..2 {
+                let created_at = now - chrono::Duration::days(days_ago);
+                manager.store_memory(test_memory("routine note", MemoryType::MediumTerm, created_at)).await.unwrap();
+            }
+        }
+        for _ in 0..20 {
+            let created_at = now - chrono::Duration::days(11);
+            manager.store_memory(test_memory("burst note", MemoryType::MediumTerm, created_at)).await.unwrap();
+        }
+
+        let (events, mut receiver) = broadcast::channel(16);
+        manager
+            .start_anomaly_alerts(events, |anomaly: &DetectedAnomaly| anomaly.anomaly_type.clone(), std::time::Duration::from_millis(20))
+            .await
+            .unwrap();
+
+        let mut published = vec![tokio::time::timeout(std::time::Duration::from_secs(5), receiver.recv()).await.unwrap().unwrap()];
+        // Later scans find the same spike and stay quiet about it
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        while let Ok(anomaly_type) = receiver.try_recv() {
+            published.push(anomaly_type);
+        }
+        manager.scheduler.shutdown().await;
+
+        let spikes = published.iter().filter(|anomaly_type| matches!(anomaly_type, AnomalyType::MemoryVolumeSpike)).count();
+        assert_eq!(spikes, 1);
+    }
+
+    #[tokio::test]
+    async fn test_full_text_search_ranks_memories_matching_more_terms() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await;
+        let now = chrono::Utc::now();
+
+        let partial = test_memory("Budget spreadsheet for the offsite", MemoryType::LongTerm, now);
+        let both = test_memory("Quarterly budget review with finance", MemoryType::LongTerm, now);
+        let unrelated = test_memory("Pick up groceries after work", MemoryType::LongTerm, now);
+        for memory in [&partial, &both, &unrelated] {
+            manager.store_memory(memory.clone()).await.unwrap();
+        }
+
+        let mut query = SearchQuery::new();
+        query.text = Some("budget review".to_string());
+        query.full_text = true;
+
+        let results = manager.search_memories(&query).await.unwrap();
+        let ids: Vec<&str> = results.iter().map(|memory| memory.id.as_str()).collect();
+        assert_eq!(ids, vec![both.id.as_str(), partial.id.as_str()]);
+    }
+
+    /// Maps words onto a handful of concepts so paraphrases land close together
+    struct ConceptEmbedder;
+
+    #[async_trait::async_trait]
+    impl Embedder for ConceptEmbedder {
+        async fn embed(&self, text: &str) -> MisaResult<Vec<f32>> {
+            let concepts: [&[&str]; 3] = [
+                &["dentist", "dental", "teeth", "checkup", "cleaning"],
+                &["car", "vehicle", "mechanic", "oil", "tyres"],
+                &["groceries", "milk", "eggs", "shopping", "supermarket"],
+            ];
+            let text = text.to_lowercase();
+            let mut vector: Vec<f32> = concepts
+                .iter()
+                .map(|words| words.iter().filter(|word| text.contains(*word)).count() as f32)
+                .collect();
+            vector.push(0.1); // Keep the vector non-zero
+            Ok(vector)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_semantic_search_finds_paraphrased_memory() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await.with_embedder(Arc::new(ConceptEmbedder));
+        let now = chrono::Utc::now();
+
+        let dentist = test_memory("Dentist appointment for a cleaning on Thursday", MemoryType::LongTerm, now);
+        let car = test_memory("Take the car to the mechanic for an oil change", MemoryType::LongTerm, now);
+        let shopping = test_memory("Buy milk and eggs", MemoryType::LongTerm, now);
+        for memory in [&dentist, &car, &shopping] {
+            manager.store_memory(memory.clone()).await.unwrap();
+        }
+        manager.wait_for_embeddings().await;
+
+        let results = manager.semantic_search("when is my dental checkup?", 2).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].id, dentist.id);
+
+        // Erasing a memory drops its embedding too
+        manager.erase_memory(&dentist.id, false).await.unwrap();
+        let results = manager.semantic_search("when is my dental checkup?", 3).await.unwrap();
+        assert!(results.iter().all(|memory| memory.id != dentist.id));
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_memories_are_not_embedded() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: true,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await.with_embedder(Arc::new(ConceptEmbedder));
+        let now = chrono::Utc::now();
+
+        let first = test_memory("Dentist appointment for a cleaning on Thursday", MemoryType::LongTerm, now);
+        let second = test_memory("Dental checkup and cleaning on Thursday", MemoryType::LongTerm, now);
+        for memory in [&first, &second] {
+            manager.store_memory(memory.clone()).await.unwrap();
+        }
+        manager.wait_for_embeddings().await;
+
+        let embeddings: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM memory_embeddings")
+            .fetch_one(&manager.db_pool)
+            .await
+            .unwrap();
+        assert_eq!(embeddings, 0);
+        assert!(manager.find_duplicates(0.5).await.unwrap().is_empty());
+        assert!(manager.semantic_search("when is my dental checkup?", 2).await.unwrap().is_empty());
+    }
+
+    /// Never finishes an embedding
+    struct StalledEmbedder;
+
+    #[async_trait::async_trait]
+    impl Embedder for StalledEmbedder {
+        async fn embed(&self, _text: &str) -> MisaResult<Vec<f32>> {
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_storing_does_not_wait_for_the_embedding() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await.with_embedder(Arc::new(StalledEmbedder));
+
+        let memory = test_memory("Dentist appointment on Thursday", MemoryType::LongTerm, chrono::Utc::now());
+        let stored = tokio::time::timeout(std::time::Duration::from_secs(5), manager.store_memory(memory)).await;
+        assert!(stored.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_pulled_memories_are_embedded() {
+        let server = MockServer::start().await;
+        mount_push(&server, 0).await;
+
+        let data_dir = tempfile::tempdir().unwrap();
+        let manager = test_memory_manager(&data_dir, sync_config(&server, ConflictStrategy::LastModifiedWins))
+            .await
+            .with_embedder(Arc::new(ConceptEmbedder));
+
+        let remote = test_memory("Dentist appointment for a cleaning on Thursday", MemoryType::LongTerm, chrono::Utc::now());
+        let envelope = CloudSync::seal_envelope(&manager.security_manager, &remote, remote.created_at).await.unwrap();
+        mount_remote_changes(&server, vec![envelope]).await;
+
+        manager.sync_with_cloud().await.unwrap();
+        manager.wait_for_embeddings().await;
+
+        let results = manager.semantic_search("when is my dental checkup?", 1).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, remote.id);
+    }
+
+    #[test]
+    fn test_cosine_similarity() {
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]), Some(1.0));
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]), Some(0.0));
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 0.0]), None);
+        assert_eq!(decode_vector(&encode_vector(&[0.5, -1.25])), vec![0.5, -1.25]);
+    }
+
+    #[tokio::test]
+    async fn test_sync_pushes_local_changes_encrypted() {
+        let server = MockServer::start().await;
+        mount_remote_changes(&server, Vec::new()).await;
+        mount_push(&server, 1).await;
+
+        let data_dir = tempfile::tempdir().unwrap();
+        let manager = test_memory_manager(&data_dir, sync_config(&server, ConflictStrategy::LastModifiedWins)).await;
+        let memory_id = manager
+            .store_memory(test_memory("launch codes are in the blue folder", MemoryType::LongTerm, chrono::Utc::now()))
+            .await
+            .unwrap();
+
+        let report = manager.sync_with_cloud().await.unwrap();
+        assert_eq!(report.pushed, 1);
+        assert_eq!(report.pulled, 0);
+
+        // Nothing changed since, so the second sync has nothing to push
+        let report = manager.sync_with_cloud().await.unwrap();
+        assert_eq!(report.pushed, 0);
+
+        let requests = server.received_requests().await.unwrap();
+        let push = requests.iter().find(|request| request.method.to_string() == "POST").unwrap();
+        let body = String::from_utf8_lossy(&push.body);
+        assert!(body.contains(&memory_id));
+        assert!(!body.contains("launch codes"));
+    }
+
+    #[tokio::test]
+    async fn test_sync_pulls_remote_changes() {
+        let server = MockServer::start().await;
+        mount_push(&server, 0).await;
+
+        let data_dir = tempfile::tempdir().unwrap();
+        let manager = test_memory_manager(&data_dir, sync_config(&server, ConflictStrategy::LastModifiedWins)).await;
+
+        let remote = test_memory("written on another device", MemoryType::LongTerm, chrono::Utc::now());
+        let envelope = CloudSync::seal_envelope(&manager.security_manager, &remote, remote.created_at).await.unwrap();
+        mount_remote_changes(&server, vec![envelope]).await;
+
+        let report = manager.sync_with_cloud().await.unwrap();
+        assert_eq!(report.pulled, 1);
+        assert_eq!(report.pushed, 0);
+
+        let stored = manager.get_memory(&remote.id).await.unwrap().unwrap();
+        assert_eq!(stored.content, "written on another device");
+    }
+
+    /// Scrubs "secret" from every source and withholds anything from "vault"
+    struct ScrubbingFilter;
+
+    #[async_trait::async_trait]
+    impl ContentFilter for ScrubbingFilter {
+        async fn filter_content(&self, source: &str, content: &str) -> MisaResult<Option<String>> {
+            Ok((source != "vault").then(|| content.replace("secret", "[REDACTED]")))
+        }
+    }
+
+    fn from_source(mut memory: MemoryItem, source: &str) -> MemoryItem {
+        memory.metadata = serde_json::json!({ "source": source });
+        memory
+    }
+
+    #[tokio::test]
+    async fn test_updated_content_is_filtered() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let manager = test_memory_manager(&data_dir, MemoryConfig::default()).await.with_content_filter(Arc::new(ScrubbingFilter));
+        let memory_id = manager
+            .store_memory(from_source(test_memory("nothing to hide", MemoryType::LongTerm, chrono::Utc::now()), "notes"))
+            .await
+            .unwrap();
+
+        let patch = MemoryPatch { content: Some("the secret is out".to_string()), ..MemoryPatch::default() };
+        let updated = manager.update_memory(&memory_id, patch).await.unwrap();
+        assert_eq!(updated.content, "the [REDACTED] is out");
+
+        let moved = MemoryPatch { metadata: Some(serde_json::json!({ "source": "vault" })), ..MemoryPatch::default() };
+        assert!(matches!(manager.update_memory(&memory_id, moved).await, Err(MisaError::Privacy(_))));
+        assert_eq!(manager.get_memory(&memory_id).await.unwrap().unwrap().content, "the [REDACTED] is out");
+    }
+
+    #[tokio::test]
+    async fn test_pulled_memories_are_filtered() {
+        let server = MockServer::start().await;
+        mount_push(&server, 0).await;
+
+        let data_dir = tempfile::tempdir().unwrap();
+        let manager = test_memory_manager(&data_dir, sync_config(&server, ConflictStrategy::LastModifiedWins))
+            .await
+            .with_content_filter(Arc::new(ScrubbingFilter));
+
+        let now = chrono::Utc::now();
+        let scrubbed = from_source(test_memory("the secret is out", MemoryType::LongTerm, now), "notes");
+        let withheld = from_source(test_memory("vault contents", MemoryType::LongTerm, now), "vault");
+        let mut envelopes = Vec::new();
+        for memory in [&scrubbed, &withheld] {
+            envelopes.push(CloudSync::seal_envelope(&manager.security_manager, memory, now).await.unwrap());
+        }
+        mount_remote_changes(&server, envelopes).await;
+
+        let report = manager.sync_with_cloud().await.unwrap();
+        assert_eq!(report.pulled, 1);
+        assert_eq!(manager.get_memory(&scrubbed.id).await.unwrap().unwrap().content, "the [REDACTED] is out");
+        assert!(manager.get_memory(&withheld.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sync_opens_envelopes_from_a_replica_sharing_the_sync_key() {
+        let server = MockServer::start().await;
+        mount_push(&server, 0).await;
+
+        let data_dir = tempfile::tempdir().unwrap();
+        let manager = test_memory_manager(&data_dir, sync_config(&server, ConflictStrategy::LastModifiedWins)).await;
+
+        // Another device with its own master key, set up with this device's sync key
+        let other_dir = tempfile::tempdir().unwrap();
+        let other = SecurityManager::new(other_dir.path().to_str().unwrap(), crate::kernel::SecurityConfig::default())
+            .await
+            .unwrap();
+        other.initialize().await.unwrap();
+        let sync_key = manager.security_manager.export_stored_key(SYNC_KEY_NAME).await.unwrap();
+        other.import_stored_key(SYNC_KEY_NAME, &sync_key).await.unwrap();
+
+        let mut remote = test_memory("encrypted on another device", MemoryType::LongTerm, chrono::Utc::now());
+        remote.encrypted = true;
+        let envelope = CloudSync::seal_envelope(&other, &remote, remote.created_at).await.unwrap();
+        mount_remote_changes(&server, vec![envelope]).await;
+
+        let report = manager.sync_with_cloud().await.unwrap();
+        assert_eq!(report.pulled, 1);
+
+        let stored = manager.get_memory(&remote.id).await.unwrap().unwrap();
+        assert_eq!(stored.content, "encrypted on another device");
+        assert!(stored.encrypted);
+        let encrypted_data: Option<Vec<u8>> = sqlx::query_scalar("SELECT encrypted_data FROM memories WHERE id = ?")
+            .bind(&remote.id)
+            .fetch_one(&manager.db_pool)
+            .await
+            .unwrap();
+        assert!(encrypted_data.is_some());
+    }
+
+    /// Edit a memory locally, then sync against a newer remote edit of it
+    async fn sync_conflicting_edit(strategy: ConflictStrategy, expected_pushes: u64) -> (MemoryItem, SyncReport) {
+        let server = MockServer::start().await;
+        mount_push(&server, expected_pushes).await;
+
+        let data_dir = tempfile::tempdir().unwrap();
+        let manager = test_memory_manager(&data_dir, sync_config(&server, strategy)).await;
+
+        let local = test_memory("local edit", MemoryType::LongTerm, chrono::Utc::now());
+        manager.store_memory(local.clone()).await.unwrap();
+
+        let mut remote = local.clone();
+        remote.content = "remote edit".to_string();
+        let remote_updated_at = chrono::Utc::now() + chrono::Duration::hours(1);
+        let envelope = CloudSync::seal_envelope(&manager.security_manager, &remote, remote_updated_at).await.unwrap();
+        mount_remote_changes(&server, vec![envelope]).await;
+
+        let report = manager.sync_with_cloud().await.unwrap();
+        let stored = manager.get_memory(&local.id).await.unwrap().unwrap();
+        (stored, report)
+    }
+
+    #[tokio::test]
+    async fn test_sync_conflict_last_modified_wins() {
+        let (stored, report) = sync_conflicting_edit(ConflictStrategy::LastModifiedWins, 0).await;
+
+        assert_eq!(report.conflicts, 1);
+        assert_eq!(stored.content, "remote edit");
+    }
+
+    #[tokio::test]
+    async fn test_sync_conflict_local_wins() {
+        let (stored, report) = sync_conflicting_edit(ConflictStrategy::LocalWins, 1).await;
+
+        assert_eq!(report.conflicts, 1);
+        assert_eq!(report.pushed, 1);
+        assert_eq!(stored.content, "local edit");
+    }
+
+    /// Edit a memory locally, then sync a remote edit whose version is derived from the local one
+    async fn sync_versioned_edit(
+        expected_pushes: u64,
+        remote_version: impl FnOnce(&VersionVector) -> VersionVector,
+    ) -> (MemoryItem, SyncReport) {
+        let server = MockServer::start().await;
+        mount_push(&server, expected_pushes).await;
+
+        let data_dir = tempfile::tempdir().unwrap();
+        let manager = test_memory_manager(&data_dir, sync_config(&server, ConflictStrategy::ManualResolution)).await;
+
+        let memory_id = manager
+            .store_memory(test_memory("local edit", MemoryType::LongTerm, chrono::Utc::now()))
+            .await
+            .unwrap();
+        let local = manager.get_memory(&memory_id).await.unwrap().unwrap();
+        assert_eq!(local.version.get(&manager.replica_id), 1);
+
+        let mut remote = local.clone();
+        remote.content = "remote edit".to_string();
+        remote.version = remote_version(&local.version);
+        let envelope = CloudSync::seal_envelope(&manager.security_manager, &remote, chrono::Utc::now()).await.unwrap();
+        mount_remote_changes(&server, vec![envelope]).await;
+
+        let report = manager.sync_with_cloud().await.unwrap();
+        let stored = manager.get_memory(&memory_id).await.unwrap().unwrap();
+        (stored, report)
+    }
+
+    #[tokio::test]
+    async fn test_sync_applies_causal_remote_update() {
+        // The other device saw the local edit before making its own
+        let (stored, report) = sync_versioned_edit(0, |local| {
+            let mut version = local.clone();
+            version.increment("other-device");
+            version
+        })
+        .await;
+
+        assert!(report.unresolved.is_empty());
+        assert_eq!(stored.content, "remote edit");
+        assert_eq!(stored.version.get("other-device"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_sync_flags_concurrent_edit() {
+        // The other device edited without having seen the local edit
+        let (stored, report) = sync_versioned_edit(0, |_| {
+            let mut version = VersionVector::default();
+            version.increment("other-device");
+            version
+        })
+        .await;
+
+        assert_eq!(report.unresolved, vec![stored.id.clone()]);
+        assert_eq!(report.pushed, 0);
+        assert_eq!(stored.content, "local edit");
+    }
+
+    #[tokio::test]
+    async fn test_sync_keeps_pulling_unresolved_edit() {
+        let server = MockServer::start().await;
+        mount_push(&server, 0).await;
+
+        let data_dir = tempfile::tempdir().unwrap();
+        let manager = test_memory_manager(&data_dir, sync_config(&server, ConflictStrategy::ManualResolution)).await;
+
+        let memory_id = manager
+            .store_memory(test_memory("local edit", MemoryType::LongTerm, chrono::Utc::now()))
+            .await
+            .unwrap();
+        let mut remote = manager.get_memory(&memory_id).await.unwrap().unwrap();
+        remote.content = "remote edit".to_string();
+        remote.version = VersionVector::default();
+        remote.version.increment("other-device");
+        let remote_updated_at = chrono::Utc::now() - chrono::Duration::minutes(5);
+        let envelope = CloudSync::seal_envelope(&manager.security_manager, &remote, remote_updated_at).await.unwrap();
+        mount_remote_changes(&server, vec![envelope]).await;
+
+        let report = manager.sync_with_cloud().await.unwrap();
+        assert_eq!(report.unresolved, vec![memory_id.clone()]);
+
+        // The remote edit stays ahead of the watermark until someone resolves it
+        assert!(*manager.cloud_sync.last_sync.read().await < remote_updated_at);
+        let report = manager.sync_with_cloud().await.unwrap();
+        assert_eq!(report.unresolved, vec![memory_id]);
+    }
+
+    #[tokio::test]
+    async fn test_resolved_conflict_is_pushed_and_cleared() {
+        let server = MockServer::start().await;
+        mount_push(&server, 1).await;
+
+        let data_dir = tempfile::tempdir().unwrap();
+        let manager = test_memory_manager(&data_dir, sync_config(&server, ConflictStrategy::ManualResolution)).await;
+
+        let memory_id = manager
+            .store_memory(test_memory("local edit", MemoryType::LongTerm, chrono::Utc::now()))
+            .await
+            .unwrap();
+        let mut remote = manager.get_memory(&memory_id).await.unwrap().unwrap();
+        remote.content = "remote edit".to_string();
+        remote.version = VersionVector::default();
+        remote.version.increment("other-device");
+        let envelope = CloudSync::seal_envelope(&manager.security_manager, &remote, chrono::Utc::now()).await.unwrap();
+        mount_remote_changes(&server, vec![envelope]).await;
+
+        manager.sync_with_cloud().await.unwrap();
+        let conflicts = manager.list_sync_conflicts().await.unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].local.content, "local edit");
+        assert_eq!(conflicts[0].remote.content, "remote edit");
+
+        let resolved = manager.resolve_sync_conflict(&memory_id, ConflictChoice::TakeRemote).await.unwrap();
+        assert_eq!(resolved.content, "remote edit");
+        assert_eq!(resolved.version.compare(&remote.version), VersionOrder::After);
+        assert!(manager.list_sync_conflicts().await.unwrap().is_empty());
+        assert!(manager.resolve_sync_conflict(&memory_id, ConflictChoice::KeepLocal).await.is_err());
+
+        // The remote edit is pulled again, found to be superseded, and the resolution is pushed
+        let report = manager.sync_with_cloud().await.unwrap();
+        assert!(report.unresolved.is_empty());
+        assert_eq!(report.pushed, 1);
+        assert_eq!(manager.get_memory(&memory_id).await.unwrap().unwrap().content, "remote edit");
+    }
+
+    #[test]
+    fn test_version_vector_ordering() {
+        let mut a = VersionVector::default();
+        a.increment("laptop");
+        let mut b = a.clone();
+        b.increment("phone");
+        let mut c = a.clone();
+        c.increment("laptop");
+
+        assert_eq!(a.compare(&a), VersionOrder::Equal);
+        assert_eq!(a.compare(&b), VersionOrder::Before);
+        assert_eq!(b.compare(&a), VersionOrder::After);
+        assert_eq!(b.compare(&c), VersionOrder::Concurrent);
+        assert_eq!(b.merged(&c).compare(&b), VersionOrder::After);
+    }
+
+    #[tokio::test]
+    async fn test_context_snapshot_round_trip() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let manager = test_memory_manager(&data_dir, MemoryConfig::default()).await;
+
+        let now = chrono::Utc::now();
+        manager.context_engine.active_context.write().await.current_task = Some("quarterly report".to_string());
+        manager
+            .context_engine
+            .add_to_short_term_memory(test_memory("draft intro paragraph", MemoryType::ShortTerm, now))
+            .await
+            .unwrap();
+        let exported = manager.get_current_context().await.unwrap();
+
+        let snapshot = manager.export_context_snapshot().await.unwrap();
+        assert!(!String::from_utf8_lossy(&snapshot).contains("quarterly report"));
+
+        manager.context_engine.restore(ContextState::default()).await;
+        manager.import_context_snapshot(&snapshot, false).await.unwrap();
+
+        let restored = manager.get_current_context().await.unwrap();
+        assert_eq!(restored.session_id, exported.session_id);
+        assert_eq!(restored.current_task.as_deref(), Some("quarterly report"));
+        assert_eq!(restored.short_term_memory.len(), 1);
+        assert_eq!(restored.short_term_memory[0].id, exported.short_term_memory[0].id);
+        assert_eq!(restored.short_term_memory[0].content, "draft intro paragraph");
+
+        assert!(matches!(
+            manager.import_context_snapshot(b"not a snapshot", false).await,
+            Err(MisaError::Validation(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_context_snapshot_moves_to_another_machine_with_the_snapshot_key() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let manager = test_memory_manager(&data_dir, MemoryConfig::default()).await;
+        manager.context_engine.active_context.write().await.current_task = Some("quarterly report".to_string());
+        let snapshot = manager.export_context_snapshot().await.unwrap();
+
+        // A second machine has its own master key and can't open the snapshot yet
+        let other_dir = tempfile::tempdir().unwrap();
+        let other = test_memory_manager(&other_dir, MemoryConfig::default()).await;
+        assert!(matches!(
+            other.import_context_snapshot(&snapshot, false).await,
+            Err(MisaError::Encryption(_))
+        ));
+
+        let snapshot_key = manager.security_manager.export_stored_key(CONTEXT_SNAPSHOT_KEY_NAME).await.unwrap();
+        other.security_manager.import_stored_key(CONTEXT_SNAPSHOT_KEY_NAME, &snapshot_key).await.unwrap();
+        other.import_context_snapshot(&snapshot, false).await.unwrap();
+        assert_eq!(
+            other.get_current_context().await.unwrap().current_task.as_deref(),
+            Some("quarterly report")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_context_snapshot_from_other_user_needs_force() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let manager = test_memory_manager(&data_dir, MemoryConfig::default()).await;
+
+        manager.context_engine.active_context.write().await.user_id = "alex".to_string();
+        let snapshot = manager.export_context_snapshot().await.unwrap();
+        manager.context_engine.restore(ContextState::default()).await;
+
+        assert!(matches!(
+            manager.import_context_snapshot(&snapshot, false).await,
+            Err(MisaError::Permission(_))
+        ));
+        assert_eq!(manager.get_current_context().await.unwrap().user_id, "default");
+
+        manager.import_context_snapshot(&snapshot, true).await.unwrap();
+        assert_eq!(manager.get_current_context().await.unwrap().user_id, "alex");
+    }
+
+    /// Whether `needle` appears anywhere in the database file or its WAL
+    fn database_contains(data_dir: &tempfile::TempDir, needle: &[u8]) -> bool {
+        let db_path = data_dir.path().join(MemoryConfig::default().local_db_path);
+        let mut wal_path = db_path.clone().into_os_string();
+        wal_path.push("-wal");
+
+        [db_path, wal_path.into()].iter().any(|path| {
+            std::fs::read(path).map_or(false, |bytes| bytes.windows(needle.len()).any(|window| window == needle))
+        })
+    }
+
+    #[tokio::test]
+    async fn test_secure_erase_leaves_no_recoverable_content() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await;
+
+        let mut conn = MemoryManager::acquire_secure_delete(&manager.db_pool).await.unwrap();
+        let enabled: i64 = sqlx::query_scalar("PRAGMA secure_delete").fetch_one(&mut *conn).await.unwrap();
+        assert_eq!(enabled, 1);
+        drop(conn);
+
+        // Whatever happened on it, the connection rejoins the pool with secure_delete off
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let mut pooled = Vec::new();
+        for _ in 0..manager.db_pool.size() {
+            pooled.push(manager.db_pool.acquire().await.unwrap());
+        }
+        for conn in &mut pooled {
+            let enabled: i64 = sqlx::query_scalar("PRAGMA secure_delete").fetch_one(&mut **conn).await.unwrap();
+            assert_eq!(enabled, 0);
+        }
+        drop(pooled);
+
+        let now = chrono::Utc::now();
+        let secret = "bank pin qz7vx1kp";
+        let plain = "grocery list wm3tj8rd";
+        let secret_id = manager.store_memory(test_memory(secret, MemoryType::LongTerm, now)).await.unwrap();
+        let plain_id = manager.store_memory(test_memory(plain, MemoryType::LongTerm, now)).await.unwrap();
+        assert!(database_contains(&data_dir, b"qz7vx1kp"));
+
+        assert!(manager.erase_memory(&secret_id, true).await.unwrap());
+        assert!(manager.get_memory(&secret_id).await.unwrap().is_none());
+        assert!(!database_contains(&data_dir, b"qz7vx1kp"));
+
+        // The plain path makes no such promise, but must still remove the row
+        assert!(manager.erase_memory(&plain_id, false).await.unwrap());
+        assert!(manager.get_memory(&plain_id).await.unwrap().is_none());
+        assert!(!manager.erase_memory(&secret_id, true).await.unwrap());
+    }
+
+    #[test]
+    fn test_time_of_day_follows_configured_timezone() {
+        use chrono::TimeZone;
+        // Friday 23:00 UTC
+        let now = chrono::Utc.with_ymd_and_hms(2024, 3, 15, 23, 0, 0).unwrap();
+
+        let new_york = EnvironmentContext::at(now, "America/New_York");
+        assert!(matches!(new_york.time_of_day, TimeOfDay::Evening));
+        assert!(matches!(new_york.day_of_week, DayOfWeek::Friday));
+
+        let tokyo = EnvironmentContext::at(now, "Asia/Tokyo");
+        assert!(matches!(tokyo.time_of_day, TimeOfDay::Morning));
+        assert!(matches!(tokyo.day_of_week, DayOfWeek::Saturday));
+
+        let utc = EnvironmentContext::at(now, "UTC");
+        assert!(matches!(utc.time_of_day, TimeOfDay::Night));
+    }
+
+    #[tokio::test]
+    async fn test_context_time_follows_updated_timezone() {
+        use chrono::TimeZone;
+        let data_dir = tempfile::tempdir().unwrap();
+        // Friday 23:00 UTC
+        let now = chrono::Utc.with_ymd_and_hms(2024, 3, 15, 23, 0, 0).unwrap();
+        let manager = test_memory_manager(&data_dir, MemoryConfig::default())
+            .await
+            .with_clock(Arc::new(move || now));
+
+        let context = manager.get_current_context().await.unwrap();
+        assert!(matches!(context.environment.time_of_day, TimeOfDay::Night));
+
+        manager
+            .update_user_preferences(UserPreferences {
+                timezone: "Asia/Tokyo".to_string(),
+                ..UserPreferences::default()
+            })
+            .await;
+        let context = manager.get_current_context().await.unwrap();
+        assert_eq!(context.user_preferences.timezone, "Asia/Tokyo");
+        assert!(matches!(context.environment.time_of_day, TimeOfDay::Morning));
+        assert!(matches!(context.environment.day_of_week, DayOfWeek::Saturday));
+    }
+
+    #[test]
+    fn test_unknown_timezone_falls_back_to_local_time() {
+        use chrono::TimeZone;
+        let now = chrono::Utc.with_ymd_and_hms(2024, 3, 15, 23, 0, 0).unwrap();
+
+        let fallback = EnvironmentContext::at(now, "Mars/Olympus_Mons");
+        let local = EnvironmentContext::in_zone(now, None);
+        assert_eq!(format!("{:?}", fallback.time_of_day), format!("{:?}", local.time_of_day));
+        assert_eq!(format!("{:?}", fallback.day_of_week), format!("{:?}", local.day_of_week));
+    }
+
+    #[tokio::test]
+    async fn test_import_rolls_back_whole_batch_on_error() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await;
+        let now = chrono::Utc::now();
+
+        let first = test_memory("first imported note", MemoryType::LongTerm, now);
+        let second = test_memory("second imported note", MemoryType::LongTerm, now);
+        let mut inline_image = test_memory("iVBORw0KGgoAAAANSUhEUgAA", MemoryType::LongTerm, now);
+        inline_image.content_type = ContentType::Image;
+
+        let result = manager
+            .import_memories(vec![first.clone(), second.clone(), inline_image])
+            .await;
+        assert!(matches!(result, Err(MisaError::Memory(_))));
+        assert_eq!(manager.count_memories().await.unwrap(), 0);
+        assert!(manager.get_memory(&first.id).await.unwrap().is_none());
+
+        let report = manager.import_memories(vec![first.clone(), second.clone()]).await.unwrap();
+        assert_eq!(report.imported, vec![first.id.clone(), second.id.clone()]);
+        assert!(report.failed.is_empty());
+        assert_eq!(manager.count_memories().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_best_effort_import_reports_failures() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await;
+        let now = chrono::Utc::now();
+
+        let existing = test_memory("already stored", MemoryType::LongTerm, now);
+        manager.store_memory(existing.clone()).await.unwrap();
+
+        let fresh = test_memory("fresh import", MemoryType::ShortTerm, now);
+        let mut oversized = test_memory("", MemoryType::LongTerm, now);
+        oversized.content = "x".repeat(manager.config.max_memory_content_bytes + 1);
+
+        let report = manager
+            .import_memories_best_effort(vec![existing.clone(), fresh.clone(), oversized.clone()])
+            .await
+            .unwrap();
+
+        assert_eq!(report.imported, vec![fresh.id.clone()]);
+        let failed: Vec<&str> = report.failed.iter().map(|failure| failure.memory_id.as_str()).collect();
+        assert_eq!(failed, vec![existing.id.as_str(), oversized.id.as_str()]);
+        assert_eq!(manager.count_memories().await.unwrap(), 2);
+        assert!(manager.get_memory(&fresh.id).await.unwrap().is_some());
+
+        let context = manager.get_current_context().await.unwrap();
+        assert!(context.short_term_memory.iter().any(|memory| memory.id == fresh.id));
+    }
+
+    #[tokio::test]
+    async fn test_find_duplicates_flags_paraphrases() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await.with_embedder(Arc::new(ConceptEmbedder));
+        let now = chrono::Utc::now();
+
+        let first = test_memory("Dentist checkup on Thursday", MemoryType::LongTerm, now);
+        let second = test_memory("Dental cleaning appointment Thursday", MemoryType::LongTerm, now);
+        let car = test_memory("Take the car to the mechanic", MemoryType::LongTerm, now);
+        for memory in [&first, &second, &car] {
+            manager.store_memory(memory.clone()).await.unwrap();
+        }
+        manager.wait_for_embeddings().await;
+
+        let duplicates = manager.find_duplicates(0.9).await.unwrap();
+        assert_eq!(duplicates.len(), 1);
+        let (a, b, score) = &duplicates[0];
+        let mut pair = vec![a.as_str(), b.as_str()];
+        pair.sort();
+        let mut expected = vec![first.id.as_str(), second.id.as_str()];
+        expected.sort();
+        assert_eq!(pair, expected);
+        assert!(*score > 0.9);
+    }
+
+    #[tokio::test]
+    async fn test_merge_memories_unions_tags_and_drops_duplicate() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await.with_embedder(Arc::new(ConceptEmbedder));
+        let now = chrono::Utc::now();
+
+        let mut keep = test_memory("Dentist checkup on Thursday", MemoryType::LongTerm, now);
+        keep.tags = vec!["health".to_string()];
+        keep.metadata = serde_json::json!({"location": "Main St"});
+        let mut drop = test_memory("Dental cleaning appointment Thursday", MemoryType::LongTerm, now);
+        drop.tags = vec!["health".to_string(), "appointments".to_string()];
+        drop.metadata = serde_json::json!({"location": "Elm St", "reminder": "1h"});
+        for memory in [&keep, &drop] {
+            manager.store_memory(memory.clone()).await.unwrap();
+        }
+        manager.wait_for_embeddings().await;
+
+        let merged = manager.merge_memories(&keep.id, &drop.id).await.unwrap();
+        assert_eq!(merged.tags, vec!["health".to_string(), "appointments".to_string()]);
+        assert_eq!(merged.metadata["location"], "Main St");
+        assert_eq!(merged.metadata["reminder"], "1h");
+        assert_eq!(merged.metadata["merged_from"], serde_json::json!([drop.id.clone()]));
+
+        assert!(manager.get_memory(&drop.id).await.unwrap().is_none());
+        assert!(manager.find_duplicates(0.9).await.unwrap().is_empty());
+        assert!(manager.merge_memories(&keep.id, &keep.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_detailed_stats_group_by_content_type_and_tag() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await;
+        let now = chrono::Utc::now();
+
+        let items = [
+            ("Standup notes", ContentType::Text, vec!["work", "meetings"]),
+            ("Retro notes", ContentType::Text, vec!["work"]),
+            ("fn main() {}", ContentType::Code, vec!["work", "rust"]),
+            ("Shopping list", ContentType::Text, vec!["home"]),
+        ];
+        for (content, content_type, tags) in items {
+            let mut memory = test_memory(content, MemoryType::LongTerm, now);
+            memory.content_type = content_type;
+            memory.tags = tags.into_iter().map(String::from).collect();
+            manager.store_memory(memory).await.unwrap();
+        }
+
+        let detailed = manager.get_memory_stats_detailed(2).await.unwrap();
+        assert_eq!(detailed.stats.total_memories, 4);
+
+        let by_content_type: Vec<(String, u32)> = detailed
+            .by_content_type
+            .iter()
+            .map(|entry| (format!("{:?}", entry.content_type), entry.count))
+            .collect();
+        assert_eq!(by_content_type, vec![("Text".to_string(), 3), ("Code".to_string(), 1)]);
+
+        // Ties are broken alphabetically, so "home" beats "meetings" and "rust"
+        assert_eq!(
+            detailed.top_tags,
+            vec![
+                TagCount { tag: "work".to_string(), count: 3 },
+                TagCount { tag: "home".to_string(), count: 1 },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_short_term_eviction_keeps_important_memories() {
+        let engine = ContextEngine::new().await.unwrap().with_short_term_capacity(2);
+        let now = chrono::Utc::now();
+
+        let earlier = now - chrono::Duration::hours(3);
+
+        let mut important = test_memory("passport number for the visa form", MemoryType::ShortTerm, earlier);
+        important.importance = Importance::Critical;
+        let mut stale = test_memory("song that was playing earlier", MemoryType::ShortTerm, earlier);
+        stale.importance = Importance::Low;
+        let fresh = test_memory("draft reply to the landlord", MemoryType::ShortTerm, now);
+
+        for memory in [&important, &stale, &fresh] {
+            engine.add_to_short_term_memory(memory.clone()).await.unwrap();
+        }
+
+        // Plain FIFO would have dropped the important memory, which was added first
+        let context = engine.get_current_context().await.unwrap();
+        let ids: Vec<&str> = context.short_term_memory.iter().map(|memory| memory.id.as_str()).collect();
+        assert_eq!(ids, vec![important.id.as_str(), fresh.id.as_str()]);
+    }
+
+    #[tokio::test]
+    async fn test_image_memory_round_trips_through_blob_store() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let manager = test_memory_manager(&data_dir, MemoryConfig::default()).await;
+
+        let pixels: Vec<u8> = b"\x89PNG\r\n\x1a\n".iter().copied().chain((0..=255u8).cycle().take(4096)).collect();
+        let mut memory = test_memory("", MemoryType::LongTerm, chrono::Utc::now());
+        memory.content_type = ContentType::Image;
+        memory.binary = Some(pixels.clone());
+        let memory_id = manager.store_memory(memory).await.unwrap();
+
+        let stored = manager.get_memory(&memory_id).await.unwrap().unwrap();
+        assert!(stored.content.starts_with(BLOB_REFERENCE_PREFIX));
+        assert_eq!(stored.binary.as_deref(), Some(pixels.as_slice()));
+
+        // Only the reference reaches the database, and the blob on disk is encrypted
+        let row_content: String = sqlx::query_scalar("SELECT content FROM memories WHERE id = ?")
+            .bind(&memory_id)
+            .fetch_one(&manager.db_pool)
+            .await
+            .unwrap();
+        assert_eq!(row_content, stored.content);
+        let hash = blob_hash(&stored.content).unwrap();
+        let on_disk = std::fs::read(manager.blob_path(hash, true)).unwrap();
+        assert!(!on_disk.windows(8).any(|window| window == &pixels[..8]));
+    }
+
+    #[tokio::test]
+    async fn test_shared_blob_is_removed_with_its_last_memory() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await;
+
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            let mut memory = test_memory("", MemoryType::LongTerm, chrono::Utc::now());
+            memory.content_type = ContentType::Audio;
+            memory.binary = Some(vec![1, 2, 3, 255]);
+            ids.push(manager.store_memory(memory).await.unwrap());
+        }
+        let reference = manager.get_memory(&ids[0]).await.unwrap().unwrap().content;
+        let blob = manager.blob_path(blob_hash(&reference).unwrap(), false);
+
+        manager.erase_memory(&ids[0], false).await.unwrap();
+        assert!(blob.exists());
+        assert_eq!(manager.get_memory(&ids[1]).await.unwrap().unwrap().binary, Some(vec![1, 2, 3, 255]));
+
+        manager.erase_memory(&ids[1], true).await.unwrap();
+        assert!(!blob.exists());
+    }
+
+    #[tokio::test]
+    async fn test_memory_whose_blob_is_on_another_replica_still_reads() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await;
+
+        let mut memory = test_memory("", MemoryType::LongTerm, chrono::Utc::now());
+        memory.content_type = ContentType::Audio;
+        memory.binary = Some(vec![4, 5, 6]);
+        let memory_id = manager.store_memory(memory).await.unwrap();
+        let reference = manager.get_memory(&memory_id).await.unwrap().unwrap().content;
+        std::fs::remove_file(manager.blob_path(blob_hash(&reference).unwrap(), false)).unwrap();
+
+        let memory = manager.get_memory(&memory_id).await.unwrap().unwrap();
+        assert_eq!(memory.content, reference);
+        assert!(memory.binary.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_forget_removes_memory_embedding_and_index_entries() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await.with_embedder(Arc::new(ConceptEmbedder));
+        let now = chrono::Utc::now();
+
+        let dentist = test_memory("Dentist appointment zq4kw on Thursday", MemoryType::ShortTerm, now);
+        manager.store_memory(dentist.clone()).await.unwrap();
+        manager.wait_for_embeddings().await;
+        let count = |sql: &'static str| {
+            let pool = manager.db_pool.clone();
+            let id = dentist.id.clone();
+            async move { sqlx::query_scalar::<_, i64>(sql).bind(id).fetch_one(&pool).await.unwrap() }
+        };
+        assert_eq!(count("SELECT COUNT(*) FROM memory_embeddings WHERE memory_id = ?").await, 1);
+
+        manager.forget(&dentist.id).await.unwrap();
+
+        assert_eq!(count("SELECT COUNT(*) FROM memories WHERE id = ?").await, 0);
+        assert_eq!(count("SELECT COUNT(*) FROM memory_embeddings WHERE memory_id = ?").await, 0);
+        if manager.fts_available {
+            let indexed: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM memories_fts WHERE memories_fts MATCH 'zq4kw'")
+                .fetch_one(&manager.db_pool)
+                .await
+                .unwrap();
+            assert_eq!(indexed, 0);
+        }
+        assert!(!database_contains(&data_dir, b"zq4kw"));
+
+        let context = manager.get_current_context().await.unwrap();
+        assert!(context.short_term_memory.iter().all(|memory| memory.id != dentist.id));
+
+        let audit = manager
+            .security_manager
+            .query_audit_log(&crate::security::AuditQuery {
+                action: Some("memory_forgotten".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(audit.len(), 1);
+        assert_eq!(audit[0].resource, dentist.id);
+
+        // A second attempt has nothing left to forget
+        assert!(matches!(manager.forget(&dentist.id).await, Err(MisaError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_forget_refuses_permanent_memory_without_force() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let manager = test_memory_manager(&data_dir, MemoryConfig::default()).await;
+        let memory = test_memory("Blood type is O negative", MemoryType::Permanent, chrono::Utc::now());
+        manager.store_memory(memory.clone()).await.unwrap();
+
+        assert!(matches!(manager.forget(&memory.id).await, Err(MisaError::Memory(_))));
+        assert!(manager.get_memory(&memory.id).await.unwrap().is_some());
+
+        manager.force_forget(&memory.id).await.unwrap();
+        assert!(manager.get_memory(&memory.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reads_are_batched_into_one_access_flush() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            access_flush_threshold: 10_000,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await;
+        let now = chrono::Utc::now();
+        let first = manager.store_memory(test_memory("first", MemoryType::LongTerm, now)).await.unwrap();
+        let second = manager.store_memory(test_memory("second", MemoryType::LongTerm, now)).await.unwrap();
+
+        for _ in 0..50 {
+            manager.get_memory(&first).await.unwrap().unwrap();
+            manager.get_memory(&second).await.unwrap().unwrap();
+        }
+        let stored_count = |memory_id: String| {
+            let pool = manager.db_pool.clone();
+            async move {
+                sqlx::query_scalar::<_, i64>("SELECT access_count FROM memories WHERE id = ?")
+                    .bind(memory_id)
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap()
+            }
+        };
+
+        // A hundred reads, not one write yet, though readers already see their reads
+        assert_eq!(stored_count(first.clone()).await, 0);
+        assert_eq!(manager.get_memory(&first).await.unwrap().unwrap().access_count, 50);
+
+        // Both memories are written in a single transaction
+        assert_eq!(manager.flush_access_stats().await.unwrap(), 2);
+        assert_eq!(stored_count(first.clone()).await, 51);
+        assert_eq!(stored_count(second.clone()).await, 50);
+        assert_eq!(manager.flush_access_stats().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_access_threshold_triggers_background_flush() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            access_flush_threshold: 5,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await;
+        let memory_id = manager
+            .store_memory(test_memory("hot", MemoryType::LongTerm, chrono::Utc::now()))
+            .await
+            .unwrap();
+
+        for _ in 0..5 {
+            manager.get_memory(&memory_id).await.unwrap();
+        }
+
+        let mut stored = 0;
+        for _ in 0..50 {
+            stored = sqlx::query_scalar::<_, i64>("SELECT access_count FROM memories WHERE id = ?")
+                .bind(&memory_id)
+                .fetch_one(&manager.db_pool)
+                .await
+                .unwrap();
+            if stored == 5 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert_eq!(stored, 5);
+    }
+
+    #[tokio::test]
+    async fn test_database_uses_wal_and_configured_pool_size() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            db_max_connections: 4,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await;
+
+        let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode").fetch_one(&manager.db_pool).await.unwrap();
+        assert_eq!(journal_mode, "wal");
+        let synchronous: i64 = sqlx::query_scalar("PRAGMA synchronous").fetch_one(&manager.db_pool).await.unwrap();
+        assert_eq!(synchronous, 1);
+        assert_eq!(manager.db_pool.options().get_max_connections(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_readers_and_writer_do_not_deadlock() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            access_flush_threshold: 10,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await;
+        let now = chrono::Utc::now();
+        let seed = manager.store_memory(test_memory("seed", MemoryType::LongTerm, now)).await.unwrap();
+
+        let mut workers = Vec::new();
+        for _ in 0..4 {
+            let reader = manager.clone();
+            let seed = seed.clone();
+            workers.push(tokio::spawn(async move {
+                for _ in 0..25 {
+                    reader.get_memory(&seed).await.unwrap().unwrap();
+                    reader.search_memories(&SearchQuery::new()).await.unwrap();
+                }
+            }));
+        }
+        let writer = manager.clone();
+        workers.push(tokio::spawn(async move {
+            for i in 0..25 {
+                let content = format!("written {}", i);
+                writer.store_memory(test_memory(&content, MemoryType::LongTerm, now)).await.unwrap();
+            }
+        }));
+
+        tokio::time::timeout(std::time::Duration::from_secs(30), futures_util::future::try_join_all(workers))
+            .await
+            .expect("readers and writer deadlocked")
+            .unwrap();
+
+        let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM memories").fetch_one(&manager.db_pool).await.unwrap();
+        assert_eq!(stored, 26);
+    }
+
+    #[tokio::test]
+    async fn test_overlapping_and_interrupted_compactions_summarize_once() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        let summarizer = Arc::new(StubSummarizer(std::sync::Mutex::new(Vec::new())));
+        let manager = test_memory_manager(&data_dir, config).await.with_summarizer(summarizer.clone());
+
+        let day = (chrono::Utc::now() - chrono::Duration::days(3))
+            .date_naive()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+            .and_utc();
+        for i in 0..4 {
+            let memory = test_memory("Drafted launch plan", MemoryType::ShortTerm, day + chrono::Duration::minutes(i));
+            manager.store_memory(memory).await.unwrap();
+        }
+
+        let (first, second) = tokio::join!(manager.compact(), manager.clone().compact());
+        assert_eq!(first.unwrap().summaries_created + second.unwrap().summaries_created, 1);
+        assert_eq!(summarizer.0.lock().unwrap().len(), 1);
+
+        // A pass that stored its summary but stopped before touching the sources
+        let earlier = day - chrono::Duration::days(1);
+        let mut source_ids = Vec::new();
+        for i in 0..4 {
+            let memory = test_memory("Called the venue", MemoryType::ShortTerm, earlier + chrono::Duration::minutes(i));
+            source_ids.push(manager.store_memory(memory).await.unwrap());
+        }
+        let mut summary = test_memory("Called the venue", MemoryType::MediumTerm, earlier);
+        summary.metadata = serde_json::json!({ "source": COMPACTION_SOURCE, "compacted_from": source_ids });
+        let summary_id = manager.store_memory(summary).await.unwrap();
+
+        let report = manager.compact().await.unwrap();
+        assert_eq!(report.summaries_created, 0);
+        assert_eq!(report.memories_retained, 4);
+        assert_eq!(summarizer.0.lock().unwrap().len(), 1);
+        for source_id in &source_ids {
+            let source = manager.get_memory(source_id).await.unwrap().unwrap();
+            assert_eq!(source.metadata["compacted_into"], summary_id.as_str());
+        }
+
+        assert_eq!(manager.compact().await.unwrap(), CompactionReport::default());
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_blob_is_readable_after_restart() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let manager = test_memory_manager(&data_dir, MemoryConfig::default()).await;
+
+        let mut memory = test_memory("", MemoryType::LongTerm, chrono::Utc::now());
+        memory.content_type = ContentType::Audio;
+        memory.binary = Some(vec![4, 8, 15, 16, 23, 42]);
+        let memory_id = manager.store_memory(memory).await.unwrap();
+        drop(manager);
+
+        // A new process starts with a new security manager, but the blob key is persisted
+        let restarted = test_memory_manager(&data_dir, MemoryConfig::default()).await;
+        let stored = restarted.get_memory(&memory_id).await.unwrap().unwrap();
+        assert_eq!(stored.binary, Some(vec![4, 8, 15, 16, 23, 42]));
+    }
+
+    fn blob_files(data_dir: &tempfile::TempDir) -> Vec<std::path::PathBuf> {
+        match std::fs::read_dir(data_dir.path().join(BLOB_DIR)) {
+            Ok(entries) => entries.map(|entry| entry.unwrap().path()).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_that_fails_to_store_leaves_no_blob() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await;
+
+        let mut memory = test_memory("", MemoryType::LongTerm, chrono::Utc::now());
+        memory.content_type = ContentType::Image;
+        memory.binary = Some(vec![1, 1, 2, 3, 5]);
+        let memory_id = manager.store_memory(memory.clone()).await.unwrap();
+
+        // The second INSERT fails on the duplicate id, so its bytes must not stay behind
+        let mut duplicate = memory.clone();
+        duplicate.binary = Some(vec![8, 13, 21]);
+        assert!(manager.store_memory(duplicate.clone()).await.is_err());
+        assert_eq!(blob_files(&data_dir).len(), 1);
+
+        // The same goes for a batch that is rolled back
+        let mut fresh = test_memory("", MemoryType::LongTerm, chrono::Utc::now());
+        fresh.content_type = ContentType::Image;
+        fresh.binary = Some(vec![34, 55]);
+        assert!(manager.import_memories(vec![fresh, duplicate]).await.is_err());
+        assert_eq!(blob_files(&data_dir).len(), 1);
+        assert_eq!(manager.get_memory(&memory_id).await.unwrap().unwrap().binary, Some(vec![1, 1, 2, 3, 5]));
+    }
+
+    #[tokio::test]
+    async fn test_pruning_releases_blobs() {
+        for secure_delete_types in [Vec::new(), vec![MemoryType::ShortTerm]] {
+            let data_dir = tempfile::tempdir().unwrap();
+            let config = MemoryConfig {
+                encryption_enabled: false,
+                secure_delete_types,
+                ..MemoryConfig::default()
+            };
+            let manager = test_memory_manager(&data_dir, config).await;
+
+            let mut expired = test_memory("", MemoryType::ShortTerm, chrono::Utc::now() - chrono::Duration::days(30));
+            expired.content_type = ContentType::Image;
+            expired.binary = Some(vec![9, 9, 9]);
+            manager.store_memory(expired).await.unwrap();
+            assert_eq!(blob_files(&data_dir).len(), 1);
+
+            assert_eq!(manager.prune_memories().await.unwrap().total(), 1);
+            assert!(blob_files(&data_dir).is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_merge_releases_the_dropped_memorys_blob() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        let manager = test_memory_manager(&data_dir, config).await;
+
+        let keep_id = manager.store_memory(test_memory("Whiteboard notes", MemoryType::LongTerm, chrono::Utc::now())).await.unwrap();
+        let mut photo = test_memory("", MemoryType::LongTerm, chrono::Utc::now());
+        photo.content_type = ContentType::Image;
+        photo.binary = Some(vec![7, 7, 7]);
+        let drop_id = manager.store_memory(photo).await.unwrap();
+
+        manager.merge_memories(&keep_id, &drop_id).await.unwrap();
+        assert!(blob_files(&data_dir).is_empty());
+    }
+}
@@ -0,0 +1,213 @@
+//! Runtime Metrics
+//!
+//! Lightweight Prometheus-style metrics for the core managers:
+//! - Counters for model, memory and device activity
+//! - Gauges refreshed when metrics are scraped
+//! - Latency summaries (sum and count)
+//! - Text exposition for the kernel's `/metrics` endpoint
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Requests handled per subsystem and operation
+pub const REQUESTS_TOTAL: MetricDef = MetricDef {
+    name: "requests_total",
+    help: "Requests handled by core subsystems",
+    kind: MetricKind::Counter,
+};
+
+/// Memories currently stored
+pub const MEMORY_ITEMS: MetricDef = MetricDef {
+    name: "memory_items",
+    help: "Memory items currently stored",
+    kind: MetricKind::Gauge,
+};
+
+/// Devices currently connected
+pub const ACTIVE_CONNECTIONS: MetricDef = MetricDef {
+    name: "active_connections",
+    help: "Devices currently connected",
+    kind: MetricKind::Gauge,
+};
+
+/// Device connections opened and dropped
+pub const CONNECTION_EVENTS_TOTAL: MetricDef = MetricDef {
+    name: "connection_events_total",
+    help: "Device connections opened and evicted",
+    kind: MetricKind::Counter,
+};
+
+/// Time spent executing model requests
+pub const MODEL_LATENCY_SECONDS: MetricDef = MetricDef {
+    name: "model_latency_seconds",
+    help: "Model execution latency in seconds",
+    kind: MetricKind::Summary,
+};
+
+/// Label value shared by every value outside a label's known set, keeping the series count bounded
+pub const OTHER_LABEL: &str = "other";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+    Summary,
+}
+
+impl MetricKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+            MetricKind::Summary => "summary",
+        }
+    }
+}
+
+/// Name, help text and type of a metric
+#[derive(Debug, Clone, Copy)]
+pub struct MetricDef {
+    pub name: &'static str,
+    pub help: &'static str,
+    pub kind: MetricKind,
+}
+
+/// Shared metrics registry; a disabled registry ignores every update
+pub struct Metrics {
+    enabled: bool,
+    families: Arc<RwLock<BTreeMap<&'static str, MetricFamily>>>,
+}
+
+struct MetricFamily {
+    def: MetricDef,
+    /// Samples keyed by their rendered label set
+    samples: BTreeMap<String, Sample>,
+}
+
+#[derive(Default, Clone, Copy)]
+struct Sample {
+    value: f64,
+    count: u64,
+}
+
+impl Metrics {
+    /// Create a registry that records updates
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            families: Arc::new(RwLock::new(BTreeMap::new())),
+        }
+    }
+
+    /// Create a registry that records nothing
+    pub fn disabled() -> Self {
+        Self {
+            enabled: false,
+            ..Self::new()
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Increase a counter by one
+    pub async fn inc(&self, def: MetricDef, labels: &[(&str, &str)]) {
+        self.update(def, labels, |sample| sample.value += 1.0).await;
+    }
+
+    /// Set a gauge to an absolute value
+    pub async fn set(&self, def: MetricDef, labels: &[(&str, &str)], value: f64) {
+        self.update(def, labels, |sample| sample.value = value).await;
+    }
+
+    /// Record one observation in a summary
+    pub async fn observe(&self, def: MetricDef, labels: &[(&str, &str)], value: f64) {
+        self.update(def, labels, |sample| {
+            sample.value += value;
+            sample.count += 1;
+        })
+        .await;
+    }
+
+    /// Current value of a counter or gauge, or the sum of a summary
+    pub async fn value(&self, def: MetricDef, labels: &[(&str, &str)]) -> Option<f64> {
+        let families = self.families.read().await;
+        families
+            .get(def.name)
+            .and_then(|family| family.samples.get(&render_labels(labels)))
+            .map(|sample| sample.value)
+    }
+
+    /// Render every metric in the Prometheus text exposition format
+    pub async fn render(&self) -> String {
+        let families = self.families.read().await;
+        let mut output = String::new();
+
+        for family in families.values() {
+            let name = family.def.name;
+            let _ = writeln!(output, "# HELP {} {}", name, family.def.help);
+            let _ = writeln!(output, "# TYPE {} {}", name, family.def.kind.as_str());
+
+            for (labels, sample) in &family.samples {
+                match family.def.kind {
+                    MetricKind::Counter | MetricKind::Gauge => {
+                        let _ = writeln!(output, "{}{} {}", name, labels, sample.value);
+                    }
+                    MetricKind::Summary => {
+                        let _ = writeln!(output, "{}_sum{} {}", name, labels, sample.value);
+                        let _ = writeln!(output, "{}_count{} {}", name, labels, sample.count);
+                    }
+                }
+            }
+        }
+
+        output
+    }
+
+    async fn update(&self, def: MetricDef, labels: &[(&str, &str)], apply: impl FnOnce(&mut Sample)) {
+        if !self.enabled {
+            return;
+        }
+
+        let mut families = self.families.write().await;
+        let family = families.entry(def.name).or_insert_with(|| MetricFamily {
+            def,
+            samples: BTreeMap::new(),
+        });
+        apply(family.samples.entry(render_labels(labels)).or_default());
+    }
+}
+
+/// `{a="1",b="2"}` with label values escaped, or empty when there are no labels
+fn render_labels(labels: &[(&str, &str)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+
+    let pairs: Vec<String> = labels
+        .iter()
+        .map(|(key, value)| {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+            format!("{}=\"{}\"", key, value)
+        })
+        .collect();
+    format!("{{{}}}", pairs.join(","))
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::disabled()
+    }
+}
+
+impl Clone for Metrics {
+    fn clone(&self) -> Self {
+        Self {
+            enabled: self.enabled,
+            families: Arc::clone(&self.families),
+        }
+    }
+}
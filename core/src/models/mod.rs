@@ -8,13 +8,21 @@
 //! - Automatic model switching based on task requirements
 
 use anyhow::Result;
+use futures_util::{Stream, StreamExt, TryStreamExt};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use tracing::{info, warn, error};
-
-use crate::kernel::{ModelConfig, ModelSwitchingPreferences, TaskPriority};
+use tokio::sync::{broadcast, watch, OwnedSemaphorePermit, RwLock, Semaphore};
+use tracing::{debug, info, warn, error, instrument};
+
+use crate::correlation;
+use crate::kernel::{ModelConcurrencyConfig, ModelConfig, ModelSwitchingPreferences, OllamaClientConfig, TaskPriority};
+use crate::metrics::{self, Metrics};
+use crate::privacy::{ConsentType, PrivacyControls};
+use crate::scheduler::Scheduler;
+use crate::util::write_json_atomic;
 use crate::errors::{MisaError, Result as MisaResult};
 
 /// Model manager for orchestrating AI models
@@ -26,6 +34,127 @@ pub struct ModelManager {
     performance_metrics: Arc<RwLock<HashMap<String, ModelPerformance>>>,
     ollama_client: OllamaClient,
     cloud_clients: Arc<RwLock<HashMap<String, CloudClient>>>,
+    execution_limiter: Arc<ExecutionLimiter>,
+    metrics: Metrics,
+    scheduler: Scheduler,
+    catalog_events: broadcast::Sender<ModelCatalogEvent>,
+    spend: Arc<RwLock<SpendTracker>>,
+    /// File the spend is saved to after every cloud call; none keeps it in memory only
+    spend_path: Option<PathBuf>,
+    /// Consent cloud fallback is checked against; none means no fallback to cloud models
+    privacy_controls: Option<PrivacyControls>,
+    /// Pulls in progress by model, each publishing whether it succeeded once it ends
+    pulls_in_flight: Arc<std::sync::Mutex<HashMap<String, watch::Receiver<Option<bool>>>>>,
+}
+
+/// Estimated cloud spend, reset at the start of each UTC day
+#[derive(Default, Serialize, Deserialize)]
+struct SpendTracker {
+    day: Option<chrono::NaiveDate>,
+    daily_usd: f64,
+    /// Estimated cost of cloud calls still in flight
+    #[serde(skip)]
+    reserved_usd: f64,
+    total_usd: f64,
+    by_model: HashMap<String, ModelSpend>,
+}
+
+/// Cloud usage and estimated cost of a single model
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelSpend {
+    pub requests: u64,
+    pub tokens: u64,
+    pub cost_usd: f64,
+}
+
+/// Estimated cloud spend against the daily budget
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpendSummary {
+    pub day: chrono::NaiveDate,
+    pub daily_spend_usd: f64,
+    pub daily_budget_usd: Option<f64>,
+    pub total_spend_usd: f64,
+    pub by_model: HashMap<String, ModelSpend>,
+}
+
+/// Completion tokens a cloud call is assumed to use when budget is reserved for it
+const RESERVED_COMPLETION_TOKENS: u64 = 1000;
+
+/// Background job re-scanning the local model server
+const CATALOG_REFRESH_JOB: &str = "models.catalog_refresh";
+
+/// File under the data directory holding the cloud spend
+const SPEND_FILE: &str = "model_spend.json";
+
+/// Context window and feature support of an OpenAI model family
+struct OpenAiModelFamily {
+    prefix: &'static str,
+    context_length: usize,
+    supports_functions: bool,
+    supports_vision: bool,
+    supports_json_mode: bool,
+}
+
+/// Known OpenAI model families, most specific prefix first
+const OPENAI_MODEL_FAMILIES: &[OpenAiModelFamily] = &[
+    OpenAiModelFamily { prefix: "gpt-4o", context_length: 128_000, supports_functions: true, supports_vision: true, supports_json_mode: true },
+    OpenAiModelFamily { prefix: "gpt-4-turbo", context_length: 128_000, supports_functions: true, supports_vision: true, supports_json_mode: true },
+    OpenAiModelFamily { prefix: "gpt-4-vision", context_length: 128_000, supports_functions: false, supports_vision: true, supports_json_mode: false },
+    OpenAiModelFamily { prefix: "gpt-4-1106", context_length: 128_000, supports_functions: true, supports_vision: false, supports_json_mode: true },
+    OpenAiModelFamily { prefix: "gpt-4-0125", context_length: 128_000, supports_functions: true, supports_vision: false, supports_json_mode: true },
+    OpenAiModelFamily { prefix: "gpt-4-32k", context_length: 32_768, supports_functions: true, supports_vision: false, supports_json_mode: false },
+    OpenAiModelFamily { prefix: "gpt-4", context_length: 8_192, supports_functions: true, supports_vision: false, supports_json_mode: false },
+    OpenAiModelFamily { prefix: "gpt-3.5-turbo-16k", context_length: 16_384, supports_functions: true, supports_vision: false, supports_json_mode: false },
+    OpenAiModelFamily { prefix: "gpt-3.5-turbo-instruct", context_length: 4_096, supports_functions: false, supports_vision: false, supports_json_mode: false },
+    OpenAiModelFamily { prefix: "gpt-3.5-turbo", context_length: 16_384, supports_functions: true, supports_vision: false, supports_json_mode: true },
+];
+
+fn openai_model_family(model: &str) -> Option<&'static OpenAiModelFamily> {
+    OPENAI_MODEL_FAMILIES.iter().find(|family| model.starts_with(family.prefix))
+}
+
+/// Changes to the set of available local models
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModelCatalogEvent {
+    ModelAdded {
+        model_id: String,
+    },
+    ModelRemoved {
+        model_id: String,
+    },
+    /// A status line streamed while a local model is being pulled
+    PullProgress {
+        model_id: String,
+        status: String,
+        completed: Option<u64>,
+        total: Option<u64>,
+    },
+}
+
+/// Local models that appeared or disappeared in a catalog refresh
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CatalogDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// Candidate model with the score it was ranked by
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelScore {
+    pub model_id: String,
+    pub score: f64,
+}
+
+/// Bounds concurrent model executions, queuing excess requests up to a fixed depth
+struct ExecutionLimiter {
+    local: ExecutionPool,
+    cloud: ExecutionPool,
+    max_queue_depth: usize,
+}
+
+struct ExecutionPool {
+    semaphore: Arc<Semaphore>,
+    queued: AtomicUsize,
 }
 
 /// Local model information
@@ -40,6 +169,8 @@ pub struct LocalModel {
     pub parameters: String,
     pub device_preference: DevicePreference,
     pub loaded: bool,
+    /// False once the model is no longer listed by the local server
+    pub available: bool,
 }
 
 /// Cloud model information
@@ -104,9 +235,13 @@ pub struct ModelPerformance {
 }
 
 /// Ollama client for local models
+#[derive(Clone)]
 pub struct OllamaClient {
     base_url: String,
     client: reqwest::Client,
+    request_timeout: std::time::Duration,
+    max_retries: u32,
+    retry_backoff: std::time::Duration,
 }
 
 /// Cloud client abstraction
@@ -118,7 +253,7 @@ pub struct CloudClient {
 }
 
 /// Model execution request
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct ModelRequest {
     pub prompt: String,
     pub model_id: Option<String>,
@@ -143,7 +278,7 @@ pub struct ModelResponse {
 impl ModelManager {
     /// Create a new model manager
     pub async fn new(config: ModelConfig) -> MisaResult<Self> {
-        let ollama_client = OllamaClient::new(config.local_server_url.clone());
+        let ollama_client = OllamaClient::with_config(config.local_server_url.clone(), &config.ollama)?;
 
         let mut cloud_clients = HashMap::new();
         for (provider, provider_config) in &config.cloud_providers {
@@ -161,6 +296,14 @@ impl ModelManager {
             performance_metrics: Arc::new(RwLock::new(HashMap::new())),
             ollama_client,
             cloud_clients: Arc::new(RwLock::new(cloud_clients)),
+            execution_limiter: Arc::new(ExecutionLimiter::new(&config.concurrency)),
+            metrics: Metrics::disabled(),
+            scheduler: Scheduler::new(),
+            catalog_events: broadcast::channel(64).0,
+            spend: Arc::new(RwLock::new(SpendTracker::default())),
+            spend_path: None,
+            privacy_controls: None,
+            pulls_in_flight: Arc::new(std::sync::Mutex::new(HashMap::new())),
         };
 
         // Initialize model catalogs
@@ -170,10 +313,51 @@ impl ModelManager {
         Ok(manager)
     }
 
+    /// Record request counts and latency in a shared metrics registry
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Run background tasks on a scheduler shared with other managers
+    pub fn with_scheduler(mut self, scheduler: Scheduler) -> Self {
+        self.scheduler = scheduler;
+        self
+    }
+
+    /// Check cloud fallback against the user's consent to cloud processing
+    pub fn with_privacy_controls(mut self, privacy_controls: PrivacyControls) -> Self {
+        self.privacy_controls = Some(privacy_controls);
+        self
+    }
+
+    /// Load cloud spend from `data_dir` and save it there after every cloud call,
+    /// so a restart doesn't reset the daily budget
+    pub async fn with_spend_store(mut self, data_dir: &str) -> MisaResult<Self> {
+        let spend_path = Path::new(data_dir).join(SPEND_FILE);
+        let spend: SpendTracker = match tokio::fs::read(&spend_path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => SpendTracker::default(),
+            Err(e) => return Err(e.into()),
+        };
+        debug!("Loaded ${:.4} of cloud spend from {}", spend.total_usd, spend_path.display());
+
+        self.spend = Arc::new(RwLock::new(spend));
+        self.spend_path = Some(spend_path);
+        Ok(self)
+    }
+
+    /// Subscribe to local models being added or removed
+    pub fn subscribe_catalog_events(&self) -> broadcast::Receiver<ModelCatalogEvent> {
+        self.catalog_events.subscribe()
+    }
+
     /// Initialize the model manager
     pub async fn initialize(&self) -> MisaResult<()> {
         info!("Initializing model manager");
 
+        self.start_catalog_refresh().await?;
+
         // Load default model
         let default_model = self.config.default_model.clone();
         if let Err(e) = self.switch_model(&default_model, None, None).await {
@@ -188,32 +372,102 @@ impl ModelManager {
     async fn discover_local_models(&self) -> MisaResult<()> {
         info!("Discovering local models via Ollama");
 
-        match self.ollama_client.list_models().await {
-            Ok(models) => {
-                let mut local_models = self.local_models.write().await;
-                for model_info in models {
-                    let local_model = LocalModel {
-                        id: model_info.name.clone(),
-                        name: model_info.name,
-                        model_type: self.classify_model_type(&model_info.name),
-                        capabilities: self.infer_model_capabilities(&model_info.name),
-                        size_gb: model_info.size as f32 / 1024.0, // Convert bytes to GB
-                        quantization: "Q4_0".to_string(), // Default assumption
-                        parameters: "unknown".to_string(),
-                        device_preference: DevicePreference::Hybrid,
-                        loaded: false,
-                    };
-
-                    local_models.insert(model_info.name, local_model);
+        match self.refresh_catalog().await {
+            Ok(diff) => info!("Discovered {} local models", diff.added.len()),
+            Err(e) => warn!("Failed to discover local models: {}", e),
+        }
+
+        Ok(())
+    }
+
+    /// Re-query the local server and sync the local model catalog with it
+    ///
+    /// New models are added, models no longer listed are marked unavailable, and
+    /// an event is emitted for each change.
+    pub async fn refresh_catalog(&self) -> MisaResult<CatalogDiff> {
+        let models = self
+            .ollama_client
+            .list_models()
+            .await
+            .map_err(|e| MisaError::Model(format!("Failed to list local models: {}", e)))?;
+
+        Self::apply_catalog(&self.local_models, &self.catalog_events, models).await
+    }
+
+    async fn apply_catalog(
+        local_models: &RwLock<HashMap<String, LocalModel>>,
+        catalog_events: &broadcast::Sender<ModelCatalogEvent>,
+        models: Vec<OllamaModelInfo>,
+    ) -> MisaResult<CatalogDiff> {
+        let mut diff = CatalogDiff::default();
+        let mut local_models = local_models.write().await;
+
+        let listed: std::collections::HashSet<String> = models.iter().map(|m| m.name.clone()).collect();
+        for model_info in models {
+            match local_models.get_mut(&model_info.name) {
+                Some(model) if model.available => {}
+                Some(model) => {
+                    model.available = true;
+                    diff.added.push(model_info.name);
+                }
+                None => {
+                    diff.added.push(model_info.name.clone());
+                    local_models.insert(model_info.name.clone(), Self::local_model_from_info(model_info));
                 }
-                info!("Discovered {} local models", local_models.len());
             }
-            Err(e) => {
-                warn!("Failed to discover local models: {}", e);
+        }
+
+        for model in local_models.values_mut() {
+            if model.available && !listed.contains(&model.id) {
+                model.available = false;
+                model.loaded = false;
+                diff.removed.push(model.id.clone());
             }
         }
+        drop(local_models);
 
-        Ok(())
+        diff.added.sort();
+        diff.removed.sort();
+        for model_id in &diff.added {
+            let _ = catalog_events.send(ModelCatalogEvent::ModelAdded { model_id: model_id.clone() });
+        }
+        for model_id in &diff.removed {
+            let _ = catalog_events.send(ModelCatalogEvent::ModelRemoved { model_id: model_id.clone() });
+        }
+
+        Ok(diff)
+    }
+
+    async fn start_catalog_refresh(&self) -> MisaResult<()> {
+        if self.config.catalog_refresh_interval_seconds == 0 {
+            return Ok(());
+        }
+
+        let ollama_client = self.ollama_client.clone();
+        let local_models = Arc::clone(&self.local_models);
+        let catalog_events = self.catalog_events.clone();
+        let interval = tokio::time::Duration::from_secs(self.config.catalog_refresh_interval_seconds);
+
+        self.scheduler
+            .register(CATALOG_REFRESH_JOB, interval, move || {
+                let ollama_client = ollama_client.clone();
+                let local_models = Arc::clone(&local_models);
+                let catalog_events = catalog_events.clone();
+                async move {
+                    let models = ollama_client
+                        .list_models()
+                        .await
+                        .map_err(|e| MisaError::Model(format!("Failed to list local models: {}", e)))?;
+                    let diff = Self::apply_catalog(&local_models, &catalog_events, models).await?;
+                    debug!(
+                        "Catalog refresh: {} added, {} removed",
+                        diff.added.len(),
+                        diff.removed.len()
+                    );
+                    Ok(())
+                }
+            })
+            .await
     }
 
     /// Register cloud model configurations
@@ -229,7 +483,7 @@ impl ModelManager {
                     id: model_name.clone(),
                     name: model_name.clone(),
                     provider: "openai".to_string(),
-                    model_type: self.classify_model_type(model_name),
+                    model_type: Self::classify_model_type(model_name),
                     capabilities: self.get_cloud_model_capabilities("openai", model_name),
                     cost_per_million_tokens: self.get_model_cost("openai", model_name),
                     context_length: self.get_model_context_length("openai", model_name),
@@ -239,6 +493,23 @@ impl ModelManager {
             }
         }
 
+        // Register Anthropic models
+        if let Some(anthropic_config) = self.config.cloud_providers.get("anthropic") {
+            for model_name in &anthropic_config.models {
+                let cloud_model = CloudModel {
+                    id: model_name.clone(),
+                    name: model_name.clone(),
+                    provider: "anthropic".to_string(),
+                    model_type: Self::classify_model_type(model_name),
+                    capabilities: self.get_cloud_model_capabilities("anthropic", model_name),
+                    cost_per_million_tokens: self.get_model_cost("anthropic", model_name),
+                    context_length: self.get_model_context_length("anthropic", model_name),
+                    max_tokens_per_minute: 4000,
+                };
+                cloud_models.insert(format!("anthropic:{}", model_name), cloud_model);
+            }
+        }
+
         info!("Registered {} cloud models", cloud_models.len());
         Ok(())
     }
@@ -252,8 +523,18 @@ impl ModelManager {
     ) -> MisaResult<String> {
         info!("Switching to model: {}", model_id);
 
-        // Load the model if it's local
-        if self.is_local_model(model_id) {
+        let is_local = self
+            .local_models
+            .read()
+            .await
+            .get(model_id)
+            .map_or(false, |model| model.available);
+        if !is_local && !self.cloud_models.read().await.contains_key(model_id) {
+            return Err(MisaError::Model(format!("unknown model: {}", model_id)));
+        }
+
+        // Load the model if it's local; the current model is kept if this fails
+        if is_local {
             self.load_local_model(model_id).await?;
         }
 
@@ -265,13 +546,81 @@ impl ModelManager {
         Ok(model_id.to_string())
     }
 
-    /// Select optimal model for a given task
+    /// Embed text with the configured local embedding model
+    pub async fn embed(&self, text: &str) -> MisaResult<Vec<f32>> {
+        let _permit = self.execution_limiter.acquire(true).await?;
+        self.ollama_client.embed(&self.config.embedding_model, text).await
+    }
+
+    /// Stream a response from a local model fragment by fragment
+    ///
+    /// Uses the current model when none is given. The execution slot is held until
+    /// the stream is dropped, and dropping it aborts the request to the local server.
+    pub async fn generate_stream(
+        &self,
+        prompt: &str,
+        model_id: Option<&str>,
+    ) -> MisaResult<impl Stream<Item = MisaResult<String>> + Send + 'static> {
+        let model_id = match model_id {
+            Some(model_id) => model_id.to_string(),
+            None => self.current_model().await,
+        };
+        if !self.is_local_model(&model_id) {
+            return Err(MisaError::Model(format!("Streaming is only supported for local models, not {}", model_id)));
+        }
+
+        let permit = self.execution_limiter.acquire(true).await?;
+        let request = ModelRequest {
+            prompt: prompt.to_string(),
+            model_id: Some(model_id),
+            context: None,
+            stream: true,
+            max_tokens: None,
+            temperature: None,
+            tools: None,
+        };
+
+        Ok(self.ollama_client.generate_stream(request).map(move |fragment| {
+            let _permit = &permit;
+            fragment
+        }))
+    }
+
+    /// Currently selected model id
+    pub async fn current_model(&self) -> String {
+        self.current_model.read().await.clone()
+    }
+
+    /// Select optimal model for a given task.
+    /// `device_preferences` is deprecated and ignored; device choice is made by the kernel.
     pub async fn select_model_for_task(
         &self,
         task_type: &str,
         device_preferences: Option<&[String]>,
         priority: &TaskPriority,
     ) -> MisaResult<String> {
+        let ranking = self.rank_models_for_task_type(task_type, device_preferences, priority).await?;
+        Ok(ranking[0].model_id.clone())
+    }
+
+    /// Best local model for a task, for work that must never leave this machine
+    pub async fn select_local_model_for_task(&self, task_type: &str, priority: &TaskPriority) -> MisaResult<String> {
+        self.rank_models_for_task_type(task_type, None, priority)
+            .await?
+            .into_iter()
+            .find(|score| self.is_local_model(&score.model_id))
+            .map(|score| score.model_id)
+            .ok_or_else(|| MisaError::Model(format!("No local models available for task type: {}", task_type)))
+    }
+
+    /// Every candidate model for a task type with its score, best first.
+    /// `_device_preferences` is deprecated and ignored; device choice is made by the kernel.
+    pub async fn rank_models_for_task_type(
+        &self,
+        task_type: &str,
+        _device_preferences: Option<&[String]>,
+        priority: &TaskPriority,
+    ) -> MisaResult<Vec<ModelScore>> {
         let model_type = self.task_type_to_enum(task_type);
 
         // Get candidate models
@@ -281,19 +630,124 @@ impl ModelManager {
             return Err(MisaError::Model(format!("No models available for task type: {}", task_type)));
         }
 
-        // Select best model based on criteria
-        let best_model = self.rank_models_for_task(candidates, device_preferences, priority).await?;
-
-        Ok(best_model)
+        self.rank_models_for_task(candidates, &model_type, priority).await
     }
 
-    /// Execute a task on the specified model
+    /// Execute a task on the specified model, falling back to other models of the same type
+    /// when a local model fails and the switching preferences allow it
+    #[instrument(skip_all, fields(request_id, model_id = %model_id))]
     pub async fn execute_task(
         &self,
         task: &str,
         model_id: &str,
         context: Option<&serde_json::Value>,
     ) -> MisaResult<serde_json::Value> {
+        let allow_cloud = self.config.switching_preferences.allow_cloud_fallback;
+        self.execute_with_fallback(task, model_id, context, allow_cloud).await
+    }
+
+    /// Execute a task on a local model, only ever falling back to other local models
+    #[instrument(skip_all, fields(request_id, model_id = %model_id))]
+    pub async fn execute_local_task(
+        &self,
+        task: &str,
+        model_id: &str,
+        context: Option<&serde_json::Value>,
+    ) -> MisaResult<serde_json::Value> {
+        if !self.is_local_model(model_id) {
+            return Err(MisaError::Model(format!("{} is not a local model", model_id)));
+        }
+        self.execute_with_fallback(task, model_id, context, false).await
+    }
+
+    async fn execute_with_fallback(
+        &self,
+        task: &str,
+        model_id: &str,
+        context: Option<&serde_json::Value>,
+        allow_cloud: bool,
+    ) -> MisaResult<serde_json::Value> {
+        correlation::enter_request();
+        let mut error = match self.execute_on_model(task, model_id, context).await {
+            Ok(response) => return Ok(serde_json::to_value(response)?),
+            Err(e) => e,
+        };
+
+        let preferences = &self.config.switching_preferences;
+        if !self.is_local_model(model_id) || preferences.max_fallback_attempts == 0 {
+            return Err(error);
+        }
+
+        let allow_cloud = allow_cloud && self.has_cloud_consent().await;
+        let model_type = self.model_type_of(model_id).await;
+        let mut tried = vec![model_id.to_string()];
+
+        for _ in 0..preferences.max_fallback_attempts {
+            let candidates: Vec<String> = self
+                .get_models_by_type(&model_type)
+                .await?
+                .into_iter()
+                .filter(|candidate| !tried.contains(candidate))
+                .filter(|candidate| allow_cloud || self.is_local_model(candidate))
+                .collect();
+            if candidates.is_empty() {
+                break;
+            }
+
+            let fallback = self
+                .rank_models_for_task(candidates, &model_type, &TaskPriority::Normal)
+                .await?
+                .remove(0)
+                .model_id;
+            warn!("Model {} failed ({}), falling back to {}", tried.last().unwrap(), error, fallback);
+
+            match self.execute_on_model(task, &fallback, context).await {
+                Ok(mut response) => {
+                    if let Some(metadata) = response.metadata.as_object_mut() {
+                        metadata.insert("fallback_from".to_string(), serde_json::json!(model_id));
+                    }
+                    return Ok(serde_json::to_value(response)?);
+                }
+                Err(e) => {
+                    error = e;
+                    tried.push(fallback);
+                }
+            }
+        }
+
+        Err(error)
+    }
+
+    /// Whether the configured user consented to their tasks being sent to cloud models
+    async fn has_cloud_consent(&self) -> bool {
+        let user_id = self.config.switching_preferences.cloud_consent_user_id.as_deref();
+        let (Some(privacy_controls), Some(user_id)) = (&self.privacy_controls, user_id) else {
+            return false;
+        };
+        privacy_controls
+            .has_consent(user_id, ConsentType::CloudProcessing)
+            .await
+            .unwrap_or_else(|e| {
+                warn!("Failed to check cloud processing consent for {}: {}", user_id, e);
+                false
+            })
+    }
+
+    /// Run a task on exactly one model, recording the outcome in the performance metrics
+    async fn execute_on_model(
+        &self,
+        task: &str,
+        model_id: &str,
+        context: Option<&serde_json::Value>,
+    ) -> MisaResult<ModelResponse> {
+        let is_local = self.is_local_model(model_id);
+
+        // Wait for an execution slot; rejects when the queue is already full
+        let _permit = self.execution_limiter.acquire(is_local).await?;
+
+        // Budget is checked and reserved in one step, so calls admitted together can't overspend it
+        let reserved_usd = if is_local { 0.0 } else { self.reserve_spend(model_id, task).await? };
+
         let start_time = std::time::Instant::now();
 
         let request = ModelRequest {
@@ -306,24 +760,147 @@ impl ModelManager {
             tools: None,
         };
 
-        let response = if self.is_local_model(model_id) {
-            self.execute_local_model(request).await?
+        let result = if is_local {
+            self.execute_local_model(request).await
         } else {
-            self.execute_cloud_model(request).await?
+            self.execute_cloud_model(request).await
         };
 
-        let execution_time = start_time.elapsed().as_millis() as u64;
+        let elapsed = start_time.elapsed();
+        let execution_time = elapsed.as_millis() as u64;
+
+        let outcome = if result.is_ok() { "success" } else { "error" };
+        self.metrics
+            .inc(metrics::REQUESTS_TOTAL, &[("subsystem", "models"), ("operation", "execute"), ("outcome", outcome)])
+            .await;
+        let model_label = self.metrics_model_label(model_id).await;
+        self.metrics
+            .observe(metrics::MODEL_LATENCY_SECONDS, &[("model", &model_label)], elapsed.as_secs_f64())
+            .await;
 
         // Update performance metrics
-        self.update_performance_metrics(model_id, execution_time, true).await;
+        match result {
+            Ok(mut response) => {
+                response.response_time_ms = execution_time;
+                self.update_performance_metrics(model_id, execution_time, response.tokens_used, true).await;
+                if !is_local {
+                    self.record_spend(model_id, response.tokens_used, reserved_usd).await;
+                }
+                Ok(response)
+            }
+            Err(e) => {
+                self.update_performance_metrics(model_id, execution_time, 0, false).await;
+                if !is_local {
+                    self.release_spend(reserved_usd).await;
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Estimated cloud spend for today and since startup
+    pub async fn get_spend_summary(&self) -> SpendSummary {
+        let today = chrono::Utc::now().date_naive();
+        let mut spend = self.spend.write().await;
+        spend.roll_over(today);
+
+        SpendSummary {
+            day: today,
+            daily_spend_usd: spend.daily_usd,
+            daily_budget_usd: self.config.daily_budget_usd,
+            total_spend_usd: spend.total_usd,
+            by_model: spend.by_model.clone(),
+        }
+    }
+
+    /// Reserve the estimated cost of a cloud call, rejecting it once today's spend,
+    /// counting calls still in flight, has reached the budget. Returns the amount reserved.
+    async fn reserve_spend(&self, model_id: &str, task: &str) -> MisaResult<f64> {
+        let cost_per_million = self
+            .cloud_models
+            .read()
+            .await
+            .get(model_id)
+            .map_or(0.0, |model| model.cost_per_million_tokens as f64);
+        // Roughly four characters per prompt token
+        let estimated_tokens = task.len() as u64 / 4 + RESERVED_COMPLETION_TOKENS;
+        let estimate = estimated_tokens as f64 * cost_per_million / 1_000_000.0;
+
+        let mut spend = self.spend.write().await;
+        spend.roll_over(chrono::Utc::now().date_naive());
+        if let Some(budget) = self.config.daily_budget_usd {
+            if spend.daily_usd + spend.reserved_usd >= budget {
+                return Err(MisaError::RateLimit(format!(
+                    "Daily cloud budget of ${:.2} exhausted (${:.4} spent, ${:.4} in flight)",
+                    budget, spend.daily_usd, spend.reserved_usd
+                )));
+            }
+        }
+        spend.reserved_usd += estimate;
+
+        Ok(estimate)
+    }
+
+    /// Give back a reservation for a call that produced nothing billable
+    async fn release_spend(&self, reserved_usd: f64) {
+        let mut spend = self.spend.write().await;
+        spend.reserved_usd = (spend.reserved_usd - reserved_usd).max(0.0);
+    }
+
+    /// Replace a call's reservation with what it actually cost
+    async fn record_spend(&self, model_id: &str, tokens_used: u32, reserved_usd: f64) {
+        self.release_spend(reserved_usd).await;
+        let cost_per_million = match self.cloud_models.read().await.get(model_id) {
+            Some(model) => model.cost_per_million_tokens,
+            None => return,
+        };
+        let cost = tokens_used as f64 * cost_per_million as f64 / 1_000_000.0;
+
+        let mut spend = self.spend.write().await;
+        spend.roll_over(chrono::Utc::now().date_naive());
+        spend.daily_usd += cost;
+        spend.total_usd += cost;
+
+        let model_spend = spend.by_model.entry(model_id.to_string()).or_default();
+        model_spend.requests += 1;
+        model_spend.tokens += tokens_used as u64;
+        model_spend.cost_usd += cost;
+
+        if let Some(path) = &self.spend_path {
+            if let Err(e) = write_json_atomic(path, &*spend).await {
+                warn!("Failed to save cloud spend to {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    async fn model_type_of(&self, model_id: &str) -> ModelType {
+        if let Some(model) = self.local_models.read().await.get(model_id) {
+            return model.model_type.clone();
+        }
+        if let Some(model) = self.cloud_models.read().await.get(model_id) {
+            return model.model_type.clone();
+        }
+        Self::classify_model_type(model_id)
+    }
+
+    /// Catalog models label metrics by id; anything else a caller passes in is counted as "other"
+    async fn metrics_model_label(&self, model_id: &str) -> String {
+        let known = self.local_models.read().await.contains_key(model_id)
+            || self.cloud_models.read().await.contains_key(model_id);
+        if known { model_id.to_string() } else { metrics::OTHER_LABEL.to_string() }
+    }
 
-        Ok(serde_json::to_value(response)?)
+    /// Round-trip time to the local model server
+    pub async fn ping_local_server(&self, timeout: std::time::Duration) -> MisaResult<std::time::Duration> {
+        self.ollama_client.ping(timeout).await
     }
 
     /// Shutdown the model manager
     pub async fn shutdown(&self) -> MisaResult<()> {
         info!("Shutting down model manager");
 
+        self.scheduler.cancel(CATALOG_REFRESH_JOB).await;
+
         // Unload all local models
         let local_models = self.local_models.read().await;
         for model in local_models.values() {
@@ -340,7 +917,22 @@ impl ModelManager {
 
     /// Helper methods
 
-    fn classify_model_type(&self, model_name: &str) -> ModelType {
+    fn local_model_from_info(model_info: OllamaModelInfo) -> LocalModel {
+        LocalModel {
+            id: model_info.name.clone(),
+            model_type: Self::classify_model_type(&model_info.name),
+            capabilities: Self::infer_model_capabilities(&model_info.name),
+            name: model_info.name,
+            size_gb: model_info.size as f32 / 1024.0, // Convert bytes to GB
+            quantization: "Q4_0".to_string(), // Default assumption
+            parameters: "unknown".to_string(),
+            device_preference: DevicePreference::Hybrid,
+            loaded: false,
+            available: true,
+        }
+    }
+
+    fn classify_model_type(model_name: &str) -> ModelType {
         let name_lower = model_name.to_lowercase();
 
         if name_lower.contains("codellama") || name_lower.contains("wizardcoder") {
@@ -356,7 +948,7 @@ impl ModelManager {
         }
     }
 
-    fn infer_model_capabilities(&self, model_name: &str) -> ModelCapabilities {
+    fn infer_model_capabilities(model_name: &str) -> ModelCapabilities {
         let name_lower = model_name.to_lowercase();
 
         ModelCapabilities {
@@ -376,18 +968,38 @@ impl ModelManager {
     }
 
     fn get_cloud_model_capabilities(&self, provider: &str, model: &str) -> ModelCapabilities {
-        match (provider, model) {
-            ("openai", "gpt-4") => ModelCapabilities {
+        if provider == "openai" {
+            if let Some(family) = openai_model_family(model) {
+                let mut specialties = vec!["reasoning".to_string(), "coding".to_string()];
+                if family.supports_vision {
+                    specialties.push("vision".to_string());
+                }
+
+                return ModelCapabilities {
+                    supports_functions: family.supports_functions,
+                    supports_vision: family.supports_vision,
+                    supports_streaming: true,
+                    max_context_length: family.context_length,
+                    supports_system_prompts: true,
+                    supports_json_mode: family.supports_json_mode,
+                    languages: vec!["en".to_string(), "zh".to_string(), "es".to_string()],
+                    specialties,
+                };
+            }
+        }
+
+        match provider {
+            "anthropic" => ModelCapabilities {
                 supports_functions: true,
-                supports_vision: model.contains("vision"),
+                supports_vision: model.starts_with("claude-3"),
                 supports_streaming: true,
-                max_context_length: 8192,
+                max_context_length: 200000,
                 supports_system_prompts: true,
-                supports_json_mode: true,
+                supports_json_mode: false,
                 languages: vec!["en".to_string(), "zh".to_string(), "es".to_string()],
-                specialties: vec!["reasoning".to_string(), "coding".to_string()],
+                specialties: vec!["reasoning".to_string(), "coding".to_string(), "writing".to_string()],
             },
-            _ => self.infer_model_capabilities(model),
+            _ => Self::infer_model_capabilities(model),
         }
     }
 
@@ -395,14 +1007,17 @@ impl ModelManager {
         match (provider, model) {
             ("openai", "gpt-4") => 30.0,
             ("openai", "gpt-3.5-turbo") => 2.0,
+            ("anthropic", m) if m.contains("opus") => 15.0,
+            ("anthropic", m) if m.contains("sonnet") => 3.0,
+            ("anthropic", m) if m.contains("haiku") => 0.25,
             _ => 5.0, // Default cost
         }
     }
 
     fn get_model_context_length(&self, provider: &str, model: &str) -> usize {
         match (provider, model) {
-            ("openai", "gpt-4") => 8192,
-            ("openai", "gpt-3.5-turbo") => 4096,
+            ("openai", _) => openai_model_family(model).map_or(4096, |family| family.context_length),
+            ("anthropic", _) => 200000,
             _ => 4096,
         }
     }
@@ -425,7 +1040,7 @@ impl ModelManager {
         // Add local models
         let local_models = self.local_models.read().await;
         for (id, model) in local_models.iter() {
-            if model.model_type == *model_type {
+            if model.available && model.model_type == *model_type {
                 models.push(id.clone());
             }
         }
@@ -441,40 +1056,100 @@ impl ModelManager {
         Ok(models)
     }
 
+    /// Score `candidates` for a task of `model_type`. Urgent tasks lean towards fast
+    /// models and low-priority ones towards cheap models; models specialised in the
+    /// task count towards quality.
     async fn rank_models_for_task(
         &self,
         candidates: Vec<String>,
-        device_preferences: Option<&[String]>,
+        model_type: &ModelType,
         priority: &TaskPriority,
-    ) -> MisaResult<String> {
+    ) -> MisaResult<Vec<ModelScore>> {
         if candidates.is_empty() {
             return Err(MisaError::Model("No candidate models available".to_string()));
         }
 
-        // Simple ranking: prefer local models first, then by performance
+        // Each component is in [0, 1]; the score is their weighted mean
+        let preferences = &self.config.switching_preferences;
+        let local_bias = if preferences.prefer_local { preferences.local_bias as f64 } else { 0.0 };
+        let (latency_factor, cost_factor) = match priority {
+            TaskPriority::Low => (0.5, 2.0),
+            TaskPriority::Normal => (1.0, 1.0),
+            TaskPriority::High => (2.0, 0.5),
+            TaskPriority::Critical => (4.0, 0.25),
+        };
+        let weights = [
+            local_bias,
+            preferences.quality_weight as f64,
+            preferences.quality_weight as f64,
+            preferences.latency_weight as f64 * latency_factor,
+            preferences.cost_weight as f64 * cost_factor,
+        ];
+        let total_weight: f64 = weights.iter().sum();
         let mut scored_models = Vec::new();
 
         for candidate in candidates {
-            let mut score = 0.0;
+            let is_local = self.is_local_model(&candidate);
+            let metrics = self.get_performance_metrics(&candidate).await;
+
+            // Models without a track record sit in the middle
+            let quality = metrics.as_ref().map_or(UNMEASURED_COMPONENT, |metrics| metrics.success_rate as f64);
+            let latency = metrics.as_ref().map_or(UNMEASURED_COMPONENT, |metrics| {
+                1.0 / (1.0 + metrics.avg_response_time_ms.max(0.0) / REFERENCE_LATENCY_MS)
+            });
+            let cost = if is_local {
+                1.0
+            } else {
+                let cost_per_million = self
+                    .cloud_models
+                    .read()
+                    .await
+                    .get(&candidate)
+                    .map_or(REFERENCE_COST_PER_MILLION, |model| model.cost_per_million_tokens as f64);
+                1.0 / (1.0 + cost_per_million.max(0.0) / REFERENCE_COST_PER_MILLION)
+            };
+            let specialised = match (Self::specialty(model_type), self.capabilities_of(&candidate).await) {
+                (Some(specialty), Some(capabilities)) => capabilities.specialties.iter().any(|s| s == specialty),
+                _ => false,
+            };
+            let components = [
+                if is_local { 1.0 } else { 0.0 },
+                quality,
+                if specialised { 1.0 } else { 0.0 },
+                latency,
+                cost,
+            ];
+
+            let score = if total_weight > 0.0 {
+                weights.iter().zip(components).map(|(weight, component)| weight * component).sum::<f64>() / total_weight
+            } else {
+                0.0
+            };
+            scored_models.push(ModelScore { model_id: candidate, score });
+        }
 
-            // Prefer local models if configured
-            if self.is_local_model(&candidate) && self.config.switching_preferences.prefer_local {
-                score += 10.0;
-            }
+        // Sort by score (descending)
+        scored_models.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
 
-            // Add performance score
-            if let Some(metrics) = self.get_performance_metrics(&candidate).await {
-                score += metrics.success_rate as f64 * 5.0;
-                score += 1000.0 / (metrics.avg_response_time_ms + 1.0);
-            }
+        Ok(scored_models)
+    }
 
-            scored_models.push((candidate, score));
+    /// Specialty a model lists when it is suited to tasks of `model_type`
+    fn specialty(model_type: &ModelType) -> Option<&'static str> {
+        match model_type {
+            ModelType::Coding => Some("coding"),
+            ModelType::Vision => Some("vision"),
+            ModelType::Reasoning => Some("reasoning"),
+            ModelType::Summarization => Some("writing"),
+            _ => None,
         }
+    }
 
-        // Sort by score (descending)
-        scored_models.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-
-        Ok(scored_models[0].0.clone())
+    async fn capabilities_of(&self, model_id: &str) -> Option<ModelCapabilities> {
+        if let Some(model) = self.local_models.read().await.get(model_id) {
+            return Some(model.capabilities.clone());
+        }
+        self.cloud_models.read().await.get(model_id).map(|model| model.capabilities.clone())
     }
 
     fn is_local_model(&self, model_id: &str) -> bool {
@@ -484,13 +1159,8 @@ impl ModelManager {
     async fn load_local_model(&self, model_id: &str) -> MisaResult<()> {
         info!("Loading local model: {}", model_id);
 
-        match self.ollama_client.pull_model(model_id).await {
+        match self.pull_local_model(model_id).await {
             Ok(_) => {
-                // Update model status
-                let mut local_models = self.local_models.write().await;
-                if let Some(model) = local_models.get_mut(model_id) {
-                    model.loaded = true;
-                }
                 info!("Local model loaded: {}", model_id);
                 Ok(())
             }
@@ -501,6 +1171,61 @@ impl ModelManager {
         }
     }
 
+    /// Pull a local model, publishing its progress as catalog events
+    ///
+    /// Tasks that find the same model missing together share one pull.
+    async fn pull_local_model(&self, model_id: &str) -> MisaResult<()> {
+        let leader = {
+            let mut in_flight = self.pulls_in_flight.lock().unwrap();
+            match in_flight.get(model_id) {
+                // A pull whose task was dropped never reports, so it doesn't count
+                Some(pull) if pull.has_changed().is_ok() => Err(pull.clone()),
+                _ => {
+                    let (done_tx, done_rx) = watch::channel(None);
+                    in_flight.insert(model_id.to_string(), done_rx);
+                    Ok(done_tx)
+                }
+            }
+        };
+
+        match leader {
+            Ok(done_tx) => {
+                let result = self.run_pull(model_id).await;
+                self.pulls_in_flight.lock().unwrap().remove(model_id);
+                let _ = done_tx.send(Some(result.is_ok()));
+                result
+            }
+            Err(mut pull) => {
+                debug!("Waiting for the pull of {} already in progress", model_id);
+                match pull.wait_for(Option::is_some).await.map(|done| *done) {
+                    Ok(Some(true)) => Ok(()),
+                    _ => Err(MisaError::Model(format!("Pull of {} failed", model_id))),
+                }
+            }
+        }
+    }
+
+    async fn run_pull(&self, model_id: &str) -> MisaResult<()> {
+        self.ollama_client
+            .pull_model(model_id, |progress| {
+                // Having no subscribers right now is not an error
+                let _ = self.catalog_events.send(ModelCatalogEvent::PullProgress {
+                    model_id: model_id.to_string(),
+                    status: progress.status.clone(),
+                    completed: progress.completed,
+                    total: progress.total,
+                });
+            })
+            .await?;
+
+        // Update model status
+        let mut local_models = self.local_models.write().await;
+        if let Some(model) = local_models.get_mut(model_id) {
+            model.loaded = true;
+        }
+        Ok(())
+    }
+
     async fn unload_local_model(&self, model_id: &str) -> MisaResult<()> {
         info!("Unloading local model: {}", model_id);
 
@@ -513,8 +1238,14 @@ impl ModelManager {
     }
 
     async fn execute_local_model(&self, request: ModelRequest) -> MisaResult<ModelResponse> {
-        let model_id = request.model_id.as_ref().unwrap();
-        self.ollama_client.generate_response(request).await
+        match self.ollama_client.generate_response(request.clone()).await {
+            Err(MisaError::ModelNotFound(model_id)) if self.config.auto_pull_models => {
+                info!("Model {} is not pulled yet, pulling it before retrying", model_id);
+                self.pull_local_model(&model_id).await?;
+                self.ollama_client.generate_response(request).await
+            }
+            result => result,
+        }
     }
 
     async fn execute_cloud_model(&self, request: ModelRequest) -> MisaResult<ModelResponse> {
@@ -535,12 +1266,18 @@ impl ModelManager {
         metrics.get(model_id).cloned()
     }
 
-    async fn update_performance_metrics(&self, model_id: &str, response_time_ms: u64, success: bool) {
+    async fn update_performance_metrics(&self, model_id: &str, response_time_ms: u64, tokens_used: u32, success: bool) {
+        let tokens_per_second = if response_time_ms > 0 {
+            tokens_used as f32 / (response_time_ms as f32 / 1000.0)
+        } else {
+            0.0
+        };
+
         let mut metrics = self.performance_metrics.write().await;
         let entry = metrics.entry(model_id.to_string()).or_insert_with(|| ModelPerformance {
             avg_response_time_ms: response_time_ms as f64,
             success_rate: if success { 1.0 } else { 0.0 },
-            tokens_per_second: 0.0,
+            tokens_per_second,
             memory_usage_mb: 0,
             energy_efficiency: 1.0,
             last_used: chrono::Utc::now(),
@@ -554,9 +1291,21 @@ impl ModelManager {
         let alpha = 0.1; // Smoothing factor
         entry.avg_response_time_ms = alpha * response_time_ms as f64 + (1.0 - alpha) * entry.avg_response_time_ms;
         entry.success_rate = alpha * if success { 1.0 } else { 0.0 } + (1.0 - alpha) * entry.success_rate;
+        if tokens_used > 0 {
+            entry.tokens_per_second = alpha as f32 * tokens_per_second + (1.0 - alpha as f32) * entry.tokens_per_second;
+        }
     }
 }
 
+/// Ranking component given to a model with no performance metrics yet
+const UNMEASURED_COMPONENT: f64 = 0.5;
+
+/// Average response time that halves a model's latency component
+const REFERENCE_LATENCY_MS: f64 = 1000.0;
+
+/// Price per million tokens that halves a cloud model's cost component
+const REFERENCE_COST_PER_MILLION: f64 = 5.0;
+
 // Implement Clone for required types
 impl Clone for ModelManager {
     fn clone(&self) -> Self {
@@ -566,74 +1315,391 @@ impl Clone for ModelManager {
             cloud_models: Arc::clone(&self.cloud_models),
             current_model: Arc::clone(&self.current_model),
             performance_metrics: Arc::clone(&self.performance_metrics),
-            ollama_client: OllamaClient::new(self.config.local_server_url.clone()),
+            ollama_client: self.ollama_client.clone(),
             cloud_clients: Arc::clone(&self.cloud_clients),
+            execution_limiter: Arc::clone(&self.execution_limiter),
+            metrics: self.metrics.clone(),
+            scheduler: self.scheduler.clone(),
+            catalog_events: self.catalog_events.clone(),
+            spend: Arc::clone(&self.spend),
+            spend_path: self.spend_path.clone(),
+            privacy_controls: self.privacy_controls.clone(),
+            pulls_in_flight: Arc::clone(&self.pulls_in_flight),
         }
     }
 }
 
-// Ollama client implementation
-impl OllamaClient {
-    pub fn new(base_url: String) -> Self {
-        Self {
-            base_url,
-            client: reqwest::Client::new(),
+impl SpendTracker {
+    /// Start a new daily total when the UTC day has changed
+    fn roll_over(&mut self, today: chrono::NaiveDate) {
+        if self.day != Some(today) {
+            self.day = Some(today);
+            self.daily_usd = 0.0;
         }
     }
+}
 
-    pub async fn list_models(&self) -> Result<Vec<OllamaModelInfo>, Box<dyn std::error::Error + Send + Sync>> {
-        let url = format!("{}/api/tags", self.base_url);
-        let response: OllamaListResponse = self.client.get(&url).send().await?.json().await?;
-        Ok(response.models)
+impl ExecutionLimiter {
+    fn new(config: &ModelConcurrencyConfig) -> Self {
+        Self {
+            local: ExecutionPool::new(config.max_concurrent_local),
+            cloud: ExecutionPool::new(config.max_concurrent_cloud),
+            max_queue_depth: config.max_queue_depth,
+        }
     }
 
-    pub async fn pull_model(&self, model_name: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let url = format!("{}/api/pull", self.base_url);
-        let request = OllamaPullRequest {
-            name: model_name.to_string(),
-        };
+    async fn acquire(&self, local: bool) -> MisaResult<OwnedSemaphorePermit> {
+        let pool = if local { &self.local } else { &self.cloud };
 
-        self.client.post(&url).json(&request).send().await?;
-        Ok(())
+        if let Ok(permit) = Arc::clone(&pool.semaphore).try_acquire_owned() {
+            return Ok(permit);
+        }
+
+        // Frees the queue slot however the wait ends, including the caller being cancelled
+        let slot = QueueSlot::take(&pool.queued);
+        if slot.position >= self.max_queue_depth {
+            return Err(MisaError::Overloaded(format!(
+                "{} model queue is full ({} waiting)",
+                if local { "Local" } else { "Cloud" },
+                slot.position
+            )));
+        }
+
+        let permit = Arc::clone(&pool.semaphore).acquire_owned().await;
+        drop(slot);
+
+        permit.map_err(|_| MisaError::Internal("Model execution limiter closed".to_string()))
     }
+}
 
-    pub async fn generate_response(&self, request: ModelRequest) -> MisaResult<ModelResponse> {
-        let url = format!("{}/api/generate", self.base_url);
-        let ollama_request = OllamaGenerateRequest {
-            model: request.model_id.unwrap_or_default(),
-            prompt: request.prompt,
-            stream: false,
-            options: serde_json::json!({
-                "temperature": request.temperature.unwrap_or(0.7),
-                "num_predict": request.max_tokens.unwrap_or(1000)
-            }),
-        };
+/// A place in an execution pool's queue, given back when dropped
+struct QueueSlot<'a> {
+    queued: &'a AtomicUsize,
+    /// Callers already waiting when this slot was taken
+    position: usize,
+}
 
-        let response: OllamaGenerateResponse = self.client
-            .post(&url)
-            .json(&ollama_request)
+impl<'a> QueueSlot<'a> {
+    fn take(queued: &'a AtomicUsize) -> Self {
+        let position = queued.fetch_add(1, Ordering::SeqCst);
+        Self { queued, position }
+    }
+}
+
+impl Drop for QueueSlot<'_> {
+    fn drop(&mut self) {
+        self.queued.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl ExecutionPool {
+    fn new(max_concurrent: usize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_concurrent.max(1))),
+            queued: AtomicUsize::new(0),
+        }
+    }
+}
+
+// Ollama client implementation
+impl OllamaClient {
+    pub fn new(base_url: String) -> MisaResult<Self> {
+        Self::with_config(base_url, &OllamaClientConfig::default())
+    }
+
+    /// Create a client, rejecting base URLs that aren't absolute http(s) URLs
+    pub fn with_config(base_url: String, config: &OllamaClientConfig) -> MisaResult<Self> {
+        let parsed = reqwest::Url::parse(&base_url)
+            .map_err(|e| MisaError::Configuration(format!("Invalid Ollama URL {}: {}", base_url, e)))?;
+        if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+            return Err(MisaError::Configuration(format!(
+                "Ollama URL must be an http(s) URL with a host, got {}",
+                base_url
+            )));
+        }
+
+        // The request timeout is applied per request so streams aren't cut off mid-generation
+        let client = reqwest::Client::builder()
+            .connect_timeout(std::time::Duration::from_millis(config.connect_timeout_ms))
+            .build()
+            .map_err(|e| MisaError::Configuration(format!("Failed to build Ollama client: {}", e)))?;
+
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client,
+            request_timeout: std::time::Duration::from_millis(config.request_timeout_ms),
+            max_retries: config.max_retries,
+            retry_backoff: std::time::Duration::from_millis(config.retry_backoff_ms),
+        })
+    }
+
+    /// Send a request, retrying with backoff while the server is unreachable or unavailable.
+    /// Timeouts are not retried so a hung server fails within one request timeout.
+    async fn send_with_retry(
+        &self,
+        url: &str,
+        build: impl Fn() -> reqwest::RequestBuilder,
+    ) -> MisaResult<reqwest::Response> {
+        let mut attempt = 0;
+        loop {
+            let result = build().timeout(self.request_timeout).send().await;
+            let retryable = match &result {
+                Ok(response) => matches!(response.status().as_u16(), 502 | 503 | 504),
+                Err(e) => e.is_connect(),
+            };
+
+            if !retryable || attempt >= self.max_retries {
+                return result
+                    .and_then(|response| response.error_for_status())
+                    .map_err(|e| self.network_error(url, e));
+            }
+
+            let delay = self.retry_backoff * 2u32.pow(attempt);
+            warn!("Ollama request to {} failed, retrying in {:?}", url, delay);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Read a JSON response body; a body that doesn't decode is a parse error, not a network one
+    async fn decode_response<T: serde::de::DeserializeOwned>(&self, url: &str, response: reqwest::Response) -> MisaResult<T> {
+        let body = response.bytes().await.map_err(|e| self.network_error(url, e))?;
+        serde_json::from_slice(&body).map_err(|e| MisaError::Parse(format!("Malformed response from {}: {}", url, e)))
+    }
+
+    fn network_error(&self, url: &str, error: reqwest::Error) -> MisaError {
+        if error.is_timeout() {
+            MisaError::NetworkTimeout(format!("{} did not respond within {:?}", url, self.request_timeout))
+        } else {
+            MisaError::Network(error)
+        }
+    }
+
+    pub async fn list_models(&self) -> Result<Vec<OllamaModelInfo>, Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!("{}/api/tags", self.base_url);
+        let response = self.send_with_retry(&url, || self.client.get(&url)).await?;
+        let response: OllamaListResponse = self.decode_response(&url, response).await?;
+        Ok(response.models)
+    }
+
+    /// Time a cheap request against the server to check it is up
+    pub async fn ping(&self, timeout: std::time::Duration) -> MisaResult<std::time::Duration> {
+        let url = format!("{}/api/tags", self.base_url);
+        let started = std::time::Instant::now();
+
+        self.client
+            .get(&url)
+            .timeout(timeout)
             .send()
             .await
-            .map_err(|e| MisaError::Network(e))?
-            .json()
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| self.network_error(&url, e))?;
+
+        Ok(started.elapsed())
+    }
+
+    /// Pull a model, passing each status line Ollama streams back to `on_progress`.
+    ///
+    /// Fails unless the server reports success before the stream ends.
+    pub async fn pull_model(&self, model_name: &str, mut on_progress: impl FnMut(&OllamaPullStatus)) -> MisaResult<()> {
+        let url = format!("{}/api/pull", self.base_url);
+        let request = OllamaPullRequest {
+            name: model_name.to_string(),
+        };
+
+        // Downloads can take far longer than the request timeout, so it bounds each wait for
+        // the server to answer or report progress rather than the whole pull
+        let stalled = || MisaError::Timeout(format!("Pull of {} stalled", model_name));
+        let mut response = tokio::time::timeout(self.request_timeout, self.client.post(&url).json(&request).send())
             .await
-            .map_err(|e| MisaError::Serialization(e))?;
+            .map_err(|_| stalled())?
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| self.network_error(&url, e))?;
+
+        let mut buffer = Vec::new();
+        let mut finished = false;
+        let mut succeeded = false;
+        while !finished {
+            let chunk = tokio::time::timeout(self.request_timeout, response.chunk())
+                .await
+                .map_err(|_| stalled())?;
+            match chunk.map_err(|e| self.network_error(&url, e))? {
+                Some(bytes) => buffer.extend_from_slice(&bytes),
+                None => {
+                    // Terminate a trailing line so it is parsed below
+                    buffer.push(b'\n');
+                    finished = true;
+                }
+            }
+
+            while let Some(pos) = buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=pos).collect();
+                if let Some(progress) = parse_pull_line(model_name, &line)? {
+                    succeeded |= progress.status == "success";
+                    on_progress(&progress);
+                }
+            }
+        }
+
+        if !succeeded {
+            return Err(MisaError::Model(format!("Pull of {} ended before it succeeded", model_name)));
+        }
+        Ok(())
+    }
+
+    pub async fn generate_response(&self, request: ModelRequest) -> MisaResult<ModelResponse> {
+        let url = format!("{}/api/generate", self.base_url);
+        let ollama_request = OllamaGenerateRequest {
+            images: request_images(request.context.as_ref()),
+            model: request.model_id.unwrap_or_default(),
+            prompt: request.prompt,
+            stream: false,
+            options: serde_json::json!({
+                "temperature": request.temperature.unwrap_or(0.7),
+                "num_predict": request.max_tokens.unwrap_or(1000)
+            }),
+        };
+
+        let response = self
+            .send_with_retry(&url, || self.client.post(&url).json(&ollama_request))
+            .await
+            .map_err(|e| match e {
+                // Ollama answers 404 for models it knows of but hasn't pulled
+                MisaError::Network(error) if error.status() == Some(reqwest::StatusCode::NOT_FOUND) => {
+                    MisaError::ModelNotFound(ollama_request.model.clone())
+                }
+                e => e,
+            })?;
+        let response: OllamaGenerateResponse = self.decode_response(&url, response).await?;
 
         Ok(ModelResponse {
             content: response.response,
             model_id: response.model,
-            tokens_used: 0, // Ollama doesn't provide token count
-            response_time_ms: 0, // Should be measured at higher level
+            tokens_used: response.eval_count.unwrap_or(0) + response.prompt_eval_count.unwrap_or(0),
+            response_time_ms: 0, // Measured at higher level in execute_task
             finish_reason: response.done.to_string(),
             metadata: serde_json::json!({
                 "done": response.done,
                 "total_duration": response.total_duration,
-                "load_duration": response.load_duration
+                "load_duration": response.load_duration,
+                "eval_count": response.eval_count,
+                "prompt_eval_count": response.prompt_eval_count
             }),
         })
     }
 }
 
+impl OllamaClient {
+    pub async fn embed(&self, model: &str, text: &str) -> MisaResult<Vec<f32>> {
+        let url = format!("{}/api/embeddings", self.base_url);
+        let request = OllamaEmbeddingsRequest {
+            model: model.to_string(),
+            prompt: text.to_string(),
+        };
+
+        let response = self.send_with_retry(&url, || self.client.post(&url).json(&request)).await?;
+        let response: OllamaEmbeddingsResponse = self.decode_response(&url, response).await?;
+
+        if response.embedding.is_empty() {
+            return Err(MisaError::Model(format!("Model {} returned an empty embedding", model)));
+        }
+
+        Ok(response.embedding)
+    }
+}
+
+impl OllamaClient {
+    /// Stream a completion from Ollama, yielding `response` fragments as they arrive.
+    ///
+    /// Ollama emits one JSON object per line when `stream` is set; the final
+    /// object carries `done: true` and is yielded before the stream ends.
+    pub fn generate_stream(&self, request: ModelRequest) -> impl Stream<Item = MisaResult<String>> + Send + 'static {
+        let url = format!("{}/api/generate", self.base_url);
+        let client = self.client.clone();
+        let ollama_request = OllamaGenerateRequest {
+            images: request_images(request.context.as_ref()),
+            model: request.model_id.unwrap_or_default(),
+            prompt: request.prompt,
+            stream: true,
+            options: serde_json::json!({
+                "temperature": request.temperature.unwrap_or(0.7),
+                "num_predict": request.max_tokens.unwrap_or(1000)
+            }),
+        };
+
+        let chunks = futures_util::stream::once(async move {
+            client
+                .post(&url)
+                .json(&ollama_request)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map(|response| response.bytes_stream().map_err(MisaError::Network))
+                .map_err(MisaError::Network)
+        })
+        .try_flatten();
+
+        futures_util::stream::unfold(
+            (Box::pin(chunks), Vec::<u8>::new(), false),
+            |(mut chunks, mut buffer, finished)| async move {
+                if finished {
+                    return None;
+                }
+
+                loop {
+                    if let Some(pos) = buffer.iter().position(|b| *b == b'\n') {
+                        let line: Vec<u8> = buffer.drain(..=pos).collect();
+                        match parse_stream_line(&line) {
+                            Ok(None) => continue,
+                            Ok(Some(chunk)) => {
+                                let done = chunk.done;
+                                return Some((Ok(chunk.response), (chunks, buffer, done)));
+                            }
+                            Err(e) => return Some((Err(e), (chunks, buffer, true))),
+                        }
+                    }
+
+                    match chunks.next().await {
+                        Some(Ok(bytes)) => buffer.extend_from_slice(&bytes),
+                        Some(Err(e)) => return Some((Err(e), (chunks, buffer, true))),
+                        None => {
+                            // Flush a trailing line that wasn't newline-terminated
+                            let line = std::mem::take(&mut buffer);
+                            return match parse_stream_line(&line) {
+                                Ok(None) => None,
+                                Ok(Some(chunk)) => Some((Ok(chunk.response), (chunks, buffer, true))),
+                                Err(e) => Some((Err(e), (chunks, buffer, true))),
+                            };
+                        }
+                    }
+                }
+            },
+        )
+    }
+}
+
+/// Parse a single NDJSON status line from an Ollama pull, failing on reported errors
+fn parse_pull_line(model_name: &str, line: &[u8]) -> MisaResult<Option<OllamaPullStatus>> {
+    if line.iter().all(|b| b.is_ascii_whitespace()) {
+        return Ok(None);
+    }
+
+    let progress: OllamaPullStatus = serde_json::from_slice(line)?;
+    if let Some(error) = &progress.error {
+        return Err(MisaError::Model(format!("Failed to pull {}: {}", model_name, error)));
+    }
+    Ok(Some(progress))
+}
+
+/// Parse a single NDJSON line from a streaming Ollama response
+fn parse_stream_line(line: &[u8]) -> MisaResult<Option<OllamaGenerateResponse>> {
+    if line.iter().all(|b| b.is_ascii_whitespace()) {
+        return Ok(None);
+    }
+
+    Ok(Some(serde_json::from_slice(line)?))
+}
+
 // Cloud client implementation
 impl CloudClient {
     pub fn new(provider: String, config: crate::kernel::CloudProviderConfig) -> Self {
@@ -648,6 +1714,7 @@ impl CloudClient {
     pub async fn generate_response(&self, model: &str, request: ModelRequest) -> MisaResult<ModelResponse> {
         match self.provider.as_str() {
             "openai" => self.openai_generate(model, request).await,
+            "anthropic" => self.anthropic_generate(model, request).await,
             _ => Err(MisaError::Model(format!("Unsupported cloud provider: {}", self.provider))),
         }
     }
@@ -693,8 +1760,63 @@ impl CloudClient {
             metadata: response,
         })
     }
+
+    async fn anthropic_generate(&self, model: &str, request: ModelRequest) -> MisaResult<ModelResponse> {
+        let url = format!("{}/v1/messages", self.base_url);
+
+        let anthropic_request = serde_json::json!({
+            "model": model,
+            "messages": [{"role": "user", "content": request.prompt}],
+            "temperature": request.temperature.unwrap_or(0.7),
+            "max_tokens": request.max_tokens.unwrap_or(1000)
+        });
+
+        let response = self.client
+            .post(&url)
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_API_VERSION)
+            .json(&anthropic_request)
+            .send()
+            .await
+            .map_err(|e| MisaError::Network(e))?;
+
+        // Error bodies carry the reason, e.g. {"type":"error","error":{"message":"..."}}
+        let status = response.status();
+        if !status.is_success() {
+            let body: serde_json::Value = response.json().await.unwrap_or_default();
+            let message = body["error"]["message"].as_str().unwrap_or("no error message");
+            return Err(MisaError::ExternalService(format!("Anthropic API returned {}: {}", status, message)));
+        }
+
+        let response: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| MisaError::Model(format!("Invalid Anthropic response: {}", e)))?;
+
+        let content = response["content"][0]["text"]
+            .as_str()
+            .ok_or_else(|| MisaError::Model("Invalid Anthropic response format".to_string()))?;
+
+        let tokens_used = response["usage"]["input_tokens"].as_u64().unwrap_or(0)
+            + response["usage"]["output_tokens"].as_u64().unwrap_or(0);
+
+        Ok(ModelResponse {
+            content: content.to_string(),
+            model_id: format!("anthropic:{}", model),
+            tokens_used: tokens_used as u32,
+            response_time_ms: 0,
+            finish_reason: response["stop_reason"]
+                .as_str()
+                .unwrap_or("unknown")
+                .to_string(),
+            metadata: response,
+        })
+    }
 }
 
+/// Anthropic Messages API version sent with every request
+const ANTHROPIC_API_VERSION: &str = "2023-06-01";
+
 // Ollama API structs
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OllamaModelInfo {
@@ -714,12 +1836,44 @@ struct OllamaPullRequest {
     pub name: String,
 }
 
+/// One status line streamed back while Ollama pulls a model
+#[derive(Debug, Clone, Deserialize)]
+pub struct OllamaPullStatus {
+    pub status: String,
+    pub digest: Option<String>,
+    pub total: Option<u64>,
+    pub completed: Option<u64>,
+    pub error: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 struct OllamaGenerateRequest {
     pub model: String,
     pub prompt: String,
     pub stream: bool,
     pub options: serde_json::Value,
+    /// Base64-encoded images for multimodal models
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<String>,
+}
+
+/// Base64-encoded images a request's context carries under `images`
+fn request_images(context: Option<&serde_json::Value>) -> Vec<String> {
+    context
+        .and_then(|context| context["images"].as_array())
+        .map(|images| images.iter().filter_map(|image| image.as_str().map(str::to_string)).collect())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaEmbeddingsRequest {
+    pub model: String,
+    pub prompt: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaEmbeddingsResponse {
+    pub embedding: Vec<f32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -729,4 +1883,960 @@ struct OllamaGenerateResponse {
     pub done: bool,
     pub total_duration: Option<u64>,
     pub load_duration: Option<u64>,
-}
\ No newline at end of file
+    pub eval_count: Option<u32>,
+    pub prompt_eval_count: Option<u32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn test_request(prompt: &str) -> ModelRequest {
+        ModelRequest {
+            prompt: prompt.to_string(),
+            model_id: Some("mixtral".to_string()),
+            context: None,
+            stream: true,
+            max_tokens: None,
+            temperature: None,
+            tools: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generate_stream_yields_fragments() {
+        let server = MockServer::start().await;
+        let body = concat!(
+            r#"{"model":"mixtral","response":"Hel","done":false}"#, "\n",
+            r#"{"model":"mixtral","response":"lo","done":false}"#, "\n",
+            r#"{"model":"mixtral","response":"","done":true,"total_duration":42}"#, "\n",
+        );
+
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(body))
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::new(server.uri()).unwrap();
+        let fragments: Vec<String> = client
+            .generate_stream(test_request("Say hello"))
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(fragments, vec!["Hel".to_string(), "lo".to_string(), String::new()]);
+    }
+
+    #[tokio::test]
+    async fn test_execute_task_records_metrics() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/api/tags"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "models": [{"name": "mixtral", "size": 26, "digest": "abc", "modified_at": "2024-01-01T00:00:00Z"}]
+            })))
+            .mount(&server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({
+                        "model": "mixtral",
+                        "response": "Hello there",
+                        "done": true,
+                        "eval_count": 20,
+                        "prompt_eval_count": 5
+                    }))
+                    .set_delay(std::time::Duration::from_millis(20)),
+            )
+            .mount(&server)
+            .await;
+
+        let config = ModelConfig {
+            local_server_url: server.uri(),
+            cloud_providers: HashMap::new(),
+            ..ModelConfig::default()
+        };
+        let manager = ModelManager::new(config).await.unwrap();
+
+        let result = manager.execute_task("Say hello", "mixtral", None).await.unwrap();
+        assert_eq!(result["tokens_used"], 25);
+        assert!(result["response_time_ms"].as_u64().unwrap() >= 20);
+
+        let metrics = manager.get_performance_metrics("mixtral").await.unwrap();
+        assert_eq!(metrics.total_requests, 1);
+        assert!(metrics.avg_response_time_ms >= 20.0);
+        assert!(metrics.tokens_per_second > 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_images_in_the_context_reach_the_model() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .and(wiremock::matchers::body_partial_json(serde_json::json!({ "images": ["aW1hZ2U="] })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "model": "llava",
+                "response": "Meeting at 3pm",
+                "done": true
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::new(server.uri()).unwrap();
+        let request = ModelRequest {
+            context: Some(serde_json::json!({ "images": ["aW1hZ2U="] })),
+            stream: false,
+            ..test_request("Transcribe the text in this image")
+        };
+        let response = client.generate_response(request).await.unwrap();
+        assert_eq!(response.content, "Meeting at 3pm");
+    }
+
+    async fn mock_ollama_with_models(models: &[&str]) -> MockServer {
+        let server = MockServer::start().await;
+        let models: Vec<_> = models
+            .iter()
+            .map(|name| serde_json::json!({"name": name, "size": 26, "digest": "abc", "modified_at": "2024-01-01T00:00:00Z"}))
+            .collect();
+
+        Mock::given(method("GET"))
+            .and(path("/api/tags"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "models": models })))
+            .mount(&server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/api/pull"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"status": "success"})))
+            .mount(&server)
+            .await;
+
+        server
+    }
+
+    #[tokio::test]
+    async fn test_switch_to_known_local_model() {
+        let server = mock_ollama_with_models(&["mixtral", "codellama"]).await;
+        let config = ModelConfig {
+            local_server_url: server.uri(),
+            cloud_providers: HashMap::new(),
+            ..ModelConfig::default()
+        };
+        let manager = ModelManager::new(config).await.unwrap();
+
+        assert_eq!(manager.switch_model("codellama", None, None).await.unwrap(), "codellama");
+        assert_eq!(manager.current_model().await, "codellama");
+    }
+
+    #[tokio::test]
+    async fn test_refresh_catalog_picks_up_pulled_models() {
+        let server = MockServer::start().await;
+        let tags = |models: &[&str]| {
+            let models: Vec<_> = models
+                .iter()
+                .map(|name| serde_json::json!({"name": name, "size": 26, "digest": "abc", "modified_at": "2024-01-01T00:00:00Z"}))
+                .collect();
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({ "models": models }))
+        };
+
+        Mock::given(method("GET"))
+            .and(path("/api/tags"))
+            .respond_with(tags(&["mixtral", "codellama"]))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/tags"))
+            .respond_with(tags(&["mixtral", "llama3"]))
+            .mount(&server)
+            .await;
+
+        let config = ModelConfig {
+            local_server_url: server.uri(),
+            cloud_providers: HashMap::new(),
+            ..ModelConfig::default()
+        };
+        let manager = ModelManager::new(config).await.unwrap();
+        let mut events = manager.subscribe_catalog_events();
+
+        let diff = manager.refresh_catalog().await.unwrap();
+
+        assert_eq!(diff.added, vec!["llama3".to_string()]);
+        assert_eq!(diff.removed, vec!["codellama".to_string()]);
+        assert_eq!(events.try_recv().unwrap(), ModelCatalogEvent::ModelAdded { model_id: "llama3".to_string() });
+        assert_eq!(events.try_recv().unwrap(), ModelCatalogEvent::ModelRemoved { model_id: "codellama".to_string() });
+        assert!(manager.switch_model("codellama", None, None).await.is_err());
+
+        // Nothing changed since the last refresh
+        assert_eq!(manager.refresh_catalog().await.unwrap(), CatalogDiff::default());
+    }
+
+    #[tokio::test]
+    async fn test_switch_to_known_cloud_model() {
+        let server = mock_ollama_with_models(&[]).await;
+        let config = ModelConfig {
+            local_server_url: server.uri(),
+            cloud_providers: HashMap::from([(
+                "openai".to_string(),
+                crate::kernel::CloudProviderConfig {
+                    api_key: "test-key".to_string(),
+                    base_url: server.uri(),
+                    models: vec!["gpt-4".to_string()],
+                },
+            )]),
+            ..ModelConfig::default()
+        };
+        let manager = ModelManager::new(config).await.unwrap();
+
+        manager.switch_model("openai:gpt-4", None, None).await.unwrap();
+        assert_eq!(manager.current_model().await, "openai:gpt-4");
+    }
+
+    #[tokio::test]
+    async fn test_switch_to_unknown_model_keeps_current() {
+        let server = mock_ollama_with_models(&["mixtral"]).await;
+        let config = ModelConfig {
+            local_server_url: server.uri(),
+            cloud_providers: HashMap::new(),
+            ..ModelConfig::default()
+        };
+        let manager = ModelManager::new(config).await.unwrap();
+        manager.switch_model("mixtral", None, None).await.unwrap();
+
+        let result = manager.switch_model("openai:gpt-9", None, None).await;
+        match result {
+            Err(MisaError::Model(message)) => assert!(message.contains("unknown model: openai:gpt-9")),
+            other => panic!("expected unknown model error, got {:?}", other),
+        }
+        assert!(manager.switch_model("llama-unknown", None, None).await.is_err());
+        assert_eq!(manager.current_model().await, "mixtral");
+    }
+
+    #[tokio::test]
+    async fn test_unknown_models_share_one_metrics_label() {
+        let server = mock_ollama_with_models(&["mixtral"]).await;
+        let config = ModelConfig {
+            local_server_url: server.uri(),
+            cloud_providers: HashMap::new(),
+            ..ModelConfig::default()
+        };
+        let manager = ModelManager::new(config).await.unwrap();
+
+        assert_eq!(manager.metrics_model_label("mixtral").await, "mixtral");
+        assert_eq!(manager.metrics_model_label("llama-unknown").await, "other");
+        assert_eq!(manager.metrics_model_label("openai:gpt-9").await, "other");
+    }
+
+    #[tokio::test]
+    async fn test_failed_local_task_falls_back_to_cloud_model() {
+        let server = mock_ollama_with_models(&["mixtral"]).await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "msg_01",
+                "type": "message",
+                "role": "assistant",
+                "content": [{"type": "text", "text": "Hi from the cloud"}],
+                "stop_reason": "end_turn",
+                "usage": {"input_tokens": 3, "output_tokens": 4}
+            })))
+            .mount(&server)
+            .await;
+
+        let mut config = ModelConfig {
+            local_server_url: server.uri(),
+            cloud_providers: HashMap::from([(
+                "anthropic".to_string(),
+                crate::kernel::CloudProviderConfig {
+                    api_key: "test-key".to_string(),
+                    base_url: server.uri(),
+                    models: vec!["claude-3-haiku-20240307".to_string()],
+                },
+            )]),
+            ..ModelConfig::default()
+        };
+        config.switching_preferences.allow_cloud_fallback = true;
+        config.switching_preferences.cloud_consent_user_id = Some("user-1".to_string());
+        let data_dir = tempfile::tempdir().unwrap();
+        let privacy_controls = PrivacyControls::new(crate::kernel::SecurityConfig::default(), data_dir.path().to_str().unwrap())
+            .await
+            .unwrap();
+        let manager = ModelManager::new(config).await.unwrap().with_privacy_controls(privacy_controls.clone());
+
+        // Nothing leaves the machine until the user consents to cloud processing
+        assert!(manager.execute_task("Say hello", "mixtral", None).await.is_err());
+        assert!(manager.get_performance_metrics("anthropic:claude-3-haiku-20240307").await.is_none());
+
+        privacy_controls.insert_test_consent("user-1", ConsentType::CloudProcessing).await;
+        let result = manager.execute_task("Say hello", "mixtral", None).await.unwrap();
+        assert_eq!(result["content"], "Hi from the cloud");
+        assert_eq!(result["model_id"], "anthropic:claude-3-haiku-20240307");
+
+        let local_metrics = manager.get_performance_metrics("mixtral").await.unwrap();
+        assert_eq!(local_metrics.total_requests, 2);
+        assert_eq!(local_metrics.success_rate, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_no_fallback_when_cloud_fallback_disabled() {
+        let server = mock_ollama_with_models(&["mixtral"]).await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let mut config = ModelConfig {
+            local_server_url: server.uri(),
+            cloud_providers: HashMap::from([(
+                "anthropic".to_string(),
+                crate::kernel::CloudProviderConfig {
+                    api_key: "test-key".to_string(),
+                    base_url: server.uri(),
+                    models: vec!["claude-3-haiku-20240307".to_string()],
+                },
+            )]),
+            ..ModelConfig::default()
+        };
+        config.switching_preferences.allow_cloud_fallback = false;
+        let manager = ModelManager::new(config).await.unwrap();
+
+        assert!(manager.execute_task("Say hello", "mixtral", None).await.is_err());
+        assert!(manager.get_performance_metrics("anthropic:claude-3-haiku-20240307").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_embed_returns_model_dimensions() {
+        let server = mock_ollama_with_models(&[]).await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [0.1, -0.2, 0.3, 0.4, 0.0, 0.9]
+            })))
+            .mount(&server)
+            .await;
+
+        let config = ModelConfig {
+            local_server_url: server.uri(),
+            cloud_providers: HashMap::new(),
+            ..ModelConfig::default()
+        };
+        let manager = ModelManager::new(config).await.unwrap();
+
+        let embedding = manager.embed("hello world").await.unwrap();
+        assert_eq!(embedding.len(), 6);
+        assert_eq!(embedding[5], 0.9);
+    }
+
+    async fn mock_slow_ollama(delay_ms: u64) -> MockServer {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/api/tags"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "models": [] })))
+            .mount(&server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"model": "mixtral", "response": "ok", "done": true}))
+                    .set_delay(std::time::Duration::from_millis(delay_ms)),
+            )
+            .mount(&server)
+            .await;
+
+        server
+    }
+
+    fn limited_config(server: &MockServer, max_queue_depth: usize) -> ModelConfig {
+        ModelConfig {
+            local_server_url: server.uri(),
+            cloud_providers: HashMap::new(),
+            concurrency: ModelConcurrencyConfig {
+                max_concurrent_local: 1,
+                max_concurrent_cloud: 1,
+                max_queue_depth,
+            },
+            ..ModelConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limit_serializes_requests() {
+        let server = mock_slow_ollama(100).await;
+        let manager = ModelManager::new(limited_config(&server, 4)).await.unwrap();
+
+        let start = std::time::Instant::now();
+        let (first, second) = tokio::join!(
+            manager.execute_task("one", "mixtral", None),
+            manager.execute_task("two", "mixtral", None),
+        );
+
+        assert!(first.is_ok());
+        assert!(second.is_ok());
+        assert!(start.elapsed() >= std::time::Duration::from_millis(200));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_waiter_frees_its_queue_slot() {
+        let limiter = ExecutionLimiter::new(&ModelConcurrencyConfig {
+            max_concurrent_local: 1,
+            max_concurrent_cloud: 1,
+            max_queue_depth: 1,
+        });
+        let running = limiter.acquire(true).await.unwrap();
+
+        let waited = tokio::time::timeout(std::time::Duration::from_millis(20), limiter.acquire(true)).await;
+        assert!(waited.is_err());
+        assert_eq!(limiter.local.queued.load(Ordering::SeqCst), 0);
+
+        drop(running);
+        assert!(limiter.acquire(true).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_exceeding_queue_depth_is_overloaded() {
+        let server = mock_slow_ollama(100).await;
+        let manager = ModelManager::new(limited_config(&server, 1)).await.unwrap();
+
+        let (first, second, third) = tokio::join!(
+            manager.execute_task("one", "mixtral", None),
+            manager.execute_task("two", "mixtral", None),
+            manager.execute_task("three", "mixtral", None),
+        );
+
+        assert!(first.is_ok());
+        assert!(second.is_ok());
+        assert!(matches!(third, Err(MisaError::Overloaded(_))));
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_generate_parses_messages_response() {
+        use wiremock::matchers::header;
+
+        let server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .and(header("x-api-key", "test-key"))
+            .and(header("anthropic-version", ANTHROPIC_API_VERSION))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "msg_01",
+                "type": "message",
+                "role": "assistant",
+                "content": [{"type": "text", "text": "Hi from Claude"}],
+                "stop_reason": "end_turn",
+                "usage": {"input_tokens": 12, "output_tokens": 4}
+            })))
+            .mount(&server)
+            .await;
+
+        let client = CloudClient::new(
+            "anthropic".to_string(),
+            crate::kernel::CloudProviderConfig {
+                api_key: "test-key".to_string(),
+                base_url: server.uri(),
+                models: vec!["claude-3-haiku-20240307".to_string()],
+            },
+        );
+
+        let response = client
+            .generate_response("claude-3-haiku-20240307", test_request("Hello"))
+            .await
+            .unwrap();
+
+        assert_eq!(response.content, "Hi from Claude");
+        assert_eq!(response.model_id, "anthropic:claude-3-haiku-20240307");
+        assert_eq!(response.tokens_used, 16);
+        assert_eq!(response.finish_reason, "end_turn");
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_generate_reports_api_errors() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(serde_json::json!({
+                "type": "error",
+                "error": {"type": "authentication_error", "message": "invalid x-api-key"}
+            })))
+            .mount(&server)
+            .await;
+
+        let client = CloudClient::new(
+            "anthropic".to_string(),
+            crate::kernel::CloudProviderConfig {
+                api_key: "wrong-key".to_string(),
+                base_url: server.uri(),
+                models: vec!["claude-3-haiku-20240307".to_string()],
+            },
+        );
+
+        let result = client.generate_response("claude-3-haiku-20240307", test_request("Hello")).await;
+        assert!(matches!(result, Err(MisaError::ExternalService(message)) if message.contains("invalid x-api-key")));
+    }
+
+    fn fast_client_config() -> OllamaClientConfig {
+        OllamaClientConfig {
+            connect_timeout_ms: 500,
+            request_timeout_ms: 200,
+            max_retries: 2,
+            retry_backoff_ms: 10,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slow_server_surfaces_network_timeout() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"model": "mixtral", "response": "late", "done": true}))
+                    .set_delay(std::time::Duration::from_secs(2)),
+            )
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::with_config(server.uri(), &fast_client_config()).unwrap();
+        let mut request = test_request("Say hello");
+        request.stream = false;
+
+        let started = std::time::Instant::now();
+        let result = client.generate_response(request).await;
+
+        assert!(matches!(result, Err(MisaError::NetworkTimeout(_))));
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_malformed_response_is_a_parse_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("<html>proxy error</html>"))
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::with_config(server.uri(), &fast_client_config()).unwrap();
+        let mut request = test_request("Say hello");
+        request.stream = false;
+
+        let result = client.generate_response(request).await;
+        assert!(matches!(result, Err(MisaError::Parse(_))), "{:?}", result);
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_server_is_retried() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({"model": "mixtral", "response": "Hello", "done": true}),
+            ))
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::with_config(server.uri(), &fast_client_config()).unwrap();
+        let response = client.generate_response(test_request("Say hello")).await.unwrap();
+
+        assert_eq!(response.content, "Hello");
+    }
+
+    #[test]
+    fn test_invalid_base_url_is_rejected() {
+        for url in ["not a url", "ftp://localhost:11434", "http://"] {
+            assert!(
+                matches!(OllamaClient::new(url.to_string()), Err(MisaError::Configuration(_))),
+                "accepted {}",
+                url
+            );
+        }
+        assert!(OllamaClient::new("http://localhost:11434/".to_string()).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_openai_capabilities_follow_model_family() {
+        let server = mock_ollama_with_models(&[]).await;
+        let config = ModelConfig {
+            local_server_url: server.uri(),
+            cloud_providers: HashMap::new(),
+            ..ModelConfig::default()
+        };
+        let manager = ModelManager::new(config).await.unwrap();
+
+        let turbo_16k = manager.get_cloud_model_capabilities("openai", "gpt-3.5-turbo-16k");
+        assert_eq!(turbo_16k.max_context_length, 16384);
+        assert!(!turbo_16k.supports_vision);
+        assert_eq!(manager.get_model_context_length("openai", "gpt-3.5-turbo-16k"), 16384);
+
+        let gpt_4o = manager.get_cloud_model_capabilities("openai", "gpt-4o-2024-05-13");
+        assert!(gpt_4o.supports_vision);
+        assert!(gpt_4o.supports_json_mode);
+        assert_eq!(gpt_4o.max_context_length, 128_000);
+
+        let gpt_4 = manager.get_cloud_model_capabilities("openai", "gpt-4");
+        assert_eq!(gpt_4.max_context_length, 8192);
+        assert!(!gpt_4.supports_vision);
+    }
+
+    async fn budgeted_manager(server: &MockServer, daily_budget_usd: Option<f64>) -> ModelManager {
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "msg_01",
+                "type": "message",
+                "role": "assistant",
+                "content": [{"type": "text", "text": "Hi from the cloud"}],
+                "stop_reason": "end_turn",
+                "usage": {"input_tokens": 12, "output_tokens": 4}
+            })))
+            .mount(server)
+            .await;
+
+        let config = ModelConfig {
+            local_server_url: server.uri(),
+            cloud_providers: HashMap::from([(
+                "anthropic".to_string(),
+                crate::kernel::CloudProviderConfig {
+                    api_key: "test-key".to_string(),
+                    base_url: server.uri(),
+                    models: vec!["claude-3-haiku-20240307".to_string()],
+                },
+            )]),
+            daily_budget_usd,
+            ..ModelConfig::default()
+        };
+        ModelManager::new(config).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_cloud_calls_accumulate_spend() {
+        let server = mock_ollama_with_models(&[]).await;
+        let manager = budgeted_manager(&server, None).await;
+        let model_id = "anthropic:claude-3-haiku-20240307";
+
+        manager.execute_task("Hello", model_id, None).await.unwrap();
+        manager.execute_task("Hello again", model_id, None).await.unwrap();
+
+        // 2 calls x 16 tokens at $0.25 per million tokens
+        let summary = manager.get_spend_summary().await;
+        let expected = 32.0 * 0.25 / 1_000_000.0;
+        assert!((summary.daily_spend_usd - expected).abs() < 1e-12);
+        assert!((summary.total_spend_usd - expected).abs() < 1e-12);
+        assert_eq!(summary.by_model[model_id].requests, 2);
+        assert_eq!(summary.by_model[model_id].tokens, 32);
+    }
+
+    #[tokio::test]
+    async fn test_cloud_calls_blocked_once_over_budget() {
+        let server = mock_ollama_with_models(&[]).await;
+        // Each call costs $0.000004, so the budget is used up after two calls
+        let manager = budgeted_manager(&server, Some(0.000_005)).await;
+        let model_id = "anthropic:claude-3-haiku-20240307";
+
+        manager.execute_task("Hello", model_id, None).await.unwrap();
+        manager.execute_task("Hello", model_id, None).await.unwrap();
+        let result = manager.execute_task("Hello", model_id, None).await;
+
+        assert!(matches!(result, Err(MisaError::RateLimit(_))));
+        assert_eq!(manager.get_spend_summary().await.by_model[model_id].requests, 2);
+    }
+
+    #[tokio::test]
+    async fn test_spend_survives_a_restart() {
+        let server = mock_ollama_with_models(&[]).await;
+        let data_dir = tempfile::tempdir().unwrap();
+        let dir = data_dir.path().to_str().unwrap();
+        let model_id = "anthropic:claude-3-haiku-20240307";
+
+        let manager = budgeted_manager(&server, Some(0.000_005)).await.with_spend_store(dir).await.unwrap();
+        manager.execute_task("Hello", model_id, None).await.unwrap();
+        manager.execute_task("Hello", model_id, None).await.unwrap();
+
+        // A fresh manager picks up today's spend and keeps enforcing the budget
+        let restarted = budgeted_manager(&server, Some(0.000_005)).await.with_spend_store(dir).await.unwrap();
+        let summary = restarted.get_spend_summary().await;
+        assert_eq!(summary.by_model[model_id].requests, 2);
+        assert!((summary.daily_spend_usd - 32.0 * 0.25 / 1_000_000.0).abs() < 1e-12);
+        assert!(matches!(restarted.execute_task("Hello", model_id, None).await, Err(MisaError::RateLimit(_))));
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_cloud_calls_cannot_overspend_budget() {
+        let server = mock_ollama_with_models(&[]).await;
+        // Room for one call; the others are admitted together but must not all pass the check
+        let manager = budgeted_manager(&server, Some(0.000_005)).await;
+        let model_id = "anthropic:claude-3-haiku-20240307";
+
+        let calls = (0..3).map(|_| manager.execute_task("Hello", model_id, None));
+        let results = futures_util::future::join_all(calls).await;
+
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+        assert!(results.iter().filter(|result| result.is_err()).all(|result| matches!(result, Err(MisaError::RateLimit(_)))));
+        assert_eq!(manager.get_spend_summary().await.by_model[model_id].requests, 1);
+
+        // The winner's reservation was settled, leaving only its actual cost
+        assert_eq!(manager.spend.read().await.reserved_usd, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_missing_local_model_is_pulled_and_retried() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/tags"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "models": [{"name": "mixtral", "size": 26, "digest": "abc", "modified_at": "2024-01-01T00:00:00Z"}]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(
+                serde_json::json!({"error": "model 'mixtral' not found, try pulling it first"}),
+            ))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({"model": "mixtral", "response": "Hello", "done": true}),
+            ))
+            .mount(&server)
+            .await;
+        let pull_body = concat!(
+            r#"{"status":"pulling manifest"}"#, "\n",
+            r#"{"status":"downloading","digest":"sha256:abc","total":100,"completed":40}"#, "\n",
+            r#"{"status":"success"}"#, "\n",
+        );
+        Mock::given(method("POST"))
+            .and(path("/api/pull"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(pull_body))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config = ModelConfig {
+            local_server_url: server.uri(),
+            cloud_providers: HashMap::new(),
+            auto_pull_models: true,
+            ..ModelConfig::default()
+        };
+        let manager = ModelManager::new(config).await.unwrap();
+        let mut events = manager.subscribe_catalog_events();
+
+        let result = manager.execute_task("Say hello", "mixtral", None).await.unwrap();
+        assert_eq!(result["content"], "Hello");
+
+        let mut statuses = Vec::new();
+        while let Ok(ModelCatalogEvent::PullProgress { model_id, status, completed, .. }) = events.try_recv() {
+            assert_eq!(model_id, "mixtral");
+            statuses.push((status, completed));
+        }
+        assert_eq!(
+            statuses,
+            vec![
+                ("pulling manifest".to_string(), None),
+                ("downloading".to_string(), Some(40)),
+                ("success".to_string(), None),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_tasks_share_one_pull() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/tags"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "models": [{"name": "mixtral", "size": 26, "digest": "abc", "modified_at": "2024-01-01T00:00:00Z"}]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(404))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({"model": "mixtral", "response": "Hello", "done": true}),
+            ))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/pull"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string("{\"status\":\"success\"}\n")
+                    .set_delay(std::time::Duration::from_millis(200)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config = ModelConfig {
+            local_server_url: server.uri(),
+            cloud_providers: HashMap::new(),
+            auto_pull_models: true,
+            ..ModelConfig::default()
+        };
+        let manager = ModelManager::new(config).await.unwrap();
+
+        let (first, second) = tokio::join!(
+            manager.execute_task("Say hello", "mixtral", None),
+            manager.execute_task("Say hello again", "mixtral", None),
+        );
+        assert_eq!(first.unwrap()["content"], "Hello");
+        assert_eq!(second.unwrap()["content"], "Hello");
+    }
+
+    #[tokio::test]
+    async fn test_missing_local_model_fails_without_auto_pull() {
+        let server = mock_ollama_with_models(&["mixtral"]).await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let config = ModelConfig {
+            local_server_url: server.uri(),
+            cloud_providers: HashMap::new(),
+            auto_pull_models: false,
+            ..ModelConfig::default()
+        };
+        let manager = ModelManager::new(config).await.unwrap();
+
+        let result = manager.execute_task("Say hello", "mixtral", None).await;
+        assert!(matches!(result, Err(MisaError::ModelNotFound(model_id)) if model_id == "mixtral"));
+    }
+
+    fn measured(avg_response_time_ms: f64, success_rate: f32) -> ModelPerformance {
+        ModelPerformance {
+            avg_response_time_ms,
+            success_rate,
+            tokens_per_second: 0.0,
+            memory_usage_mb: 0,
+            energy_efficiency: 1.0,
+            last_used: chrono::Utc::now(),
+            total_requests: 10,
+        }
+    }
+
+    async fn ranking_manager(preferences: ModelSwitchingPreferences) -> ModelManager {
+        let config = ModelConfig {
+            cloud_providers: HashMap::new(),
+            switching_preferences: preferences,
+            ..ModelConfig::default()
+        };
+        let manager = ModelManager::new(config).await.unwrap();
+        let mut metrics = manager.performance_metrics.write().await;
+        metrics.insert("careful".to_string(), measured(2000.0, 1.0));
+        metrics.insert("quick".to_string(), measured(100.0, 0.6));
+        drop(metrics);
+        manager
+    }
+
+    async fn winner(manager: &ModelManager) -> String {
+        let candidates = vec!["careful".to_string(), "quick".to_string()];
+        let ranking = manager.rank_models_for_task(candidates, &ModelType::Chat, &TaskPriority::Normal).await.unwrap();
+        assert!(ranking.iter().all(|model| (0.0..=1.0).contains(&model.score)));
+        ranking[0].model_id.clone()
+    }
+
+    #[tokio::test]
+    async fn test_latency_weight_flips_winner_to_faster_model() {
+        let quality_first = ModelSwitchingPreferences {
+            quality_weight: 3.0,
+            latency_weight: 1.0,
+            ..ModelSwitchingPreferences::default()
+        };
+        assert_eq!(winner(&ranking_manager(quality_first.clone()).await).await, "careful");
+
+        let latency_first = ModelSwitchingPreferences {
+            latency_weight: 5.0,
+            ..quality_first
+        };
+        assert_eq!(winner(&ranking_manager(latency_first).await).await, "quick");
+    }
+
+    #[tokio::test]
+    async fn test_local_bias_outweighs_cloud_track_record() {
+        let manager = ranking_manager(ModelSwitchingPreferences::default()).await;
+        manager.performance_metrics.write().await.insert("openai:gpt-4".to_string(), measured(50.0, 1.0));
+
+        let candidates = vec!["openai:gpt-4".to_string(), "careful".to_string()];
+        let ranking = manager.rank_models_for_task(candidates.clone(), &ModelType::Chat, &TaskPriority::Normal).await.unwrap();
+        assert_eq!(ranking[0].model_id, "careful");
+
+        let unbiased = ranking_manager(ModelSwitchingPreferences {
+            local_bias: 0.0,
+            ..ModelSwitchingPreferences::default()
+        })
+        .await;
+        unbiased.performance_metrics.write().await.insert("openai:gpt-4".to_string(), measured(50.0, 1.0));
+        let ranking = unbiased.rank_models_for_task(candidates, &ModelType::Chat, &TaskPriority::Normal).await.unwrap();
+        assert_eq!(ranking[0].model_id, "openai:gpt-4");
+    }
+
+    #[tokio::test]
+    async fn test_priority_trades_latency_against_quality() {
+        let manager = ranking_manager(ModelSwitchingPreferences::default()).await;
+        let candidates = vec!["careful".to_string(), "quick".to_string()];
+
+        let ranking = manager.rank_models_for_task(candidates.clone(), &ModelType::Chat, &TaskPriority::Low).await.unwrap();
+        assert_eq!(ranking[0].model_id, "careful");
+
+        let ranking = manager.rank_models_for_task(candidates, &ModelType::Chat, &TaskPriority::Critical).await.unwrap();
+        assert_eq!(ranking[0].model_id, "quick");
+    }
+
+    #[tokio::test]
+    async fn test_specialised_model_ranks_first_for_its_task() {
+        let manager = ranking_manager(ModelSwitchingPreferences::default()).await;
+        let mut local_models = manager.local_models.write().await;
+        for name in ["codellama", "mixtral"] {
+            let model = ModelManager::local_model_from_info(OllamaModelInfo {
+                name: name.to_string(),
+                size: 0,
+                digest: String::new(),
+                modified_at: String::new(),
+            });
+            local_models.insert(name.to_string(), model);
+        }
+        drop(local_models);
+
+        let candidates = vec!["mixtral".to_string(), "codellama".to_string()];
+        let ranking = manager.rank_models_for_task(candidates.clone(), &ModelType::Coding, &TaskPriority::Normal).await.unwrap();
+        assert_eq!(ranking[0].model_id, "codellama");
+
+        let ranking = manager.rank_models_for_task(candidates, &ModelType::Chat, &TaskPriority::Normal).await.unwrap();
+        assert_eq!(ranking[0].score, ranking[1].score);
+    }
+}
@@ -9,15 +9,45 @@
 //! - Opt-in telemetry with anonymization
 
 use anyhow::Result;
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::Path;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 use tracing::{info, warn, error, debug};
 
 use crate::kernel::SecurityConfig;
-use crate::errors::{MisaError, Result as MisaResult};
+use crate::scheduler::Scheduler;
+use crate::util::{read_json_map, write_file_atomic, write_json_atomic};
+use crate::security::{AuditResult, SecurityManager};
+use crate::memory::{ContentFilter, ContentType, MemoryItem, MemoryManager, MemoryType, SearchQuery, SortField, SortOrder};
+use crate::errors::{MisaError, PrivacyError, Result as MisaResult};
+
+/// Data source id for screen capture controls
+pub const SCREEN_CAPTURE_SOURCE: &str = "screen_capture";
+
+/// Scheduler job looking for consents that are about to expire
+const CONSENT_SWEEP_JOB: &str = "privacy.consent_sweep";
+
+/// Metadata key recording when the user was asked to renew a consent
+const RE_CONSENT_NOTIFIED_KEY: &str = "re_consent_notified_at";
+
+/// Scheduler job applying each data source's retention rule to its artifacts
+const RETENTION_ENFORCEMENT_JOB: &str = "privacy.retention_enforcement";
+
+/// Coordinates given to more than one decimal place, which generalization truncates
+const PRECISE_COORDINATE_PATTERN: &str = r"-?\d+\.\d{2,}";
+
+static PRECISE_COORDINATE: Lazy<regex::Regex> =
+    Lazy::new(|| regex::Regex::new(PRECISE_COORDINATE_PATTERN).expect("valid coordinate pattern"));
+
+/// Directory under the data dir holding captured artifacts, one subdirectory per source
+const SOURCE_ARTIFACTS_DIR: &str = "sources";
+
+/// Subdirectory of a source's artifacts that archived, compressed and anonymized ones move to
+const RETAINED_ARTIFACTS_DIR: &str = "retained";
 
 /// Privacy controls manager
 pub struct PrivacyControls {
@@ -27,13 +57,19 @@ pub struct PrivacyControls {
     data_controls: DataControls,
     compliance_manager: ComplianceManager,
     anonymization_engine: AnonymizationEngine,
+    memory_manager: Option<MemoryManager>,
+    security_manager: Option<SecurityManager>,
+    scheduler: Scheduler,
 }
 
 /// Consent manager for handling user consents
 pub struct ConsentManager {
     consents: Arc<RwLock<HashMap<String, ConsentRecord>>>,
+    /// File consents are written through to
+    store_path: PathBuf,
     consent_templates: Arc<RwLock<HashMap<String, ConsentTemplate>>>,
     active_sessions: Arc<RwLock<HashMap<String, ConsentSession>>>,
+    events: broadcast::Sender<PrivacyEvent>,
 }
 
 /// Consent record
@@ -48,10 +84,81 @@ pub struct ConsentRecord {
     pub granted_at: Option<chrono::DateTime<chrono::Utc>>,
     pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
     pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Set by the expiry sweep once `expires_at` has passed
+    #[serde(default)]
+    pub expired_at: Option<chrono::DateTime<chrono::Utc>>,
     pub version: String,
     pub metadata: serde_json::Value,
 }
 
+/// Consent changes the user has to act on
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum PrivacyEvent {
+    /// A consent expires within the warning window, or already has
+    ReConsentRequired {
+        user_id: String,
+        consent_id: String,
+        consent_type: ConsentType,
+        expires_at: chrono::DateTime<chrono::Utc>,
+        expired: bool,
+    },
+}
+
+/// Outcome of one consent expiry sweep
+#[derive(Debug, Clone, Default)]
+pub struct ConsentSweepReport {
+    /// Consents marked expired by this sweep
+    pub expired: Vec<String>,
+    /// Consents the user was asked to renew
+    pub notified: Vec<String>,
+}
+
+/// Why an artifact was taken out of its source's active artifacts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionReason {
+    /// Older than the rule's `max_age_days`
+    Expired,
+    /// Past the rule's `max_size_mb` once newer artifacts are counted
+    OverSizeLimit,
+}
+
+/// What happened to an artifact its retention rule applied to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionOutcome {
+    Deleted,
+    Archived,
+    Compressed,
+    Anonymized,
+}
+
+/// One artifact a retention rule was applied to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionAction {
+    pub source_id: String,
+    pub artifact: PathBuf,
+    pub reason: RetentionReason,
+    pub outcome: RetentionOutcome,
+}
+
+/// A stored memory captured by a source and erased under its retention rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionErasure {
+    pub source_id: String,
+    pub memory_id: String,
+}
+
+/// Outcome of one retention enforcement pass
+#[derive(Debug, Clone, Default)]
+pub struct RetentionReport {
+    pub actions: Vec<RetentionAction>,
+    pub erased_memories: Vec<RetentionErasure>,
+    /// Artifacts or memories the pass could not retire, left for the next run
+    pub failures: Vec<String>,
+}
+
 /// Consent type
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum ConsentType {
@@ -73,7 +180,7 @@ pub enum ConsentType {
 }
 
 /// Data type classification
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum DataType {
     PersonalInfo,
     HealthData,
@@ -92,6 +199,26 @@ pub enum DataType {
     ChatData,
 }
 
+impl DataType {
+    /// Classify a stored memory; a `data_type` entry in its metadata takes precedence
+    pub fn for_memory(memory: &MemoryItem) -> Self {
+        if let Some(data_type) = memory
+            .metadata
+            .get("data_type")
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+        {
+            return data_type;
+        }
+
+        match memory.content_type {
+            ContentType::Text | ContentType::StructuredData => DataType::TextData,
+            ContentType::Audio => DataType::AudioData,
+            ContentType::Image | ContentType::Video => DataType::VideoData,
+            ContentType::Document | ContentType::Code => DataType::FileData,
+        }
+    }
+}
+
 /// Consent template for reusable consent flows
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConsentTemplate {
@@ -107,11 +234,25 @@ pub struct ConsentTemplate {
     help_text: Option<String>,
 }
 
+impl ConsentTemplate {
+    /// Link to the privacy policy covering this consent, if any
+    pub fn privacy_policy_url(&self) -> Option<&str> {
+        self.privacy_policy_url.as_deref()
+    }
+
+    /// Short explanatory text shown alongside the consent prompt
+    pub fn help_text(&self) -> Option<&str> {
+        self.help_text.as_deref()
+    }
+}
+
 /// Active consent session
 #[derive(Debug, Clone)]
 pub struct ConsentSession {
     pub session_id: String,
     pub user_id: String,
+    pub consent_type: ConsentType,
+    pub template_id: String,
     pub requested_consents: Vec<String>, // consent_ids
     pub status: ConsentSessionStatus,
     pub created_at: chrono::DateTime<chrono::Utc>,
@@ -132,8 +273,13 @@ pub enum ConsentSessionStatus {
 pub struct DataControls {
     source_controls: Arc<RwLock<HashMap<String, DataSourceControl>>>,
     app_permissions: Arc<RwLock<HashMap<String, AppPermissions>>>,
+    /// File app permissions are written through to
+    permissions_path: PathBuf,
     data_retention: Arc<RwLock<DataRetentionPolicy>>,
     privacy_filters: Arc<RwLock<HashMap<String, PrivacyFilter>>>,
+    /// Rule patterns by (pattern, case sensitive); invalid patterns are kept as `None`
+    compiled_patterns: Arc<RwLock<HashMap<(String, bool), Option<regex::Regex>>>>,
+    anonymization_engine: AnonymizationEngine,
 }
 
 /// Data source control
@@ -228,14 +374,52 @@ pub struct Permission {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PermissionCondition {
+    /// Local time window as "HH:MM"; a start after the end spans midnight
     TimeRange { start: String, end: String },
     LocationBased { allowed_locations: Vec<String> },
     UserPresent,
+    /// Only while the device is locked
     DeviceLocked,
     NetworkSecure,
+    /// The user must confirm each use
     ExplicitConfirmation,
 }
 
+/// Circumstances a permission is checked under
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PermissionContext {
+    pub now: chrono::DateTime<chrono::Utc>,
+    pub local_time: chrono::NaiveTime,
+    pub location: Option<String>,
+    pub user_present: bool,
+    pub device_locked: bool,
+    pub network_secure: bool,
+    pub confirmed: bool,
+}
+
+impl PermissionContext {
+    /// Context for the current moment; unknown state is assumed least permissive
+    pub fn current() -> Self {
+        Self {
+            now: chrono::Utc::now(),
+            local_time: chrono::Local::now().time(),
+            location: None,
+            user_present: false,
+            device_locked: false,
+            network_secure: false,
+            confirmed: false,
+        }
+    }
+}
+
+/// Outcome of a permission check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PermissionDecision {
+    Denied,
+    Granted,
+    NeedsConfirmation,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PermissionScope {
     Read,
@@ -324,6 +508,24 @@ pub enum FilterAction {
     Log,
 }
 
+/// A filter rule that fired on a piece of text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppliedFilter {
+    pub filter_id: String,
+    pub rule_id: String,
+    pub outcome: FilterOutcome,
+    /// Number of spans the rule matched
+    pub matches: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FilterOutcome {
+    Blocked,
+    Redacted,
+    Anonymized,
+    Logged,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AnonymizationMethod {
     Hash,
@@ -361,6 +563,29 @@ pub struct Requirement {
     pub mandatory: bool,
     pub implementation_status: ImplementationStatus,
     pub evidence: Vec<String>,
+    /// Automated check backing this requirement; unchecked requirements keep their declared status
+    #[serde(default)]
+    pub check: Option<ComplianceCheck>,
+}
+
+/// System checks the compliance evaluator knows how to run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ComplianceCheck {
+    RetentionLimits,
+    Erasure,
+    BreachNotification,
+    ConsentManagement,
+    DataPortability,
+}
+
+/// Snapshot of system state a compliance report is evaluated against
+#[derive(Debug, Clone, Default)]
+pub struct ComplianceContext {
+    /// Longest configured retention per data type, across category and source policies
+    pub retention_days: HashMap<DataType, u32>,
+    pub erasure_available: bool,
+    pub export_available: bool,
+    pub consent_templates: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -487,8 +712,13 @@ pub enum RequestStatus {
     Completed,
     Rejected,
     Expired,
+    /// Processing hit an error; the request can be processed again
+    Failed,
 }
 
+/// Days a data-subject request may stay open before it is overdue
+const USER_REQUEST_DEADLINE_DAYS: i64 = 30;
+
 /// Processed user data for export
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProcessedUserData {
@@ -510,6 +740,7 @@ pub enum ExportFormat {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DataFile {
     pub filename: String,
+    pub path: String,
     pub content_type: String,
     pub size_bytes: u64,
     pub data_type: DataType,
@@ -529,6 +760,8 @@ pub struct AnonymizationEngine {
     methods: Arc<RwLock<HashMap<String, AnonymizationMethod>>>,
     suppression_lists: Arc<RwLock<HashMap<String, SuppressionList>>>,
     pseudonymization_tables: Arc<RwLock<HashMap<String, PseudonymTable>>>,
+    /// Where pseudonymization keys are looked up, shared so every clone sees it once attached
+    key_store: Arc<std::sync::RwLock<Option<SecurityManager>>>,
 }
 
 /// Suppression list
@@ -555,9 +788,10 @@ impl PrivacyControls {
     /// Create new privacy controls
     pub async fn new(config: SecurityConfig, data_dir: &str) -> MisaResult<Self> {
         let consent_manager = ConsentManager::new(data_dir).await?;
-        let data_controls = DataControls::new().await?;
+        let data_controls = DataControls::new(data_dir).await?;
         let compliance_manager = ComplianceManager::new(data_dir).await?;
-        let anonymization_engine = AnonymizationEngine::new().await?;
+        // Shared with the data controls so both see the same pseudonym tables
+        let anonymization_engine = data_controls.anonymization_engine.clone();
 
         let controls = Self {
             config,
@@ -566,12 +800,330 @@ impl PrivacyControls {
             data_controls,
             compliance_manager,
             anonymization_engine,
+            memory_manager: None,
+            security_manager: None,
+            scheduler: Scheduler::new(),
         };
 
         info!("Privacy controls initialized");
         Ok(controls)
     }
 
+    /// Attach the memory store so exports and deletions cover stored memories
+    pub fn with_memory_manager(mut self, memory_manager: MemoryManager) -> Self {
+        self.memory_manager = Some(memory_manager);
+        self
+    }
+
+    /// Run background tasks on a scheduler shared with other managers
+    pub fn with_scheduler(mut self, scheduler: Scheduler) -> Self {
+        self.scheduler = scheduler;
+        self
+    }
+
+    /// Record retention enforcement in the security audit log and resolve pseudonymization keys
+    pub fn with_security_manager(mut self, security_manager: SecurityManager) -> Self {
+        self.anonymization_engine.set_key_store(security_manager.clone());
+        self.security_manager = Some(security_manager);
+        self
+    }
+
+    /// Start the consent expiry sweep and retention enforcement
+    pub async fn initialize(&self) -> MisaResult<()> {
+        if self.config.consent_sweep_interval_seconds > 0 {
+            let consent_manager = self.consent_manager.clone();
+            let window = chrono::Duration::days(self.config.consent_expiry_warning_days as i64);
+            let interval = tokio::time::Duration::from_secs(self.config.consent_sweep_interval_seconds);
+
+            self.scheduler
+                .register(CONSENT_SWEEP_JOB, interval, move || {
+                    let consent_manager = consent_manager.clone();
+                    async move {
+                        let report = consent_manager.sweep_expiring(chrono::Utc::now(), window).await?;
+                        debug!(
+                            "Consent sweep: {} expired, {} asked to renew",
+                            report.expired.len(),
+                            report.notified.len()
+                        );
+                        Ok(())
+                    }
+                })
+                .await?;
+        }
+
+        if self.config.retention_enforcement_interval_seconds > 0 {
+            let controls = self.clone();
+            let interval = tokio::time::Duration::from_secs(self.config.retention_enforcement_interval_seconds);
+
+            self.scheduler
+                .register(RETENTION_ENFORCEMENT_JOB, interval, move || {
+                    let controls = controls.clone();
+                    async move {
+                        let report = controls.enforce_retention().await?;
+                        debug!(
+                            "Retention enforcement: {} artifacts retired, {} memories erased, {} failures",
+                            report.actions.len(),
+                            report.erased_memories.len(),
+                            report.failures.len()
+                        );
+                        Ok(())
+                    }
+                })
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Stop background tasks
+    pub async fn shutdown(&self) -> MisaResult<()> {
+        self.scheduler.cancel(CONSENT_SWEEP_JOB).await;
+        self.scheduler.cancel(RETENTION_ENFORCEMENT_JOB).await;
+        Ok(())
+    }
+
+    /// Directory a data source's captured artifacts are stored in
+    pub fn source_artifacts_dir(&self, source_id: &str) -> PathBuf {
+        Path::new(&self.data_dir).join(SOURCE_ARTIFACTS_DIR).join(source_id)
+    }
+
+    /// Apply each enabled data source's retention rule to its stored artifacts, the artifacts
+    /// earlier runs retained, and the memories it captured
+    ///
+    /// A failure on one artifact or memory is logged and the pass carries on with the rest.
+    pub async fn enforce_retention(&self) -> MisaResult<RetentionReport> {
+        self.enforce_retention_at(chrono::Utc::now()).await
+    }
+
+    async fn enforce_retention_at(&self, now: chrono::DateTime<chrono::Utc>) -> MisaResult<RetentionReport> {
+        let sources: Vec<DataSourceControl> = self
+            .data_controls
+            .source_controls
+            .read()
+            .await
+            .values()
+            .filter(|source| source.enabled)
+            .cloned()
+            .collect();
+
+        let mut report = RetentionReport::default();
+        for source in sources {
+            // Rules without auto-delete are left for the user to clean up
+            let Some(rule) = source.retention_policy.as_ref().filter(|rule| rule.auto_delete) else {
+                continue;
+            };
+
+            let source_dir = self.source_artifacts_dir(&source.source_id);
+
+            // Retained artifacts are the last stage, so the rule deletes them outright. They are
+            // handled first so artifacts retired below keep their archival for at least one run.
+            let retained_dir = source_dir.join(RETAINED_ARTIFACTS_DIR);
+            match Self::retention_candidates(&retained_dir, rule, now).await {
+                Ok(candidates) => {
+                    for (artifact, reason) in candidates {
+                        let deleted = tokio::fs::remove_file(&artifact).await.map(|_| RetentionOutcome::Deleted);
+                        self.record_retention(&mut report, &source.source_id, artifact, reason, deleted.map_err(Into::into))
+                            .await;
+                    }
+                }
+                Err(e) => Self::record_failure(&mut report, format!("Listing {}: {}", retained_dir.display(), e)),
+            }
+
+            match Self::retention_candidates(&source_dir, rule, now).await {
+                Ok(candidates) => {
+                    for (artifact, reason) in candidates {
+                        let outcome = self.retire_artifact(&source_dir, &artifact, &rule.archival_policy).await;
+                        self.record_retention(&mut report, &source.source_id, artifact, reason, outcome).await;
+                    }
+                }
+                Err(e) => Self::record_failure(&mut report, format!("Listing {}: {}", source_dir.display(), e)),
+            }
+
+            self.erase_expired_memories(&mut report, &source.source_id, rule, now).await;
+        }
+
+        Ok(report)
+    }
+
+    /// Erase the memories `source_id` captured that are older than its rule allows
+    async fn erase_expired_memories(
+        &self,
+        report: &mut RetentionReport,
+        source_id: &str,
+        rule: &RetentionRule,
+        now: chrono::DateTime<chrono::Utc>,
+    ) {
+        let Some(memory_manager) = &self.memory_manager else {
+            return;
+        };
+
+        let cutoff = now - chrono::Duration::days(rule.max_age_days as i64);
+        let mut query = SearchQuery::new();
+        query.limit = None;
+        query.offset = None;
+        query.date_range = Some((std::time::UNIX_EPOCH.into(), cutoff));
+        let memories = match memory_manager.search_memories(&query).await {
+            Ok(memories) => memories,
+            Err(e) => return Self::record_failure(report, format!("Searching {} memories: {}", source_id, e)),
+        };
+
+        for memory in memories.iter().filter(|memory| memory.metadata["source"] == source_id) {
+            match memory_manager.erase_memory(&memory.id, true).await {
+                Ok(true) => {
+                    info!("Erased memory {} under the {} retention rule", memory.id, source_id);
+                    self.audit_retention(
+                        &memory.id,
+                        serde_json::json!({
+                            "source_id": source_id,
+                            "reason": RetentionReason::Expired,
+                            "outcome": RetentionOutcome::Deleted,
+                        }),
+                    )
+                    .await;
+                    report.erased_memories.push(RetentionErasure {
+                        source_id: source_id.to_string(),
+                        memory_id: memory.id.clone(),
+                    });
+                }
+                // Already gone, e.g. pruned concurrently
+                Ok(false) => {}
+                Err(e) => Self::record_failure(report, format!("Erasing memory {}: {}", memory.id, e)),
+            }
+        }
+    }
+
+    fn record_failure(report: &mut RetentionReport, failure: String) {
+        warn!("Retention enforcement: {}", failure);
+        report.failures.push(failure);
+    }
+
+    async fn audit_retention(&self, resource: &str, details: serde_json::Value) {
+        if let Some(security_manager) = &self.security_manager {
+            if let Err(e) = security_manager
+                .log_security_event(None, "retention_enforced", resource, AuditResult::Success, details)
+                .await
+            {
+                warn!("Failed to audit retention of {}: {}", resource, e);
+            }
+        }
+    }
+
+    /// Log and audit a retired artifact and add it to the enforcement report
+    async fn record_retention(
+        &self,
+        report: &mut RetentionReport,
+        source_id: &str,
+        artifact: PathBuf,
+        reason: RetentionReason,
+        outcome: MisaResult<RetentionOutcome>,
+    ) {
+        let outcome = match outcome {
+            Ok(outcome) => outcome,
+            Err(e) => return Self::record_failure(report, format!("Retiring {}: {}", artifact.display(), e)),
+        };
+        info!("{:?} {} under the {} retention rule", outcome, artifact.display(), source_id);
+
+        self.audit_retention(
+            &artifact.display().to_string(),
+            serde_json::json!({
+                "source_id": source_id,
+                "reason": reason,
+                "outcome": outcome,
+            }),
+        )
+        .await;
+
+        report.actions.push(RetentionAction {
+            source_id: source_id.to_string(),
+            artifact,
+            reason,
+            outcome,
+        });
+    }
+
+    /// Artifacts in `dir` a retention rule applies to; the size limit keeps the newest ones
+    async fn retention_candidates(
+        dir: &Path,
+        rule: &RetentionRule,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> MisaResult<Vec<(PathBuf, RetentionReason)>> {
+        let mut entries = match tokio::fs::read_dir(dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut artifacts = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let metadata = entry.metadata().await?;
+            if metadata.is_file() {
+                let modified: chrono::DateTime<chrono::Utc> = metadata.modified()?.into();
+                artifacts.push((entry.path(), modified, metadata.len()));
+            }
+        }
+        artifacts.sort_by(|a, b| b.1.cmp(&a.1));
+
+        let cutoff = now - chrono::Duration::days(rule.max_age_days as i64);
+        let max_bytes = rule.max_size_mb.saturating_mul(1024 * 1024);
+        let mut total_bytes = 0u64;
+        let mut candidates = Vec::new();
+        for (path, modified, size) in artifacts {
+            total_bytes = total_bytes.saturating_add(size);
+            if modified < cutoff {
+                candidates.push((path, RetentionReason::Expired));
+            } else if total_bytes > max_bytes {
+                candidates.push((path, RetentionReason::OverSizeLimit));
+            }
+        }
+
+        Ok(candidates)
+    }
+
+    /// Take an artifact out of its source's active artifacts as the archival policy says
+    async fn retire_artifact(&self, source_dir: &Path, artifact: &Path, policy: &ArchivalPolicy) -> MisaResult<RetentionOutcome> {
+        let retained_dir = source_dir.join(RETAINED_ARTIFACTS_DIR);
+        let file_name = artifact.file_name().unwrap_or_default().to_os_string();
+
+        let outcome = match policy {
+            ArchivalPolicy::Delete => RetentionOutcome::Deleted,
+            ArchivalPolicy::Archive => {
+                tokio::fs::create_dir_all(&retained_dir).await?;
+                tokio::fs::rename(artifact, retained_dir.join(&file_name)).await?;
+                return Ok(RetentionOutcome::Archived);
+            }
+            ArchivalPolicy::Compress => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(&tokio::fs::read(artifact).await?)?;
+                let mut compressed_name = file_name;
+                compressed_name.push(".gz");
+                write_file_atomic(&retained_dir.join(compressed_name), &encoder.finish()?).await?;
+                RetentionOutcome::Compressed
+            }
+            ArchivalPolicy::AnonymizeAndRetain => match String::from_utf8(tokio::fs::read(artifact).await?) {
+                Ok(text) => {
+                    let (anonymized, _) = self.data_controls.apply_filters(&text).await;
+                    write_file_atomic(&retained_dir.join(file_name), anonymized.as_bytes()).await?;
+                    RetentionOutcome::Anonymized
+                }
+                // Binary artifacts can't be scrubbed, so they aren't kept
+                Err(_) => RetentionOutcome::Deleted,
+            },
+        };
+
+        tokio::fs::remove_file(artifact).await?;
+        Ok(outcome)
+    }
+
+    /// Subscribe to re-consent prompts and other privacy events
+    pub fn subscribe_events(&self) -> broadcast::Receiver<PrivacyEvent> {
+        self.consent_manager.events.subscribe()
+    }
+
+    /// Mark expired consents and ask users to renew those expiring within `window`
+    pub async fn sweep_expiring_consents(&self, window: chrono::Duration) -> MisaResult<ConsentSweepReport> {
+        self.consent_manager.sweep_expiring(chrono::Utc::now(), window).await
+    }
+
     /// Request user consent
     pub async fn request_consent(&self, user_id: &str, consent_type: ConsentType, context: serde_json::Value) -> MisaResult<String> {
         info!("Requesting consent for user: {}, type: {:?}", user_id, consent_type);
@@ -600,26 +1152,65 @@ impl PrivacyControls {
         self.data_controls.set_source_control(source_id, enabled).await
     }
 
+    /// Data controls, e.g. to filter memory content before it is stored
+    pub fn data_controls(&self) -> DataControls {
+        self.data_controls.clone()
+    }
+
     /// Get data source status
     pub async fn get_data_source_status(&self, source_id: &str) -> MisaResult<Option<DataSourceControl>> {
         self.data_controls.get_source_status(source_id).await
     }
 
+    /// Fail unless the user consented to screen capture and the screen capture source is enabled
+    pub async fn check_screen_capture(&self, user_id: &str) -> MisaResult<()> {
+        if !self.has_consent(user_id, ConsentType::ScreenCapture).await? {
+            return Err(PrivacyError::ConsentRequired {
+                action: SCREEN_CAPTURE_SOURCE.to_string(),
+            }
+            .into());
+        }
+
+        let enabled = self
+            .get_data_source_status(SCREEN_CAPTURE_SOURCE)
+            .await?
+            .map_or(false, |control| control.enabled);
+        if !enabled {
+            return Err(PrivacyError::DataAccessDenied {
+                data_type: SCREEN_CAPTURE_SOURCE.to_string(),
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+
     /// Set app permissions
     pub async fn set_app_permission(&self, app_id: &str, permission_id: &str, granted: bool) -> MisaResult<()> {
         self.data_controls.set_app_permission(app_id, permission_id, granted).await
     }
 
-    /// Check if app has permission
-    pub async fn has_app_permission(&self, app_id: &str, permission_id: &str) -> MisaResult<bool> {
-        self.data_controls.has_app_permission(app_id, permission_id).await
+    /// Check if app has permission under the given context
+    pub async fn has_app_permission(&self, app_id: &str, permission_id: &str, context: &PermissionContext) -> MisaResult<PermissionDecision> {
+        self.data_controls.has_app_permission(app_id, permission_id, context).await
     }
 
-    /// Delete user data (GDPR right to erasure)
+    /// Delete user data (GDPR right to erasure), keeping permanent memories
     pub async fn delete_user_data(&self, user_id: &str, data_types: Option<Vec<DataType>>) -> MisaResult<DeletionResult> {
+        self.erase_user_data(user_id, data_types, false).await
+    }
+
+    /// Delete user data including memories marked permanent
+    pub async fn force_delete_user_data(&self, user_id: &str, data_types: Option<Vec<DataType>>) -> MisaResult<DeletionResult> {
+        self.erase_user_data(user_id, data_types, true).await
+    }
+
+    async fn erase_user_data(&self, user_id: &str, data_types: Option<Vec<DataType>>, force: bool) -> MisaResult<DeletionResult> {
         info!("Processing data deletion request for user: {}", user_id);
 
-        let result = self.data_controls.delete_user_data(user_id, data_types).await?;
+        let result = self.data_controls
+            .delete_user_data(user_id, data_types, self.memory_manager.as_ref(), force)
+            .await?;
 
         // Log deletion for compliance
         self.compliance_manager.log_data_deletion(user_id, &result).await?;
@@ -635,14 +1226,79 @@ impl PrivacyControls {
         let data = self.collect_user_data(user_id).await?;
 
         // Apply privacy filters
-        let filtered_data = self.apply_privacy_filters(data, user_id).await?;
+        let filtered = self.apply_privacy_filters(data, user_id).await?;
 
         // Format for export
-        let export_data = self.format_for_export(filtered_data, format).await?;
+        let mut export_data = self.format_for_export(filtered.data, format).await?;
+        export_data.redacted_fields = filtered.redacted_fields;
+        export_data.anonymized_fields = filtered.anonymized_fields;
+        export_data.metadata.processing_notes.extend(filtered.notes);
 
+        info!("Exported {} files for user: {}", export_data.data_files.len(), user_id);
         Ok(export_data)
     }
 
+    /// File a data-subject request (access, erasure, ...)
+    pub async fn submit_user_request(&self, user_id: &str, request_type: UserRequestType, description: &str) -> MisaResult<UserRequest> {
+        self.compliance_manager.submit_user_request(user_id, request_type, description).await
+    }
+
+    /// Get the current state of a data-subject request
+    pub async fn get_request_status(&self, request_id: &str) -> MisaResult<Option<UserRequest>> {
+        self.compliance_manager.get_request_status(request_id).await
+    }
+
+    /// Work a data-subject request through validation and processing.
+    /// Access and portability requests are fulfilled with a data export and become `Ready`;
+    /// other request types are left in `Processing` for manual follow-up.
+    pub async fn process_request(&self, request_id: &str, format: ExportFormat) -> MisaResult<UserRequest> {
+        match self.work_request(request_id, format).await {
+            Ok(request) => Ok(request),
+            Err(e) => {
+                // A request that never left `Received` or `Ready` can't fail and is left as it was
+                let _ = self
+                    .compliance_manager
+                    .advance_request(request_id, RequestStatus::Failed, Some(e.to_string()))
+                    .await;
+                Err(e)
+            }
+        }
+    }
+
+    async fn work_request(&self, request_id: &str, format: ExportFormat) -> MisaResult<UserRequest> {
+        let request = self
+            .compliance_manager
+            .advance_request(request_id, RequestStatus::Validating, None)
+            .await?;
+
+        if request.user_id.trim().is_empty() {
+            return self
+                .compliance_manager
+                .advance_request(request_id, RequestStatus::Rejected, Some("Request has no user".to_string()))
+                .await;
+        }
+
+        let request = self
+            .compliance_manager
+            .advance_request(request_id, RequestStatus::Processing, None)
+            .await?;
+
+        match request.request_type {
+            UserRequestType::Access | UserRequestType::Portability => {
+                let export = self.export_user_data(&request.user_id, format).await?;
+                let note = format!("Exported {} files", export.data_files.len());
+                self.compliance_manager.attach_processed_data(request_id, export).await?;
+                self.compliance_manager
+                    .advance_request(request_id, RequestStatus::Ready, Some(note))
+                    .await
+            }
+            _ => {
+                warn!("{:?} request {} needs manual handling", request.request_type, request_id);
+                Ok(request)
+            }
+        }
+    }
+
     /// Anonymize data
     pub async fn anonymize_data(&self, data: &str, data_type: DataType, method: AnonymizationMethod) -> MisaResult<String> {
         self.anonymization_engine.anonymize(data, data_type, method).await
@@ -650,7 +1306,14 @@ impl PrivacyControls {
 
     /// Check privacy compliance
     pub async fn check_compliance(&self, regulation_id: &str) -> MisaResult<ComplianceReport> {
-        self.compliance_manager.generate_compliance_report(regulation_id).await
+        let context = ComplianceContext {
+            retention_days: self.data_controls.configured_retention_days().await,
+            erasure_available: self.memory_manager.is_some(),
+            export_available: true,
+            consent_templates: self.consent_manager.consent_templates.read().await.len(),
+        };
+
+        self.compliance_manager.generate_compliance_report(regulation_id, &context).await
     }
 
     /// Report data breach
@@ -665,6 +1328,17 @@ impl PrivacyControls {
         Ok(())
     }
 
+    /// Consent records whose purpose mentions `term`, across all users
+    pub async fn search_consents(&self, term: &str) -> MisaResult<Vec<ConsentRecord>> {
+        self.consent_manager.search(term).await
+    }
+
+    /// Run one record through the enabled privacy filters, returning `None` when a filter withholds it
+    pub async fn filter_for_display(&self, data_type: DataType, record: String) -> MisaResult<Option<String>> {
+        let filtered = self.apply_privacy_filters(vec![(data_type, record)], "local").await?;
+        Ok(filtered.data.into_iter().next().map(|(_, record)| record))
+    }
+
     /// Get privacy settings summary
     pub async fn get_privacy_summary(&self, user_id: &str) -> MisaResult<PrivacySummary> {
         let consents = self.consent_manager.get_user_consents(user_id).await?;
@@ -683,31 +1357,247 @@ impl PrivacyControls {
     /// Private helper methods
 
     async fn collect_user_data(&self, user_id: &str) -> MisaResult<Vec<(DataType, String)>> {
-        // In real implementation, this would query all data stores
-        Ok(Vec::new())
-    }
+        let mut data = Vec::new();
+
+        // The memory store is local to this device, so all of it belongs to the user
+        match &self.memory_manager {
+            Some(memory_manager) => {
+                let mut query = SearchQuery::new();
+                query.limit = None;
+                query.offset = None;
+                query.sort_by = SortField::CreatedAt;
+                query.sort_order = SortOrder::Asc;
+
+                for memory in memory_manager.search_memories(&query).await? {
+                    data.push((DataType::for_memory(&memory), serde_json::to_string(&memory)?));
+                }
+            }
+            None => warn!("No memory store attached; export will not include memories"),
+        }
+
+        for consent in self.consent_manager.get_user_consents(user_id).await? {
+            data.push((DataType::PersonalInfo, serde_json::to_string(&consent)?));
+        }
+
+        for control in self.data_controls.get_user_data_controls(user_id).await? {
+            data.push((DataType::DeviceData, serde_json::to_string(&control)?));
+        }
 
-    async fn apply_privacy_filters(&self, data: Vec<(DataType, String)>, user_id: &str) -> MisaResult<Vec<(DataType, String)>> {
-        // Apply privacy filters based on user preferences
+        debug!("Collected {} records for user: {}", data.len(), user_id);
         Ok(data)
     }
 
+    async fn apply_privacy_filters(&self, data: Vec<(DataType, String)>, user_id: &str) -> MisaResult<FilteredData> {
+        let mut filtered = FilteredData::default();
+        for (data_type, record) in data {
+            let (record, applied) = self.data_controls.apply_filters_for(&record, &data_type).await;
+
+            let mut withheld = false;
+            for applied in &applied {
+                match applied.outcome {
+                    FilterOutcome::Blocked => {
+                        filtered.notes.push(format!("Withheld a {:?} record ({})", data_type, applied.rule_id));
+                        withheld = true;
+                    }
+                    FilterOutcome::Redacted => Self::note_field(&mut filtered.redacted_fields, &applied.rule_id),
+                    FilterOutcome::Anonymized => Self::note_field(&mut filtered.anonymized_fields, &applied.rule_id),
+                    FilterOutcome::Logged => {
+                        debug!("Privacy filter {} saw a {:?} record for user {}", applied.rule_id, data_type, user_id);
+                    }
+                }
+            }
+
+            if !withheld {
+                filtered.data.push((data_type, record));
+            }
+        }
+
+        Ok(filtered)
+    }
+
     async fn format_for_export(&self, data: Vec<(DataType, String)>, format: ExportFormat) -> MisaResult<ProcessedUserData> {
-        // Format data according to export format
+        let (extension, content_type) = match format {
+            ExportFormat::JSON => ("json", "application/json"),
+            ExportFormat::CSV => ("csv", "text/csv"),
+            ExportFormat::XML | ExportFormat::PDF => {
+                return Err(MisaError::Validation(format!("Export format {:?} is not supported", format)));
+            }
+        };
+
+        // Group records by category, keeping the order categories first appear in
+        let mut categories: Vec<(DataType, Vec<serde_json::Value>)> = Vec::new();
+        for (data_type, record) in data {
+            let value = serde_json::from_str(&record).unwrap_or(serde_json::Value::String(record));
+            match categories.iter_mut().find(|(existing, _)| *existing == data_type) {
+                Some((_, records)) => records.push(value),
+                None => categories.push((data_type, vec![value])),
+            }
+        }
+
+        let exported_at = chrono::Utc::now();
+        let export_dir = Path::new(&self.data_dir)
+            .join("exports")
+            .join(format!("{}-{}", exported_at.format("%Y%m%d%H%M%S"), uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(&export_dir).await?;
+
+        let mut data_files = Vec::new();
+        let mut time_range: Option<(chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)> = None;
+        for (data_type, records) in &categories {
+            for created_at in records.iter().filter_map(|record| {
+                record.get("created_at").and_then(|value| serde_json::from_value(value.clone()).ok())
+            }) {
+                time_range = Some(match time_range {
+                    Some((start, end)) => (start.min(created_at), end.max(created_at)),
+                    None => (created_at, created_at),
+                });
+            }
+
+            let contents = match format {
+                ExportFormat::CSV => Self::records_to_csv(records),
+                _ => serde_json::to_string_pretty(records)?,
+            };
+
+            let filename = format!("{}.{}", Self::export_file_stem(data_type), extension);
+            let path = export_dir.join(&filename);
+            tokio::fs::write(&path, contents.as_bytes()).await?;
+
+            data_files.push(DataFile {
+                filename,
+                path: path.to_string_lossy().into_owned(),
+                content_type: content_type.to_string(),
+                size_bytes: contents.len() as u64,
+                data_type: data_type.clone(),
+            });
+        }
+
         Ok(ProcessedUserData {
             export_format: format,
-            data_files: Vec::new(),
             metadata: ExportMetadata {
-                exported_at: chrono::Utc::now(),
-                total_size_bytes: 0,
-                data_categories: Vec::new(),
-                time_range: None,
+                exported_at,
+                total_size_bytes: data_files.iter().map(|file| file.size_bytes).sum(),
+                data_categories: categories.iter().map(|(data_type, _)| data_type.clone()).collect(),
+                time_range,
                 processing_notes: Vec::new(),
             },
+            data_files,
             redacted_fields: Vec::new(),
             anonymized_fields: Vec::new(),
         })
     }
+
+    fn rule_regex(pattern: &str, parameters: &serde_json::Value) -> Option<regex::Regex> {
+        let case_sensitive = parameters.get("case_sensitive").and_then(|value| value.as_bool()).unwrap_or(true);
+        match regex::RegexBuilder::new(pattern).case_insensitive(!case_sensitive).build() {
+            Ok(re) => Some(re),
+            Err(e) => {
+                debug!("Skipping privacy rule with invalid pattern {}: {}", pattern, e);
+                None
+            }
+        }
+    }
+
+    /// Rules may restrict themselves to data types via a `data_types` parameter
+    fn rule_applies_to(rule: &FilterRule, data_type: &DataType) -> bool {
+        match rule.parameters.get("data_types") {
+            Some(data_types) => serde_json::from_value::<Vec<DataType>>(data_types.clone())
+                .map_or(false, |data_types| data_types.contains(data_type)),
+            None => true,
+        }
+    }
+
+    fn note_field(fields: &mut Vec<String>, rule_id: &str) {
+        if !fields.iter().any(|field| field == rule_id) {
+            fields.push(rule_id.to_string());
+        }
+    }
+
+    fn export_file_stem(data_type: &DataType) -> String {
+        let mut stem = String::new();
+        for (i, c) in format!("{:?}", data_type).chars().enumerate() {
+            if c.is_uppercase() && i > 0 {
+                stem.push('_');
+            }
+            stem.push(c.to_ascii_lowercase());
+        }
+        stem
+    }
+
+    /// One row per record and one column per top-level field
+    fn records_to_csv(records: &[serde_json::Value]) -> String {
+        let mut columns = std::collections::BTreeSet::new();
+        for record in records {
+            match record.as_object() {
+                Some(object) => columns.extend(object.keys().cloned()),
+                None => {
+                    columns.insert("value".to_string());
+                }
+            }
+        }
+        let columns: Vec<String> = columns.into_iter().collect();
+
+        let escape = |field: &str| {
+            if field.contains(|c: char| matches!(c, ',' | '"' | '\n' | '\r')) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        };
+
+        let mut csv = columns.iter().map(|column| escape(column)).collect::<Vec<_>>().join(",");
+        csv.push('\n');
+        for record in records {
+            let row: Vec<String> = columns
+                .iter()
+                .map(|column| {
+                    let value = match record.as_object() {
+                        Some(object) => object.get(column),
+                        None if column == "value" => Some(record),
+                        None => None,
+                    };
+                    match value {
+                        Some(serde_json::Value::String(text)) => escape(text),
+                        Some(serde_json::Value::Null) | None => String::new(),
+                        Some(other) => escape(&other.to_string()),
+                    }
+                })
+                .collect();
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+#[async_trait::async_trait]
+impl ContentFilter for DataControls {
+    /// Filter content from sources that require anonymization; other sources pass through
+    async fn filter_content(&self, source: &str, content: &str) -> MisaResult<Option<String>> {
+        let anonymization_required = self
+            .get_source_status(source)
+            .await?
+            .map_or(false, |control| control.anonymization_required);
+        if !anonymization_required {
+            return Ok(Some(content.to_string()));
+        }
+
+        let (filtered, applied) = self.apply_filters(content).await;
+        if applied.iter().any(|filter| filter.outcome == FilterOutcome::Blocked) {
+            return Ok(None);
+        }
+        if !applied.is_empty() {
+            debug!("Applied {} privacy rules to content from {}", applied.len(), source);
+        }
+        Ok(Some(filtered))
+    }
+}
+
+/// Collected records after privacy filters ran
+#[derive(Debug, Default)]
+struct FilteredData {
+    data: Vec<(DataType, String)>,
+    redacted_fields: Vec<String>,
+    anonymized_fields: Vec<String>,
+    notes: Vec<String>,
 }
 
 /// Privacy summary for user
@@ -731,12 +1621,24 @@ pub struct DeletionResult {
     pub completion_time: chrono::DateTime<chrono::Utc>,
 }
 
+/// File under the data directory holding granted and revoked consents
+const CONSENTS_FILE: &str = "consents.json";
+
+/// File under the data directory holding per-app permissions
+const APP_PERMISSIONS_FILE: &str = "app_permissions.json";
+
 impl ConsentManager {
     pub async fn new(data_dir: &str) -> MisaResult<Self> {
+        let store_path = Path::new(data_dir).join(CONSENTS_FILE);
+        let consents = read_json_map(&store_path).await?;
+        debug!("Loaded {} consent records from {}", consents.len(), store_path.display());
+
         let mut manager = Self {
-            consents: Arc::new(RwLock::new(HashMap::new())),
+            consents: Arc::new(RwLock::new(consents)),
+            store_path,
             consent_templates: Arc::new(RwLock::new(HashMap::new())),
             active_sessions: Arc::new(RwLock::new(HashMap::new())),
+            events: broadcast::channel(64).0,
         };
 
         // Initialize default consent templates
@@ -770,7 +1672,7 @@ impl ConsentManager {
                 version: "1.0".to_string(),
                 expiry_days: Some(180),
                 privacy_policy_url: Some("https://misa.ai/privacy".to_string()),
-                help_text: "All data is anonymized and aggregated".to_string(),
+                help_text: Some("All data is anonymized and aggregated".to_string()),
             },
             ConsentTemplate {
                 template_id: "biometric".to_string(),
@@ -782,7 +1684,7 @@ impl ConsentManager {
                 version: "1.0".to_string(),
                 expiry_days: None,
                 privacy_policy_url: Some("https://misa.ai/privacy".to_string()),
-                help_text: "Biometric data never leaves your device".to_string(),
+                help_text: Some("Biometric data never leaves your device".to_string()),
             },
             ConsentTemplate {
                 template_id: "voice_assistant".to_string(),
@@ -794,7 +1696,19 @@ impl ConsentManager {
                 version: "1.0".to_string(),
                 expiry_days: Some(365),
                 privacy_policy_url: Some("https://misa.ai/privacy".to_string()),
-                help_text: "Voice data is processed locally and optionally sent to AI models".to_string(),
+                help_text: Some("Voice data is processed locally and optionally sent to AI models".to_string()),
+            },
+            ConsentTemplate {
+                template_id: "screen_capture".to_string(),
+                name: "Screen Understanding".to_string(),
+                description: "Read text on your screen to summarize and remember it".to_string(),
+                consent_type: ConsentType::ScreenCapture,
+                data_types: vec![DataType::TextData, DataType::VideoData],
+                required: false,
+                version: "1.0".to_string(),
+                expiry_days: Some(90),
+                privacy_policy_url: Some("https://misa.ai/privacy".to_string()),
+                help_text: Some("Screenshots are processed on your device and only summaries are kept".to_string()),
             },
         ];
 
@@ -808,10 +1722,20 @@ impl ConsentManager {
     }
 
     pub async fn create_consent_session(&self, user_id: &str, consent_type: ConsentType, context: serde_json::Value) -> MisaResult<String> {
+        let template_id = {
+            let templates = self.consent_templates.read().await;
+            templates.values()
+                .find(|template| template.consent_type == consent_type)
+                .map(|template| template.template_id.clone())
+                .ok_or_else(|| MisaError::Validation(format!("No consent template for {:?}", consent_type)))?
+        };
+
         let session_id = uuid::Uuid::new_v4().to_string();
         let session = ConsentSession {
             session_id: session_id.clone(),
             user_id: user_id.to_string(),
+            consent_type,
+            template_id,
             requested_consents: Vec::new(),
             status: ConsentSessionStatus::Pending,
             created_at: chrono::Utc::now(),
@@ -830,7 +1754,7 @@ impl ConsentManager {
 
         for consent in consents.values() {
             if consent.user_id == user_id && consent.consent_type == consent_type {
-                if consent.granted {
+                if consent.granted && consent.expired_at.is_none() {
                     // Check if consent is still valid
                     if let Some(expires_at) = consent.expires_at {
                         if chrono::Utc::now() < expires_at {
@@ -855,14 +1779,28 @@ impl ConsentManager {
 
         let session = session.ok_or_else(|| MisaError::Security("Invalid session ID".to_string()))?;
 
-        // Find the appropriate template
-        let template = {
-            let templates = self.consent_templates.read().await;
-            // For simplicity, we'll use the first consent type from the context
-            templates.values().next().cloned()
-        };
+        if session.user_id != user_id {
+            return Err(MisaError::Security("Consent session belongs to another user".to_string()));
+        }
 
-        if let Some(template) = template {
+        if chrono::Utc::now() >= session.expires_at {
+            let mut sessions = self.active_sessions.write().await;
+            if let Some(session) = sessions.get_mut(session_id) {
+                session.status = ConsentSessionStatus::Expired;
+            }
+            return Err(PrivacyError::ConsentExpired {
+                consent_id: session_id.to_string(),
+            }
+            .into());
+        }
+
+        // Find the template the session was created for
+        let template = {
+            let templates = self.consent_templates.read().await;
+            templates.get(&session.template_id).cloned()
+        };
+
+        if let Some(template) = template {
             // Create consent record
             let consent_record = ConsentRecord {
                 consent_id: uuid::Uuid::new_v4().to_string(),
@@ -874,6 +1812,7 @@ impl ConsentManager {
                 granted_at: Some(chrono::Utc::now()),
                 expires_at: template.expiry_days.map(|days| chrono::Utc::now() + chrono::Duration::days(days as i64)),
                 revoked_at: None,
+                expired_at: None,
                 version: template.version.clone(),
                 metadata: serde_json::json!({
                     "session_id": session_id,
@@ -882,17 +1821,24 @@ impl ConsentManager {
                 }),
             };
 
-            // Store consent record
+            let consent_id = consent_record.consent_id.clone();
+
+            // Store consent record, keeping the lock until it is on disk so writes stay ordered
             let mut consents = self.consents.write().await;
-            consents.insert(consent_record.consent_id.clone(), consent_record);
+            consents.insert(consent_id.clone(), consent_record);
+            write_json_atomic(&self.store_path, &*consents).await?;
+            drop(consents);
 
             // Update session status
             let mut sessions = self.active_sessions.write().await;
             if let Some(session) = sessions.get_mut(session_id) {
                 session.status = ConsentSessionStatus::Granted;
+                session.requested_consents.push(consent_id);
             }
 
             info!("Consent granted for user: {}, type: {:?}", user_id, template.consent_type);
+        } else {
+            return Err(MisaError::Validation(format!("Consent template {} no longer exists", session.template_id)));
         }
 
         Ok(())
@@ -908,7 +1854,67 @@ impl ConsentManager {
             }
         }
 
-        Ok(())
+        write_json_atomic(&self.store_path, &*consents).await
+    }
+
+    /// Mark consents past `expires_at` as expired and emit `ReConsentRequired` for them
+    /// and, once per consent, for those expiring within `window` of `now`
+    pub async fn sweep_expiring(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+        window: chrono::Duration,
+    ) -> MisaResult<ConsentSweepReport> {
+        let mut report = ConsentSweepReport::default();
+        let mut events = Vec::new();
+        let mut consents = self.consents.write().await;
+
+        for consent in consents.values_mut() {
+            let expires_at = match consent.expires_at {
+                Some(expires_at) if consent.granted && consent.expired_at.is_none() => expires_at,
+                _ => continue,
+            };
+
+            let expired = now >= expires_at;
+            if expired {
+                consent.expired_at = Some(now);
+                report.expired.push(consent.consent_id.clone());
+            } else if expires_at - now > window || consent.metadata.get(RE_CONSENT_NOTIFIED_KEY).is_some() {
+                continue;
+            } else {
+                // The marker needs an object to live in; anything else stored there is kept inside it
+                if !consent.metadata.is_object() {
+                    consent.metadata = match std::mem::take(&mut consent.metadata) {
+                        serde_json::Value::Null => serde_json::json!({}),
+                        previous => serde_json::json!({ "value": previous }),
+                    };
+                }
+                consent.metadata[RE_CONSENT_NOTIFIED_KEY] = serde_json::json!(now);
+            }
+
+            report.notified.push(consent.consent_id.clone());
+            events.push(PrivacyEvent::ReConsentRequired {
+                user_id: consent.user_id.clone(),
+                consent_id: consent.consent_id.clone(),
+                consent_type: consent.consent_type.clone(),
+                expires_at,
+                expired,
+            });
+        }
+
+        if !events.is_empty() {
+            write_json_atomic(&self.store_path, &*consents).await?;
+        }
+        drop(consents);
+
+        for event in events {
+            if let PrivacyEvent::ReConsentRequired { user_id, consent_type, .. } = &event {
+                info!("Re-consent required for user: {}, type: {:?}", user_id, consent_type);
+            }
+            // Nobody listening is fine; the consent is still marked on disk
+            let _ = self.events.send(event);
+        }
+
+        Ok(report)
     }
 
     pub async fn get_user_consents(&self, user_id: &str) -> MisaResult<Vec<ConsentRecord>> {
@@ -919,15 +1925,32 @@ impl ConsentManager {
             .collect();
         Ok(user_consents)
     }
+
+    /// Consents whose purpose contains `term`, ignoring case
+    pub async fn search(&self, term: &str) -> MisaResult<Vec<ConsentRecord>> {
+        let needle = term.to_lowercase();
+        let consents = self.consents.read().await;
+        Ok(consents
+            .values()
+            .filter(|c| c.purpose.to_lowercase().contains(&needle))
+            .cloned()
+            .collect())
+    }
 }
 
 impl DataControls {
-    pub async fn new() -> MisaResult<Self> {
+    pub async fn new(data_dir: &str) -> MisaResult<Self> {
+        let permissions_path = Path::new(data_dir).join(APP_PERMISSIONS_FILE);
+        let app_permissions = read_json_map(&permissions_path).await?;
+
         let mut controls = Self {
             source_controls: Arc::new(RwLock::new(HashMap::new())),
-            app_permissions: Arc::new(RwLock::new(HashMap::new())),
+            app_permissions: Arc::new(RwLock::new(app_permissions)),
+            permissions_path,
             data_retention: Arc::new(RwLock::new(DataRetentionPolicy::default())),
             privacy_filters: Arc::new(RwLock::new(HashMap::new())),
+            compiled_patterns: Arc::new(RwLock::new(HashMap::new())),
+            anonymization_engine: AnonymizationEngine::new().await?,
         };
 
         // Initialize default data source controls
@@ -1096,11 +2119,11 @@ impl DataControls {
                 rules: vec![
                     FilterRule {
                         rule_id: "geo_generalization".to_string(),
-                        condition: "precision_high".to_string(),
+                        condition: PRECISE_COORDINATE_PATTERN.to_string(),
                         action: FilterAction::Anonymize {
                             method: AnonymizationMethod::Generalize,
                         },
-                        parameters: serde_json::json!({"precision": "city_level"}),
+                        parameters: serde_json::json!({"precision": "city_level", "data_types": ["LocationData"]}),
                     },
                 ],
                 enabled: true,
@@ -1125,42 +2148,294 @@ impl DataControls {
         Ok(())
     }
 
+    /// Run text through the enabled privacy filters, highest priority first.
+    /// A blocking rule empties the text and stops evaluation.
+    pub async fn apply_filters(&self, text: &str) -> (String, Vec<AppliedFilter>) {
+        self.apply_filters_for(text, &DataType::TextData).await
+    }
+
+    /// Like `apply_filters`, for a record of `data_type`; rules scoped to other data types are skipped
+    pub async fn apply_filters_for(&self, text: &str, data_type: &DataType) -> (String, Vec<AppliedFilter>) {
+        let mut filters: Vec<PrivacyFilter> = self
+            .privacy_filters
+            .read()
+            .await
+            .values()
+            .filter(|filter| filter.enabled)
+            .cloned()
+            .collect();
+        filters.sort_by(|a, b| b.priority.cmp(&a.priority).then_with(|| a.filter_id.cmp(&b.filter_id)));
+
+        let mut text = text.to_string();
+        let mut applied = Vec::new();
+        for filter in &filters {
+            for rule in &filter.rules {
+                if !PrivacyControls::rule_applies_to(rule, data_type) {
+                    continue;
+                }
+
+                let fired = match &rule.action {
+                    FilterAction::Block => {
+                        let matches = self.count_matches(&rule.condition, &rule.parameters, &text).await;
+                        if matches > 0 {
+                            applied.push(AppliedFilter {
+                                filter_id: filter.filter_id.clone(),
+                                rule_id: rule.rule_id.clone(),
+                                outcome: FilterOutcome::Blocked,
+                                matches,
+                            });
+                            return (String::new(), applied);
+                        }
+                        None
+                    }
+                    FilterAction::Redact { pattern, replacement } => {
+                        match self.compiled_pattern(pattern, &rule.parameters).await {
+                            Some(re) => {
+                                let matches = re.find_iter(&text).count();
+                                if matches > 0 {
+                                    text = re.replace_all(&text, replacement.as_str()).into_owned();
+                                }
+                                Some((FilterOutcome::Redacted, matches))
+                            }
+                            None => None,
+                        }
+                    }
+                    FilterAction::Anonymize { method } => match self.compiled_pattern(&rule.condition, &rule.parameters).await {
+                        Some(re) => {
+                            let spans: Vec<(usize, usize)> = re.find_iter(&text).map(|m| (m.start(), m.end())).collect();
+                            let mut anonymized = String::with_capacity(text.len());
+                            let mut last = 0;
+                            for (start, end) in &spans {
+                                anonymized.push_str(&text[last..*start]);
+                                match self
+                                    .anonymization_engine
+                                    .anonymize(&text[*start..*end], data_type.clone(), method.clone())
+                                    .await
+                                {
+                                    Ok(replacement) => anonymized.push_str(&replacement),
+                                    // Drop the span rather than leak it
+                                    Err(e) => warn!("Privacy rule {} failed to anonymize a span: {}", rule.rule_id, e),
+                                }
+                                last = *end;
+                            }
+                            anonymized.push_str(&text[last..]);
+                            text = anonymized;
+                            Some((FilterOutcome::Anonymized, spans.len()))
+                        }
+                        None => None,
+                    },
+                    FilterAction::Log => {
+                        let matches = self.count_matches(&rule.condition, &rule.parameters, &text).await;
+                        if matches > 0 {
+                            debug!("Privacy rule {} matched {} spans", rule.rule_id, matches);
+                        }
+                        Some((FilterOutcome::Logged, matches))
+                    }
+                    FilterAction::Transform { .. } => None,
+                };
+
+                if let Some((outcome, matches)) = fired.filter(|(_, matches)| *matches > 0) {
+                    applied.push(AppliedFilter {
+                        filter_id: filter.filter_id.clone(),
+                        rule_id: rule.rule_id.clone(),
+                        outcome,
+                        matches,
+                    });
+                }
+            }
+        }
+
+        (text, applied)
+    }
+
+    /// Compile a rule pattern the first time it is used
+    async fn compiled_pattern(&self, pattern: &str, parameters: &serde_json::Value) -> Option<regex::Regex> {
+        let case_sensitive = parameters.get("case_sensitive").and_then(|value| value.as_bool()).unwrap_or(true);
+        let key = (pattern.to_string(), case_sensitive);
+        if let Some(compiled) = self.compiled_patterns.read().await.get(&key) {
+            return compiled.clone();
+        }
+
+        let compiled = PrivacyControls::rule_regex(pattern, parameters);
+        self.compiled_patterns.write().await.insert(key, compiled.clone());
+        compiled
+    }
+
+    async fn count_matches(&self, pattern: &str, parameters: &serde_json::Value, text: &str) -> usize {
+        self.compiled_pattern(pattern, parameters)
+            .await
+            .map_or(0, |re| re.find_iter(text).count())
+    }
+
     pub async fn get_source_status(&self, source_id: &str) -> MisaResult<Option<DataSourceControl>> {
         let controls = self.source_controls.read().await;
         Ok(controls.get(source_id).cloned())
     }
 
+    /// Longest explicitly configured retention for each data type
+    pub async fn configured_retention_days(&self) -> HashMap<DataType, u32> {
+        let mut retention_days: HashMap<DataType, u32> = HashMap::new();
+
+        let retention = self.data_retention.read().await;
+        for (data_type, policy) in &retention.category_policies {
+            let days = retention_days.entry(data_type.clone()).or_default();
+            *days = (*days).max(policy.retention_days);
+        }
+
+        let sources = self.source_controls.read().await;
+        for source in sources.values() {
+            if let Some(rule) = &source.retention_policy {
+                for data_type in &source.data_types {
+                    let days = retention_days.entry(data_type.clone()).or_default();
+                    *days = (*days).max(rule.max_age_days);
+                }
+            }
+        }
+
+        retention_days
+    }
+
     pub async fn set_app_permission(&self, app_id: &str, permission_id: &str, granted: bool) -> MisaResult<()> {
         let mut permissions = self.app_permissions.write().await;
-        if let Some(app_perms) = permissions.get_mut(app_id) {
-            if let Some(permission) = app_perms.permissions.get_mut(permission_id) {
-                permission.granted = granted;
-                permission.granted_at = Some(chrono::Utc::now());
-            }
+        let changed = match permissions.get_mut(app_id) {
+            Some(app_perms) => match app_perms.permissions.get_mut(permission_id) {
+                Some(permission) => {
+                    permission.granted = granted;
+                    permission.granted_at = Some(chrono::Utc::now());
+                    app_perms.last_updated = chrono::Utc::now();
+                    true
+                }
+                None => false,
+            },
+            None => false,
+        };
+
+        if changed {
+            write_json_atomic(&self.permissions_path, &*permissions).await?;
         }
         Ok(())
     }
 
-    pub async fn has_app_permission(&self, app_id: &str, permission_id: &str) -> MisaResult<bool> {
+    pub async fn has_app_permission(&self, app_id: &str, permission_id: &str, context: &PermissionContext) -> MisaResult<PermissionDecision> {
         let permissions = self.app_permissions.read().await;
-        if let Some(app_perms) = permissions.get(app_id) {
-            if let Some(permission) = app_perms.permissions.get(permission_id) {
-                return Ok(permission.granted);
+        let permission = match permissions.get(app_id).and_then(|app_perms| app_perms.permissions.get(permission_id)) {
+            Some(permission) => permission,
+            None => return Ok(PermissionDecision::Denied),
+        };
+
+        if !permission.granted || permission.expires_at.map_or(false, |expires_at| context.now >= expires_at) {
+            return Ok(PermissionDecision::Denied);
+        }
+
+        let mut needs_confirmation = false;
+        for condition in &permission.conditions {
+            let satisfied = match condition {
+                PermissionCondition::TimeRange { start, end } => Self::within_time_range(start, end, context.local_time),
+                PermissionCondition::LocationBased { allowed_locations } => context.location.as_ref().map_or(false, |location| {
+                    allowed_locations.iter().any(|allowed| allowed.eq_ignore_ascii_case(location))
+                }),
+                PermissionCondition::UserPresent => context.user_present,
+                PermissionCondition::DeviceLocked => context.device_locked,
+                PermissionCondition::NetworkSecure => context.network_secure,
+                PermissionCondition::ExplicitConfirmation => {
+                    needs_confirmation |= !context.confirmed;
+                    true
+                }
+            };
+
+            if !satisfied {
+                debug!("Permission {} for app {} failed condition {:?}", permission_id, app_id, condition);
+                return Ok(PermissionDecision::Denied);
             }
         }
-        Ok(false)
+
+        Ok(if needs_confirmation {
+            PermissionDecision::NeedsConfirmation
+        } else {
+            PermissionDecision::Granted
+        })
     }
 
-    pub async fn delete_user_data(&self, user_id: &str, data_types: Option<Vec<DataType>>) -> MisaResult<DeletionResult> {
-        // Implementation would delete user data from all stores
-        Ok(DeletionResult {
+    fn within_time_range(start: &str, end: &str, time: chrono::NaiveTime) -> bool {
+        let parse = |value: &str| chrono::NaiveTime::parse_from_str(value, "%H:%M");
+        match (parse(start), parse(end)) {
+            (Ok(start), Ok(end)) if start <= end => start <= time && time < end,
+            (Ok(start), Ok(end)) => time >= start || time < end,
+            _ => {
+                // Fail closed rather than grant on a malformed window
+                warn!("Invalid permission time range {} - {}", start, end);
+                false
+            }
+        }
+    }
+
+    pub async fn delete_user_data(
+        &self,
+        user_id: &str,
+        data_types: Option<Vec<DataType>>,
+        memory_manager: Option<&MemoryManager>,
+        force: bool,
+    ) -> MisaResult<DeletionResult> {
+        let mut result = DeletionResult {
             user_id: user_id.to_string(),
             deleted_items: 0,
             failed_items: 0,
             categories_deleted: Vec::new(),
             errors: Vec::new(),
             completion_time: chrono::Utc::now(),
-        })
+        };
+
+        let memory_manager = match memory_manager {
+            Some(memory_manager) => memory_manager,
+            None => {
+                result.errors.push("No memory store attached".to_string());
+                return Ok(result);
+            }
+        };
+
+        let mut query = SearchQuery::new();
+        query.limit = None;
+        query.offset = None;
+        let memories = memory_manager.search_memories(&query).await?;
+        let retention = self.data_retention.read().await.clone();
+
+        for memory in memories {
+            let data_type = DataType::for_memory(&memory);
+            if data_types.as_ref().map_or(false, |requested| !requested.contains(&data_type)) {
+                continue;
+            }
+            if matches!(memory.memory_type, MemoryType::Permanent) && !force {
+                debug!("Keeping permanent memory {}", memory.id);
+                continue;
+            }
+
+            let secure_delete = retention
+                .category_policies
+                .get(&data_type)
+                .map_or(false, |policy| policy.secure_delete);
+
+            match memory_manager.erase_memory(&memory.id, secure_delete).await {
+                Ok(true) => {
+                    result.deleted_items += 1;
+                    if !result.categories_deleted.contains(&data_type) {
+                        result.categories_deleted.push(data_type);
+                    }
+                }
+                // Already gone, e.g. pruned concurrently
+                Ok(false) => {}
+                Err(e) => {
+                    result.failed_items += 1;
+                    result.errors.push(format!("Failed to delete memory {}: {}", memory.id, e));
+                }
+            }
+        }
+
+        result.completion_time = chrono::Utc::now();
+        info!(
+            "Deleted {} items for user {} ({} failed)",
+            result.deleted_items, user_id, result.failed_items
+        );
+        Ok(result)
     }
 
     pub async fn get_user_data_controls(&self, _user_id: &str) -> MisaResult<Vec<DataSourceControl>> {
@@ -1176,28 +2451,265 @@ impl DataControls {
 
 impl ComplianceManager {
     pub async fn new(_data_dir: &str) -> MisaResult<Self> {
-        Ok(Self {
+        let manager = Self {
             regulations: Arc::new(RwLock::new(HashMap::new())),
             compliance_reports: Arc::new(RwLock::new(Vec::new())),
             data_breach_logs: Arc::new(RwLock::new(Vec::new())),
             user_requests: Arc::new(RwLock::new(HashMap::new())),
-        })
+        };
+
+        manager.initialize_default_regulations().await;
+
+        Ok(manager)
+    }
+
+    /// Register the regulations supported out of the box
+    async fn initialize_default_regulations(&self) {
+        fn requirement(id: &str, description: &str, check: ComplianceCheck) -> Requirement {
+            Requirement {
+                requirement_id: id.to_string(),
+                description: description.to_string(),
+                mandatory: true,
+                implementation_status: ImplementationStatus::NotImplemented,
+                evidence: Vec::new(),
+                check: Some(check),
+            }
+        }
+
+        let gdpr = Regulation {
+            regulation_id: "gdpr".to_string(),
+            name: "General Data Protection Regulation".to_string(),
+            jurisdiction: "EU".to_string(),
+            requirements: vec![
+                requirement("gdpr_art5_storage_limitation", "Personal data is kept no longer than necessary", ComplianceCheck::RetentionLimits),
+                requirement("gdpr_art7_consent", "Processing is based on recorded, withdrawable consent", ComplianceCheck::ConsentManagement),
+                requirement("gdpr_art17_erasure", "Users can have their personal data erased", ComplianceCheck::Erasure),
+                requirement("gdpr_art20_portability", "Users can receive their data in a machine-readable format", ComplianceCheck::DataPortability),
+                requirement("gdpr_art33_breach_notification", "Breaches are reported within 72 hours", ComplianceCheck::BreachNotification),
+            ],
+            retention_limits: HashMap::from([
+                (DataType::BiometricData, 30),
+                (DataType::LocationData, 90),
+                (DataType::AudioData, 90),
+                (DataType::VideoData, 30),
+                (DataType::HealthData, 365),
+            ]),
+            user_rights: vec![
+                UserRight::Access,
+                UserRight::Rectification,
+                UserRight::Erasure,
+                UserRight::Portability,
+                UserRight::Restriction,
+                UserRight::Objection,
+                UserRight::AutomatedDecisionMaking,
+            ],
+            breach_notification_days: 3,
+        };
+
+        let ccpa = Regulation {
+            regulation_id: "ccpa".to_string(),
+            name: "California Consumer Privacy Act".to_string(),
+            jurisdiction: "US-CA".to_string(),
+            requirements: vec![
+                requirement("ccpa_1798_100_right_to_know", "Consumers can access the personal information collected about them", ComplianceCheck::DataPortability),
+                requirement("ccpa_1798_105_deletion", "Consumers can have their personal information deleted", ComplianceCheck::Erasure),
+                requirement("ccpa_1798_120_opt_out", "Consumers can opt out of the use of their personal information", ComplianceCheck::ConsentManagement),
+                requirement("ccpa_1798_150_security", "Breaches of personal information are disclosed promptly", ComplianceCheck::BreachNotification),
+            ],
+            retention_limits: HashMap::new(),
+            user_rights: vec![
+                UserRight::Access,
+                UserRight::Erasure,
+                UserRight::Portability,
+                UserRight::Objection,
+            ],
+            breach_notification_days: 45,
+        };
+
+        let mut regulations = self.regulations.write().await;
+        for regulation in [gdpr, ccpa] {
+            regulations.insert(regulation.regulation_id.clone(), regulation);
+        }
     }
 
-    pub async fn generate_compliance_report(&self, regulation_id: &str) -> MisaResult<ComplianceReport> {
+    pub async fn generate_compliance_report(&self, regulation_id: &str, context: &ComplianceContext) -> MisaResult<ComplianceReport> {
+        let regulation = self
+            .regulations
+            .read()
+            .await
+            .get(regulation_id)
+            .cloned()
+            .ok_or_else(|| MisaError::Validation(format!("Unknown regulation: {}", regulation_id)))?;
+
+        let mut requirement_statuses = Vec::new();
+        let mut recommendations = Vec::new();
+        let mut overall_status = if regulation.requirements.is_empty() {
+            ComplianceStatus::PendingReview
+        } else {
+            ComplianceStatus::Compliant
+        };
+
+        for requirement in &regulation.requirements {
+            let status = self.evaluate_requirement(requirement, &regulation, context).await;
+
+            let requirement_compliance = Self::requirement_compliance(&status.status, requirement.mandatory);
+            if !matches!(requirement_compliance, ComplianceStatus::Compliant) {
+                recommendations.push(format!("{}: {}", requirement.description, status.findings));
+            }
+            if Self::severity(&requirement_compliance) > Self::severity(&overall_status) {
+                overall_status = requirement_compliance;
+            }
+
+            requirement_statuses.push(status);
+        }
+
+        let now = chrono::Utc::now();
         let report = ComplianceReport {
             report_id: uuid::Uuid::new_v4().to_string(),
             regulation_id: regulation_id.to_string(),
-            generated_at: chrono::Utc::now(),
-            overall_status: ComplianceStatus::Compliant,
-            requirement_statuses: Vec::new(),
-            recommendations: Vec::new(),
-            next_review_date: chrono::Utc::now() + chrono::Duration::days(30),
+            generated_at: now,
+            overall_status,
+            requirement_statuses,
+            recommendations,
+            next_review_date: now + chrono::Duration::days(30),
         };
 
+        info!("Compliance report for {}: {:?}", regulation_id, report.overall_status);
+        self.compliance_reports.write().await.push(report.clone());
+
         Ok(report)
     }
 
+    /// Evaluate a requirement against the current system state
+    async fn evaluate_requirement(&self, requirement: &Requirement, regulation: &Regulation, context: &ComplianceContext) -> RequirementStatus {
+        let check = match requirement.check {
+            Some(check) => check,
+            None => {
+                return RequirementStatus {
+                    requirement_id: requirement.requirement_id.clone(),
+                    status: requirement.implementation_status.clone(),
+                    findings: "Not automatically evaluated".to_string(),
+                    evidence: requirement.evidence.clone(),
+                };
+            }
+        };
+
+        let (status, findings, evidence) = match check {
+            ComplianceCheck::RetentionLimits => {
+                let mut violations: Vec<String> = regulation
+                    .retention_limits
+                    .iter()
+                    .filter_map(|(data_type, limit)| {
+                        context
+                            .retention_days
+                            .get(data_type)
+                            .filter(|days| *days > limit)
+                            .map(|days| format!("{:?} retained for {} days (limit {})", data_type, days, limit))
+                    })
+                    .collect();
+                violations.sort();
+
+                if violations.is_empty() {
+                    (
+                        ImplementationStatus::Compliant,
+                        "All retention policies are within regulatory limits".to_string(),
+                        vec![format!("{} data types have retention policies", context.retention_days.len())],
+                    )
+                } else {
+                    (ImplementationStatus::InProgress, violations.join("; "), Vec::new())
+                }
+            }
+            ComplianceCheck::Erasure => {
+                if context.erasure_available {
+                    (
+                        ImplementationStatus::Implemented,
+                        "User data erasure is available".to_string(),
+                        vec!["Memory store supports per-type erasure with secure delete".to_string()],
+                    )
+                } else {
+                    (ImplementationStatus::NotImplemented, "No memory store is attached for erasure".to_string(), Vec::new())
+                }
+            }
+            ComplianceCheck::DataPortability => {
+                if context.export_available {
+                    (
+                        ImplementationStatus::Implemented,
+                        "User data can be exported".to_string(),
+                        vec!["JSON and CSV export formats".to_string()],
+                    )
+                } else {
+                    (ImplementationStatus::NotImplemented, "Data export is unavailable".to_string(), Vec::new())
+                }
+            }
+            ComplianceCheck::ConsentManagement => {
+                if context.consent_templates > 0 {
+                    (
+                        ImplementationStatus::Implemented,
+                        "Consent is requested and recorded per data use".to_string(),
+                        vec![format!("{} consent templates", context.consent_templates)],
+                    )
+                } else {
+                    (ImplementationStatus::NotImplemented, "No consent templates are configured".to_string(), Vec::new())
+                }
+            }
+            ComplianceCheck::BreachNotification => {
+                let deadline = chrono::Duration::days(regulation.breach_notification_days as i64);
+                let now = chrono::Utc::now();
+                let breaches = self.data_breach_logs.read().await;
+
+                let late: Vec<&str> = breaches
+                    .iter()
+                    .filter(|breach| {
+                        let reported_at = breach.reported_at.unwrap_or(now);
+                        reported_at - breach.detected_at > deadline
+                    })
+                    .map(|breach| breach.breach_id.as_str())
+                    .collect();
+
+                if late.is_empty() {
+                    (
+                        ImplementationStatus::Implemented,
+                        format!("All breaches reported within {} days", regulation.breach_notification_days),
+                        vec![format!("{} breach records logged", breaches.len())],
+                    )
+                } else {
+                    (
+                        ImplementationStatus::NotImplemented,
+                        format!("Breaches not reported in time: {}", late.join(", ")),
+                        vec![format!("{} breach records logged", breaches.len())],
+                    )
+                }
+            }
+        };
+
+        RequirementStatus {
+            requirement_id: requirement.requirement_id.clone(),
+            status,
+            findings,
+            evidence,
+        }
+    }
+
+    fn requirement_compliance(status: &ImplementationStatus, mandatory: bool) -> ComplianceStatus {
+        match status {
+            ImplementationStatus::Implemented
+            | ImplementationStatus::Validated
+            | ImplementationStatus::Compliant => ComplianceStatus::Compliant,
+            ImplementationStatus::InProgress => ComplianceStatus::PartiallyCompliant,
+            ImplementationStatus::NotImplemented if mandatory => ComplianceStatus::NonCompliant,
+            ImplementationStatus::NotImplemented => ComplianceStatus::PartiallyCompliant,
+        }
+    }
+
+    fn severity(status: &ComplianceStatus) -> u8 {
+        match status {
+            ComplianceStatus::Compliant => 0,
+            ComplianceStatus::PendingReview => 1,
+            ComplianceStatus::PartiallyCompliant => 2,
+            ComplianceStatus::NonCompliant => 3,
+        }
+    }
+
     pub async fn log_breach(&self, breach: DataBreachRecord) -> MisaResult<()> {
         let mut logs = self.data_breach_logs.write().await;
         logs.push(breach);
@@ -1208,6 +2720,96 @@ impl ComplianceManager {
         // Log deletion for compliance audit trail
         Ok(())
     }
+
+    /// File a new data-subject request
+    pub async fn submit_user_request(&self, user_id: &str, request_type: UserRequestType, description: &str) -> MisaResult<UserRequest> {
+        let now = chrono::Utc::now();
+        let request = UserRequest {
+            request_id: uuid::Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            request_type,
+            description: description.to_string(),
+            status: RequestStatus::Received,
+            created_at: now,
+            due_date: now + chrono::Duration::days(USER_REQUEST_DEADLINE_DAYS),
+            processed_data: None,
+            notes: Vec::new(),
+        };
+
+        info!("Received {:?} request {} for user: {}", request.request_type, request.request_id, user_id);
+        self.user_requests.write().await.insert(request.request_id.clone(), request.clone());
+
+        Ok(request)
+    }
+
+    /// Look up a data-subject request, warning if it has passed its due date
+    pub async fn get_request_status(&self, request_id: &str) -> MisaResult<Option<UserRequest>> {
+        let requests = self.user_requests.read().await;
+        let request = requests.get(request_id).cloned();
+
+        if let Some(request) = &request {
+            if Self::is_overdue(request, chrono::Utc::now()) {
+                warn!("User request {} is overdue (due {})", request.request_id, request.due_date);
+            }
+        }
+
+        Ok(request)
+    }
+
+    /// Move a request to its next status; only forward transitions are allowed
+    pub async fn advance_request(&self, request_id: &str, status: RequestStatus, note: Option<String>) -> MisaResult<UserRequest> {
+        let mut requests = self.user_requests.write().await;
+        let request = requests
+            .get_mut(request_id)
+            .ok_or_else(|| MisaError::Validation(format!("Unknown user request: {}", request_id)))?;
+
+        let allowed = matches!(
+            (&request.status, &status),
+            (RequestStatus::Received, RequestStatus::Validating)
+                | (RequestStatus::Validating, RequestStatus::Processing)
+                | (RequestStatus::Processing, RequestStatus::Ready)
+                | (RequestStatus::Ready, RequestStatus::Completed)
+                | (RequestStatus::Received | RequestStatus::Validating | RequestStatus::Processing, RequestStatus::Rejected)
+                | (RequestStatus::Validating | RequestStatus::Processing, RequestStatus::Failed)
+                | (RequestStatus::Failed, RequestStatus::Validating)
+        );
+        if !allowed {
+            return Err(MisaError::Validation(format!(
+                "User request {} cannot move from {:?} to {:?}",
+                request_id, request.status, status
+            )));
+        }
+
+        debug!("User request {}: {:?} -> {:?}", request_id, request.status, status);
+        request.status = status;
+        request.notes.extend(note);
+
+        if Self::is_overdue(request, chrono::Utc::now()) {
+            warn!("User request {} is overdue (due {})", request.request_id, request.due_date);
+        }
+
+        Ok(request.clone())
+    }
+
+    /// Attach exported data to a request being processed
+    pub async fn attach_processed_data(&self, request_id: &str, data: ProcessedUserData) -> MisaResult<()> {
+        let mut requests = self.user_requests.write().await;
+        let request = requests
+            .get_mut(request_id)
+            .ok_or_else(|| MisaError::Validation(format!("Unknown user request: {}", request_id)))?;
+
+        request.processed_data = Some(data);
+        Ok(())
+    }
+
+    /// Open requests past their due date
+    fn is_overdue(request: &UserRequest, now: chrono::DateTime<chrono::Utc>) -> bool {
+        let open = !matches!(
+            request.status,
+            RequestStatus::Ready | RequestStatus::Completed | RequestStatus::Rejected | RequestStatus::Expired
+        );
+        open && now > request.due_date
+    }
 }
 
 impl AnonymizationEngine {
@@ -1216,27 +2818,176 @@ impl AnonymizationEngine {
             methods: Arc::new(RwLock::new(HashMap::new())),
             suppression_lists: Arc::new(RwLock::new(HashMap::new())),
             pseudonymization_tables: Arc::new(RwLock::new(HashMap::new())),
+            key_store: Arc::new(std::sync::RwLock::new(None)),
         })
     }
 
-    pub async fn anonymize(&self, data: &str, _data_type: DataType, method: AnonymizationMethod) -> MisaResult<String> {
+    /// Resolve pseudonymization key ids against this security manager's stored keys
+    pub fn set_key_store(&self, security_manager: SecurityManager) {
+        *self.key_store.write().unwrap_or_else(|e| e.into_inner()) = Some(security_manager);
+    }
+
+    pub async fn anonymize(&self, data: &str, data_type: DataType, method: AnonymizationMethod) -> MisaResult<String> {
         match method {
-            AnonymizationMethod::Hash => {
-                use sha2::{Sha256, Digest};
-                let mut hasher = Sha256::new();
-                hasher.update(data.as_bytes());
-                let result = hasher.finalize();
-                Ok(format!("{:x}", result))
-            }
-            AnonymizationMethod::Redact { pattern: _, replacement: _ } => {
-                Ok("[REDACTED]".to_string())
-            }
+            AnonymizationMethod::Hash => Ok(Self::sha256_hex(data)),
             AnonymizationMethod::Suppress => {
                 Ok("".to_string())
             }
-            _ => Ok(data.to_string()),
+            AnonymizationMethod::Tokenize => Ok(self.tokenize(data, data_type).await),
+            AnonymizationMethod::Pseudonymize => Ok(self.pseudonymize(data, data_type).await),
+            AnonymizationMethod::Generalize => Ok(Self::generalize_coordinates(data)),
+            AnonymizationMethod::AddNoise => Self::add_noise(data),
+        }
+    }
+
+    /// Control whether pseudonyms for a data type can be reversed.
+    /// Only allowed before any value of that type has been pseudonymized,
+    /// and `encryption_key_id` must name a key already in the key store.
+    pub async fn set_pseudonymization_policy(
+        &self,
+        data_type: DataType,
+        reversible: bool,
+        encryption_key_id: Option<String>,
+    ) -> MisaResult<()> {
+        if let Some(key_id) = &encryption_key_id {
+            let key_store = self.key_store.read().unwrap_or_else(|e| e.into_inner()).clone();
+            let found = match key_store {
+                Some(security_manager) => security_manager.has_stored_key(key_id).await,
+                None => false,
+            };
+            if !found {
+                return Err(PrivacyError::AnonymizationFailed {
+                    reason: format!("Encryption key {} not found", key_id),
+                }
+                .into());
+            }
+        }
+
+        let mut tables = self.pseudonymization_tables.write().await;
+        let table = tables
+            .entry(Self::table_id("pseudonym", &data_type))
+            .or_insert_with(|| Self::new_table("pseudonym", data_type));
+
+        if !table.mapping.is_empty() {
+            return Err(PrivacyError::AnonymizationFailed {
+                reason: format!("Pseudonym table {} is already in use", table.table_id),
+            }
+            .into());
+        }
+
+        table.reversible = reversible;
+        table.encryption_key_id = encryption_key_id;
+        Ok(())
+    }
+
+    /// Recover the original value behind a pseudonym, if its table allows it
+    pub async fn reverse_pseudonym(&self, pseudonym: &str, data_type: DataType) -> MisaResult<String> {
+        let table_id = Self::table_id("pseudonym", &data_type);
+        let tables = self.pseudonymization_tables.read().await;
+        let table = tables.get(&table_id).ok_or_else(|| PrivacyError::AnonymizationFailed {
+            reason: format!("No pseudonyms recorded for {:?}", data_type),
+        })?;
+
+        if !table.reversible || table.encryption_key_id.is_none() {
+            return Err(PrivacyError::AnonymizationFailed {
+                reason: format!("Pseudonyms for {:?} are not reversible", data_type),
+            }
+            .into());
+        }
+
+        table.mapping
+            .iter()
+            .find(|(_, value)| value.as_str() == pseudonym)
+            .map(|(original, _)| original.clone())
+            .ok_or_else(|| PrivacyError::AnonymizationFailed {
+                reason: "Unknown pseudonym".to_string(),
+            }
+            .into())
+    }
+
+    /// Same input and data type always yield the same token
+    async fn tokenize(&self, data: &str, data_type: DataType) -> String {
+        let mut tables = self.pseudonymization_tables.write().await;
+        let table = tables
+            .entry(Self::table_id("token", &data_type))
+            .or_insert_with(|| Self::new_table("token", data_type));
+
+        table.mapping
+            .entry(data.to_string())
+            .or_insert_with(|| format!("tok_{}", Self::random_hex(8)))
+            .clone()
+    }
+
+    async fn pseudonymize(&self, data: &str, data_type: DataType) -> String {
+        let mut tables = self.pseudonymization_tables.write().await;
+        let table = tables
+            .entry(Self::table_id("pseudonym", &data_type))
+            .or_insert_with(|| Self::new_table("pseudonym", data_type));
+
+        // Irreversible tables never hold the original, only its hash
+        let key = if table.reversible && table.encryption_key_id.is_some() {
+            data.to_string()
+        } else {
+            Self::sha256_hex(data)
+        };
+
+        table.mapping
+            .entry(key)
+            .or_insert_with(|| format!("pseudo_{}", Self::random_hex(6)))
+            .clone()
+    }
+
+    /// Truncate decimal coordinates to one decimal place (roughly city level)
+    fn generalize_coordinates(data: &str) -> String {
+        PRECISE_COORDINATE
+            .replace_all(data, |captures: &regex::Captures| {
+                let value: f64 = captures[0].parse().unwrap_or_default();
+                format!("{:.1}", (value * 10.0).trunc() / 10.0)
+            })
+            .into_owned()
+    }
+
+    /// Perturb a numeric value by up to 5% of its magnitude
+    fn add_noise(data: &str) -> MisaResult<String> {
+        use rand::Rng;
+
+        let value: f64 = data.trim().parse().map_err(|_| PrivacyError::AnonymizationFailed {
+            reason: "Noise can only be added to numeric values".to_string(),
+        })?;
+        // "NaN" and "inf" parse, but noise can't hide them and they'd come back out unchanged
+        if !value.is_finite() {
+            return Err(MisaError::Validation(format!("Cannot add noise to non-finite value {}", data.trim())));
+        }
+
+        let scale = (value.abs() * 0.05).max(1.0);
+        let noise = rand::thread_rng().gen_range(-scale..=scale);
+        Ok((value + noise).to_string())
+    }
+
+    fn table_id(kind: &str, data_type: &DataType) -> String {
+        format!("{}:{:?}", kind, data_type)
+    }
+
+    fn new_table(kind: &str, data_type: DataType) -> PseudonymTable {
+        PseudonymTable {
+            table_id: Self::table_id(kind, &data_type),
+            data_type,
+            mapping: HashMap::new(),
+            reversible: false,
+            encryption_key_id: None,
         }
     }
+
+    fn sha256_hex(data: &str) -> String {
+        use sha2::{Sha256, Digest};
+        let mut hasher = Sha256::new();
+        hasher.update(data.as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+
+    fn random_hex(len: usize) -> String {
+        (0..len).map(|_| format!("{:02x}", rand::random::<u8>())).collect()
+    }
 }
 
 // Implement Clone for Arc-wrapped structs
@@ -1244,8 +2995,10 @@ impl Clone for ConsentManager {
     fn clone(&self) -> Self {
         Self {
             consents: Arc::clone(&self.consents),
+            store_path: self.store_path.clone(),
             consent_templates: Arc::clone(&self.consent_templates),
             active_sessions: Arc::clone(&self.active_sessions),
+            events: self.events.clone(),
         }
     }
 }
@@ -1255,8 +3008,11 @@ impl Clone for DataControls {
         Self {
             source_controls: Arc::clone(&self.source_controls),
             app_permissions: Arc::clone(&self.app_permissions),
+            permissions_path: self.permissions_path.clone(),
             data_retention: Arc::clone(&self.data_retention),
             privacy_filters: Arc::clone(&self.privacy_filters),
+            compiled_patterns: Arc::clone(&self.compiled_patterns),
+            anonymization_engine: self.anonymization_engine.clone(),
         }
     }
 }
@@ -1278,6 +3034,7 @@ impl Clone for AnonymizationEngine {
             methods: Arc::clone(&self.methods),
             suppression_lists: Arc::clone(&self.suppression_lists),
             pseudonymization_tables: Arc::clone(&self.pseudonymization_tables),
+            key_store: Arc::clone(&self.key_store),
         }
     }
 }
@@ -1287,10 +3044,13 @@ impl Clone for PrivacyControls {
         Self {
             config: self.config.clone(),
             data_dir: self.data_dir.clone(),
-            consent_manager: ConsentManager::new("").await.unwrap(),
-            data_controls: DataControls::new().await.unwrap(),
-            compliance_manager: ComplianceManager::new("").await.unwrap(),
-            anonymization_engine: AnonymizationEngine::new().await.unwrap(),
+            consent_manager: self.consent_manager.clone(),
+            data_controls: self.data_controls.clone(),
+            compliance_manager: self.compliance_manager.clone(),
+            anonymization_engine: self.anonymization_engine.clone(),
+            memory_manager: self.memory_manager.clone(),
+            security_manager: self.security_manager.clone(),
+            scheduler: self.scheduler.clone(),
         }
     }
 }
@@ -1317,4 +3077,837 @@ impl FilterAction {
             _ => AnonymizationMethod::Hash,
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+impl PrivacyControls {
+    /// Record a granted consent directly, bypassing the consent session flow
+    pub(crate) async fn insert_test_consent(&self, user_id: &str, consent_type: ConsentType) {
+        let record = ConsentRecord {
+            consent_id: uuid::Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            consent_type,
+            purpose: "test".to_string(),
+            data_types: Vec::new(),
+            granted: true,
+            granted_at: Some(chrono::Utc::now()),
+            expires_at: None,
+            revoked_at: None,
+            expired_at: None,
+            version: "1.0".to_string(),
+            metadata: serde_json::json!({}),
+        };
+
+        let mut consents = self.consent_manager.consents.write().await;
+        consents.insert(record.consent_id.clone(), record);
+    }
+
+    /// The channel privacy events are published on
+    pub(crate) fn test_events(&self) -> broadcast::Sender<PrivacyEvent> {
+        self.consent_manager.events.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cloned_controls_share_consent_state() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let controls = PrivacyControls::new(SecurityConfig::default(), data_dir.path().to_str().unwrap())
+            .await
+            .unwrap();
+        let cloned = controls.clone();
+
+        let session_id = controls
+            .request_consent("user-1", ConsentType::CloudSync, serde_json::json!({}))
+            .await
+            .unwrap();
+        controls.grant_consent(&session_id, "user-1").await.unwrap();
+
+        let summary = cloned.get_privacy_summary("user-1").await.unwrap();
+        assert_eq!(summary.granted_consents.len(), 1);
+        assert!(summary.granted_consents[0].granted);
+    }
+
+    async fn test_memory_manager(data_dir: &tempfile::TempDir) -> MemoryManager {
+        use crate::kernel::MemoryConfig;
+        use crate::security::SecurityManager;
+
+        let dir = data_dir.path().to_str().unwrap();
+        let security_manager = SecurityManager::new(dir, SecurityConfig::default()).await.unwrap();
+        let memory_config = MemoryConfig {
+            encryption_enabled: false,
+            ..MemoryConfig::default()
+        };
+        MemoryManager::new(dir, memory_config, security_manager).await.unwrap()
+    }
+
+    fn test_memory(content: &str, content_type: ContentType, memory_type: MemoryType) -> MemoryItem {
+        let now = chrono::Utc::now();
+        MemoryItem {
+            id: uuid::Uuid::new_v4().to_string(),
+            content: content.to_string(),
+            content_type,
+            memory_type,
+            importance: crate::memory::Importance::Medium,
+            tags: Vec::new(),
+            metadata: serde_json::json!({}),
+            created_at: now,
+            last_accessed: now,
+            access_count: 0,
+            encrypted: false,
+            version: crate::memory::VersionVector::default(),
+            binary: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_json_export_contains_stored_memories() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let memory_manager = test_memory_manager(&data_dir).await;
+
+        let memory = test_memory(
+            "Dentist appointment on Tuesday, confirm with jane@example.com",
+            ContentType::Text,
+            MemoryType::LongTerm,
+        );
+        memory_manager.store_memory(memory.clone()).await.unwrap();
+
+        let controls = PrivacyControls::new(SecurityConfig::default(), data_dir.path().to_str().unwrap())
+            .await
+            .unwrap()
+            .with_memory_manager(memory_manager);
+        let export = controls.export_user_data("user-1", ExportFormat::JSON).await.unwrap();
+
+        let text_file = export
+            .data_files
+            .iter()
+            .find(|file| matches!(file.data_type, DataType::TextData))
+            .expect("no text data exported");
+        let contents = tokio::fs::read_to_string(&text_file.path).await.unwrap();
+        assert_eq!(text_file.size_bytes, contents.len() as u64);
+        assert!(contents.contains(&memory.id));
+        assert!(contents.contains("Dentist appointment on Tuesday"));
+
+        // The PII filter is on by default
+        assert!(!contents.contains("jane@example.com"));
+        assert!(export.redacted_fields.contains(&"email_redaction".to_string()));
+        assert!(export.metadata.data_categories.contains(&DataType::TextData));
+    }
+
+    #[tokio::test]
+    async fn test_delete_audio_data_removes_only_audio_memories() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let memory_manager = test_memory_manager(&data_dir).await;
+
+        let voice_notes = [
+            test_memory("voice note one", ContentType::Audio, MemoryType::MediumTerm),
+            test_memory("voice note two", ContentType::Audio, MemoryType::LongTerm),
+        ];
+        let permanent_audio = test_memory("wedding vows", ContentType::Audio, MemoryType::Permanent);
+        let text = test_memory("shopping list", ContentType::Text, MemoryType::MediumTerm);
+        for memory in voice_notes.iter().chain([&permanent_audio, &text]) {
+            memory_manager.store_memory(memory.clone()).await.unwrap();
+        }
+
+        let controls = PrivacyControls::new(SecurityConfig::default(), data_dir.path().to_str().unwrap())
+            .await
+            .unwrap()
+            .with_memory_manager(memory_manager.clone());
+        controls.data_controls.data_retention.write().await.category_policies.insert(
+            DataType::AudioData,
+            RetentionPolicy {
+                retention_days: 30,
+                archival_days: None,
+                anonymization_enabled: false,
+                secure_delete: true,
+            },
+        );
+
+        let result = controls.delete_user_data("user-1", Some(vec![DataType::AudioData])).await.unwrap();
+
+        assert_eq!(result.deleted_items, 2);
+        assert_eq!(result.failed_items, 0);
+        assert_eq!(result.categories_deleted, vec![DataType::AudioData]);
+        for memory in &voice_notes {
+            assert!(memory_manager.get_memory(&memory.id).await.unwrap().is_none());
+        }
+        assert!(memory_manager.get_memory(&permanent_audio.id).await.unwrap().is_some());
+        assert!(memory_manager.get_memory(&text.id).await.unwrap().is_some());
+
+        let forced = controls.force_delete_user_data("user-1", Some(vec![DataType::AudioData])).await.unwrap();
+        assert_eq!(forced.deleted_items, 1);
+        assert!(memory_manager.get_memory(&permanent_audio.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_granting_biometric_session_records_biometric_consent() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let manager = ConsentManager::new(data_dir.path().to_str().unwrap()).await.unwrap();
+
+        let session_id = manager
+            .create_consent_session("user-1", ConsentType::Biometric, serde_json::json!({}))
+            .await
+            .unwrap();
+        manager.grant_consent(&session_id, "user-1").await.unwrap();
+
+        let consents = manager.get_user_consents("user-1").await.unwrap();
+        assert_eq!(consents.len(), 1);
+        assert_eq!(consents[0].consent_type, ConsentType::Biometric);
+        assert_eq!(consents[0].data_types, vec![DataType::BiometricData]);
+        assert!(manager.has_consent("user-1", ConsentType::Biometric).await.unwrap());
+        assert!(!manager.has_consent("user-1", ConsentType::CloudSync).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_expired_session_cannot_be_granted() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let manager = ConsentManager::new(data_dir.path().to_str().unwrap()).await.unwrap();
+
+        let session_id = manager
+            .create_consent_session("user-1", ConsentType::Microphone, serde_json::json!({}))
+            .await
+            .unwrap();
+        manager.active_sessions.write().await.get_mut(&session_id).unwrap().expires_at =
+            chrono::Utc::now() - chrono::Duration::minutes(1);
+
+        let result = manager.grant_consent(&session_id, "user-1").await;
+        assert!(matches!(result, Err(MisaError::Privacy(_))));
+        assert!(!manager.has_consent("user-1", ConsentType::Microphone).await.unwrap());
+    }
+
+    async fn controls_with_permission(conditions: Vec<PermissionCondition>) -> (DataControls, tempfile::TempDir) {
+        let data_dir = tempfile::tempdir().unwrap();
+        let controls = DataControls::new(data_dir.path().to_str().unwrap()).await.unwrap();
+        let permission = Permission {
+            permission_id: "microphone".to_string(),
+            name: "Microphone".to_string(),
+            description: "Record audio".to_string(),
+            granted: true,
+            granted_at: Some(chrono::Utc::now()),
+            expires_at: None,
+            conditions,
+            scope: PermissionScope::Read,
+        };
+
+        controls.app_permissions.write().await.insert(
+            "notes".to_string(),
+            AppPermissions {
+                app_id: "notes".to_string(),
+                app_name: "Notes".to_string(),
+                permissions: HashMap::from([(permission.permission_id.clone(), permission)]),
+                last_updated: chrono::Utc::now(),
+                trust_level: TrustLevel::Medium,
+            },
+        );
+        (controls, data_dir)
+    }
+
+    fn context_at(hour: u32, minute: u32) -> PermissionContext {
+        PermissionContext {
+            local_time: chrono::NaiveTime::from_hms_opt(hour, minute, 0).unwrap(),
+            ..PermissionContext::current()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_time_range_permission_inside_window() {
+        let (controls, _data_dir) = controls_with_permission(vec![PermissionCondition::TimeRange {
+            start: "09:00".to_string(),
+            end: "17:00".to_string(),
+        }])
+        .await;
+
+        let decision = controls.has_app_permission("notes", "microphone", &context_at(10, 30)).await.unwrap();
+        assert_eq!(decision, PermissionDecision::Granted);
+    }
+
+    #[tokio::test]
+    async fn test_time_range_permission_outside_window() {
+        let (controls, _data_dir) = controls_with_permission(vec![PermissionCondition::TimeRange {
+            start: "09:00".to_string(),
+            end: "17:00".to_string(),
+        }])
+        .await;
+
+        for (hour, minute) in [(8, 59), (17, 0), (23, 30)] {
+            let decision = controls.has_app_permission("notes", "microphone", &context_at(hour, minute)).await.unwrap();
+            assert_eq!(decision, PermissionDecision::Denied, "at {:02}:{:02}", hour, minute);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_confirmation_condition_requires_confirmation() {
+        let (controls, _data_dir) = controls_with_permission(vec![PermissionCondition::ExplicitConfirmation]).await;
+
+        let mut context = PermissionContext::current();
+        let decision = controls.has_app_permission("notes", "microphone", &context).await.unwrap();
+        assert_eq!(decision, PermissionDecision::NeedsConfirmation);
+
+        context.confirmed = true;
+        let decision = controls.has_app_permission("notes", "microphone", &context).await.unwrap();
+        assert_eq!(decision, PermissionDecision::Granted);
+    }
+
+    #[tokio::test]
+    async fn test_user_request_is_due_in_thirty_days() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let controls = PrivacyControls::new(SecurityConfig::default(), data_dir.path().to_str().unwrap())
+            .await
+            .unwrap();
+
+        let request = controls
+            .submit_user_request("user-1", UserRequestType::Access, "Send me my data")
+            .await
+            .unwrap();
+
+        assert!(matches!(request.status, RequestStatus::Received));
+        assert_eq!(request.due_date - request.created_at, chrono::Duration::days(30));
+        assert!(!ComplianceManager::is_overdue(&request, request.created_at + chrono::Duration::days(29)));
+        assert!(ComplianceManager::is_overdue(&request, request.created_at + chrono::Duration::days(31)));
+    }
+
+    #[tokio::test]
+    async fn test_access_request_is_fulfilled_with_export() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let memory_manager = test_memory_manager(&data_dir).await;
+        memory_manager
+            .store_memory(test_memory("Book flights to Lisbon", ContentType::Text, MemoryType::LongTerm))
+            .await
+            .unwrap();
+        let controls = PrivacyControls::new(SecurityConfig::default(), data_dir.path().to_str().unwrap())
+            .await
+            .unwrap()
+            .with_memory_manager(memory_manager);
+
+        let request = controls
+            .submit_user_request("user-1", UserRequestType::Access, "Send me my data")
+            .await
+            .unwrap();
+        let processed = controls.process_request(&request.request_id, ExportFormat::JSON).await.unwrap();
+
+        assert!(matches!(processed.status, RequestStatus::Ready));
+        let data = processed.processed_data.expect("no export attached");
+        assert!(data.data_files.iter().any(|file| matches!(file.data_type, DataType::TextData)));
+
+        let stored = controls.get_request_status(&request.request_id).await.unwrap().unwrap();
+        assert!(matches!(stored.status, RequestStatus::Ready));
+
+        // A ready request can't be processed again
+        assert!(controls.process_request(&request.request_id, ExportFormat::JSON).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failed_export_marks_the_request_failed() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let memory_manager = test_memory_manager(&data_dir).await;
+        let controls = PrivacyControls::new(SecurityConfig::default(), data_dir.path().to_str().unwrap())
+            .await
+            .unwrap()
+            .with_memory_manager(memory_manager.clone());
+
+        let request = controls
+            .submit_user_request("user-1", UserRequestType::Access, "Send me my data")
+            .await
+            .unwrap();
+        memory_manager.shutdown().await.unwrap();
+        assert!(controls.process_request(&request.request_id, ExportFormat::JSON).await.is_err());
+
+        let stored = controls.get_request_status(&request.request_id).await.unwrap().unwrap();
+        assert!(matches!(stored.status, RequestStatus::Failed));
+        assert!(!stored.notes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_erasure_request_waits_in_processing() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let controls = PrivacyControls::new(SecurityConfig::default(), data_dir.path().to_str().unwrap())
+            .await
+            .unwrap();
+
+        let request = controls
+            .submit_user_request("user-1", UserRequestType::Erasure, "Forget me")
+            .await
+            .unwrap();
+        let processed = controls.process_request(&request.request_id, ExportFormat::JSON).await.unwrap();
+
+        assert!(matches!(processed.status, RequestStatus::Processing));
+        assert!(processed.processed_data.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_default_configuration_is_gdpr_compliant() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let memory_manager = test_memory_manager(&data_dir).await;
+        let controls = PrivacyControls::new(SecurityConfig::default(), data_dir.path().to_str().unwrap())
+            .await
+            .unwrap()
+            .with_memory_manager(memory_manager);
+
+        let report = controls.check_compliance("gdpr").await.unwrap();
+
+        assert!(matches!(report.overall_status, ComplianceStatus::Compliant));
+        assert_eq!(report.requirement_statuses.len(), 5);
+        assert!(report.recommendations.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_overlong_retention_is_partially_compliant() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let memory_manager = test_memory_manager(&data_dir).await;
+        let controls = PrivacyControls::new(SecurityConfig::default(), data_dir.path().to_str().unwrap())
+            .await
+            .unwrap()
+            .with_memory_manager(memory_manager);
+        controls.data_controls.data_retention.write().await.category_policies.insert(
+            DataType::BiometricData,
+            RetentionPolicy {
+                retention_days: 400,
+                archival_days: None,
+                anonymization_enabled: false,
+                secure_delete: true,
+            },
+        );
+
+        let report = controls.check_compliance("gdpr").await.unwrap();
+
+        assert!(matches!(report.overall_status, ComplianceStatus::PartiallyCompliant));
+        let retention = report
+            .requirement_statuses
+            .iter()
+            .find(|status| status.requirement_id == "gdpr_art5_storage_limitation")
+            .unwrap();
+        assert!(matches!(retention.status, ImplementationStatus::InProgress));
+        assert!(retention.findings.contains("BiometricData"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_regulation_is_rejected() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let controls = PrivacyControls::new(SecurityConfig::default(), data_dir.path().to_str().unwrap())
+            .await
+            .unwrap();
+
+        assert!(controls.check_compliance("hipaa").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_hash_and_suppress() {
+        let engine = AnonymizationEngine::new().await.unwrap();
+
+        let hashed = engine.anonymize("alice", DataType::PersonalInfo, AnonymizationMethod::Hash).await.unwrap();
+        assert_eq!(hashed.len(), 64);
+        assert_eq!(hashed, engine.anonymize("alice", DataType::PersonalInfo, AnonymizationMethod::Hash).await.unwrap());
+
+        let suppressed = engine.anonymize("alice", DataType::PersonalInfo, AnonymizationMethod::Suppress).await.unwrap();
+        assert!(suppressed.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tokenize_is_consistent_per_data_type() {
+        let engine = AnonymizationEngine::new().await.unwrap();
+
+        let first = engine.anonymize("alice@example.com", DataType::ContactData, AnonymizationMethod::Tokenize).await.unwrap();
+        let second = engine.anonymize("alice@example.com", DataType::ContactData, AnonymizationMethod::Tokenize).await.unwrap();
+        let other = engine.anonymize("bob@example.com", DataType::ContactData, AnonymizationMethod::Tokenize).await.unwrap();
+
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+        assert!(!first.contains("alice"));
+    }
+
+    #[tokio::test]
+    async fn test_pseudonymize_reversible_only_with_key() {
+        let engine = AnonymizationEngine::new().await.unwrap();
+
+        let pseudonym = engine.anonymize("Alice Smith", DataType::PersonalInfo, AnonymizationMethod::Pseudonymize).await.unwrap();
+        assert_eq!(
+            pseudonym,
+            engine.anonymize("Alice Smith", DataType::PersonalInfo, AnonymizationMethod::Pseudonymize).await.unwrap()
+        );
+        assert!(engine.reverse_pseudonym(&pseudonym, DataType::PersonalInfo).await.is_err());
+
+        let key_dir = tempfile::tempdir().unwrap();
+        let security_manager = SecurityManager::new(key_dir.path().to_str().unwrap(), SecurityConfig::default())
+            .await
+            .unwrap();
+        security_manager.import_stored_key("contacts-key", &[7u8; 32]).await.unwrap();
+        engine.set_key_store(security_manager);
+
+        // Key ids have to resolve to a stored key
+        assert!(engine
+            .set_pseudonymization_policy(DataType::ContactData, true, Some("missing-key".to_string()))
+            .await
+            .is_err());
+        engine
+            .set_pseudonymization_policy(DataType::ContactData, true, Some("contacts-key".to_string()))
+            .await
+            .unwrap();
+        let reversible = engine.anonymize("Bob Jones", DataType::ContactData, AnonymizationMethod::Pseudonymize).await.unwrap();
+        assert_eq!(engine.reverse_pseudonym(&reversible, DataType::ContactData).await.unwrap(), "Bob Jones");
+
+        // Reversibility can't be switched on once pseudonyms exist
+        assert!(engine
+            .set_pseudonymization_policy(DataType::PersonalInfo, true, Some("key".to_string()))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_generalize_truncates_coordinates() {
+        let engine = AnonymizationEngine::new().await.unwrap();
+
+        let generalized = engine
+            .anonymize("51.50735, -0.12776", DataType::LocationData, AnonymizationMethod::Generalize)
+            .await
+            .unwrap();
+        assert_eq!(generalized, "51.5, -0.1");
+    }
+
+    #[tokio::test]
+    async fn test_add_noise_perturbs_numbers() {
+        let engine = AnonymizationEngine::new().await.unwrap();
+
+        let noisy: f64 = engine
+            .anonymize("200", DataType::HealthData, AnonymizationMethod::AddNoise)
+            .await
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((190.0..=210.0).contains(&noisy));
+
+        assert!(engine.anonymize("not a number", DataType::HealthData, AnonymizationMethod::AddNoise).await.is_err());
+        for value in ["NaN", "inf", "-infinity"] {
+            assert!(matches!(
+                engine.anonymize(value, DataType::HealthData, AnonymizationMethod::AddNoise).await,
+                Err(MisaError::Validation(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_default_templates_are_complete() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let manager = ConsentManager::new(data_dir.path().to_str().unwrap()).await.unwrap();
+        let templates = manager.consent_templates.read().await;
+
+        assert!(!templates.is_empty());
+        for template in templates.values() {
+            assert!(!template.name.is_empty(), "{} has no name", template.template_id);
+            assert!(!template.description.is_empty(), "{} has no description", template.template_id);
+            assert!(
+                template.help_text().map_or(false, |text| !text.is_empty()),
+                "{} has no help text",
+                template.template_id
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_granted_consent_survives_rebuild() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let dir = data_dir.path().to_str().unwrap();
+
+        let controls = PrivacyControls::new(SecurityConfig::default(), dir).await.unwrap();
+        let session_id = controls
+            .request_consent("user-1", ConsentType::CloudSync, serde_json::json!({}))
+            .await
+            .unwrap();
+        controls.grant_consent(&session_id, "user-1").await.unwrap();
+        drop(controls);
+
+        let rebuilt = PrivacyControls::new(SecurityConfig::default(), dir).await.unwrap();
+        assert!(rebuilt.has_consent("user-1", ConsentType::CloudSync).await.unwrap());
+        assert!(!data_dir.path().join("consents.json.tmp").exists());
+
+        rebuilt.revoke_consent("user-1", ConsentType::CloudSync).await.unwrap();
+        let rebuilt = PrivacyControls::new(SecurityConfig::default(), dir).await.unwrap();
+        assert!(!rebuilt.has_consent("user-1", ConsentType::CloudSync).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_app_permission_change_survives_rebuild() {
+        let (controls, data_dir) = controls_with_permission(Vec::new()).await;
+        controls.set_app_permission("notes", "microphone", false).await.unwrap();
+
+        let rebuilt = DataControls::new(data_dir.path().to_str().unwrap()).await.unwrap();
+        let decision = rebuilt
+            .has_app_permission("notes", "microphone", &PermissionContext::current())
+            .await
+            .unwrap();
+        assert_eq!(decision, PermissionDecision::Denied);
+        assert_eq!(rebuilt.get_user_app_permissions("user-1").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_apply_filters_redacts_email_and_phone() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let controls = DataControls::new(data_dir.path().to_str().unwrap()).await.unwrap();
+
+        let (filtered, applied) = controls
+            .apply_filters("Mail Jane.Doe@Example.com or call 555-123-4567 before Friday")
+            .await;
+
+        assert_eq!(filtered, "Mail [EMAIL] or call [PHONE] before Friday");
+        let fired: Vec<(&str, FilterOutcome, usize)> = applied
+            .iter()
+            .map(|filter| (filter.rule_id.as_str(), filter.outcome, filter.matches))
+            .collect();
+        assert_eq!(
+            fired,
+            vec![
+                ("email_redaction", FilterOutcome::Redacted, 1),
+                ("phone_redaction", FilterOutcome::Redacted, 1),
+            ]
+        );
+
+        // Location rules only target location data, so plain text is left alone
+        let (unchanged, applied) = controls.apply_filters("Meet at 52.5200, 13.4050").await;
+        assert_eq!(unchanged, "Meet at 52.5200, 13.4050");
+        assert!(applied.is_empty());
+
+        let (generalized, applied) = controls
+            .apply_filters_for("Meet at 52.520008, 13.404954", &DataType::LocationData)
+            .await;
+        assert_eq!(generalized, "Meet at 52.5, 13.4");
+        assert_eq!(applied.len(), 1);
+        assert_eq!((applied[0].rule_id.as_str(), applied[0].outcome), ("geo_generalization", FilterOutcome::Anonymized));
+    }
+
+    #[tokio::test]
+    async fn test_memories_from_anonymized_sources_are_filtered() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let controls = PrivacyControls::new(SecurityConfig::default(), data_dir.path().to_str().unwrap())
+            .await
+            .unwrap();
+        let memory_manager = test_memory_manager(&data_dir)
+            .await
+            .with_content_filter(Arc::new(controls.data_controls()));
+
+        let mut captured = test_memory("Invoice from billing@example.com", ContentType::Text, MemoryType::ShortTerm);
+        captured.metadata = serde_json::json!({ "source": "screen_capture" });
+        let captured_id = memory_manager.store_memory(captured).await.unwrap();
+        let stored = memory_manager.get_memory(&captured_id).await.unwrap().unwrap();
+        assert_eq!(stored.content, "Invoice from [EMAIL]");
+
+        // Microphone content does not require anonymization
+        let mut dictated = test_memory("Call 555-123-4567", ContentType::Text, MemoryType::ShortTerm);
+        dictated.metadata = serde_json::json!({ "source": "microphone" });
+        let dictated_id = memory_manager.store_memory(dictated).await.unwrap();
+        let stored = memory_manager.get_memory(&dictated_id).await.unwrap().unwrap();
+        assert_eq!(stored.content, "Call 555-123-4567");
+    }
+
+    /// Store a granted consent expiring at `expires_at` and return its id
+    async fn insert_expiring_consent(
+        controls: &PrivacyControls,
+        consent_type: ConsentType,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> String {
+        controls.insert_test_consent("user-1", consent_type.clone()).await;
+        let mut consents = controls.consent_manager.consents.write().await;
+        let consent = consents
+            .values_mut()
+            .find(|consent| consent.consent_type == consent_type)
+            .unwrap();
+        consent.expires_at = Some(expires_at);
+        consent.consent_id.clone()
+    }
+
+    #[tokio::test]
+    async fn test_consent_expiring_soon_requires_re_consent_once() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let controls = PrivacyControls::new(SecurityConfig::default(), data_dir.path().to_str().unwrap())
+            .await
+            .unwrap();
+        let mut events = controls.subscribe_events();
+
+        let now = chrono::Utc::now();
+        let expires_at = now + chrono::Duration::days(3);
+        let soon = insert_expiring_consent(&controls, ConsentType::CloudSync, expires_at).await;
+        insert_expiring_consent(&controls, ConsentType::Analytics, now + chrono::Duration::days(90)).await;
+
+        let report = controls.sweep_expiring_consents(chrono::Duration::days(7)).await.unwrap();
+        assert_eq!(report.notified, vec![soon.clone()]);
+        assert!(report.expired.is_empty());
+        assert_eq!(
+            events.try_recv().unwrap(),
+            PrivacyEvent::ReConsentRequired {
+                user_id: "user-1".to_string(),
+                consent_id: soon,
+                consent_type: ConsentType::CloudSync,
+                expires_at,
+                expired: false,
+            }
+        );
+        assert!(controls.has_consent("user-1", ConsentType::CloudSync).await.unwrap());
+
+        // The user is only prompted once per consent
+        let report = controls.sweep_expiring_consents(chrono::Duration::days(7)).await.unwrap();
+        assert!(report.notified.is_empty());
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_re_consent_marker_survives_non_object_metadata() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let controls = PrivacyControls::new(SecurityConfig::default(), data_dir.path().to_str().unwrap())
+            .await
+            .unwrap();
+
+        let soon = insert_expiring_consent(&controls, ConsentType::CloudSync, chrono::Utc::now() + chrono::Duration::days(3)).await;
+        controls.consent_manager.consents.write().await.get_mut(&soon).unwrap().metadata = serde_json::json!("imported");
+
+        let report = controls.sweep_expiring_consents(chrono::Duration::days(7)).await.unwrap();
+        assert_eq!(report.notified, vec![soon.clone()]);
+        let metadata = controls.consent_manager.consents.read().await[&soon].metadata.clone();
+        assert_eq!(metadata["value"], "imported");
+        assert!(metadata.get(RE_CONSENT_NOTIFIED_KEY).is_some());
+
+        let report = controls.sweep_expiring_consents(chrono::Duration::days(7)).await.unwrap();
+        assert!(report.notified.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_expired_consent_is_marked_and_denied() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let dir = data_dir.path().to_str().unwrap();
+        let controls = PrivacyControls::new(SecurityConfig::default(), dir).await.unwrap();
+        let mut events = controls.subscribe_events();
+
+        let expires_at = chrono::Utc::now() - chrono::Duration::minutes(5);
+        let consent_id = insert_expiring_consent(&controls, ConsentType::ScreenCapture, expires_at).await;
+
+        let report = controls.sweep_expiring_consents(chrono::Duration::days(7)).await.unwrap();
+        assert_eq!(report.expired, vec![consent_id.clone()]);
+        assert!(matches!(
+            events.try_recv().unwrap(),
+            PrivacyEvent::ReConsentRequired { consent_type: ConsentType::ScreenCapture, expired: true, .. }
+        ));
+        assert!(!controls.has_consent("user-1", ConsentType::ScreenCapture).await.unwrap());
+
+        // The expiry mark is persisted
+        let reloaded = PrivacyControls::new(SecurityConfig::default(), dir).await.unwrap();
+        let consents = reloaded.consent_manager.get_user_consents("user-1").await.unwrap();
+        assert!(consents.iter().any(|consent| consent.consent_id == consent_id && consent.expired_at.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_retention_deletes_expired_screen_captures() {
+        use crate::security::{AuditQuery, SecurityManager};
+
+        let data_dir = tempfile::tempdir().unwrap();
+        let dir = data_dir.path().to_str().unwrap();
+        let security_manager = SecurityManager::new(dir, SecurityConfig::default()).await.unwrap();
+        let controls = PrivacyControls::new(SecurityConfig::default(), dir)
+            .await
+            .unwrap()
+            .with_security_manager(security_manager.clone());
+        controls.set_data_source_control(SCREEN_CAPTURE_SOURCE, true).await.unwrap();
+
+        // Screen captures are kept for a single day
+        let captures = controls.source_artifacts_dir(SCREEN_CAPTURE_SOURCE);
+        std::fs::create_dir_all(&captures).unwrap();
+        let expired = captures.join("capture-old.png");
+        let recent = captures.join("capture-new.png");
+        std::fs::write(&expired, b"old pixels").unwrap();
+        std::fs::write(&recent, b"new pixels").unwrap();
+        let two_days_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(2 * 24 * 60 * 60);
+        std::fs::File::options().write(true).open(&expired).unwrap().set_modified(two_days_ago).unwrap();
+
+        let report = controls.enforce_retention().await.unwrap();
+
+        assert_eq!(report.actions.len(), 1);
+        assert_eq!(report.actions[0].source_id, SCREEN_CAPTURE_SOURCE);
+        assert_eq!(report.actions[0].artifact, expired);
+        assert_eq!(report.actions[0].reason, RetentionReason::Expired);
+        assert_eq!(report.actions[0].outcome, RetentionOutcome::Deleted);
+        assert!(!expired.exists());
+        assert!(recent.exists());
+
+        let audited = security_manager
+            .query_audit_log(&AuditQuery { action: Some("retention_enforced".to_string()), ..AuditQuery::default() })
+            .await
+            .unwrap();
+        assert_eq!(audited.len(), 1);
+        assert_eq!(audited[0].resource, expired.display().to_string());
+        assert_eq!(audited[0].details["outcome"], "deleted");
+    }
+
+    #[tokio::test]
+    async fn test_retention_applies_to_retained_artifacts_of_enabled_sources() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let controls = PrivacyControls::new(SecurityConfig::default(), data_dir.path().to_str().unwrap())
+            .await
+            .unwrap();
+        controls.set_data_source_control("app_usage", true).await.unwrap();
+
+        // A disabled source's rule is not enforced
+        let captures = controls.source_artifacts_dir(SCREEN_CAPTURE_SOURCE);
+        std::fs::create_dir_all(&captures).unwrap();
+        let untouched = captures.join("capture-old.png");
+        std::fs::write(&untouched, b"old pixels").unwrap();
+
+        // App usage artifacts expire after a year, whether active or already retained
+        let usage = controls.source_artifacts_dir("app_usage");
+        let retained = usage.join(RETAINED_ARTIFACTS_DIR);
+        std::fs::create_dir_all(&retained).unwrap();
+        let expired = usage.join("usage-2023.json");
+        let expired_archive = retained.join("usage-2022.json.gz");
+        std::fs::write(&expired, b"{}").unwrap();
+        std::fs::write(&expired_archive, b"compressed").unwrap();
+        let two_years_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(2 * 365 * 24 * 60 * 60);
+        for path in [&expired, &expired_archive, &untouched] {
+            std::fs::File::options().write(true).open(path).unwrap().set_modified(two_years_ago).unwrap();
+        }
+
+        let report = controls.enforce_retention().await.unwrap();
+
+        let outcomes: Vec<(&Path, &RetentionOutcome)> =
+            report.actions.iter().map(|action| (action.artifact.as_path(), &action.outcome)).collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                (expired_archive.as_path(), &RetentionOutcome::Deleted),
+                (expired.as_path(), &RetentionOutcome::Compressed),
+            ]
+        );
+        assert!(!expired.exists());
+        assert!(!expired_archive.exists());
+        assert!(retained.join("usage-2023.json.gz").exists());
+        assert!(untouched.exists());
+    }
+
+    #[tokio::test]
+    async fn test_retention_erases_expired_memories_of_the_source() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let memory_manager = test_memory_manager(&data_dir).await;
+        let controls = PrivacyControls::new(SecurityConfig::default(), data_dir.path().to_str().unwrap())
+            .await
+            .unwrap()
+            .with_memory_manager(memory_manager.clone());
+        controls.set_data_source_control(SCREEN_CAPTURE_SOURCE, true).await.unwrap();
+
+        let mut expired = test_memory("Old screen summary", ContentType::Text, MemoryType::MediumTerm);
+        expired.metadata = serde_json::json!({ "source": SCREEN_CAPTURE_SOURCE });
+        expired.created_at = chrono::Utc::now() - chrono::Duration::days(2);
+        let mut recent = test_memory("New screen summary", ContentType::Text, MemoryType::MediumTerm);
+        recent.metadata = serde_json::json!({ "source": SCREEN_CAPTURE_SOURCE });
+        let mut other_source = test_memory("Old note", ContentType::Text, MemoryType::MediumTerm);
+        other_source.created_at = expired.created_at;
+        for memory in [&expired, &recent, &other_source] {
+            memory_manager.store_memory(memory.clone()).await.unwrap();
+        }
+
+        let report = controls.enforce_retention().await.unwrap();
+
+        let erased: Vec<&str> = report.erased_memories.iter().map(|erasure| erasure.memory_id.as_str()).collect();
+        assert_eq!(erased, vec![expired.id.as_str()]);
+        assert!(report.failures.is_empty());
+        assert!(memory_manager.get_memory(&expired.id).await.unwrap().is_none());
+        assert!(memory_manager.get_memory(&recent.id).await.unwrap().is_some());
+        assert!(memory_manager.get_memory(&other_source.id).await.unwrap().is_some());
+    }
+}
@@ -0,0 +1,304 @@
+//! Background Job Scheduler
+//!
+//! Central place for the periodic work core managers run in the background:
+//! - Named jobs running on a fixed interval
+//! - Exponential backoff while a job keeps failing
+//! - Per-job and global cancellation
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn, debug};
+
+use crate::errors::{MisaError, Result as MisaResult};
+
+/// Upper bound on the delay between retries of a failing job
+pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(60 * 60);
+
+/// Scheduler owning every registered background job
+pub struct Scheduler {
+    jobs: Arc<RwLock<HashMap<String, ScheduledJob>>>,
+    shutdown_token: CancellationToken,
+    max_backoff: Duration,
+}
+
+struct ScheduledJob {
+    token: CancellationToken,
+    handle: JoinHandle<()>,
+    stats: Arc<RwLock<JobStats>>,
+}
+
+/// Run history of a single job
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JobStats {
+    pub runs: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    pub last_run: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl Scheduler {
+    /// Create an empty scheduler
+    pub fn new() -> Self {
+        Self {
+            jobs: Arc::new(RwLock::new(HashMap::new())),
+            shutdown_token: CancellationToken::new(),
+            max_backoff: DEFAULT_MAX_BACKOFF,
+        }
+    }
+
+    /// Cap the retry delay of failing jobs
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Register a job that runs immediately and then once per `interval`
+    ///
+    /// While the job keeps failing the delay doubles on each failure, up to the
+    /// scheduler's max backoff, and resets after the next success.
+    pub async fn register<F, Fut>(&self, name: &str, interval: Duration, job: F) -> MisaResult<()>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = MisaResult<()>> + Send + 'static,
+    {
+        if self.shutdown_token.is_cancelled() {
+            return Err(MisaError::Internal("Scheduler has been shut down".to_string()));
+        }
+
+        let mut jobs = self.jobs.write().await;
+        if jobs.get(name).map_or(false, |existing| !existing.handle.is_finished()) {
+            return Err(MisaError::Validation(format!("Job {} is already registered", name)));
+        }
+
+        let token = self.shutdown_token.child_token();
+        let stats = Arc::new(RwLock::new(JobStats::default()));
+        let handle = tokio::spawn(Self::run_job(
+            name.to_string(),
+            interval,
+            self.max_backoff,
+            job,
+            token.clone(),
+            Arc::clone(&stats),
+        ));
+
+        jobs.insert(name.to_string(), ScheduledJob { token, handle, stats });
+        debug!("Registered background job {} every {:?}", name, interval);
+        Ok(())
+    }
+
+    /// Stop a single job, returning whether it was registered
+    pub async fn cancel(&self, name: &str) -> bool {
+        let job = self.jobs.write().await.remove(name);
+        match job {
+            Some(job) => {
+                job.token.cancel();
+                let _ = job.handle.await;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Run history of a job
+    pub async fn job_stats(&self, name: &str) -> Option<JobStats> {
+        let stats = self.jobs.read().await.get(name).map(|job| Arc::clone(&job.stats))?;
+        let stats = stats.read().await.clone();
+        Some(stats)
+    }
+
+    /// Names of all registered jobs, sorted
+    pub async fn job_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.jobs.read().await.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Cancel every job and wait for in-flight runs to finish
+    pub async fn shutdown(&self) {
+        self.shutdown_token.cancel();
+
+        let jobs: Vec<(String, ScheduledJob)> = self.jobs.write().await.drain().collect();
+        for (name, job) in jobs {
+            if let Err(e) = job.handle.await {
+                warn!("Background job {} ended abnormally: {}", name, e);
+            }
+        }
+
+        info!("Scheduler shut down");
+    }
+
+    /// Delay before the next run after `consecutive_failures` failures in a row
+    pub fn backoff_delay(interval: Duration, consecutive_failures: u32, max_backoff: Duration) -> Duration {
+        if consecutive_failures == 0 {
+            return interval;
+        }
+
+        let factor = 1u32 << consecutive_failures.min(16);
+        interval.saturating_mul(factor).min(max_backoff.max(interval))
+    }
+
+    async fn run_job<F, Fut>(
+        name: String,
+        interval: Duration,
+        max_backoff: Duration,
+        job: F,
+        token: CancellationToken,
+        stats: Arc<RwLock<JobStats>>,
+    ) where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = MisaResult<()>> + Send + 'static,
+    {
+        loop {
+            // A run that has started is allowed to finish so it never stops halfway
+            let result = job().await;
+
+            let consecutive_failures = {
+                let mut stats = stats.write().await;
+                stats.runs += 1;
+                stats.last_run = Some(chrono::Utc::now());
+                match result {
+                    Ok(()) => {
+                        stats.consecutive_failures = 0;
+                        stats.last_error = None;
+                    }
+                    Err(e) => {
+                        warn!("Background job {} failed: {}", name, e);
+                        stats.failures += 1;
+                        stats.consecutive_failures += 1;
+                        stats.last_error = Some(e.to_string());
+                    }
+                }
+                stats.consecutive_failures
+            };
+
+            let delay = Self::backoff_delay(interval, consecutive_failures, max_backoff);
+            tokio::select! {
+                _ = token.cancelled() => break,
+                _ = tokio::time::sleep(delay) => {}
+            }
+        }
+
+        debug!("Background job {} stopped", name);
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for Scheduler {
+    fn clone(&self) -> Self {
+        Self {
+            jobs: Arc::clone(&self.jobs),
+            shutdown_token: self.shutdown_token.clone(),
+            max_backoff: self.max_backoff,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[tokio::test]
+    async fn test_registered_job_runs_repeatedly() {
+        let scheduler = Scheduler::new();
+        let runs = Arc::new(AtomicU32::new(0));
+
+        let counter = Arc::clone(&runs);
+        scheduler
+            .register("counter", Duration::from_millis(10), move || {
+                let counter = Arc::clone(&counter);
+                async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            })
+            .await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert!(runs.load(Ordering::SeqCst) >= 3);
+        let stats = scheduler.job_stats("counter").await.unwrap();
+        assert!(stats.runs >= 3);
+        assert_eq!(stats.failures, 0);
+        assert!(scheduler
+            .register("counter", Duration::from_millis(10), || async { Ok(()) })
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_backoff_doubles_and_is_capped() {
+        let interval = Duration::from_secs(10);
+        let max = Duration::from_secs(60);
+
+        assert_eq!(Scheduler::backoff_delay(interval, 0, max), interval);
+        assert_eq!(Scheduler::backoff_delay(interval, 1, max), Duration::from_secs(20));
+        assert_eq!(Scheduler::backoff_delay(interval, 2, max), Duration::from_secs(40));
+        assert_eq!(Scheduler::backoff_delay(interval, 3, max), max);
+        assert_eq!(Scheduler::backoff_delay(interval, 40, max), max);
+    }
+
+    #[tokio::test]
+    async fn test_failing_job_backs_off() {
+        let scheduler = Scheduler::new().with_max_backoff(Duration::from_secs(10));
+        scheduler
+            .register("flaky", Duration::from_millis(20), || async {
+                Err(MisaError::Internal("boom".to_string()))
+            })
+            .await
+            .unwrap();
+
+        // Without backoff this would run ~15 times; with it the runs land at 0, 40, 120 and 280ms
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        let stats = scheduler.job_stats("flaky").await.unwrap();
+        assert!(stats.runs >= 3 && stats.runs <= 5, "ran {} times", stats.runs);
+        assert_eq!(stats.failures, stats.runs);
+        assert_eq!(stats.consecutive_failures as u64, stats.runs);
+        assert_eq!(stats.last_error.as_deref(), Some("Internal error: boom"));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_stops_all_jobs() {
+        let scheduler = Scheduler::new();
+        let runs = Arc::new(AtomicU32::new(0));
+
+        for name in ["first", "second"] {
+            let counter = Arc::clone(&runs);
+            scheduler
+                .register(name, Duration::from_millis(10), move || {
+                    let counter = Arc::clone(&counter);
+                    async move {
+                        counter.fetch_add(1, Ordering::SeqCst);
+                        Ok(())
+                    }
+                })
+                .await
+                .unwrap();
+        }
+        assert_eq!(scheduler.job_names().await, vec!["first".to_string(), "second".to_string()]);
+
+        scheduler.shutdown().await;
+        let after_shutdown = runs.load(Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert_eq!(runs.load(Ordering::SeqCst), after_shutdown);
+        assert!(scheduler.job_names().await.is_empty());
+        assert!(scheduler
+            .register("late", Duration::from_millis(10), || async { Ok(()) })
+            .await
+            .is_err());
+    }
+}
@@ -11,17 +11,40 @@
 use anyhow::Result;
 use aes_gcm::{Aes256Gcm, Key, Nonce};
 use aes_gcm::aead::{Aead, NewAead};
-use argon2::{Argon2, password_hash::{PasswordHash, PasswordHasher, SaltString}};
+use argon2::{Argon2, password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString}};
+use ring::{agreement, hkdf, hmac};
 use ring::rand::{SecureRandom, SystemRandom};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
 use tokio::sync::RwLock;
 use tracing::{info, warn, error, debug};
 
 use crate::kernel::SecurityConfig;
-use crate::errors::{MisaError, Result as MisaResult};
+use crate::errors::{MisaError, PluginError, Result as MisaResult};
+
+/// File under the data directory holding user credentials
+const CREDENTIALS_FILE: &str = "credentials.json";
+
+/// Stored key biometric templates are hashed with before they are kept or compared
+const BIOMETRIC_TEMPLATE_KEY: &str = "biometric-templates";
+
+/// Key id recorded on envelopes sealed with a paired device's shared key
+const DEVICE_CHANNEL_KEY_ID: &str = "device-channel";
+
+/// Directory under the data directory holding keys that must survive restarts
+const KEY_DIR: &str = "keys";
+
+/// File in the key directory holding paired devices' keys, sealed under `DEVICE_KEY_STORE_KEY`
+const DEVICE_KEYS_FILE: &str = "device_keys.sealed";
+
+/// Stored key that seals the device key file
+const DEVICE_KEY_STORE_KEY: &str = "device-key-store";
+
+/// HKDF salt for message keys agreed with paired devices
+const DEVICE_KEY_EXCHANGE_SALT: &[u8] = b"misa-device-channel-v2";
 
 /// Main security manager
 pub struct SecurityManager {
@@ -30,6 +53,8 @@ pub struct SecurityManager {
     encryption_manager: Arc<EncryptionManager>,
     auth_manager: Arc<AuthManager>,
     audit_logger: Arc<AuditLogger>,
+    permission_checker: PermissionChecker,
+    sandbox_manager: Arc<SandboxManager>,
     secure_rng: SystemRandom,
 }
 
@@ -37,6 +62,10 @@ pub struct SecurityManager {
 pub struct EncryptionManager {
     master_key: Arc<RwLock<Option<[u8; 32]>>>,
     encrypted_keys: Arc<RwLock<HashMap<String, EncryptedKey>>>,
+    device_keys: Arc<RwLock<HashMap<String, [u8; 32]>>>,
+    /// Keys persisted in `key_dir`, cached once read
+    stored_keys: Arc<RwLock<HashMap<String, [u8; 32]>>>,
+    key_dir: PathBuf,
     secure_rng: SystemRandom,
 }
 
@@ -44,22 +73,52 @@ pub struct EncryptionManager {
 pub struct AuthManager {
     sessions: Arc<RwLock<HashMap<String, AuthSession>>>,
     user_credentials: Arc<RwLock<HashMap<String, UserCredentials>>>,
+    /// Where credentials were loaded from and are saved back to
+    credentials_path: Arc<RwLock<Option<PathBuf>>>,
     biometric_providers: Arc<RwLock<HashMap<String, Box<dyn BiometricProvider>>>>,
     session_timeout_minutes: u64,
+    sliding_expiry: bool,
+    session_sweeper: Arc<RwLock<Option<tokio::task::JoinHandle<()>>>>,
 }
 
-/// Audit logger for security events
+/// Audit logger for security events, appending JSON lines to `audit.log` under the data directory
 pub struct AuditLogger {
-    log_file: Arc<RwLock<Option<tokio::fs::File>>>,
+    log_path: PathBuf,
+    log_file: Arc<RwLock<Option<AuditLogFile>>>,
     log_entries: Arc<RwLock<Vec<AuditEntry>>>,
     max_entries: usize,
+    max_file_bytes: u64,
+}
+
+/// Open audit log file and its current size
+struct AuditLogFile {
+    file: tokio::fs::File,
+    size: u64,
+}
+
+/// Filter for reading audit entries back
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+    pub until: Option<chrono::DateTime<chrono::Utc>>,
+    pub user_id: Option<String>,
+    pub action: Option<String>,
+    /// Case-insensitive substring of the action, resource or details
+    pub text: Option<String>,
+    /// Keep only the newest `limit` matches
+    pub limit: Option<usize>,
 }
 
-/// Plugin sandbox manager
+/// Plugin sandbox manager, running each plugin as a monitored child process.
+///
+/// The "sandbox" limits resources and declared permissions; it does not isolate the
+/// filesystem, so callers choose the environment and working directory it starts with.
 pub struct SandboxManager {
     active_sandboxes: Arc<RwLock<HashMap<String, SandboxInfo>>>,
+    stop_signals: Arc<RwLock<HashMap<String, tokio::sync::oneshot::Sender<()>>>>,
     resource_limits: ResourceLimits,
     permission_checker: PermissionChecker,
+    sample_interval: std::time::Duration,
 }
 
 /// Biometric provider trait
@@ -71,7 +130,7 @@ pub trait BiometricProvider: Send + Sync {
 }
 
 /// Biometric type enumeration
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum BiometricType {
     Fingerprint,
     Face,
@@ -180,7 +239,8 @@ pub struct ResourceUsage {
     pub network_bytes_received: u64,
 }
 
-/// Permission checker for plugin operations
+/// Permission checker mapping roles to the permissions they grant.
+/// Permissions are `scope:action` strings; `scope:*` grants every action in a scope and `*` grants everything.
 pub struct PermissionChecker {
     permission_matrix: Arc<RwLock<HashMap<String, Vec<String>>>>,
 }
@@ -193,8 +253,12 @@ impl SecurityManager {
             .map_err(|e| MisaError::Io(e))?;
 
         let encryption_manager = Arc::new(EncryptionManager::new(data_dir).await?);
-        let auth_manager = Arc::new(AuthManager::new(config.session_timeout_minutes).await?);
-        let audit_logger = Arc::new(AuditLogger::new(data_dir).await?);
+        let auth_manager = Arc::new(
+            AuthManager::new(config.session_timeout_minutes, config.sliding_session_expiry).await?,
+        );
+        let audit_logger = Arc::new(AuditLogger::new(data_dir, config.audit_log_max_bytes).await?);
+        let permission_checker = PermissionChecker::with_default_roles();
+        let sandbox_manager = Arc::new(SandboxManager::new(ResourceLimits::default(), permission_checker.clone()));
 
         let manager = Self {
             config,
@@ -202,6 +266,8 @@ impl SecurityManager {
             encryption_manager,
             auth_manager,
             audit_logger,
+            permission_checker,
+            sandbox_manager,
             secure_rng: SystemRandom::new(),
         };
 
@@ -227,6 +293,9 @@ impl SecurityManager {
         // Load existing user credentials
         self.auth_manager.load_credentials(&self.data_dir).await?;
 
+        // Drop expired sessions in the background
+        self.auth_manager.start_session_sweeper(std::time::Duration::from_secs(60)).await;
+
         info!("Security manager fully initialized");
         Ok(())
     }
@@ -241,6 +310,86 @@ impl SecurityManager {
         self.encryption_manager.decrypt(encrypted_data).await
     }
 
+    /// Whether the encryption master key has been loaded or generated
+    pub async fn has_master_key(&self) -> bool {
+        self.encryption_manager.has_master_key().await
+    }
+
+    /// Encrypt with a key kept in the data directory, so the data stays readable after a restart
+    pub async fn encrypt_with_stored_key(&self, key_name: &str, data: &[u8]) -> MisaResult<EncryptedData> {
+        self.encryption_manager.encrypt_with_stored_key(key_name, data).await
+    }
+
+    /// Decrypt data sealed with `encrypt_with_stored_key`
+    pub async fn decrypt_with_stored_key(&self, key_name: &str, encrypted_data: &EncryptedData) -> MisaResult<Vec<u8>> {
+        self.encryption_manager.decrypt_with_stored_key(key_name, encrypted_data).await
+    }
+
+    /// Install a 256-bit key under `key_name`, e.g. one exported from another of the user's devices
+    pub async fn import_stored_key(&self, key_name: &str, key: &[u8]) -> MisaResult<()> {
+        self.encryption_manager.import_stored_key(key_name, key).await
+    }
+
+    /// Whether a key is stored under `key_name`, without generating one
+    pub async fn has_stored_key(&self, key_name: &str) -> bool {
+        self.encryption_manager.has_stored_key(key_name).await
+    }
+
+    /// The key stored under `key_name`, generated on first use
+    pub async fn export_stored_key(&self, key_name: &str) -> MisaResult<Vec<u8>> {
+        Ok(self.encryption_manager.stored_key(key_name).await?.to_vec())
+    }
+
+    /// Share a 256-bit key with a paired device for end-to-end message encryption
+    pub async fn register_device_key(&self, device_id: &str, key: &[u8]) -> MisaResult<()> {
+        let key: [u8; 32] = key
+            .try_into()
+            .map_err(|_| MisaError::Encryption(format!("Device keys must be 32 bytes, got {}", key.len())))?;
+        self.encryption_manager.set_device_key(device_id, key).await
+    }
+
+    /// Start agreeing a message key with a device; send it the exchange's public key
+    pub fn start_device_key_exchange(&self) -> MisaResult<DeviceKeyExchange> {
+        DeviceKeyExchange::generate(&self.secure_rng)
+    }
+
+    /// Finish a key agreement with the device's public key and keep the agreed key for it
+    pub async fn complete_device_key_exchange(
+        &self,
+        device_id: &str,
+        exchange: DeviceKeyExchange,
+        peer_public_key: &[u8],
+    ) -> MisaResult<()> {
+        let key = exchange.agree(peer_public_key)?;
+        self.encryption_manager.set_device_key(device_id, key).await?;
+        info!("Agreed a new message key with device {}", device_id);
+        Ok(())
+    }
+
+    pub async fn has_device_key(&self, device_id: &str) -> bool {
+        self.encryption_manager.has_device_key(device_id).await
+    }
+
+    /// Encrypt data so only the given paired device can read it
+    pub async fn encrypt_for_device(&self, device_id: &str, data: &[u8]) -> MisaResult<EncryptedData> {
+        self.encryption_manager.encrypt_for_device(device_id, data).await
+    }
+
+    /// Decrypt data sent by a paired device
+    pub async fn decrypt_from_device(&self, device_id: &str, encrypted_data: &EncryptedData) -> MisaResult<Vec<u8>> {
+        self.encryption_manager.decrypt_from_device(device_id, encrypted_data).await
+    }
+
+    /// Sign data with HMAC-SHA256 using a key derived for the given purpose
+    pub async fn sign_data(&self, data: &[u8], purpose: &str) -> MisaResult<Vec<u8>> {
+        self.encryption_manager.sign(data, purpose).await
+    }
+
+    /// Verify an HMAC-SHA256 signature in constant time
+    pub async fn verify_signature(&self, data: &[u8], signature: &[u8], purpose: &str) -> MisaResult<bool> {
+        self.encryption_manager.verify(data, signature, purpose).await
+    }
+
     /// Authenticate user with password
     pub async fn authenticate_password(&self, user_id: &str, password: &str) -> MisaResult<AuthSession> {
         self.auth_manager.authenticate_password(user_id, password).await
@@ -248,7 +397,8 @@ impl SecurityManager {
 
     /// Authenticate user with biometrics
     pub async fn authenticate_biometric(&self, user_id: &str, biometric_type: BiometricType, data: &[u8]) -> MisaResult<AuthSession> {
-        self.auth_manager.authenticate_biometric(user_id, biometric_type, data).await
+        let keyed = self.encryption_manager.hash_with_stored_key(BIOMETRIC_TEMPLATE_KEY, data).await?;
+        self.auth_manager.authenticate_biometric(user_id, biometric_type, &keyed).await
     }
 
     /// Validate session
@@ -256,6 +406,44 @@ impl SecurityManager {
         self.auth_manager.validate_session(session_id).await
     }
 
+    /// Create a user with a password
+    pub async fn create_user(&self, user_id: &str, password: &str) -> MisaResult<()> {
+        self.auth_manager.create_user(user_id, password).await?;
+        self.log_security_event(Some(user_id), "user_created", user_id, AuditResult::Success, serde_json::json!({}))
+            .await
+    }
+
+    /// Replace a user's password after verifying the current one
+    pub async fn change_password(&self, user_id: &str, old_password: &str, new_password: &str) -> MisaResult<()> {
+        let result = self.auth_manager.change_password(user_id, old_password, new_password).await;
+        let outcome = if result.is_ok() { AuditResult::Success } else { AuditResult::Failure };
+        self.log_security_event(Some(user_id), "password_changed", user_id, outcome, serde_json::json!({}))
+            .await?;
+        result
+    }
+
+    /// Store a keyed hash of a biometric template for a user; the template itself is never written
+    pub async fn enroll_biometric(&self, user_id: &str, biometric_type: BiometricType, template: &[u8]) -> MisaResult<()> {
+        if template.is_empty() {
+            return Err(MisaError::Validation("Biometric template is empty".to_string()));
+        }
+        let keyed = self.encryption_manager.hash_with_stored_key(BIOMETRIC_TEMPLATE_KEY, template).await?;
+        self.auth_manager.enroll_biometric(user_id, biometric_type.clone(), &keyed).await?;
+        self.log_security_event(
+            Some(user_id),
+            "biometric_enrolled",
+            user_id,
+            AuditResult::Success,
+            serde_json::json!({ "biometric_type": biometric_type }),
+        )
+        .await
+    }
+
+    /// Extend a still-valid session by the timeout window
+    pub async fn refresh_session(&self, session_id: &str) -> MisaResult<Option<AuthSession>> {
+        self.auth_manager.refresh_session(session_id).await
+    }
+
     /// Log security event
     pub async fn log_security_event(
         &self,
@@ -281,21 +469,54 @@ impl SecurityManager {
         self.audit_logger.log_entry(entry).await
     }
 
-    /// Check if user has permission for action
+    /// Read audit entries back from the log files
+    pub async fn query_audit_log(&self, filter: &AuditQuery) -> MisaResult<Vec<AuditEntry>> {
+        self.audit_logger.query(filter).await
+    }
+
+    /// Check if user has permission for action through one of their active sessions
     pub async fn check_permission(&self, user_id: &str, permission: &str) -> MisaResult<bool> {
-        // This would integrate with the auth manager's permission system
-        // For now, return true for authenticated users
-        Ok(true)
+        for session in self.auth_manager.active_sessions(user_id).await {
+            if self.permission_checker.is_granted(&session.permissions, permission).await {
+                return Ok(true);
+            }
+        }
+
+        debug!("Permission {} denied for user: {}", permission, user_id);
+        self.log_security_event(
+            Some(user_id),
+            "permission_denied",
+            permission,
+            AuditResult::Failure,
+            serde_json::json!({}),
+        ).await?;
+
+        Ok(false)
     }
 
-    /// Create sandbox for plugin
-    pub async fn create_plugin_sandbox(&self, plugin_id: &str, permissions: Vec<String>) -> MisaResult<String> {
-        let sandbox_manager = SandboxManager::new(
-            ResourceLimits::default(),
-            PermissionChecker::new(),
-        );
+    /// Replace the permissions granted by a role
+    pub async fn set_role_permissions(&self, role: &str, permissions: Vec<String>) {
+        self.permission_checker.set_role_permissions(role, permissions).await;
+    }
 
-        sandbox_manager.create_sandbox(plugin_id, permissions).await
+    /// Run a plugin process inside a resource-limited sandbox
+    pub async fn create_plugin_sandbox(
+        &self,
+        plugin_id: &str,
+        command: tokio::process::Command,
+        permissions: Vec<String>,
+    ) -> MisaResult<String> {
+        self.sandbox_manager.create_sandbox(plugin_id, command, permissions).await
+    }
+
+    /// Current state of a plugin sandbox
+    pub async fn get_plugin_sandbox(&self, sandbox_id: &str) -> Option<SandboxInfo> {
+        self.sandbox_manager.get_sandbox(sandbox_id).await
+    }
+
+    /// Stop a plugin sandbox and its process
+    pub async fn stop_plugin_sandbox(&self, sandbox_id: &str) -> MisaResult<()> {
+        self.sandbox_manager.stop_sandbox(sandbox_id).await
     }
 
     /// Shutdown security manager
@@ -308,6 +529,9 @@ impl SecurityManager {
         // Close all sessions
         self.auth_manager.close_all_sessions().await?;
 
+        // Stop plugin processes
+        self.sandbox_manager.shutdown().await;
+
         info!("Security manager shut down");
         Ok(())
     }
@@ -321,6 +545,8 @@ impl Clone for SecurityManager {
             encryption_manager: Arc::clone(&self.encryption_manager),
             auth_manager: Arc::clone(&self.auth_manager),
             audit_logger: Arc::clone(&self.audit_logger),
+            permission_checker: self.permission_checker.clone(),
+            sandbox_manager: Arc::clone(&self.sandbox_manager),
             secure_rng: SystemRandom::new(),
         }
     }
@@ -336,13 +562,65 @@ pub struct EncryptedData {
     pub tag: Vec<u8>,
 }
 
+/// One side of an X25519 key agreement with a device, consumed when it completes
+pub struct DeviceKeyExchange {
+    private_key: agreement::EphemeralPrivateKey,
+    public_key: Vec<u8>,
+}
+
+impl DeviceKeyExchange {
+    fn generate(rng: &SystemRandom) -> MisaResult<Self> {
+        let private_key = agreement::EphemeralPrivateKey::generate(&agreement::X25519, rng)
+            .map_err(|_| MisaError::Cryptographic("Failed to generate key exchange key".to_string()))?;
+        let public_key = private_key
+            .compute_public_key()
+            .map_err(|_| MisaError::Cryptographic("Failed to compute key exchange public key".to_string()))?
+            .as_ref()
+            .to_vec();
+        Ok(Self { private_key, public_key })
+    }
+
+    /// Public half to send to the other device
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    /// Derive the shared message key; both sides bind it to the same pair of public keys
+    fn agree(self, peer_public_key: &[u8]) -> MisaResult<[u8; 32]> {
+        let mut public_keys = [self.public_key.as_slice(), peer_public_key];
+        public_keys.sort();
+
+        agreement::agree_ephemeral(
+            self.private_key,
+            &agreement::UnparsedPublicKey::new(&agreement::X25519, peer_public_key),
+            MisaError::Cryptographic("Key agreement with device failed".to_string()),
+            |shared_secret| {
+                let mut key = [0u8; 32];
+                hkdf::Salt::new(hkdf::HKDF_SHA256, DEVICE_KEY_EXCHANGE_SALT)
+                    .extract(shared_secret)
+                    .expand(&public_keys, hkdf::HKDF_SHA256)
+                    .and_then(|okm| okm.fill(&mut key))
+                    .map_err(|_| MisaError::Cryptographic("Failed to derive device key".to_string()))?;
+                Ok(key)
+            },
+        )
+    }
+}
+
 impl EncryptionManager {
     pub async fn new(data_dir: &str) -> MisaResult<Self> {
-        Ok(Self {
+        let manager = Self {
             master_key: Arc::new(RwLock::new(None)),
             encrypted_keys: Arc::new(RwLock::new(HashMap::new())),
+            device_keys: Arc::new(RwLock::new(HashMap::new())),
+            stored_keys: Arc::new(RwLock::new(HashMap::new())),
+            key_dir: Path::new(data_dir).join(KEY_DIR),
             secure_rng: SystemRandom::new(),
-        })
+        };
+
+        let device_keys = manager.load_device_keys().await?;
+        *manager.device_keys.write().await = device_keys;
+        Ok(manager)
     }
 
     pub async fn initialize(&self) -> MisaResult<()> {
@@ -364,7 +642,162 @@ impl EncryptionManager {
         let master_key = self.master_key.read().await;
         let key = master_key.ok_or_else(|| MisaError::Encryption("Master key not initialized".to_string()))?;
 
-        let key = Key::from_slice(&*key);
+        self.seal(&key, data, key_id)
+    }
+
+    pub async fn decrypt(&self, encrypted_data: &EncryptedData) -> MisaResult<Vec<u8>> {
+        let master_key = self.master_key.read().await;
+        let key = master_key.ok_or_else(|| MisaError::Encryption("Master key not initialized".to_string()))?;
+
+        Self::open(&key, encrypted_data)
+    }
+
+    pub async fn has_master_key(&self) -> bool {
+        self.master_key.read().await.is_some()
+    }
+
+    pub async fn encrypt_with_stored_key(&self, key_name: &str, data: &[u8]) -> MisaResult<EncryptedData> {
+        let key = self.stored_key(key_name).await?;
+        self.seal(&key, data, key_name)
+    }
+
+    pub async fn decrypt_with_stored_key(&self, key_name: &str, encrypted_data: &EncryptedData) -> MisaResult<Vec<u8>> {
+        if encrypted_data.key_id != key_name {
+            return Err(MisaError::Encryption(format!("Unexpected key id {}", encrypted_data.key_id)));
+        }
+
+        let key = self.stored_key(key_name).await?;
+        Self::open(&key, encrypted_data)
+    }
+
+    /// HMAC-SHA256 of `data` under the key stored as `key_name`, stable across restarts
+    pub async fn hash_with_stored_key(&self, key_name: &str, data: &[u8]) -> MisaResult<Vec<u8>> {
+        let key = self.stored_key(key_name).await?;
+        Ok(hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, &key), data).as_ref().to_vec())
+    }
+
+    pub async fn has_stored_key(&self, key_name: &str) -> bool {
+        if self.stored_keys.read().await.contains_key(key_name) {
+            return true;
+        }
+        match self.key_path(key_name) {
+            Ok(path) => tokio::fs::try_exists(&path).await.unwrap_or(false),
+            Err(_) => false,
+        }
+    }
+
+    /// Replace the key stored under `key_name`
+    pub async fn import_stored_key(&self, key_name: &str, key: &[u8]) -> MisaResult<()> {
+        let key: [u8; 32] = key
+            .try_into()
+            .map_err(|_| MisaError::Encryption(format!("Stored keys must be 32 bytes, got {}", key.len())))?;
+
+        let mut stored_keys = self.stored_keys.write().await;
+        write_private_file(&self.key_path(key_name)?, &key).await?;
+        stored_keys.insert(key_name.to_string(), key);
+        info!("Imported stored key {}", key_name);
+        Ok(())
+    }
+
+    /// The key persisted under `key_name`, generated and written on first use
+    async fn stored_key(&self, key_name: &str) -> MisaResult<[u8; 32]> {
+        if let Some(key) = self.stored_keys.read().await.get(key_name) {
+            return Ok(*key);
+        }
+
+        // Hold the lock while loading so two first uses can't generate different keys
+        let mut stored_keys = self.stored_keys.write().await;
+        if let Some(key) = stored_keys.get(key_name) {
+            return Ok(*key);
+        }
+
+        let path = self.key_path(key_name)?;
+        let key = match tokio::fs::read(&path).await {
+            Ok(bytes) => <[u8; 32]>::try_from(bytes.as_slice())
+                .map_err(|_| MisaError::Encryption(format!("Key file {} is corrupt", path.display())))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let mut key = [0u8; 32];
+                self.secure_rng.fill(&mut key)
+                    .map_err(|e| MisaError::Encryption(format!("Failed to generate key {}: {}", key_name, e)))?;
+                write_private_file(&path, &key).await?;
+                info!("Generated stored key {}", key_name);
+                key
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        stored_keys.insert(key_name.to_string(), key);
+        Ok(key)
+    }
+
+    fn key_path(&self, key_name: &str) -> MisaResult<PathBuf> {
+        let valid = !key_name.is_empty()
+            && key_name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(MisaError::Validation(format!("Invalid key name: {}", key_name)));
+        }
+        Ok(self.key_dir.join(format!("{}.key", key_name)))
+    }
+
+    /// Store the key shared with a paired device, rewriting the sealed device key file
+    pub async fn set_device_key(&self, device_id: &str, key: [u8; 32]) -> MisaResult<()> {
+        let mut device_keys = self.device_keys.write().await;
+        let mut updated = device_keys.clone();
+        updated.insert(device_id.to_string(), key);
+
+        let sealed = self.encrypt_with_stored_key(DEVICE_KEY_STORE_KEY, &serde_json::to_vec(&updated)?).await?;
+        write_private_file(&self.key_dir.join(DEVICE_KEYS_FILE), &serde_json::to_vec(&sealed)?).await?;
+
+        *device_keys = updated;
+        Ok(())
+    }
+
+    /// Device keys saved by an earlier run, none if nothing was paired yet
+    async fn load_device_keys(&self) -> MisaResult<HashMap<String, [u8; 32]>> {
+        let path = self.key_dir.join(DEVICE_KEYS_FILE);
+        let sealed = match tokio::fs::read(&path).await {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let sealed: EncryptedData = serde_json::from_slice(&sealed)?;
+        let device_keys = self.decrypt_with_stored_key(DEVICE_KEY_STORE_KEY, &sealed).await?;
+        Ok(serde_json::from_slice(&device_keys)?)
+    }
+
+    pub async fn has_device_key(&self, device_id: &str) -> bool {
+        self.device_keys.read().await.contains_key(device_id)
+    }
+
+    /// Encrypt for a paired device; fails if no key is shared with it
+    pub async fn encrypt_for_device(&self, device_id: &str, data: &[u8]) -> MisaResult<EncryptedData> {
+        let key = self.device_key(device_id).await?;
+        self.seal(&key, data, DEVICE_CHANNEL_KEY_ID)
+    }
+
+    /// Decrypt data from a paired device; fails if no key is shared with it
+    pub async fn decrypt_from_device(&self, device_id: &str, encrypted_data: &EncryptedData) -> MisaResult<Vec<u8>> {
+        if encrypted_data.key_id != DEVICE_CHANNEL_KEY_ID {
+            return Err(MisaError::Encryption(format!("Unexpected key id {}", encrypted_data.key_id)));
+        }
+
+        let key = self.device_key(device_id).await?;
+        Self::open(&key, encrypted_data)
+    }
+
+    async fn device_key(&self, device_id: &str) -> MisaResult<[u8; 32]> {
+        self.device_keys
+            .read()
+            .await
+            .get(device_id)
+            .copied()
+            .ok_or_else(|| MisaError::Encryption(format!("No key shared with device {}", device_id)))
+    }
+
+    /// AES-256-GCM encrypt with a fresh nonce
+    fn seal(&self, key: &[u8; 32], data: &[u8], key_id: &str) -> MisaResult<EncryptedData> {
+        let key = Key::from_slice(key);
         let cipher = Aes256Gcm::new(key);
 
         // Generate nonce
@@ -389,15 +822,16 @@ impl EncryptionManager {
         })
     }
 
-    pub async fn decrypt(&self, encrypted_data: &EncryptedData) -> MisaResult<Vec<u8>> {
-        let master_key = self.master_key.read().await;
-        let key = master_key.ok_or_else(|| MisaError::Encryption("Master key not initialized".to_string()))?;
-
+    fn open(key: &[u8; 32], encrypted_data: &EncryptedData) -> MisaResult<Vec<u8>> {
         if encrypted_data.algorithm != "AES-256-GCM" {
             return Err(MisaError::Encryption("Unsupported encryption algorithm".to_string()));
         }
 
-        let key = Key::from_slice(&*key);
+        if encrypted_data.nonce.len() != 12 {
+            return Err(MisaError::Encryption("Invalid nonce length".to_string()));
+        }
+
+        let key = Key::from_slice(key);
         let cipher = Aes256Gcm::new(key);
 
         let nonce = Nonce::from_slice(&encrypted_data.nonce);
@@ -411,20 +845,43 @@ impl EncryptionManager {
 
         Ok(plaintext)
     }
+
+    pub async fn sign(&self, data: &[u8], purpose: &str) -> MisaResult<Vec<u8>> {
+        let key = self.signing_key(purpose).await?;
+        Ok(hmac::sign(&key, data).as_ref().to_vec())
+    }
+
+    pub async fn verify(&self, data: &[u8], signature: &[u8], purpose: &str) -> MisaResult<bool> {
+        let key = self.signing_key(purpose).await?;
+        Ok(hmac::verify(&key, data, signature).is_ok())
+    }
+
+    /// Derive a per-purpose HMAC key so signatures for one use can't be replayed for another
+    async fn signing_key(&self, purpose: &str) -> MisaResult<hmac::Key> {
+        let master_key = self.master_key.read().await;
+        let key = master_key.ok_or_else(|| MisaError::Cryptographic("Master key not initialized".to_string()))?;
+
+        let derived = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, &key), purpose.as_bytes());
+        Ok(hmac::Key::new(hmac::HMAC_SHA256, derived.as_ref()))
+    }
 }
 
 impl AuthManager {
-    pub async fn new(session_timeout_minutes: u64) -> MisaResult<Self> {
+    pub async fn new(session_timeout_minutes: u64, sliding_expiry: bool) -> MisaResult<Self> {
         Ok(Self {
             sessions: Arc::new(RwLock::new(HashMap::new())),
             user_credentials: Arc::new(RwLock::new(HashMap::new())),
+            credentials_path: Arc::new(RwLock::new(None)),
             biometric_providers: Arc::new(RwLock::new(HashMap::new())),
             session_timeout_minutes,
+            sliding_expiry,
+            session_sweeper: Arc::new(RwLock::new(None)),
         })
     }
 
     pub async fn load_credentials(&self, data_dir: &str) -> MisaResult<()> {
-        let credentials_path = Path::new(data_dir).join("credentials.json");
+        let credentials_path = Path::new(data_dir).join(CREDENTIALS_FILE);
+        *self.credentials_path.write().await = Some(credentials_path.clone());
 
         if credentials_path.exists() {
             let content = tokio::fs::read_to_string(&credentials_path).await
@@ -440,28 +897,102 @@ impl AuthManager {
         Ok(())
     }
 
-    pub async fn authenticate_password(&self, user_id: &str, password: &str) -> MisaResult<AuthSession> {
+    /// Create a user with an Argon2-hashed password and save the credentials
+    pub async fn create_user(&self, user_id: &str, password: &str) -> MisaResult<()> {
+        // Hashing is slow on purpose, so it happens before anyone else is locked out of the credentials
+        let password_hash = hash_password(password).await?;
+
+        let mut credentials = self.user_credentials.write().await;
+        if credentials.contains_key(user_id) {
+            return Err(MisaError::Security(format!("User already exists: {}", user_id)));
+        }
+
+        credentials.insert(
+            user_id.to_string(),
+            UserCredentials {
+                user_id: user_id.to_string(),
+                password_hash,
+                biometric_templates: HashMap::new(),
+                created_at: chrono::Utc::now(),
+                last_login: None,
+                failed_attempts: 0,
+                locked_until: None,
+            },
+        );
+        self.save_credentials(&credentials).await?;
+
+        info!("Created user: {}", user_id);
+        Ok(())
+    }
+
+    /// Replace a user's password after verifying the old one; a successful change clears any lockout
+    pub async fn change_password(&self, user_id: &str, old_password: &str, new_password: &str) -> MisaResult<()> {
+        let current_hash = self.unlocked_password_hash(user_id).await?;
+        if !verify_password(old_password, &current_hash).await? {
+            self.handle_failed_attempt(user_id).await?;
+            return Err(MisaError::Security("Invalid password".to_string()));
+        }
+        let new_hash = hash_password(new_password).await?;
+
+        let mut credentials = self.user_credentials.write().await;
+        let user_creds = credentials.get_mut(user_id)
+            .ok_or_else(|| MisaError::Security("User not found".to_string()))?;
+        // Another change may have landed while the hashes were computed without the lock
+        if user_creds.password_hash != current_hash {
+            return Err(MisaError::Security("Password was changed concurrently".to_string()));
+        }
+
+        user_creds.password_hash = new_hash;
+        user_creds.failed_attempts = 0;
+        user_creds.locked_until = None;
+        self.save_credentials(&credentials).await?;
+
+        info!("Changed password for user: {}", user_id);
+        Ok(())
+    }
+
+    /// Store a biometric template for an existing user, replacing any previous one of the same type.
+    /// `SecurityManager` hands over a keyed hash, never the raw template.
+    pub async fn enroll_biometric(&self, user_id: &str, biometric_type: BiometricType, template: &[u8]) -> MisaResult<()> {
+        if template.is_empty() {
+            return Err(MisaError::Validation("Biometric template is empty".to_string()));
+        }
+
+        let mut credentials = self.user_credentials.write().await;
+        let user_creds = credentials.get_mut(user_id)
+            .ok_or_else(|| MisaError::Security("User not found".to_string()))?;
+        user_creds.biometric_templates.insert(biometric_type.clone(), template.to_vec());
+        self.save_credentials(&credentials).await?;
+
+        info!("Enrolled {:?} biometric for user: {}", biometric_type, user_id);
+        Ok(())
+    }
+
+    /// Write credentials back to the file they were loaded from, readable by its owner only
+    async fn save_credentials(&self, credentials: &HashMap<String, UserCredentials>) -> MisaResult<()> {
+        let credentials_path = self.credentials_path.read().await.clone()
+            .ok_or_else(|| MisaError::Security("Credentials have not been loaded".to_string()))?;
+        write_private_file(&credentials_path, &serde_json::to_vec_pretty(credentials)?).await
+    }
+
+    /// A user's password hash, unless the account is locked
+    async fn unlocked_password_hash(&self, user_id: &str) -> MisaResult<String> {
         let credentials = self.user_credentials.read().await;
         let user_creds = credentials.get(user_id)
             .ok_or_else(|| MisaError::Security("User not found".to_string()))?;
 
-        // Check if account is locked
-        if let Some(locked_until) = user_creds.locked_until {
-            if chrono::Utc::now() < locked_until {
-                return Err(MisaError::Security("Account is locked".to_string()));
-            }
+        if user_creds.locked_until.is_some_and(|locked_until| chrono::Utc::now() < locked_until) {
+            return Err(MisaError::Security("Account is locked".to_string()));
         }
+        Ok(user_creds.password_hash.clone())
+    }
 
-        // Verify password
-        let password_hash = PasswordHash::new(&user_creds.password_hash)
-            .map_err(|e| MisaError::Security(format!("Invalid password hash: {}", e)))?;
+    pub async fn authenticate_password(&self, user_id: &str, password: &str) -> MisaResult<AuthSession> {
+        let password_hash = self.unlocked_password_hash(user_id).await?;
 
-        if Argon2::default().verify_password(password.as_bytes(), &password_hash).is_ok() {
-            // Password correct - create session
+        if verify_password(password, &password_hash).await? {
             self.create_session(user_id, vec!["user".to_string()]).await
         } else {
-            // Password incorrect - log failed attempt
-            drop(credentials);
             self.handle_failed_attempt(user_id).await?;
             Err(MisaError::Security("Invalid password".to_string()))
         }
@@ -475,30 +1006,106 @@ impl AuthManager {
         let template = user_creds.biometric_templates.get(&biometric_type)
             .ok_or_else(|| MisaError::Security("Biometric template not found".to_string()))?;
 
-        // Simple template matching (in real implementation, use proper biometric matching)
-        if data.len() == template.len() {
+        // Both sides are keyed hashes, so only the enrolled template itself matches
+        if ring::constant_time::verify_slices_are_equal(data, template).is_ok() {
             self.create_session(user_id, vec!["user".to_string()]).await
         } else {
             Err(MisaError::Security("Biometric authentication failed".to_string()))
         }
     }
 
+    /// Validate a session, recording the activity and sliding its expiry if enabled
     pub async fn validate_session(&self, session_id: &str) -> MisaResult<Option<AuthSession>> {
-        let sessions = self.sessions.read().await;
-        let session = sessions.get(session_id);
+        let now = chrono::Utc::now();
+        let mut sessions = self.sessions.write().await;
+
+        let session = match sessions.get_mut(session_id) {
+            Some(session) => session,
+            None => return Ok(None), // Session not found
+        };
+
+        if now >= session.expires_at {
+            debug!("Session expired for user: {}", session.user_id);
+            sessions.remove(session_id);
+            return Ok(None);
+        }
+
+        session.last_activity = now;
+        if self.sliding_expiry {
+            session.expires_at = now + self.session_timeout();
+        }
 
-        if let Some(session) = session {
-            if chrono::Utc::now() < session.expires_at {
+        Ok(Some(session.clone()))
+    }
+
+    /// Extend a still-valid session so it expires one timeout window from now
+    pub async fn refresh_session(&self, session_id: &str) -> MisaResult<Option<AuthSession>> {
+        let now = chrono::Utc::now();
+        let mut sessions = self.sessions.write().await;
+
+        match sessions.get_mut(session_id) {
+            Some(session) if now < session.expires_at => {
+                session.last_activity = now;
+                session.expires_at = now + self.session_timeout();
                 Ok(Some(session.clone()))
-            } else {
-                Ok(None) // Session expired
             }
-        } else {
-            Ok(None) // Session not found
+            Some(_) => {
+                sessions.remove(session_id);
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Remove expired sessions, returning how many were dropped
+    pub async fn purge_expired_sessions(&self) -> usize {
+        let now = chrono::Utc::now();
+        let mut sessions = self.sessions.write().await;
+        let before = sessions.len();
+        sessions.retain(|_, session| now < session.expires_at);
+        before - sessions.len()
+    }
+
+    /// Periodically purge expired sessions; a no-op if the sweeper is already running
+    pub async fn start_session_sweeper(&self, interval: std::time::Duration) {
+        let mut sweeper = self.session_sweeper.write().await;
+        if sweeper.is_some() {
+            return;
         }
+
+        let auth_manager = self.clone();
+        *sweeper = Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                let purged = auth_manager.purge_expired_sessions().await;
+                if purged > 0 {
+                    debug!("Purged {} expired sessions", purged);
+                }
+            }
+        }));
+    }
+
+    fn session_timeout(&self) -> chrono::Duration {
+        chrono::Duration::minutes(self.session_timeout_minutes as i64)
+    }
+
+    /// Unexpired sessions belonging to a user
+    pub async fn active_sessions(&self, user_id: &str) -> Vec<AuthSession> {
+        let now = chrono::Utc::now();
+        let sessions = self.sessions.read().await;
+        sessions
+            .values()
+            .filter(|session| session.user_id == user_id && now < session.expires_at)
+            .cloned()
+            .collect()
     }
 
     pub async fn close_all_sessions(&self) -> MisaResult<()> {
+        if let Some(sweeper) = self.session_sweeper.write().await.take() {
+            sweeper.abort();
+        }
+
         let mut sessions = self.sessions.write().await;
         sessions.clear();
         Ok(())
@@ -507,7 +1114,7 @@ impl AuthManager {
     async fn create_session(&self, user_id: &str, permissions: Vec<String>) -> MisaResult<AuthSession> {
         let session_id = uuid::Uuid::new_v4().to_string();
         let now = chrono::Utc::now();
-        let expires_at = now + chrono::Duration::minutes(self.session_timeout_minutes as i64);
+        let expires_at = now + self.session_timeout();
 
         let session = AuthSession {
             session_id: session_id.clone(),
@@ -541,73 +1148,439 @@ impl AuthManager {
     }
 }
 
+/// Resolve `.`, `..` and repeated separators without touching the filesystem,
+/// so `/tmp/..//etc/./shadow` is judged as `/etc/shadow`
+fn normalize_path(path: &str) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in Path::new(path).components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
+
+/// Write a file readable by its owner only, replacing any previous one atomically
+async fn write_private_file(path: &Path, contents: &[u8]) -> MisaResult<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let mut file = options.open(&tmp_path).await?;
+    file.write_all(contents).await?;
+    file.sync_all().await?;
+    drop(file);
+
+    tokio::fs::rename(&tmp_path, path).await?;
+    Ok(())
+}
+
+/// Hash a password with Argon2 and a fresh random salt, off the async runtime
+async fn hash_password(password: &str) -> MisaResult<String> {
+    if password.is_empty() {
+        return Err(MisaError::Validation("Password must not be empty".to_string()));
+    }
+
+    let password = password.to_string();
+    tokio::task::spawn_blocking(move || {
+        let salt = SaltString::generate(&mut rand::rngs::OsRng);
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| MisaError::Security(format!("Failed to hash password: {}", e)))
+    })
+    .await
+    .map_err(|e| MisaError::Security(format!("Password hashing task failed: {}", e)))?
+}
+
+/// Check a password against an Argon2 hash, off the async runtime
+async fn verify_password(password: &str, password_hash: &str) -> MisaResult<bool> {
+    let password = password.to_string();
+    let password_hash = password_hash.to_string();
+    tokio::task::spawn_blocking(move || {
+        let password_hash = PasswordHash::new(&password_hash)
+            .map_err(|e| MisaError::Security(format!("Invalid password hash: {}", e)))?;
+        Ok(Argon2::default().verify_password(password.as_bytes(), &password_hash).is_ok())
+    })
+    .await
+    .map_err(|e| MisaError::Security(format!("Password verification task failed: {}", e)))?
+}
+
+/// Number of rotated audit log files kept (`audit.log.1` is the newest)
+const AUDIT_LOG_ROTATIONS: usize = 5;
+
 impl AuditLogger {
-    pub async fn new(data_dir: &str) -> MisaResult<Self> {
-        let log_file = Arc::new(RwLock::new(None));
+    pub async fn new(data_dir: &str, max_file_bytes: u64) -> MisaResult<Self> {
+        let log_path = Path::new(data_dir).join("audit.log");
+        let log_file = Self::open_log_file(&log_path).await?;
 
         Ok(Self {
-            log_file,
+            log_path,
+            log_file: Arc::new(RwLock::new(Some(log_file))),
             log_entries: Arc::new(RwLock::new(Vec::new())),
             max_entries: 10000,
+            max_file_bytes,
         })
     }
 
     pub async fn log_entry(&self, entry: AuditEntry) -> MisaResult<()> {
         debug!("Logging audit entry: {}", entry.action);
 
+        let mut log_line = serde_json::to_string(&entry)?;
+        log_line.push('\n');
+
         // Add to in-memory buffer
         let mut entries = self.log_entries.write().await;
-        entries.push(entry.clone());
+        entries.push(entry);
 
         // Trim if exceeding max entries
         if entries.len() > self.max_entries {
             entries.remove(0);
         }
-
-        // Write to file (in production, use proper file rotation)
-        if let Some(log_file) = self.log_file.read().await.as_ref() {
-            let log_line = serde_json::to_string(&entry) + "\n";
-            // In real implementation, write to file asynchronously
+        drop(entries);
+
+        let mut log_file = self.log_file.write().await;
+        if let Some(current) = log_file.as_mut() {
+            current.file.write_all(log_line.as_bytes()).await?;
+            current.file.flush().await?;
+            current.size += log_line.len() as u64;
+
+            if current.size >= self.max_file_bytes {
+                current.file.sync_all().await?;
+                *log_file = None;
+                self.rotate().await?;
+                *log_file = Some(Self::open_log_file(&self.log_path).await?);
+            }
         }
 
         Ok(())
     }
 
     pub async fn flush(&self) -> MisaResult<()> {
-        // Flush all pending log entries
+        if let Some(current) = self.log_file.write().await.as_mut() {
+            current.file.flush().await?;
+            current.file.sync_all().await?;
+        }
+
         let entries = self.log_entries.read().await;
-        info!("Flushing {} audit log entries", entries.len());
+        info!("Flushed {} audit log entries", entries.len());
+        Ok(())
+    }
+
+    /// Read matching entries from the rotated and current log files, oldest first
+    pub async fn query(&self, filter: &AuditQuery) -> MisaResult<Vec<AuditEntry>> {
+        // Hold the lock so a rotation can't move files mid-read
+        let _log_file = self.log_file.read().await;
+
+        let mut paths: Vec<PathBuf> = (1..=AUDIT_LOG_ROTATIONS).rev().map(|n| self.rotated_path(n)).collect();
+        paths.push(self.log_path.clone());
+
+        let needle = filter.text.as_ref().map(|text| text.to_lowercase());
+        let mut matches = VecDeque::new();
+        for path in paths {
+            let content = match tokio::fs::read_to_string(&path).await {
+                Ok(content) => content,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+
+            for line in content.lines().filter(|line| !line.trim().is_empty()) {
+                match serde_json::from_str::<AuditEntry>(line) {
+                    Ok(entry) if Self::matches(&entry, filter, needle.as_deref()) => {
+                        if filter.limit.is_some_and(|limit| matches.len() >= limit) {
+                            matches.pop_front();
+                        }
+                        matches.push_back(entry);
+                    }
+                    Ok(_) => {}
+                    Err(e) => warn!("Skipping malformed audit entry in {}: {}", path.display(), e),
+                }
+            }
+        }
+
+        if filter.limit == Some(0) {
+            matches.clear();
+        }
+        Ok(matches.into())
+    }
+
+    fn matches(entry: &AuditEntry, filter: &AuditQuery, needle: Option<&str>) -> bool {
+        filter.since.map_or(true, |since| entry.timestamp >= since)
+            && filter.until.map_or(true, |until| entry.timestamp <= until)
+            && filter.user_id.as_ref().map_or(true, |user_id| entry.user_id.as_ref() == Some(user_id))
+            && filter.action.as_ref().map_or(true, |action| &entry.action == action)
+            && needle.map_or(true, |needle| {
+                format!("{} {} {}", entry.action, entry.resource, entry.details).to_lowercase().contains(needle)
+            })
+    }
+
+    /// Shift `audit.log.N` to `audit.log.N+1`, dropping the oldest, and move the current log to `audit.log.1`
+    async fn rotate(&self) -> MisaResult<()> {
+        for n in (1..AUDIT_LOG_ROTATIONS).rev() {
+            let from = self.rotated_path(n);
+            if tokio::fs::metadata(&from).await.is_ok() {
+                tokio::fs::rename(&from, self.rotated_path(n + 1)).await?;
+            }
+        }
+
+        tokio::fs::rename(&self.log_path, self.rotated_path(1)).await?;
+        info!("Rotated audit log {}", self.log_path.display());
         Ok(())
     }
+
+    fn rotated_path(&self, n: usize) -> PathBuf {
+        let mut path = self.log_path.clone().into_os_string();
+        path.push(format!(".{}", n));
+        PathBuf::from(path)
+    }
+
+    async fn open_log_file(path: &Path) -> MisaResult<AuditLogFile> {
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await?;
+        let size = file.metadata().await?.len();
+
+        Ok(AuditLogFile { file, size })
+    }
 }
 
+/// CPU samples over the limit in a row before a sandbox is terminated, to ride out startup spikes
+const SANDBOX_CPU_STRIKES: u32 = 3;
+
 impl SandboxManager {
     pub fn new(resource_limits: ResourceLimits, permission_checker: PermissionChecker) -> Self {
         Self {
             active_sandboxes: Arc::new(RwLock::new(HashMap::new())),
+            stop_signals: Arc::new(RwLock::new(HashMap::new())),
             resource_limits,
             permission_checker,
+            sample_interval: std::time::Duration::from_millis(500),
         }
     }
 
-    pub async fn create_sandbox(&self, plugin_id: &str, permissions: Vec<String>) -> MisaResult<String> {
-        let sandbox_id = uuid::Uuid::new_v4().to_string();
+    /// How often sandboxed processes are sampled for resource usage
+    pub fn with_sample_interval(mut self, sample_interval: std::time::Duration) -> Self {
+        self.sample_interval = sample_interval;
+        self
+    }
 
+    /// Spawn the plugin process and start monitoring it against the resource limits
+    pub async fn create_sandbox(
+        &self,
+        plugin_id: &str,
+        mut command: tokio::process::Command,
+        permissions: Vec<String>,
+    ) -> MisaResult<String> {
+        self.check_permission_policy(&permissions)?;
+
+        let child = command
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| MisaError::Plugin(format!("Failed to start plugin {}: {}", plugin_id, e)))?;
+        let pid = child
+            .id()
+            .ok_or_else(|| MisaError::Plugin(format!("Plugin {} exited immediately", plugin_id)))?;
+
+        let sandbox_id = uuid::Uuid::new_v4().to_string();
         let sandbox_info = SandboxInfo {
             plugin_id: plugin_id.to_string(),
             sandbox_id: sandbox_id.clone(),
-            pid: 0, // Would be set when actually creating the sandbox
+            pid,
             resource_usage: ResourceUsage::default(),
             permissions,
             started_at: chrono::Utc::now(),
             status: SandboxStatus::Running,
         };
+        self.active_sandboxes.write().await.insert(sandbox_id.clone(), sandbox_info);
+
+        let (stop_tx, stop_rx) = tokio::sync::oneshot::channel();
+        self.stop_signals.write().await.insert(sandbox_id.clone(), stop_tx);
+
+        let monitor = Self::monitor(
+            sandbox_id.clone(),
+            child,
+            stop_rx,
+            Arc::clone(&self.active_sandboxes),
+            self.resource_limits.clone(),
+            self.sample_interval,
+        );
+        let stop_signals = Arc::clone(&self.stop_signals);
+        let finished_id = sandbox_id.clone();
+        tokio::spawn(async move {
+            monitor.await;
+            // Sandboxes that exit on their own have nothing left to stop
+            stop_signals.write().await.remove(&finished_id);
+        });
+
+        info!("Created sandbox {} for plugin {} (pid {})", sandbox_id, plugin_id, pid);
+        Ok(sandbox_id)
+    }
 
-        let mut sandboxes = self.active_sandboxes.write().await;
-        sandboxes.insert(sandbox_id.clone(), sandbox_info);
+    pub async fn get_sandbox(&self, sandbox_id: &str) -> Option<SandboxInfo> {
+        self.active_sandboxes.read().await.get(sandbox_id).cloned()
+    }
 
-        info!("Created sandbox {} for plugin {}", sandbox_id, plugin_id);
-        Ok(sandbox_id)
+    /// Ask the monitor to kill the sandboxed process
+    pub async fn stop_sandbox(&self, sandbox_id: &str) -> MisaResult<()> {
+        let stop = self
+            .stop_signals
+            .write()
+            .await
+            .remove(sandbox_id)
+            .ok_or_else(|| MisaError::NotFound(format!("Sandbox not running: {}", sandbox_id)))?;
+
+        // The monitor may have exited on its own in the meantime
+        let _ = stop.send(());
+        Ok(())
+    }
+
+    pub async fn shutdown(&self) {
+        let signals: Vec<_> = self.stop_signals.write().await.drain().collect();
+        for (_, stop) in signals {
+            let _ = stop.send(());
+        }
+    }
+
+    /// Reject permissions the resource limits forbid before the plugin ever runs.
+    /// File permissions take the form `files:<action>:<path>`.
+    fn check_permission_policy(&self, permissions: &[String]) -> MisaResult<()> {
+        for permission in permissions {
+            let scope = permission.split(':').next().unwrap_or_default();
+
+            if scope == "network" && self.resource_limits.network_isolated {
+                return Err(PluginError::SandboxViolation {
+                    violation: format!("{} requested but sandbox is network isolated", permission),
+                }
+                .into());
+            }
+
+            if scope == "files" {
+                // A file permission without a path covers the whole filesystem, blocked files included.
+                // Relative paths can't be judged against the policy, so they are refused too.
+                let path = normalize_path(permission.splitn(3, ':').nth(2).unwrap_or_default());
+                let blocked = !path.is_absolute()
+                    || self
+                        .resource_limits
+                        .blocked_files
+                        .iter()
+                        .any(|blocked| path.starts_with(normalize_path(blocked)));
+                let allowed = self.resource_limits.allowed_files.is_empty()
+                    || self
+                        .resource_limits
+                        .allowed_files
+                        .iter()
+                        .any(|allowed| path.starts_with(normalize_path(allowed)));
+
+                if blocked || !allowed {
+                    return Err(PluginError::SandboxViolation {
+                        violation: format!("{} is not permitted by the sandbox file policy", permission),
+                    }
+                    .into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sample the process until it exits, is stopped, or breaks a limit
+    async fn monitor(
+        sandbox_id: String,
+        mut child: tokio::process::Child,
+        mut stop: tokio::sync::oneshot::Receiver<()>,
+        sandboxes: Arc<RwLock<HashMap<String, SandboxInfo>>>,
+        limits: ResourceLimits,
+        sample_interval: std::time::Duration,
+    ) {
+        use sysinfo::{Pid, PidExt, ProcessExt, System, SystemExt};
+
+        let pid = match child.id() {
+            Some(pid) => Pid::from_u32(pid),
+            None => return,
+        };
+        let memory_limit_bytes = limits.max_memory_mb * 1024 * 1024;
+        let mut system = System::new();
+        let mut interval = tokio::time::interval(sample_interval);
+        let mut cpu_strikes = 0;
+
+        let final_status = loop {
+            tokio::select! {
+                _ = &mut stop => {
+                    let _ = child.kill().await;
+                    break SandboxStatus::Stopped;
+                }
+                _ = interval.tick() => {}
+            }
+
+            if let Ok(Some(exit)) = child.try_wait() {
+                break if exit.success() {
+                    SandboxStatus::Stopped
+                } else {
+                    SandboxStatus::Error(format!("Plugin exited with {}", exit))
+                };
+            }
+
+            if !system.refresh_process(pid) {
+                continue;
+            }
+            let process = match system.process(pid) {
+                Some(process) => process,
+                None => continue,
+            };
+
+            let memory_bytes = process.memory();
+            let cpu_percent = process.cpu_usage();
+            let disk = process.disk_usage();
+
+            if let Some(info) = sandboxes.write().await.get_mut(&sandbox_id) {
+                info.resource_usage.memory_mb = memory_bytes / (1024 * 1024);
+                info.resource_usage.cpu_percent = cpu_percent;
+                info.resource_usage.disk_bytes_read = disk.total_read_bytes;
+                info.resource_usage.disk_bytes_written = disk.total_written_bytes;
+            }
+
+            cpu_strikes = if cpu_percent > limits.max_cpu_percent { cpu_strikes + 1 } else { 0 };
+
+            let violation = if memory_bytes > memory_limit_bytes {
+                Some(format!(
+                    "memory limit exceeded: {} MB used, limit {} MB",
+                    memory_bytes / (1024 * 1024),
+                    limits.max_memory_mb
+                ))
+            } else if cpu_strikes >= SANDBOX_CPU_STRIKES {
+                Some(format!(
+                    "cpu limit exceeded: {:.1}% used, limit {:.1}%",
+                    cpu_percent, limits.max_cpu_percent
+                ))
+            } else {
+                None
+            };
+
+            if let Some(violation) = violation {
+                warn!("Terminating sandbox {}: {}", sandbox_id, violation);
+                let _ = child.kill().await;
+                break SandboxStatus::Error(violation);
+            }
+        };
+
+        if let Some(info) = sandboxes.write().await.get_mut(&sandbox_id) {
+            info!("Sandbox {} finished: {:?}", sandbox_id, final_status);
+            info.status = final_status;
+        }
     }
 }
 
@@ -617,6 +1590,74 @@ impl PermissionChecker {
             permission_matrix: Arc::new(RwLock::new(HashMap::new())),
         }
     }
+
+    /// Checker preloaded with the built-in `admin`, `user` and `guest` roles
+    pub fn with_default_roles() -> Self {
+        let roles = [
+            ("admin", vec!["*"]),
+            ("user", vec![
+                "memory:*",
+                "files:*",
+                "devices:read",
+                "devices:pair",
+                "remote_desktop:start",
+                "privacy:*",
+            ]),
+            ("guest", vec!["memory:read", "devices:read"]),
+        ];
+
+        let matrix = roles
+            .into_iter()
+            .map(|(role, permissions)| {
+                (role.to_string(), permissions.into_iter().map(String::from).collect())
+            })
+            .collect();
+
+        Self {
+            permission_matrix: Arc::new(RwLock::new(matrix)),
+        }
+    }
+
+    pub async fn set_role_permissions(&self, role: &str, permissions: Vec<String>) {
+        self.permission_matrix.write().await.insert(role.to_string(), permissions);
+    }
+
+    /// Whether any of the grants (roles or direct permissions) covers the requested permission
+    pub async fn is_granted(&self, grants: &[String], permission: &str) -> bool {
+        let matrix = self.permission_matrix.read().await;
+
+        grants.iter().any(|grant| {
+            let role_permissions = matrix.get(grant).map(|permissions| permissions.as_slice()).unwrap_or_default();
+            Self::matches(grant, permission)
+                || role_permissions.iter().any(|granted| Self::matches(granted, permission))
+        })
+    }
+
+    /// Whether permissions declared outright, with no role expansion, cover the requested one
+    pub fn is_declared(declared: &[String], permission: &str) -> bool {
+        declared.iter().any(|granted| Self::matches(granted, permission))
+    }
+
+    fn matches(granted: &str, requested: &str) -> bool {
+        if granted == "*" || granted == requested {
+            return true;
+        }
+
+        match granted.strip_suffix(":*") {
+            Some(scope) => requested
+                .strip_prefix(scope)
+                .map_or(false, |rest| rest.is_empty() || rest.starts_with(':')),
+            None => false,
+        }
+    }
+}
+
+impl Clone for PermissionChecker {
+    fn clone(&self) -> Self {
+        Self {
+            permission_matrix: Arc::clone(&self.permission_matrix),
+        }
+    }
 }
 
 impl Default for ResourceLimits {
@@ -654,6 +1695,9 @@ impl Clone for EncryptionManager {
         Self {
             master_key: Arc::clone(&self.master_key),
             encrypted_keys: Arc::clone(&self.encrypted_keys),
+            device_keys: Arc::clone(&self.device_keys),
+            stored_keys: Arc::clone(&self.stored_keys),
+            key_dir: self.key_dir.clone(),
             secure_rng: SystemRandom::new(),
         }
     }
@@ -664,8 +1708,11 @@ impl Clone for AuthManager {
         Self {
             sessions: Arc::clone(&self.sessions),
             user_credentials: Arc::clone(&self.user_credentials),
+            credentials_path: Arc::clone(&self.credentials_path),
             biometric_providers: Arc::clone(&self.biometric_providers),
             session_timeout_minutes: self.session_timeout_minutes,
+            sliding_expiry: self.sliding_expiry,
+            session_sweeper: Arc::clone(&self.session_sweeper),
         }
     }
 }
@@ -673,9 +1720,384 @@ impl Clone for AuthManager {
 impl Clone for AuditLogger {
     fn clone(&self) -> Self {
         Self {
+            log_path: self.log_path.clone(),
             log_file: Arc::clone(&self.log_file),
             log_entries: Arc::clone(&self.log_entries),
             max_entries: self.max_entries,
+            max_file_bytes: self.max_file_bytes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_security_manager(data_dir: &tempfile::TempDir) -> SecurityManager {
+        SecurityManager::new(data_dir.path().to_str().unwrap(), SecurityConfig::default())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_role_grants_permission() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let manager = test_security_manager(&data_dir).await;
+        manager.auth_manager.create_session("alice", vec!["user".to_string()]).await.unwrap();
+
+        assert!(manager.check_permission("alice", "remote_desktop:start").await.unwrap());
+        assert!(manager.check_permission("alice", "memory:write").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_permission_denied_without_grant_or_session() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let manager = test_security_manager(&data_dir).await;
+        manager.auth_manager.create_session("guest-1", vec!["guest".to_string()]).await.unwrap();
+
+        assert!(!manager.check_permission("guest-1", "remote_desktop:start").await.unwrap());
+        assert!(!manager.check_permission("guest-1", "memory:write").await.unwrap());
+        assert!(!manager.check_permission("nobody", "memory:read").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_sliding_expiry_extends_active_sessions() {
+        let auth = AuthManager::new(30, true).await.unwrap();
+        let session = auth.create_session("alice", vec!["user".to_string()]).await.unwrap();
+
+        // Pretend the session is about to run out
+        {
+            let mut sessions = auth.sessions.write().await;
+            let stored = sessions.get_mut(&session.session_id).unwrap();
+            stored.expires_at = chrono::Utc::now() + chrono::Duration::minutes(1);
+            stored.last_activity = chrono::Utc::now() - chrono::Duration::minutes(29);
+        }
+
+        let validated = auth.validate_session(&session.session_id).await.unwrap().unwrap();
+        assert!(validated.expires_at > chrono::Utc::now() + chrono::Duration::minutes(29));
+        assert!(validated.last_activity > chrono::Utc::now() - chrono::Duration::seconds(5));
+    }
+
+    #[tokio::test]
+    async fn test_fixed_expiry_only_extends_on_refresh() {
+        let auth = AuthManager::new(30, false).await.unwrap();
+        let session = auth.create_session("alice", vec!["user".to_string()]).await.unwrap();
+        let near_expiry = chrono::Utc::now() + chrono::Duration::minutes(1);
+        auth.sessions.write().await.get_mut(&session.session_id).unwrap().expires_at = near_expiry;
+
+        let validated = auth.validate_session(&session.session_id).await.unwrap().unwrap();
+        assert_eq!(validated.expires_at, near_expiry);
+
+        let refreshed = auth.refresh_session(&session.session_id).await.unwrap().unwrap();
+        assert!(refreshed.expires_at > chrono::Utc::now() + chrono::Duration::minutes(29));
+    }
+
+    #[tokio::test]
+    async fn test_session_expires_after_inactivity() {
+        let auth = AuthManager::new(30, true).await.unwrap();
+        let idle = auth.create_session("alice", vec!["user".to_string()]).await.unwrap();
+        let active = auth.create_session("bob", vec!["user".to_string()]).await.unwrap();
+        auth.sessions.write().await.get_mut(&idle.session_id).unwrap().expires_at =
+            chrono::Utc::now() - chrono::Duration::seconds(1);
+
+        assert_eq!(auth.purge_expired_sessions().await, 1);
+        assert!(auth.validate_session(&idle.session_id).await.unwrap().is_none());
+        assert!(auth.refresh_session(&idle.session_id).await.unwrap().is_none());
+        assert!(auth.validate_session(&active.session_id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_rotates_and_reads_back() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let logger = AuditLogger::new(data_dir.path().to_str().unwrap(), 512).await.unwrap();
+
+        for i in 0..10 {
+            let entry = AuditEntry {
+                id: format!("entry-{}", i),
+                timestamp: chrono::Utc::now(),
+                user_id: Some(if i % 2 == 0 { "alice" } else { "bob" }.to_string()),
+                session_id: None,
+                action: if i < 5 { "login" } else { "file_read" }.to_string(),
+                resource: "test".to_string(),
+                result: AuditResult::Success,
+                details: serde_json::json!({ "sequence": i }),
+                ip_address: None,
+                user_agent: None,
+            };
+            logger.log_entry(entry).await.unwrap();
         }
+        logger.flush().await.unwrap();
+
+        assert!(data_dir.path().join("audit.log.1").exists());
+        assert!(tokio::fs::metadata(data_dir.path().join("audit.log")).await.unwrap().len() < 512);
+
+        let all = logger.query(&AuditQuery::default()).await.unwrap();
+        let ids: Vec<String> = all.iter().map(|entry| entry.id.clone()).collect();
+        assert_eq!(ids, (0..10).map(|i| format!("entry-{}", i)).collect::<Vec<_>>());
+
+        let alice_reads = logger
+            .query(&AuditQuery {
+                user_id: Some("alice".to_string()),
+                action: Some("file_read".to_string()),
+                ..AuditQuery::default()
+            })
+            .await
+            .unwrap();
+        let ids: Vec<&str> = alice_reads.iter().map(|entry| entry.id.as_str()).collect();
+        assert_eq!(ids, vec!["entry-6", "entry-8"]);
+
+        let future = logger
+            .query(&AuditQuery {
+                since: Some(chrono::Utc::now() + chrono::Duration::minutes(1)),
+                ..AuditQuery::default()
+            })
+            .await
+            .unwrap();
+        assert!(future.is_empty());
+
+        let newest_reads = logger
+            .query(&AuditQuery {
+                text: Some("FILE_READ".to_string()),
+                limit: Some(2),
+                ..AuditQuery::default()
+            })
+            .await
+            .unwrap();
+        let ids: Vec<&str> = newest_reads.iter().map(|entry| entry.id.as_str()).collect();
+        assert_eq!(ids, vec!["entry-8", "entry-9"]);
     }
-}
\ No newline at end of file
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_sandbox_over_memory_limit_is_terminated() {
+        let limits = ResourceLimits {
+            max_memory_mb: 0,
+            ..ResourceLimits::default()
+        };
+        let sandboxes = SandboxManager::new(limits, PermissionChecker::new())
+            .with_sample_interval(std::time::Duration::from_millis(20));
+
+        let mut command = tokio::process::Command::new("sleep");
+        command.arg("30");
+        let sandbox_id = sandboxes.create_sandbox("hungry-plugin", command, vec![]).await.unwrap();
+        let pid = sandboxes.get_sandbox(&sandbox_id).await.unwrap().pid;
+        assert_ne!(pid, 0);
+
+        let mut status = SandboxStatus::Running;
+        for _ in 0..100 {
+            status = sandboxes.get_sandbox(&sandbox_id).await.unwrap().status;
+            if !matches!(status, SandboxStatus::Running) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+
+        match status {
+            SandboxStatus::Error(reason) => assert!(reason.contains("memory limit exceeded")),
+            other => panic!("sandbox was not terminated: {:?}", other),
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_sandbox_that_exits_on_its_own_releases_its_stop_signal() {
+        let sandboxes = SandboxManager::new(ResourceLimits::default(), PermissionChecker::new())
+            .with_sample_interval(std::time::Duration::from_millis(20));
+
+        let sandbox_id = sandboxes
+            .create_sandbox("short-plugin", tokio::process::Command::new("true"), vec![])
+            .await
+            .unwrap();
+
+        for _ in 0..100 {
+            if !sandboxes.stop_signals.read().await.contains_key(&sandbox_id) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+
+        assert!(sandboxes.stop_signals.read().await.is_empty());
+        assert!(matches!(sandboxes.stop_sandbox(&sandbox_id).await, Err(MisaError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_sandbox_policy_rejects_network_and_blocked_files() {
+        let sandboxes = SandboxManager::new(ResourceLimits::default(), PermissionChecker::new());
+
+        let network = sandboxes.check_permission_policy(&["network:connect".to_string()]);
+        assert!(matches!(network, Err(MisaError::Plugin(_))));
+
+        assert!(sandboxes.check_permission_policy(&["files:read:/etc/shadow".to_string()]).is_err());
+        assert!(sandboxes.check_permission_policy(&["files:read:/tmp/notes.txt".to_string()]).is_ok());
+    }
+
+    #[test]
+    fn test_sandbox_file_policy_normalizes_paths() {
+        let limits = ResourceLimits {
+            allowed_files: vec!["/tmp/plugin".to_string()],
+            ..ResourceLimits::default()
+        };
+        let sandboxes = SandboxManager::new(limits, PermissionChecker::new());
+        let check = |path: &str| sandboxes.check_permission_policy(&[format!("files:read:{}", path)]);
+
+        assert!(check("//etc/shadow").is_err());
+        assert!(check("/etc//shadow").is_err());
+        assert!(check("/tmp/plugin/../../etc/shadow").is_err());
+        assert!(check("/tmp/plugin/./../plugin-other/data").is_err());
+        assert!(check("../etc/shadow").is_err());
+
+        assert!(check("/tmp/plugin//cache/./data.json").is_ok());
+        assert!(check("/tmp/plugin/cache/../data.json").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_wildcard_scope_matching() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let manager = test_security_manager(&data_dir).await;
+        manager.auth_manager.create_session("bob", vec!["files:*".to_string()]).await.unwrap();
+
+        assert!(manager.check_permission("bob", "files:read").await.unwrap());
+        assert!(manager.check_permission("bob", "files:delete").await.unwrap());
+        assert!(!manager.check_permission("bob", "filesystem:read").await.unwrap());
+        assert!(!manager.check_permission("bob", "memory:read").await.unwrap());
+
+        manager.auth_manager.create_session("root", vec!["admin".to_string()]).await.unwrap();
+        assert!(manager.check_permission("root", "remote_desktop:start").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_password_change_replaces_old_password() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let security = test_security_manager(&data_dir).await;
+        security.initialize().await.unwrap();
+
+        security.create_user("alice", "first-passphrase").await.unwrap();
+        assert!(security.authenticate_password("alice", "first-passphrase").await.is_ok());
+        assert!(security.create_user("alice", "other").await.is_err());
+
+        assert!(security.change_password("alice", "wrong", "second-passphrase").await.is_err());
+        security.change_password("alice", "first-passphrase", "second-passphrase").await.unwrap();
+
+        assert!(security.authenticate_password("alice", "first-passphrase").await.is_err());
+        assert!(security.authenticate_password("alice", "second-passphrase").await.is_ok());
+
+        // The change survives a restart
+        let reloaded = AuthManager::new(30, true).await.unwrap();
+        reloaded.load_credentials(data_dir.path().to_str().unwrap()).await.unwrap();
+        assert!(reloaded.authenticate_password("alice", "second-passphrase").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_password_change_clears_lockout() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let auth = AuthManager::new(30, true).await.unwrap();
+        auth.load_credentials(data_dir.path().to_str().unwrap()).await.unwrap();
+        auth.create_user("bob", "correct horse").await.unwrap();
+
+        for _ in 0..5 {
+            assert!(auth.authenticate_password("bob", "battery staple").await.is_err());
+        }
+
+        // Locked out: even the right password is refused, and so is changing it
+        assert!(auth.user_credentials.read().await["bob"].locked_until.is_some());
+        assert!(auth.authenticate_password("bob", "correct horse").await.is_err());
+        assert!(auth.change_password("bob", "correct horse", "battery staple").await.is_err());
+
+        // Once the lockout has run out, a change clears it along with the failure count
+        auth.user_credentials.write().await.get_mut("bob").unwrap().locked_until =
+            Some(chrono::Utc::now() - chrono::Duration::seconds(1));
+        auth.change_password("bob", "correct horse", "battery staple").await.unwrap();
+
+        let credentials = auth.user_credentials.read().await;
+        assert_eq!(credentials["bob"].failed_attempts, 0);
+        assert!(credentials["bob"].locked_until.is_none());
+        drop(credentials);
+        assert!(auth.authenticate_password("bob", "battery staple").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_biometric_templates_are_stored_keyed() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let security = test_security_manager(&data_dir).await;
+        security.initialize().await.unwrap();
+        security.create_user("carol", "correct horse").await.unwrap();
+
+        let template = b"carol's fingerprint minutiae";
+        security.enroll_biometric("carol", BiometricType::Fingerprint, template).await.unwrap();
+        assert!(security.authenticate_biometric("carol", BiometricType::Fingerprint, template).await.is_ok());
+        assert!(security
+            .authenticate_biometric("carol", BiometricType::Fingerprint, b"someone else's fingerprint..")
+            .await
+            .is_err());
+
+        // The hash key is kept with the other stored keys, so enrolment survives a restart
+        let restarted = test_security_manager(&data_dir).await;
+        restarted.initialize().await.unwrap();
+        assert!(restarted.authenticate_biometric("carol", BiometricType::Fingerprint, template).await.is_ok());
+
+        let path = data_dir.path().join(CREDENTIALS_FILE);
+        let on_disk = std::fs::read(&path).unwrap();
+        let raw: Vec<String> = template.iter().map(|byte| byte.to_string()).collect();
+        assert!(!String::from_utf8_lossy(&on_disk).contains(&raw.join(",")));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stored_key_survives_restart() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let sealed = {
+            let manager = test_security_manager(&data_dir).await;
+            manager.encrypt_with_stored_key("cloud-sync", b"synced memory").await.unwrap()
+        };
+
+        let restarted = test_security_manager(&data_dir).await;
+        assert_eq!(restarted.decrypt_with_stored_key("cloud-sync", &sealed).await.unwrap(), b"synced memory");
+        assert!(restarted.decrypt_with_stored_key("memory-blobs", &sealed).await.is_err());
+        assert!(restarted.export_stored_key("../escape").await.is_err());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(data_dir.path().join("keys/cloud-sync.key")).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_device_key_exchange_agrees_and_survives_restart() {
+        let laptop_dir = tempfile::tempdir().unwrap();
+        let phone_dir = tempfile::tempdir().unwrap();
+        let laptop = test_security_manager(&laptop_dir).await;
+        let phone = test_security_manager(&phone_dir).await;
+
+        let laptop_exchange = laptop.start_device_key_exchange().unwrap();
+        let phone_exchange = phone.start_device_key_exchange().unwrap();
+        let laptop_public = laptop_exchange.public_key().to_vec();
+        let phone_public = phone_exchange.public_key().to_vec();
+        laptop.complete_device_key_exchange("phone", laptop_exchange, &phone_public).await.unwrap();
+        phone.complete_device_key_exchange("laptop", phone_exchange, &laptop_public).await.unwrap();
+
+        let sealed = laptop.encrypt_for_device("phone", b"hello phone").await.unwrap();
+        assert_eq!(phone.decrypt_from_device("laptop", &sealed).await.unwrap(), b"hello phone");
+
+        // The key file on disk is sealed, and a restart reads it back
+        let on_disk = std::fs::read(laptop_dir.path().join("keys").join(DEVICE_KEYS_FILE)).unwrap();
+        assert!(serde_json::from_slice::<HashMap<String, [u8; 32]>>(&on_disk).is_err());
+        drop(laptop);
+        let restarted = test_security_manager(&laptop_dir).await;
+        assert!(restarted.has_device_key("phone").await);
+        let reply = phone.encrypt_for_device("laptop", b"hello laptop").await.unwrap();
+        assert_eq!(restarted.decrypt_from_device("phone", &reply).await.unwrap(), b"hello laptop");
+
+        // A bogus public key is refused rather than producing a key
+        let exchange = restarted.start_device_key_exchange().unwrap();
+        assert!(restarted.complete_device_key_exchange("tablet", exchange, &[0u8; 5]).await.is_err());
+        assert!(!restarted.has_device_key("tablet").await);
+    }
+}
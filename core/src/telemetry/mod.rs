@@ -0,0 +1,396 @@
+//! Opt-in Telemetry
+//!
+//! Anonymized usage statistics, only ever collected with analytics consent:
+//! - Aggregate counters of feature invocations and error classes, never content
+//! - Counts are noised by the anonymization engine before they leave the device
+//! - Counters are buffered locally and flushed to the configured endpoint on an interval
+//! - A kill switch stops collection and purges the buffer at once, and stays thrown across restarts
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::{info, warn, debug};
+
+use crate::kernel::TelemetryConfig;
+use crate::privacy::{AnonymizationMethod, ConsentType, DataType, PrivacyControls};
+use crate::scheduler::Scheduler;
+use crate::errors::{MisaError, Result as MisaResult};
+
+/// Background job sending buffered counters
+const FLUSH_JOB: &str = "telemetry.flush";
+
+/// File in the data directory whose presence means the kill switch was thrown
+const KILL_SWITCH_FILE: &str = "telemetry_disabled";
+
+/// What a telemetry counter counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CounterKind {
+    Feature,
+    Error,
+}
+
+/// One anonymized counter as sent to the telemetry endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TelemetryCounter {
+    pub kind: CounterKind,
+    /// Feature name or error class, never user content
+    pub name: String,
+    /// Occurrences since the last flush, with noise added
+    pub count: u64,
+}
+
+/// Counters sent in one flush
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryBatch {
+    pub version: String,
+    pub counters: Vec<TelemetryCounter>,
+}
+
+/// Collects anonymized usage counters for one user, if they opted in
+pub struct TelemetryManager {
+    user_id: Option<String>,
+    endpoint: Option<String>,
+    api_key: Option<String>,
+    flush_interval: Duration,
+    privacy_controls: PrivacyControls,
+    buffer: Arc<RwLock<HashMap<(CounterKind, String), u64>>>,
+    /// Set by the kill switch; nothing is collected or sent afterwards
+    killed: Arc<AtomicBool>,
+    kill_switch_path: PathBuf,
+    scheduler: Scheduler,
+    client: reqwest::Client,
+}
+
+impl TelemetryManager {
+    /// Create a telemetry manager; it collects nothing without an endpoint and a user,
+    /// or once the kill switch has been thrown for `data_dir`
+    pub async fn new(config: &TelemetryConfig, data_dir: &str, privacy_controls: PrivacyControls) -> MisaResult<Self> {
+        let kill_switch_path = Path::new(data_dir).join(KILL_SWITCH_FILE);
+        let killed = tokio::fs::try_exists(&kill_switch_path).await?;
+        if killed {
+            info!("Telemetry kill switch is set, nothing will be collected");
+        }
+
+        Ok(Self {
+            user_id: config.user_id.clone(),
+            endpoint: config.endpoint.as_ref().map(|endpoint| endpoint.trim_end_matches('/').to_string()),
+            api_key: config.api_key.clone(),
+            flush_interval: Duration::from_secs(config.flush_interval_seconds.max(1)),
+            privacy_controls,
+            buffer: Arc::new(RwLock::new(HashMap::new())),
+            killed: Arc::new(AtomicBool::new(killed)),
+            kill_switch_path,
+            scheduler: Scheduler::new(),
+            client: reqwest::Client::new(),
+        })
+    }
+
+    /// Run the flush job on a scheduler shared with other managers
+    pub fn with_scheduler(mut self, scheduler: Scheduler) -> Self {
+        self.scheduler = scheduler;
+        self
+    }
+
+    /// Whether there is an endpoint and a user, and the kill switch hasn't been thrown
+    pub fn is_active(&self) -> bool {
+        self.endpoint.is_some() && self.user_id.is_some() && !self.killed.load(Ordering::SeqCst)
+    }
+
+    /// Start flushing counters on the configured interval
+    pub async fn start(&self) -> MisaResult<()> {
+        if !self.is_active() {
+            return Ok(());
+        }
+
+        let telemetry = self.clone();
+        self.scheduler
+            .register(FLUSH_JOB, self.flush_interval, move || {
+                let telemetry = telemetry.clone();
+                async move {
+                    telemetry.flush().await?;
+                    Ok(())
+                }
+            })
+            .await
+    }
+
+    /// Count one use of a feature, returning whether it was collected
+    ///
+    /// Feature names are compile-time constants so user content can't end up in one.
+    pub async fn record_feature(&self, feature: &'static str) -> MisaResult<bool> {
+        self.record(CounterKind::Feature, feature.to_string()).await
+    }
+
+    /// Count one error by its class alone; the message is never looked at
+    pub async fn record_error(&self, error: &MisaError) -> MisaResult<bool> {
+        self.record(CounterKind::Error, error_class(error)).await
+    }
+
+    /// Counters waiting for the next flush, before anonymization
+    pub async fn buffered(&self) -> Vec<TelemetryCounter> {
+        let buffer = self.buffer.read().await;
+        let mut counters: Vec<TelemetryCounter> = buffer
+            .iter()
+            .map(|((kind, name), count)| TelemetryCounter { kind: *kind, name: name.clone(), count: *count })
+            .collect();
+        counters.sort_by(|a, b| a.name.cmp(&b.name));
+        counters
+    }
+
+    /// Send buffered counters now, returning how many were sent
+    pub async fn flush(&self) -> MisaResult<usize> {
+        let Some(endpoint) = self.endpoint.as_deref() else {
+            return Ok(0);
+        };
+        let counters = std::mem::take(&mut *self.buffer.write().await);
+        if counters.is_empty() || self.killed.load(Ordering::SeqCst) {
+            return Ok(0);
+        }
+
+        // Counters gathered before consent was withdrawn are dropped, not sent
+        if !self.has_consent().await? {
+            debug!("Analytics consent withdrawn, dropping {} telemetry counters", counters.len());
+            return Ok(0);
+        }
+
+        let batch = TelemetryBatch {
+            version: crate::VERSION.to_string(),
+            counters: self.anonymize(&counters).await?,
+        };
+
+        let mut request = self.client.post(format!("{}/telemetry", endpoint)).json(&batch);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let sent = async { request.send().await?.error_for_status() }.await;
+
+        if let Err(e) = sent {
+            self.restore(counters).await;
+            return Err(e.into());
+        }
+
+        debug!("Sent {} telemetry counters", batch.counters.len());
+        Ok(batch.counters.len())
+    }
+
+    /// Stop collecting and sending at once and purge everything buffered.
+    /// The switch is recorded in the data directory so a restart doesn't undo it.
+    pub async fn kill_switch(&self) -> MisaResult<()> {
+        self.killed.store(true, Ordering::SeqCst);
+        self.scheduler.cancel(FLUSH_JOB).await;
+        let purged = {
+            let mut buffer = self.buffer.write().await;
+            let purged = buffer.len();
+            buffer.clear();
+            purged
+        };
+
+        info!("Telemetry disabled, purged {} buffered counters", purged);
+        crate::util::write_file_atomic(&self.kill_switch_path, chrono::Utc::now().to_rfc3339().as_bytes()).await
+    }
+
+    /// Stop the flush job, sending whatever is still buffered
+    pub async fn shutdown(&self) -> MisaResult<()> {
+        self.scheduler.cancel(FLUSH_JOB).await;
+        if let Err(e) = self.flush().await {
+            warn!("Failed to send final telemetry: {}", e);
+        }
+        Ok(())
+    }
+
+    async fn record(&self, kind: CounterKind, name: String) -> MisaResult<bool> {
+        if !self.is_active() || !self.has_consent().await? {
+            return Ok(false);
+        }
+
+        let mut buffer = self.buffer.write().await;
+        // The kill switch may have purged the buffer while consent was checked
+        if self.killed.load(Ordering::SeqCst) {
+            return Ok(false);
+        }
+        *buffer.entry((kind, name)).or_insert(0) += 1;
+        Ok(true)
+    }
+
+    async fn has_consent(&self) -> MisaResult<bool> {
+        match &self.user_id {
+            Some(user_id) => self.privacy_controls.has_consent(user_id, ConsentType::Analytics).await,
+            None => Ok(false),
+        }
+    }
+
+    /// Add noise to every count so exact usage can't be read back from a batch
+    async fn anonymize(&self, counters: &HashMap<(CounterKind, String), u64>) -> MisaResult<Vec<TelemetryCounter>> {
+        let mut anonymized = Vec::with_capacity(counters.len());
+        for ((kind, name), count) in counters {
+            let noised = self
+                .privacy_controls
+                .anonymize_data(&count.to_string(), DataType::UsageData, AnonymizationMethod::AddNoise)
+                .await?;
+            let count = noised.parse::<f64>().map(|value| value.round().max(0.0) as u64).unwrap_or(0);
+            anonymized.push(TelemetryCounter { kind: *kind, name: name.clone(), count });
+        }
+        anonymized.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(anonymized)
+    }
+
+    /// Put counters back after a failed send, unless telemetry was killed meanwhile
+    async fn restore(&self, counters: HashMap<(CounterKind, String), u64>) {
+        let mut buffer = self.buffer.write().await;
+        if self.killed.load(Ordering::SeqCst) {
+            return;
+        }
+        for (key, count) in counters {
+            *buffer.entry(key).or_insert(0) += count;
+        }
+    }
+}
+
+/// Variant name of an error, e.g. `Memory` for `MisaError::Memory(..)`
+fn error_class(error: &MisaError) -> String {
+    format!("{:?}", error)
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect()
+}
+
+impl Clone for TelemetryManager {
+    fn clone(&self) -> Self {
+        Self {
+            user_id: self.user_id.clone(),
+            endpoint: self.endpoint.clone(),
+            api_key: self.api_key.clone(),
+            flush_interval: self.flush_interval,
+            privacy_controls: self.privacy_controls.clone(),
+            buffer: Arc::clone(&self.buffer),
+            killed: Arc::clone(&self.killed),
+            kill_switch_path: self.kill_switch_path.clone(),
+            scheduler: self.scheduler.clone(),
+            client: self.client.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel::SecurityConfig;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn test_telemetry(data_dir: &tempfile::TempDir, server: &MockServer) -> (TelemetryManager, PrivacyControls) {
+        let privacy_controls = PrivacyControls::new(SecurityConfig::default(), data_dir.path().to_str().unwrap())
+            .await
+            .unwrap();
+        let config = TelemetryConfig {
+            endpoint: Some(server.uri()),
+            api_key: Some("telemetry-key".to_string()),
+            user_id: Some("user-1".to_string()),
+            ..TelemetryConfig::default()
+        };
+        let telemetry = TelemetryManager::new(&config, data_dir.path().to_str().unwrap(), privacy_controls.clone())
+            .await
+            .unwrap();
+        (telemetry, privacy_controls)
+    }
+
+    #[tokio::test]
+    async fn test_nothing_is_collected_without_consent() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/telemetry"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+        let data_dir = tempfile::tempdir().unwrap();
+        let (telemetry, _) = test_telemetry(&data_dir, &server).await;
+
+        assert!(!telemetry.record_feature("memory.search").await.unwrap());
+        assert!(!telemetry.record_error(&MisaError::Memory("disk full".to_string())).await.unwrap());
+        assert!(telemetry.buffered().await.is_empty());
+        assert_eq!(telemetry.flush().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_sent_counters_carry_no_content() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/telemetry"))
+            .and(header("authorization", "Bearer telemetry-key"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let data_dir = tempfile::tempdir().unwrap();
+        let (telemetry, privacy_controls) = test_telemetry(&data_dir, &server).await;
+        privacy_controls.insert_test_consent("user-1", ConsentType::Analytics).await;
+
+        for _ in 0..3 {
+            assert!(telemetry.record_feature("memory.search").await.unwrap());
+        }
+        let secret = "Meet Alice at 42 Elm Street";
+        telemetry.record_error(&MisaError::Memory(secret.to_string())).await.unwrap();
+
+        assert_eq!(telemetry.flush().await.unwrap(), 2);
+        let requests = server.received_requests().await.unwrap();
+        let body = String::from_utf8(requests[0].body.clone()).unwrap();
+        assert!(!body.contains("Alice") && !body.contains("Elm Street"), "{}", body);
+
+        let batch: TelemetryBatch = serde_json::from_str(&body).unwrap();
+        let names: Vec<(CounterKind, &str)> = batch.counters.iter().map(|c| (c.kind, c.name.as_str())).collect();
+        assert_eq!(names, vec![(CounterKind::Error, "Memory"), (CounterKind::Feature, "memory.search")]);
+        assert!(telemetry.buffered().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_kill_switch_purges_and_stops_collection() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/telemetry"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+        let data_dir = tempfile::tempdir().unwrap();
+        let (telemetry, privacy_controls) = test_telemetry(&data_dir, &server).await;
+        privacy_controls.insert_test_consent("user-1", ConsentType::Analytics).await;
+        telemetry.start().await.unwrap();
+
+        telemetry.record_feature("route_task").await.unwrap();
+        telemetry.kill_switch().await.unwrap();
+
+        assert!(telemetry.buffered().await.is_empty());
+        assert!(!telemetry.record_feature("route_task").await.unwrap());
+        assert_eq!(telemetry.flush().await.unwrap(), 0);
+        assert!(!telemetry.is_active());
+    }
+
+    #[tokio::test]
+    async fn test_kill_switch_survives_restart() {
+        let server = MockServer::start().await;
+        let data_dir = tempfile::tempdir().unwrap();
+        let (telemetry, privacy_controls) = test_telemetry(&data_dir, &server).await;
+        privacy_controls.insert_test_consent("user-1", ConsentType::Analytics).await;
+        assert!(telemetry.is_active());
+        telemetry.kill_switch().await.unwrap();
+
+        let (restarted, privacy_controls) = test_telemetry(&data_dir, &server).await;
+        privacy_controls.insert_test_consent("user-1", ConsentType::Analytics).await;
+        assert!(!restarted.is_active());
+        assert!(!restarted.record_feature("route_task").await.unwrap());
+    }
+
+    #[test]
+    fn test_partial_config_takes_defaults() {
+        let config: TelemetryConfig = toml::from_str(r#"endpoint = "https://telemetry.example.com""#).unwrap();
+        assert_eq!(config.endpoint.as_deref(), Some("https://telemetry.example.com"));
+        assert!(config.api_key.is_none() && config.user_id.is_none());
+        assert_eq!(config.flush_interval_seconds, TelemetryConfig::default().flush_interval_seconds);
+    }
+}
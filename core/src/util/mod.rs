@@ -0,0 +1,44 @@
+//! Shared File Helpers
+//!
+//! Small persistence helpers the managers use for their JSON stores and blobs.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::errors::Result as MisaResult;
+
+/// Read a JSON map written by `write_json_atomic`, starting empty when the file is missing
+pub(crate) async fn read_json_map<T: serde::de::DeserializeOwned>(path: &Path) -> MisaResult<HashMap<String, T>> {
+    match tokio::fs::read(path).await {
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Write `value` to a temporary file and rename it over `path`, so a crash
+/// mid-write leaves the previous contents intact
+pub(crate) async fn write_json_atomic<T: Serialize>(path: &Path, value: &T) -> MisaResult<()> {
+    write_file_atomic(path, &serde_json::to_vec_pretty(value)?).await
+}
+
+/// Write `bytes` to a temporary file and rename it over `path`
+pub(crate) async fn write_file_atomic(path: &Path, bytes: &[u8]) -> MisaResult<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+
+    let mut file = tokio::fs::File::create(&tmp_path).await?;
+    tokio::io::AsyncWriteExt::write_all(&mut file, bytes).await?;
+    file.sync_all().await?;
+    drop(file);
+
+    tokio::fs::rename(&tmp_path, path).await?;
+    Ok(())
+}
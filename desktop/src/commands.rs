@@ -3,7 +3,7 @@
 
 use tauri::{State, Window};
 use serde::{Deserialize, Serialize};
-use crate::{MisaAppState, AppResult, AppError};
+use crate::{MisaAppState, AppResult, AppError, AppErrorPayload};
 use crate::config::Config;
 use crate::device::DeviceInfo;
 use crate::file::{FileNode, FileUploadParams, FileSearchParams};
@@ -18,13 +18,13 @@ use crate::ai::{AIRequest, AIResponse, AIRecommendationType};
 
 /// Get application information
 #[tauri::command]
-pub async fn get_app_info() -> Result<crate::AppInfo, String> {
+pub async fn get_app_info() -> Result<crate::AppInfo, AppErrorPayload> {
     Ok(crate::AppInfo::default())
 }
 
 /// Get current configuration
 #[tauri::command]
-pub async fn get_config(state: State<'_, MisaAppState>) -> Result<Config, String> {
+pub async fn get_config(state: State<'_, MisaAppState>) -> Result<Config, AppErrorPayload> {
     Ok(state.get_config())
 }
 
@@ -33,8 +33,8 @@ pub async fn get_config(state: State<'_, MisaAppState>) -> Result<Config, String
 pub async fn update_config(
     config: Config,
     state: State<'_, MisaAppState>
-) -> Result<(), String> {
-    state.update_config(config).await.map_err(|e| e.to_string())
+) -> Result<(), AppErrorPayload> {
+    state.update_config(config).await.map_err(|e| AppErrorPayload::classify(e, AppError::Config))
 }
 
 // =============================================================================
@@ -45,27 +45,27 @@ pub async fn update_config(
 #[tauri::command]
 pub async fn start_device_discovery(
     state: State<'_, MisaAppState>
-) -> Result<(), String> {
+) -> Result<(), AppErrorPayload> {
     state.device_manager.start_discovery().await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::Device))
 }
 
 /// Stop device discovery
 #[tauri::command]
 pub async fn stop_device_discovery(
     state: State<'_, MisaAppState>
-) -> Result<(), String> {
+) -> Result<(), AppErrorPayload> {
     state.device_manager.stop_discovery().await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::Device))
 }
 
 /// Get connected devices
 #[tauri::command]
 pub async fn get_connected_devices(
     state: State<'_, MisaAppState>
-) -> Result<Vec<DeviceInfo>, String> {
+) -> Result<Vec<DeviceInfo>, AppErrorPayload> {
     state.device_manager.get_connected_devices().await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::Device))
 }
 
 /// Send message to device
@@ -74,9 +74,9 @@ pub async fn send_message_to_device(
     device_id: String,
     message: String,
     state: State<'_, MisaAppState>
-) -> Result<(), String> {
+) -> Result<(), AppErrorPayload> {
     state.device_manager.send_message(device_id, message).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::Device))
 }
 
 /// Connect to device
@@ -84,9 +84,9 @@ pub async fn send_message_to_device(
 pub async fn connect_to_device(
     device_id: String,
     state: State<'_, MisaAppState>
-) -> Result<(), String> {
+) -> Result<(), AppErrorPayload> {
     state.device_manager.connect_to_device(device_id).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::Device))
 }
 
 /// Disconnect from device
@@ -94,9 +94,9 @@ pub async fn connect_to_device(
 pub async fn disconnect_from_device(
     device_id: String,
     state: State<'_, MisaAppState>
-) -> Result<(), String> {
+) -> Result<(), AppErrorPayload> {
     state.device_manager.disconnect_from_device(device_id).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::Device))
 }
 
 // =============================================================================
@@ -108,9 +108,9 @@ pub async fn disconnect_from_device(
 pub async fn capture_screen(
     params: ScreenCaptureParams,
     state: State<'_, MisaAppState>
-) -> Result<String, String> { // Returns capture ID
+) -> Result<String, AppErrorPayload> { // Returns capture ID
     let capture_id = state.vision_manager.capture_screen(params).await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| AppErrorPayload::classify(e, AppError::Vision))?;
     Ok(capture_id)
 }
 
@@ -119,9 +119,9 @@ pub async fn capture_screen(
 pub async fn detect_ui_elements(
     capture_id: String,
     state: State<'_, MisaAppState>
-) -> Result<Vec<UIElement>, String> {
+) -> Result<Vec<UIElement>, AppErrorPayload> {
     state.vision_manager.detect_ui_elements(capture_id).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::Vision))
 }
 
 /// Extract text from captured screen
@@ -129,9 +129,9 @@ pub async fn detect_ui_elements(
 pub async fn extract_text_from_image(
     capture_id: String,
     state: State<'_, MisaAppState>
-) -> Result<Vec<TextRegion>, String> {
+) -> Result<Vec<TextRegion>, AppErrorPayload> {
     state.vision_manager.extract_text(capture_id).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::Vision))
 }
 
 /// Get capture thumbnail
@@ -139,9 +139,9 @@ pub async fn extract_text_from_image(
 pub async fn get_capture_thumbnail(
     capture_id: String,
     state: State<'_, MisaAppState>
-) -> Result<String, String> { // Returns base64 image
+) -> Result<String, AppErrorPayload> { // Returns base64 image
     state.vision_manager.get_thumbnail(capture_id).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::Vision))
 }
 
 /// Perform intelligent screenshot analysis
@@ -149,9 +149,9 @@ pub async fn get_capture_thumbnail(
 pub async fn intelligent_screenshot(
     params: ScreenCaptureParams,
     state: State<'_, MisaAppState>
-) -> Result<crate::vision::IntelligentScreenshot, String> {
+) -> Result<crate::vision::IntelligentScreenshot, AppErrorPayload> {
     state.vision_manager.intelligent_screenshot(params).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::Vision))
 }
 
 // =============================================================================
@@ -164,10 +164,10 @@ pub async fn list_files(
     parent_id: Option<String>,
     search_params: Option<FileSearchParams>,
     state: State<'_, MisaAppState>
-) -> Result<Vec<FileNode>, String> {
+) -> Result<Vec<FileNode>, AppErrorPayload> {
     let params = search_params.unwrap_or_default();
     state.file_manager.list_files(parent_id, params).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::File))
 }
 
 /// Upload file
@@ -175,9 +175,9 @@ pub async fn list_files(
 pub async fn upload_file(
     params: FileUploadParams,
     state: State<'_, MisaAppState>
-) -> Result<String, String> { // Returns file ID
+) -> Result<String, AppErrorPayload> { // Returns file ID
     let file_id = state.file_manager.upload_file(params).await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| AppErrorPayload::classify(e, AppError::File))?;
     Ok(file_id)
 }
 
@@ -187,9 +187,9 @@ pub async fn download_file(
     file_id: String,
     local_path: String,
     state: State<'_, MisaAppState>
-) -> Result<(), String> {
+) -> Result<(), AppErrorPayload> {
     state.file_manager.download_file(file_id, local_path).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::File))
 }
 
 /// Create folder
@@ -198,9 +198,9 @@ pub async fn create_folder(
     name: String,
     parent_id: Option<String>,
     state: State<'_, MisaAppState>
-) -> Result<String, String> { // Returns folder ID
+) -> Result<String, AppErrorPayload> { // Returns folder ID
     let folder_id = state.file_manager.create_folder(name, parent_id).await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| AppErrorPayload::classify(e, AppError::File))?;
     Ok(folder_id)
 }
 
@@ -210,9 +210,9 @@ pub async fn delete_file(
     file_id: String,
     permanent: bool,
     state: State<'_, MisaAppState>
-) -> Result<(), String> {
+) -> Result<(), AppErrorPayload> {
     state.file_manager.delete_file(file_id, permanent).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::File))
 }
 
 /// Move file
@@ -221,9 +221,9 @@ pub async fn move_file(
     file_id: String,
     new_parent_id: String,
     state: State<'_, MisaAppState>
-) -> Result<(), String> {
+) -> Result<(), AppErrorPayload> {
     state.file_manager.move_file(file_id, new_parent_id).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::File))
 }
 
 /// Copy file
@@ -233,9 +233,9 @@ pub async fn copy_file(
     new_parent_id: String,
     new_name: Option<String>,
     state: State<'_, MisaAppState>
-) -> Result<String, String> { // Returns new file ID
+) -> Result<String, AppErrorPayload> { // Returns new file ID
     let new_file_id = state.file_manager.copy_file(file_id, new_parent_id, new_name).await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| AppErrorPayload::classify(e, AppError::File))?;
     Ok(new_file_id)
 }
 
@@ -245,9 +245,9 @@ pub async fn rename_file(
     file_id: String,
     new_name: String,
     state: State<'_, MisaAppState>
-) -> Result<(), String> {
+) -> Result<(), AppErrorPayload> {
     state.file_manager.rename_file(file_id, new_name).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::File))
 }
 
 /// Search files
@@ -256,11 +256,11 @@ pub async fn search_files(
     query: String,
     filters: Option<FileSearchParams>,
     state: State<'_, MisaAppState>
-) -> Result<Vec<FileNode>, String> {
+) -> Result<Vec<FileNode>, AppErrorPayload> {
     let mut params = filters.unwrap_or_default();
     params.query = Some(query);
     state.file_manager.search_files(params).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::File))
 }
 
 /// Get file metadata
@@ -268,9 +268,9 @@ pub async fn search_files(
 pub async fn get_file_metadata(
     file_id: String,
     state: State<'_, MisaAppState>
-) -> Result<crate::file::FileMetadata, String> {
+) -> Result<crate::file::FileMetadata, AppErrorPayload> {
     state.file_manager.get_file_metadata(file_id).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::File))
 }
 
 /// Update file metadata
@@ -279,9 +279,9 @@ pub async fn update_file_metadata(
     file_id: String,
     metadata: crate::file::FileMetadataUpdate,
     state: State<'_, MisaAppState>
-) -> Result<(), String> {
+) -> Result<(), AppErrorPayload> {
     state.file_manager.update_file_metadata(file_id, metadata).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::File))
 }
 
 /// Share file
@@ -290,9 +290,9 @@ pub async fn share_file(
     file_id: String,
     share_params: crate::file::ShareParams,
     state: State<'_, MisaAppState>
-) -> Result<String, String> { // Returns share ID
+) -> Result<String, AppErrorPayload> { // Returns share ID
     let share_id = state.file_manager.share_file(file_id, share_params).await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| AppErrorPayload::classify(e, AppError::File))?;
     Ok(share_id)
 }
 
@@ -305,9 +305,10 @@ pub async fn share_file(
 pub async fn start_focus_session(
     params: FocusSessionParams,
     state: State<'_, MisaAppState>
-) -> Result<String, String> { // Returns session ID
+) -> Result<String, AppErrorPayload> { // Returns session ID
+    state.require_database()?;
     let session_id = state.focus_manager.start_session(params).await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| AppErrorPayload::classify(e, AppError::Focus))?;
     Ok(session_id)
 }
 
@@ -316,9 +317,10 @@ pub async fn start_focus_session(
 pub async fn stop_focus_session(
     session_id: String,
     state: State<'_, MisaAppState>
-) -> Result<(), String> {
+) -> Result<(), AppErrorPayload> {
+    state.require_database()?;
     state.focus_manager.stop_session(session_id).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::Focus))
 }
 
 /// Pause focus session
@@ -326,9 +328,10 @@ pub async fn stop_focus_session(
 pub async fn pause_focus_session(
     session_id: String,
     state: State<'_, MisaAppState>
-) -> Result<(), String> {
+) -> Result<(), AppErrorPayload> {
+    state.require_database()?;
     state.focus_manager.pause_session(session_id).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::Focus))
 }
 
 /// Resume focus session
@@ -336,18 +339,20 @@ pub async fn pause_focus_session(
 pub async fn resume_focus_session(
     session_id: String,
     state: State<'_, MisaAppState>
-) -> Result<(), String> {
+) -> Result<(), AppErrorPayload> {
+    state.require_database()?;
     state.focus_manager.resume_session(session_id).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::Focus))
 }
 
 /// Get current focus session
 #[tauri::command]
 pub async fn get_current_focus_session(
     state: State<'_, MisaAppState>
-) -> Result<Option<FocusSession>, String> {
+) -> Result<Option<FocusSession>, AppErrorPayload> {
+    state.require_database()?;
     state.focus_manager.get_current_session().await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::Focus))
 }
 
 /// Get focus session by ID
@@ -355,9 +360,10 @@ pub async fn get_current_focus_session(
 pub async fn get_focus_session(
     session_id: String,
     state: State<'_, MisaAppState>
-) -> Result<Option<FocusSession>, String> {
+) -> Result<Option<FocusSession>, AppErrorPayload> {
+    state.require_database()?;
     state.focus_manager.get_session(session_id).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::Focus))
 }
 
 /// Get focus statistics
@@ -365,10 +371,11 @@ pub async fn get_focus_session(
 pub async fn get_focus_stats(
     period: Option<String>, // "day", "week", "month", "year"
     state: State<'_, MisaAppState>
-) -> Result<FocusStats, String> {
+) -> Result<FocusStats, AppErrorPayload> {
+    state.require_database()?;
     let period = period.unwrap_or_else(|| "week".to_string());
     state.focus_manager.get_stats(&period).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::Focus))
 }
 
 /// Get focus session history
@@ -377,11 +384,12 @@ pub async fn get_focus_session_history(
     limit: Option<u32>,
     offset: Option<u32>,
     state: State<'_, MisaAppState>
-) -> Result<Vec<FocusSession>, String> {
+) -> Result<Vec<FocusSession>, AppErrorPayload> {
+    state.require_database()?;
     let limit = limit.unwrap_or(50);
     let offset = offset.unwrap_or(0);
     state.focus_manager.get_session_history(limit, offset).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::Focus))
 }
 
 /// Add interruption to focus session
@@ -391,11 +399,12 @@ pub async fn add_focus_interruption(
     interruption_type: String,
     reason: String,
     state: State<'_, MisaAppState>
-) -> Result<(), String> {
+) -> Result<(), AppErrorPayload> {
+    state.require_database()?;
     let interruption_type = interruption_type.parse()
-        .map_err(|e| format!("Invalid interruption type: {}", e))?;
+        .map_err(|e| AppErrorPayload::from(AppError::Focus(format!("Invalid interruption type: {}", e))))?;
     state.focus_manager.add_interruption(session_id, interruption_type, reason).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::Focus))
 }
 
 /// Update focus session settings
@@ -404,9 +413,10 @@ pub async fn update_focus_settings(
     session_id: String,
     settings: crate::focus::FocusSessionSettings,
     state: State<'_, MisaAppState>
-) -> Result<(), String> {
+) -> Result<(), AppErrorPayload> {
+    state.require_database()?;
     state.focus_manager.update_session_settings(session_id, settings).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::Focus))
 }
 
 // =============================================================================
@@ -417,9 +427,9 @@ pub async fn update_focus_settings(
 #[tauri::command]
 pub async fn get_system_info(
     state: State<'_, MisaAppState>
-) -> Result<SystemInfo, String> {
+) -> Result<SystemInfo, AppErrorPayload> {
     state.system_manager.get_system_info().await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::System))
 }
 
 /// Set power save mode
@@ -427,9 +437,9 @@ pub async fn get_system_info(
 pub async fn set_powersave_mode(
     enabled: bool,
     state: State<'_, MisaAppState>
-) -> Result<(), String> {
+) -> Result<(), AppErrorPayload> {
     state.system_manager.set_powersave_mode(enabled).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::System))
 }
 
 /// Show system notification
@@ -439,36 +449,36 @@ pub async fn show_notification(
     body: String,
     icon: Option<String>,
     window: Window,
-) -> Result<(), String> {
+) -> Result<(), AppErrorPayload> {
     crate::notification::show_notification(&window, &title, &body, icon.as_deref())
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::System))
 }
 
 /// Get battery status
 #[tauri::command]
 pub async fn get_battery_status(
     state: State<'_, MisaAppState>
-) -> Result<crate::system::BatteryStatus, String> {
+) -> Result<crate::system::BatteryStatus, AppErrorPayload> {
     state.system_manager.get_battery_status().await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::System))
 }
 
 /// Get network status
 #[tauri::command]
 pub async fn get_network_status(
     state: State<'_, MisaAppState>
-) -> Result<crate::system::NetworkStatus, String> {
+) -> Result<crate::system::NetworkStatus, AppErrorPayload> {
     state.system_manager.get_network_status().await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::System))
 }
 
 /// Get running processes
 #[tauri::command]
 pub async fn get_running_processes(
     state: State<'_, MisaAppState>
-) -> Result<Vec<crate::system::ProcessInfo>, String> {
+) -> Result<Vec<crate::system::ProcessInfo>, AppErrorPayload> {
     state.system_manager.get_running_processes().await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::System))
 }
 
 /// Kill process
@@ -476,9 +486,9 @@ pub async fn get_running_processes(
 pub async fn kill_process(
     process_id: u32,
     state: State<'_, MisaAppState>
-) -> Result<(), String> {
+) -> Result<(), AppErrorPayload> {
     state.system_manager.kill_process(process_id).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::System))
 }
 
 /// Set system theme
@@ -486,10 +496,10 @@ pub async fn kill_process(
 pub async fn set_system_theme(
     theme: String,
     window: Window,
-) -> Result<(), String> {
+) -> Result<(), AppErrorPayload> {
     let theme = theme.parse()
-        .map_err(|e| format!("Invalid theme: {}", e))?;
-    crate::system::set_theme(&window, theme).map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::from(AppError::System(format!("Invalid theme: {}", e))))?;
+    crate::system::set_theme(&window, theme).map_err(|e| AppErrorPayload::classify(e, AppError::System))
 }
 
 // =============================================================================
@@ -501,9 +511,55 @@ pub async fn set_system_theme(
 pub async fn process_natural_language(
     request: AIRequest,
     state: State<'_, MisaAppState>
-) -> Result<AIResponse, String> {
+) -> Result<AIResponse, AppErrorPayload> {
     state.ai_manager.process_request(request).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::AI))
+}
+
+/// Generate a response through the kernel, streaming tokens back as `AIResponseChunk` events
+#[tauri::command]
+pub async fn stream_natural_language(
+    prompt: String,
+    model: Option<String>,
+    state: State<'_, MisaAppState>
+) -> Result<String, AppErrorPayload> { // Returns request ID
+    use futures_util::StreamExt;
+
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let mut stream = crate::kernel_client::stream_generation(
+        crate::kernel_client::DEFAULT_KERNEL_WS_URL,
+        &prompt,
+        model.as_deref(),
+    )
+    .await?;
+    let event_bus = state.event_bus.clone();
+    let stream_request_id = request_id.clone();
+
+    tokio::spawn(async move {
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(chunk) => {
+                    let _ = event_bus.send(crate::AppEvent::AIResponseChunk {
+                        request_id: stream_request_id.clone(),
+                        chunk,
+                        done: false,
+                    });
+                }
+                Err(e) => {
+                    let _ = event_bus.send(crate::AppEvent::ErrorOccurred(e.to_string()));
+                    break;
+                }
+            }
+        }
+
+        let _ = event_bus.send(crate::AppEvent::AIResponseChunk {
+            request_id: stream_request_id,
+            chunk: String::new(),
+            done: true,
+        });
+    });
+
+    Ok(request_id)
 }
 
 /// Get AI recommendations
@@ -512,9 +568,9 @@ pub async fn get_ai_recommendations(
     recommendation_type: AIRecommendationType,
     context: Option<serde_json::Value>,
     state: State<'_, MisaAppState>
-) -> Result<Vec<crate::ai::AIRecommendation>, String> {
+) -> Result<Vec<crate::ai::AIRecommendation>, AppErrorPayload> {
     state.ai_manager.get_recommendations(recommendation_type, context).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::AI))
 }
 
 /// Generate summary
@@ -523,9 +579,9 @@ pub async fn generate_summary(
     content: String,
     content_type: String,
     state: State<'_, MisaAppState>
-) -> Result<String, String> {
+) -> Result<String, AppErrorPayload> {
     state.ai_manager.generate_summary(content, content_type).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::AI))
 }
 
 /// Analyze sentiment
@@ -533,9 +589,9 @@ pub async fn generate_summary(
 pub async fn analyze_sentiment(
     text: String,
     state: State<'_, MisaAppState>
-) -> Result<crate::ai::SentimentAnalysis, String> {
+) -> Result<crate::ai::SentimentAnalysis, AppErrorPayload> {
     state.ai_manager.analyze_sentiment(text).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::AI))
 }
 
 /// Extract entities from text
@@ -543,9 +599,9 @@ pub async fn analyze_sentiment(
 pub async fn extract_entities(
     text: String,
     state: State<'_, MisaAppState>
-) -> Result<Vec<crate::ai::Entity>, String> {
+) -> Result<Vec<crate::ai::Entity>, AppErrorPayload> {
     state.ai_manager.extract_entities(text).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::AI))
 }
 
 /// Generate task suggestions
@@ -553,9 +609,9 @@ pub async fn extract_entities(
 pub async fn generate_task_suggestions(
     input: String,
     state: State<'_, MisaAppState>
-) -> Result<Vec<crate::ai::TaskSuggestion>, String> {
+) -> Result<Vec<crate::ai::TaskSuggestion>, AppErrorPayload> {
     state.ai_manager.generate_task_suggestions(input).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::AI))
 }
 
 /// Get productivity insights
@@ -563,10 +619,11 @@ pub async fn generate_task_suggestions(
 pub async fn get_productivity_insights(
     period: Option<String>,
     state: State<'_, MisaAppState>
-) -> Result<crate::ai::ProductivityInsights, String> {
+) -> Result<crate::ai::ProductivityInsights, AppErrorPayload> {
+    state.require_database()?;
     let period = period.unwrap_or_else(|| "week".to_string());
     state.ai_manager.get_productivity_insights(&period).await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppErrorPayload::classify(e, AppError::AI))
 }
 
 // =============================================================================
@@ -579,12 +636,21 @@ pub async fn subscribe_to_events(
     event_types: Vec<String>,
     window: Window,
     state: State<'_, MisaAppState>
-) -> Result<(), String> {
-    let mut receiver = state.subscribe_events();
+) -> Result<(), AppErrorPayload> {
+    let mut receiver = state.subscribe_events_lossy();
+    let mut shutdown = state.shutdown_signal();
     let window_clone = window.clone();
 
     tokio::spawn(async move {
-        while let Ok(event) = receiver.recv().await {
+        loop {
+            let event = tokio::select! {
+                event = receiver.recv() => match event {
+                    Some(event) => event,
+                    None => break,
+                },
+                _ = shutdown.changed() => break,
+            };
+
             if should_send_event(&event, &event_types) {
                 let event_json = serde_json::to_string(&event).unwrap_or_default();
                 if let Err(e) = window_clone.emit("app-event", &event_json) {
@@ -618,7 +684,11 @@ fn should_send_event(event: &crate::AppEvent, event_types: &[String]) -> bool {
         crate::AppEvent::UIElementsDetected { .. } => "vision.ui_elements_detected",
         crate::AppEvent::TextExtracted { .. } => "vision.text_extracted",
         crate::AppEvent::AIResponseReceived { .. } => "ai.response_received",
+        crate::AppEvent::AIResponseChunk { .. } => "ai.response_chunk",
         crate::AppEvent::AISummaryGenerated { .. } => "ai.summary_generated",
+        crate::AppEvent::PredictionGenerated { .. } => "ai.prediction_generated",
+        crate::AppEvent::AnomalyDetected { .. } => "ai.anomaly_detected",
+        crate::AppEvent::ReConsentRequired { .. } => "privacy.re_consent_required",
         crate::AppEvent::ConfigUpdated => "config.updated",
         crate::AppEvent::SettingsChanged(_) => "config.settings_changed",
         crate::AppEvent::AppReady => "app.ready",
@@ -0,0 +1,262 @@
+//! Client for the kernel's streaming WebSocket endpoint
+//! Forwards generation requests to the local kernel and yields its token frames,
+//! and relays the predictions and privacy events the kernel pushes to subscribers
+
+use futures_util::stream::BoxStream;
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::{AppError, AppEvent, AppResult};
+
+/// WebSocket endpoint of a kernel started with its default bind address
+pub const DEFAULT_KERNEL_WS_URL: &str = "ws://127.0.0.1:8080/ws";
+
+/// Streaming request understood by the kernel WebSocket
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamRequest<'a> {
+    Generate {
+        prompt: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        model: Option<&'a str>,
+    },
+    SubscribePredictions,
+    SubscribePrivacyEvents,
+    SubscribeAnomalies,
+}
+
+/// Frame the kernel sends back while answering a streaming request
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamFrame {
+    Token { content: String },
+    Done,
+    Error { message: String },
+    Prediction { prediction_type: String, suggestion: String, confidence: f32 },
+    ReConsentRequired { user_id: String, consent_id: String, consent_type: String, expired: bool },
+    Anomaly { anomaly_type: String, severity: String, description: String },
+}
+
+/// Ask the kernel at `url` to generate a response, yielding tokens as they arrive.
+/// The stream ends after the kernel's `done` frame or the first error.
+pub async fn stream_generation(
+    url: &str,
+    prompt: &str,
+    model: Option<&str>,
+) -> AppResult<BoxStream<'static, AppResult<String>>> {
+    let (mut socket, _) = tokio_tungstenite::connect_async(url)
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to connect to kernel at {}: {}", url, e)))?;
+
+    let request = serde_json::to_string(&StreamRequest::Generate { prompt, model })?;
+    socket
+        .send(Message::Text(request))
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to send request to kernel: {}", e)))?;
+
+    Ok(futures_util::stream::unfold(Some(socket), |socket| async move {
+        let mut socket = socket?;
+        loop {
+            let frame = match socket.next().await {
+                Some(Ok(Message::Text(text))) => serde_json::from_str::<StreamFrame>(&text),
+                Some(Ok(Message::Close(_))) | None => {
+                    let error = AppError::Network("Kernel closed the stream before it finished".to_string());
+                    return Some((Err(error), None));
+                }
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => return Some((Err(AppError::Network(e.to_string())), None)),
+            };
+
+            return match frame {
+                Ok(StreamFrame::Token { content }) => Some((Ok(content), Some(socket))),
+                Ok(StreamFrame::Done) => {
+                    let _ = socket.close(None).await;
+                    None
+                }
+                Ok(StreamFrame::Error { message }) => Some((Err(AppError::AI(message)), None)),
+                Ok(StreamFrame::Prediction { .. } | StreamFrame::ReConsentRequired { .. } | StreamFrame::Anomaly { .. }) => continue,
+                Err(e) => Some((Err(e.into()), None)),
+            };
+        }
+    })
+    .boxed())
+}
+
+/// Subscribe to the predictions of the kernel at `url`, yielding each as a
+/// `PredictionGenerated` event. The stream ends when the kernel closes the connection.
+pub async fn subscribe_predictions(url: &str) -> AppResult<BoxStream<'static, AppResult<AppEvent>>> {
+    subscribe(url, StreamRequest::SubscribePredictions, |frame| match frame {
+        StreamFrame::Prediction { prediction_type, suggestion, confidence } => {
+            Some(AppEvent::PredictionGenerated { prediction_type, suggestion, confidence })
+        }
+        _ => None,
+    })
+    .await
+}
+
+/// Subscribe to the privacy events of the kernel at `url`, yielding re-consent prompts
+/// as `ReConsentRequired` events. The stream ends when the kernel closes the connection.
+pub async fn subscribe_privacy_events(url: &str) -> AppResult<BoxStream<'static, AppResult<AppEvent>>> {
+    subscribe(url, StreamRequest::SubscribePrivacyEvents, |frame| match frame {
+        StreamFrame::ReConsentRequired { user_id, consent_id, consent_type, expired } => {
+            Some(AppEvent::ReConsentRequired { user_id, consent_id, consent_type, expired })
+        }
+        _ => None,
+    })
+    .await
+}
+
+/// Subscribe to the anomalies the kernel at `url` finds in recent memories, yielding each
+/// as an `AnomalyDetected` event. The stream ends when the kernel closes the connection.
+pub async fn subscribe_anomalies(url: &str) -> AppResult<BoxStream<'static, AppResult<AppEvent>>> {
+    subscribe(url, StreamRequest::SubscribeAnomalies, |frame| match frame {
+        StreamFrame::Anomaly { anomaly_type, severity, description } => {
+            Some(AppEvent::AnomalyDetected { anomaly_type, severity, description })
+        }
+        _ => None,
+    })
+    .await
+}
+
+/// Send a subscription `request` and yield the events `to_event` makes of the pushed frames
+async fn subscribe(
+    url: &str,
+    request: StreamRequest<'_>,
+    to_event: fn(StreamFrame) -> Option<AppEvent>,
+) -> AppResult<BoxStream<'static, AppResult<AppEvent>>> {
+    let (mut socket, _) = tokio_tungstenite::connect_async(url)
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to connect to kernel at {}: {}", url, e)))?;
+
+    let request = serde_json::to_string(&request)?;
+    socket
+        .send(Message::Text(request))
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to subscribe to kernel events: {}", e)))?;
+
+    Ok(futures_util::stream::unfold(Some(socket), move |socket| async move {
+        let mut socket = socket?;
+        loop {
+            let frame = match socket.next().await {
+                Some(Ok(Message::Text(text))) => serde_json::from_str::<StreamFrame>(&text),
+                Some(Ok(Message::Close(_))) | None => return None,
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => return Some((Err(AppError::Network(e.to_string())), None)),
+            };
+
+            return match frame.map(to_event) {
+                Ok(Some(event)) => Some((Ok(event), Some(socket))),
+                Ok(None) => continue,
+                // A malformed frame is reported without ending the subscription
+                Err(e) => Some((Err(e.into()), Some(socket))),
+            };
+        }
+    })
+    .boxed())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Kernel stand-in that answers one generation request with `frames`
+    async fn serve_frames(frames: Vec<&'static str>) -> String {
+        serve_request(serde_json::json!({ "type": "generate", "prompt": "greet me" }), frames).await
+    }
+
+    /// Kernel stand-in that expects `expected` as its first message and answers with `frames`
+    async fn serve_request(expected: serde_json::Value, frames: Vec<&'static str>) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let request = socket.next().await.unwrap().unwrap();
+            let request: serde_json::Value = serde_json::from_str(request.to_text().unwrap()).unwrap();
+            assert_eq!(request, expected);
+
+            for frame in frames {
+                socket.send(Message::Text(frame.to_string())).await.unwrap();
+            }
+            let _ = socket.close(None).await;
+        });
+
+        format!("ws://{}/ws", addr)
+    }
+
+    #[tokio::test]
+    async fn test_stream_yields_tokens_until_done() {
+        let url = serve_frames(vec![
+            r#"{"type":"token","content":"Hel"}"#,
+            r#"{"type":"token","content":"lo"}"#,
+            r#"{"type":"done"}"#,
+        ])
+        .await;
+
+        let tokens: Vec<AppResult<String>> = stream_generation(&url, "greet me", None).await.unwrap().collect().await;
+        let tokens: Vec<String> = tokens.into_iter().map(Result::unwrap).collect();
+        assert_eq!(tokens, vec!["Hel".to_string(), "lo".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_stream_surfaces_kernel_error() {
+        let url = serve_frames(vec![r#"{"type":"error","message":"model not found"}"#]).await;
+
+        let mut stream = stream_generation(&url, "greet me", None).await.unwrap();
+        assert!(matches!(stream.next().await, Some(Err(AppError::AI(message))) if message == "model not found"));
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_predictions_become_app_events() {
+        let url = serve_request(
+            serde_json::json!({ "type": "subscribe_predictions" }),
+            vec![r#"{"type":"prediction","prediction_type":"time_based","suggestion":"Plan your morning","confidence":0.5}"#],
+        )
+        .await;
+
+        let events: Vec<AppResult<AppEvent>> = subscribe_predictions(&url).await.unwrap().collect().await;
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            &events[0],
+            Ok(AppEvent::PredictionGenerated { prediction_type, suggestion, confidence })
+                if prediction_type == "time_based" && suggestion == "Plan your morning" && *confidence == 0.5
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_re_consent_prompts_become_app_events() {
+        let url = serve_request(
+            serde_json::json!({ "type": "subscribe_privacy_events" }),
+            vec![r#"{"type":"re_consent_required","user_id":"user-1","consent_id":"consent-1","consent_type":"ScreenCapture","expired":true}"#],
+        )
+        .await;
+
+        let events: Vec<AppResult<AppEvent>> = subscribe_privacy_events(&url).await.unwrap().collect().await;
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            &events[0],
+            Ok(AppEvent::ReConsentRequired { consent_id, consent_type, expired: true, .. })
+                if consent_id == "consent-1" && consent_type == "ScreenCapture"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_anomalies_become_app_events() {
+        let url = serve_request(
+            serde_json::json!({ "type": "subscribe_anomalies" }),
+            vec![r#"{"type":"anomaly","anomaly_type":"MemoryVolumeSpike","severity":"High","description":"Unusual memory volume"}"#],
+        )
+        .await;
+
+        let events: Vec<AppResult<AppEvent>> = subscribe_anomalies(&url).await.unwrap().collect().await;
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            &events[0],
+            Ok(AppEvent::AnomalyDetected { anomaly_type, severity, .. })
+                if anomaly_type == "MemoryVolumeSpike" && severity == "High"
+        ));
+    }
+}
@@ -8,16 +8,21 @@ pub mod core;
 pub mod device;
 pub mod file;
 pub mod focus;
+pub mod kernel_client;
 pub mod notification;
 pub mod system;
 pub mod tray;
 pub mod vision;
 pub mod ai;
 
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 use anyhow::Result;
-use parking_lot::RwLock;
-use tokio::sync::broadcast;
+use parking_lot::{Mutex, RwLock};
+use tokio::sync::{broadcast, mpsc, watch};
 
 // Re-export main components
 pub use app::MisaApp;
@@ -30,6 +35,18 @@ pub use system::SystemManager;
 pub use vision::VisionManager;
 pub use ai::AIManager;
 
+/// Events buffered per subscriber before slow subscribers start lagging
+pub const DEFAULT_EVENT_BUS_CAPACITY: usize = 4096;
+
+/// How long shutdown waits for subscribers and shutdown hooks
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Decides from an event's kind whether a filtered subscriber wants it
+pub type EventPredicate = Box<dyn Fn(AppEventKind) -> bool + Send + Sync>;
+
+/// Async cleanup run once during shutdown, e.g. flushing a manager
+pub type ShutdownHook = Box<dyn FnOnce() -> Pin<Box<dyn Future<Output = Result<()>> + Send>> + Send>;
+
 /// Application state shared across Tauri commands
 pub struct MisaAppState {
     pub config_manager: Arc<RwLock<ConfigManager>>,
@@ -41,12 +58,53 @@ pub struct MisaAppState {
     pub vision_manager: Arc<VisionManager>,
     pub ai_manager: Arc<AIManager>,
     pub event_bus: broadcast::Sender<AppEvent>,
+    database: DatabaseStatus,
+    event_capacity: usize,
+    filtered_subscribers: Mutex<Vec<FilteredSubscriber>>,
+    shutdown_tx: watch::Sender<bool>,
+    shutdown_hooks: Mutex<Vec<(String, ShutdownHook)>>,
+}
+
+/// Whether the database opened; without it the app runs with memory features disabled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DatabaseStatus {
+    Available,
+    Degraded { reason: String },
+}
+
+impl DatabaseStatus {
+    /// Status after an attempt to initialize the database
+    pub fn from_init(result: &AppResult<()>) -> Self {
+        match result {
+            Ok(()) => DatabaseStatus::Available,
+            Err(e) => DatabaseStatus::Degraded { reason: e.to_string() },
+        }
+    }
+}
+
+/// Subscriber that is only handed the events its predicate accepts
+struct FilteredSubscriber {
+    predicate: EventPredicate,
+    sender: mpsc::Sender<AppEvent>,
 }
 
 impl MisaAppState {
     /// Create new application state
     pub async fn new() -> Result<Self> {
-        let config_manager = Arc::new(RwLock::new(ConfigManager::new().await?));
+        Self::with_event_capacity(DEFAULT_EVENT_BUS_CAPACITY).await
+    }
+
+    /// Create application state with a custom event bus capacity.
+    /// Subscribers that fall more than `event_capacity` events behind miss the oldest ones.
+    pub async fn with_event_capacity(event_capacity: usize) -> Result<Self> {
+        Self::with_database_at(Path::new(database::DB_FILE), event_capacity).await
+    }
+
+    /// Create application state whose database lives at `database_path`
+    pub async fn with_database_at(database_path: &Path, event_capacity: usize) -> Result<Self> {
+        let config_manager = ConfigManager::new().await?;
+        let db_max_connections = config_manager.get_config().db_max_connections;
+        let config_manager = Arc::new(RwLock::new(config_manager));
         let device_manager = Arc::new(DeviceManager::new().await?);
         let file_manager = Arc::new(FileManager::new().await?);
         let focus_manager = Arc::new(FocusManager::new().await?);
@@ -55,7 +113,14 @@ impl MisaAppState {
         let vision_manager = Arc::new(VisionManager::new().await?);
         let ai_manager = Arc::new(AIManager::new().await?);
 
-        let (event_tx, _) = broadcast::channel(1000);
+        // A locked or broken database shouldn't keep the rest of the app from starting
+        let database = DatabaseStatus::from_init(&database::initialize_at(database_path, db_max_connections).await);
+        if let DatabaseStatus::Degraded { reason } = &database {
+            log::error!("Starting without a database, memory features are disabled: {}", reason);
+        }
+
+        let (event_tx, _) = broadcast::channel(event_capacity);
+        let (shutdown_tx, _) = watch::channel(false);
 
         Ok(Self {
             config_manager,
@@ -67,9 +132,30 @@ impl MisaAppState {
             vision_manager,
             ai_manager,
             event_bus: event_tx,
+            database,
+            event_capacity,
+            filtered_subscribers: Mutex::new(Vec::new()),
+            shutdown_tx,
+            shutdown_hooks: Mutex::new(Vec::new()),
         })
     }
 
+    /// Whether the database opened at startup
+    pub fn database_status(&self) -> &DatabaseStatus {
+        &self.database
+    }
+
+    /// Fail with `AppError::Database` when memory features are disabled for lack of a database
+    pub fn require_database(&self) -> AppResult<()> {
+        match &self.database {
+            DatabaseStatus::Available => Ok(()),
+            DatabaseStatus::Degraded { reason } => Err(AppError::Database(format!(
+                "Memory features are disabled because the database is unavailable: {}",
+                reason
+            ))),
+        }
+    }
+
     /// Get configuration
     pub fn get_config(&self) -> Config {
         self.config_manager.read().get_config()
@@ -82,8 +168,14 @@ impl MisaAppState {
 
     /// Emit event to all subscribers
     pub fn emit_event(&self, event: AppEvent) -> Result<()> {
+        if self.is_shutting_down() {
+            return Err(anyhow::anyhow!("Failed to emit event: application is shutting down"));
+        }
+
+        let delivered_filtered = self.dispatch_filtered(&event);
         match self.event_bus.send(event) {
             Ok(_) => Ok(()),
+            Err(_) if delivered_filtered => Ok(()),
             Err(e) => Err(anyhow::anyhow!("Failed to emit event: {}", e)),
         }
     }
@@ -92,6 +184,230 @@ impl MisaAppState {
     pub fn subscribe_events(&self) -> broadcast::Receiver<AppEvent> {
         self.event_bus.subscribe()
     }
+
+    /// Subscribe to only the events whose kind matches `predicate`.
+    /// Events are checked before being cloned, so rejected ones cost the subscriber nothing;
+    /// a subscriber more than the event bus capacity behind misses the newest events.
+    pub fn subscribe_filtered<P>(&self, predicate: P) -> impl futures_util::Stream<Item = AppEvent>
+    where
+        P: Fn(AppEventKind) -> bool + Send + Sync + 'static,
+    {
+        let (sender, receiver) = mpsc::channel(self.event_capacity);
+        self.filtered_subscribers.lock().push(FilteredSubscriber {
+            predicate: Box::new(predicate),
+            sender,
+        });
+
+        futures_util::stream::unfold(receiver, |mut receiver| async move {
+            receiver.recv().await.map(|event| (event, receiver))
+        })
+    }
+
+    /// Hand `event` to each filtered subscriber that wants it, dropping closed subscribers.
+    /// Returns whether any subscriber took it.
+    fn dispatch_filtered(&self, event: &AppEvent) -> bool {
+        let kind = event.kind();
+        let mut delivered = false;
+
+        self.filtered_subscribers.lock().retain(|subscriber| {
+            if subscriber.sender.is_closed() {
+                return false;
+            }
+            if !(subscriber.predicate)(kind) {
+                return true;
+            }
+
+            match subscriber.sender.try_send(event.clone()) {
+                Ok(()) => delivered = true,
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    log::warn!("Filtered event subscriber is full, dropped {:?} event", kind);
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => return false,
+            }
+            true
+        });
+
+        delivered
+    }
+
+    /// Watch for shutdown; background tasks should stop once this turns `true`
+    pub fn shutdown_signal(&self) -> watch::Receiver<bool> {
+        self.shutdown_tx.subscribe()
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        *self.shutdown_tx.borrow()
+    }
+
+    /// Register cleanup to run during shutdown, such as flushing audit logs or a final cloud sync
+    pub fn register_shutdown_hook<F, Fut>(&self, name: &str, hook: F)
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.shutdown_hooks
+            .lock()
+            .push((name.to_string(), Box::new(move || Box::pin(hook()))));
+    }
+
+    /// Register the hooks that stop the app's background work and flush its state on exit
+    pub fn register_default_shutdown_hooks(&self) {
+        let device_manager = Arc::clone(&self.device_manager);
+        self.register_shutdown_hook("device_discovery", move || async move {
+            device_manager.stop_discovery().await.map_err(Into::into)
+        });
+
+        self.register_shutdown_hook("database", || async {
+            // Closing waits for in-flight queries and checkpoints the WAL
+            if let Some(pool) = database::get_pool() {
+                let pool = pool.read().clone();
+                pool.close().await;
+            }
+            Ok(())
+        });
+    }
+
+    /// Shut down with the default timeout
+    pub async fn shutdown(&self) -> Result<()> {
+        self.shutdown_with_timeout(DEFAULT_SHUTDOWN_TIMEOUT).await
+    }
+
+    /// Announce shutdown, stop background tasks, let subscribers drain the event bus and
+    /// run the shutdown hooks. Returns once everything finished or `timeout` elapsed.
+    pub async fn shutdown_with_timeout(&self, timeout: Duration) -> Result<()> {
+        if self.is_shutting_down() {
+            return Ok(());
+        }
+
+        log::info!("Shutting down MISA desktop");
+        // Emitting only fails when nobody is subscribed, which must not block shutdown
+        if let Err(e) = self.emit_event(AppEvent::AppShutdown) {
+            log::debug!("Shutdown event not delivered: {}", e);
+        }
+        // Filtered streams end once they have yielded what was already sent to them
+        self.filtered_subscribers.lock().clear();
+        self.shutdown_tx.send_replace(true);
+
+        let hooks: Vec<_> = self.shutdown_hooks.lock().drain(..).collect();
+        let event_bus = self.event_bus.clone();
+
+        let drain_and_flush = async move {
+            // Give subscribers the chance to see everything up to AppShutdown
+            while event_bus.receiver_count() > 0 && event_bus.len() > 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+
+            let results = futures_util::future::join_all(hooks.into_iter().map(|(name, hook)| async move {
+                (name, hook().await)
+            }))
+            .await;
+
+            for (name, result) in results {
+                if let Err(e) = result {
+                    log::error!("Shutdown hook {} failed: {}", name, e);
+                }
+            }
+        };
+
+        if tokio::time::timeout(timeout, drain_and_flush).await.is_err() {
+            log::warn!("Shutdown did not finish within {:?}", timeout);
+        }
+
+        log::info!("MISA desktop shut down");
+        Ok(())
+    }
+
+    /// Relay the predictions of the kernel at `url` onto the event bus until shutdown
+    /// or until the kernel goes away
+    pub async fn forward_kernel_predictions(self: &Arc<Self>, url: &str) -> AppResult<tokio::task::JoinHandle<()>> {
+        let predictions = kernel_client::subscribe_predictions(url).await?;
+        Ok(self.forward_kernel_events(predictions, "prediction"))
+    }
+
+    /// Relay the re-consent prompts of the kernel at `url` onto the event bus until
+    /// shutdown or until the kernel goes away
+    pub async fn forward_kernel_privacy_events(self: &Arc<Self>, url: &str) -> AppResult<tokio::task::JoinHandle<()>> {
+        let events = kernel_client::subscribe_privacy_events(url).await?;
+        Ok(self.forward_kernel_events(events, "privacy event"))
+    }
+
+    /// Relay the anomalies the kernel at `url` finds onto the event bus until shutdown
+    /// or until the kernel goes away
+    pub async fn forward_kernel_anomalies(self: &Arc<Self>, url: &str) -> AppResult<tokio::task::JoinHandle<()>> {
+        let anomalies = kernel_client::subscribe_anomalies(url).await?;
+        Ok(self.forward_kernel_events(anomalies, "anomaly"))
+    }
+
+    fn forward_kernel_events(
+        self: &Arc<Self>,
+        mut events: futures_util::stream::BoxStream<'static, AppResult<AppEvent>>,
+        kind: &'static str,
+    ) -> tokio::task::JoinHandle<()> {
+        use futures_util::StreamExt;
+
+        let mut shutdown = self.shutdown_signal();
+        let state = Arc::clone(self);
+
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    event = events.next() => match event {
+                        // An event nobody is listening for is simply dropped
+                        Some(Ok(event)) => { let _ = state.emit_event(event); }
+                        Some(Err(e)) => log::warn!("Skipping kernel {}: {}", kind, e),
+                        None => {
+                            log::info!("Kernel {} stream ended", kind);
+                            break;
+                        }
+                    },
+                    _ = shutdown.changed() => break,
+                }
+            }
+        })
+    }
+
+    /// Subscribe to events, skipping past any that were missed instead of failing
+    pub fn subscribe_events_lossy(&self) -> LossyEventReceiver {
+        LossyEventReceiver {
+            receiver: self.event_bus.subscribe(),
+            skipped: 0,
+        }
+    }
+}
+
+/// Event receiver that logs and skips lagged events, so a slow subscriber keeps
+/// receiving newer events rather than stopping on `RecvError::Lagged`
+pub struct LossyEventReceiver {
+    receiver: broadcast::Receiver<AppEvent>,
+    skipped: u64,
+}
+
+impl LossyEventReceiver {
+    /// Next event, or `None` once the event bus is closed
+    pub async fn recv(&mut self) -> Option<AppEvent> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) => return Some(event),
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    log::warn!("Event subscriber lagged, skipped {} events", missed);
+                    self.skipped += missed;
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// Total events skipped because this subscriber fell behind
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    /// Adapt into a stream of events
+    pub fn into_stream(self) -> impl futures_util::Stream<Item = AppEvent> {
+        futures_util::stream::unfold(self, |mut receiver| async move {
+            receiver.recv().await.map(|event| (event, receiver))
+        })
+    }
 }
 
 /// Application events
@@ -124,7 +440,13 @@ pub enum AppEvent {
 
     // AI events
     AIResponseReceived { request_id: String, response: String },
+    AIResponseChunk { request_id: String, chunk: String, done: bool },
     AISummaryGenerated { content_id: String, summary: String },
+    PredictionGenerated { prediction_type: String, suggestion: String, confidence: f32 },
+    AnomalyDetected { anomaly_type: String, severity: String, description: String },
+
+    // Privacy events
+    ReConsentRequired { user_id: String, consent_id: String, consent_type: String, expired: bool },
 
     // Configuration events
     ConfigUpdated,
@@ -136,6 +458,87 @@ pub enum AppEvent {
     ErrorOccurred(String),
 }
 
+/// Payload-free discriminant of an `AppEvent`, used to filter subscriptions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AppEventKind {
+    DeviceConnected,
+    DeviceDisconnected,
+    DeviceMessageReceived,
+    FileUploaded,
+    FileDownloaded,
+    FileSyncCompleted,
+    FocusSessionStarted,
+    FocusSessionCompleted,
+    FocusSessionInterrupted,
+    SystemSuspend,
+    SystemResume,
+    LowBattery,
+    ScreenCaptured,
+    UIElementsDetected,
+    TextExtracted,
+    AIResponseReceived,
+    AIResponseChunk,
+    AISummaryGenerated,
+    PredictionGenerated,
+    AnomalyDetected,
+    ReConsentRequired,
+    ConfigUpdated,
+    SettingsChanged,
+    AppReady,
+    AppShutdown,
+    ErrorOccurred,
+}
+
+impl AppEventKind {
+    pub fn is_device(self) -> bool {
+        matches!(self, Self::DeviceConnected | Self::DeviceDisconnected | Self::DeviceMessageReceived)
+    }
+
+    pub fn is_ai(self) -> bool {
+        matches!(
+            self,
+            Self::AIResponseReceived
+                | Self::AIResponseChunk
+                | Self::AISummaryGenerated
+                | Self::PredictionGenerated
+                | Self::AnomalyDetected
+        )
+    }
+}
+
+impl AppEvent {
+    pub fn kind(&self) -> AppEventKind {
+        match self {
+            AppEvent::DeviceConnected(_) => AppEventKind::DeviceConnected,
+            AppEvent::DeviceDisconnected(_) => AppEventKind::DeviceDisconnected,
+            AppEvent::DeviceMessageReceived { .. } => AppEventKind::DeviceMessageReceived,
+            AppEvent::FileUploaded(_) => AppEventKind::FileUploaded,
+            AppEvent::FileDownloaded(_) => AppEventKind::FileDownloaded,
+            AppEvent::FileSyncCompleted { .. } => AppEventKind::FileSyncCompleted,
+            AppEvent::FocusSessionStarted(_) => AppEventKind::FocusSessionStarted,
+            AppEvent::FocusSessionCompleted(_) => AppEventKind::FocusSessionCompleted,
+            AppEvent::FocusSessionInterrupted(_) => AppEventKind::FocusSessionInterrupted,
+            AppEvent::SystemSuspend => AppEventKind::SystemSuspend,
+            AppEvent::SystemResume => AppEventKind::SystemResume,
+            AppEvent::LowBattery => AppEventKind::LowBattery,
+            AppEvent::ScreenCaptured(_) => AppEventKind::ScreenCaptured,
+            AppEvent::UIElementsDetected { .. } => AppEventKind::UIElementsDetected,
+            AppEvent::TextExtracted { .. } => AppEventKind::TextExtracted,
+            AppEvent::AIResponseReceived { .. } => AppEventKind::AIResponseReceived,
+            AppEvent::AIResponseChunk { .. } => AppEventKind::AIResponseChunk,
+            AppEvent::AISummaryGenerated { .. } => AppEventKind::AISummaryGenerated,
+            AppEvent::PredictionGenerated { .. } => AppEventKind::PredictionGenerated,
+            AppEvent::AnomalyDetected { .. } => AppEventKind::AnomalyDetected,
+            AppEvent::ReConsentRequired { .. } => AppEventKind::ReConsentRequired,
+            AppEvent::ConfigUpdated => AppEventKind::ConfigUpdated,
+            AppEvent::SettingsChanged(_) => AppEventKind::SettingsChanged,
+            AppEvent::AppReady => AppEventKind::AppReady,
+            AppEvent::AppShutdown => AppEventKind::AppShutdown,
+            AppEvent::ErrorOccurred(_) => AppEventKind::ErrorOccurred,
+        }
+    }
+}
+
 /// Application information
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct AppInfo {
@@ -208,19 +611,75 @@ pub enum AppError {
     Internal(String),
 }
 
+impl AppError {
+    /// Stable machine-readable code the frontend can match on
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Config(_) => "config",
+            AppError::Device(_) => "device",
+            AppError::File(_) => "file",
+            AppError::Focus(_) => "focus",
+            AppError::Vision(_) => "vision",
+            AppError::AI(_) => "ai",
+            AppError::System(_) => "system",
+            AppError::Network(_) => "network",
+            AppError::Database(_) => "database",
+            AppError::IO(_) => "io",
+            AppError::Serialization(_) => "serialization",
+            AppError::Tauri(_) => "tauri",
+            AppError::Internal(_) => "internal",
+        }
+    }
+}
+
 impl From<AppError> for String {
     fn from(error: AppError) -> Self {
         error.to_string()
     }
 }
 
+/// Error returned from Tauri commands
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AppErrorPayload {
+    pub code: String,
+    pub message: String,
+}
+
+impl AppErrorPayload {
+    /// Keep the code of an underlying `AppError`, otherwise wrap the error with `fallback`
+    pub fn classify<E: Into<anyhow::Error>>(error: E, fallback: fn(String) -> AppError) -> Self {
+        let error = error.into();
+        match error.downcast::<AppError>() {
+            Ok(app_error) => app_error.into(),
+            Err(other) => fallback(other.to_string()).into(),
+        }
+    }
+}
+
+impl From<AppError> for AppErrorPayload {
+    fn from(error: AppError) -> Self {
+        Self {
+            code: error.code().to_string(),
+            message: error.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for AppErrorPayload {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}", self.code, self.message)
+    }
+}
+
 /// Module initialization
 pub async fn initialize_modules() -> AppResult<()> {
     // Initialize logging
     env_logger::init();
 
-    // Initialize database
-    crate::database::initialize().await?;
+    // Without a database the app still runs, with memory features disabled
+    if let Err(e) = crate::database::initialize().await {
+        log::error!("Starting without a database, memory features are disabled: {}", e);
+    }
 
     // Initialize each module
     DeviceManager::initialize().await?;
@@ -235,27 +694,75 @@ pub async fn initialize_modules() -> AppResult<()> {
 
 /// Database module
 pub mod database {
+    use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
     use sqlx::{Pool, Sqlite, SqlitePool};
+    use std::path::Path;
     use std::sync::Arc;
+    use std::time::Duration;
     use parking_lot::RwLock;
     use anyhow::Result;
 
+    use crate::{AppError, AppResult};
+
+    /// Database file, relative to the working directory
+    pub const DB_FILE: &str = "misa_desktop.db";
+
+    /// Most open connections to the database unless the config's `db_max_connections` says otherwise
+    pub const DEFAULT_MAX_CONNECTIONS: u32 = 8;
+
+    /// How long a connection waits on a locked database before giving up
+    const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
     static DB_POOL: std::sync::OnceLock<Arc<RwLock<SqlitePool>>> = std::sync::OnceLock::new();
 
     /// Initialize database
-    pub async fn initialize() -> Result<()> {
-        let pool = SqlitePool::connect("sqlite:misa_desktop.db").await?;
+    pub async fn initialize() -> AppResult<()> {
+        initialize_with(DEFAULT_MAX_CONNECTIONS).await
+    }
 
-        // Run migrations
-        sqlx::migrate!("./migrations").run(&pool).await?;
+    /// Initialize database with a pool of at most `max_connections`
+    pub async fn initialize_with(max_connections: u32) -> AppResult<()> {
+        initialize_at(Path::new(DB_FILE), max_connections).await
+    }
 
-        DB_POOL.set(Arc::new(RwLock::new(pool)))
-            .expect("Failed to set database pool");
+    /// Initialize the database at `path`; later calls keep the first pool
+    pub async fn initialize_at(path: &Path, max_connections: u32) -> AppResult<()> {
+        if DB_POOL.get().is_some() {
+            return Ok(());
+        }
+
+        let pool = open(path, max_connections).await?;
+        if DB_POOL.set(Arc::new(RwLock::new(pool))).is_err() {
+            log::debug!("Database was initialized concurrently, keeping the existing pool");
+        }
 
         log::info!("Database initialized successfully");
         Ok(())
     }
 
+    /// Open the database at `path` and bring its schema up to date
+    pub async fn open(path: &Path, max_connections: u32) -> AppResult<SqlitePool> {
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .synchronous(SqliteSynchronous::Normal)
+            .busy_timeout(BUSY_TIMEOUT);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(max_connections.max(1))
+            .connect_with(options)
+            .await
+            .map_err(|e| AppError::Database(format!("Failed to open {}: {}", path.display(), e)))?;
+
+        // Run migrations
+        if let Err(e) = sqlx::migrate!("./migrations").run(&pool).await {
+            pool.close().await;
+            return Err(AppError::Database(format!("Failed to migrate {}: {}", path.display(), e)));
+        }
+
+        Ok(pool)
+    }
+
     /// Get database pool
     pub fn get_pool() -> Option<Arc<RwLock<SqlitePool>>> {
         DB_POOL.get().cloned()
@@ -279,9 +786,20 @@ mod tests {
     use super::*;
     use tokio_test;
 
+    /// App state backed by a database in `data_dir`, which is removed when the test drops it.
+    /// The pool is process-wide, so only the first state in a run actually opens one.
+    async fn test_state(data_dir: &tempfile::TempDir) -> Result<MisaAppState> {
+        test_state_with_capacity(data_dir, DEFAULT_EVENT_BUS_CAPACITY).await
+    }
+
+    async fn test_state_with_capacity(data_dir: &tempfile::TempDir, event_capacity: usize) -> Result<MisaAppState> {
+        MisaAppState::with_database_at(&data_dir.path().join(database::DB_FILE), event_capacity).await
+    }
+
     #[tokio::test]
     async fn test_app_state_creation() {
-        let state = MisaAppState::new().await;
+        let data_dir = tempfile::tempdir().unwrap();
+        let state = test_state(&data_dir).await;
         assert!(state.is_ok());
     }
 
@@ -294,7 +812,8 @@ mod tests {
 
     #[tokio::test]
     async fn test_event_bus() {
-        let state = MisaAppState::new().await.unwrap();
+        let data_dir = tempfile::tempdir().unwrap();
+        let state = test_state(&data_dir).await.unwrap();
         let event = AppEvent::AppReady;
 
         // Emit event
@@ -314,4 +833,272 @@ mod tests {
             _ => panic!("Unexpected event type"),
         }
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_shutdown_runs_hooks_and_rejects_new_events() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let state = test_state(&data_dir).await.unwrap();
+        let mut receiver = state.subscribe_events_lossy();
+        let mut shutdown_signal = state.shutdown_signal();
+
+        let flushed = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let flushed_hook = flushed.clone();
+        state.register_shutdown_hook("flush", move || async move {
+            flushed_hook.store(true, std::sync::atomic::Ordering::SeqCst);
+            Ok(())
+        });
+
+        let consumer = tokio::spawn(async move { receiver.recv().await });
+        state.shutdown().await.unwrap();
+
+        assert!(flushed.load(std::sync::atomic::Ordering::SeqCst));
+        assert!(matches!(consumer.await.unwrap(), Some(AppEvent::AppShutdown)));
+        shutdown_signal.changed().await.unwrap();
+        assert!(*shutdown_signal.borrow());
+        assert!(state.emit_event(AppEvent::AppReady).is_err());
+
+        // Shutting down twice is harmless
+        assert!(state.shutdown().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_without_subscribers_still_runs_hooks() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let state = test_state(&data_dir).await.unwrap();
+        let flushed = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let flushed_hook = flushed.clone();
+        state.register_shutdown_hook("flush", move || async move {
+            flushed_hook.store(true, std::sync::atomic::Ordering::SeqCst);
+            Ok(())
+        });
+
+        state.shutdown().await.unwrap();
+        assert!(flushed.load(std::sync::atomic::Ordering::SeqCst));
+        assert!(state.is_shutting_down());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_gives_up_on_stuck_hooks() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let state = test_state(&data_dir).await.unwrap();
+        state.register_shutdown_hook("stuck", || async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(())
+        });
+
+        let started = std::time::Instant::now();
+        state.shutdown_with_timeout(Duration::from_millis(50)).await.unwrap();
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_lossy_subscriber_keeps_receiving_after_lag() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let state = test_state_with_capacity(&data_dir, 4).await.unwrap();
+        let mut receiver = state.subscribe_events_lossy();
+
+        // Overfill the channel so the subscriber lags
+        for i in 0..10 {
+            state.emit_event(AppEvent::DeviceConnected(format!("device-{}", i))).unwrap();
+        }
+
+        match receiver.recv().await {
+            Some(AppEvent::DeviceConnected(id)) => assert_eq!(id, "device-6"),
+            other => panic!("Unexpected event: {:?}", other),
+        }
+        assert_eq!(receiver.skipped(), 6);
+
+        state.emit_event(AppEvent::AppReady).unwrap();
+        for expected in ["device-7", "device-8", "device-9"] {
+            match receiver.recv().await {
+                Some(AppEvent::DeviceConnected(id)) => assert_eq!(id, expected),
+                other => panic!("Unexpected event: {:?}", other),
+            }
+        }
+        assert!(matches!(receiver.recv().await, Some(AppEvent::AppReady)));
+    }
+
+    #[test]
+    fn test_app_error_codes_reach_payload() {
+        let cases = vec![
+            (AppError::Config("bad".into()), "config"),
+            (AppError::Device("bad".into()), "device"),
+            (AppError::File("bad".into()), "file"),
+            (AppError::Focus("bad".into()), "focus"),
+            (AppError::Vision("bad".into()), "vision"),
+            (AppError::AI("bad".into()), "ai"),
+            (AppError::System("bad".into()), "system"),
+            (AppError::Network("bad".into()), "network"),
+            (AppError::Database("bad".into()), "database"),
+            (AppError::IO(std::io::Error::new(std::io::ErrorKind::Other, "bad")), "io"),
+            (AppError::Serialization(serde_json::from_str::<u32>("bad").unwrap_err()), "serialization"),
+            (AppError::Internal("bad".into()), "internal"),
+        ];
+
+        for (error, code) in cases {
+            assert_eq!(error.code(), code);
+            let message = error.to_string();
+            let payload = AppErrorPayload::from(error);
+            assert_eq!(payload.code, code);
+            assert_eq!(payload.message, message);
+
+            let json = serde_json::to_value(&payload).unwrap();
+            assert_eq!(json["code"], code);
+        }
+    }
+
+    #[test]
+    fn test_payload_classify_keeps_wrapped_app_error_code() {
+        let wrapped = anyhow::Error::new(AppError::Network("timed out".into()));
+        assert_eq!(AppErrorPayload::classify(wrapped, AppError::Device).code, "network");
+
+        let plain = anyhow::anyhow!("no such device");
+        let payload = AppErrorPayload::classify(plain, AppError::Device);
+        assert_eq!(payload.code, "device");
+        assert_eq!(payload.message, "Device error: no such device");
+    }
+
+    #[tokio::test]
+    async fn test_kernel_predictions_are_forwarded_to_the_event_bus() {
+        use futures_util::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::Message;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/ws", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let subscribe = socket.next().await.unwrap().unwrap();
+            assert_eq!(subscribe.to_text().unwrap(), r#"{"type":"subscribe_predictions"}"#);
+            let frame = r#"{"type":"prediction","prediction_type":"time_based","suggestion":"Plan your morning","confidence":0.5}"#;
+            socket.send(Message::Text(frame.to_string())).await.unwrap();
+            let _ = socket.close(None).await;
+        });
+
+        let data_dir = tempfile::tempdir().unwrap();
+        let state = Arc::new(test_state(&data_dir).await.unwrap());
+        let mut events = state.subscribe_events();
+        let forwarding = state.forward_kernel_predictions(&url).await.unwrap();
+
+        let event = tokio::time::timeout(Duration::from_secs(5), events.recv()).await.unwrap().unwrap();
+        assert!(matches!(
+            event,
+            AppEvent::PredictionGenerated { prediction_type, suggestion, .. }
+                if prediction_type == "time_based" && suggestion == "Plan your morning"
+        ));
+        forwarding.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_kernel_re_consent_prompts_are_forwarded_to_the_event_bus() {
+        use futures_util::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::Message;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/ws", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let subscribe = socket.next().await.unwrap().unwrap();
+            assert_eq!(subscribe.to_text().unwrap(), r#"{"type":"subscribe_privacy_events"}"#);
+            let frame = r#"{"type":"re_consent_required","user_id":"user-1","consent_id":"consent-1","consent_type":"CloudSync","expired":false}"#;
+            socket.send(Message::Text(frame.to_string())).await.unwrap();
+            let _ = socket.close(None).await;
+        });
+
+        let data_dir = tempfile::tempdir().unwrap();
+        let state = Arc::new(test_state(&data_dir).await.unwrap());
+        let mut events = state.subscribe_events();
+        let forwarding = state.forward_kernel_privacy_events(&url).await.unwrap();
+
+        let event = tokio::time::timeout(Duration::from_secs(5), events.recv()).await.unwrap().unwrap();
+        assert!(matches!(
+            event,
+            AppEvent::ReConsentRequired { user_id, consent_type, expired: false, .. }
+                if user_id == "user-1" && consent_type == "CloudSync"
+        ));
+        forwarding.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_filtered_subscriber_only_sees_matching_events() {
+        use futures_util::StreamExt;
+
+        let data_dir = tempfile::tempdir().unwrap();
+        let state = test_state(&data_dir).await.unwrap();
+        // Emitting fails when nobody at all is listening
+        let _everything = state.subscribe_events();
+        let device_events = state.subscribe_filtered(AppEventKind::is_device);
+        futures_util::pin_mut!(device_events);
+
+        state.emit_event(AppEvent::AIResponseChunk {
+            request_id: "r1".into(),
+            chunk: "x".repeat(64 * 1024),
+            done: false,
+        }).unwrap();
+        state.emit_event(AppEvent::DeviceConnected("phone".into())).unwrap();
+        state.emit_event(AppEvent::PredictionGenerated {
+            prediction_type: "next_app".into(),
+            suggestion: "editor".into(),
+            confidence: 0.9,
+        }).unwrap();
+        state.emit_event(AppEvent::DeviceDisconnected("phone".into())).unwrap();
+
+        let received: Vec<AppEvent> = device_events.take(2).collect().await;
+        assert!(matches!(&received[0], AppEvent::DeviceConnected(id) if id == "phone"));
+        assert!(matches!(&received[1], AppEvent::DeviceDisconnected(id) if id == "phone"));
+        assert!(received.iter().all(|event| !event.kind().is_ai()));
+    }
+
+    #[tokio::test]
+    async fn test_filtered_streams_end_after_shutdown() {
+        use futures_util::StreamExt;
+
+        let data_dir = tempfile::tempdir().unwrap();
+        let state = test_state(&data_dir).await.unwrap();
+        let events = state.subscribe_filtered(|_| true);
+
+        state.shutdown_with_timeout(Duration::from_secs(1)).await.unwrap();
+
+        let received: Vec<AppEvent> = tokio::time::timeout(Duration::from_secs(5), events.collect())
+            .await
+            .expect("filtered stream did not end");
+        assert!(matches!(received.as_slice(), [AppEvent::AppShutdown]));
+    }
+
+    #[tokio::test]
+    async fn test_unwritable_database_path_is_a_clean_error() {
+        // A regular file can't hold the database as if it were a directory
+        let data_dir = tempfile::tempdir().unwrap();
+        let blocker = data_dir.path().join("blocker");
+        std::fs::write(&blocker, b"not a directory").unwrap();
+
+        let result = database::open(&blocker.join("misa_desktop.db"), 1).await;
+
+        let error = result.unwrap_err();
+        assert_eq!(error.code(), "database");
+        assert!(error.to_string().contains("Failed to open"), "{}", error);
+    }
+
+    #[test]
+    fn test_failed_database_degrades_instead_of_failing() {
+        let status = DatabaseStatus::from_init(&Err(AppError::Database("database is locked".into())));
+        assert_eq!(
+            status,
+            DatabaseStatus::Degraded { reason: "Database error: database is locked".to_string() }
+        );
+        assert_eq!(DatabaseStatus::from_init(&Ok(())), DatabaseStatus::Available);
+    }
+
+    #[tokio::test]
+    async fn test_degraded_state_refuses_memory_features() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let mut state = test_state(&data_dir).await.unwrap();
+        assert!(state.require_database().is_ok());
+
+        state.database = DatabaseStatus::Degraded { reason: "database is locked".to_string() };
+        let error = state.require_database().unwrap_err();
+        assert_eq!(error.code(), "database");
+        assert!(error.to_string().contains("database is locked"), "{}", error);
+    }
+}
@@ -5,7 +5,7 @@
 
 use std::sync::Arc;
 use tauri::{Manager, State};
-use misa_desktop_lib::{MisaApp, MisaAppState};
+use misa_desktop_lib::{kernel_client, MisaApp, MisaAppState};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -15,6 +15,20 @@ async fn main() -> anyhow::Result<()> {
     // Create application state
     let app_state = Arc::new(MisaAppState::new().await?);
 
+    // Predictions are only available while the kernel is running
+    if let Err(e) = app_state.forward_kernel_predictions(kernel_client::DEFAULT_KERNEL_WS_URL).await {
+        log::warn!("Not forwarding kernel predictions: {}", e);
+    }
+    if let Err(e) = app_state.forward_kernel_privacy_events(kernel_client::DEFAULT_KERNEL_WS_URL).await {
+        log::warn!("Not forwarding kernel re-consent prompts: {}", e);
+    }
+    if let Err(e) = app_state.forward_kernel_anomalies(kernel_client::DEFAULT_KERNEL_WS_URL).await {
+        log::warn!("Not forwarding kernel anomalies: {}", e);
+    }
+
+    app_state.register_default_shutdown_hooks();
+    let shutdown_state = app_state.clone();
+
     // Build Tauri application
     tauri::Builder::default()
         .manage(app_state.clone())
@@ -62,6 +76,7 @@ async fn main() -> anyhow::Result<()> {
 
             // AI commands
             misa_desktop_lib::commands::process_natural_language,
+            misa_desktop_lib::commands::stream_natural_language,
             misa_desktop_lib::commands::get_ai_recommendations,
             misa_desktop_lib::commands::generate_summary
         ])
@@ -87,8 +102,24 @@ async fn main() -> anyhow::Result<()> {
             .closable(true)
             .theme(Some(tauri::Theme::Light))
         )
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(move |app_handle, event| {
+            if let tauri::RunEvent::ExitRequested { api, .. } = event {
+                // Hold the exit until the shutdown hooks ran, then exit for real
+                if !shutdown_state.is_shutting_down() {
+                    api.prevent_exit();
+                    let app_handle = app_handle.clone();
+                    let state = shutdown_state.clone();
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = state.shutdown().await {
+                            log::error!("Shutdown failed: {}", e);
+                        }
+                        app_handle.exit(0);
+                    });
+                }
+            }
+        });
 
     Ok(())
 }
\ No newline at end of file
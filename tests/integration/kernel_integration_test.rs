@@ -108,6 +108,8 @@ async fn test_device_management() {
         .await
         .expect("Failed to create security manager");
 
+    security_manager.initialize().await.expect("Failed to initialize security manager");
+
     let device_manager = DeviceManager::new(config, security_manager)
         .await
         .expect("Failed to create device manager");
@@ -116,9 +118,16 @@ async fn test_device_management() {
     device_manager.start_discovery().await.expect("Failed to start device discovery");
 
     // Test device pairing
-    let qr_token = "misa://pair/test_device/1234567890/abc123";
+    let qr_token = device_manager
+        .generate_pairing_token("test_device")
+        .await
+        .expect("Failed to generate pairing token");
+    let scanner_public_key = device_manager
+        .scan_pairing_token(&qr_token)
+        .await
+        .expect("Failed to scan pairing token");
     let pairing_result = device_manager
-        .pair_device(qr_token)
+        .pair_device(&qr_token, &scanner_public_key)
         .await
         .expect("Failed to pair device");
 